@@ -119,7 +119,7 @@ def test_pulse():
     assert str(typed.output_fact(0)) == "5,1000,F32"
     properties = typed.property_keys()
     properties.sort()
-    assert properties == ["pulse.delay", "pulse.input_axes", "pulse.output_axes"]
+    assert properties == ["pulse.delay", "pulse.input_axes", "pulse.input_symbols", "pulse.output_axes", "pulse.output_symbols"]
     assert typed.property("pulse.delay").to_numpy() == [0]
 
 def test_half():
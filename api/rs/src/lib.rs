@@ -20,6 +20,9 @@ use tract_pulse::WithPulse;
 
 use tract_api::*;
 
+/// Number of profiling turns recorded as trace events by profile_json.
+const PROFILE_TRACE_TURNS: usize = 10;
+
 /// Creates an instance of an NNEF framework and parser that can be used to load and dump NNEF models.
 pub fn nnef() -> Result<Nnef> {
     Ok(Nnef(tract_nnef::nnef()))
@@ -269,6 +272,7 @@ impl ModelInterface for Model {
                 .into_iter()
                 .map(|v| Ok(v.try_into().unwrap().0))
                 .collect::<TractResult<TVec<_>>>()?;
+            annotations.trace = Some(tract_libcli::trace::Trace::new(PROFILE_TRACE_TURNS));
             tract_libcli::profile::profile(
                 &self.0,
                 &BenchLimits::default(),
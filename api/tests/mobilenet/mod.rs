@@ -173,7 +173,16 @@ fn test_pulse() -> anyhow::Result<()> {
     assert_eq!(typed.output_fact(0)?.to_string(), "5,1000,F32");
     let mut properties = typed.property_keys()?;
     properties.sort();
-    assert_eq!(&properties, &["pulse.delay", "pulse.input_axes", "pulse.output_axes"]);
+    assert_eq!(
+        &properties,
+        &[
+            "pulse.delay",
+            "pulse.input_axes",
+            "pulse.input_symbols",
+            "pulse.output_axes",
+            "pulse.output_symbols"
+        ]
+    );
     assert_eq!(typed.property("pulse.delay")?.view::<i64>()?, ndarray::arr1(&[0i64]).into_dyn());
     Ok(())
 }
@@ -254,5 +263,8 @@ fn test_profile() -> anyhow::Result<()> {
     assert!(profiling_info["iterations"].as_i64().unwrap() >= 1);
     let nodes = profile.get("nodes").unwrap().as_array().unwrap();
     assert!(nodes.iter().find_map(|n| n.get("secs_per_iter").and_then(|c| c.as_f64())).is_some());
+    let events = profile["traceEvents"].as_array().unwrap();
+    assert!(events.iter().any(|e| e["cat"] == "turn"));
+    assert!(events.iter().any(|e| e["cat"] == "step"));
     Ok(())
 }
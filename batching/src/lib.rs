@@ -0,0 +1,412 @@
+//! Dynamic batching of concurrent requests over a model with a symbolic batch
+//! dimension.
+//!
+//! A [Batcher] owns a worker thread. Requests submitted from any thread are
+//! queued, concatenated along the batch axis until the batch reaches
+//! `max_batch_size` or the oldest request has waited `max_latency`, then run
+//! at once. Outputs are split back and handed to each request.
+//!
+//! Every batch size gets its own plan, built by concretizing the batch symbol
+//! and optimizing the model the first time a batch of that size runs, then
+//! cached. Sizes listed in `warmup_batch_sizes` are built when the batcher
+//! starts instead.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tract_core::internal::*;
+
+#[derive(Clone, Debug)]
+pub struct BatchingConfig {
+    /// Name of the batch symbol in the model.
+    pub batch_symbol: String,
+    /// Batch axis, in every input and output.
+    pub batch_axis: usize,
+    /// Maximum batch size. Bigger requests are rejected.
+    pub max_batch_size: usize,
+    /// Maximum time a request waits for others to join its batch.
+    pub max_latency: Duration,
+    /// Batch sizes to optimize the model for upfront. Others are optimized
+    /// on their first batch.
+    pub warmup_batch_sizes: Vec<usize>,
+}
+
+impl Default for BatchingConfig {
+    fn default() -> BatchingConfig {
+        BatchingConfig {
+            batch_symbol: "N".to_string(),
+            batch_axis: 0,
+            max_batch_size: 32,
+            max_latency: Duration::from_millis(5),
+            warmup_batch_sizes: vec![1],
+        }
+    }
+}
+
+type Reply = TractResult<TVec<Tensor>>;
+
+/// The failure of a batch, reported to each of its requests.
+#[derive(Clone, Debug)]
+pub struct BatchError(pub Arc<TractError>);
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for BatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+struct Request {
+    inputs: TVec<Tensor>,
+    size: usize,
+    received: Instant,
+    reply: mpsc::Sender<Reply>,
+}
+
+/// Outputs of a submitted request, to be waited for.
+pub struct Pending(mpsc::Receiver<Reply>);
+
+impl Pending {
+    pub fn wait(self) -> TractResult<TVec<Tensor>> {
+        self.0.recv().map_err(|_| format_err!("Batcher worker is gone"))?
+    }
+}
+
+pub struct Batcher {
+    input_facts: TVec<TypedFact>,
+    config: BatchingConfig,
+    queue: Mutex<Option<mpsc::Sender<Request>>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Batcher {
+    /// Start a batcher on a decluttered model, optimizing it for the
+    /// `warmup_batch_sizes`.
+    ///
+    /// All inputs and outputs must have the batch symbol as their batch axis.
+    pub fn new(model: TypedModel, config: BatchingConfig) -> TractResult<Batcher> {
+        ensure!(config.max_batch_size > 0, "max_batch_size must be positive");
+        let symbol = model
+            .symbol_table
+            .get(&config.batch_symbol)
+            .with_context(|| format!("No symbol {} in model", config.batch_symbol))?;
+        let batch_dim = TDim::from(symbol.clone());
+        for outlet in model.input_outlets()?.iter().chain(model.output_outlets()?.iter()) {
+            let fact = model.outlet_fact(*outlet)?;
+            ensure!(
+                fact.shape.get(config.batch_axis) == Some(&batch_dim),
+                "Expected {} as axis {} of {}, got {:?}",
+                batch_dim,
+                config.batch_axis,
+                model.node(outlet.node).name,
+                fact
+            );
+        }
+        let input_facts = model
+            .input_outlets()?
+            .iter()
+            .map(|o| model.outlet_fact(*o).cloned())
+            .collect::<TractResult<TVec<_>>>()?;
+        ensure!(!input_facts.is_empty(), "Model has no input");
+        let mut worker =
+            Worker { config: config.clone(), model, symbol, plans: HashMap::default() };
+        for &size in &config.warmup_batch_sizes {
+            ensure!(
+                size > 0 && size <= config.max_batch_size,
+                "Warmup batch size {} is out of the supported range 1..={}",
+                size,
+                config.max_batch_size
+            );
+            worker.plan(size)?;
+        }
+        let (queue, requests) = mpsc::channel();
+        let worker = std::thread::Builder::new()
+            .name("tract-batcher".to_string())
+            .spawn(move || worker.run(requests))?;
+        Ok(Batcher { input_facts, config, queue: Mutex::new(Some(queue)), worker: Some(worker) })
+    }
+
+    /// Queue a request. Its inputs must all have the same size on the batch
+    /// axis.
+    pub fn submit(&self, inputs: TVec<Tensor>) -> TractResult<Pending> {
+        ensure!(
+            inputs.len() == self.input_facts.len(),
+            "Expected {} inputs, got {}",
+            self.input_facts.len(),
+            inputs.len()
+        );
+        let axis = self.config.batch_axis;
+        let size = inputs[0].shape().get(axis).copied().context("Input has no batch axis")?;
+        ensure!(
+            size > 0 && size <= self.config.max_batch_size,
+            "Batch size {} is out of the supported range 1..={}",
+            size,
+            self.config.max_batch_size
+        );
+        for (input, fact) in inputs.iter().zip(&self.input_facts) {
+            let compatible = input.datum_type() == fact.datum_type
+                && input.rank() == fact.rank()
+                && input.shape()[axis] == size
+                && input.shape().iter().zip(fact.shape.iter()).enumerate().all(|(ix, (i, f))| {
+                    ix == axis || f.to_usize().map(|f| f == *i).unwrap_or(true)
+                });
+            ensure!(
+                compatible,
+                "Input {:?} does not match {:?} with batch size {}",
+                input,
+                fact,
+                size
+            );
+        }
+        let (reply, pending) = mpsc::channel();
+        let request = Request { inputs, size, received: Instant::now(), reply };
+        self.queue
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .send(request)
+            .map_err(|_| format_err!("Batcher worker is gone"))?;
+        Ok(Pending(pending))
+    }
+
+    /// Submit a request and wait for its outputs.
+    pub fn run(&self, inputs: TVec<Tensor>) -> TractResult<TVec<Tensor>> {
+        self.submit(inputs)?.wait()
+    }
+}
+
+impl Drop for Batcher {
+    fn drop(&mut self) {
+        // closing the queue stops the worker once pending requests are served
+        std::mem::drop(self.queue.get_mut().unwrap().take());
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+struct Worker {
+    config: BatchingConfig,
+    model: TypedModel,
+    symbol: Symbol,
+    /// Plans by batch size, built on first use.
+    plans: HashMap<usize, TypedRunnableModel<TypedModel>>,
+}
+
+impl Worker {
+    fn plan(&mut self, size: usize) -> TractResult<&TypedRunnableModel<TypedModel>> {
+        if !self.plans.contains_key(&size) {
+            let values = SymbolValues::default().with(&self.symbol, size as i64);
+            let plan = self
+                .model
+                .concretize_dims(&values)?
+                .into_optimized()?
+                .into_runnable()
+                .with_context(|| format!("Optimizing for batch size {size}"))?;
+            self.plans.insert(size, plan);
+        }
+        Ok(&self.plans[&size])
+    }
+
+    fn run(&mut self, requests: mpsc::Receiver<Request>) {
+        let mut carry: Option<Request> = None;
+        loop {
+            let Some(first) = carry.take().or_else(|| requests.recv().ok()) else { return };
+            let deadline = first.received + self.config.max_latency;
+            let mut size = first.size;
+            let mut batch = vec![first];
+            while size < self.config.max_batch_size {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                match requests.recv_timeout(timeout) {
+                    Ok(request) if size + request.size > self.config.max_batch_size => {
+                        carry = Some(request);
+                        break;
+                    }
+                    Ok(request) => {
+                        size += request.size;
+                        batch.push(request);
+                    }
+                    Err(_) => break,
+                }
+            }
+            self.run_batch(batch, size);
+        }
+    }
+
+    fn run_batch(&mut self, batch: Vec<Request>, size: usize) {
+        log::debug!("Running a batch of {} ({} requests)", size, batch.len());
+        match self.eval(&batch, size) {
+            Ok(outputs) => {
+                for (request, outputs) in batch.into_iter().zip(outputs) {
+                    let _ = request.reply.send(Ok(outputs));
+                }
+            }
+            Err(e) if batch.len() == 1 => {
+                let _ = batch[0].reply.send(Err(e));
+            }
+            Err(e) => {
+                let e = BatchError(Arc::new(e));
+                for request in batch {
+                    let _ = request.reply.send(Err(e.clone().into()));
+                }
+            }
+        }
+    }
+
+    fn eval(&mut self, batch: &[Request], size: usize) -> TractResult<Vec<TVec<Tensor>>> {
+        let axis = self.config.batch_axis;
+        let inputs = (0..batch[0].inputs.len())
+            .map(|ix| {
+                if batch.len() == 1 {
+                    Ok(batch[0].inputs[ix].clone().into_tvalue())
+                } else {
+                    let inputs: Vec<&Tensor> = batch.iter().map(|r| &r.inputs[ix]).collect();
+                    Ok(Tensor::stack_tensors(axis, &inputs)?.into_tvalue())
+                }
+            })
+            .collect::<TractResult<TVec<_>>>()?;
+        let outputs = self.plan(size)?.run(inputs)?;
+        let mut offset = 0;
+        let mut split = vec![];
+        for request in batch {
+            split.push(
+                outputs
+                    .iter()
+                    .map(|o| o.slice(axis, offset, offset + request.size))
+                    .collect::<TractResult<TVec<_>>>()?,
+            );
+            offset += request.size;
+        }
+        Ok(split)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tract_core::ops::math::mul;
+
+    /// y = 2 * x, over a [N, 3] input.
+    fn model() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let n = model.symbol_table.sym("N");
+        let x = model.add_source("x", f32::fact(&[TDim::from(n), 3.to_dim()]))?;
+        let two = model.add_const("two", tensor2(&[[2f32]]))?;
+        let y = model.wire_node("y", mul(), &[x, two])?;
+        model.set_output_outlets(&y)?;
+        model.into_decluttered()
+    }
+
+    fn input(id: usize, size: usize) -> Tensor {
+        let data = (0..size * 3).map(|i| (id * 100 + i) as f32).collect::<Vec<_>>();
+        Tensor::from_shape(&[size, 3], &data).unwrap()
+    }
+
+    fn check(output: &Tensor, id: usize, size: usize) {
+        let data: Vec<f32> =
+            input(id, size).as_slice::<f32>().unwrap().iter().map(|x| x * 2.).collect();
+        assert_eq!(output, &Tensor::from_shape(&[size, 3], &data).unwrap());
+    }
+
+    #[test]
+    fn single() -> TractResult<()> {
+        let batcher = Batcher::new(model()?, BatchingConfig::default())?;
+        check(&batcher.run(tvec!(input(1, 2)))?[0], 1, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_generators() -> TractResult<()> {
+        let config = BatchingConfig {
+            max_batch_size: 8,
+            max_latency: Duration::from_millis(20),
+            ..BatchingConfig::default()
+        };
+        let batcher = Arc::new(Batcher::new(model()?, config)?);
+        let threads = (0..6)
+            .map(|t| {
+                let batcher = batcher.clone();
+                std::thread::spawn(move || {
+                    for i in 0..10 {
+                        let id = t * 10 + i;
+                        let size = 1 + id % 3;
+                        check(&batcher.run(tvec!(input(id, size))).unwrap()[0], id, size);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for t in threads {
+            t.join().unwrap();
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn fills_batches_from_queue() -> TractResult<()> {
+        let config = BatchingConfig {
+            max_batch_size: 4,
+            max_latency: Duration::from_secs(10),
+            ..BatchingConfig::default()
+        };
+        let batcher = Batcher::new(model()?, config)?;
+        // full batches start without waiting for the deadline
+        let start = Instant::now();
+        let pending = (0..8).map(|id| batcher.submit(tvec!(input(id, 1)))).collect::<Vec<_>>();
+        for (id, pending) in pending.into_iter().enumerate() {
+            check(&pending?.wait()?[0], id, 1);
+        }
+        assert!(start.elapsed() < Duration::from_secs(5));
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_oversized_requests() -> TractResult<()> {
+        let config = BatchingConfig { max_batch_size: 2, ..BatchingConfig::default() };
+        let batcher = Batcher::new(model()?, config)?;
+        assert!(batcher.submit(tvec!(input(1, 3))).is_err());
+        assert!(batcher.submit(tvec!(input(1, 0))).is_err());
+        check(&batcher.run(tvec!(input(2, 2)))?[0], 2, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn warms_configured_sizes() -> TractResult<()> {
+        let config = BatchingConfig {
+            max_batch_size: 4,
+            warmup_batch_sizes: vec![1, 5],
+            ..BatchingConfig::default()
+        };
+        assert!(Batcher::new(model()?, config.clone()).is_err());
+        let config = BatchingConfig { warmup_batch_sizes: vec![2, 4], ..config };
+        let batcher = Batcher::new(model()?, config)?;
+        check(&batcher.run(tvec!(input(1, 3)))?[0], 1, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn batch_errors_keep_their_chain() {
+        let e = TractError::msg("root cause").context("running the plan");
+        let e: TractError = BatchError(Arc::new(e)).into();
+        let chain: Vec<String> = e.chain().map(|e| e.to_string()).collect();
+        assert_eq!(chain, vec!["running the plan", "root cause"]);
+        assert!(e.downcast_ref::<BatchError>().is_some());
+    }
+
+    #[test]
+    fn rejects_inconsistent_requests() -> TractResult<()> {
+        let batcher = Batcher::new(model()?, BatchingConfig::default())?;
+        assert!(batcher.submit(tvec!()).is_err());
+        assert!(batcher.submit(tvec!(Tensor::zero::<f32>(&[2, 4])?)).is_err());
+        assert!(batcher.submit(tvec!(Tensor::zero::<i32>(&[2, 3])?)).is_err());
+        check(&batcher.run(tvec!(input(3, 1)))?[0], 3, 1);
+        Ok(())
+    }
+}
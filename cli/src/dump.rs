@@ -106,6 +106,17 @@ pub fn handle(
     annotate_with_graph_def(&mut annotations, model, &params.graph)?;
     if options.cost {
         tract_libcli::profile::extract_costs(&mut annotations, model)?;
+        if let Some(roofline) = sub_matches.value_of("roofline") {
+            use tract_core::tract_linalg::roofline::RooflineTable;
+            let table = if roofline == "host" {
+                RooflineTable::for_host()
+            } else if let Some(table) = RooflineTable::builtin(roofline) {
+                table
+            } else {
+                RooflineTable::parse(&std::fs::read_to_string(roofline)?)?
+            };
+            tract_libcli::roofline::estimate_latencies(&mut annotations, model, &table)?;
+        }
     }
     if options.profile {
         let run_params = run_params_from_subcommand(params, sub_matches)?;
@@ -114,6 +125,10 @@ pub fn handle(
             .downcast_ref::<TypedModel>()
             .context("Can only profile typed models")?;
         let inputs = retrieve_or_make_inputs(model, &run_params)?;
+        if sub_matches.is_present("trace-file") {
+            let turns = sub_matches.value_of("trace-turns").unwrap().parse()?;
+            annotations.trace = Some(tract_libcli::trace::Trace::new(turns));
+        }
         tract_libcli::profile::profile(
             model,
             bench_limits,
@@ -122,6 +137,10 @@ pub fn handle(
             None,
             options.folded,
         )?;
+        if let Some(path) = sub_matches.value_of("trace-file") {
+            let trace = annotations.trace.as_ref().unwrap().to_json()?;
+            std::fs::write(path, trace).with_context(|| format!("Writing trace to {path}"))?;
+        }
     }
 
     if sub_matches.is_present("axes") || sub_matches.is_present("axes-names") {
@@ -162,6 +181,13 @@ pub fn handle(
         let nnef = super::nnef(matches);
         if let Some(mut typed) = model.downcast_ref::<TypedModel>().cloned() {
             rename_outputs(&mut typed, sub_matches)?;
+            if sub_matches.is_present("nnef-sparse") {
+                use tract_core::ops::matmul::sparse::*;
+                sparsify_weights(&mut typed, SPARSE_DENSITY_THRESHOLD)?;
+            }
+            if sub_matches.is_present("nnef-prepack") {
+                nnef.embed_prepacked_weights(&mut typed)?;
+            }
             let file = std::fs::File::create(path)?;
             let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
             nnef.write_to_tar_with_config(&typed, encoder, compress_submodels)
@@ -175,6 +201,13 @@ pub fn handle(
         let nnef = super::nnef(matches);
         if let Some(mut typed) = model.downcast_ref::<TypedModel>().cloned() {
             rename_outputs(&mut typed, sub_matches)?;
+            if sub_matches.is_present("nnef-sparse") {
+                use tract_core::ops::matmul::sparse::*;
+                sparsify_weights(&mut typed, SPARSE_DENSITY_THRESHOLD)?;
+            }
+            if sub_matches.is_present("nnef-prepack") {
+                nnef.embed_prepacked_weights(&mut typed)?;
+            }
             let file = std::fs::File::create(path)?;
             nnef.write_to_tar_with_config(&typed, file, compress_submodels)
                 .context("Writting model to tar")?;
@@ -187,6 +220,13 @@ pub fn handle(
         let nnef = super::nnef(matches);
         if let Some(mut typed) = model.downcast_ref::<TypedModel>().cloned() {
             rename_outputs(&mut typed, sub_matches)?;
+            if sub_matches.is_present("nnef-sparse") {
+                use tract_core::ops::matmul::sparse::*;
+                sparsify_weights(&mut typed, SPARSE_DENSITY_THRESHOLD)?;
+            }
+            if sub_matches.is_present("nnef-prepack") {
+                nnef.embed_prepacked_weights(&mut typed)?;
+            }
             if let Some(renamed) = sub_matches.values_of("nnef-override-output-name") {
                 for (ix, name) in renamed.into_iter().enumerate() {
                     let output = typed.wire_node(
@@ -255,9 +295,17 @@ pub fn handle(
     if options.json {
         let export = tract_libcli::export::GraphPerfInfo::from(model, &annotations);
         serde_json::to_writer(std::io::stdout(), &export)?;
+    } else if options.html {
+        print!("{}", tract_libcli::html::render(model, &annotations)?);
     } else {
         terminal::render(model, &annotations, options)?;
         terminal::render_summaries(model, &annotations, options)?;
+        #[cfg(feature = "pulse")]
+        if let Some(pulsed) = &params.pulsed_model {
+            let report = tract_pulse::internal::PulseReport::new(pulsed)?;
+            println!("{}", Style::new().bold().paint("Pulse latency and state"));
+            print!("{report}");
+        }
     }
 
     Ok(())
@@ -136,7 +136,9 @@ fn main() -> tract_core::anyhow::Result<()> {
         .arg(arg!(--"nnef-extended-identifier" "Allow usage of the i\"...\" syntax to escape identifier names"))
 
         .arg(arg!(-O --optimize "Optimize before running"))
-        .arg(arg!(--pulse [PULSE] "Translate to pulse network"))
+        .arg(arg!(--"tuning-file" [FILE] "Use matrix multiplication kernels from a tuning database during optimisation"))
+        .arg(arg!(--autotune "Benchmark kernels for matrix multiplication shapes missing from the tuning database (saved to --tuning-file)"))
+        .arg(arg!(--pulse [PULSE] "Translate to pulse network (S=4, or S=4,T=1 for several streams)"))
 
         .arg(arg!(--"machine-friendly" "Machine friendly output"))
 
@@ -304,6 +306,25 @@ fn dump_subcommand<'a>() -> clap::Command<'a> {
             .long("assert-cost")
             .help("Checks computed against the provided value (form: \"FMA(F32)=2060448 DIV(F32)=24576\")")
             )
+        .arg(
+            Arg::new("roofline")
+            .takes_value(true)
+            .long("roofline")
+            .help("With --cost, estimate latencies with a roofline table: a builtin name (generic, x86_64_fma, x86_64_avx512, arm64, arm64_little, armv7), \"host\" or a table file")
+            )
+        .arg(
+            Arg::new("trace-file")
+            .takes_value(true)
+            .long("trace-file")
+            .help("With --profile, write a timeline of the first profiling turns as a Chrome trace-event json file")
+            )
+        .arg(
+            Arg::new("trace-turns")
+            .takes_value(true)
+            .long("trace-turns")
+            .default_value("10")
+            .help("Number of profiling turns recorded by --trace-file")
+            )
         .arg(
             Arg::new("nnef-override-output-name")
             .takes_value(true)
@@ -335,6 +356,16 @@ fn dump_subcommand<'a>() -> clap::Command<'a> {
             .long("tflite")
             .help("Dump the network in TfLite format"),
             )
+        .arg(
+            Arg::new("nnef-prepack")
+            .long("nnef-prepack")
+            .help("Embed the weights packed for this host CPU in the NNEF dump"),
+            )
+        .arg(
+            Arg::new("nnef-sparse")
+            .long("nnef-sparse")
+            .help("Store sparse enough matrix multiplication weights in block-sparse form in the NNEF dump"),
+            )
         .arg(
             Arg::new("compress-submodels")
             .long("compress-submodels")
@@ -470,6 +501,11 @@ fn output_options(command: clap::Command) -> clap::Command {
         .arg(Arg::new("io-long").long("io-long").help("show full i/o information"))
         .arg(Arg::new("io-none").long("io-none").help("hide i/o information"))
         .arg(Arg::new("json").long("json").help("dump performance info as json"))
+        .arg(
+            Arg::new("html")
+                .long("html")
+                .help("dump model, annotations and nested models as a standalone html page"),
+        )
         .arg(Arg::new("outlet-labels").long("outlet-labels").help("display outlet labels"))
         .arg(Arg::new("cost").long("cost").help("Include const information"))
         .arg(Arg::new("profile").long("profile").help("Include results for profile run"))
@@ -504,6 +540,19 @@ fn handle(matches: clap::ArgMatches, probe: Option<&Probe>) -> TractResult<()> {
         return Ok(());
     }
 
+    let tuning_file = matches.value_of("tuning-file");
+    if tuning_file.is_some() || matches.is_present("autotune") {
+        use tract_core::tract_linalg::tuning::*;
+        let mut db = match tuning_file {
+            Some(file) if std::path::Path::new(file).exists() => {
+                TuningDb::parse(&std::fs::read_to_string(file)?)?
+            }
+            _ => TuningDb::default(),
+        };
+        db.autotune = matches.is_present("autotune");
+        set_tuning(Some(db));
+    }
+
     let builder_result = Parameters::from_clap(&matches, probe);
     #[allow(unused_mut)]
     let mut params = match builder_result {
@@ -532,6 +581,11 @@ fn handle(matches: clap::ArgMatches, probe: Option<&Probe>) -> TractResult<()> {
         }
     };
 
+    if let (Some(file), true) = (tuning_file, matches.is_present("autotune")) {
+        let db = tract_core::tract_linalg::tuning::tuning().unwrap();
+        std::fs::write(file, db.to_string())?;
+    }
+
     let mut need_optimisations = false;
 
     match matches.subcommand() {
@@ -686,17 +686,28 @@ impl Parameters {
         {
             if let Some(spec) = matches.value_of("pulse") {
                 stage!("pulse", typed_model -> pulsed_model, |m:TypedModel| {
-                    let (sym, pulse) = if let Ok((s,p)) = scan_fmt!(spec, "{}={}", String, String) {
-                        (s, parse_tdim(&m.symbol_table, &p)?)
-                    } else if let Ok(i) = parse_tdim(&m.symbol_table, spec) {
-                        ("S".to_owned(), i)
-                    } else {
-                        bail!("Can not parse pulse specification {}", spec)
-                    };
-                    let sym = m.symbol_table.sym(&sym);
-                    PulsedModel::new(&m, sym, &pulse)
+                    let mut streams = vec!();
+                    for spec in spec.split(',') {
+                        let (sym, pulse) = if let Ok((s,p)) = scan_fmt!(spec, "{}={}", String, String) {
+                            (s, parse_tdim(&m.symbol_table, &p)?)
+                        } else if let Ok(i) = parse_tdim(&m.symbol_table, spec) {
+                            ("S".to_owned(), i)
+                        } else {
+                            bail!("Can not parse pulse specification {}", spec)
+                        };
+                        streams.push((m.symbol_table.sym(&sym), pulse));
+                    }
+                    PulsedModel::new_with_streams(&m, &streams)
                 });
+                // keep the pulsed model around for latency reporting and stream checks
+                let keep_pulsed = match matches.subcommand() {
+                    Some(("dump", sub)) => !sub.is_present("json") && !sub.is_present("html"),
+                    Some(("stream-check", _)) => true,
+                    _ => false,
+                };
+                let pulsed = if keep_pulsed { pulsed_model.clone() } else { None };
                 stage!("pulse-to-type", pulsed_model -> typed_model, |m:PulsedModel| m.into_typed());
+                pulsed_model = pulsed;
                 stage!("pulse-declutter", typed_model -> typed_model, |m:TypedModel| m.into_decluttered());
             }
         }
@@ -995,6 +1006,7 @@ pub fn display_params_from_clap(
         },
         info: matches.is_present("info"),
         json: matches.is_present("json"),
+        html: matches.is_present("html"),
     })
 }
 
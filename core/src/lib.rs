@@ -78,7 +78,9 @@ pub mod half;
 pub mod model;
 pub mod optim;
 pub mod plan;
+pub mod random;
 pub mod runtime;
+pub mod training;
 pub mod value;
 
 pub use dyn_clone;
@@ -108,7 +110,7 @@ pub mod internal {
     pub use crate::ops::change_axes::*;
     pub use crate::ops::element_wise::ElementWiseMiniOp;
     pub use crate::ops::{Cost, EvalOp, FrozenOpState, Op, OpState, Validation};
-    pub use crate::plan::SessionState;
+    pub use crate::plan::{EvalStep, SessionState};
     pub use crate::prelude::*;
     pub use anyhow::{anyhow, bail, ensure, format_err, Context as TractErrorContext};
     pub use dims;
@@ -56,7 +56,8 @@ where
         target.inputs = source.input_outlets()?.iter().map(|i| mapping[i]).collect();
         target.outputs = source.output_outlets()?.iter().map(|o| mapping[o]).collect();
         target.symbol_table = source.symbol_table.clone();
-        target.properties = source.properties.clone();
+        // keep the properties the translated nodes may have set on the target
+        target.properties.extend(source.properties.clone());
         Ok((target, mapping))
     }
 }
@@ -0,0 +1,506 @@
+//! Audio front-end: log-mel spectrogram, MFCC and polyphase resampling.
+use crate::internal::*;
+use num_complex::Complex;
+use num_integer::Integer;
+use tract_ndarray::{Array2, ArrayViewD, ArrayViewMutD, Ix2};
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum WindowType {
+    Blackman,
+    Hamming,
+    Hann,
+}
+
+impl WindowType {
+    pub fn generate(&self, size: usize, periodic: bool) -> TractResult<Tensor> {
+        use std::f32::consts::PI;
+        let divisor = ((size - 1 + periodic as usize) as f32).recip();
+        let mut output = Tensor::zero::<f32>(&[size])?;
+        match self {
+            Self::Blackman => {
+                output.as_slice_mut::<f32>()?.iter_mut().enumerate().for_each(|(ix, y)| {
+                    *y = 0.42 - 0.5 * (2. * PI * ix as f32 * divisor).cos()
+                        + 0.08 * (4. * PI * ix as f32 * divisor).cos()
+                })
+            }
+            Self::Hamming => {
+                output.as_slice_mut::<f32>()?.iter_mut().enumerate().for_each(|(ix, y)| {
+                    *y = (25. / 46.) - (21. / 46.) * (2. * PI * ix as f32 * divisor).cos()
+                })
+            }
+            Self::Hann => output
+                .as_slice_mut::<f32>()?
+                .iter_mut()
+                .enumerate()
+                .for_each(|(ix, y)| *y = 0.5 - 0.5 * (2. * PI * ix as f32 * divisor).cos()),
+        }
+        Ok(output)
+    }
+}
+
+fn hz_to_mel(hz: f32) -> f32 {
+    2595. * (1. + hz / 700.).log10()
+}
+
+fn mel_to_hz(mel: f32) -> f32 {
+    700. * (10f32.powf(mel / 2595.) - 1.)
+}
+
+/// Triangular filters evenly spaced on the (HTK) mel scale between `lower_hz`
+/// and `upper_hz`, as a [fft_len / 2 + 1, mel_bins] matrix mapping a one-sided
+/// spectrum to mel bands.
+pub fn mel_filterbank(
+    mel_bins: usize,
+    fft_len: usize,
+    sample_rate: f32,
+    lower_hz: f32,
+    upper_hz: f32,
+) -> Tensor {
+    let (low, high) = (hz_to_mel(lower_hz), hz_to_mel(upper_hz));
+    let edges: Vec<f32> = (0..mel_bins + 2)
+        .map(|ix| mel_to_hz(low + (high - low) * ix as f32 / (mel_bins + 1) as f32))
+        .collect();
+    let mut filterbank = Array2::<f32>::zeros((fft_len / 2 + 1, mel_bins));
+    for ((bin, mel), weight) in filterbank.indexed_iter_mut() {
+        let freq = bin as f32 * sample_rate / fft_len as f32;
+        let rising = (freq - edges[mel]) / (edges[mel + 1] - edges[mel]);
+        let falling = (edges[mel + 2] - freq) / (edges[mel + 2] - edges[mel + 1]);
+        *weight = rising.min(falling).max(0.);
+    }
+    filterbank.into_tensor()
+}
+
+/// Call `f` for each 1D lane of `input` along `axis`, with the matching
+/// `output` lane, whose `axis` and trailing axes are kept.
+fn for_each_lane(
+    input: &ArrayViewD<f32>,
+    output: &mut ArrayViewMutD<f32>,
+    axis: usize,
+    mut f: impl FnMut(&[f32], &mut ArrayViewMutD<f32>) -> TractResult<()>,
+) -> TractResult<()> {
+    let rank = input.ndim();
+    let mut iterator_shape: TVec<usize> = input.shape().into();
+    iterator_shape[axis] = 1;
+    let mut signal = vec![];
+    for coords in tract_ndarray::indices(&*iterator_shape) {
+        let slice = |ax: usize| -> tract_ndarray::Slice {
+            if ax == axis || ax >= rank {
+                (..).into()
+            } else {
+                let c = coords[ax] as isize;
+                (c..=c).into()
+            }
+        };
+        signal.clear();
+        signal.extend(input.slice_each_axis(|ax| slice(ax.axis.index())).iter().copied());
+        f(&signal, &mut output.slice_each_axis_mut(|ax| slice(ax.axis.index())))?;
+    }
+    Ok(())
+}
+
+/// Log-mel spectrogram of a real signal: the signal is split in frames of
+/// `frame` samples every `stride`, windowed and zero-padded to `fft_len`. The
+/// magnitude of the one-sided spectrum raised to `power` goes through the
+/// filterbank and the natural logarithm.
+///
+/// The time axis becomes the frame axis, and the mel bins are appended as the
+/// last axis.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogMelSpectrogram {
+    pub axis: usize,
+    pub frame: usize,
+    pub stride: usize,
+    pub fft_len: usize,
+    /// Window of `frame` samples applied to each frame.
+    pub window: Option<Arc<Tensor>>,
+    /// 1 for a magnitude spectrum, 2 for a power spectrum.
+    pub power: f32,
+    /// [fft_len / 2 + 1, mel_bins] filterbank, see `mel_filterbank`.
+    pub filterbank: Arc<Tensor>,
+    /// Mel energies are clamped to this floor before the logarithm.
+    pub log_floor: f32,
+}
+
+impl LogMelSpectrogram {
+    pub fn mel_bins(&self) -> usize {
+        self.filterbank.shape()[1]
+    }
+
+    pub fn frames(&self, len: &TDim) -> TDim {
+        (len.clone() + 1 - self.frame).div_ceil(self.stride as u64)
+    }
+
+    fn output_shape(&self, input: &[TDim], features: usize) -> TVec<TDim> {
+        let mut shape: TVec<TDim> = input.into();
+        shape[self.axis] = self.frames(&shape[self.axis]);
+        shape.push(features.to_dim());
+        shape
+    }
+
+    fn eval_f32(&self, input: &Tensor) -> TractResult<Tensor> {
+        let len = input.shape()[self.axis];
+        let frames = Integer::div_ceil(&(len + 1).saturating_sub(self.frame), &self.stride);
+        let mut shape: TVec<usize> = input.shape().into();
+        shape[self.axis] = frames;
+        shape.push(self.mel_bins());
+        let mut output = Tensor::zero::<f32>(&shape)?;
+        let window = self.window.as_ref().map(|w| w.as_slice::<f32>()).transpose()?;
+        let filterbank = self.filterbank.to_array_view::<f32>()?.into_dimensionality::<Ix2>()?;
+        let fft = rustfft::FftPlanner::new().plan_fft_forward(self.fft_len);
+        let mut buffer = vec![Complex::new(0f32, 0f32); self.fft_len];
+        let mut spectrum = tract_ndarray::Array1::<f32>::zeros(self.fft_len / 2 + 1);
+        for_each_lane(
+            &input.to_array_view::<f32>()?,
+            &mut output.to_array_view_mut::<f32>()?,
+            self.axis,
+            |signal, output| {
+                let mut values = Vec::with_capacity(frames * self.mel_bins());
+                for f in 0..frames {
+                    buffer.iter_mut().for_each(|c| *c = Complex::new(0., 0.));
+                    for (ix, x) in signal[f * self.stride..][..self.frame].iter().enumerate() {
+                        buffer[ix].re = x * window.map(|w| w[ix]).unwrap_or(1.);
+                    }
+                    fft.process(&mut buffer);
+                    spectrum.iter_mut().zip(buffer.iter()).for_each(|(s, c)| {
+                        *s = if self.power == 2. { c.norm_sqr() } else { c.norm().powf(self.power) }
+                    });
+                    let mel = spectrum.dot(&filterbank);
+                    values.extend(mel.iter().map(|m| m.max(self.log_floor).ln()));
+                }
+                // other axes are singletons: frames, then mel bins
+                output.iter_mut().zip(values).for_each(|(o, v)| *o = v);
+                Ok(())
+            },
+        )?;
+        Ok(output)
+    }
+}
+
+impl Op for LogMelSpectrogram {
+    fn name(&self) -> Cow<str> {
+        "LogMelSpectrogram".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "axis: {} frame: {} stride: {} fft: {} mel bins: {}",
+            self.axis,
+            self.frame,
+            self.stride,
+            self.fft_len,
+            self.mel_bins()
+        )])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for LogMelSpectrogram {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        Ok(tvec!(self.eval_f32(&input)?.into_tvalue()))
+    }
+}
+
+impl TypedOp for LogMelSpectrogram {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs[0].datum_type == f32::datum_type(), "LogMelSpectrogram expects f32 input");
+        ensure!(self.fft_len >= self.frame, "FFT length must be at least the frame length");
+        ensure!(self.filterbank.shape() == [self.fft_len / 2 + 1, self.mel_bins()]);
+        ensure!(self.window.as_ref().map(|w| w.len() == self.frame).unwrap_or(true));
+        Ok(tvec!(f32::fact(self.output_shape(&inputs[0].shape, self.mel_bins()))))
+    }
+
+    as_op!();
+}
+
+/// Mel-frequency cepstral coefficients: the first `coefficients` terms of the
+/// orthonormal DCT-II of a log-mel spectrogram.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mfcc {
+    pub log_mel: LogMelSpectrogram,
+    pub coefficients: usize,
+}
+
+impl Mfcc {
+    /// [mel_bins, coefficients] DCT-II matrix.
+    fn dct(&self) -> Array2<f32> {
+        use std::f32::consts::PI;
+        let n = self.log_mel.mel_bins();
+        Array2::from_shape_fn((n, self.coefficients), |(i, k)| {
+            let scale = if k == 0 { (1. / n as f32).sqrt() } else { (2. / n as f32).sqrt() };
+            scale * (PI / n as f32 * (i as f32 + 0.5) * k as f32).cos()
+        })
+    }
+}
+
+impl Op for Mfcc {
+    fn name(&self) -> Cow<str> {
+        "Mfcc".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        let mut info = self.log_mel.info()?;
+        info.push(format!("coefficients: {}", self.coefficients));
+        Ok(info)
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for Mfcc {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let log_mel = self.log_mel.eval_f32(&input)?;
+        let log_mel = log_mel.to_array_view::<f32>()?;
+        let mel_bins = self.log_mel.mel_bins();
+        let rows = log_mel.len() / mel_bins;
+        let cepstrum = log_mel.to_shape((rows, mel_bins))?.dot(&self.dct());
+        let mut shape: TVec<usize> = log_mel.shape().into();
+        *shape.last_mut().unwrap() = self.coefficients;
+        Ok(tvec!(cepstrum.into_tensor().into_shape(&shape)?.into_tvalue()))
+    }
+}
+
+impl TypedOp for Mfcc {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        self.log_mel.output_facts(inputs)?;
+        ensure!(self.coefficients <= self.log_mel.mel_bins(), "More coefficients than mel bins");
+        Ok(tvec!(f32::fact(self.log_mel.output_shape(&inputs[0].shape, self.coefficients))))
+    }
+
+    as_op!();
+}
+
+/// Polyphase resampling by a rational factor `up / down` along `axis`.
+///
+/// The signal is conceptually upsampled by `up` with zeros, low-pass filtered
+/// by `filter` and decimated by `down`. No padding is applied: output `m` is
+/// computed from the `taps` inputs starting at `m * down / up`, which makes
+/// the op causal and streamable.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resample {
+    pub axis: usize,
+    pub up: usize,
+    pub down: usize,
+    /// Filter at the upsampled rate, of `up * taps` coefficients.
+    pub filter: Arc<Tensor>,
+}
+
+impl Resample {
+    /// Resampling from `from_rate` to `to_rate` with a Hann-windowed sinc
+    /// filter spanning `zeros` zero crossings on each side. The filter is
+    /// normalized so that each phase preserves constant signals.
+    pub fn new(axis: usize, from_rate: usize, to_rate: usize, zeros: usize) -> Resample {
+        use std::f32::consts::PI;
+        let gcd = from_rate.gcd(&to_rate);
+        let (up, down) = (to_rate / gcd, from_rate / gcd);
+        // cutoff at the lowest nyquist frequency: zero crossings every `period`
+        // upsampled samples
+        let period = up.max(down);
+        let half = zeros * period;
+        let taps = Integer::div_ceil(&(2 * half + 1), &up);
+        let mut filter: Vec<f32> = (0..taps * up)
+            .map(|ix| {
+                let x = ix as f32 - half as f32;
+                if x.abs() > half as f32 {
+                    0.
+                } else if x == 0. {
+                    1.
+                } else {
+                    let sinc = (PI * x / period as f32).sin() / (PI * x / period as f32);
+                    sinc * (0.5 + 0.5 * (PI * x / (half + 1) as f32).cos())
+                }
+            })
+            .collect();
+        for phase in 0..up {
+            let gain = filter.iter().skip(phase).step_by(up).sum::<f32>().recip();
+            filter.iter_mut().skip(phase).step_by(up).for_each(|h| *h *= gain);
+        }
+        Resample { axis, up, down, filter: rctensor1(&filter) }
+    }
+
+    pub fn taps(&self) -> usize {
+        self.filter.len() / self.up
+    }
+
+    pub fn output_len(&self, len: &TDim) -> TDim {
+        ((len.clone() + 1 - self.taps()) * self.up).div_ceil(self.down as u64)
+    }
+}
+
+impl Op for Resample {
+    fn name(&self) -> Cow<str> {
+        "Resample".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "axis: {} up: {} down: {} taps: {}",
+            self.axis,
+            self.up,
+            self.down,
+            self.taps()
+        )])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for Resample {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let taps = self.taps();
+        let len = Integer::div_ceil(
+            &((input.shape()[self.axis] + 1).saturating_sub(taps) * self.up),
+            &self.down,
+        );
+        let mut shape: TVec<usize> = input.shape().into();
+        shape[self.axis] = len;
+        let mut output = Tensor::zero::<f32>(&shape)?;
+        let filter = self.filter.as_slice::<f32>()?;
+        for_each_lane(
+            &input.to_array_view::<f32>()?,
+            &mut output.to_array_view_mut::<f32>()?,
+            self.axis,
+            |signal, output| {
+                for (m, y) in output.iter_mut().enumerate() {
+                    let (q, p) = (m * self.down / self.up, m * self.down % self.up);
+                    *y = signal[q..][..taps]
+                        .iter()
+                        .enumerate()
+                        .map(|(j, x)| x * filter[(taps - 1 - j) * self.up + p])
+                        .sum();
+                }
+                Ok(())
+            },
+        )?;
+        Ok(tvec!(output.into_tvalue()))
+    }
+}
+
+impl TypedOp for Resample {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs[0].datum_type == f32::datum_type(), "Resample expects f32 input");
+        ensure!(self.up > 0 && self.down > 0 && self.filter.len() % self.up == 0);
+        let mut fact = inputs[0].without_value();
+        fact.shape.set(self.axis, self.output_len(&inputs[0].shape[self.axis]));
+        Ok(tvec!(fact))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn log_mel(frame: usize, stride: usize, fft_len: usize, mel_bins: usize) -> LogMelSpectrogram {
+        LogMelSpectrogram {
+            axis: 1,
+            frame,
+            stride,
+            fft_len,
+            window: Some(WindowType::Hann.generate(frame, true).unwrap().into_arc_tensor()),
+            power: 2.,
+            filterbank: mel_filterbank(mel_bins, fft_len, 16000., 0., 8000.).into_arc_tensor(),
+            log_floor: 1e-10,
+        }
+    }
+
+    fn run(op: impl EvalOp, input: Tensor) -> TractResult<Tensor> {
+        Ok(op.eval(tvec!(input.into_tvalue()))?.remove(0).into_tensor())
+    }
+
+    fn signal(len: usize) -> Tensor {
+        let samples: Vec<f32> = (0..len).map(|i| (i as f32 * 0.3).sin() + 0.1 * i as f32).collect();
+        tensor1(&samples).into_shape(&[1, len]).unwrap()
+    }
+
+    #[test]
+    fn log_mel_against_naive_dft() -> TractResult<()> {
+        use std::f32::consts::PI;
+        let op = log_mel(6, 4, 32, 3);
+        let input = signal(18);
+        let output = run(op.clone(), input.clone())?;
+        assert_eq!(output.shape(), &[1, 4, 3]);
+        let samples = input.as_slice::<f32>()?;
+        let window = op.window.as_ref().unwrap().as_slice::<f32>()?;
+        let filterbank = op.filterbank.to_array_view::<f32>()?.into_dimensionality::<Ix2>()?;
+        for f in 0..4 {
+            let power: Vec<f32> = (0..17)
+                .map(|k| {
+                    let (mut re, mut im) = (0., 0.);
+                    for n in 0..6 {
+                        let x = samples[f * 4 + n] * window[n];
+                        re += x * (2. * PI * (k * n) as f32 / 32.).cos();
+                        im -= x * (2. * PI * (k * n) as f32 / 32.).sin();
+                    }
+                    re * re + im * im
+                })
+                .collect();
+            for m in 0..3 {
+                let mel: f32 = (0..17).map(|k| power[k] * filterbank[(k, m)]).sum();
+                assert!(mel > 0.);
+                let got = output.to_array_view::<f32>()?[[0, f, m]];
+                assert!((mel.max(1e-10).ln() - got).abs() < 1e-4, "frame {f} mel {m}");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn mfcc_first_coefficient() -> TractResult<()> {
+        let op = Mfcc { log_mel: log_mel(8, 4, 16, 5), coefficients: 3 };
+        let input = signal(24);
+        let log_mel = run(op.log_mel.clone(), input.clone())?;
+        let mfcc = run(op, input)?;
+        assert_eq!(mfcc.shape(), &[1, 5, 3]);
+        let log_mel = log_mel.to_array_view::<f32>()?;
+        for f in 0..5 {
+            let sum: f32 = (0..5).map(|m| log_mel[[0, f, m]]).sum();
+            let c0 = mfcc.to_array_view::<f32>()?[[0, f, 0]];
+            assert!((sum / 5f32.sqrt() - c0).abs() < 1e-4);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn resample_keeps_dc() -> TractResult<()> {
+        for (from, to) in [(16000, 8000), (8000, 16000), (44100, 16000)] {
+            let op = Resample::new(0, from, to, 4);
+            let output = run(op.clone(), tensor1(&[1f32; 200]))?;
+            assert_eq!(output.len(), op.output_len(&200.to_dim()).to_usize()?);
+            assert!(output.as_slice::<f32>()?.iter().all(|y| (y - 1.).abs() < 1e-3));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn resample_sine() -> TractResult<()> {
+        use std::f32::consts::PI;
+        let op = Resample::new(0, 8000, 12000, 8);
+        assert_eq!((op.up, op.down), (3, 2));
+        let input: Vec<f32> = (0..400).map(|i| (2. * PI * 440. * i as f32 / 8000.).sin()).collect();
+        let output = run(op.clone(), tensor1(&input))?;
+        // the filter is centered on its 24th coefficient, at the upsampled rate
+        let lag = (op.taps() - 1) as f32 - 24. / 3.;
+        for (m, y) in output.as_slice::<f32>()?.iter().enumerate() {
+            let t = m as f32 * 2. / 3. + lag;
+            assert!((y - (2. * PI * 440. * t / 8000.).sin()).abs() < 1e-2, "sample {m}");
+        }
+        Ok(())
+    }
+}
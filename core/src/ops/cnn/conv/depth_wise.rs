@@ -2,7 +2,9 @@ use crate::internal::*;
 use crate::ops::cnn::patches::{Zone, ZoneScanner};
 use crate::ops::cnn::Patch;
 use crate::ops::nn::DataShape;
-use num_traits::Zero;
+use num_traits::{AsPrimitive, Zero};
+use tract_linalg::depthwise::DepthWise as DepthWiseKernel;
+use tract_linalg::Scaler;
 
 #[derive(Debug, Clone, new, Hash)]
 pub struct DepthWise {
@@ -11,6 +13,20 @@ pub struct DepthWise {
     output_shape: DataShape,
     kernel_chw: Arc<Tensor>,
     bias: Arc<Tensor>,
+    q_params: Option<DepthWiseQParams>,
+}
+
+/// Quantization of an integer depthwise convolution.
+///
+/// The input is i8, the kernel is i32 with its zero point already removed,
+/// the bias is i32 in the accumulator scale.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct DepthWiseQParams {
+    pub input_zero_point: i32,
+    pub output_zero_point: i32,
+    /// Requantization of the accumulators, one per channel.
+    pub scalers: TVec<Scaler>,
+    pub output_dt: DatumType,
 }
 
 impl Op for DepthWise {
@@ -36,16 +52,22 @@ impl EvalOp for DepthWise {
 
     fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
         let dt = inputs[0].datum_type();
-        #[cfg(target_arch = "aarch64")]
-        if dt == f16::datum_type() && tract_linalg::arm64::has_fp16() {
-            return unsafe { eval_t_aarch64fp16::<f16>(
-                self,
-                inputs,
-                |a, b| tract_linalg::arm64::add_f16(a, b),
-                |a, b| tract_linalg::arm64::mul_f16(a, b),
-            ) };
+        if let Some(q) = &self.q_params {
+            return self.eval_quant(q, &inputs[0]);
         }
-        dispatch_floatlike!(Self::eval_gen(dt)(self, inputs))
+        let mut output = unsafe { Tensor::uninitialized_dt(dt, &self.output_shape.shape)? };
+        if dt == f32::datum_type() {
+            let bias = self.bias.as_slice::<f32>()?;
+            let kernel = (tract_linalg::ops().depthwise_f32)();
+            self.eval_simd(&*kernel, &inputs[0], &mut output, |_, b| b.copy_from_slice(bias))?;
+        } else if dt == f16::datum_type() {
+            let bias = self.bias.as_slice::<f16>()?;
+            let kernel = (tract_linalg::ops().depthwise_f16)();
+            self.eval_simd(&*kernel, &inputs[0], &mut output, |_, b| b.copy_from_slice(bias))?;
+        } else {
+            return dispatch_floatlike!(Self::eval_gen(dt)(self, inputs));
+        }
+        Ok(tvec!(output.into_tvalue()))
     }
 }
 
@@ -57,6 +79,151 @@ impl DepthWise {
         unsafe { eval_t_generic::<T>(self, inputs, |a, b| a + b, |a, b| a * b) }
     }
 
+    fn eval_quant(&self, q: &DepthWiseQParams, input: &Tensor) -> TractResult<TVec<TValue>> {
+        let c = *self.input_shape.c();
+        let taps = self.kernel_chw.len() / c;
+        let kernel = self.kernel_chw.as_slice::<i32>()?;
+        let bias = self.bias.as_slice::<i32>()?;
+        let mut acc = unsafe { Tensor::uninitialized::<i32>(&self.output_shape.shape)? };
+        // padding is the input zero point: only valid taps contribute to the
+        // sum of kernel values to compensate
+        self.eval_simd(&*(tract_linalg::ops().depthwise_i8)(), input, &mut acc, |zone, b| {
+            for (ix, b) in b.iter_mut().enumerate() {
+                let kernel = &kernel[ix * taps..][..taps];
+                let sum_k: i32 = zone.values_offsets.iter().map(|(k, _)| kernel[*k]).sum();
+                *b = bias[ix] - q.input_zero_point * sum_k;
+            }
+        })?;
+        let mut output =
+            unsafe { Tensor::uninitialized_dt(q.output_dt, &self.output_shape.shape)? };
+        match q.output_dt.unquantized() {
+            DatumType::I8 => self.requant::<i8>(q, &acc, &mut output)?,
+            DatumType::U8 => self.requant::<u8>(q, &acc, &mut output)?,
+            DatumType::I32 => self.requant::<i32>(q, &acc, &mut output)?,
+            dt => bail!("Unsupported output type for quantized DepthWiseConv: {:?}", dt),
+        }
+        Ok(tvec!(output.into_tvalue()))
+    }
+
+    fn requant<T: Datum + Copy>(
+        &self,
+        q: &DepthWiseQParams,
+        acc: &Tensor,
+        output: &mut Tensor,
+    ) -> TractResult<()>
+    where
+        i32: AsPrimitive<T>,
+    {
+        let min = q.output_dt.unquantized().min_value().cast_to_scalar::<i32>()?;
+        let max = q.output_dt.unquantized().max_value().cast_to_scalar::<i32>()?;
+        let c_axis = ndarray::Axis(self.output_shape.c_axis());
+        let acc = acc.to_array_view::<i32>()?;
+        let mut output = output.to_array_view_mut::<T>()?;
+        for (c, scaler) in q.scalers.iter().enumerate() {
+            ndarray::Zip::from(output.index_axis_mut(c_axis, c))
+                .and(acc.index_axis(c_axis, c))
+                .for_each(|o, a| *o = (*a * *scaler + q.output_zero_point).clamp(min, max).as_());
+        }
+        Ok(())
+    }
+
+    /// Evaluates the convolution with the linalg depthwise kernels.
+    ///
+    /// If channels are the innermost axis, the kernels vectorize over them.
+    /// Otherwise they scan rows of the patch zones, one channel at a time,
+    /// gathering strided inputs when needed. `zone_bias` fills the
+    /// accumulator initial values of a zone.
+    fn eval_simd<TI: Datum + Copy, TA: Datum + Copy>(
+        &self,
+        kernel_impl: &dyn DepthWiseKernel<TI, TA>,
+        input: &Tensor,
+        output: &mut Tensor,
+        zone_bias: impl Fn(&Zone, &mut [TA]),
+    ) -> TractResult<()> {
+        let c = *self.input_shape.c();
+        let taps = self.kernel_chw.len() / c;
+        let kernel = self.kernel_chw.as_slice::<TA>()?;
+        let n = *self.input_shape.n().unwrap_or(&1);
+        let n_stride_i = *self.input_shape.n_stride().unwrap_or(&0);
+        let n_stride_o = *self.output_shape.n_stride().unwrap_or(&0);
+        let c_stride_i = *self.input_shape.c_stride();
+        let c_stride_o = *self.output_shape.c_stride();
+        let input = input.as_slice::<TI>()?;
+        let output = output.as_slice_mut::<TA>()?;
+        let mut bias = vec![TA::default(); c];
+        if c_stride_i == 1 && c_stride_o == 1 {
+            let mut kernel_hwc = vec![TA::default(); kernel.len()];
+            for ci in 0..c {
+                for tap in 0..taps {
+                    kernel_hwc[tap * c + ci] = kernel[ci * taps + tap];
+                }
+            }
+            for zone in &self.patch.zones {
+                zone_bias(zone, &mut bias);
+                for ni in 0..n {
+                    let input = &input[ni * n_stride_i..];
+                    let output = &mut output[ni * n_stride_o..];
+                    zone.visit_output(&self.patch, |scanner| {
+                        let acc = &mut output[scanner.output_offset as usize..][..c];
+                        acc.copy_from_slice(&bias);
+                        for (tap, offset) in scanner.valid_offsets_ker_in() {
+                            kernel_impl.mul_add(
+                                acc,
+                                &input[offset as usize..][..c],
+                                &kernel_hwc[tap * c..][..c],
+                            );
+                        }
+                    });
+                }
+            }
+        } else {
+            let mut row = vec![];
+            let mut gathered = vec![];
+            for zone in &self.patch.zones {
+                zone_bias(zone, &mut bias);
+                let mut scanner = ZoneScanner::new(zone, &self.patch);
+                for ni in 0..n {
+                    let input = &input[ni * n_stride_i..];
+                    let output = &mut output[ni * n_stride_o..];
+                    unsafe { scanner.reset() };
+                    while !scanner.done {
+                        let len = scanner.inner_loop_len;
+                        let stride_i = scanner.inner_loop_input_full_stride as usize;
+                        let stride_o = scanner.inner_loop_output_stride as usize;
+                        for ci in 0..c {
+                            let kernel = &kernel[ci * taps..][..taps];
+                            let input = &input[ci * c_stride_i..];
+                            let start_o = scanner.output_offset as usize + ci * c_stride_o;
+                            let acc = if stride_o == 1 {
+                                &mut output[start_o..][..len]
+                            } else {
+                                row.resize(len, TA::default());
+                                &mut row[..]
+                            };
+                            acc.fill(bias[ci]);
+                            for (tap, offset) in scanner.valid_offsets_ker_in() {
+                                let input = &input[offset as usize..];
+                                if stride_i == 1 {
+                                    kernel_impl.mul_add_scalar(acc, &input[..len], kernel[tap]);
+                                } else {
+                                    gathered.clear();
+                                    gathered.extend((0..len).map(|i| input[i * stride_i]));
+                                    kernel_impl.mul_add_scalar(acc, &gathered, kernel[tap]);
+                                }
+                            }
+                            if stride_o != 1 {
+                                for (i, v) in row.iter().enumerate() {
+                                    output[start_o + i * stride_o] = *v;
+                                }
+                            }
+                        }
+                        unsafe { scanner.next_non_inner_axis() };
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl TypedOp for DepthWise {
@@ -71,7 +238,8 @@ impl TypedOp for DepthWise {
             self.input_shape.c(),
             self.bias.len()
         );
-        Ok(tvec!(inputs[0].datum_type.fact(&self.output_shape.shape)))
+        let dt = self.q_params.as_ref().map(|q| q.output_dt).unwrap_or(inputs[0].datum_type);
+        Ok(tvec!(dt.fact(&self.output_shape.shape)))
     }
 
     fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
@@ -304,169 +472,3 @@ macro_rules! impl_eval {
 }
 
 impl_eval!(generic);
-impl_eval! {
-#[target_feature(enable = "fp16")] 
-#[cfg(target_arch = "aarch64")]
-aarch64fp16 
-}
-//#[target_feature(enable = "fp16")] impl_eval!(aarch64fp16);
-
-/* partial alternative impl that may be relevant when simd gets better */
-
-/*
-#[inline(never)]
-unsafe fn process_zone_4_f32(
-&self,
-zone: &Zone,
-c_stride_i: isize,
-c_stride_o: isize,
-k_stride_i: isize,
-iptr: *const f32,
-kptr: *const f32,
-bias: *const f32,
-optr: *mut f32,
-) {
-use std::simd::*;
-let mut visitor = ZoneScanner::new(zone, &self.patch);
-let ioffset0 = zone.values_offsets[0].1;
-let ioffset1 = zone.values_offsets[1].1;
-let ioffset2 = zone.values_offsets[2].1;
-let ioffset3 = zone.values_offsets[3].1;
-for c in 0..*self.input_shape.c() as isize {
-visitor.reset();
-let kptr = kptr.offset(k_stride_i * c);
-let iptr = iptr.offset(c_stride_i * c);
-let optr = optr.offset(c_stride_o * c);
-let k0 = *kptr.offset(zone.values_offsets[0].0 as isize);
-let k1 = *kptr.offset(zone.values_offsets[1].0 as isize);
-let k2 = *kptr.offset(zone.values_offsets[2].0 as isize);
-let k3 = *kptr.offset(zone.values_offsets[3].0 as isize);
-let k0 = f32x4::splat(k0);
-let k1 = f32x4::splat(k1);
-let k2 = f32x4::splat(k2);
-let k3 = f32x4::splat(k3);
-let bias = f32x4::splat(*bias.offset(c));
-while !visitor.done {
-let iptr = iptr.offset(visitor.input_center_offset);
-let optr = optr.offset(visitor.output_offset);
-let mut i  = 0;
-while i + 4 <
-for i in 0..visitor.inner_loop_len as isize {
-let iptr = iptr.offset(visitor.inner_loop_input_full_stride * i);
-let optr = optr.offset(visitor.inner_loop_output_stride * i);
-let i0 = *iptr.offset(ioffset0);
-let i1 = *iptr.offset(ioffset1);
-let i2 = *iptr.offset(ioffset2);
-let i3 = *iptr.offset(ioffset3);
-let i = f32x4::from_array([i0, i1, i2, i3]);
-let p = (i * k).reduce_sum();
-let sum = bias + p;
-     *optr = sum
-     }
-     visitor.next_non_inner_axis()
-     }
-     }
-     }
-     */
-
-/*
-#[inline(never)]
-unsafe fn process_zone_4_f32(
-&self,
-zone: &Zone,
-c_stride_i: isize,
-c_stride_o: isize,
-k_stride_i: isize,
-iptr: *const f32,
-kptr: *const f32,
-bias: *const f32,
-optr: *mut f32,
-) {
-use std::simd::*;
-let mut visitor = ZoneScanner::new(zone, &self.patch);
-let ioffset0 = zone.values_offsets[0].1;
-let ioffset1 = zone.values_offsets[1].1;
-let ioffset2 = zone.values_offsets[2].1;
-let ioffset3 = zone.values_offsets[3].1;
-for c in 0..*self.input_shape.c() as isize {
-visitor.reset();
-let kptr = kptr.offset(k_stride_i * c);
-let iptr = iptr.offset(c_stride_i * c);
-let optr = optr.offset(c_stride_o * c);
-let k0 = *kptr.offset(zone.values_offsets[0].0 as isize);
-let k1 = *kptr.offset(zone.values_offsets[1].0 as isize);
-let k2 = *kptr.offset(zone.values_offsets[2].0 as isize);
-let k3 = *kptr.offset(zone.values_offsets[3].0 as isize);
-let k = f32x4::from_array([k0, k1, k2, k3]);
-let bias = *bias.offset(c);
-while !visitor.done {
-let iptr = iptr.offset(visitor.input_center_offset);
-let optr = optr.offset(visitor.output_offset);
-for i in 0..visitor.inner_loop_len as isize {
-let iptr = iptr.offset(visitor.inner_loop_input_full_stride * i);
-let optr = optr.offset(visitor.inner_loop_output_stride * i);
-let i0 = *iptr.offset(ioffset0);
-let i1 = *iptr.offset(ioffset1);
-let i2 = *iptr.offset(ioffset2);
-let i3 = *iptr.offset(ioffset3);
-let i = f32x4::from_array([i0, i1, i2, i3]);
-let p = (i * k).reduce_sum();
-let sum = bias + p;
-     *optr = sum
-     }
-     visitor.next_non_inner_axis()
-     }
-     }
-     }
-     */
-
-/*
-#[inline(never)]
-unsafe fn process_zone_4<T: Datum + Copy + ndarray::LinalgScalar>(
-&self,
-zone: &Zone,
-c_stride_i: isize,
-c_stride_o: isize,
-k_stride_i: isize,
-iptr: *const T,
-kptr: *const T,
-bias: *const T,
-optr: *mut T,
-) {
-let mut visitor = ZoneScanner::new(zone, &self.patch);
-let ioffset0 = zone.values_offsets[0].1;
-let ioffset1 = zone.values_offsets[1].1;
-let ioffset2 = zone.values_offsets[2].1;
-let ioffset3 = zone.values_offsets[3].1;
-for c in 0..*self.input_shape.c() as isize {
-visitor.reset();
-let kptr = kptr.offset(k_stride_i * c);
-let iptr = iptr.offset(c_stride_i * c);
-let optr = optr.offset(c_stride_o * c);
-let k0 = *kptr.offset(zone.values_offsets[0].0 as isize);
-let k1 = *kptr.offset(zone.values_offsets[1].0 as isize);
-let k2 = *kptr.offset(zone.values_offsets[2].0 as isize);
-let k3 = *kptr.offset(zone.values_offsets[3].0 as isize);
-let bias = *bias.offset(c);
-while !visitor.done {
-let iptr = iptr.offset(visitor.input_center_offset);
-let optr = optr.offset(visitor.output_offset);
-for i in 0..visitor.inner_loop_len as isize {
-let iptr = iptr.offset(visitor.inner_loop_input_full_stride * i);
-let optr = optr.offset(visitor.inner_loop_output_stride * i);
-let i0 = *iptr.offset(ioffset0);
-let i1 = *iptr.offset(ioffset1);
-let i2 = *iptr.offset(ioffset2);
-let i3 = *iptr.offset(ioffset3);
-let p0 = i0 * k0;
-let p1 = i1 * k1;
-let p2 = i2 * k2;
-let p3 = i3 * k3;
-let sum = bias + p0 + p1 + p2 + p3;
-     *optr = sum
-     }
-     visitor.next_non_inner_axis()
-     }
-     }
-     }
-     */
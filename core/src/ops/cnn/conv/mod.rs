@@ -3,12 +3,14 @@ mod im2col;
 mod lazy_im2col;
 mod q_sum_b;
 mod unary;
+mod winograd;
 
 use crate::internal::*;
 
 pub use self::im2col::Im2Col;
 pub(crate) use self::q_sum_b::QSumB;
 pub use self::unary::ConvUnary;
+pub use self::winograd::{WinogradInputTransform, WinogradOutputTransform, WinogradTile};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
 pub enum KernelFormat {
@@ -1,7 +1,7 @@
 use ndarray::*;
 use num_integer::Integer;
 use tract_data::itertools::izip;
-use tract_linalg::mmm::InputStoreSpec;
+use tract_linalg::mmm::{InputStoreSpec, RoundingPolicy};
 use tract_num_traits::Zero;
 
 use crate::internal::*;
@@ -19,9 +19,11 @@ use crate::ops::math::Sub;
 use crate::ops::matmul::lir_unary::AddMatMulGeometry;
 use crate::ops::matmul::lir_unary::MapOutputAxisToInput;
 use crate::ops::matmul::mir_quant::wire_offset_u8_as_i8;
+use crate::ops::matmul::pack::prepacked;
 
-use super::depth_wise::DepthWise;
+use super::depth_wise::{DepthWise, DepthWiseQParams};
 use super::im2col::Im2Col;
+use super::winograd::{WinogradInputTransform, WinogradOutputTransform, WinogradTile};
 use crate::ops::cnn::conv::KernelFormat;
 use crate::ops::cnn::pools::{ConcretePoolGeometry, PoolGeometry, PoolSpec};
 use crate::ops::matmul::lir_unary::{LirMatMulUnary, ProtoFusedSpec};
@@ -29,6 +31,7 @@ use crate::ops::nn::{BaseDataShape, DataFormat, DataShape};
 
 use tract_linalg::frame::Packer;
 use tract_linalg::mmm::MatMatMul;
+use tract_linalg::Scaler;
 
 use std::iter::Sum;
 
@@ -63,17 +66,21 @@ impl ConvUnary {
         )
     }
 
+    fn packed_kernel_shape(&self, packer: &Packer, k: usize, m: usize) -> [usize; 2] {
+        let packed = Integer::next_multiple_of(
+            &packer.len(k, m),
+            &(packer.alignment() / self.kernel.datum_type().size_of()),
+        );
+        [self.group, packed]
+    }
+
     // shape is g,packed
     fn kernel_as_packed_as(&self, packer: &Packer, k: usize, m: usize) -> TractResult<Arc<Tensor>> {
         let kernel = self.kernel_as_group_o_ihw()?;
         unsafe {
-            let packed = Integer::next_multiple_of(
-                &packer.len(k, m),
-                &(packer.alignment() / kernel.datum_type().size_of()),
-            );
             let packed = Tensor::uninitialized_aligned_dt(
                 kernel.datum_type(),
-                &[self.group, packed],
+                &self.packed_kernel_shape(packer, k, m),
                 packer.alignment(),
             )?;
             for g in 0..self.group {
@@ -427,6 +434,67 @@ impl ConvUnary {
         Self::wire_geo_reshape(model, name, &wire, &geo.output_shape)
     }
 
+    /// Winograd variant to use for this convolution, if it is eligible and
+    /// expected to be cheaper than im2col.
+    pub fn winograd_tile(&self, input_fact: &TypedFact) -> TractResult<Option<WinogradTile>> {
+        let Some(shape) = input_fact.shape.as_concrete() else { return Ok(None) };
+        if input_fact.datum_type != f32::datum_type()
+            || self.kernel.datum_type() != f32::datum_type()
+            || self.q_params.is_some()
+            || self.group != 1
+            || *self.pool_spec.kernel_shape != [3, 3]
+            || self.pool_spec.strides().iter().any(|s| *s != 1)
+            || self.pool_spec.dilations().iter().any(|d| *d != 1)
+        {
+            return Ok(None);
+        }
+        let output_shape = self.pool_spec.output_shape(shape)?;
+        Ok(WinogradTile::pick(
+            *output_shape.n().unwrap_or(&1),
+            self.input_channels(),
+            self.output_channels(),
+            output_shape.hw_dims(),
+        ))
+    }
+
+    pub fn wire_as_winograd(
+        &self,
+        model: &mut TypedModel,
+        name: &str,
+        wire: OutletId,
+        tile: WinogradTile,
+    ) -> TractResult<TVec<OutletId>> {
+        let shape =
+            model.outlet_fact(wire)?.shape.as_concrete().context("Expect concrete shape")?;
+        let input_shape = self.pool_spec.data_format.shape(shape.into())?;
+        let output_shape = self.pool_spec.output_shape(shape)?;
+        let padding = self.pool_spec.computed_padding(input_shape.hw_dims());
+        let tiles = tile.tiles(output_shape.hw_dims());
+        let (ci, co) = (self.input_channels(), self.output_channels());
+        let kernel = self.kernel_as_group_o_ihw()?;
+        let kernel = kernel.to_array_view::<f32>()?.into_shape((co, ci, 3, 3))?;
+        let kernel = model.add_const(
+            format!("{name}.winograd_kernel"),
+            tile.transform_kernel(kernel).into_arc_tensor(),
+        )?;
+        let input = WinogradInputTransform {
+            tile,
+            input_shape,
+            pad_before: [padding[0].pad_before, padding[1].pad_before],
+            tiles,
+        };
+        let wire = model.wire_node(format!("{name}.winograd_input"), input, &[wire])?;
+        let wire = model.wire_node(
+            format!("{name}.winograd_matmul"),
+            EinSum::new("emk,aekn->aemn".parse()?, f32::datum_type()),
+            &[kernel, wire[0]],
+        )?;
+        let bias =
+            self.bias.as_ref().map(|b| b.broadcast_to_shape(&[co])).transpose()?.map(Arc::new);
+        let output = WinogradOutputTransform { tile, output_shape, tiles, bias };
+        model.wire_node(name, output, &wire)
+    }
+
     #[allow(clippy::type_complexity)]
     fn compute_geo(
         &self,
@@ -466,7 +534,19 @@ impl ConvUnary {
         c_n_axis: usize,
         b_storage: InputStoreSpec,
     ) -> TractResult<TVec<OutletId>> {
-        let kernels = self.kernel_as_packed_as(&mmm.a_pack(), k, m)?;
+        let packer = mmm.a_pack();
+        let kernels = if let Some(packed) = prepacked(
+            &model.properties,
+            &format!("{name}.kernels"),
+            &*mmm,
+            &packer,
+            self.kernel.datum_type(),
+            &self.packed_kernel_shape(&packer, k, m),
+        )? {
+            packed
+        } else {
+            self.kernel_as_packed_as(&packer, k, m)?
+        };
         let a_storage = unsafe { mmm.a_packed(self.kernel.datum_type().size_of(), k) };
         let (mut c_to_a_axis_mapping, mut c_to_b_axis_mapping) = (tvec!(), tvec!());
 
@@ -519,10 +599,105 @@ impl ConvUnary {
             output_shape,
             self.kernel_as_group_o_ihw().context("in kernel_as_group_o_ihw")?,
             bias,
+            None,
         );
         Ok(Box::new(op))
     }
 
+    fn is_depth_wise(&self, input: &TypedFact) -> bool {
+        self.group != 1
+            && self.group == self.output_channels()
+            && self.group == self.input_channels()
+            && input.shape.as_concrete().is_some()
+    }
+
+    /// Wires a quantized depthwise convolution as a single DepthWise op.
+    ///
+    /// Requires all quantization parameters to be constants, per-tensor for
+    /// the input and the output, per-tensor or per-channel for the kernel.
+    /// Returns None otherwise.
+    pub fn wire_as_quant_depth_wise(
+        &self,
+        model: &mut TypedModel,
+        name: &str,
+        wires: &[OutletId],
+    ) -> TractResult<Option<TVec<OutletId>>> {
+        let c = self.input_channels();
+        let mut q = tvec!();
+        for wire in &wires[1..] {
+            let Some(konst) = model.outlet_fact(*wire)?.konst.clone() else { return Ok(None) };
+            if konst.len() != 1 && konst.len() != c {
+                return Ok(None);
+            }
+            q.push(konst);
+        }
+        let [a0, a_scale, b0, b_scale, c0, c_scale] = &*q else { bail!("Wrong number of inputs") };
+        if [b0, b_scale, c0, c_scale].iter().any(|t| t.len() != 1) {
+            return Ok(None);
+        }
+        let per_channel = |t: &Tensor, dt: DatumType| -> TractResult<Tensor> {
+            let t = t.cast_to_dt(dt)?.into_owned();
+            if t.len() == 1 {
+                t.into_shape(&[])?.broadcast_scalar_to_shape(&[c])
+            } else {
+                t.into_shape(&[c])
+            }
+        };
+        let a0 = per_channel(a0, i32::datum_type())?;
+        let a_scale = per_channel(a_scale, f32::datum_type())?;
+        let mut input_zero_point = b0.cast_to_scalar::<i32>()?;
+        let b_scale = b_scale.cast_to_scalar::<f32>()?;
+        let c_scale = c_scale.cast_to_scalar::<f32>()?;
+
+        let mut input = wires[0];
+        let input_fact = model.outlet_fact(input)?.clone();
+        if input_fact.datum_type.unquantized() == u8::datum_type() {
+            input = model.wire_node(
+                format!("{name}.offset_b_as_i8"),
+                ops::quant::offset_u8_as_i8(),
+                &[input],
+            )?[0];
+            input_zero_point -= 128;
+        }
+        let ConcretePoolGeometry { input_shape, patch, output_shape } = self
+            .pool_spec
+            .compute_geo(&input_fact.shape)?
+            .to_concrete(input_fact.shape.as_concrete().unwrap())?
+            .into_owned();
+
+        let mut kernel = self.kernel_as_group_o_ihw()?.cast_to::<i32>()?.into_owned();
+        let taps = kernel.len() / c;
+        for (ix, k) in kernel.as_slice_mut::<i32>()?.iter_mut().enumerate() {
+            *k -= a0.as_slice::<i32>()?[ix / taps];
+        }
+        let bias = if let Some(b) = &self.bias {
+            per_channel(b, i32::datum_type())?
+        } else {
+            Tensor::zero::<i32>(&[c])?
+        };
+        let scalers = a_scale
+            .as_slice::<f32>()?
+            .iter()
+            .map(|a| Scaler::new(a * b_scale / c_scale, RoundingPolicy::Even))
+            .collect();
+        let c0 = c0.cast_to_scalar::<i32>()?;
+        let q_params = DepthWiseQParams {
+            input_zero_point,
+            output_zero_point: c0,
+            scalers,
+            output_dt: self.q_params.unwrap(),
+        };
+        let op = DepthWise::new(
+            patch,
+            input_shape,
+            output_shape,
+            kernel.into_arc_tensor(),
+            bias.into_arc_tensor(),
+            Some(q_params),
+        );
+        Ok(Some(model.wire_node(name, op, &[input])?))
+    }
+
     fn declutter_stride_slice_to_downsample(
         &self,
         model: &TypedModel,
@@ -1056,13 +1231,30 @@ impl TypedOp for ConvUnary {
             let dt = input_fact.datum_type;
             if self.q_params.is_some() {
                 let mut patch = TypedModelPatch::default();
+                patch.properties.clone_from(&model.properties);
                 let inputs = patch.taps(model, &node.inputs)?;
-                let wire = self
-                    .wire_as_quant_im2col(&mut patch, &node.name, &inputs)
-                    .context("in wire_as_quant_im2col")?;
+                let depth_wise = if self.is_depth_wise(input_fact) {
+                    self.wire_as_quant_depth_wise(&mut patch, &node.name, &inputs)
+                        .context("in wire_as_quant_depth_wise")?
+                } else {
+                    None
+                };
+                let wire = if let Some(wire) = depth_wise {
+                    wire
+                } else {
+                    self.wire_as_quant_im2col(&mut patch, &node.name, &inputs)
+                        .context("in wire_as_quant_im2col")?
+                };
                 patch.shunt_outside(model, node.id.into(), wire[0])?;
                 patch.obliterate(node.id)?;
                 Ok(Some(patch.with_context("quantized-codegen")))
+            } else if let Some(tile) = self.winograd_tile(input_fact)? {
+                let mut patch = TypedModelPatch::new("wire_as_winograd");
+                let wire = patch.tap_model(model, node.inputs[0])?;
+                let wire = self.wire_as_winograd(&mut patch, &node.name, wire, tile)?[0];
+                patch.shunt_outside(model, OutletId::new(node.id, 0), wire)?;
+                patch.obliterate(node.id)?;
+                Ok(Some(patch))
             } else if input_fact
                 .shape
                 .as_concrete()
@@ -1076,21 +1268,19 @@ impl TypedOp for ConvUnary {
                 .unwrap_or(false)
             {
                 let mut patch = TypedModelPatch::new("wire_as_lazy_im2col");
+                patch.properties.clone_from(&model.properties);
                 let mut wire = patch.tap_model(model, node.inputs[0])?;
                 wire = self.wire_as_lazy_im2col(&mut patch, &node.name, wire)?[0];
                 patch.shunt_outside(model, OutletId::new(node.id, 0), wire)?;
                 patch.obliterate(node.id)?;
                 Ok(Some(patch))
-            } else if self.group != 1
-                && self.group == self.output_channels()
-                && self.group == self.input_channels()
-                && input_fact.shape.as_concrete().is_some()
-            {
+            } else if self.is_depth_wise(input_fact) {
                 let op = dispatch_floatlike!(Self::to_depth_wise(dt)(self, input_fact))
                     .context("in to_depth_wise")?;
                 Ok(Some(TypedModelPatch::single_unary_op(model, node, op)?))
             } else {
                 let mut patch = TypedModelPatch::default();
+                patch.properties.clone_from(&model.properties);
                 let wire = patch.tap_model(model, node.inputs[0])?;
                 let wire = self
                     .wire_as_im2col_pair(&mut patch, &node.name, &[wire])
@@ -1185,4 +1375,77 @@ mod test {
         assert_eq!(cv.pool_spec.padding, Explicit(tvec![1], tvec![0])); // source + conv
         Ok(())
     }
+
+    #[test]
+    fn winograd_codegen() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let wire = model.add_source("source", f32::fact([1, 64, 32, 32]))?;
+        let kernel = (0..64 * 64 * 9).map(|i| (i % 7) as f32 - 3.0).collect::<Vec<_>>();
+        let conv = ConvUnary {
+            pool_spec: PoolSpec {
+                data_format: NCHW,
+                kernel_shape: tvec!(3, 3),
+                padding: SameUpper,
+                dilations: None,
+                strides: None,
+                output_channel_override: Some(64),
+            },
+            kernel_fmt: KernelFormat::OIHW,
+            kernel: Tensor::from_shape(&[64, 64, 3, 3], &kernel)?.into_arc_tensor(),
+            group: 1,
+            bias: Some(rctensor0(1f32)),
+            q_params: None,
+        };
+        let wire = model.wire_node("conv", conv, &[wire])?;
+        model.set_output_outlets(&wire)?;
+        let optimized = model.clone().into_optimized()?;
+        // whether Winograd wins depends on the host kernels geometry
+        let winograd = WinogradTile::pick(1, 64, 64, &[32, 32]).is_some();
+        assert_eq!(optimized.nodes().iter().any(|n| n.op_is::<WinogradInputTransform>()), winograd);
+
+        let input = (0..64 * 32 * 32).map(|i| (i % 5) as f32 - 2.0).collect::<Vec<_>>();
+        let input = Tensor::from_shape(&[1, 64, 32, 32], &input)?;
+        let expected = model.into_runnable()?.run(tvec!(input.clone().into()))?;
+        let found = optimized.into_runnable()?.run(tvec!(input.into()))?;
+        found[0].close_enough(&expected[0], Approximation::Approximate)
+    }
+
+    #[test]
+    fn quant_depth_wise_codegen() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let idt = u8::datum_type().with_zp_scale(130, 0.5);
+        let wire = model.add_source("source", idt.fact([5, 6, 4]))?;
+        let kernel = (0..4 * 9).map(|i| (i * 37 % 255) as i8).collect::<Vec<_>>();
+        let conv = ConvUnary {
+            pool_spec: PoolSpec {
+                data_format: HWC,
+                kernel_shape: tvec!(3, 3),
+                padding: SameUpper,
+                dilations: None,
+                strides: None,
+                output_channel_override: Some(4),
+            },
+            kernel_fmt: KernelFormat::OIHW,
+            kernel: Tensor::from_shape(&[4, 1, 3, 3], &kernel)?.into_arc_tensor(),
+            group: 4,
+            bias: Some(rctensor1(&[100i32, -200, 0, 3000])),
+            q_params: Some(i8::datum_type().with_zp_scale(-3, 16.)),
+        };
+        let mut inputs = tvec!(wire);
+        let qp = [tensor0(1i8), tensor1(&[0.5f32, 1., 2., 0.25]), tensor0(130u8), tensor0(0.5f32)];
+        for (ix, t) in qp.into_iter().chain([tensor0(-3i8), tensor0(16f32)]).enumerate() {
+            inputs.push(model.add_const(format!("qp.{ix}"), t)?);
+        }
+        let wire = model.wire_node("conv", conv, &inputs)?;
+        model.set_output_outlets(&wire)?;
+        let optimized = model.clone().into_optimized()?;
+        assert!(optimized.nodes().iter().any(|n| n.op_is::<DepthWise>()));
+        assert!(!optimized.nodes().iter().any(|n| n.op_is::<Im2Col>()));
+
+        let input = (0..5 * 6 * 4).map(|i| (i * 53 % 256) as u8).collect::<Vec<_>>();
+        let input = Tensor::from_shape(&[5, 6, 4], &input)?.cast_to_dt(idt)?.into_owned();
+        let expected = model.into_runnable()?.run(tvec!(input.clone().into()))?;
+        let found = optimized.into_runnable()?.run(tvec!(input.into()))?;
+        found[0].close_enough(&expected[0], Approximation::Exact)
+    }
 }
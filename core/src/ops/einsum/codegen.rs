@@ -7,7 +7,7 @@ use crate::ops::matmul::lir_unary::{
 use crate::ops::matmul::mir_quant::{
     combine_scales, compensate_zero_points, requant, wire_offset_u8_as_i8,
 };
-use crate::ops::matmul::pack::MatMatMulPack;
+use crate::ops::matmul::pack::{prepacked, MatMatMulPack};
 use crate::ops::nn::{Reduce, Reducer};
 
 pub enum AxesOrPatch<'a> {
@@ -39,7 +39,7 @@ pub(crate) fn codegen(
     }
 }
 
-pub(super) fn ensure_mkn_axes<'a>(
+pub(crate) fn ensure_mkn_axes<'a>(
     op: &'a EinSum,
     model: &TypedModel,
     node: &TypedNode,
@@ -332,7 +332,23 @@ fn lir_mat_mul_unary(
     let b = patch.tap_model(model, node.inputs[1])?;
     let pack_a = MatMatMulPack { packer: mmm.a_pack(), k_axis: a_k, mn_axis: a_m };
     let pack_b = MatMatMulPack { packer: mmm.b_pack(), k_axis: b_k, mn_axis: b_n };
-    let pa = patch.wire_node(format!("{name}.pack_a"), pack_a, &[a])?[0];
+    let a_packed = if let Some(shape) = input_facts[0].shape.as_concrete() {
+        prepacked(
+            &model.properties,
+            &format!("{name}.pack_a"),
+            &*mmm,
+            &pack_a.packer,
+            a_dt,
+            &pack_a.output_shape(shape),
+        )?
+    } else {
+        None
+    };
+    let pa = if let Some(packed) = a_packed {
+        patch.add_const(format!("{name}.pack_a"), packed)?
+    } else {
+        patch.wire_node(format!("{name}.pack_a"), pack_a, &[a])?[0]
+    };
     let pb = patch.wire_node(format!("{name}.pack_b"), pack_b, &[b])?[0];
 
     let mut c_to_a_axis_mapping = tvec!();
@@ -9,7 +9,7 @@ mod eval;
 use super::array::TypedConcat;
 use super::math::add;
 mod as_matmul;
-mod codegen;
+pub(crate) mod codegen;
 
 #[cfg(test)]
 mod proptest;
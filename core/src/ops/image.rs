@@ -0,0 +1,451 @@
+//! Image preprocessing: YUV to RGB conversion, centered crop or pad, and
+//! fused resize and normalization of u8 images into f32 NCHW tensors.
+use crate::internal::*;
+use crate::ops::array::{Pad, PadMode, Slice};
+use crate::ops::resize::{resize_tensor_axis, Interpolator, Resize};
+use std::str::FromStr;
+use tract_ndarray::Axis;
+
+/// Layout of YUV 4:2:0 images, stored as a [height * 3 / 2, width] u8 plane.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum YuvFormat {
+    /// Y plane, then interleaved U and V.
+    Nv12,
+    /// Y plane, then interleaved V and U.
+    Nv21,
+    /// Y plane, then U plane, then V plane.
+    I420,
+}
+
+impl YuvFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            YuvFormat::Nv12 => "nv12",
+            YuvFormat::Nv21 => "nv21",
+            YuvFormat::I420 => "i420",
+        }
+    }
+}
+
+impl FromStr for YuvFormat {
+    type Err = TractError;
+    fn from_str(s: &str) -> TractResult<Self> {
+        Ok(match s {
+            "nv12" => YuvFormat::Nv12,
+            "nv21" => YuvFormat::Nv21,
+            "i420" => YuvFormat::I420,
+            _ => bail!("Unsupported YUV format: {}", s),
+        })
+    }
+}
+
+/// BT.601 conversion of [.., height * 3 / 2, width] YUV 4:2:0 images to
+/// [.., height, width, 3] RGB, from video (16-235) or full (0-255) range.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct YuvToRgb {
+    pub format: YuvFormat,
+    pub full_range: bool,
+}
+
+impl YuvToRgb {
+    fn rgb(&self, y: u8, u: u8, v: u8) -> [u8; 3] {
+        let (u, v) = (u as f32 - 128., v as f32 - 128.);
+        let (y, coefs) = if self.full_range {
+            (y as f32, [1.402, 0.344136, 0.714136, 1.772])
+        } else {
+            (1.164 * (y as f32 - 16.), [1.596, 0.392, 0.813, 2.017])
+        };
+        let clamp = |x: f32| x.round().clamp(0., 255.) as u8;
+        [clamp(y + coefs[0] * v), clamp(y - coefs[1] * u - coefs[2] * v), clamp(y + coefs[3] * u)]
+    }
+}
+
+impl Op for YuvToRgb {
+    fn name(&self) -> Cow<str> {
+        "YuvToRgb".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "{} {} range",
+            self.format.as_str(),
+            if self.full_range { "full" } else { "video" }
+        )])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for YuvToRgb {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let rank = input.rank();
+        let (rows, w) = (input.shape()[rank - 2], input.shape()[rank - 1]);
+        let h = rows * 2 / 3;
+        ensure!(rows % 3 == 0 && h % 2 == 0 && w % 2 == 0, "Invalid YUV 4:2:0 plane shape");
+        let mut shape: TVec<usize> = input.shape()[..rank - 2].into();
+        shape.extend([h, w, 3]);
+        let mut output = Tensor::zero::<u8>(&shape)?;
+        let rgb = output.as_slice_mut::<u8>()?;
+        let data = input.as_slice::<u8>()?;
+        if rows * w > 0 {
+            for (image, rgb) in data.chunks(rows * w).zip(rgb.chunks_mut(h * w * 3)) {
+                let (luma, chroma) = image.split_at(h * w);
+                for r in 0..h {
+                    for c in 0..w {
+                        let (u, v) = match self.format {
+                            YuvFormat::Nv12 | YuvFormat::Nv21 => {
+                                let ix = r / 2 * w + c / 2 * 2;
+                                if self.format == YuvFormat::Nv12 {
+                                    (chroma[ix], chroma[ix + 1])
+                                } else {
+                                    (chroma[ix + 1], chroma[ix])
+                                }
+                            }
+                            YuvFormat::I420 => {
+                                let ix = r / 2 * (w / 2) + c / 2;
+                                (chroma[ix], chroma[h * w / 4 + ix])
+                            }
+                        };
+                        let pixel = (r * w + c) * 3;
+                        rgb[pixel..pixel + 3].copy_from_slice(&self.rgb(luma[r * w + c], u, v));
+                    }
+                }
+            }
+        }
+        Ok(tvec!(output.into_tvalue()))
+    }
+}
+
+impl TypedOp for YuvToRgb {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs[0].datum_type == u8::datum_type(), "YuvToRgb expects u8 input");
+        ensure!(inputs[0].rank() >= 2, "YuvToRgb expects a [.., height * 3 / 2, width] input");
+        let rank = inputs[0].rank();
+        let mut shape: TVec<TDim> = inputs[0].shape[..rank - 2].into();
+        shape.push(inputs[0].shape[rank - 2].clone() * 2 / 3);
+        shape.push(inputs[0].shape[rank - 1].clone());
+        shape.push(3.into());
+        Ok(tvec!(u8::fact(shape)))
+    }
+
+    as_op!();
+}
+
+/// Crop or pad `axes` to `sizes`, keeping the input centered. Padding is
+/// zero.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CenterCropPad {
+    pub axes: TVec<usize>,
+    pub sizes: TVec<usize>,
+}
+
+impl CenterCropPad {
+    /// Crop (start, end) and pads (before, after) of an axis.
+    fn crop_and_pad(len: usize, size: usize) -> ((usize, usize), (usize, usize)) {
+        if len >= size {
+            let start = (len - size) / 2;
+            ((start, start + size), (0, 0))
+        } else {
+            let before = (size - len) / 2;
+            ((0, len), (before, size - len - before))
+        }
+    }
+}
+
+impl Op for CenterCropPad {
+    fn name(&self) -> Cow<str> {
+        "CenterCropPad".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axes: {:?} sizes: {:?}", self.axes, self.sizes)])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for CenterCropPad {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let mut tensor = args_1!(inputs).into_tensor();
+        let mut pads = vec![(0, 0); tensor.rank()];
+        for (&axis, &size) in self.axes.iter().zip(&self.sizes) {
+            let ((start, end), pad) = Self::crop_and_pad(tensor.shape()[axis], size);
+            if end - start < tensor.shape()[axis] {
+                tensor = tensor.slice(axis, start, end)?;
+            }
+            pads[axis] = pad;
+        }
+        if pads.iter().any(|p| *p != (0, 0)) {
+            let zero = Tensor::zero_scalar_dt(tensor.datum_type())?.into_arc_tensor();
+            let pad = Pad { pads, mode: PadMode::Constant(zero) };
+            return pad.eval(tvec!(tensor.into_tvalue()));
+        }
+        Ok(tvec!(tensor.into_tvalue()))
+    }
+}
+
+impl TypedOp for CenterCropPad {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(self.axes.len() == self.sizes.len());
+        ensure!(self.axes.iter().all(|&axis| axis < inputs[0].rank()));
+        let mut fact = inputs[0].without_value();
+        for (&axis, &size) in self.axes.iter().zip(&self.sizes) {
+            fact.shape.set(axis, size.into());
+        }
+        Ok(tvec!(fact))
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let fact = model.outlet_fact(node.inputs[0])?;
+        let Ok(lens) = self
+            .axes
+            .iter()
+            .map(|&axis| fact.shape[axis].to_usize())
+            .collect::<TractResult<TVec<usize>>>()
+        else {
+            return Ok(None);
+        };
+        let mut patch = TypedModelPatch::default();
+        let mut wire = patch.tap_model(model, node.inputs[0])?;
+        let mut pads = vec![(0, 0); fact.rank()];
+        for ((&axis, &size), len) in self.axes.iter().zip(&self.sizes).zip(lens) {
+            let ((start, end), pad) = Self::crop_and_pad(len, size);
+            if end - start < len {
+                wire = patch.wire_node(
+                    format!("{}.crop-{}", node.name, axis),
+                    Slice::new(axis, start, end),
+                    &[wire],
+                )?[0];
+            }
+            pads[axis] = pad;
+        }
+        if pads.iter().any(|p| *p != (0, 0)) {
+            let zero = Tensor::zero_scalar_dt(fact.datum_type)?.into_arc_tensor();
+            wire = patch.wire_node(
+                format!("{}.pad", node.name),
+                Pad { pads, mode: PadMode::Constant(zero) },
+                &[wire],
+            )?[0];
+        }
+        patch.shunt_outside(model, node.id.into(), wire)?;
+        Ok(Some(patch))
+    }
+
+    as_op!();
+}
+
+/// Turns u8 [height, width, channels] images (or batches of them) into
+/// normalized f32 [batch, channels, height, width] tensors, optionally
+/// resized with half-pixel coordinates: `(x * scale - mean[c]) / std[c]`.
+///
+/// `mean` and `std` have one value per channel, or a single value for all.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NormalizeImage {
+    /// Output (height, width).
+    pub size: Option<(usize, usize)>,
+    pub interpolator: Interpolator,
+    pub antialias: bool,
+    pub scale: f32,
+    pub mean: TVec<f32>,
+    pub std: TVec<f32>,
+}
+
+impl NormalizeImage {
+    fn resize(&self) -> Option<Resize> {
+        self.size.map(|(h, w)| Resize {
+            axes: tvec!(1, 2),
+            sizes: Some(tvec!(h.to_dim(), w.to_dim())),
+            interpolator: self.interpolator,
+            antialias: self.antialias,
+            ..Resize::default()
+        })
+    }
+}
+
+impl Op for NormalizeImage {
+    fn name(&self) -> Cow<str> {
+        "NormalizeImage".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        let mut info =
+            vec![format!("scale: {} mean: {:?} std: {:?}", self.scale, self.mean, self.std)];
+        if let Some((h, w)) = self.size {
+            info.push(format!("resize to {}x{} ({})", h, w, self.interpolator.as_str()));
+        }
+        Ok(info)
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for NormalizeImage {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let mut data = input.cast_to::<f32>()?.into_owned();
+        if data.rank() == 3 {
+            data.insert_axis(0)?;
+        }
+        if let Some(resize) = self.resize() {
+            let lengths = resize.scales_and_lengths(data.shape())?;
+            for (ix, (scale, len)) in lengths.into_iter().enumerate() {
+                let taps = resize.axis_taps(ix, data.shape()[ix + 1], len, scale);
+                data = resize_tensor_axis(&data, ix + 1, &taps, 0.0)?;
+            }
+        }
+        let mut data = data.into_array::<f32>()?;
+        for (c, mut channel) in data.axis_iter_mut(Axis(3)).enumerate() {
+            let mean = self.mean[if self.mean.len() == 1 { 0 } else { c }];
+            let std = self.std[if self.std.len() == 1 { 0 } else { c }];
+            channel.mapv_inplace(|x| (x * self.scale - mean) / std);
+        }
+        let data = data.permuted_axes(vec![0, 3, 1, 2]).as_standard_layout().into_owned();
+        Ok(tvec!(data.into_tensor().into_tvalue()))
+    }
+}
+
+impl TypedOp for NormalizeImage {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let input = inputs[0];
+        ensure!(input.datum_type == u8::datum_type(), "NormalizeImage expects u8 images");
+        ensure!(
+            input.rank() == 3 || input.rank() == 4,
+            "NormalizeImage expects [height, width, channels] images, optionally batched"
+        );
+        let shape: TVec<TDim> =
+            if input.rank() == 3 { tvec!(1.into()) } else { tvec!(input.shape[0].clone()) }
+                .into_iter()
+                .chain(input.shape.iter().skip(input.rank() - 3))
+                .collect();
+        let channels = shape[3].to_usize()?;
+        for values in [&self.mean, &self.std] {
+            ensure!(
+                values.len() == 1 || values.len() == channels,
+                "Expected 1 or {} normalization values, got {:?}",
+                channels,
+                values
+            );
+        }
+        let (h, w) = match self.size {
+            Some((h, w)) => (h.to_dim(), w.to_dim()),
+            None => (shape[1].clone(), shape[2].clone()),
+        };
+        Ok(tvec!(f32::fact([shape[0].clone(), shape[3].clone(), h, w])))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(op: impl EvalOp, input: Tensor) -> TractResult<Tensor> {
+        Ok(op.eval(tvec!(input.into_tvalue()))?.remove(0).into_tensor())
+    }
+
+    #[test]
+    fn yuv_layouts() -> TractResult<()> {
+        // 2x2 image: one chroma sample for the four pixels
+        let luma = [81u8, 81, 145, 145];
+        let nv12 = tensor1(&[&luma[..], &[90, 240]].concat()).into_shape(&[3, 2])?;
+        let nv21 = tensor1(&[&luma[..], &[240, 90]].concat()).into_shape(&[3, 2])?;
+        let i420 = tensor1(&[&luma[..], &[90, 240]].concat()).into_shape(&[3, 2])?;
+        let mut outputs = vec![];
+        for (format, input) in
+            [(YuvFormat::Nv12, nv12), (YuvFormat::Nv21, nv21), (YuvFormat::I420, i420)]
+        {
+            outputs.push(run(YuvToRgb { format, full_range: false }, input)?);
+        }
+        assert_eq!(outputs[0].shape(), &[2, 2, 3]);
+        // (81, 90, 240) is video range red
+        assert_eq!(outputs[0].as_slice::<u8>()?[..3], [254, 0, 0]);
+        assert_eq!(outputs[0], outputs[1]);
+        assert_eq!(outputs[0], outputs[2]);
+        Ok(())
+    }
+
+    #[test]
+    fn yuv_full_range_gray() -> TractResult<()> {
+        let input = tensor1(&[100u8, 100, 100, 100, 128, 128]).into_shape(&[1, 3, 2])?;
+        let output = run(YuvToRgb { format: YuvFormat::Nv12, full_range: true }, input)?;
+        assert_eq!(output, tensor1(&[100u8; 12]).into_shape(&[1, 2, 2, 3])?);
+        Ok(())
+    }
+
+    #[test]
+    fn center_crop_and_pad() -> TractResult<()> {
+        let input = tensor2(&[[1i32, 2, 3, 4], [5, 6, 7, 8]]);
+        let op = CenterCropPad { axes: tvec!(0, 1), sizes: tvec!(4, 2) };
+        let output = run(op, input)?;
+        assert_eq!(output, tensor2(&[[0, 0], [2, 3], [6, 7], [0, 0]]));
+        Ok(())
+    }
+
+    #[test]
+    fn center_crop_pad_declutters_to_slice_and_pad() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", i32::fact([2, 5]))?;
+        let op = CenterCropPad { axes: tvec!(0, 1), sizes: tvec!(3, 2) };
+        let output = model.wire_node("crop_pad", op, &[source])?;
+        model.set_output_outlets(&output)?;
+        let input = tensor2(&[[1i32, 2, 3, 4, 5], [6, 7, 8, 9, 10]]);
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone().into_tvalue()))?;
+        let model = model.into_decluttered()?;
+        assert!(model.nodes().iter().all(|n| !n.op_is::<CenterCropPad>()));
+        let got = model.into_runnable()?.run(tvec!(input.into_tvalue()))?;
+        assert_eq!(got[0], expected[0]);
+        assert_eq!(*got[0], tensor2(&[[2, 3], [7, 8], [0, 0]]));
+        Ok(())
+    }
+
+    #[test]
+    fn normalize_hwc_to_nchw() -> TractResult<()> {
+        let input = tensor3(&[[[0u8, 255], [51, 102]]]);
+        let op = NormalizeImage {
+            size: None,
+            interpolator: Interpolator::Linear,
+            antialias: false,
+            scale: 1. / 255.,
+            mean: tvec!(0.5, 0.0),
+            std: tvec!(0.5, 0.2),
+        };
+        let output = run(op, input)?;
+        assert_eq!(output.shape(), &[1, 2, 1, 2]);
+        output.close_enough(&tensor4(&[[[[-1f32, -0.6]], [[5.0, 2.0]]]]), true)?;
+        Ok(())
+    }
+
+    #[test]
+    fn normalize_with_resize() -> TractResult<()> {
+        let input = tensor3(&[[[10u8], [30]], [[50], [70]]]);
+        let op = NormalizeImage {
+            size: Some((1, 1)),
+            interpolator: Interpolator::Linear,
+            antialias: false,
+            scale: 1.,
+            mean: tvec!(0.),
+            std: tvec!(1.),
+        };
+        let output = run(op, input)?;
+        assert_eq!(output, tensor4(&[[[[40f32]]]]));
+        Ok(())
+    }
+}
@@ -244,6 +244,9 @@ fn declutter_mul(
     if let Some(p) = declutter_neutral(model, node, 1, true).context("decluttering neutral")? {
         return Ok(Some(p));
     }
+    if let Some(p) = declutter_silu(model, node).context("decluttering silu")? {
+        return Ok(Some(p));
+    }
     if let Some(uniform) = crate::ops::binary::one_input_is_uniform(model, node)? {
         let var_fact = model.outlet_fact(uniform.var)?;
         if uniform.uni.cast_to_scalar::<f64>()? == 0.0 {
@@ -293,6 +296,32 @@ fn declutter_mul(
     Ok(None)
 }
 
+/// x·sigmoid(x) becomes Silu, which matrix multiplication kernels can fuse.
+fn declutter_silu(model: &TypedModel, node: &TypedNode) -> TractResult<Option<TypedModelPatch>> {
+    if model.outlet_fact(node.inputs[0])?.datum_type != f32::datum_type() {
+        return Ok(None);
+    }
+    for (x, sigmoid) in [(0, 1), (1, 0)] {
+        let sigmoid = model.node(node.inputs[sigmoid].node);
+        if sigmoid.inputs.first() == Some(&node.inputs[x])
+            && sigmoid.outputs[0].successors.len() == 1
+            && sigmoid
+                .op_as::<crate::ops::element_wise::ElementWiseOp>()
+                .map(|op| op.0.is::<crate::ops::nn::Sigmoid>())
+                .unwrap_or(false)
+        {
+            let silu = crate::ops::nn::silu();
+            return Ok(Some(TypedModelPatch::replace_single_op(
+                model,
+                node,
+                &[node.inputs[x]],
+                silu,
+            )?));
+        }
+    }
+    Ok(None)
+}
+
 fn declutter_div(
     _op: &Div,
     model: &TypedModel,
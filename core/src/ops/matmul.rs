@@ -1,6 +1,7 @@
 pub mod lir_unary;
 pub mod mir_quant;
 pub mod pack;
+pub mod sparse;
 
 use crate::internal::*;
 
@@ -5,7 +5,8 @@ use ndarray::*;
 use tract_itertools::Itertools;
 
 use tract_linalg::mmm::{
-    BinOp, FusedSpec, InputStoreSpec, MatMatMul, OutputStoreSpec, ScratchSpace, VirtualInputSpec,
+    Activation, BinOp, FusedSpec, InputStoreSpec, MatMatMul, OutputStoreSpec, ScratchSpace,
+    VirtualInputSpec,
 };
 use tract_linalg::Scaler;
 use tract_smallvec::ToSmallVec;
@@ -25,6 +26,7 @@ pub enum ProtoFusedSpec {
     AddRowColProducts(usize, usize),
     AddUnicast(OutputStoreSpec, usize, MapOutputAxisToInput),
     Scaler(Scaler),
+    Activation(Activation),
     Store(OutputStoreSpec),
 }
 
@@ -39,6 +41,7 @@ impl ProtoFusedSpec {
             AddRowColProducts(_, _) => "add_row_col_product".to_string(),
             AddUnicast(_, _, _) => "add_to_matrix".to_string(),
             Scaler(s) => format!("scale({})", 1f32 * *s),
+            Activation(a) => format!("{a:?}"),
             Store(_oss) => "store".to_string(),
         }
     }
@@ -97,6 +100,7 @@ impl ProtoFusedSpec {
                 FusedSpec::AddUnicast(store.wrap(&view))
             },
             ProtoFusedSpec::Scaler(scaler) => scaler.as_fused_spec(),
+            ProtoFusedSpec::Activation(a) => FusedSpec::Activation(*a),
             ProtoFusedSpec::Store(oss) => unsafe {
                 let view = output.view_offsetting_unchecked(output_coords);
                 FusedSpec::Store(oss.wrap(&view))
@@ -155,6 +159,7 @@ impl ProtoFusedSpec {
                 FusedSpec::AddUnicast(store.wrap(&view))
             },
             ProtoFusedSpec::Scaler(scaler) => scaler.as_fused_spec(),
+            ProtoFusedSpec::Activation(a) => FusedSpec::Activation(*a),
             ProtoFusedSpec::Store(oss) => unsafe { FusedSpec::Store(oss.wrap(&output.view_mut())) },
         };
         fs
@@ -176,7 +181,7 @@ impl ProtoFusedSpec {
                 geo.c_to_a_axis_mapping.rm_c_axis(axis);
                 geo.c_to_b_axis_mapping.rm_c_axis(axis);
             }
-            BinScalar(..) | Scaler(..) | AddRowColProducts(_, _) => {}
+            BinScalar(..) | Scaler(..) | Activation(..) | AddRowColProducts(_, _) => {}
             BinPerRow(_, _, map) | BinPerCol(_, _, map) => map.rm_c_axis(axis),
             AddUnicast(_, _, map) => {
                 map.rm_c_axis(axis);
@@ -304,7 +309,7 @@ impl OpState for State {
             let scratch = session
                 .cached_mmm_scratch_space
                 .get_or_insert_with(|| op.mmm.allocate_scratch_space());
-            eval(op, &session.resolved_symbols, scratch.as_mut(), &inputs)
+            eval(op, &session.resolved_symbols, scratch.as_mut(), &inputs, &mut session.steps)
         }
     }
 }
@@ -324,7 +329,7 @@ impl EvalOp for LirMatMulUnary {
 
     fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
         let mut scratch = unsafe { self.mmm.allocate_scratch_space() };
-        eval(self, &Default::default(), scratch.as_mut(), &inputs)
+        eval(self, &Default::default(), scratch.as_mut(), &inputs, &mut None)
     }
 }
 
@@ -334,6 +339,7 @@ fn eval(
     symbols: &SymbolValues,
     scratch: &mut dyn ScratchSpace,
     inputs: &[TValue],
+    steps: &mut Option<Vec<EvalStep>>,
 ) -> TractResult<TVec<TValue>> {
     unsafe {
         if op.trivial_path {
@@ -342,7 +348,11 @@ fn eval(
             let mut c = Tensor::uninitialized_dt(op.c_fact.datum_type, c_shape)?;
             let uops: Vec<FusedSpec> =
                 op.micro_ops.iter().map(|o| o.resolve_trivial(inputs, &mut c)).collect();
-            op.mmm.run_with_scratch_space(geometry.m, geometry.n, scratch, &uops)?;
+            SessionState::step(
+                steps,
+                || format!("mmm {}x{}", geometry.m, geometry.n),
+                || op.mmm.run_with_scratch_space(geometry.m, geometry.n, scratch, &uops),
+            )?;
             Ok(tvec!(c.into_tvalue()))
         } else {
             let geometry = op.geometry.to_concrete(symbols)?;
@@ -361,7 +371,11 @@ fn eval(
                         &c,
                     );
                 }
-                op.mmm.run_with_scratch_space(geometry.m, geometry.n, scratch, &uops)?;
+                SessionState::step(
+                    steps,
+                    || format!("mmm {}x{} at {:?}", geometry.m, geometry.n, c_coords.slice()),
+                    || op.mmm.run_with_scratch_space(geometry.m, geometry.n, scratch, &uops),
+                )?;
             }
             Ok(tvec!(c.into_tvalue()))
         }
@@ -428,6 +442,19 @@ impl TypedOp for LirMatMulUnary {
                     &[],
                 );
             }
+            if let Some(activation) = Self::activation(op) {
+                if self.mmm.internal_type().is_float()
+                    && self.mmm.can_fuse(&FusedSpec::Activation(activation))
+                {
+                    return self.fuse_op(
+                        model,
+                        node,
+                        patch,
+                        vec![ProtoFusedSpec::Activation(activation)],
+                        &[],
+                    );
+                }
+            }
         }
         if let Some(cast_to) = succ.op_as::<ops::cast::Cast>().map(|cast| cast.to) {
             if (cast_to.unquantized() == i8::datum_type()
@@ -584,6 +611,23 @@ impl LirMatMulUnary {
             && self.micro_ops.iter().all(|o| !o.has_symbols())
     }
 
+    fn activation(op: &dyn ElementWiseMiniOp) -> Option<Activation> {
+        use crate::ops::{math, nn};
+        if op.is::<nn::Sigmoid>() {
+            Some(Activation::Sigmoid)
+        } else if op.is::<math::Tanh>() {
+            Some(Activation::Tanh)
+        } else if op.is::<nn::GeluApproximate>() {
+            Some(Activation::Gelu)
+        } else if op.is::<nn::Silu>() {
+            Some(Activation::Silu)
+        } else if op.is::<nn::HardSwish>() {
+            Some(Activation::HardSwish)
+        } else {
+            op.downcast_ref::<nn::LeakyRelu>().map(|op| Activation::LeakyRelu(op.alpha))
+        }
+    }
+
     fn fuse_op(
         &self,
         model: &TypedModel,
@@ -667,3 +711,70 @@ impl LirMatMulUnary {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::einsum::EinSum;
+    use crate::ops::binary::TypedBinOp;
+    use crate::ops::element_wise::ElementWiseOp;
+
+    fn check(activation: Box<dyn ElementWiseMiniOp>) -> TractResult<()> {
+        check_wire(|model, c| model.wire_node("y", ElementWiseOp(activation.clone()), &[c]))
+    }
+
+    fn check_wire(
+        activation: impl Fn(&mut TypedModel, OutletId) -> TractResult<TVec<OutletId>>,
+    ) -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact(&[32, 8]))?;
+        let w = (0..16 * 32).map(|i| ((i % 7) as f32 - 3.) / 8.).collect::<Vec<_>>();
+        let w = model.add_const("w", Tensor::from_shape(&[16, 32], &w)?)?;
+        let c =
+            model.wire_node("c", EinSum::new("mk,kn->mn".parse()?, f32::datum_type()), &[w, x])?;
+        let y = activation(&mut model, c[0])?;
+        model.set_output_outlets(&y)?;
+        let model = model.into_decluttered()?;
+        let x =
+            Tensor::from_shape(&[32, 8], &(0..256).map(|i| i as f32 / 64.).collect::<Vec<_>>())?;
+        let expected = model.clone().into_runnable()?.run(tvec!(x.clone().into_tvalue()))?;
+        let optimized = model.into_optimized()?;
+        let lir = optimized.nodes().iter().find_map(|n| n.op_as::<LirMatMulUnary>()).unwrap();
+        if lir.mmm.can_fuse(&FusedSpec::Activation(Activation::Sigmoid)) {
+            assert!(!optimized
+                .nodes()
+                .iter()
+                .any(|n| n.op_is::<ElementWiseOp>() || n.op_is::<TypedBinOp>()));
+        }
+        let found = optimized.into_runnable()?.run(tvec!(x.into_tvalue()))?;
+        found[0].close_enough(&expected[0], Approximation::Close)
+    }
+
+    #[test]
+    fn fuse_sigmoid() -> TractResult<()> {
+        check(Box::new(crate::ops::nn::Sigmoid {}))
+    }
+
+    #[test]
+    fn fuse_tanh() -> TractResult<()> {
+        check(Box::new(crate::ops::math::Tanh {}))
+    }
+
+    #[test]
+    fn fuse_gelu() -> TractResult<()> {
+        check(Box::new(crate::ops::nn::GeluApproximate {}))
+    }
+
+    #[test]
+    fn fuse_leaky_relu() -> TractResult<()> {
+        check(Box::new(crate::ops::nn::LeakyRelu { alpha: 0.1 }))
+    }
+
+    #[test]
+    fn fuse_sigmoid_mul_as_silu() -> TractResult<()> {
+        check_wire(|model, c| {
+            let sigmoid = model.wire_node("sigmoid", crate::ops::nn::sigmoid(), &[c])?;
+            model.wire_node("y", crate::ops::math::mul(), &[c, sigmoid[0]])
+        })
+    }
+}
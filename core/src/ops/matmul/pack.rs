@@ -2,7 +2,13 @@ use crate::axes::Axis;
 use crate::internal::*;
 use ndarray::*;
 
+use crate::ops::konst::Const;
+use crate::ops::matmul::lir_unary::{LirMatMulUnary, ProtoFusedSpec};
 use tract_linalg::frame::Packer;
+use tract_linalg::mmm::MatMatMul;
+
+/// Prefix of the model properties holding pre-packed constant weights.
+pub const PREPACKED_PROPERTY_PREFIX: &str = "tract_prepacked.";
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MatMatMulPack {
@@ -88,7 +94,7 @@ impl TypedOp for MatMatMulPack {
 }
 
 impl MatMatMulPack {
-    fn output_shape<D: DimLike>(&self, input: &[D]) -> TVec<D> {
+    pub(crate) fn output_shape<D: DimLike>(&self, input: &[D]) -> TVec<D> {
         let mut packed_shape: TVec<D> = input.into();
         packed_shape.remove(self.mn_axis.max(self.k_axis));
         packed_shape.remove(self.mn_axis.min(self.k_axis));
@@ -96,3 +102,120 @@ impl MatMatMulPack {
         packed_shape
     }
 }
+
+/// Identifies a packed layout: target architecture, kernel (which implies the
+/// CPU features it relies on) and packer geometry.
+pub fn packing_tag(mmm: &dyn MatMatMul, packer: &Packer) -> String {
+    format!("{} {} {:?}", std::env::consts::ARCH, mmm.kernel_name(), packer)
+}
+
+/// Record the packed constant weights of `optimized` in `model` properties.
+///
+/// `model` is expected to be the decluttered model `optimized` was built
+/// from. Once serialized and reloaded, its codegen will pick the packed
+/// weights instead of packing them again if the host selects the same kernel,
+/// and pack the portable weights otherwise.
+pub fn embed_prepacked_weights(model: &mut TypedModel, optimized: &TypedModel) -> TractResult<()> {
+    for node in optimized.nodes() {
+        let Some(op) = node.op_as::<LirMatMulUnary>() else { continue };
+        for spec in &op.micro_ops {
+            let ProtoFusedSpec::AddMatMul(_, a, b) = spec else { continue };
+            for (slot, packer) in [(*a, op.mmm.a_pack()), (*b, op.mmm.b_pack())] {
+                let input = optimized.node(node.inputs[slot].node);
+                if let Some(konst) = input.op_as::<Const>() {
+                    let key = format!("{PREPACKED_PROPERTY_PREFIX}{}", input.name);
+                    model
+                        .properties
+                        .insert(format!("{key}.tag"), rctensor0(packing_tag(&*op.mmm, &packer)));
+                    model.properties.insert(key, konst.0.clone());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Look for a pre-packed version of the constant `name` in the `properties`
+/// matching the `packer` of `mmm` and the expected type and shape.
+pub(crate) fn prepacked(
+    properties: &HashMap<String, Arc<Tensor>>,
+    name: &str,
+    mmm: &dyn MatMatMul,
+    packer: &Packer,
+    dt: DatumType,
+    shape: &[usize],
+) -> TractResult<Option<Arc<Tensor>>> {
+    let key = format!("{PREPACKED_PROPERTY_PREFIX}{name}");
+    let (Some(packed), Some(tag)) = (properties.get(&key), properties.get(&format!("{key}.tag")))
+    else {
+        return Ok(None);
+    };
+    if tag.to_scalar::<String>()? != &packing_tag(mmm, packer)
+        || packed.datum_type() != dt
+        || packed.shape() != shape
+    {
+        return Ok(None);
+    }
+    unsafe {
+        if packed.as_ptr_unchecked::<u8>() as usize % packer.alignment() == 0 {
+            return Ok(Some(packed.clone()));
+        }
+        let mut aligned = Tensor::uninitialized_aligned_dt(dt, shape, packer.alignment())?;
+        aligned.as_bytes_mut().copy_from_slice(packed.as_bytes());
+        Ok(Some(aligned.into_arc_tensor()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::einsum::EinSum;
+
+    fn model() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let a = Tensor::from_shape(&[16, 8], &(0..128).map(|x| x as f32).collect::<Vec<_>>())?;
+        let a = model.add_const("a", a)?;
+        let b = model.add_source("b", f32::fact([8, 4]))?;
+        let op = EinSum::new("mk,kn->mn".parse()?, f32::datum_type());
+        let c = model.wire_node("c", op, &[a, b])?;
+        model.set_output_outlets(&c)?;
+        model.into_decluttered()
+    }
+
+    fn packed_a(optimized: &TypedModel) -> Arc<Tensor> {
+        let node = optimized.node_by_name("c.pack_a").unwrap();
+        node.op_as::<Const>().unwrap().0.clone()
+    }
+
+    #[test]
+    fn reuse_prepacked_weights() -> TractResult<()> {
+        let mut model = model()?;
+        let optimized = model.clone().into_optimized()?;
+        embed_prepacked_weights(&mut model, &optimized)?;
+        let reference = &model.properties["tract_prepacked.c.pack_a"];
+        assert_eq!(packed_a(&optimized).as_ref(), reference.as_ref());
+
+        let reoptimized = model.clone().into_optimized()?;
+        assert!(Arc::ptr_eq(&packed_a(&reoptimized), reference));
+        let input = Tensor::from_shape(&[8, 4], &(0..32).map(|x| x as f32).collect::<Vec<_>>())?;
+        let expected = optimized.into_runnable()?.run(tvec!(input.clone().into()))?;
+        let found = reoptimized.into_runnable()?.run(tvec!(input.into()))?;
+        assert_eq!(expected, found);
+        Ok(())
+    }
+
+    #[test]
+    fn repack_on_tag_mismatch() -> TractResult<()> {
+        let mut model = model()?;
+        let optimized = model.clone().into_optimized()?;
+        embed_prepacked_weights(&mut model, &optimized)?;
+        model
+            .properties
+            .insert("tract_prepacked.c.pack_a.tag".to_string(), rctensor0("other".to_string()));
+        let reoptimized = model.clone().into_optimized()?;
+        let reference = &model.properties["tract_prepacked.c.pack_a"];
+        assert!(!Arc::ptr_eq(&packed_a(&reoptimized), reference));
+        assert_eq!(packed_a(&reoptimized).as_ref(), reference.as_ref());
+        Ok(())
+    }
+}
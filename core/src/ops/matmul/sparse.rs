@@ -0,0 +1,274 @@
+use crate::internal::*;
+use crate::ops::einsum::codegen::{ensure_mkn_axes, AxesOrPatch};
+use crate::ops::einsum::EinSum;
+use tract_linalg::sparse::BlockSparseMatrix;
+
+/// Default density below which [sparsify_weights] makes constant weights
+/// sparse.
+pub const SPARSE_DENSITY_THRESHOLD: f32 = 0.3;
+
+/// Product of constant block-sparse f32 weights by an input.
+///
+/// The input holds the k and n axes at `b_k` and `b_n`, the output the m and
+/// n axes at `c_m` and `c_n`. Other axes are batch axes, in the same order in
+/// input and output.
+#[derive(Debug, Clone, Hash)]
+pub struct SparseMatMul {
+    pub weights: Arc<BlockSparseMatrix>,
+    pub b_k: usize,
+    pub b_n: usize,
+    pub c_m: usize,
+    pub c_n: usize,
+}
+
+impl SparseMatMul {
+    /// Weights are re-blocked for the host kernel if needed.
+    pub fn new(
+        weights: &BlockSparseMatrix,
+        b_k: usize,
+        b_n: usize,
+        c_m: usize,
+        c_n: usize,
+    ) -> SparseMatMul {
+        let mr = (tract_linalg::ops().sparse_f32)().mr();
+        SparseMatMul { weights: Arc::new(weights.with_mr(mr)), b_k, b_n, c_m, c_n }
+    }
+
+    fn output_shape<D: DimLike>(&self, b: &[D]) -> TVec<D> {
+        let mut batch =
+            b.iter().enumerate().filter(|(ix, _)| *ix != self.b_k && *ix != self.b_n).map(|p| p.1);
+        (0..b.len())
+            .map(|ix| {
+                if ix == self.c_m {
+                    self.weights.m.into()
+                } else if ix == self.c_n {
+                    b[self.b_n].clone()
+                } else {
+                    batch.next().unwrap().clone()
+                }
+            })
+            .collect()
+    }
+}
+
+impl Op for SparseMatMul {
+    fn name(&self) -> Cow<str> {
+        "SparseMatMul".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        let w = &self.weights;
+        Ok(vec![format!(
+            "{}x{} weights, blocks of {} rows, density {:.3}",
+            w.m,
+            w.k,
+            w.mr,
+            w.density()
+        )])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for SparseMatMul {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let b = args_1!(inputs).into_tensor();
+        let rank = b.rank();
+        let (m, k) = (self.weights.m, self.weights.k);
+        let mut to_kn: Vec<usize> = (0..rank).filter(|&a| a != self.b_k && a != self.b_n).collect();
+        to_kn.extend([self.b_k, self.b_n]);
+        let b = b.permute_axes(&to_kn)?;
+        ensure!(b.shape()[rank - 2] == k);
+        let n = b.shape()[rank - 1];
+        let mut c_shape: TVec<usize> = b.shape()[..rank - 2].into();
+        c_shape.extend([m, n]);
+        let mut c = unsafe { Tensor::uninitialized::<f32>(&c_shape)? };
+        let kernel = (tract_linalg::ops().sparse_f32)();
+        let (b_len, c_len) = (k * n, m * n);
+        if b_len > 0 && c_len > 0 {
+            for (b, c) in
+                b.as_slice::<f32>()?.chunks(b_len).zip(c.as_slice_mut::<f32>()?.chunks_mut(c_len))
+            {
+                kernel.run(&self.weights, b, c, n);
+            }
+        }
+        let mut batch = 0..rank - 2;
+        let from_mn: Vec<usize> = (0..rank)
+            .map(|ix| {
+                if ix == self.c_m {
+                    rank - 2
+                } else if ix == self.c_n {
+                    rank - 1
+                } else {
+                    batch.next().unwrap()
+                }
+            })
+            .collect();
+        Ok(tvec!(c.permute_axes(&from_mn)?.into_tvalue()))
+    }
+}
+
+impl TypedOp for SparseMatMul {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs[0].datum_type == f32::datum_type());
+        ensure!(inputs[0].shape[self.b_k] == self.weights.k.to_dim());
+        Ok(tvec!(f32::fact(self.output_shape(&inputs[0].shape))))
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let columns = inputs[0].shape.volume() / self.weights.k;
+        Ok(tvec!(
+            (Cost::FMA(f32::datum_type()), columns * self.weights.values.len()),
+            (Cost::Params(f32::datum_type()), self.weights.values.len().to_dim())
+        ))
+    }
+
+    as_op!();
+}
+
+/// Try to turn an f32 EinSum by a constant matrix that is sparse enough into
+/// a SparseMatMul.
+fn wire_sparse_weights(
+    op: &EinSum,
+    model: &TypedModel,
+    node: &TypedNode,
+    (m_axis, k_axis, n_axis): (&Axis, &Axis, &Axis),
+    max_density: f32,
+) -> TractResult<Option<TypedModelPatch>> {
+    let input_facts = model.node_input_facts(node.id)?;
+    if op.q_params.is_some()
+        || node.inputs.len() != 2
+        || op.operating_dt != f32::datum_type()
+        || input_facts.iter().any(|f| f.datum_type != f32::datum_type())
+    {
+        return Ok(None);
+    }
+    // weights can be on either side: slot, their non-k axis and the input one
+    for (w_slot, w_axis, x_axis) in [(0, m_axis, n_axis), (1, n_axis, m_axis)] {
+        let x_slot = 1 - w_slot;
+        let Some(weights) = &input_facts[w_slot].konst else { continue };
+        if weights.rank() != 2 || weights.len() == 0 {
+            continue;
+        }
+        let Some(mut batch_axes) = op
+            .axes
+            .iter_all_axes()
+            .filter(|axis| ![m_axis, k_axis, n_axis].contains(axis))
+            .map(|axis| match (&*axis.inputs[w_slot], &*axis.inputs[x_slot], &*axis.outputs[0]) {
+                (&[], &[x], &[c]) => Some((x, c)),
+                _ => None,
+            })
+            .collect::<Option<TVec<(usize, usize)>>>()
+        else {
+            continue;
+        };
+        batch_axes.sort();
+        if batch_axes.windows(2).any(|w| w[0].1 > w[1].1) {
+            continue;
+        }
+        let (w_mn, w_k) = (w_axis.inputs[w_slot][0], k_axis.inputs[w_slot][0]);
+        let dense = weights.clone().into_tensor().move_axis(w_mn, 0)?;
+        let dense = dense.as_slice::<f32>()?;
+        let non_zero = dense.iter().filter(|w| **w != 0.0).count();
+        if non_zero as f32 > max_density * dense.len() as f32 {
+            continue;
+        }
+        let (m, k) = (weights.shape()[w_mn], weights.shape()[w_k]);
+        let mr = (tract_linalg::ops().sparse_f32)().mr();
+        let sparse = BlockSparseMatrix::from_dense(dense, m, k, mr);
+        if sparse.density() > max_density {
+            continue;
+        }
+        let op = SparseMatMul::new(
+            &sparse,
+            k_axis.inputs[x_slot][0],
+            x_axis.inputs[x_slot][0],
+            w_axis.outputs[0][0],
+            x_axis.outputs[0][0],
+        );
+        return TypedModelPatch::replace_single_op(model, node, &[node.inputs[x_slot]], op)
+            .map(Some);
+    }
+    Ok(None)
+}
+
+/// Replace products by constant weights storing less than `max_density` of
+/// their values by SparseMatMul in a decluttered model.
+///
+/// This is opt-in: codegen keeps dense products, as the sparse kernels only
+/// pay off on weights sparse enough for the target. Optimized and serialized
+/// models then keep the sparse form of the weights.
+pub fn sparsify_weights(model: &mut TypedModel, max_density: f32) -> TractResult<()> {
+    for id in model.eval_order()? {
+        let node = model.node(id);
+        let Some(op) = node.op_as::<EinSum>() else { continue };
+        let AxesOrPatch::Axes(m, k, n) = ensure_mkn_axes(op, model, node)? else { continue };
+        if let Some(patch) = wire_sparse_weights(op, model, node, (m, k, n), max_density)? {
+            patch.apply(model)?;
+        }
+    }
+    model.compact()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn weights() -> Tensor {
+        // non-zero columns shared by groups of 4 rows, one in five
+        let w = (0..64 * 32).map(|i| (i / 32, i % 32)).map(|(r, c)| {
+            if (r / 4 + c) % 5 == 0 {
+                (r + c + 1) as f32
+            } else {
+                0.0
+            }
+        });
+        Tensor::from_shape(&[64, 32], &w.collect::<Vec<_>>()).unwrap()
+    }
+
+    fn check(expr: &str, weights_first: bool, x_shape: &[usize]) -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact(x_shape))?;
+        let w = model.add_const("w", weights())?;
+        let inputs = if weights_first { [w, x] } else { [x, w] };
+        let c = model.wire_node("c", EinSum::new(expr.parse()?, f32::datum_type()), &inputs)?;
+        model.set_output_outlets(&c)?;
+        let model = model.into_decluttered()?;
+        let x_len = x_shape.iter().product::<usize>();
+        let x = Tensor::from_shape(x_shape, &(0..x_len).map(|i| i as f32).collect::<Vec<_>>())?;
+        let expected = model.clone().into_runnable()?.run(tvec!(x.clone().into_tvalue()))?;
+
+        let mut sparse = model.clone();
+        sparsify_weights(&mut sparse, SPARSE_DENSITY_THRESHOLD)?;
+        assert!(sparse.nodes().iter().any(|n| n.op_is::<SparseMatMul>()));
+        let found = sparse.clone().into_runnable()?.run(tvec!(x.clone().into_tvalue()))?;
+        found[0].close_enough(&expected[0], true)?;
+
+        let dense = model.into_optimized()?;
+        assert!(!dense.nodes().iter().any(|n| n.op_is::<SparseMatMul>()));
+
+        let optimized = sparse.into_optimized()?;
+        assert!(optimized.nodes().iter().any(|n| n.op_is::<SparseMatMul>()));
+        let found = optimized.into_runnable()?.run(tvec!(x.into_tvalue()))?;
+        found[0].close_enough(&expected[0], true)
+    }
+
+    #[test]
+    fn weights_first() -> TractResult<()> {
+        check("mk,kn->mn", true, &[32, 5])
+    }
+
+    #[test]
+    fn weights_second() -> TractResult<()> {
+        check("mk,nk->mn", false, &[3, 32])
+    }
+
+    #[test]
+    fn batched_transposed_output() -> TractResult<()> {
+        check("mk,bkn->bnm", true, &[2, 32, 9])
+    }
+}
@@ -13,6 +13,7 @@ pub mod element_wise;
 pub mod binary;
 
 pub mod array;
+pub mod audio;
 pub mod cast;
 pub mod change_axes;
 pub mod cnn;
@@ -20,6 +21,7 @@ pub mod downsample;
 pub mod dummy;
 pub mod einsum;
 pub mod fft;
+pub mod image;
 pub mod identity;
 pub mod konst;
 pub mod logic;
@@ -28,6 +30,7 @@ pub mod matmul;
 pub mod memory;
 pub mod nn;
 pub mod quant;
+pub mod resize;
 pub mod scan;
 pub mod source;
 pub mod submodel;
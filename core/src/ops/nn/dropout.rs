@@ -0,0 +1,185 @@
+use crate::internal::*;
+use crate::ops::identity::Identity;
+use crate::random::{RandomOp, RandomOpState, RandomStream};
+use num_traits::Float;
+
+/// Dropout. At inference, it passes its input through. In training mode, it
+/// zeroes each value with probability `ratio` and scales the others by
+/// `1 / (1 - ratio)`, drawing from the seedable state generator: running a
+/// training-mode model several times gives Monte-Carlo dropout estimates
+/// (see `SimpleState::run_monte_carlo`).
+///
+/// With `output_mask`, a second output tells which values were kept.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dropout {
+    pub ratio: f32,
+    pub training: bool,
+    pub output_mask: bool,
+    pub seed: Option<u64>,
+}
+
+impl Dropout {
+    fn apply<T: Datum + Float>(tensor: &mut Tensor, mask: &[bool], scale: f64) -> TractResult<()> {
+        let scale = T::from(scale).unwrap();
+        for (x, &keep) in tensor.as_slice_mut::<T>()?.iter_mut().zip(mask) {
+            *x = if keep { *x * scale } else { T::zero() };
+        }
+        Ok(())
+    }
+
+    fn outputs(&self, output: TValue, mask: Tensor) -> TVec<TValue> {
+        if self.output_mask {
+            tvec!(output, mask.into_tvalue())
+        } else {
+            tvec!(output)
+        }
+    }
+}
+
+impl Op for Dropout {
+    fn name(&self) -> Cow<str> {
+        "Dropout".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("ratio: {} training: {}", self.ratio, self.training)])
+    }
+
+    fn validation(&self) -> Validation {
+        if self.training {
+            Validation::Random
+        } else {
+            Validation::Accurate
+        }
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for Dropout {
+    fn is_stateless(&self) -> bool {
+        !self.training
+    }
+
+    fn state(
+        &self,
+        session: &mut SessionState,
+        node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        if !self.training {
+            return Ok(None);
+        }
+        let stream = RandomStream::for_op(session, node_id, self.seed);
+        Ok(Some(Box::new(RandomOpState::<Self>::new(stream))))
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        ensure!(!self.training, "Training-mode dropout needs a state");
+        let input = args_1!(inputs);
+        let mask = tensor0(true).broadcast_scalar_to_shape(input.shape())?;
+        Ok(self.outputs(input, mask))
+    }
+}
+
+impl RandomOp for Dropout {
+    fn eval_with_stream(
+        &self,
+        _session: &SessionState,
+        stream: &mut RandomStream,
+        inputs: TVec<TValue>,
+    ) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let mask: Vec<bool> = (0..input.len()).map(|_| stream.next_f32() >= self.ratio).collect();
+        let mut output = input.into_tensor();
+        let scale = if self.ratio < 1.0 { 1.0 / (1.0 - self.ratio as f64) } else { 0.0 };
+        dispatch_floatlike!(Self::apply(output.datum_type())(&mut output, &mask, scale))?;
+        let mask = tensor1(&mask).into_shape(output.shape())?;
+        Ok(self.outputs(output.into_tvalue(), mask))
+    }
+}
+
+impl TypedOp for Dropout {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs[0].datum_type.is_float(), "Dropout expects float input");
+        let mut facts = tvec!(inputs[0].without_value());
+        if self.output_mask {
+            facts.push(bool::fact(inputs[0].shape.clone()));
+        }
+        Ok(facts)
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let mask_used = self.output_mask && node.outputs[1].successors.len() > 0;
+        if mask_used || model.output_outlets()?.contains(&OutletId::new(node.id, 1)) {
+            Ok(None)
+        } else if !self.training {
+            Ok(Some(TypedModelPatch::single_unary_op(model, node, Identity)?))
+        } else if self.output_mask {
+            let op = Dropout { output_mask: false, ..self.clone() };
+            Ok(Some(TypedModelPatch::single_unary_op(model, node, op)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn model(training: bool) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact([1000]))?;
+        let op = Dropout { ratio: 0.25, training, output_mask: true, seed: None };
+        let outputs = model.wire_node("dropout", op, &[source])?;
+        model.set_output_outlets(&outputs)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn inference_is_identity() -> TractResult<()> {
+        let input = Tensor::from_shape(&[1000], &[1f32; 1000])?;
+        let outputs = model(false)?.into_runnable()?.run(tvec!(input.clone().into_tvalue()))?;
+        assert_eq!(*outputs[0], input);
+        assert!(outputs[1].as_slice::<bool>()?.iter().all(|b| *b));
+        Ok(())
+    }
+
+    #[test]
+    fn training_masks_and_scales() -> TractResult<()> {
+        let plan = model(true)?.into_runnable()?;
+        let mut state = SimpleState::new(&plan)?;
+        state.set_seed(7)?;
+        let input = Tensor::from_shape(&[1000], &[1f32; 1000])?;
+        let outputs = state.run(tvec!(input.into_tvalue()))?;
+        let kept = outputs[1].as_slice::<bool>()?;
+        for (x, keep) in outputs[0].as_slice::<f32>()?.iter().zip(kept) {
+            assert_eq!(*x, if *keep { 1. / 0.75 } else { 0. });
+        }
+        let dropped = kept.iter().filter(|k| !**k).count();
+        assert!((200..300).contains(&dropped), "{dropped}");
+        Ok(())
+    }
+
+    #[test]
+    fn monte_carlo() -> TractResult<()> {
+        let plan = model(true)?.into_runnable()?;
+        let mut state = SimpleState::new(&plan)?;
+        state.set_seed(3)?;
+        let input = Tensor::from_shape(&[1000], &[3f32; 1000])?;
+        let stats = state.run_monte_carlo(tvec!(input.into_tvalue()), 50)?;
+        let (mean, var) = &stats[0];
+        let mean = mean.as_slice::<f32>()?.iter().sum::<f32>() / 1000.;
+        let var = var.as_slice::<f32>()?.iter().sum::<f32>() / 1000.;
+        // a 3 / 0.75 = 4 value kept with probability 0.75: mean 3, variance 3
+        assert!((mean - 3.).abs() < 0.1, "{mean}");
+        assert!((var - 3.).abs() < 0.3, "{var}");
+        Ok(())
+    }
+}
@@ -0,0 +1,588 @@
+//! Resizing by interpolation, with the semantics of ONNX Resize.
+use crate::internal::*;
+use std::str::FromStr;
+use tract_ndarray::{ArrayD, Axis as NdAxis};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoordTransformer {
+    HalfPixel,
+    HalfPixelSymmetric,
+    PytorchHalfPixel,
+    AlignCorners,
+    Asymmetric,
+    TfCropAndResize,
+}
+
+impl CoordTransformer {
+    pub fn as_str(&self) -> &'static str {
+        use CoordTransformer::*;
+        match self {
+            HalfPixel => "half_pixel",
+            HalfPixelSymmetric => "half_pixel_symmetric",
+            PytorchHalfPixel => "pytorch_half_pixel",
+            AlignCorners => "align_corners",
+            Asymmetric => "asymmetric",
+            TfCropAndResize => "tf_crop_and_resize",
+        }
+    }
+}
+
+impl FromStr for CoordTransformer {
+    type Err = TractError;
+    fn from_str(s: &str) -> TractResult<Self> {
+        use CoordTransformer::*;
+        Ok(match s {
+            "half_pixel" => HalfPixel,
+            "half_pixel_symmetric" => HalfPixelSymmetric,
+            "pytorch_half_pixel" => PytorchHalfPixel,
+            "align_corners" => AlignCorners,
+            "asymmetric" => Asymmetric,
+            "tf_crop_and_resize" => TfCropAndResize,
+            _ => bail!("Unsupported coordinate transformation mode: {}", s),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolator {
+    Nearest,
+    Linear,
+    /// Keys cubic convolution, with `a` the cubic coefficient.
+    Cubic {
+        a: f32,
+    },
+}
+
+impl Interpolator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Interpolator::Nearest => "nearest",
+            Interpolator::Linear => "linear",
+            Interpolator::Cubic { .. } => "cubic",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Nearest {
+    Floor,
+    Ceil,
+    RoundPreferFloor,
+    RoundPreferCeil,
+}
+
+impl Nearest {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Nearest::Floor => "floor",
+            Nearest::Ceil => "ceil",
+            Nearest::RoundPreferFloor => "round_prefer_floor",
+            Nearest::RoundPreferCeil => "round_prefer_ceil",
+        }
+    }
+}
+
+impl FromStr for Nearest {
+    type Err = TractError;
+    fn from_str(s: &str) -> TractResult<Self> {
+        Ok(match s {
+            "floor" => Nearest::Floor,
+            "ceil" => Nearest::Ceil,
+            "round_prefer_floor" => Nearest::RoundPreferFloor,
+            "round_prefer_ceil" => Nearest::RoundPreferCeil,
+            _ => bail!("Unsupported nearest mode: {}", s),
+        })
+    }
+}
+
+/// How `sizes` are honoured: exactly, or by a common scale for all resized
+/// axes so the result fits in (not larger) or covers (not smaller) them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeepAspectRatioPolicy {
+    Stretch,
+    NotLarger,
+    NotSmaller,
+}
+
+impl KeepAspectRatioPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeepAspectRatioPolicy::Stretch => "stretch",
+            KeepAspectRatioPolicy::NotLarger => "not_larger",
+            KeepAspectRatioPolicy::NotSmaller => "not_smaller",
+        }
+    }
+}
+
+impl FromStr for KeepAspectRatioPolicy {
+    type Err = TractError;
+    fn from_str(s: &str) -> TractResult<Self> {
+        Ok(match s {
+            "stretch" => KeepAspectRatioPolicy::Stretch,
+            "not_larger" => KeepAspectRatioPolicy::NotLarger,
+            "not_smaller" => KeepAspectRatioPolicy::NotSmaller,
+            _ => bail!("Unsupported keep_aspect_ratio_policy: {}", s),
+        })
+    }
+}
+
+/// Weighted input positions contributing to an output position along one
+/// axis. `None` stands for the extrapolation value.
+pub type Taps = Option<TVec<(usize, f32)>>;
+
+/// Resize the `axes` of the input, by `scales` or to `sizes` (one per axis).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resize {
+    pub axes: TVec<usize>,
+    pub scales: Option<TVec<f32>>,
+    pub sizes: Option<TVec<TDim>>,
+    /// Starts then ends of the region of interest, as fractions of the axes,
+    /// for tf_crop_and_resize.
+    pub roi: Option<TVec<f32>>,
+    pub coord_transformer: CoordTransformer,
+    pub interpolator: Interpolator,
+    pub nearest: Nearest,
+    pub antialias: bool,
+    pub exclude_outside: bool,
+    pub extrapolation_value: f32,
+    pub keep_aspect_ratio_policy: KeepAspectRatioPolicy,
+}
+
+impl Default for Resize {
+    fn default() -> Resize {
+        Resize {
+            axes: tvec!(),
+            scales: None,
+            sizes: None,
+            roi: None,
+            coord_transformer: CoordTransformer::HalfPixel,
+            interpolator: Interpolator::Nearest,
+            nearest: Nearest::RoundPreferFloor,
+            antialias: false,
+            exclude_outside: false,
+            extrapolation_value: 0.0,
+            keep_aspect_ratio_policy: KeepAspectRatioPolicy::Stretch,
+        }
+    }
+}
+
+impl Resize {
+    fn roi(&self, ix: usize) -> (f32, f32) {
+        if let (CoordTransformer::TfCropAndResize, Some(roi)) = (self.coord_transformer, &self.roi)
+        {
+            (roi[ix], roi[self.axes.len() + ix])
+        } else {
+            (0.0, 1.0)
+        }
+    }
+
+    /// Scale and output length of each resized axis, for a concrete input
+    /// shape.
+    pub fn scales_and_lengths(&self, input: &[usize]) -> TractResult<TVec<(f32, usize)>> {
+        let lens: TVec<usize> = self.axes.iter().map(|&axis| input[axis]).collect();
+        if let Some(sizes) = &self.sizes {
+            let sizes = sizes.iter().map(|s| s.to_usize()).collect::<TractResult<TVec<_>>>()?;
+            let ratios = sizes.iter().zip(&lens).map(|(&s, &l)| s as f32 / l as f32);
+            let common = match self.keep_aspect_ratio_policy {
+                KeepAspectRatioPolicy::Stretch => {
+                    return Ok(ratios.zip(sizes.iter().copied()).collect())
+                }
+                KeepAspectRatioPolicy::NotLarger => ratios.fold(f32::INFINITY, f32::min),
+                KeepAspectRatioPolicy::NotSmaller => ratios.fold(0.0, f32::max),
+            };
+            Ok(lens.iter().map(|&l| (common, (common * l as f32).round() as usize)).collect())
+        } else if let Some(scales) = &self.scales {
+            Ok(scales
+                .iter()
+                .zip(&lens)
+                .enumerate()
+                .map(|(ix, (&s, &l))| {
+                    let (start, end) = self.roi(ix);
+                    (s, (l as f32 * (end - start) * s).floor() as usize)
+                })
+                .collect())
+        } else {
+            bail!("Resize needs either scales or sizes")
+        }
+    }
+
+    pub fn output_shape<D: DimLike>(&self, input: &[D]) -> TractResult<TVec<D>> {
+        ensure!(self.axes.iter().all(|&axis| axis < input.len()));
+        let mut shape: TVec<D> = input.into();
+        if let Ok(concrete) = input.iter().map(|d| d.to_usize()).collect::<TractResult<TVec<_>>>() {
+            for (&axis, (_, len)) in self.axes.iter().zip(self.scales_and_lengths(&concrete)?) {
+                shape[axis] = len.into();
+            }
+            return Ok(shape);
+        }
+        for (ix, &axis) in self.axes.iter().enumerate() {
+            shape[axis] = match (&self.sizes, &self.scales) {
+                (Some(sizes), _)
+                    if self.keep_aspect_ratio_policy == KeepAspectRatioPolicy::Stretch =>
+                {
+                    D::try_from(&sizes[ix])?
+                }
+                (None, Some(scales))
+                    if scales[ix] >= 1.0
+                        && scales[ix].fract() == 0.0
+                        && self.roi(ix) == (0.0, 1.0) =>
+                {
+                    input[axis].clone() * scales[ix] as usize
+                }
+                _ => {
+                    bail!("Can not compute output shape of {:?} for input shape {:?}", self, input)
+                }
+            }
+        }
+        Ok(shape)
+    }
+
+    fn original_coord(
+        &self,
+        ix: usize,
+        x: usize,
+        in_len: usize,
+        out_len: usize,
+        scale: f32,
+    ) -> f32 {
+        use CoordTransformer::*;
+        let (x, in_f, out_f) = (x as f32, in_len as f32, out_len as f32);
+        match self.coord_transformer {
+            HalfPixel => (x + 0.5) / scale - 0.5,
+            HalfPixelSymmetric => {
+                let adjustment = out_f / (scale * in_f);
+                in_f / 2.0 * (1.0 - adjustment) + (x + 0.5) / scale - 0.5
+            }
+            PytorchHalfPixel if out_len > 1 => (x + 0.5) / scale - 0.5,
+            AlignCorners if out_len > 1 => x * (in_f - 1.0) / (out_f - 1.0),
+            PytorchHalfPixel | AlignCorners => 0.0,
+            Asymmetric => x / scale,
+            TfCropAndResize => {
+                let (start, end) = self.roi(ix);
+                if out_len > 1 {
+                    start * (in_f - 1.0) + x * (end - start) * (in_f - 1.0) / (out_f - 1.0)
+                } else {
+                    0.5 * (start + end) * (in_f - 1.0)
+                }
+            }
+        }
+    }
+
+    /// Taps of every output position of the `ix`-th resized axis.
+    pub fn axis_taps(&self, ix: usize, in_len: usize, out_len: usize, scale: f32) -> Vec<Taps> {
+        let clamp = |i: isize| i.clamp(0, in_len as isize - 1) as usize;
+        (0..out_len)
+            .map(|o| {
+                let x = self.original_coord(ix, o, in_len, out_len, scale);
+                if in_len == 0
+                    || (self.coord_transformer == CoordTransformer::TfCropAndResize
+                        && (x < 0.0 || x > in_len as f32 - 1.0))
+                {
+                    return None;
+                }
+                // integer coordinates are seen as the right end of the interval on their left
+                let (base, ratio) = if x.fract() == 0.0 {
+                    (x as isize - 1, 1.0)
+                } else {
+                    (x.floor() as isize, x - x.floor())
+                };
+                let radius = match self.interpolator {
+                    Interpolator::Nearest => {
+                        let right = match self.nearest {
+                            _ if ratio == 1.0 => true,
+                            Nearest::Floor => false,
+                            Nearest::Ceil => true,
+                            Nearest::RoundPreferFloor => ratio > 0.5,
+                            Nearest::RoundPreferCeil => ratio >= 0.5,
+                        };
+                        return Some(tvec!((clamp(base + right as isize), 1.0)));
+                    }
+                    Interpolator::Linear => 1.0,
+                    Interpolator::Cubic { .. } => 2.0,
+                };
+                // antialiasing widens the kernel when downsampling
+                let s = if self.antialias { scale.min(1.0) } else { 1.0 };
+                let start = (-radius / s).floor() as isize + 1;
+                let mut taps: TVec<(usize, f32)> = (start..2 - start)
+                    .filter(|i| !self.exclude_outside || (0..in_len as isize).contains(&(base + i)))
+                    .map(|i| (clamp(base + i), self.kernel((i as f32 - ratio) * s)))
+                    .filter(|(_, w)| *w != 0.0)
+                    .collect();
+                let sum: f32 = taps.iter().map(|t| t.1).sum();
+                if sum != 0.0 && sum != 1.0 {
+                    taps.iter_mut().for_each(|t| t.1 /= sum);
+                }
+                Some(taps)
+            })
+            .collect()
+    }
+
+    fn kernel(&self, t: f32) -> f32 {
+        let t = t.abs();
+        match self.interpolator {
+            Interpolator::Cubic { a } if t <= 1.0 => ((a + 2.0) * t - (a + 3.0)) * t * t + 1.0,
+            Interpolator::Cubic { a } if t < 2.0 => ((a * t - 5.0 * a) * t + 8.0 * a) * t - 4.0 * a,
+            Interpolator::Cubic { .. } => 0.0,
+            _ => (1.0 - t).max(0.0),
+        }
+    }
+}
+
+fn resize_axis(data: &ArrayD<f32>, axis: usize, taps: &[Taps], extrapolation: f32) -> ArrayD<f32> {
+    let mut shape = data.shape().to_vec();
+    shape[axis] = taps.len();
+    let mut output = ArrayD::<f32>::zeros(shape);
+    for (o, taps) in taps.iter().enumerate() {
+        let mut slice = output.index_axis_mut(NdAxis(axis), o);
+        if let Some(taps) = taps {
+            for &(i, w) in taps {
+                slice.scaled_add(w, &data.index_axis(NdAxis(axis), i));
+            }
+        } else {
+            slice.fill(extrapolation);
+        }
+    }
+    output
+}
+
+/// Resizes one axis of a tensor of any numeric type with precomputed taps.
+pub fn resize_tensor_axis(
+    input: &Tensor,
+    axis: usize,
+    taps: &[Taps],
+    extrapolation: f32,
+) -> TractResult<Tensor> {
+    let dt = input.datum_type();
+    let data = input.cast_to::<f32>()?.into_owned().into_array::<f32>()?;
+    let mut data = resize_axis(&data, axis, taps, extrapolation);
+    if !dt.is_float() {
+        data.mapv_inplace(f32::round);
+    }
+    Ok(data.into_tensor().cast_to_dt(dt)?.into_owned())
+}
+
+impl Op for Resize {
+    fn name(&self) -> Cow<str> {
+        "Resize".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        let mut info = vec![format!(
+            "{} {} on axes {:?}",
+            self.interpolator.as_str(),
+            self.coord_transformer.as_str(),
+            self.axes
+        )];
+        if let Some(scales) = &self.scales {
+            info.push(format!("scales: {scales:?}"));
+        }
+        if let Some(sizes) = &self.sizes {
+            info.push(format!("sizes: {sizes:?} ({})", self.keep_aspect_ratio_policy.as_str()));
+        }
+        Ok(info)
+    }
+
+    impl_op_same_as!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for Resize {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let dt = input.datum_type();
+        let mut data = input.cast_to::<f32>()?.into_owned().into_array::<f32>()?;
+        for (ix, (scale, len)) in self.scales_and_lengths(input.shape())?.into_iter().enumerate() {
+            let axis = self.axes[ix];
+            let taps = self.axis_taps(ix, data.shape()[axis], len, scale);
+            let identity = taps.len() == data.shape()[axis]
+                && taps.iter().enumerate().all(|(o, t)| t.as_deref() == Some(&[(o, 1.0)]));
+            if !identity {
+                data = resize_axis(&data, axis, &taps, self.extrapolation_value);
+            }
+        }
+        if !dt.is_float() {
+            data.mapv_inplace(f32::round);
+        }
+        Ok(tvec!(data.into_tensor().cast_to_dt(dt)?.into_owned().into_tvalue()))
+    }
+}
+
+impl TypedOp for Resize {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if let Some(scales) = &self.scales {
+            ensure!(scales.len() == self.axes.len());
+        }
+        if let Some(sizes) = &self.sizes {
+            ensure!(sizes.len() == self.axes.len());
+        }
+        Ok(tvec!(inputs[0].datum_type.fact(self.output_shape(&inputs[0].shape)?)))
+    }
+
+    fn axes_mapping(
+        &self,
+        inputs: &[&TypedFact],
+        outputs: &[&TypedFact],
+    ) -> TractResult<AxesMapping> {
+        let mut result = AxesMapping::disconnected(inputs, outputs)?;
+        for axis in 0..inputs[0].rank() {
+            if !self.axes.contains(&axis) {
+                result = result.linking((InOut::In(0), axis), (InOut::Out(0), axis))?;
+            }
+        }
+        Ok(result)
+    }
+
+    fn change_axes(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+        _io: InOut,
+        change: &AxisOp,
+    ) -> TractResult<Option<AxisChangeConsequence>> {
+        let mut axes = tvec!();
+        for resized in &self.axes {
+            if let Some(axis) = change.transform_axis(*resized) {
+                axes.push(axis);
+            } else {
+                return Ok(None);
+            }
+        }
+        let op = Some(Box::new(Self { axes, ..self.clone() }) as _);
+        Ok(Some(AxisChangeConsequence::new(model, node, op, change)))
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let input_fact = model.outlet_fact(node.inputs[0])?;
+        if self.axes.is_empty()
+            || (self.coord_transformer != CoordTransformer::TfCropAndResize
+                && node.outputs[0].fact.shape == input_fact.shape
+                && self.sizes.is_none()
+                && self.scales.as_ref().map(|s| s.iter().all(|s| *s == 1.0)).unwrap_or(false))
+        {
+            return TypedModelPatch::shunt_one_op(model, node);
+        }
+        Ok(None)
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn resize(op: Resize, input: Tensor) -> TractResult<Tensor> {
+        Ok(op.eval(tvec!(input.into_tvalue()))?.remove(0).into_tensor())
+    }
+
+    fn scales(interpolator: Interpolator, scales: &[f32]) -> Resize {
+        Resize {
+            axes: (0..scales.len()).collect(),
+            scales: Some(scales.into()),
+            interpolator,
+            ..Resize::default()
+        }
+    }
+
+    // expected values are from the ONNX backend test suite
+    #[test]
+    fn upsample_nearest() -> TractResult<()> {
+        let op = Resize {
+            coord_transformer: CoordTransformer::Asymmetric,
+            nearest: Nearest::Floor,
+            ..scales(Interpolator::Nearest, &[2.0, 3.0])
+        };
+        let found = resize(op, tensor2(&[[1f32, 2.], [3., 4.]]))?;
+        let expected = tensor2(&[
+            [1f32, 1., 1., 2., 2., 2.],
+            [1., 1., 1., 2., 2., 2.],
+            [3., 3., 3., 4., 4., 4.],
+            [3., 3., 3., 4., 4., 4.],
+        ]);
+        assert_eq!(found, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn upsample_linear_half_pixel() -> TractResult<()> {
+        let found =
+            resize(scales(Interpolator::Linear, &[2.0, 2.0]), tensor2(&[[1f32, 2.], [3., 4.]]))?;
+        let expected = tensor2(&[
+            [1f32, 1.25, 1.75, 2.],
+            [1.5, 1.75, 2.25, 2.5],
+            [2.5, 2.75, 3.25, 3.5],
+            [3., 3.25, 3.75, 4.],
+        ]);
+        found.close_enough(&expected, true)
+    }
+
+    #[test]
+    fn upsample_cubic() -> TractResult<()> {
+        let input = Tensor::from_shape(&[4, 4], &(1..=16).map(|x| x as f32).collect::<Vec<_>>())?;
+        let found = resize(scales(Interpolator::Cubic { a: -0.75 }, &[2.0, 2.0]), input)?;
+        let first_row = [
+            0.47265625f32,
+            0.76953125,
+            1.24609375,
+            1.875,
+            2.28125,
+            2.91015625,
+            3.38671875,
+            3.68359375,
+        ];
+        found.slice(0, 0, 1)?.close_enough(&tensor2(&[first_row]), true)
+    }
+
+    #[test]
+    fn downsample_linear_antialias() -> TractResult<()> {
+        let input = Tensor::from_shape(&[4, 4], &(1..=16).map(|x| x as f32).collect::<Vec<_>>())?;
+        let op = Resize { antialias: true, ..scales(Interpolator::Linear, &[0.6, 0.6]) };
+        let found = resize(op, input)?;
+        found.close_enough(&tensor2(&[[2.875f32, 4.5], [9.375, 11.0]]), true)
+    }
+
+    #[test]
+    fn tf_crop_and_resize_extrapolation() -> TractResult<()> {
+        let input = Tensor::from_shape(&[4, 4], &(1..=16).map(|x| x as f32).collect::<Vec<_>>())?;
+        let op = Resize {
+            axes: tvec!(0, 1),
+            sizes: Some(tvec!(3.to_dim(), 3.to_dim())),
+            roi: Some(tvec!(0.4, 0.6, 1.2, 1.7)),
+            coord_transformer: CoordTransformer::TfCropAndResize,
+            interpolator: Interpolator::Linear,
+            extrapolation_value: 10.0,
+            ..Resize::default()
+        };
+        let found = resize(op, input)?;
+        let expected = tensor2(&[[7.6f32, 10., 10.], [12.4, 10., 10.], [10., 10., 10.]]);
+        found.close_enough(&expected, true)
+    }
+
+    #[test]
+    fn sizes_not_larger() -> TractResult<()> {
+        let op = Resize {
+            axes: tvec!(0, 1),
+            sizes: Some(tvec!(7.to_dim(), 8.to_dim())),
+            keep_aspect_ratio_policy: KeepAspectRatioPolicy::NotLarger,
+            ..Resize::default()
+        };
+        assert_eq!(op.output_shape(&[2usize, 2])?, tvec!(7, 7));
+        Ok(())
+    }
+
+    #[test]
+    fn symbolic_integer_scale() -> TractResult<()> {
+        let s = SymbolTable::default().sym("S");
+        let op = scales(Interpolator::Linear, &[3.0]);
+        assert_eq!(op.output_shape(&[s.to_dim()])?, tvec!(s.to_dim() * 3));
+        Ok(())
+    }
+}
@@ -14,6 +14,37 @@ pub struct SessionState {
     pub resolved_symbols: SymbolValues,
     pub tensors: HashMap<String, Tensor>,
     pub cached_mmm_scratch_space: Option<Box<dyn tract_linalg::mmm::ScratchSpace>>,
+    /// When set, ops push timings of their inner steps here (profiling timelines).
+    pub steps: Option<Vec<EvalStep>>,
+    /// Seed of the random ops that have none of their own (see `crate::random`).
+    pub seed: Option<u64>,
+}
+
+/// A timed inner step of an op evaluation.
+#[derive(Debug, Clone)]
+pub struct EvalStep {
+    pub name: String,
+    pub start: std::time::Instant,
+    pub duration: std::time::Duration,
+}
+
+impl SessionState {
+    /// Runs `f`, recording it as an inner step if steps are being recorded.
+    #[inline]
+    pub fn step<R>(
+        steps: &mut Option<Vec<EvalStep>>,
+        name: impl FnOnce() -> String,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        if let Some(steps) = steps {
+            let start = std::time::Instant::now();
+            let r = f();
+            steps.push(EvalStep { name: name(), start, duration: start.elapsed() });
+            r
+        } else {
+            f()
+        }
+    }
 }
 
 impl Clone for SessionState {
@@ -23,6 +54,8 @@ impl Clone for SessionState {
             resolved_symbols: self.resolved_symbols.clone(),
             tensors: self.tensors.clone(),
             cached_mmm_scratch_space: None,
+            steps: None,
+            seed: self.seed,
         }
     }
 }
@@ -180,6 +213,13 @@ where
         Ok(())
     }
 
+    /// Seed the random ops that have no seed of their own, and reset op
+    /// states so they start drawing from the seeded generators.
+    pub fn set_seed(&mut self, seed: u64) -> TractResult<()> {
+        self.session_state.seed = Some(seed);
+        self.reset_op_states()
+    }
+
     /// Reset op inner state.
     pub fn reset_op_states(&mut self) -> TractResult<()> {
         let &mut SimpleState { ref plan, ref mut session_state, ref mut states, .. } = self;
@@ -197,6 +237,45 @@ where
         self.run_plan_with_eval(inputs, self::eval)
     }
 
+    /// Run the plan `passes` times on the same inputs and aggregate each
+    /// output into a (mean, variance) pair of f32 tensors. Random ops (like
+    /// training-mode dropout) keep drawing from their streams between passes.
+    pub fn run_monte_carlo(
+        &mut self,
+        inputs: TVec<TValue>,
+        passes: usize,
+    ) -> TractResult<TVec<(Tensor, Tensor)>> {
+        ensure!(passes > 0, "Monte-Carlo run needs at least one pass");
+        let mut stats: TVec<(Tensor, Tensor)> = tvec!();
+        for pass in 0..passes {
+            let outputs = self.run(inputs.clone())?;
+            if pass == 0 {
+                for output in &outputs {
+                    let zero = Tensor::zero::<f64>(output.shape())?;
+                    stats.push((zero.clone(), zero));
+                }
+            }
+            // Welford's online mean and variance
+            for (output, (mean, m2)) in outputs.iter().zip(stats.iter_mut()) {
+                let output = output.cast_to::<f64>()?;
+                let mean = mean.as_slice_mut::<f64>()?;
+                let m2 = m2.as_slice_mut::<f64>()?;
+                for ((x, mean), m2) in output.as_slice::<f64>()?.iter().zip(mean).zip(m2) {
+                    let delta = x - *mean;
+                    *mean += delta / (pass + 1) as f64;
+                    *m2 += delta * (x - *mean);
+                }
+            }
+        }
+        stats
+            .into_iter()
+            .map(|(mean, mut m2)| {
+                m2.as_slice_mut::<f64>()?.iter_mut().for_each(|v| *v /= passes as f64);
+                Ok((mean.cast_to::<f32>()?.into_owned(), m2.cast_to::<f32>()?.into_owned()))
+            })
+            .collect()
+    }
+
     pub fn exec(&mut self) -> TractResult<()> {
         self.exec_plan_with_eval(self::eval)
     }
@@ -526,6 +605,7 @@ where
                 .collect(),
             resolved_symbols: self.session_state.resolved_symbols.clone(),
             tensors: self.session_state.tensors.clone(),
+            seed: self.session_state.seed,
             states: self.states.iter().map(|s| s.as_ref().map(|s| s.freeze())).collect(),
             values: self
                 .values
@@ -574,6 +654,7 @@ where
     pub inputs: HashMap<usize, Tensor>,
     pub resolved_symbols: SymbolValues,
     pub tensors: HashMap<String, Tensor>,
+    pub seed: Option<u64>,
     pub states: Vec<Option<Box<dyn FrozenOpState>>>,
     pub values: Vec<Option<TVec<Tensor>>>,
     _phantom: PhantomData<(M, F, O)>,
@@ -594,6 +675,8 @@ where
                 resolved_symbols: self.resolved_symbols.clone(),
                 tensors: self.tensors.clone(),
                 cached_mmm_scratch_space: None,
+                steps: None,
+                seed: self.seed,
             },
             states: self.states.iter().map(|s| s.as_ref().map(|s| s.unfreeze())).collect(),
             values: self
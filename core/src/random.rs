@@ -0,0 +1,361 @@
+//! Random number generation shared by random ops.
+//!
+//! Generators are Philox4x32x10, with TensorFlow conventions for seeding and
+//! for converting raw u32 to floats, so seeded ops reproduce TensorFlow
+//! outputs. Ops keep their generator in their `OpState`: frozen states
+//! replay the same values, and seeding a `SimpleState` with
+//! `SimpleState::set_seed` makes the ops that do not have a seed of their own
+//! deterministic.
+//
+// Philox from https://github.com/tensorflow/tensorflow/blob/master/tensorflow/core/lib/random/philox_random.h
+
+use crate::internal::*;
+use crate::ops::OpStateFreeze;
+use std::hash::{BuildHasher, Hasher};
+use tract_num_traits::Float;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Philox4x32x10 {
+    key: u64,
+    counter: u128,
+}
+
+fn mul_hilo(a: u32, b: u32) -> (u32, u32) {
+    ((((a as u64) * (b as u64)) >> 32) as u32, ((a as u64) * (b as u64)) as u32)
+}
+
+#[allow(non_upper_case_globals)]
+impl Philox4x32x10 {
+    /// TensorFlow's PhiloxRandom(seed_lo, seed_hi): seed_hi goes to the
+    /// upper half of the counter.
+    pub fn weird_tf_constructor(seed_lo: u64, seed_hi: u64) -> Philox4x32x10 {
+        let mut ph = Self::for_seed(seed_lo);
+        ph.skip_fast((seed_hi as u128) << 64);
+        ph
+    }
+
+    pub fn for_seeds(seed1: u32, seed2: u32) -> Philox4x32x10 {
+        Self::for_seed((seed2 as u64) << 32 | seed1 as u64)
+    }
+
+    pub fn for_seed(seed: u64) -> Philox4x32x10 {
+        Philox4x32x10 { key: seed, counter: 0 }
+    }
+
+    pub fn skip_fast(&mut self, n: u128) {
+        self.counter = self.counter.wrapping_add(n);
+    }
+
+    pub fn next_as_u32s(&mut self) -> [u32; 4] {
+        let v = self.next_u128();
+        [v as u32, (v >> 32) as u32, (v >> 64) as u32, (v >> 96) as u32]
+    }
+
+    pub fn next_u128(&mut self) -> u128 {
+        let mut key = self.key;
+        let mut counter = self.counter;
+
+        // 0
+        Self::compute_one(&mut counter, key);
+        Self::raise_key(&mut key);
+        // 1
+        Self::compute_one(&mut counter, key);
+        Self::raise_key(&mut key);
+        // 2
+        Self::compute_one(&mut counter, key);
+        Self::raise_key(&mut key);
+        // 3
+        Self::compute_one(&mut counter, key);
+        Self::raise_key(&mut key);
+        // 4
+        Self::compute_one(&mut counter, key);
+        Self::raise_key(&mut key);
+        // 5
+        Self::compute_one(&mut counter, key);
+        Self::raise_key(&mut key);
+        // 6
+        Self::compute_one(&mut counter, key);
+        Self::raise_key(&mut key);
+        // 7
+        Self::compute_one(&mut counter, key);
+        Self::raise_key(&mut key);
+        // 8
+        Self::compute_one(&mut counter, key);
+        Self::raise_key(&mut key);
+        // 9
+        Self::compute_one(&mut counter, key);
+
+        self.counter = self.counter.wrapping_add(1);
+        counter
+    }
+
+    fn raise_key(key: &mut u64) {
+        const kPhiloxW32A: u32 = 0x9E3779B9;
+        const kPhiloxW32B: u32 = 0xBB67AE85;
+
+        let k0 = *key as u32;
+        let k1 = (*key >> 32) as u32;
+        let k0 = k0.wrapping_add(kPhiloxW32A) as u64;
+        let k1 = k1.wrapping_add(kPhiloxW32B) as u64;
+
+        *key = k1 << 32 | k0;
+    }
+
+    fn compute_one(counter: &mut u128, key: u64) {
+        const kPhiloxM4x32A: u32 = 0xD2511F53;
+        const kPhiloxM4x32B: u32 = 0xCD9E8D57;
+
+        let c0 = *counter as u32;
+        let c1 = (*counter >> 32) as u32;
+        let c2 = (*counter >> 64) as u32;
+        let c3 = (*counter >> 96) as u32;
+
+        let (hi0, lo0) = mul_hilo(kPhiloxM4x32A, c0);
+        let (hi1, lo1) = mul_hilo(kPhiloxM4x32B, c2);
+
+        let r0 = (hi1 ^ c1 ^ (key as u32)) as u128;
+        let r1 = lo1 as u128;
+        let r2 = (hi0 ^ c3 ^ ((key >> 32) as u32)) as u128;
+        let r3 = lo0 as u128;
+
+        *counter = r3 << 96 | r2 << 64 | r1 << 32 | r0
+    }
+
+    pub fn u32_iter(self) -> impl Iterator<Item = u32> {
+        self.flat_map(|big| {
+            tvec![big as u32, (big >> 32) as u32, (big >> 64) as u32, (big >> 96) as u32]
+                .into_iter()
+        })
+    }
+}
+
+impl Iterator for Philox4x32x10 {
+    type Item = u128;
+    fn next(&mut self) -> Option<u128> {
+        Some(self.next_u128())
+    }
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// A seed from the process entropy, for generators nobody seeded.
+pub fn entropy_seed() -> u64 {
+    std::collections::hash_map::RandomState::new().build_hasher().finish()
+}
+
+/// A Philox generator consumed one u32 at a time, in TensorFlow order.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RandomStream {
+    philox: Philox4x32x10,
+    buffer: [u32; 4],
+    used: usize,
+}
+
+impl RandomStream {
+    pub fn new(philox: Philox4x32x10) -> RandomStream {
+        RandomStream { philox, buffer: [0; 4], used: 4 }
+    }
+
+    /// Stream of node `node_id` when its op has no seed of its own: derived
+    /// from the session seed if there is one, from entropy otherwise.
+    pub fn for_node(session: &SessionState, node_id: usize) -> RandomStream {
+        let seed = session.seed.unwrap_or_else(entropy_seed);
+        RandomStream::new(Philox4x32x10::for_seed(splitmix64(seed ^ splitmix64(node_id as u64))))
+    }
+
+    /// Stream of an op with an optional seed, falling back on `for_node`.
+    pub fn for_op(session: &SessionState, node_id: usize, seed: Option<u64>) -> RandomStream {
+        seed.map(|s| RandomStream::new(Philox4x32x10::for_seed(s)))
+            .unwrap_or_else(|| RandomStream::for_node(session, node_id))
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        if self.used == 4 {
+            self.buffer = self.philox.next_as_u32s();
+            self.used = 0;
+        }
+        self.used += 1;
+        self.buffer[self.used - 1]
+    }
+
+    /// Uniform in [0, 1), from 23 random mantissa bits.
+    pub fn next_f32(&mut self) -> f32 {
+        f32::from_bits(127 << 23 | (self.next_u32() & 0x7fffff)) - 1.0
+    }
+
+    /// Uniform in [0, 1), from 52 random mantissa bits.
+    pub fn next_f64(&mut self) -> f64 {
+        let hi = (self.next_u32() & 0xfffff) as u64;
+        let lo = self.next_u32() as u64;
+        f64::from_bits(1023 << 52 | hi << 32 | lo) - 1.0
+    }
+
+    /// Uniform in [0, 1) as f32 or f64.
+    pub fn next_float<T: Datum + Float>(&mut self) -> T {
+        if T::datum_type() == f64::datum_type() {
+            T::from(self.next_f64()).unwrap()
+        } else {
+            T::from(self.next_f32()).unwrap()
+        }
+    }
+
+    /// Fills a float tensor with uniform values in [low, high).
+    pub fn fill_uniform<T: Datum + Float>(
+        &mut self,
+        t: &mut Tensor,
+        low: T,
+        high: T,
+    ) -> TractResult<()> {
+        for x in t.as_slice_mut::<T>()? {
+            *x = low + (high - low) * self.next_float::<T>();
+        }
+        Ok(())
+    }
+
+    /// Fills a float tensor with normal values, by Box-Muller on pairs.
+    pub fn fill_normal<T: Datum + Float>(
+        &mut self,
+        t: &mut Tensor,
+        mean: T,
+        dev: T,
+    ) -> TractResult<()> {
+        let epsilon = T::from(1e-7).unwrap();
+        let two_pi = T::from(2.0 * std::f64::consts::PI).unwrap();
+        for pair in t.as_slice_mut::<T>()?.chunks_mut(2) {
+            let u1 = self.next_float::<T>().max(epsilon);
+            let v1 = two_pi * self.next_float::<T>();
+            let u2 = (T::from(-2.0).unwrap() * u1.ln()).sqrt();
+            let (s, c) = v1.sin_cos();
+            pair[0] = mean + dev * s * u2;
+            if pair.len() == 2 {
+                pair[1] = mean + dev * c * u2;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Ops drawing their values from a `RandomStream` kept in a
+/// `RandomOpState`.
+pub trait RandomOp: Op + Clone {
+    fn eval_with_stream(
+        &self,
+        session: &SessionState,
+        stream: &mut RandomStream,
+        inputs: TVec<TValue>,
+    ) -> TractResult<TVec<TValue>>;
+}
+
+/// State of a `RandomOp`: its generator. Freezing it captures the position
+/// in the stream.
+#[derive(Debug)]
+pub struct RandomOpState<O: RandomOp> {
+    pub stream: RandomStream,
+    _op: PhantomData<fn() -> O>,
+}
+
+impl<O: RandomOp> RandomOpState<O> {
+    pub fn new(stream: RandomStream) -> RandomOpState<O> {
+        RandomOpState { stream, _op: PhantomData }
+    }
+}
+
+impl<O: RandomOp> Clone for RandomOpState<O> {
+    fn clone(&self) -> Self {
+        RandomOpState::new(self.stream.clone())
+    }
+}
+
+impl<O: RandomOp> OpState for RandomOpState<O> {
+    fn eval(
+        &mut self,
+        session: &mut SessionState,
+        op: &dyn Op,
+        inputs: TVec<TValue>,
+    ) -> TractResult<TVec<TValue>> {
+        let op = op.downcast_ref::<O>().context("op and state mismatch")?;
+        op.eval_with_stream(session, &mut self.stream, inputs)
+    }
+}
+
+impl<O: RandomOp> OpStateFreeze for RandomOpState<O> {
+    fn freeze(&self) -> Box<dyn FrozenOpState> {
+        Box::new(self.clone())
+    }
+}
+
+impl<O: RandomOp> FrozenOpState for RandomOpState<O> {
+    fn unfreeze(&self) -> Box<dyn OpState> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // checked against https://github.com/dominikwerder/philox
+    // https://github.com/dominikwerder/philox/blob/master/src/test.rs#L62
+    #[test]
+    fn seed() {
+        let mut ph = Philox4x32x10::for_seeds(1, 2);
+        assert_eq!(ph.next_as_u32s(), [0x598de3a, 0x98d2802e, 0x270f8f9e, 0xeab709d3]);
+    }
+
+    #[test]
+    fn zeros() {
+        let mut ph = Philox4x32x10::for_seeds(0, 0);
+        assert_eq!(ph.next_as_u32s(), [0x6627e8d5, 0xe169c58d, 0xbc57ac4c, 0x9b00dbd8]);
+    }
+
+    #[test]
+    fn ffff() {
+        let mut ph = Philox4x32x10::for_seeds(0xffffffff, 0xffffffff);
+        ph.skip_fast(0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff);
+        assert_eq!(ph.next_as_u32s(), [0x408f276d, 0x41c83b0e, 0xa20bc7c6, 0x6d5451fd]);
+    }
+
+    #[test]
+    fn x243f6a88() {
+        let mut ph = Philox4x32x10::for_seeds(0xa4093822, 0x299f31d0);
+        ph.skip_fast(0x0370_7344_1319_8a2e_85a3_08d3_243f_6a88);
+        assert_eq!(ph.next_as_u32s(), [0xd16cfe09, 0x94fdcceb, 0x5001e420, 0x24126ea1]);
+    }
+
+    #[test]
+    fn stream_follows_philox_order() {
+        let mut stream = RandomStream::new(Philox4x32x10::for_seeds(1, 2));
+        let u32s: Vec<u32> = (0..6).map(|_| stream.next_u32()).collect();
+        let expected: Vec<u32> = Philox4x32x10::for_seeds(1, 2).u32_iter().take(6).collect();
+        assert_eq!(u32s, expected);
+    }
+
+    #[test]
+    fn session_seed() {
+        let session = SessionState { seed: Some(12), ..SessionState::default() };
+        let mut a = RandomStream::for_node(&session, 3);
+        let mut b = RandomStream::for_node(&session, 3);
+        let mut c = RandomStream::for_node(&session, 4);
+        let (a, b, c) = (a.next_u32(), b.next_u32(), c.next_u32());
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn normal_moments() -> TractResult<()> {
+        let mut stream = RandomStream::new(Philox4x32x10::for_seed(5));
+        let mut t = Tensor::zero::<f32>(&[10000])?;
+        stream.fill_normal(&mut t, 1.0f32, 2.0)?;
+        let values = t.as_slice::<f32>()?;
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        let var = values.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / values.len() as f32;
+        assert!((mean - 1.0).abs() < 0.1, "{mean}");
+        assert!((var - 4.0).abs() < 0.2, "{var}");
+        Ok(())
+    }
+}
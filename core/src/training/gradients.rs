@@ -0,0 +1,90 @@
+use std::collections::HashSet;
+
+use super::vjp;
+use crate::internal::*;
+use crate::model::order::eval_order_for_nodes;
+use crate::ops::math;
+
+/// Wire in `model` the gradients of the scalar `loss` with respect to the
+/// `wrt` outlets, by reverse-mode differentiation of the nodes between them.
+///
+/// The returned outlets have the shapes and types of the `wrt` outlets. An
+/// outlet the loss does not depend on gets a zero gradient.
+pub fn wire_gradients(
+    model: &mut TypedModel,
+    loss: OutletId,
+    wrt: &[OutletId],
+) -> TractResult<TVec<OutletId>> {
+    let loss_fact = model.outlet_fact(loss)?.clone();
+    ensure!(loss_fact.datum_type.is_float(), "Loss must be a float, got {:?}", loss_fact);
+    let loss_shape = loss_fact
+        .shape
+        .as_concrete()
+        .filter(|shape| shape.iter().all(|d| *d == 1))
+        .with_context(|| format!("Loss must be a scalar, got {loss_fact:?}"))?
+        .to_vec();
+
+    let inputs: Vec<usize> = model.input_outlets()?.iter().map(|o| o.node).collect();
+    let order = eval_order_for_nodes(model.nodes(), &inputs, &[loss.node], &[])?;
+    // outlets depending on the wrt outlets
+    let mut active: HashSet<OutletId> = wrt.iter().cloned().collect();
+    for &id in &order {
+        let node = model.node(id);
+        if node.inputs.iter().any(|i| active.contains(i)) {
+            active.extend((0..node.outputs.len()).map(|slot| OutletId::new(id, slot)));
+        }
+    }
+
+    let mut contributions: HashMap<OutletId, TVec<OutletId>> = HashMap::default();
+    let seed =
+        tensor0(1f32).cast_to_dt(loss_fact.datum_type)?.broadcast_scalar_to_shape(&loss_shape)?;
+    let seed = model.add_const(format!("{}.grad.seed", model.node(loss.node).name), seed)?;
+    contributions.insert(loss, tvec!(seed));
+    for &id in order.iter().rev() {
+        let node = model.node(id).clone();
+        let wanted: TVec<bool> = node.inputs.iter().map(|i| active.contains(i)).collect();
+        if !wanted.iter().any(|w| *w) {
+            continue;
+        }
+        let grads = (0..node.outputs.len())
+            .map(|slot| accumulate(model, &mut contributions, OutletId::new(id, slot)))
+            .collect::<TractResult<TVec<_>>>()?;
+        let input_grads = vjp::wire_vjp(model, &node, &grads, &wanted)
+            .with_context(|| format!("Differentiating {node}"))?;
+        for ((input, grad), wanted) in node.inputs.iter().zip(input_grads).zip(wanted) {
+            if let (Some(grad), true) = (grad, wanted) {
+                contributions.entry(*input).or_default().push(grad);
+            }
+        }
+    }
+
+    wrt.iter()
+        .map(|&outlet| {
+            if let Some(grad) = accumulate(model, &mut contributions, outlet)? {
+                return Ok(grad);
+            }
+            let fact = model.outlet_fact(outlet)?;
+            let shape = fact.shape.as_concrete().with_context(|| {
+                format!("Loss does not depend on {outlet:?}, and its shape is not known")
+            })?;
+            let zero = Tensor::zero_dt(fact.datum_type, shape)?;
+            model.add_const(format!("{}.grad.{}", model.node(outlet.node).name, outlet.slot), zero)
+        })
+        .collect()
+}
+
+/// Sum the gradient contributions to an outlet.
+fn accumulate(
+    model: &mut TypedModel,
+    contributions: &mut HashMap<OutletId, TVec<OutletId>>,
+    outlet: OutletId,
+) -> TractResult<Option<OutletId>> {
+    let Some(grads) = contributions.get(&outlet).cloned() else { return Ok(None) };
+    let mut sum = grads[0];
+    for (ix, grad) in grads.iter().enumerate().skip(1) {
+        let name = format!("{}.grad.{}.sum_{}", model.node(outlet.node).name, outlet.slot, ix);
+        sum = model.wire_node(name, math::add(), &[sum, *grad])?[0];
+    }
+    contributions.insert(outlet, tvec!(sum));
+    Ok(Some(sum))
+}
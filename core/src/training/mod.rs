@@ -0,0 +1,276 @@
+//! Training support: reverse-mode differentiation of typed models, and
+//! optimizer updates of their trainable constants.
+//!
+//! Everything is wired as regular ops in the model, so a training step can
+//! be serialized like any other model. Trainable parameters live in the
+//! session tensors (see `Load` and `Store`), so successive runs of the same
+//! state keep training them.
+use crate::internal::*;
+use crate::model::order::eval_order_for_nodes;
+use crate::ops::cnn::ConvUnary;
+use crate::ops::konst::Const;
+use crate::ops::memory::load::Load;
+use crate::ops::memory::store::Store;
+
+mod gradients;
+mod optimizer;
+mod vjp;
+
+pub use gradients::wire_gradients;
+pub use optimizer::{Adam, Optimizer, Sgd};
+
+/// Turn `model` into a training step minimizing the scalar `loss`.
+///
+/// Each trainable `Const` node is replaced by a session tensor named after
+/// the node, initialized with the constant value. Running the model updates
+/// these tensors with `optimizer` from the gradients of the loss, along with
+/// the optimizer state.
+///
+/// Only `Const` nodes can be trained. Convolution kernels are embedded in
+/// `ConvUnary` rather than wired from a `Const`, so they stay frozen: the
+/// convolution only propagates the gradient of its input.
+///
+/// Returns an outlet that carries the loss once the updates are stored: it
+/// should be one of the model outputs.
+pub fn wire_training_step(
+    model: &mut TypedModel,
+    loss: OutletId,
+    trainables: &[usize],
+    optimizer: &dyn Optimizer,
+) -> TractResult<OutletId> {
+    let mut params = tvec!();
+    for &id in trainables {
+        let node = model.node(id);
+        if node.op_is::<ConvUnary>() {
+            bail!(
+                "Trainable node {} is a convolution: its embedded kernel can not be trained",
+                node
+            );
+        }
+        ensure!(node.op_is::<Const>(), "Trainable node {} is not a Const", node);
+        let name = node.name.clone();
+        let konst = OutletId::new(id, 0);
+        let successors = model.outlet_successors(konst).to_vec();
+        let param = model.wire_node(format!("{name}.load"), Load::new(&name), &[konst])?[0];
+        for inlet in successors {
+            model.add_edge(param, inlet)?;
+        }
+        let outputs: TVec<OutletId> =
+            model.output_outlets()?.iter().map(|&o| if o == konst { param } else { o }).collect();
+        model.set_output_outlets(&outputs)?;
+        params.push((name, param));
+    }
+    refresh_facts(model, loss)?;
+
+    let wrt: TVec<OutletId> = params.iter().map(|(_, param)| *param).collect();
+    let grads = wire_gradients(model, loss, &wrt)?;
+    let mut wire = loss;
+    for ((name, param), grad) in params.iter().zip(grads) {
+        for (id, value) in optimizer.wire_update(model, name, *param, grad)? {
+            wire = model.wire_node(format!("{id}.store"), Store::new(&id), &[wire, value])?[0];
+        }
+    }
+    Ok(wire)
+}
+
+/// Recompute output facts once constants have been replaced by session
+/// tensors, so that no constant value is left in the facts downstream.
+fn refresh_facts(model: &mut TypedModel, loss: OutletId) -> TractResult<()> {
+    let inputs: Vec<usize> = model.input_outlets()?.iter().map(|o| o.node).collect();
+    let outputs: Vec<usize> =
+        model.output_outlets()?.iter().map(|o| o.node).chain(std::iter::once(loss.node)).collect();
+    for id in eval_order_for_nodes(model.nodes(), &inputs, &outputs, &[])? {
+        let input_facts: TVec<TypedFact> =
+            model.node_input_facts(id)?.into_iter().cloned().collect();
+        let input_facts: TVec<&TypedFact> = input_facts.iter().collect();
+        let facts = model.node(id).op.output_facts(&input_facts)?;
+        for (slot, fact) in facts.into_iter().enumerate() {
+            model.set_outlet_fact(OutletId::new(id, slot), fact)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::cnn::{KernelFormat, PaddingSpec, PoolSpec};
+    use crate::ops::einsum::EinSum;
+    use crate::ops::math;
+    use crate::ops::nn::{DataFormat, Reduce, Reducer, Softmax};
+
+    fn run(model: &TypedModel, inputs: &[Tensor]) -> TractResult<TVec<TValue>> {
+        let inputs = inputs.iter().map(|t| t.clone().into_tvalue()).collect();
+        model.clone().into_runnable()?.run(inputs)
+    }
+
+    /// Compare the wired gradients to finite differences, for all the model
+    /// inputs.
+    fn check_gradients(mut model: TypedModel, inputs: &[Tensor]) -> TractResult<()> {
+        let loss = model.output_outlets()?[0];
+        let wrt = model.input_outlets()?.to_vec();
+        let grads = wire_gradients(&mut model, loss, &wrt)?;
+        let mut grad_model = model.clone();
+        grad_model.set_output_outlets(&grads)?;
+        let grads = run(&grad_model, inputs)?;
+        let eps = 1e-3f32;
+        for (ix, grad) in grads.iter().enumerate() {
+            for i in 0..inputs[ix].len() {
+                let mut loss = [0f32; 2];
+                for (sign, loss) in [-1f32, 1.].iter().zip(&mut loss) {
+                    let mut inputs = inputs.to_vec();
+                    inputs[ix].as_slice_mut::<f32>()?[i] += sign * eps;
+                    *loss = run(&model, &inputs)?[0].as_slice::<f32>()?[0];
+                }
+                let expected = (loss[1] - loss[0]) / (2. * eps);
+                let found = grad.as_slice::<f32>()?[i];
+                ensure!(
+                    (expected - found).abs() < 1e-2 * (1. + expected.abs()),
+                    "input {} at {}: expected {} found {}",
+                    ix,
+                    i,
+                    expected,
+                    found
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn sum_all(model: &mut TypedModel, wire: OutletId) -> TractResult<OutletId> {
+        let rank = model.outlet_fact(wire)?.rank();
+        Ok(model.wire_node("loss", Reduce::new((0..rank).collect(), Reducer::Sum), &[wire])?[0])
+    }
+
+    fn tensor(shape: &[usize], seed: usize) -> Tensor {
+        let len = shape.iter().product::<usize>();
+        let values: Vec<f32> = (0..len).map(|i| ((i * 7 + seed) % 11) as f32 / 5. - 1.).collect();
+        Tensor::from_shape(shape, &values).unwrap()
+    }
+
+    #[test]
+    fn dense_tanh() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact([2, 3]))?;
+        let w = model.add_source("w", f32::fact([3, 4]))?;
+        let b = model.add_source("b", f32::fact([1, 4]))?;
+        let mm = EinSum::new("mk,kn->mn".parse()?, f32::datum_type());
+        let y = model.wire_node("mm", mm, &[x, w])?[0];
+        let y = model.wire_node("bias", math::add(), &[y, b])?[0];
+        let y = model.wire_node("tanh", math::tanh(), &[y])?[0];
+        let y = model.wire_node("square", math::mul(), &[y, y])?[0];
+        let loss = sum_all(&mut model, y)?;
+        model.set_output_outlets(&[loss])?;
+        check_gradients(model, &[tensor(&[2, 3], 0), tensor(&[3, 4], 1), tensor(&[1, 4], 2)])
+    }
+
+    #[test]
+    fn softmax_cross_entropy() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact([2, 5]))?;
+        let target = model.add_const("target", tensor(&[2, 5], 3))?;
+        let y = model.wire_node("softmax", Softmax::new(tvec!(1), f32::datum_type()), &[x])?[0];
+        let y = model.wire_node("ln", math::ln(), &[y])?[0];
+        let y = model.wire_node("mul", math::mul(), &[y, target])?[0];
+        let y = model.wire_node("transpose", AxisOp::Move(1, 0), &[y])?[0];
+        let loss = sum_all(&mut model, y)?;
+        model.set_output_outlets(&[loss])?;
+        check_gradients(model, &[tensor(&[2, 5], 4)])
+    }
+
+    #[test]
+    fn conv_input() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact([1, 2, 5, 4]))?;
+        let pool_spec = PoolSpec::new(
+            DataFormat::NCHW,
+            tvec!(3, 2),
+            PaddingSpec::SameUpper,
+            None,
+            Some(tvec!(2, 1)),
+            Some(3),
+        );
+        let kernel = tensor(&[3, 2, 3, 2], 5).into_arc_tensor();
+        let conv = ConvUnary::new(pool_spec, KernelFormat::OIHW, kernel, 1, None, None);
+        let y = model.wire_node("conv", conv, &[x])?[0];
+        let y = model.wire_node("square", math::square(), &[y])?[0];
+        let loss = sum_all(&mut model, y)?;
+        model.set_output_outlets(&[loss])?;
+        check_gradients(model, &[tensor(&[1, 2, 5, 4], 6)])
+    }
+
+    #[test]
+    fn conv_input_grouped_hwio() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact([1, 5, 4, 4]))?;
+        let pool_spec =
+            PoolSpec::new(DataFormat::NHWC, tvec!(3, 2), PaddingSpec::Valid, None, None, Some(6));
+        // HWIO grouped kernels are [H, W, I, O/group]
+        let kernel = tensor(&[3, 2, 4, 3], 7).into_arc_tensor();
+        let conv = ConvUnary::new(pool_spec, KernelFormat::HWIO, kernel, 2, None, None);
+        let y = model.wire_node("conv", conv, &[x])?[0];
+        let y = model.wire_node("square", math::square(), &[y])?[0];
+        let loss = sum_all(&mut model, y)?;
+        model.set_output_outlets(&[loss])?;
+        check_gradients(model, &[tensor(&[1, 5, 4, 4], 8)])
+    }
+
+    #[test]
+    fn conv_kernel_is_not_trainable() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact([1, 2, 3, 3]))?;
+        let pool_spec =
+            PoolSpec::new(DataFormat::NCHW, tvec!(2, 2), PaddingSpec::Valid, None, None, Some(1));
+        let kernel = tensor(&[1, 2, 2, 2], 9).into_arc_tensor();
+        let conv = ConvUnary::new(pool_spec, KernelFormat::OIHW, kernel, 1, None, None);
+        let y = model.wire_node("conv", conv, &[x])?[0];
+        let loss = sum_all(&mut model, y)?;
+        let err = wire_training_step(
+            &mut model,
+            loss,
+            &[y.node],
+            &Sgd { learning_rate: 0.1, momentum: 0. },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("convolution"));
+        Ok(())
+    }
+
+    fn regression(optimizer: &dyn Optimizer) -> TractResult<()> {
+        // fit w so that x.w = x.[1, -2]
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact([4, 2]))?;
+        let w = model.add_const("w", tensor1(&[0f32, 0.]))?;
+        let y =
+            model.wire_node("mv", EinSum::new("mk,k->m".parse()?, f32::datum_type()), &[x, w])?[0];
+        let target = model.add_const("target", tensor1(&[1f32, -2., -1., 3.]))?;
+        let diff = model.wire_node("diff", math::sub(), &[y, target])?[0];
+        let square = model.wire_node("square", math::square(), &[diff])?[0];
+        let loss = sum_all(&mut model, square)?;
+        let loss = wire_training_step(&mut model, loss, &[w.node], optimizer)?;
+        model.set_output_outlets(&[loss])?;
+        let model = model.into_decluttered()?;
+
+        let plan = SimplePlan::new(model)?;
+        let mut state = SimpleState::new(&plan)?;
+        let x = tensor2(&[[1f32, 0.], [0., 1.], [1., 1.], [-1., -2.]]);
+        let mut losses = vec![];
+        for _ in 0..200 {
+            losses.push(state.run(tvec!(x.clone().into_tvalue()))?[0].cast_to_scalar::<f32>()?);
+        }
+        assert!(losses[199] < 1e-3 * losses[0], "{losses:?}");
+        let w = &state.session_state.tensors["w"];
+        w.close_enough(&tensor1(&[1f32, -2.]), true)?;
+        Ok(())
+    }
+
+    #[test]
+    fn sgd_regression() -> TractResult<()> {
+        regression(&Sgd { learning_rate: 0.05, momentum: 0.5 })
+    }
+
+    #[test]
+    fn adam_regression() -> TractResult<()> {
+        regression(&Adam { learning_rate: 0.05, ..Adam::default() })
+    }
+}
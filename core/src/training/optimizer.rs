@@ -0,0 +1,232 @@
+use std::fmt;
+
+use crate::internal::*;
+use crate::ops::math;
+use crate::ops::memory::load::Load;
+use num_traits::Float;
+
+/// Wires parameter updates from their gradients.
+pub trait Optimizer: fmt::Debug {
+    /// Wire the update of the trainable parameter `name`, with its optimizer
+    /// state loaded from the session. Returns the (id, value) pairs to store
+    /// back in the session, the updated parameter first.
+    fn wire_update(
+        &self,
+        model: &mut TypedModel,
+        name: &str,
+        param: OutletId,
+        grad: OutletId,
+    ) -> TractResult<TVec<(String, OutletId)>>;
+}
+
+/// Load a session tensor, starting with zeros shaped like `like`.
+fn load_zeros(model: &mut TypedModel, id: &str, like: OutletId) -> TractResult<OutletId> {
+    let fact = model.outlet_fact(like)?;
+    let shape = fact.shape.as_concrete().context("Optimizer state expects concrete shapes")?;
+    let zeros = Tensor::zero_dt(fact.datum_type, shape)?;
+    let zeros = model.add_const(format!("{id}.init"), zeros)?;
+    Ok(model.wire_node(format!("{id}.load"), Load::new(id), &[zeros])?[0])
+}
+
+/// Stochastic gradient descent, with optional momentum.
+///
+/// Inputs are the parameter, its gradient and, with momentum, its velocity.
+/// Outputs are the updated parameter and, with momentum, the updated
+/// velocity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sgd {
+    pub learning_rate: f32,
+    pub momentum: f32,
+}
+
+impl Sgd {
+    fn eval_t<T: Datum + Float>(&self, inputs: &[TValue]) -> TractResult<TVec<TValue>> {
+        let lr = T::from(self.learning_rate).unwrap();
+        let momentum = T::from(self.momentum).unwrap();
+        let mut param = inputs[0].clone().into_tensor();
+        let grad = inputs[1].as_slice::<T>()?;
+        if self.momentum == 0.0 {
+            for (p, g) in param.as_slice_mut::<T>()?.iter_mut().zip(grad) {
+                *p = *p - lr * *g;
+            }
+            Ok(tvec!(param.into_tvalue()))
+        } else {
+            let mut velocity = inputs[2].clone().into_tensor();
+            for ((p, v), g) in
+                param.as_slice_mut::<T>()?.iter_mut().zip(velocity.as_slice_mut::<T>()?).zip(grad)
+            {
+                *v = momentum * *v + *g;
+                *p = *p - lr * *v;
+            }
+            Ok(tvec!(param.into_tvalue(), velocity.into_tvalue()))
+        }
+    }
+}
+
+impl Op for Sgd {
+    fn name(&self) -> Cow<str> {
+        "Sgd".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("learning_rate: {} momentum: {}", self.learning_rate, self.momentum)])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for Sgd {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        dispatch_floatlike!(Self::eval_t(inputs[0].datum_type())(self, &inputs))
+    }
+}
+
+impl TypedOp for Sgd {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let expected = if self.momentum == 0.0 { 2 } else { 3 };
+        ensure!(inputs.len() == expected, "Sgd expects {} inputs", expected);
+        ensure!(inputs[0].datum_type.is_float(), "Sgd expects float parameters");
+        ensure!(inputs.iter().all(|i| i.datum_type == inputs[0].datum_type));
+        ensure!(inputs.iter().all(|i| i.shape == inputs[0].shape));
+        Ok(inputs[1..].iter().map(|_| inputs[0].without_value()).collect())
+    }
+
+    as_op!();
+}
+
+impl Optimizer for Sgd {
+    fn wire_update(
+        &self,
+        model: &mut TypedModel,
+        name: &str,
+        param: OutletId,
+        grad: OutletId,
+    ) -> TractResult<TVec<(String, OutletId)>> {
+        let prefix = format!("{name}.sgd");
+        if self.momentum == 0.0 {
+            let param = model.wire_node(prefix, self.clone(), &[param, grad])?[0];
+            return Ok(tvec!((name.to_string(), param)));
+        }
+        let velocity_id = format!("{name}.velocity");
+        let velocity = load_zeros(model, &velocity_id, param)?;
+        let outputs = model.wire_node(prefix, self.clone(), &[param, grad, velocity])?;
+        Ok(tvec!((name.to_string(), outputs[0]), (velocity_id, outputs[1])))
+    }
+}
+
+/// Adam.
+///
+/// Inputs are the parameter, its gradient, the first and second moment
+/// estimates, and the 1-based step as an i64 scalar. Outputs are the
+/// updated parameter and moment estimates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Adam {
+    pub learning_rate: f32,
+    pub beta1: f32,
+    pub beta2: f32,
+    pub epsilon: f32,
+}
+
+impl Default for Adam {
+    fn default() -> Adam {
+        Adam { learning_rate: 0.001, beta1: 0.9, beta2: 0.999, epsilon: 1e-8 }
+    }
+}
+
+impl Adam {
+    fn eval_t<T: Datum + Float>(&self, inputs: &[TValue]) -> TractResult<TVec<TValue>> {
+        let step = inputs[4].cast_to_scalar::<i64>()?;
+        ensure!(step > 0, "Adam step is 1-based, got {}", step);
+        let t = |x: f32| T::from(x).unwrap();
+        let (b1, b2) = (t(self.beta1), t(self.beta2));
+        let correction1 = T::one() - b1.powi(step as i32);
+        let correction2 = T::one() - b2.powi(step as i32);
+        let mut param = inputs[0].clone().into_tensor();
+        let grad = inputs[1].as_slice::<T>()?;
+        let mut m = inputs[2].clone().into_tensor();
+        let mut v = inputs[3].clone().into_tensor();
+        for (((p, m), v), g) in param
+            .as_slice_mut::<T>()?
+            .iter_mut()
+            .zip(m.as_slice_mut::<T>()?)
+            .zip(v.as_slice_mut::<T>()?)
+            .zip(grad)
+        {
+            *m = b1 * *m + (T::one() - b1) * *g;
+            *v = b2 * *v + (T::one() - b2) * *g * *g;
+            let m_hat = *m / correction1;
+            let v_hat = *v / correction2;
+            *p = *p - t(self.learning_rate) * m_hat / (v_hat.sqrt() + t(self.epsilon));
+        }
+        Ok(tvec!(param.into_tvalue(), m.into_tvalue(), v.into_tvalue()))
+    }
+}
+
+impl Op for Adam {
+    fn name(&self) -> Cow<str> {
+        "Adam".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "learning_rate: {} beta1: {} beta2: {} epsilon: {}",
+            self.learning_rate, self.beta1, self.beta2, self.epsilon
+        )])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for Adam {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        dispatch_floatlike!(Self::eval_t(inputs[0].datum_type())(self, &inputs))
+    }
+}
+
+impl TypedOp for Adam {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs.len() == 5, "Adam expects 5 inputs");
+        ensure!(inputs[0].datum_type.is_float(), "Adam expects float parameters");
+        ensure!(inputs[..4].iter().all(|i| i.datum_type == inputs[0].datum_type));
+        ensure!(inputs[..4].iter().all(|i| i.shape == inputs[0].shape));
+        ensure!(inputs[4].datum_type == i64::datum_type() && inputs[4].rank() == 0);
+        Ok(tvec!(inputs[0].without_value(); 3))
+    }
+
+    as_op!();
+}
+
+impl Optimizer for Adam {
+    fn wire_update(
+        &self,
+        model: &mut TypedModel,
+        name: &str,
+        param: OutletId,
+        grad: OutletId,
+    ) -> TractResult<TVec<(String, OutletId)>> {
+        let (m_id, v_id, step_id) =
+            (format!("{name}.adam.m"), format!("{name}.adam.v"), format!("{name}.adam.step"));
+        let m = load_zeros(model, &m_id, param)?;
+        let v = load_zeros(model, &v_id, param)?;
+        let zero = model.add_const(format!("{step_id}.init"), tensor0(0i64))?;
+        let step = model.wire_node(format!("{step_id}.load"), Load::new(&step_id), &[zero])?[0];
+        let one = model.add_const(format!("{name}.adam.one"), tensor0(1i64))?;
+        let step = model.wire_node(format!("{name}.adam.next_step"), math::add(), &[step, one])?[0];
+        let outputs =
+            model.wire_node(format!("{name}.adam"), self.clone(), &[param, grad, m, v, step])?;
+        Ok(tvec!(
+            (name.to_string(), outputs[0]),
+            (m_id, outputs[1]),
+            (v_id, outputs[2]),
+            (step_id, step)
+        ))
+    }
+}
@@ -0,0 +1,392 @@
+//! Vector-Jacobian products: given the gradients of a node outputs, wire the
+//! gradients of its inputs.
+use crate::internal::*;
+use crate::ops::array::{MultiBroadcastTo, Pad, PadMode, Slice};
+use crate::ops::binary::{wire_with_rank_broadcast, TypedBinOp};
+use crate::ops::cast::{cast, Cast};
+use crate::ops::cnn::deconv::adjustments;
+use crate::ops::cnn::{ConvUnary, DeconvUnary, KernelFormat, PaddingSpec, PoolSpec};
+use crate::ops::einsum::EinSum;
+use crate::ops::element_wise::ElementWiseOp;
+use crate::ops::identity::Identity;
+use crate::ops::logic::equals;
+use crate::ops::math;
+use crate::ops::nn::{Dropout, Reduce, Reducer, Sigmoid, Softmax};
+use tract_data::itertools::Itertools;
+
+pub(super) fn wire_vjp(
+    model: &mut TypedModel,
+    node: &TypedNode,
+    grads: &[Option<OutletId>],
+    wanted: &[bool],
+) -> TractResult<TVec<Option<OutletId>>> {
+    let prefix = format!("{}.grad", node.name);
+    let Some(g) = grads[0] else { return Ok(tvec!(None; node.inputs.len())) };
+    let grads: TVec<Option<OutletId>> = if node.op_is::<Identity>() {
+        tvec!(Some(g))
+    } else if let Some(op) = node.op_as::<TypedBinOp>() {
+        binary(model, &prefix, node, op, g)?
+    } else if let Some(op) = node.op_as::<ElementWiseOp>() {
+        tvec!(Some(element_wise(model, &prefix, node, op, g)?))
+    } else if let Some(op) = node.op_as::<Cast>() {
+        let dt = model.outlet_fact(node.inputs[0])?.datum_type;
+        ensure!(dt.is_float(), "Can not differentiate a cast from {:?} to {:?}", dt, op.to);
+        tvec!(Some(unary(model, &prefix, cast(dt), g)?))
+    } else if let Some(op) = node.op_as::<AxisOp>() {
+        tvec!(Some(unary(model, &prefix, op.recip(), g)?))
+    } else if node.op_is::<MultiBroadcastTo>() {
+        tvec!(Some(unbroadcast(model, &prefix, g, node.inputs[0])?))
+    } else if let Some(op) = node.op_as::<Slice>() {
+        tvec!(Some(slice(model, &prefix, node, op, g)?))
+    } else if let Some(op) = node.op_as::<Reduce>() {
+        tvec!(Some(reduce(model, &prefix, node, op, g)?))
+    } else if let Some(op) = node.op_as::<Softmax>() {
+        tvec!(Some(softmax(model, &prefix, node, op, g)?))
+    } else if let Some(op) = node.op_as::<EinSum>() {
+        einsum(model, &prefix, node, op, g, wanted)?
+    } else if let Some(op) = node.op_as::<ConvUnary>() {
+        tvec!(Some(conv(model, &prefix, node, op, g)?))
+    } else if let Some(op) = node.op_as::<Dropout>() {
+        tvec!(Some(dropout(model, &prefix, node, op, g)?))
+    } else {
+        bail!("No gradient rule for {}", node.op.name())
+    };
+    Ok(grads)
+}
+
+fn bin(
+    model: &mut TypedModel,
+    name: &str,
+    op: TypedBinOp,
+    a: OutletId,
+    b: OutletId,
+) -> TractResult<OutletId> {
+    Ok(wire_with_rank_broadcast(name, model, op, &[a, b])?[0])
+}
+
+fn unary(
+    model: &mut TypedModel,
+    name: &str,
+    op: impl Into<Box<dyn TypedOp>>,
+    a: OutletId,
+) -> TractResult<OutletId> {
+    Ok(model.wire_node(name, op.into(), &[a])?[0])
+}
+
+fn scalar(model: &mut TypedModel, name: &str, dt: DatumType, value: f64) -> TractResult<OutletId> {
+    model.add_const(name, tensor0(value).cast_to_dt(dt)?.into_owned())
+}
+
+/// Sum the gradient over the axes its input was broadcast along.
+pub(super) fn unbroadcast(
+    model: &mut TypedModel,
+    name: &str,
+    grad: OutletId,
+    input: OutletId,
+) -> TractResult<OutletId> {
+    let input_fact = model.outlet_fact(input)?.clone();
+    let grad_fact = model.outlet_fact(grad)?.clone();
+    ensure!(input_fact.rank() == grad_fact.rank());
+    let axes: TVec<usize> = (0..input_fact.rank())
+        .filter(|&ax| input_fact.shape[ax].is_one() && !grad_fact.shape[ax].is_one())
+        .collect();
+    let mut wire = grad;
+    if axes.len() > 0 {
+        wire = unary(model, &format!("{name}.unbroadcast"), Reduce::new(axes, Reducer::Sum), wire)?;
+    }
+    if grad_fact.datum_type != input_fact.datum_type {
+        wire = unary(model, &format!("{name}.cast"), cast(input_fact.datum_type), wire)?;
+    }
+    Ok(wire)
+}
+
+fn binary(
+    model: &mut TypedModel,
+    prefix: &str,
+    node: &TypedNode,
+    op: &TypedBinOp,
+    g: OutletId,
+) -> TractResult<TVec<Option<OutletId>>> {
+    let (a, b, y) = (node.inputs[0], node.inputs[1], OutletId::new(node.id, 0));
+    let dt = model.outlet_fact(y)?.datum_type;
+    ensure!(dt.is_float(), "Can not differentiate {} on {:?}", node.op.name(), dt);
+    let (ga, gb) = if op.0.is::<math::Add>() {
+        (g, g)
+    } else if op.0.is::<math::Sub>() {
+        (g, unary(model, &format!("{prefix}.b.neg"), math::neg(), g)?)
+    } else if op.0.is::<math::Mul>() {
+        let ga = bin(model, &format!("{prefix}.a.mul"), math::mul(), g, b)?;
+        let gb = bin(model, &format!("{prefix}.b.mul"), math::mul(), g, a)?;
+        (ga, gb)
+    } else if op.0.is::<math::Div>() {
+        // d(a/b)/db = -a/b² = -y/b
+        let ga = bin(model, &format!("{prefix}.a.div"), math::div(), g, b)?;
+        let gb = bin(model, &format!("{prefix}.b.mul"), math::mul(), ga, y)?;
+        (ga, unary(model, &format!("{prefix}.b.neg"), math::neg(), gb)?)
+    } else if op.0.is::<math::Max>() || op.0.is::<math::Min>() {
+        // the gradient goes to a where it was selected, to b elsewhere
+        let selected = bin(model, &format!("{prefix}.a.selected"), equals(), a, y)?;
+        let mask = unary(model, &format!("{prefix}.a.mask"), cast(dt), selected)?;
+        let ga = bin(model, &format!("{prefix}.a.mul"), math::mul(), g, mask)?;
+        let gb = bin(model, &format!("{prefix}.b.sub"), math::sub(), g, ga)?;
+        (ga, gb)
+    } else if op.0.is::<math::Pow>() {
+        // d(a^b)/da = b.a^(b-1), d(a^b)/db = ln(a).a^b
+        let one = scalar(model, &format!("{prefix}.one"), dt, 1.0)?;
+        let bm1 = bin(model, &format!("{prefix}.a.exponent"), math::sub(), b, one)?;
+        let pow = bin(model, &format!("{prefix}.a.pow"), math::pow(), a, bm1)?;
+        let da = bin(model, &format!("{prefix}.a.derivative"), math::mul(), b, pow)?;
+        let ga = bin(model, &format!("{prefix}.a.mul"), math::mul(), g, da)?;
+        let ln = unary(model, &format!("{prefix}.b.ln"), math::ln(), a)?;
+        let db = bin(model, &format!("{prefix}.b.derivative"), math::mul(), ln, y)?;
+        let gb = bin(model, &format!("{prefix}.b.mul"), math::mul(), g, db)?;
+        (ga, gb)
+    } else {
+        bail!("No gradient rule for {}", node.op.name())
+    };
+    Ok(tvec!(
+        Some(unbroadcast(model, &format!("{prefix}.a"), ga, a)?),
+        Some(unbroadcast(model, &format!("{prefix}.b"), gb, b)?)
+    ))
+}
+
+fn element_wise(
+    model: &mut TypedModel,
+    prefix: &str,
+    node: &TypedNode,
+    op: &ElementWiseOp,
+    g: OutletId,
+) -> TractResult<OutletId> {
+    let (x, y) = (node.inputs[0], OutletId::new(node.id, 0));
+    let dt = model.outlet_fact(y)?.datum_type;
+    ensure!(dt.is_float(), "Can not differentiate {} on {:?}", node.op.name(), dt);
+    let mini = &op.0;
+    let name = |s: &str| format!("{prefix}.{s}");
+    // derivative of the function, to be multiplied by g
+    let derivative = if mini.is::<math::Neg>() {
+        return unary(model, prefix, math::neg(), g);
+    } else if mini.is::<math::Exp>() {
+        y
+    } else if mini.is::<math::Ln>() {
+        unary(model, &name("recip"), math::recip(), x)?
+    } else if mini.is::<math::Square>() {
+        let two = scalar(model, &name("two"), dt, 2.0)?;
+        bin(model, &name("double"), math::mul(), two, x)?
+    } else if mini.is::<math::Sqrt>() {
+        let half = scalar(model, &name("half"), dt, 0.5)?;
+        bin(model, &name("div"), math::div(), half, y)?
+    } else if mini.is::<math::Rsqrt>() {
+        let factor = scalar(model, &name("factor"), dt, -0.5)?;
+        let cube = unary(model, &name("cube"), math::cube(), y)?;
+        bin(model, &name("scale"), math::mul(), factor, cube)?
+    } else if mini.is::<math::Recip>() {
+        let square = unary(model, &name("square"), math::square(), y)?;
+        unary(model, &name("neg"), math::neg(), square)?
+    } else if mini.is::<math::Tanh>() {
+        let one = scalar(model, &name("one"), dt, 1.0)?;
+        let square = unary(model, &name("square"), math::square(), y)?;
+        bin(model, &name("sub"), math::sub(), one, square)?
+    } else if mini.is::<Sigmoid>() {
+        let one = scalar(model, &name("one"), dt, 1.0)?;
+        let complement = bin(model, &name("sub"), math::sub(), one, y)?;
+        bin(model, &name("mul"), math::mul(), y, complement)?
+    } else if mini.is::<math::Abs>() {
+        unary(model, &name("sign"), math::sign(), x)?
+    } else if mini.is::<math::Sin>() {
+        unary(model, &name("cos"), math::cos(), x)?
+    } else if mini.is::<math::Cos>() {
+        let sin = unary(model, &name("sin"), math::sin(), x)?;
+        unary(model, &name("neg"), math::neg(), sin)?
+    } else if mini.is::<math::Erf>() {
+        // 2/√π.exp(-x²)
+        let square = unary(model, &name("square"), math::square(), x)?;
+        let neg = unary(model, &name("neg"), math::neg(), square)?;
+        let exp = unary(model, &name("exp"), math::exp(), neg)?;
+        let factor = scalar(model, &name("factor"), dt, std::f64::consts::FRAC_2_SQRT_PI)?;
+        bin(model, &name("scale"), math::mul(), factor, exp)?
+    } else {
+        bail!("No gradient rule for {}", node.op.name())
+    };
+    bin(model, &name("mul"), math::mul(), g, derivative)
+}
+
+fn slice(
+    model: &mut TypedModel,
+    prefix: &str,
+    node: &TypedNode,
+    op: &Slice,
+    g: OutletId,
+) -> TractResult<OutletId> {
+    let fact = model.outlet_fact(node.inputs[0])?.clone();
+    let start = op.start.to_usize()?;
+    let end = op.end.to_usize()?;
+    let dim = fact.shape[op.axis].to_usize()?;
+    let mut pads = vec![(0, 0); fact.rank()];
+    pads[op.axis] = (start, dim - end);
+    let zero = Tensor::zero_scalar_dt(fact.datum_type)?.into_arc_tensor();
+    unary(model, prefix, Pad { pads, mode: PadMode::Constant(zero) }, g)
+}
+
+fn reduce(
+    model: &mut TypedModel,
+    prefix: &str,
+    node: &TypedNode,
+    op: &Reduce,
+    g: OutletId,
+) -> TractResult<OutletId> {
+    let (x, y) = (node.inputs[0], OutletId::new(node.id, 0));
+    let fact = model.outlet_fact(x)?.clone();
+    ensure!(fact.datum_type.is_float(), "Can not differentiate {} on {:?}", node.op.name(), fact);
+    let broadcast = MultiBroadcastTo::new(fact.shape.clone());
+    match op.reducer {
+        Reducer::Sum => unary(model, prefix, broadcast, g),
+        Reducer::Prod => {
+            // dy/dx = y/x
+            let ratio = bin(model, &format!("{prefix}.ratio"), math::div(), y, x)?;
+            bin(model, &format!("{prefix}.mul"), math::mul(), g, ratio)
+        }
+        Reducer::Max | Reducer::Min => {
+            let selected = bin(model, &format!("{prefix}.selected"), equals(), x, y)?;
+            let mask = unary(model, &format!("{prefix}.mask"), cast(fact.datum_type), selected)?;
+            bin(model, &format!("{prefix}.mul"), math::mul(), g, mask)
+        }
+        _ => bail!("Can not differentiate {}", node.op.name()),
+    }
+}
+
+fn softmax(
+    model: &mut TypedModel,
+    prefix: &str,
+    node: &TypedNode,
+    op: &Softmax,
+    g: OutletId,
+) -> TractResult<OutletId> {
+    ensure!(op.output_dt.is_float(), "Can not differentiate quantized Softmax");
+    // dx = y.(g - Σ g.y)
+    let y = OutletId::new(node.id, 0);
+    let gy = bin(model, &format!("{prefix}.gy"), math::mul(), g, y)?;
+    let sum =
+        unary(model, &format!("{prefix}.sum"), Reduce::new(op.axes.clone(), Reducer::Sum), gy)?;
+    let ysum = bin(model, &format!("{prefix}.ysum"), math::mul(), y, sum)?;
+    bin(model, prefix, math::sub(), gy, ysum)
+}
+
+fn einsum(
+    model: &mut TypedModel,
+    prefix: &str,
+    node: &TypedNode,
+    op: &EinSum,
+    g: OutletId,
+    wanted: &[bool],
+) -> TractResult<TVec<Option<OutletId>>> {
+    ensure!(op.q_params.is_none(), "Can not differentiate quantized EinSum");
+    let (inputs, outputs) = op.axes.to_strs();
+    let mut grads = tvec!();
+    for (ix, input) in inputs.iter().enumerate() {
+        if !wanted[ix] {
+            grads.push(None);
+            continue;
+        }
+        ensure!(
+            input.chars().all_unique(),
+            "Can not differentiate EinSum {} with a repeated axis",
+            op.axes
+        );
+        // the gradient of an input is the einsum of the output gradient with
+        // the other inputs
+        let mut exprs = vec![outputs[0].clone()];
+        let mut wires = tvec!(g);
+        for (other, expr) in inputs.iter().enumerate().filter(|(other, _)| *other != ix) {
+            exprs.push(expr.clone());
+            wires.push(node.inputs[other]);
+        }
+        // axes only found in this input get summed over, and are broadcast back
+        let (kept, orphans): (String, Vec<(usize, char)>) = {
+            let (kept, orphans): (Vec<_>, Vec<_>) =
+                input.chars().enumerate().partition(|(_, c)| exprs.iter().any(|e| e.contains(*c)));
+            (kept.into_iter().map(|(_, c)| c).collect(), orphans)
+        };
+        let axes = AxesMapping::from_strs(&exprs[..], &[kept])?;
+        let name = format!("{prefix}.{ix}");
+        let mut wire = model.wire_node(&name, EinSum::new(axes, op.operating_dt), &wires)?[0];
+        for (pos, _) in &orphans {
+            wire = unary(model, &format!("{name}.add_axis_{pos}"), AxisOp::Add(*pos), wire)?;
+        }
+        wire = unbroadcast(model, &name, wire, node.inputs[ix])?;
+        if orphans.len() > 0 {
+            let shape = model.outlet_fact(node.inputs[ix])?.shape.clone();
+            wire = unary(model, &format!("{name}.broadcast"), MultiBroadcastTo::new(shape), wire)?;
+        }
+        grads.push(Some(wire));
+    }
+    Ok(grads)
+}
+
+fn conv(
+    model: &mut TypedModel,
+    prefix: &str,
+    node: &TypedNode,
+    op: &ConvUnary,
+    g: OutletId,
+) -> TractResult<OutletId> {
+    ensure!(op.q_params.is_none(), "Can not differentiate quantized convolution");
+    // the gradient of the input is the transposed convolution of the output
+    // gradient with the same kernel. The kernel is embedded in the op, not
+    // an input, so there is no kernel gradient to compute.
+    let fmt = op.kernel_fmt;
+    let mut shape: TVec<usize> = tvec!(
+        fmt.output_channels(op.kernel.shape(), op.group),
+        fmt.input_channels(op.kernel.shape(), op.group) / op.group
+    );
+    shape.extend(fmt.spatial_shape(op.kernel.shape()).iter().copied());
+    let kernel = op.kernel_as_group_o_ihw()?.into_tensor().into_shape(&shape)?;
+    // tract deconvolution kernels are [O/group, I, H, W]
+    let mut axes: TVec<usize> = (0..kernel.rank()).collect();
+    axes.swap(0, 1);
+    let kernel = kernel.permute_axes(&axes)?;
+
+    let x_fact = model.outlet_fact(node.inputs[0])?.clone();
+    let g_fact = model.outlet_fact(g)?.clone();
+    let x_shape = x_fact.shape.as_concrete().context("Conv gradient expects concrete shapes")?;
+    let g_shape = g_fact.shape.as_concrete().context("Conv gradient expects concrete shapes")?;
+    let x_shape = op.pool_spec.data_format.shape(x_shape)?;
+    let g_shape = op.pool_spec.data_format.shape(g_shape)?;
+    let padding = op.pool_spec.computed_padding(x_shape.hw_dims());
+    let pool_spec = PoolSpec {
+        padding: PaddingSpec::Explicit(
+            padding.iter().map(|p| p.pad_before).collect(),
+            padding.iter().map(|p| p.pad_after).collect(),
+        ),
+        output_channel_override: Some(*x_shape.c()),
+        ..op.pool_spec.clone()
+    };
+    let adjustments = adjustments(&pool_spec, g_shape.hw_dims(), x_shape.hw_dims())?;
+    let deconv = DeconvUnary::new(
+        pool_spec,
+        KernelFormat::OIHW,
+        kernel.into_arc_tensor(),
+        None,
+        adjustments,
+        op.group,
+    );
+    unary(model, prefix, deconv, g)
+}
+
+fn dropout(
+    model: &mut TypedModel,
+    prefix: &str,
+    node: &TypedNode,
+    op: &Dropout,
+    g: OutletId,
+) -> TractResult<OutletId> {
+    if !op.training {
+        return Ok(g);
+    }
+    ensure!(op.output_mask, "Differentiating training-mode Dropout needs its mask output");
+    let dt = model.outlet_fact(g)?.datum_type;
+    let mask = unary(model, &format!("{prefix}.mask"), cast(dt), OutletId::new(node.id, 1))?;
+    let scale = if op.ratio < 1.0 { 1.0 / (1.0 - op.ratio as f64) } else { 0.0 };
+    let scale = scalar(model, &format!("{prefix}.scale"), dt, scale)?;
+    let mask = bin(model, &format!("{prefix}.scaled_mask"), math::mul(), mask, scale)?;
+    bin(model, prefix, math::mul(), g, mask)
+}
@@ -19,7 +19,7 @@ pub mod prelude {
     pub use crate::datum::{round_ties_to_even, Blob, Datum, DatumType, QParams};
     pub use crate::dim::{Symbol, SymbolTable, SymbolValues, TDim, ToDim};
     pub use crate::tensor::litteral::*;
-    pub use crate::tensor::{ natural_strides, IntoArcTensor, IntoTensor, Tensor };
+    pub use crate::tensor::{ natural_strides, IntoArcTensor, IntoTensor, Tensor, TensorStorage };
     #[cfg(feature = "complex")]
     pub use crate::tensor::{ reinterpret_complex_as_inner_dim, reinterpret_inner_dim_as_complex, };
     pub use crate::tvec;
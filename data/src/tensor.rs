@@ -50,8 +50,19 @@ impl Approximation {
     }
 }
 
+/// Read-only bytes a tensor can borrow its data from, like a memory mapped
+/// file.
+pub trait TensorStorage: fmt::Debug + Send + Sync + 'static {
+    fn as_bytes(&self) -> &[u8];
+}
+
+impl<T: AsRef<[u8]> + fmt::Debug + Send + Sync + 'static> TensorStorage for T {
+    fn as_bytes(&self) -> &[u8] {
+        self.as_ref()
+    }
+}
+
 /// Tensor is a concrete tensor in tract.
-#[derive(Eq)]
 pub struct Tensor {
     dt: DatumType,
     shape: TVec<usize>,
@@ -59,11 +70,14 @@ pub struct Tensor {
     len: usize,
     layout: alloc::Layout,
     data: *mut u8,
+    storage: Option<Arc<dyn TensorStorage>>,
 }
 
 unsafe impl Send for Tensor {}
 unsafe impl Sync for Tensor {}
 
+impl Eq for Tensor {}
+
 impl Hash for Tensor {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         use DatumType::*;
@@ -109,7 +123,17 @@ impl Hash for Tensor {
 
 impl Clone for Tensor {
     fn clone(&self) -> Tensor {
-        self.deep_clone()
+        if self.storage.is_some() {
+            // read-only data can be shared
+            Tensor {
+                shape: self.shape.clone(),
+                strides: self.strides.clone(),
+                storage: self.storage.clone(),
+                ..*self
+            }
+        } else {
+            self.deep_clone()
+        }
     }
 }
 
@@ -145,7 +169,7 @@ impl Drop for Tensor {
                     .for_each(|s| std::ptr::drop_in_place(s as *mut TDim));
             }
         }
-        if !self.data.is_null() && self.layout.size() > 0 {
+        if self.storage.is_none() && !self.data.is_null() && self.layout.size() > 0 {
             unsafe { alloc::dealloc(self.data, self.layout) }
         }
     }
@@ -193,7 +217,15 @@ impl Tensor {
             assert!(!ptr.is_null());
             ptr
         } as *mut u8;
-        let mut tensor = Tensor { strides: tvec!(), layout, dt, shape: shape.into(), data, len: 0 };
+        let mut tensor = Tensor {
+            strides: tvec!(),
+            layout,
+            dt,
+            shape: shape.into(),
+            data,
+            len: 0,
+            storage: None,
+        };
         tensor.update_strides_and_len();
         #[cfg(debug_assertions)]
         if !data.is_null() {
@@ -207,6 +239,53 @@ impl Tensor {
         Ok(tensor)
     }
 
+    /// Create a tensor borrowing its data from a read-only storage, starting at
+    /// `offset` bytes.
+    ///
+    /// No copy is made: the storage is kept alive by the tensor and its
+    /// clones, and is copied only if mutable access to the data is requested.
+    /// Falls back to a copy if the data is not properly aligned for `dt`.
+    pub unsafe fn from_storage_dt(
+        dt: DatumType,
+        shape: &[usize],
+        storage: Arc<dyn TensorStorage>,
+        offset: usize,
+    ) -> anyhow::Result<Tensor> {
+        ensure!(dt.is_copy(), "Can not borrow {:?} tensor data from a storage", dt);
+        let bytes = shape.iter().product::<usize>() * dt.size_of();
+        let content = storage.as_bytes().get(offset..offset + bytes).with_context(|| {
+            format!("Storage too short for {:?} tensor of shape {:?}", dt, shape)
+        })?;
+        if bytes == 0 || content.as_ptr() as usize % dt.alignment() != 0 {
+            return Tensor::from_raw_dt(dt, shape, content);
+        }
+        let layout = alloc::Layout::from_size_align(bytes, dt.alignment())?;
+        let data = content.as_ptr() as *mut u8;
+        let mut tensor = Tensor {
+            strides: tvec!(),
+            layout,
+            dt,
+            shape: shape.into(),
+            data,
+            len: 0,
+            storage: Some(storage),
+        };
+        tensor.update_strides_and_len();
+        Ok(tensor)
+    }
+
+    /// Is the tensor data borrowed from a read-only storage ?
+    pub fn is_borrowed(&self) -> bool {
+        self.storage.is_some()
+    }
+
+    /// Copy borrowed data to an owned buffer, before it gets modified.
+    fn make_owned(&mut self) {
+        if self.storage.is_some() {
+            *self = self.deep_clone();
+        }
+    }
+
     pub fn stack_tensors(
         axis: usize,
         tensors: &[impl std::borrow::Borrow<Tensor>],
@@ -686,6 +765,7 @@ impl Tensor {
         axis: usize,
     ) {
         use ndarray::Slice;
+        self.make_owned();
         unsafe fn assign_slice_t<T: Datum>(
             to: &mut Tensor,
             to_range: Range<usize>,
@@ -844,6 +924,7 @@ impl Tensor {
 
     /// Transform the data as a mutable `ndarray::Array`.
     pub unsafe fn to_array_view_mut_unchecked<D: Datum>(&mut self) -> ArrayViewMutD<D> {
+        self.make_owned();
         if self.len() != 0 {
             ArrayViewMutD::from_shape_ptr(&*self.shape, self.data as *mut D)
         } else {
@@ -864,12 +945,14 @@ impl Tensor {
 
     /// Access the data as a pointer.
     pub unsafe fn as_ptr_mut_unchecked<D: Datum>(&mut self) -> *mut D {
+        self.make_owned();
         self.data as *mut D
     }
 
     /// Access the data as a mutable pointer.
     pub fn as_ptr_mut<D: Datum>(&mut self) -> anyhow::Result<*mut D> {
-        self.as_ptr::<D>().map(|p| p as *mut D)
+        self.check_for_access::<D>()?;
+        unsafe { Ok(self.as_ptr_mut_unchecked()) }
     }
 
     /// Access the data as a slice.
@@ -903,6 +986,7 @@ impl Tensor {
 
     /// Access the data as a mutable slice.
     pub unsafe fn as_slice_mut_unchecked<D: Datum>(&mut self) -> &mut [D] {
+        self.make_owned();
         if self.data.is_null() {
             &mut []
         } else {
@@ -943,6 +1027,7 @@ impl Tensor {
 
     /// Mutable access the data as a scalar.
     pub unsafe fn to_scalar_mut_unchecked<D: Datum>(&mut self) -> &mut D {
+        self.make_owned();
         &mut *(self.data as *mut D)
     }
 
@@ -955,6 +1040,7 @@ impl Tensor {
     }
 
     pub unsafe fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self.make_owned();
         if self.data.is_null() {
             &mut []
         } else {
@@ -1281,8 +1367,15 @@ impl Tensor {
             let shape = it.shape().into();
             let vec = it.into_raw_vec().into_boxed_slice();
             let data = Box::into_raw(vec) as *mut u8;
-            let mut t =
-                Tensor { dt: T::datum_type(), shape, layout, data, strides: tvec!(), len: 0 };
+            let mut t = Tensor {
+                dt: T::datum_type(),
+                shape,
+                layout,
+                data,
+                strides: tvec!(),
+                len: 0,
+                storage: None,
+            };
             t.update_strides_and_len();
             return t;
         }
@@ -1326,6 +1419,7 @@ impl Tensor {
                 data: data as *mut u8,
                 shape: self.shape.clone(),
                 strides: self.strides.clone(),
+                storage: None,
                 ..*self
             }
         } else if self.dt == DatumType::TDim {
@@ -1336,6 +1430,7 @@ impl Tensor {
                 data: data as *mut u8,
                 shape: self.shape.clone(),
                 strides: self.strides.clone(),
+                storage: None,
                 ..*self
             }
         } else {
@@ -1392,16 +1487,19 @@ impl Tensor {
 
     #[inline]
     pub fn view_mut(&mut self) -> view::TensorView {
+        self.make_owned();
         unsafe { view::TensorView::at_prefix_unchecked(self, &[]) }
     }
 
     #[inline]
     pub fn view_at_prefix_mut(&mut self, prefix: &[usize]) -> anyhow::Result<view::TensorView> {
+        self.make_owned();
         view::TensorView::at_prefix(self, prefix)
     }
 
     #[inline]
     pub fn view_offsetting_mut(&mut self, coords: &[usize]) -> anyhow::Result<view::TensorView> {
+        self.make_owned();
         view::TensorView::offsetting(self, coords)
     }
 
@@ -1675,6 +1773,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn borrowed_storage() -> anyhow::Result<()> {
+        let storage: Arc<dyn TensorStorage> = Arc::new(vec![0u8, 1, 2, 3, 4, 5, 6]);
+        let t = unsafe { Tensor::from_storage_dt(DatumType::U8, &[2, 3], storage.clone(), 1)? };
+        assert!(t.is_borrowed());
+        assert_eq!(t, crate::internal::tensor2(&[[1u8, 2, 3], [4, 5, 6]]));
+        let mut cloned = t.clone();
+        assert!(cloned.is_borrowed());
+        assert_eq!(t.as_ptr::<u8>()?, cloned.as_ptr::<u8>()?);
+        cloned.as_slice_mut::<u8>()?[0] = 42;
+        assert!(!cloned.is_borrowed());
+        assert_eq!(storage.as_bytes()[1], 1);
+        assert_eq!(t.as_slice::<u8>()?[0], 1);
+        Ok(())
+    }
+
+    #[test]
+    fn borrowed_storage_misaligned() -> anyhow::Result<()> {
+        let bytes: Vec<u8> = [0u8].into_iter().chain(2f32.to_le_bytes()).collect();
+        let t = unsafe { Tensor::from_storage_dt(DatumType::F32, &[], Arc::new(bytes), 1)? };
+        assert!(!t.is_borrowed());
+        assert_eq!(t, crate::internal::tensor0(2f32));
+        Ok(())
+    }
+
     #[test]
     #[cfg(feature = "complex")]
     fn test_reinterpret_inner_dim_as_complex() -> anyhow::Result<()> {
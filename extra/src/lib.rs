@@ -1,6 +1,8 @@
 use tract_nnef::internal::*;
+use tract_nnef_resources::internal::JsonLoader;
 
 mod exp_unit_norm;
+pub mod tokenizer;
 
 pub trait WithTractExtra {
     fn enable_tract_extra(&mut self);
@@ -11,6 +13,10 @@ impl WithTractExtra for tract_nnef::framework::Nnef {
     fn enable_tract_extra(&mut self) {
         self.enable_tract_core();
         self.registries.push(tract_extra_registry());
+        // tokenizer vocabularies are JSON resources
+        if !self.resource_loaders.iter().any(|l| l.name() == JsonLoader.name()) {
+            self.resource_loaders.push(JsonLoader.into_boxed());
+        }
     }
 
     fn with_tract_extra(mut self) -> Self {
@@ -22,6 +28,7 @@ impl WithTractExtra for tract_nnef::framework::Nnef {
 pub fn tract_extra_registry() -> Registry {
     let mut reg = Registry::new("tract_extra");
     exp_unit_norm::register(&mut reg);
+    tokenizer::register(&mut reg);
     reg
 }
 
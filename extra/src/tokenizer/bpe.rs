@@ -0,0 +1,164 @@
+use super::json::*;
+use super::model::{byte_fallback, vocab_field};
+use tract_nnef::internal::*;
+
+/// Byte-pair encoding: the characters of a word are merged pairwise, lowest
+/// merge rank first, as long as a merge applies.
+#[derive(Clone, Debug)]
+pub struct Bpe {
+    pub(super) vocab: HashMap<String, i64>,
+    pub(super) tokens: HashMap<i64, String>,
+    /// Pairs of token ids to merge, with their rank and the merged token id.
+    merges: HashMap<(i64, i64), (usize, i64)>,
+    unk_id: Option<i64>,
+    continuing_subword_prefix: Option<String>,
+    end_of_word_suffix: Option<String>,
+    fuse_unk: bool,
+    byte_fallback: bool,
+}
+
+impl Bpe {
+    pub fn from_json(json: &Json) -> TractResult<Bpe> {
+        let (vocab, tokens) = vocab_field(json, "vocab")?;
+        let continuing_subword_prefix =
+            opt_str_field(json, "continuing_subword_prefix")?.map(String::from);
+        let prefix = continuing_subword_prefix.as_deref().unwrap_or("");
+        let mut merges = HashMap::new();
+        for (rank, merge) in array_field(json, "merges")?.iter().enumerate() {
+            // "a b" in older files, ["a", "b"] in newer ones
+            let pair = if let Some(merge) = merge.as_str() {
+                merge.split_once(' ')
+            } else {
+                merge.as_array().and_then(|pair| match pair.as_slice() {
+                    [a, b] => a.as_str().zip(b.as_str()),
+                    _ => None,
+                })
+            };
+            let (a, b) = pair.with_context(|| format!("Invalid merge {merge}"))?;
+            let merged = format!("{}{}", a, b.strip_prefix(prefix).unwrap_or(b));
+            let id = |token: &str| {
+                vocab
+                    .get(token)
+                    .copied()
+                    .with_context(|| format!("Token {token:?} of merge {merge} not in vocabulary"))
+            };
+            merges.insert((id(a)?, id(b)?), (rank, id(&merged)?));
+        }
+        let unk_id = opt_str_field(json, "unk_token")?.and_then(|unk| vocab.get(unk).copied());
+        Ok(Bpe {
+            end_of_word_suffix: opt_str_field(json, "end_of_word_suffix")?.map(String::from),
+            fuse_unk: bool_field(json, "fuse_unk", false)?,
+            byte_fallback: bool_field(json, "byte_fallback", false)?,
+            vocab,
+            tokens,
+            merges,
+            unk_id,
+            continuing_subword_prefix,
+        })
+    }
+
+    pub fn tokenize(&self, word: &str, ids: &mut Vec<i64>) -> TractResult<()> {
+        let chars: Vec<&str> =
+            word.char_indices().map(|(ix, c)| &word[ix..][..c.len_utf8()]).collect();
+        // symbols are token ids, or the character they failed to map
+        let mut symbols: Vec<Result<i64, &str>> = chars
+            .iter()
+            .enumerate()
+            .map(|(ix, c)| {
+                let mut token = String::new();
+                if let (true, Some(prefix)) = (ix > 0, &self.continuing_subword_prefix) {
+                    token.push_str(prefix);
+                }
+                token.push_str(c);
+                if let (true, Some(suffix)) = (ix == chars.len() - 1, &self.end_of_word_suffix) {
+                    token.push_str(suffix);
+                }
+                self.vocab.get(&token).copied().ok_or(*c)
+            })
+            .collect();
+        loop {
+            let best = symbols
+                .windows(2)
+                .enumerate()
+                .filter_map(|(ix, pair)| match pair {
+                    [Ok(a), Ok(b)] => self.merges.get(&(*a, *b)).map(|m| (m.0, ix, m.1)),
+                    _ => None,
+                })
+                .min();
+            let Some((_, ix, merged)) = best else { break };
+            symbols[ix] = Ok(merged);
+            symbols.drain(ix + 1..ix + 2);
+        }
+        let mut previous_unk = false;
+        for symbol in symbols {
+            let c = match symbol {
+                Ok(id) => {
+                    ids.push(id);
+                    previous_unk = false;
+                    continue;
+                }
+                Err(c) => c,
+            };
+            if self.byte_fallback {
+                if let Some(bytes) = byte_fallback(|t| self.vocab.get(t).copied(), c) {
+                    ids.extend(bytes);
+                    previous_unk = false;
+                    continue;
+                }
+            }
+            let unk =
+                self.unk_id.with_context(|| format!("No token for {c:?} and no unknown token"))?;
+            if !(self.fuse_unk && previous_unk) {
+                ids.push(unk);
+            }
+            previous_unk = true;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn bpe(merges: Json, fuse_unk: bool, byte_fallback: bool) -> TractResult<Bpe> {
+        Bpe::from_json(&json!({
+            "vocab": { "a": 0, "b": 1, "c": 2, "ab": 3, "bc": 4, "abc": 5, "<unk>": 6, "<0x78>": 7 },
+            "merges": merges,
+            "unk_token": "<unk>",
+            "fuse_unk": fuse_unk,
+            "byte_fallback": byte_fallback,
+        }))
+    }
+
+    fn tokenize(bpe: &Bpe, word: &str) -> TractResult<Vec<i64>> {
+        let mut ids = vec![];
+        bpe.tokenize(word, &mut ids)?;
+        Ok(ids)
+    }
+
+    #[test]
+    fn merges_by_rank() -> TractResult<()> {
+        assert_eq!(tokenize(&bpe(json!(["b c", "a b"]), false, false)?, "abc")?, vec![0, 4]);
+        assert_eq!(tokenize(&bpe(json!(["a b", "b c"]), false, false)?, "abc")?, vec![3, 2]);
+        let bpe = bpe(json!(["b c", "a b", ["a", "bc"]]), false, false)?;
+        assert_eq!(tokenize(&bpe, "abc")?, vec![5]);
+        assert_eq!(tokenize(&bpe, "cab")?, vec![2, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_characters() -> TractResult<()> {
+        assert_eq!(tokenize(&bpe(json!([]), false, false)?, "ayyb")?, vec![0, 6, 6, 1]);
+        assert_eq!(tokenize(&bpe(json!([]), true, false)?, "ayyb")?, vec![0, 6, 1]);
+        assert_eq!(tokenize(&bpe(json!([]), false, true)?, "axyb")?, vec![0, 7, 6, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_merge() {
+        assert!(bpe(json!(["a x"]), false, false).is_err());
+        assert!(bpe(json!(["abc"]), false, false).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use super::json::*;
+use super::pre_tokenizer::bytes_to_chars;
+use regex::Regex;
+use tract_nnef::internal::*;
+
+#[derive(Clone, Debug)]
+pub enum Decoder {
+    ByteLevel(HashMap<char, u8>),
+    WordPiece { prefix: String, cleanup: bool },
+    Metaspace { replacement: char, prepend: bool },
+    Bpe { suffix: String },
+    ByteFallback,
+    Fuse,
+    Strip { content: char, start: usize, stop: usize },
+    Replace { pattern: Regex, content: String },
+    Sequence(Vec<Decoder>),
+}
+
+impl Decoder {
+    pub fn from_json(json: &Json) -> TractResult<Decoder> {
+        let decoder = match type_field(json)? {
+            "ByteLevel" => Decoder::ByteLevel(
+                bytes_to_chars().iter().zip(0..=255).map(|(c, b)| (*c, b)).collect(),
+            ),
+            "WordPiece" => Decoder::WordPiece {
+                prefix: opt_str_field(json, "prefix")?.unwrap_or("##").to_string(),
+                cleanup: bool_field(json, "cleanup", true)?,
+            },
+            "Metaspace" => Decoder::Metaspace {
+                replacement: char_field(json, "replacement")?,
+                prepend: match opt_str_field(json, "prepend_scheme")? {
+                    Some(scheme) => scheme != "never",
+                    None => bool_field(json, "add_prefix_space", true)?,
+                },
+            },
+            "BPEDecoder" => Decoder::Bpe {
+                suffix: opt_str_field(json, "suffix")?.unwrap_or("</w>").to_string(),
+            },
+            "ByteFallback" => Decoder::ByteFallback,
+            "Fuse" => Decoder::Fuse,
+            "Strip" => Decoder::Strip {
+                content: char_field(json, "content")?,
+                start: int_field(json, "start")? as usize,
+                stop: int_field(json, "stop")? as usize,
+            },
+            "Replace" => Decoder::Replace {
+                pattern: pattern_field(json, "pattern")?,
+                content: str_field(json, "content")?.to_string(),
+            },
+            "Sequence" => Decoder::Sequence(
+                array_field(json, "decoders")?
+                    .iter()
+                    .map(Decoder::from_json)
+                    .collect::<TractResult<_>>()?,
+            ),
+            other => bail!("Unsupported decoder {}", other),
+        };
+        Ok(decoder)
+    }
+
+    /// Turn tokens into text pieces, to be concatenated.
+    pub fn decode(&self, tokens: Vec<String>) -> Vec<String> {
+        match self {
+            Decoder::ByteLevel(bytes) => {
+                let mut data = vec![];
+                for token in &tokens {
+                    if let Some(token_bytes) =
+                        token.chars().map(|c| bytes.get(&c).copied()).collect::<Option<Vec<u8>>>()
+                    {
+                        data.extend(token_bytes);
+                    } else {
+                        data.extend(token.as_bytes());
+                    }
+                }
+                vec![String::from_utf8_lossy(&data).into_owned()]
+            }
+            Decoder::WordPiece { prefix, cleanup } => tokens
+                .into_iter()
+                .enumerate()
+                .map(|(ix, token)| {
+                    let token = if ix == 0 {
+                        token
+                    } else if let Some(stripped) = token.strip_prefix(prefix.as_str()) {
+                        stripped.to_string()
+                    } else {
+                        format!(" {token}")
+                    };
+                    if *cleanup {
+                        cleanup_tokenization(&token)
+                    } else {
+                        token
+                    }
+                })
+                .collect(),
+            Decoder::Metaspace { replacement, prepend } => tokens
+                .into_iter()
+                .enumerate()
+                .map(|(ix, token)| {
+                    token
+                        .chars()
+                        .filter_map(|c| match c {
+                            c if c != *replacement => Some(c),
+                            _ if ix == 0 && *prepend => None,
+                            _ => Some(' '),
+                        })
+                        .collect()
+                })
+                .collect(),
+            Decoder::Bpe { suffix } => {
+                let last = tokens.len().saturating_sub(1);
+                tokens
+                    .into_iter()
+                    .enumerate()
+                    .map(|(ix, token)| token.replace(suffix, if ix == last { "" } else { " " }))
+                    .collect()
+            }
+            Decoder::ByteFallback => {
+                let mut pieces = vec![];
+                let mut bytes = vec![];
+                for token in tokens {
+                    if let Some(byte) = byte_token(&token) {
+                        bytes.push(byte);
+                    } else {
+                        flush_bytes(&mut bytes, &mut pieces);
+                        pieces.push(token);
+                    }
+                }
+                flush_bytes(&mut bytes, &mut pieces);
+                pieces
+            }
+            Decoder::Fuse => vec![tokens.concat()],
+            Decoder::Strip { content, start, stop } => tokens
+                .into_iter()
+                .map(|token| {
+                    let chars: Vec<char> = token.chars().collect();
+                    let start = chars.iter().take(*start).take_while(|c| *c == content).count();
+                    let stop = chars[start..]
+                        .iter()
+                        .rev()
+                        .take(*stop)
+                        .take_while(|c| *c == content)
+                        .count();
+                    chars[start..chars.len() - stop].iter().collect()
+                })
+                .collect(),
+            Decoder::Replace { pattern, content } => tokens
+                .into_iter()
+                .map(|token| pattern.replace_all(&token, content.as_str()).into_owned())
+                .collect(),
+            Decoder::Sequence(decoders) => {
+                decoders.iter().fold(tokens, |tokens, decoder| decoder.decode(tokens))
+            }
+        }
+    }
+}
+
+/// Byte of a `<0xXX>` byte fallback token.
+fn byte_token(token: &str) -> Option<u8> {
+    let hex = token.strip_prefix("<0x")?.strip_suffix('>')?;
+    if hex.len() == 2 {
+        u8::from_str_radix(hex, 16).ok()
+    } else {
+        None
+    }
+}
+
+fn flush_bytes(bytes: &mut Vec<u8>, pieces: &mut Vec<String>) {
+    if bytes.is_empty() {
+        return;
+    }
+    match String::from_utf8(std::mem::take(bytes)) {
+        Ok(s) => pieces.push(s),
+        Err(e) => pieces.extend(e.as_bytes().iter().map(|_| "\u{fffd}".to_string())),
+    }
+}
+
+/// Undo the spaces WordPiece decoding inserts before punctuation and english
+/// contractions.
+fn cleanup_tokenization(text: &str) -> String {
+    text.replace(" .", ".")
+        .replace(" ?", "?")
+        .replace(" !", "!")
+        .replace(" ,", ",")
+        .replace(" ' ", "'")
+        .replace(" n't", "n't")
+        .replace(" 'm", "'m")
+        .replace(" do not", " don't")
+        .replace(" 's", "'s")
+        .replace(" 've", "'ve")
+        .replace(" 're", "'re")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn decode(json: Json, tokens: &[&str]) -> TractResult<Vec<String>> {
+        let decoder = Decoder::from_json(&json)?;
+        Ok(decoder.decode(tokens.iter().map(|s| s.to_string()).collect()))
+    }
+
+    #[test]
+    fn wordpiece() -> TractResult<()> {
+        let tokens = ["un", "##aff", "##able", "world", "!"];
+        let cleanup = decode(json!({ "type": "WordPiece" }), &tokens)?;
+        assert_eq!(cleanup.concat(), "unaffable world!");
+        let raw = decode(json!({ "type": "WordPiece", "cleanup": false }), &tokens)?;
+        assert_eq!(raw.concat(), "unaffable world !");
+        Ok(())
+    }
+
+    #[test]
+    fn metaspace_and_bpe() -> TractResult<()> {
+        let metaspace =
+            json!({ "type": "Metaspace", "replacement": "▁", "add_prefix_space": true });
+        assert_eq!(decode(metaspace, &["▁hello", "▁wor", "ld"])?.concat(), "hello world");
+        let bpe = json!({ "type": "BPEDecoder" });
+        assert_eq!(decode(bpe, &["hel", "lo</w>", "world</w>"])?.concat(), "hello world");
+        Ok(())
+    }
+
+    #[test]
+    fn byte_fallback() -> TractResult<()> {
+        let byte_fallback = json!({ "type": "ByteFallback" });
+        assert_eq!(
+            decode(byte_fallback.clone(), &["a", "<0xC3>", "<0xA9>", "b"])?,
+            ["a", "é", "b"]
+        );
+        assert_eq!(decode(byte_fallback, &["<0xFF>", "<0x41>"])?, ["\u{fffd}", "\u{fffd}"]);
+        Ok(())
+    }
+
+    #[test]
+    fn sequence() -> TractResult<()> {
+        let sequence = json!({
+            "type": "Sequence",
+            "decoders": [
+                { "type": "Replace", "pattern": { "String": "▁" }, "content": " " },
+                { "type": "ByteFallback" },
+                { "type": "Fuse" },
+                { "type": "Strip", "content": " ", "start": 1, "stop": 0 },
+            ]
+        });
+        assert_eq!(decode(sequence, &["▁hi", "<0x21>", "▁there"])?, ["hi! there"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use regex::Regex;
+use tract_nnef::internal::*;
+
+pub use serde_json::Value as Json;
+
+/// Field `name` of a JSON object, None when missing or null.
+pub fn field<'a>(json: &'a Json, name: &str) -> Option<&'a Json> {
+    json.get(name).filter(|v| !v.is_null())
+}
+
+pub fn type_field(json: &Json) -> TractResult<&str> {
+    str_field(json, "type")
+}
+
+pub fn str_field<'a>(json: &'a Json, name: &str) -> TractResult<&'a str> {
+    opt_str_field(json, name)?.with_context(|| format!("Missing string field {name:?}"))
+}
+
+pub fn opt_str_field<'a>(json: &'a Json, name: &str) -> TractResult<Option<&'a str>> {
+    field(json, name)
+        .map(|v| v.as_str().with_context(|| format!("Expected a string for {name:?}, got {v}")))
+        .transpose()
+}
+
+pub fn int_field(json: &Json, name: &str) -> TractResult<i64> {
+    opt_int_field(json, name)?.with_context(|| format!("Missing integer field {name:?}"))
+}
+
+pub fn opt_int_field(json: &Json, name: &str) -> TractResult<Option<i64>> {
+    field(json, name)
+        .map(|v| v.as_i64().with_context(|| format!("Expected an integer for {name:?}, got {v}")))
+        .transpose()
+}
+
+pub fn bool_field(json: &Json, name: &str, default: bool) -> TractResult<bool> {
+    field(json, name)
+        .map(|v| v.as_bool().with_context(|| format!("Expected a boolean for {name:?}, got {v}")))
+        .transpose()
+        .map(|b| b.unwrap_or(default))
+}
+
+pub fn array_field<'a>(json: &'a Json, name: &str) -> TractResult<&'a [Json]> {
+    field(json, name)
+        .and_then(|v| v.as_array())
+        .map(|v| v.as_slice())
+        .with_context(|| format!("Missing array field {name:?}"))
+}
+
+/// Single character field, like Metaspace replacement.
+pub fn char_field(json: &Json, name: &str) -> TractResult<char> {
+    let s = str_field(json, name)?;
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => bail!("Expected a single character for {:?}, got {:?}", name, s),
+    }
+}
+
+/// Pattern of Replace and Split components: `{"String": ...}` or `{"Regex": ...}`.
+pub fn pattern_field(json: &Json, name: &str) -> TractResult<Regex> {
+    let pattern = field(json, name).with_context(|| format!("Missing pattern field {name:?}"))?;
+    let regex = if let Some(s) = opt_str_field(pattern, "String")? {
+        regex::escape(s)
+    } else {
+        str_field(pattern, "Regex")?.to_string()
+    };
+    Regex::new(&regex).with_context(|| format!("Invalid pattern {regex:?}"))
+}
@@ -0,0 +1,351 @@
+use tract_nnef::internal::*;
+use tract_nnef_resources::internal::JsonResource;
+
+mod bpe;
+mod decoder;
+mod json;
+mod model;
+mod normalizer;
+mod pre_tokenizer;
+mod unigram;
+mod vocabulary;
+mod wordpiece;
+
+pub use vocabulary::Vocabulary;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<Tokenize>(), dump_tokenize);
+    registry.register_primitive(
+        "tract_extra_tokenize",
+        &[
+            TypeName::String.tensor().named("input"),
+            TypeName::String.named("vocabulary"),
+            TypeName::Integer.named("pad_id").default(false),
+            TypeName::Integer.named("max_length").default(0),
+        ],
+        &[("ids", TypeName::Integer.tensor()), ("mask", TypeName::Integer.tensor())],
+        load_tokenize,
+    );
+    registry.register_dumper(TypeId::of::<Detokenize>(), dump_detokenize);
+    registry.register_primitive(
+        "tract_extra_detokenize",
+        &[
+            TypeName::Integer.tensor().named("input"),
+            TypeName::String.named("vocabulary"),
+            TypeName::Logical.named("skip_special_tokens").default(true),
+        ],
+        &[("output", TypeName::String.tensor())],
+        load_detokenize,
+    );
+}
+
+/// Maps strings to token ids along a new last axis, padded with `pad_id`,
+/// and the matching attention mask. The vocabulary is a JSON resource of the
+/// model, labelled `label`.
+#[derive(Clone, Debug)]
+pub struct Tokenize {
+    pub label: String,
+    pub vocabulary: Arc<Vocabulary>,
+    pub pad_id: i64,
+    pub max_length: Option<usize>,
+    pub output_len: Symbol,
+}
+
+impl Op for Tokenize {
+    fn name(&self) -> Cow<str> {
+        "Tokenize".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        let mut info =
+            vec![format!("{:?} from {:?}, pad_id: {}", self.vocabulary, self.label, self.pad_id)];
+        if let Some(max_length) = self.max_length {
+            info.push(format!("max_length: {max_length}"));
+        }
+        Ok(info)
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for Tokenize {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let encoded = input
+            .as_slice::<String>()?
+            .iter()
+            .map(|text| self.vocabulary.encode(text, self.max_length))
+            .collect::<TractResult<Vec<_>>>()?;
+        let len = encoded.iter().map(|ids| ids.len()).max().unwrap_or(0);
+        let mut ids = vec![self.pad_id; encoded.len() * len];
+        let mut mask = vec![0i64; encoded.len() * len];
+        if len > 0 {
+            for ((ids, mask), encoded) in
+                ids.chunks_mut(len).zip(mask.chunks_mut(len)).zip(&encoded)
+            {
+                ids[..encoded.len()].copy_from_slice(encoded);
+                mask[..encoded.len()].iter_mut().for_each(|m| *m = 1);
+            }
+        }
+        let mut shape: TVec<usize> = input.shape().into();
+        shape.push(len);
+        Ok(tvec!(
+            tensor1(&ids).into_shape(&shape)?.into_tvalue(),
+            tensor1(&mask).into_shape(&shape)?.into_tvalue()
+        ))
+    }
+}
+
+impl TypedOp for Tokenize {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs[0].datum_type == String::datum_type());
+        let mut shape = inputs[0].shape.to_tvec();
+        shape.push(self.output_len.to_dim());
+        Ok(tvec!(i64::fact(&shape), i64::fact(&shape)))
+    }
+
+    as_op!();
+}
+
+/// Maps token ids along the last axis back to strings.
+#[derive(Clone, Debug)]
+pub struct Detokenize {
+    pub label: String,
+    pub vocabulary: Arc<Vocabulary>,
+    pub skip_special_tokens: bool,
+}
+
+impl Op for Detokenize {
+    fn name(&self) -> Cow<str> {
+        "Detokenize".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "{:?} from {:?}, skip_special_tokens: {}",
+            self.vocabulary, self.label, self.skip_special_tokens
+        )])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for Detokenize {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let input = input.cast_to::<i64>()?;
+        let (len, shape) = input.shape().split_last().context("Expect at least one axis")?;
+        let rows: usize = shape.iter().product();
+        let ids = input.as_slice::<i64>()?;
+        let output = (0..rows)
+            .map(|row| self.vocabulary.decode(&ids[row * len..][..*len], self.skip_special_tokens))
+            .collect::<TractResult<Vec<_>>>()?;
+        Ok(tvec!(tensor1(&output).into_shape(shape)?.into_tvalue()))
+    }
+}
+
+impl TypedOp for Detokenize {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs[0].datum_type.is_integer());
+        ensure!(inputs[0].rank() > 0, "Detokenize expects token ids along a last axis");
+        let shape = &inputs[0].shape[..inputs[0].rank() - 1];
+        Ok(tvec!(String::fact(shape)))
+    }
+
+    as_op!();
+}
+
+fn vocabulary(builder: &ModelBuilder, label: &str) -> TractResult<Arc<Vocabulary>> {
+    let resource = builder
+        .proto_model
+        .resources
+        .get(label)
+        .with_context(|| format!("No resource found for vocabulary {label:?} in the model"))?
+        .clone()
+        .downcast_arc::<JsonResource>()
+        .map_err(|_| format_err!("Vocabulary {:?} is not a JSON resource", label))?;
+    Vocabulary::from_resource(resource)
+        .with_context(|| format!("Loading vocabulary {label:?}"))
+        .map(Arc::new)
+}
+
+fn dump_tokenize(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<Tokenize>().context("wrong op")?;
+    ast.resources.insert(op.label.clone(), op.vocabulary.resource().clone());
+    Ok(Some(invocation(
+        "tract_extra_tokenize",
+        &[input],
+        &[
+            ("vocabulary", string(&op.label)),
+            ("pad_id", numeric(op.pad_id)),
+            ("max_length", numeric(op.max_length.unwrap_or(0))),
+        ],
+    )))
+}
+
+fn load_tokenize(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let label: String = invocation.named_arg_as(builder, "vocabulary")?;
+    let vocabulary = vocabulary(builder, &label)?;
+    let pad_id = invocation.optional_named_arg_as(builder, "pad_id")?;
+    let max_length: i64 = invocation.named_arg_as(builder, "max_length")?;
+    let op = Tokenize {
+        label,
+        pad_id: pad_id.unwrap_or_else(|| vocabulary.pad_id()),
+        vocabulary,
+        max_length: Some(max_length as usize).filter(|_| max_length > 0),
+        output_len: builder.model.symbol_table.new_with_prefix("n"),
+    };
+    builder.wire(op, &[input])
+}
+
+fn dump_detokenize(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<Detokenize>().context("wrong op")?;
+    ast.resources.insert(op.label.clone(), op.vocabulary.resource().clone());
+    Ok(Some(invocation(
+        "tract_extra_detokenize",
+        &[input],
+        &[
+            ("vocabulary", string(&op.label)),
+            ("skip_special_tokens", logical(op.skip_special_tokens)),
+        ],
+    )))
+}
+
+fn load_detokenize(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let label: String = invocation.named_arg_as(builder, "vocabulary")?;
+    let op = Detokenize {
+        vocabulary: vocabulary(builder, &label)?,
+        label,
+        skip_special_tokens: invocation.named_arg_as(builder, "skip_special_tokens")?,
+    };
+    builder.wire(op, &[input])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::WithTractExtra;
+    use serde_json::json;
+
+    fn vocabulary(json: serde_json::Value) -> TractResult<Vocabulary> {
+        Vocabulary::from_resource(Arc::new(JsonResource(json)))
+    }
+
+    fn bert() -> TractResult<Vocabulary> {
+        let special =
+            |id: i64, content: &str| json!({ "id": id, "content": content, "special": true });
+        vocabulary(json!({
+            "added_tokens": [special(0, "[PAD]"), special(1, "[UNK]"), special(2, "[CLS]"), special(3, "[SEP]")],
+            "normalizer": { "type": "BertNormalizer", "lowercase": true },
+            "pre_tokenizer": { "type": "BertPreTokenizer" },
+            "post_processor": { "type": "BertProcessing", "cls": ["[CLS]", 2], "sep": ["[SEP]", 3] },
+            "decoder": { "type": "WordPiece", "prefix": "##", "cleanup": true },
+            "model": {
+                "type": "WordPiece",
+                "unk_token": "[UNK]",
+                "vocab": {
+                    "[PAD]": 0, "[UNK]": 1, "[CLS]": 2, "[SEP]": 3, "hello": 4, "world": 5,
+                    "wor": 6, "##ld": 7, "!": 8, "un": 9, "##aff": 10, "##able": 11
+                }
+            }
+        }))
+    }
+
+    #[test]
+    fn wordpiece() -> TractResult<()> {
+        let vocabulary = bert()?;
+        let ids = vocabulary.encode("Héllo, unaffable World!", None)?;
+        assert_eq!(ids, vec![2, 4, 1, 9, 10, 11, 5, 8, 3]);
+        assert_eq!(vocabulary.decode(&[4, 9, 10, 11, 5, 8], true)?, "hello unaffable world!");
+        assert_eq!(vocabulary.encode("Hello unaffable World!", Some(5))?, vec![2, 4, 9, 10, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn byte_level_bpe() -> TractResult<()> {
+        let vocabulary = vocabulary(json!({
+            "pre_tokenizer": { "type": "ByteLevel", "add_prefix_space": false },
+            "decoder": { "type": "ByteLevel" },
+            "model": {
+                "type": "BPE",
+                "vocab": {
+                    "h": 0, "e": 1, "l": 2, "o": 3, "Ġ": 4, "w": 5, "r": 6, "d": 7, "he": 8,
+                    "ll": 9, "hell": 10, "hello": 11, "Ġw": 12, "or": 13, "Ġwor": 14
+                },
+                "merges": ["h e", "l l", "he ll", "hell o", "Ġ w", ["o", "r"], "Ġw or"]
+            }
+        }))?;
+        let ids = vocabulary.encode("hello  world", None)?;
+        assert_eq!(ids, vec![11, 4, 14, 2, 7]);
+        assert_eq!(vocabulary.decode(&ids, true)?, "hello  world");
+        Ok(())
+    }
+
+    #[test]
+    fn unigram() -> TractResult<()> {
+        let vocabulary = vocabulary(json!({
+            "pre_tokenizer": { "type": "Metaspace", "replacement": "▁", "add_prefix_space": true },
+            "decoder": { "type": "Metaspace", "replacement": "▁", "add_prefix_space": true },
+            "model": {
+                "type": "Unigram",
+                "unk_id": 0,
+                "vocab": [
+                    ["<unk>", 0.0], ["▁", -2.0], ["▁hel", -3.0], ["lo", -3.0], ["▁hello", -4.0],
+                    ["h", -5.0], ["e", -5.0], ["l", -5.0], ["o", -5.0]
+                ]
+            }
+        }))?;
+        assert_eq!(vocabulary.encode("hello hexxo", None)?, vec![4, 1, 5, 6, 0, 8]);
+        assert_eq!(vocabulary.decode(&[4, 1, 5, 6, 7, 8], true)?, "hello helo");
+        Ok(())
+    }
+
+    #[test]
+    fn nnef_round_trip() -> TractResult<()> {
+        let vocabulary = Arc::new(bert()?);
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", String::fact([2]))?;
+        let tokenize = Tokenize {
+            label: "tokenizer".to_string(),
+            vocabulary: vocabulary.clone(),
+            pad_id: 0,
+            max_length: None,
+            output_len: model.symbol_table.sym("n"),
+        };
+        let tokens = model.wire_node("tokenize", tokenize, &[source])?;
+        let detokenize =
+            Detokenize { label: "tokenizer".to_string(), vocabulary, skip_special_tokens: true };
+        let text = model.wire_node("detokenize", detokenize, &[tokens[0]])?;
+        model.set_output_outlets(&[tokens[0], tokens[1], text[0]])?;
+
+        let nnef = tract_nnef::nnef().with_tract_extra();
+        let mut buffer = vec![];
+        nnef.write(&model, &mut buffer)?;
+        let reloaded = nnef.model_for_read(&mut &*buffer)?;
+
+        let input = tensor1(&["Hello world".to_string(), "unaffable".to_string()]);
+        let outputs = reloaded.into_runnable()?.run(tvec!(input.into_tvalue()))?;
+        assert_eq!(*outputs[0], tensor2(&[[2i64, 4, 5, 3, 0], [2, 9, 10, 11, 3]]));
+        assert_eq!(*outputs[1], tensor2(&[[1i64, 1, 1, 1, 0], [1, 1, 1, 1, 1]]));
+        assert_eq!(*outputs[2], tensor1(&["hello world".to_string(), "unaffable".to_string()]));
+        Ok(())
+    }
+}
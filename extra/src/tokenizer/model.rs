@@ -0,0 +1,87 @@
+use super::bpe::Bpe;
+use super::json::*;
+use super::unigram::Unigram;
+use super::wordpiece::WordPiece;
+use tract_nnef::internal::*;
+
+#[derive(Clone, Debug)]
+pub enum Model {
+    Bpe(Bpe),
+    WordPiece(WordPiece),
+    Unigram(Unigram),
+}
+
+impl Model {
+    pub fn from_json(json: &Json) -> TractResult<Model> {
+        match type_field(json)? {
+            "BPE" => Ok(Model::Bpe(Bpe::from_json(json)?)),
+            "WordPiece" => Ok(Model::WordPiece(WordPiece::from_json(json)?)),
+            "Unigram" => Ok(Model::Unigram(Unigram::from_json(json)?)),
+            other => bail!("Unsupported tokenizer model {}", other),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Model::Bpe(_) => "BPE",
+            Model::WordPiece(_) => "WordPiece",
+            Model::Unigram(_) => "Unigram",
+        }
+    }
+
+    /// Append the ids of the tokens of a pre-tokenized word.
+    pub fn tokenize(&self, word: &str, ids: &mut Vec<i64>) -> TractResult<()> {
+        match self {
+            Model::Bpe(m) => m.tokenize(word, ids),
+            Model::WordPiece(m) => m.tokenize(word, ids),
+            Model::Unigram(m) => m.tokenize(word, ids),
+        }
+    }
+
+    pub fn token_to_id(&self, token: &str) -> Option<i64> {
+        match self {
+            Model::Bpe(m) => m.vocab.get(token).copied(),
+            Model::WordPiece(m) => m.vocab.get(token).copied(),
+            Model::Unigram(m) => m.token_to_id(token),
+        }
+    }
+
+    pub fn id_to_token(&self, id: i64) -> Option<&str> {
+        match self {
+            Model::Bpe(m) => m.tokens.get(&id).map(|s| s.as_str()),
+            Model::WordPiece(m) => m.tokens.get(&id).map(|s| s.as_str()),
+            Model::Unigram(m) => m.id_to_token(id),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            Model::Bpe(m) => m.tokens.len(),
+            Model::WordPiece(m) => m.tokens.len(),
+            Model::Unigram(m) => m.size(),
+        }
+    }
+}
+
+/// `{ token: id }` vocabulary of BPE and WordPiece models.
+pub fn vocab_field(
+    json: &Json,
+    name: &str,
+) -> TractResult<(HashMap<String, i64>, HashMap<i64, String>)> {
+    let vocab = field(json, name)
+        .and_then(|v| v.as_object())
+        .with_context(|| format!("Missing vocabulary object {name:?}"))?;
+    let mut ids = HashMap::with_capacity(vocab.len());
+    let mut tokens = HashMap::with_capacity(vocab.len());
+    for (token, id) in vocab {
+        let id = id.as_i64().with_context(|| format!("Invalid id for token {token:?}: {id}"))?;
+        ids.insert(token.clone(), id);
+        tokens.insert(id, token.clone());
+    }
+    Ok((ids, tokens))
+}
+
+/// Ids of the `<0xXX>` tokens of a text bytes, for models with byte fallback.
+pub fn byte_fallback(vocab: impl Fn(&str) -> Option<i64>, text: &str) -> Option<Vec<i64>> {
+    text.bytes().map(|b| vocab(&format!("<0x{b:02X}>"))).collect()
+}
@@ -0,0 +1,176 @@
+use super::json::*;
+use regex::Regex;
+use tract_nnef::internal::*;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Clone, Debug)]
+pub enum Normalizer {
+    Bert { clean_text: bool, handle_chinese_chars: bool, strip_accents: bool, lowercase: bool },
+    Lowercase,
+    StripAccents,
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+    Replace { pattern: Regex, content: String },
+    Prepend(String),
+    Strip { left: bool, right: bool },
+    Sequence(Vec<Normalizer>),
+}
+
+impl Normalizer {
+    pub fn from_json(json: &Json) -> TractResult<Normalizer> {
+        let normalizer = match type_field(json)? {
+            "BertNormalizer" => {
+                let lowercase = bool_field(json, "lowercase", true)?;
+                Normalizer::Bert {
+                    clean_text: bool_field(json, "clean_text", true)?,
+                    handle_chinese_chars: bool_field(json, "handle_chinese_chars", true)?,
+                    strip_accents: bool_field(json, "strip_accents", lowercase)?,
+                    lowercase,
+                }
+            }
+            "Lowercase" => Normalizer::Lowercase,
+            "StripAccents" => Normalizer::StripAccents,
+            "NFC" => Normalizer::Nfc,
+            "NFD" => Normalizer::Nfd,
+            "NFKC" => Normalizer::Nfkc,
+            "NFKD" => Normalizer::Nfkd,
+            "Replace" => Normalizer::Replace {
+                pattern: pattern_field(json, "pattern")?,
+                content: str_field(json, "content")?.to_string(),
+            },
+            "Prepend" => Normalizer::Prepend(str_field(json, "prepend")?.to_string()),
+            "Strip" => Normalizer::Strip {
+                left: bool_field(json, "strip_left", true)?,
+                right: bool_field(json, "strip_right", true)?,
+            },
+            "Sequence" => Normalizer::Sequence(
+                array_field(json, "normalizers")?
+                    .iter()
+                    .map(Normalizer::from_json)
+                    .collect::<TractResult<_>>()?,
+            ),
+            other => bail!("Unsupported normalizer {}", other),
+        };
+        Ok(normalizer)
+    }
+
+    pub fn normalize(&self, text: &str) -> String {
+        match self {
+            Normalizer::Bert { clean_text, handle_chinese_chars, strip_accents, lowercase } => {
+                let mut s = String::with_capacity(text.len());
+                for c in text.chars() {
+                    if *clean_text && (c == '\0' || c == '\u{fffd}' || is_bert_control(c)) {
+                        continue;
+                    }
+                    if *clean_text && c.is_whitespace() {
+                        s.push(' ');
+                    } else if *handle_chinese_chars && is_chinese_char(c) {
+                        s.push(' ');
+                        s.push(c);
+                        s.push(' ');
+                    } else {
+                        s.push(c);
+                    }
+                }
+                if *strip_accents {
+                    s = remove_accents(&s);
+                }
+                if *lowercase {
+                    s = s.to_lowercase();
+                }
+                s
+            }
+            Normalizer::Lowercase => text.to_lowercase(),
+            Normalizer::StripAccents => remove_accents(text),
+            Normalizer::Nfc => text.nfc().collect(),
+            Normalizer::Nfd => text.nfd().collect(),
+            Normalizer::Nfkc => text.nfkc().collect(),
+            Normalizer::Nfkd => text.nfkd().collect(),
+            Normalizer::Replace { pattern, content } => {
+                pattern.replace_all(text, content.as_str()).into_owned()
+            }
+            Normalizer::Prepend(prepend) if !text.is_empty() => format!("{prepend}{text}"),
+            Normalizer::Prepend(_) => String::new(),
+            Normalizer::Strip { left, right } => {
+                let s = if *left { text.trim_start() } else { text };
+                let s = if *right { s.trim_end() } else { s };
+                s.to_string()
+            }
+            Normalizer::Sequence(normalizers) => {
+                normalizers.iter().fold(text.to_string(), |s, n| n.normalize(&s))
+            }
+        }
+    }
+}
+
+fn remove_accents(text: &str) -> String {
+    text.nfd().filter(|c| !is_combining_mark(*c)).collect()
+}
+
+fn is_bert_control(c: char) -> bool {
+    !matches!(c, '\t' | '\n' | '\r') && c.is_control()
+}
+
+fn is_chinese_char(c: char) -> bool {
+    matches!(c as u32,
+        0x4E00..=0x9FFF
+        | 0x3400..=0x4DBF
+        | 0x20000..=0x2A6DF
+        | 0x2A700..=0x2B73F
+        | 0x2B740..=0x2B81F
+        | 0x2B820..=0x2CEAF
+        | 0xF900..=0xFAFF
+        | 0x2F800..=0x2FA1F)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn normalize(json: Json, text: &str) -> TractResult<String> {
+        Ok(Normalizer::from_json(&json)?.normalize(text))
+    }
+
+    #[test]
+    fn bert() -> TractResult<()> {
+        let bert = json!({ "type": "BertNormalizer" });
+        assert_eq!(normalize(bert, "Héllo\tWörld\u{0}你好")?, "hello world 你  好 ");
+        let cased = json!({ "type": "BertNormalizer", "lowercase": false });
+        assert_eq!(normalize(cased, "Héllo\u{7}")?, "Héllo");
+        Ok(())
+    }
+
+    #[test]
+    fn unicode() -> TractResult<()> {
+        assert_eq!(normalize(json!({ "type": "NFKC" }), "ﬁ")?, "fi");
+        assert_eq!(normalize(json!({ "type": "NFD" }), "é")?, "e\u{301}");
+        assert_eq!(normalize(json!({ "type": "NFC" }), "e\u{301}")?, "é");
+        assert_eq!(normalize(json!({ "type": "StripAccents" }), "Àé")?, "Ae");
+        assert_eq!(normalize(json!({ "type": "Lowercase" }), "ÀB")?, "àb");
+        Ok(())
+    }
+
+    #[test]
+    fn sequence() -> TractResult<()> {
+        let sequence = json!({
+            "type": "Sequence",
+            "normalizers": [
+                { "type": "Strip", "strip_left": true, "strip_right": false },
+                { "type": "Replace", "pattern": { "String": " " }, "content": "▁" },
+                { "type": "Prepend", "prepend": "▁" },
+            ]
+        });
+        assert_eq!(normalize(sequence.clone(), "  a b ")?, "▁a▁b▁");
+        assert_eq!(normalize(sequence, "  ")?, "");
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported() {
+        assert!(Normalizer::from_json(&json!({ "type": "Precompiled" })).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use super::json::*;
+use regex::Regex;
+use tract_nnef::internal::*;
+
+/// GPT-2 split pattern, without the `\s+(?!\S)` alternative: look-around is
+/// not supported by the regex crate, see `byte_level_split`.
+const BYTE_LEVEL_PATTERN: &str =
+    r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+";
+
+#[derive(Clone, Debug)]
+pub enum PreTokenizer {
+    /// Splits matching the regex are the words, what does not match is dropped.
+    Matches(Regex),
+    WhitespaceSplit,
+    Metaspace {
+        replacement: char,
+        prepend: bool,
+        split: bool,
+    },
+    ByteLevel {
+        add_prefix_space: bool,
+        regex: Option<Regex>,
+    },
+    Sequence(Vec<PreTokenizer>),
+}
+
+impl PreTokenizer {
+    pub fn from_json(json: &Json) -> TractResult<PreTokenizer> {
+        let matches = |pattern: &str| -> TractResult<PreTokenizer> {
+            Ok(PreTokenizer::Matches(Regex::new(pattern)?))
+        };
+        match type_field(json)? {
+            "BertPreTokenizer" => matches(r"[^\s\p{P}[:punct:]]+|[\p{P}[:punct:]]"),
+            "Whitespace" => matches(r"\w+|[^\w\s]+"),
+            "WhitespaceSplit" => Ok(PreTokenizer::WhitespaceSplit),
+            "Punctuation" => matches(r"[^\p{P}[:punct:]]+|[\p{P}[:punct:]]"),
+            "Digits" if bool_field(json, "individual_digits", false)? => {
+                matches(r"\p{N}|[^\p{N}]+")
+            }
+            "Digits" => matches(r"\p{N}+|[^\p{N}]+"),
+            "Metaspace" => {
+                let prepend = match opt_str_field(json, "prepend_scheme")? {
+                    Some(scheme) => scheme != "never",
+                    None => bool_field(json, "add_prefix_space", true)?,
+                };
+                Ok(PreTokenizer::Metaspace {
+                    replacement: char_field(json, "replacement")?,
+                    prepend,
+                    split: bool_field(json, "split", true)?,
+                })
+            }
+            "ByteLevel" => Ok(PreTokenizer::ByteLevel {
+                add_prefix_space: bool_field(json, "add_prefix_space", true)?,
+                regex: if bool_field(json, "use_regex", true)? {
+                    Some(Regex::new(BYTE_LEVEL_PATTERN)?)
+                } else {
+                    None
+                },
+            }),
+            "Sequence" => Ok(PreTokenizer::Sequence(
+                array_field(json, "pretokenizers")?
+                    .iter()
+                    .map(PreTokenizer::from_json)
+                    .collect::<TractResult<_>>()?,
+            )),
+            other => bail!("Unsupported pre-tokenizer {}", other),
+        }
+    }
+
+    /// Split a normalized text in words, to be tokenized independently.
+    pub fn pre_tokenize(&self, text: &str) -> Vec<String> {
+        self.split(vec![text.to_string()])
+    }
+
+    fn split(&self, words: Vec<String>) -> Vec<String> {
+        if let PreTokenizer::Sequence(pre_tokenizers) = self {
+            pre_tokenizers.iter().fold(words, |words, p| p.split(words))
+        } else {
+            words.iter().flat_map(|w| self.split_word(w)).collect()
+        }
+    }
+
+    fn split_word(&self, word: &str) -> Vec<String> {
+        match self {
+            PreTokenizer::Matches(regex) => {
+                regex.find_iter(word).map(|m| m.as_str().to_string()).collect()
+            }
+            PreTokenizer::WhitespaceSplit => word.split_whitespace().map(String::from).collect(),
+            PreTokenizer::Metaspace { replacement, prepend, split } => {
+                let mut s = word.replace(' ', &replacement.to_string());
+                if *prepend && !s.starts_with(*replacement) {
+                    s.insert(0, *replacement);
+                }
+                if !split {
+                    return vec![s];
+                }
+                // each replacement starts a new word
+                let mut words = vec![];
+                let mut start = 0;
+                for (ix, c) in s.char_indices() {
+                    if c == *replacement && ix > start {
+                        words.push(s[start..ix].to_string());
+                        start = ix;
+                    }
+                }
+                words.push(s[start..].to_string());
+                words
+            }
+            PreTokenizer::ByteLevel { add_prefix_space, regex } => {
+                let mut s = word.to_string();
+                if *add_prefix_space && !s.starts_with(' ') {
+                    s.insert(0, ' ');
+                }
+                let words = if let Some(regex) = regex {
+                    byte_level_split(regex, &s)
+                } else {
+                    vec![s.as_str()]
+                };
+                let chars = bytes_to_chars();
+                words.iter().map(|w| w.bytes().map(|b| chars[b as usize]).collect()).collect()
+            }
+            PreTokenizer::Sequence(_) => self.split(vec![word.to_string()]),
+        }
+    }
+}
+
+/// GPT-2 pre-tokenization. Whitespace runs followed by a word leave their last
+/// character to it, as the `\s+(?!\S)` alternative of the original pattern.
+fn byte_level_split<'t>(regex: &Regex, text: &'t str) -> Vec<&'t str> {
+    let mut words = vec![];
+    let mut pos = 0;
+    while let Some(m) = regex.find_at(text, pos) {
+        let mut end = m.end();
+        if end < text.len() && m.as_str().chars().all(char::is_whitespace) {
+            if let Some((last, _)) = m.as_str().char_indices().last().filter(|(ix, _)| *ix > 0) {
+                end = m.start() + last;
+            }
+        }
+        words.push(&text[m.start()..end]);
+        pos = end;
+    }
+    words
+}
+
+/// GPT-2 mapping of bytes to printable unicode characters.
+pub fn bytes_to_chars() -> [char; 256] {
+    let mut chars = ['\0'; 256];
+    let mut extra = 0;
+    for b in 0..256u32 {
+        let printable = (b'!' as u32..=b'~' as u32).contains(&b)
+            || (0xA1..=0xAC).contains(&b)
+            || (0xAE..=0xFF).contains(&b);
+        chars[b as usize] = if printable {
+            char::from_u32(b).unwrap()
+        } else {
+            extra += 1;
+            char::from_u32(255 + extra).unwrap()
+        };
+    }
+    chars
+}
@@ -0,0 +1,115 @@
+use super::json::*;
+use super::model::byte_fallback;
+use tract_nnef::internal::*;
+
+/// Score penalty of unknown characters, below the worst scoring piece.
+const UNK_PENALTY: f64 = 10.0;
+
+/// Unigram language model of SentencePiece: words are split in the pieces
+/// maximizing the sum of their log-probability scores.
+#[derive(Clone, Debug)]
+pub struct Unigram {
+    /// Piece and score, by id.
+    pieces: Vec<(String, f64)>,
+    ids: HashMap<String, i64>,
+    unk_id: Option<i64>,
+    min_score: f64,
+    max_piece_chars: usize,
+    byte_fallback: bool,
+}
+
+impl Unigram {
+    pub fn from_json(json: &Json) -> TractResult<Unigram> {
+        let pieces = array_field(json, "vocab")?
+            .iter()
+            .map(|piece| {
+                let entry = match piece.as_array().map(|p| p.as_slice()) {
+                    Some([token, score]) => token.as_str().zip(score.as_f64()),
+                    _ => None,
+                };
+                let (token, score) = entry
+                    .with_context(|| format!("Expected a [piece, score] entry, got {piece}"))?;
+                Ok((token.to_string(), score))
+            })
+            .collect::<TractResult<Vec<_>>>()?;
+        let ids = pieces.iter().enumerate().map(|(id, p)| (p.0.clone(), id as i64)).collect();
+        let min_score = pieces.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        let max_piece_chars = pieces.iter().map(|p| p.0.chars().count()).max().unwrap_or(1);
+        Ok(Unigram {
+            unk_id: opt_int_field(json, "unk_id")?,
+            byte_fallback: bool_field(json, "byte_fallback", false)?,
+            pieces,
+            ids,
+            min_score,
+            max_piece_chars,
+        })
+    }
+
+    pub fn token_to_id(&self, token: &str) -> Option<i64> {
+        self.ids.get(token).copied()
+    }
+
+    pub fn id_to_token(&self, id: i64) -> Option<&str> {
+        usize::try_from(id).ok().and_then(|id| self.pieces.get(id)).map(|p| p.0.as_str())
+    }
+
+    pub fn size(&self) -> usize {
+        self.pieces.len()
+    }
+
+    pub fn tokenize(&self, word: &str, ids: &mut Vec<i64>) -> TractResult<()> {
+        let bounds: Vec<usize> =
+            word.char_indices().map(|(ix, _)| ix).chain(std::iter::once(word.len())).collect();
+        let n = bounds.len() - 1;
+        // best segmentation of the first i characters: score, start of the last
+        // piece, and its id (None for an unknown character)
+        let mut best: Vec<Option<(f64, usize, Option<i64>)>> = vec![None; n + 1];
+        best[0] = Some((0.0, 0, None));
+        for end in 1..=n {
+            for start in end.saturating_sub(self.max_piece_chars)..end {
+                let Some((score, _, _)) = best[start] else { continue };
+                let piece = &word[bounds[start]..bounds[end]];
+                let candidate = if let Some(&id) = self.ids.get(piece) {
+                    (score + self.pieces[id as usize].1, start, Some(id))
+                } else if end == start + 1 {
+                    (score + self.min_score - UNK_PENALTY, start, None)
+                } else {
+                    continue;
+                };
+                if best[end].map(|b| candidate.0 > b.0).unwrap_or(true) {
+                    best[end] = Some(candidate);
+                }
+            }
+        }
+        let mut pieces = vec![];
+        let mut end = n;
+        while end > 0 {
+            let (_, start, id) = best[end].unwrap();
+            pieces.push((&word[bounds[start]..bounds[end]], id));
+            end = start;
+        }
+        let mut previous_unk = false;
+        for (piece, id) in pieces.into_iter().rev() {
+            if let Some(id) = id {
+                ids.push(id);
+                previous_unk = false;
+                continue;
+            }
+            if self.byte_fallback {
+                if let Some(bytes) = byte_fallback(|t| self.ids.get(t).copied(), piece) {
+                    ids.extend(bytes);
+                    previous_unk = false;
+                    continue;
+                }
+            }
+            let unk =
+                self.unk_id.with_context(|| format!("No piece for {piece:?} and no unknown id"))?;
+            // consecutive unknown characters are fused in a single token
+            if !previous_unk {
+                ids.push(unk);
+            }
+            previous_unk = true;
+        }
+        Ok(())
+    }
+}
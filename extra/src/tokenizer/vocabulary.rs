@@ -0,0 +1,225 @@
+use super::decoder::Decoder;
+use super::json::*;
+use super::model::Model;
+use super::normalizer::Normalizer;
+use super::pre_tokenizer::PreTokenizer;
+use regex::Regex;
+use tract_nnef::internal::*;
+use tract_nnef::tract_core::tract_data::itertools::Itertools;
+use tract_nnef_resources::internal::JsonResource;
+
+/// Tokenizer vocabulary and pipeline, from a HuggingFace tokenizers
+/// `tokenizer.json` file: normalizer, pre-tokenizer, BPE, WordPiece or
+/// Unigram model, special tokens of the post-processor and decoder.
+#[derive(Clone)]
+pub struct Vocabulary {
+    resource: Arc<JsonResource>,
+    added_tokens: HashMap<i64, AddedToken>,
+    added_tokens_ids: HashMap<String, i64>,
+    /// Matches added tokens in raw text, longest first.
+    added_tokens_regex: Option<Regex>,
+    normalizer: Option<Normalizer>,
+    pre_tokenizer: Option<PreTokenizer>,
+    model: Model,
+    /// Special tokens surrounding single sequences.
+    prefix: Vec<i64>,
+    suffix: Vec<i64>,
+    decoder: Option<Decoder>,
+    pad_id: i64,
+}
+
+#[derive(Clone, Debug)]
+struct AddedToken {
+    content: String,
+    special: bool,
+}
+
+impl std::fmt::Debug for Vocabulary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Vocabulary({} model, {} tokens, {} added tokens)",
+            self.model.kind(),
+            self.model.size(),
+            self.added_tokens.len()
+        )
+    }
+}
+
+impl Vocabulary {
+    pub fn from_resource(resource: Arc<JsonResource>) -> TractResult<Vocabulary> {
+        let json = &resource.0;
+        let model = Model::from_json(field(json, "model").context("Missing tokenizer model")?)?;
+        let mut added_tokens = HashMap::new();
+        let mut added_tokens_ids = HashMap::new();
+        if let Some(tokens) = field(json, "added_tokens") {
+            for token in tokens.as_array().context("Expected an array of added tokens")? {
+                let id = int_field(token, "id")?;
+                let content = str_field(token, "content")?.to_string();
+                added_tokens_ids.insert(content.clone(), id);
+                added_tokens.insert(
+                    id,
+                    AddedToken { content, special: bool_field(token, "special", false)? },
+                );
+            }
+        }
+        let added_tokens_regex = if added_tokens_ids.is_empty() {
+            None
+        } else {
+            let pattern = added_tokens_ids
+                .keys()
+                .filter(|t| !t.is_empty())
+                .sorted_by_key(|t| std::cmp::Reverse(t.len()))
+                .map(|t| regex::escape(t))
+                .join("|");
+            Some(Regex::new(&pattern)?)
+        };
+        let (prefix, suffix) = if let Some(post_processor) = field(json, "post_processor") {
+            special_tokens(post_processor)?
+        } else {
+            (vec![], vec![])
+        };
+        let pad_id = field(json, "padding").map(|p| opt_int_field(p, "pad_id")).transpose()?;
+        Ok(Vocabulary {
+            normalizer: field(json, "normalizer").map(Normalizer::from_json).transpose()?,
+            pre_tokenizer: field(json, "pre_tokenizer").map(PreTokenizer::from_json).transpose()?,
+            decoder: field(json, "decoder").map(Decoder::from_json).transpose()?,
+            pad_id: pad_id.flatten().unwrap_or(0),
+            resource,
+            added_tokens,
+            added_tokens_ids,
+            added_tokens_regex,
+            model,
+            prefix,
+            suffix,
+        })
+    }
+
+    /// The JSON resource the vocabulary was built from.
+    pub fn resource(&self) -> &Arc<JsonResource> {
+        &self.resource
+    }
+
+    /// Padding id of the vocabulary, or 0 if it does not specify one.
+    pub fn pad_id(&self) -> i64 {
+        self.pad_id
+    }
+
+    pub fn token_to_id(&self, token: &str) -> Option<i64> {
+        self.added_tokens_ids.get(token).copied().or_else(|| self.model.token_to_id(token))
+    }
+
+    pub fn id_to_token(&self, id: i64) -> Option<&str> {
+        self.added_tokens
+            .get(&id)
+            .map(|t| t.content.as_str())
+            .or_else(|| self.model.id_to_token(id))
+    }
+
+    /// Token ids of a text, surrounded by the post-processor special tokens.
+    /// With `max_length`, the text tokens are truncated to fit.
+    pub fn encode(&self, text: &str, max_length: Option<usize>) -> TractResult<Vec<i64>> {
+        let mut ids = vec![];
+        let mut start = 0;
+        if let Some(regex) = &self.added_tokens_regex {
+            for m in regex.find_iter(text) {
+                self.encode_text(&text[start..m.start()], &mut ids)?;
+                ids.push(self.added_tokens_ids[m.as_str()]);
+                start = m.end();
+            }
+        }
+        self.encode_text(&text[start..], &mut ids)?;
+        if let Some(max_length) = max_length {
+            ids.truncate(max_length.saturating_sub(self.prefix.len() + self.suffix.len()));
+        }
+        Ok(self.prefix.iter().chain(&ids).chain(&self.suffix).copied().collect())
+    }
+
+    fn encode_text(&self, text: &str, ids: &mut Vec<i64>) -> TractResult<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        let text = match &self.normalizer {
+            Some(normalizer) => normalizer.normalize(text),
+            None => text.to_string(),
+        };
+        let words = match &self.pre_tokenizer {
+            Some(pre_tokenizer) => pre_tokenizer.pre_tokenize(&text),
+            None => vec![text],
+        };
+        for word in words.iter().filter(|w| !w.is_empty()) {
+            self.model.tokenize(word, ids)?;
+        }
+        Ok(())
+    }
+
+    /// Text of a sequence of token ids, optionally skipping special tokens.
+    pub fn decode(&self, ids: &[i64], skip_special_tokens: bool) -> TractResult<String> {
+        let mut tokens = vec![];
+        for &id in ids {
+            if let Some(added) = self.added_tokens.get(&id) {
+                if !(skip_special_tokens && added.special) {
+                    tokens.push(added.content.clone());
+                }
+            } else {
+                let token =
+                    self.model.id_to_token(id).with_context(|| format!("Unknown token id {id}"))?;
+                tokens.push(token.to_string());
+            }
+        }
+        Ok(match &self.decoder {
+            Some(decoder) => decoder.decode(tokens).concat(),
+            None => tokens.join(" "),
+        })
+    }
+}
+
+/// Special token ids before and after single sequences in a post-processor.
+fn special_tokens(json: &Json) -> TractResult<(Vec<i64>, Vec<i64>)> {
+    match type_field(json)? {
+        "BertProcessing" | "RobertaProcessing" => {
+            // "cls": ["[CLS]", 101]
+            let id = |name: &str| -> TractResult<i64> {
+                array_field(json, name)?
+                    .get(1)
+                    .and_then(|id| id.as_i64())
+                    .with_context(|| format!("Expected a [token, id] pair for {name:?}"))
+            };
+            Ok((vec![id("cls")?], vec![id("sep")?]))
+        }
+        "TemplateProcessing" => {
+            let specials = field(json, "special_tokens").context("Missing special_tokens")?;
+            let (mut prefix, mut suffix) = (vec![], vec![]);
+            let mut in_suffix = false;
+            for piece in array_field(json, "single")? {
+                if field(piece, "Sequence").is_some() {
+                    in_suffix = true;
+                    continue;
+                }
+                let token = field(piece, "SpecialToken")
+                    .with_context(|| format!("Unexpected template piece {piece}"))?;
+                let name = str_field(token, "id")?;
+                let special = field(specials, name)
+                    .with_context(|| format!("Undeclared special token {name:?}"))?;
+                let ids = array_field(special, "ids")?
+                    .iter()
+                    .map(|id| id.as_i64().with_context(|| format!("Invalid id {id}")))
+                    .collect::<TractResult<Vec<_>>>()?;
+                let target = if in_suffix { &mut suffix } else { &mut prefix };
+                target.extend(ids);
+            }
+            Ok((prefix, suffix))
+        }
+        "ByteLevel" => Ok((vec![], vec![])),
+        "Sequence" => {
+            let (mut prefix, mut suffix) = (vec![], vec![]);
+            for processor in array_field(json, "processors")? {
+                let (p, s) = special_tokens(processor)?;
+                prefix.extend(p);
+                suffix.extend(s);
+            }
+            Ok((prefix, suffix))
+        }
+        other => bail!("Unsupported post-processor {}", other),
+    }
+}
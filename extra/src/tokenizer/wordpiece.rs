@@ -0,0 +1,106 @@
+use super::json::*;
+use super::model::vocab_field;
+use tract_nnef::internal::*;
+
+/// WordPiece: greedy longest-match-first split of words in vocabulary
+/// tokens, words that can not be split are mapped to the unknown token.
+#[derive(Clone, Debug)]
+pub struct WordPiece {
+    pub(super) vocab: HashMap<String, i64>,
+    pub(super) tokens: HashMap<i64, String>,
+    unk_id: i64,
+    continuing_subword_prefix: String,
+    max_input_chars_per_word: usize,
+}
+
+impl WordPiece {
+    pub fn from_json(json: &Json) -> TractResult<WordPiece> {
+        let (vocab, tokens) = vocab_field(json, "vocab")?;
+        let unk = str_field(json, "unk_token")?;
+        let unk_id =
+            *vocab.get(unk).with_context(|| format!("Unknown token {unk:?} not in vocabulary"))?;
+        Ok(WordPiece {
+            vocab,
+            tokens,
+            unk_id,
+            continuing_subword_prefix: opt_str_field(json, "continuing_subword_prefix")?
+                .unwrap_or("##")
+                .to_string(),
+            max_input_chars_per_word: opt_int_field(json, "max_input_chars_per_word")?
+                .unwrap_or(100) as usize,
+        })
+    }
+
+    pub fn tokenize(&self, word: &str, ids: &mut Vec<i64>) -> TractResult<()> {
+        if word.chars().count() > self.max_input_chars_per_word {
+            ids.push(self.unk_id);
+            return Ok(());
+        }
+        let mut pieces = vec![];
+        let mut start = 0;
+        while start < word.len() {
+            let mut end = word.len();
+            let piece = loop {
+                if end == start {
+                    break None;
+                }
+                let candidate = if start > 0 {
+                    format!("{}{}", self.continuing_subword_prefix, &word[start..end])
+                } else {
+                    word[start..end].to_string()
+                };
+                if let Some(id) = self.vocab.get(&candidate) {
+                    break Some(*id);
+                }
+                end = word[..end].char_indices().last().unwrap().0;
+            };
+            let Some(piece) = piece else {
+                ids.push(self.unk_id);
+                return Ok(());
+            };
+            pieces.push(piece);
+            start = end;
+        }
+        ids.extend(pieces);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn tokenize(json: Json, word: &str) -> TractResult<Vec<i64>> {
+        let mut ids = vec![];
+        WordPiece::from_json(&json)?.tokenize(word, &mut ids)?;
+        Ok(ids)
+    }
+
+    fn vocab() -> Json {
+        json!({ "[UNK]": 0, "un": 1, "##aff": 2, "##able": 3, "aff": 4, "##a": 5 })
+    }
+
+    #[test]
+    fn longest_match_first() -> TractResult<()> {
+        let json = json!({ "vocab": vocab(), "unk_token": "[UNK]" });
+        assert_eq!(tokenize(json.clone(), "unaffable")?, vec![1, 2, 3]);
+        assert_eq!(tokenize(json.clone(), "affable")?, vec![4, 3]);
+        assert_eq!(tokenize(json.clone(), "unx")?, vec![0]);
+        assert_eq!(tokenize(json, "x")?, vec![0]);
+        Ok(())
+    }
+
+    #[test]
+    fn long_words_are_unknown() -> TractResult<()> {
+        let json = json!({ "vocab": vocab(), "unk_token": "[UNK]", "max_input_chars_per_word": 5 });
+        assert_eq!(tokenize(json.clone(), "unaff")?, vec![1, 2]);
+        assert_eq!(tokenize(json, "unaffable")?, vec![0]);
+        Ok(())
+    }
+
+    #[test]
+    fn missing_unknown_token() {
+        assert!(WordPiece::from_json(&json!({ "vocab": vocab(), "unk_token": "<unk>" })).is_err());
+    }
+}
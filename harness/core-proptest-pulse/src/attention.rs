@@ -0,0 +1,145 @@
+use proptest::proptest;
+use proptest::test_runner::TestCaseResult;
+use tract_core::ops::array::{MultiBroadcastTo, Trilu};
+use tract_core::ops::einsum::EinSum;
+use tract_core::ops::logic::Iff;
+use tract_core::ops::nn::Softmax;
+
+use super::*;
+
+#[derive(Debug, Clone)]
+struct CausalAttentionProblem {
+    input: Vec<f32>,
+    pulse: usize,
+    delay: usize,
+    window: usize,
+    softmax: bool,
+}
+
+impl Arbitrary for CausalAttentionProblem {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> BoxedStrategy<Self> {
+        (1usize..4, 0usize..3, 1usize..5, any::<bool>())
+            .prop_flat_map(|(pulse, delay, window, softmax)| {
+                let frames = (delay + 1..delay + 10).prop_map(|frames| frames * 2);
+                (Just(pulse), Just(delay), Just(window), Just(softmax), vec(frames))
+            })
+            .prop_map(|(pulse, delay, window, softmax, input)| CausalAttentionProblem {
+                input,
+                pulse,
+                delay,
+                window,
+                softmax,
+            })
+            .boxed()
+    }
+}
+
+fn einsum(model: &mut TypedModel, name: &str, expr: &str, inputs: &[OutletId]) -> OutletId {
+    let op = EinSum::new(expr.parse().unwrap(), f32::datum_type());
+    model.wire_node(name, op, inputs).unwrap()[0]
+}
+
+impl CausalAttentionProblem {
+    // linear attention: out = band(q.k^T).v, with the band allowing each
+    // frame to attend to itself and the window-1 previous frames
+    //
+    // softmax attention: out = softmax(q.k^T masked to -inf out of the
+    // band).v
+    pub fn run(&self) -> TestCaseResult {
+        let mut model = TypedModel::default();
+        let s = model.symbol_table.sym("S");
+        let x = model.add_source("x", f32::fact(dims!(s, 2))).unwrap();
+        let x = model.wire_node("delay", Slice::new(0, self.delay, s), &[x]).unwrap()[0];
+        let wq = model.add_const("wq", rctensor2(&[[1f32, 0.5], [-1.0, 2.0]])).unwrap();
+        let wk = model.add_const("wk", rctensor2(&[[0.5f32, 1.0], [1.0, -0.5]])).unwrap();
+        let wv = model.add_const("wv", rctensor2(&[[2f32, 0.0], [1.0, 1.0]])).unwrap();
+        let q = einsum(&mut model, "q", "sd,de->se", &[x, wq]);
+        let k = einsum(&mut model, "k", "sd,de->se", &[x, wk]);
+        let v = einsum(&mut model, "v", "sd,de->se", &[x, wv]);
+        let scores = einsum(&mut model, "scores", "se,te->st", &[q, k]);
+        let weights = if self.softmax {
+            let len = model.outlet_fact(x).unwrap().shape[0].clone();
+            let ones = model.add_const("ones", rctensor2(&[[true]])).unwrap();
+            let shape = ShapeFact::from_dims([len.clone(), len]);
+            let ones = model.wire_node("square", MultiBroadcastTo { shape }, &[ones]).unwrap()[0];
+            let band = self.band(&mut model, ones);
+            let fill = model.add_const("fill", rctensor2(&[[f32::NEG_INFINITY]])).unwrap();
+            let masked = model.wire_node("masked", Iff, &[band, scores, fill]).unwrap()[0];
+            let softmax = Softmax::new(tvec!(1), f32::datum_type());
+            model.wire_node("softmax", softmax, &[masked]).unwrap()[0]
+        } else {
+            self.band(&mut model, scores)
+        };
+        let output = einsum(&mut model, "output", "st,te->se", &[weights, v]);
+        model.set_output_outlets(&[output]).unwrap();
+
+        let input = arr1(&self.input).into_shape((self.input.len() / 2, 2)).unwrap();
+        proptest_regular_against_pulse(model, self.pulse, input.into_dyn(), 0)
+    }
+
+    fn band(&self, model: &mut TypedModel, wire: OutletId) -> OutletId {
+        let zero = model.add_const("zero", rctensor0(0i64)).unwrap();
+        let causal = model.wire_node("causal", Trilu { upper: false }, &[wire, zero]).unwrap()[0];
+        let back = model.add_const("back", rctensor0(1 - self.window as i64)).unwrap();
+        model.wire_node("band", Trilu { upper: true }, &[causal, back]).unwrap()[0]
+    }
+}
+
+proptest! {
+    #[test]
+    fn proptest(pb in CausalAttentionProblem::arbitrary()) { pb.run().unwrap() }
+}
+
+#[test]
+fn test_window_1() {
+    CausalAttentionProblem {
+        input: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+        pulse: 1,
+        delay: 0,
+        window: 1,
+        softmax: false,
+    }
+    .run()
+    .unwrap()
+}
+
+#[test]
+fn test_window_longer_than_pulse() {
+    CausalAttentionProblem {
+        input: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, -1.0, 0.0, 2.0, 1.0],
+        pulse: 2,
+        delay: 1,
+        window: 3,
+        softmax: false,
+    }
+    .run()
+    .unwrap()
+}
+
+#[test]
+fn test_softmax_window_longer_than_pulse() {
+    CausalAttentionProblem {
+        input: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, -1.0, 0.0, 2.0, 1.0],
+        pulse: 2,
+        delay: 1,
+        window: 3,
+        softmax: true,
+    }
+    .run()
+    .unwrap()
+}
+
+#[test]
+fn unbounded_attention_can_not_pulse() {
+    let mut model = TypedModel::default();
+    let s = model.symbol_table.sym("S");
+    let x = model.add_source("x", f32::fact(dims!(s, 2))).unwrap();
+    let scores = einsum(&mut model, "scores", "sd,td->st", &[x, x]);
+    let zero = model.add_const("zero", rctensor0(0i64)).unwrap();
+    let causal = model.wire_node("causal", Trilu { upper: false }, &[scores, zero]).unwrap();
+    model.set_output_outlets(&causal).unwrap();
+    assert!(PulsedModel::new(&model, s, &1.to_dim()).is_err());
+}
@@ -0,0 +1,143 @@
+use proptest::proptest;
+use proptest::test_runner::TestCaseResult;
+use tract_core::ops::audio::{mel_filterbank, LogMelSpectrogram, Mfcc, Resample, WindowType};
+
+use super::*;
+
+#[derive(Debug, Clone)]
+struct LogMelProblem {
+    input: Vec<f32>,
+    pulse: usize,
+    frame: usize,
+    stride: usize,
+    mfcc: bool,
+}
+
+impl Arbitrary for LogMelProblem {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> BoxedStrategy<Self> {
+        (1usize..8, 1usize..4, 1usize..4, any::<bool>())
+            .prop_flat_map(|(frame, stride, pulse_factor, mfcc)| {
+                (
+                    vec(frame..frame + 20),
+                    Just(pulse_factor * stride),
+                    Just(frame),
+                    Just(stride),
+                    Just(mfcc),
+                )
+            })
+            .prop_map(|(input, pulse, frame, stride, mfcc)| LogMelProblem {
+                input,
+                pulse,
+                frame,
+                stride,
+                mfcc,
+            })
+            .boxed()
+    }
+}
+
+impl LogMelProblem {
+    pub fn run(&self) -> TestCaseResult {
+        let mut model = TypedModel::default();
+        let s = model.symbol_table.sym("S");
+        let a = model.add_source("a", f32::fact([1.to_dim(), s.to_dim()])).unwrap();
+        let log_mel = LogMelSpectrogram {
+            axis: 1,
+            frame: self.frame,
+            stride: self.stride,
+            fft_len: 16,
+            window: Some(WindowType::Hann.generate(self.frame, true).unwrap().into_arc_tensor()),
+            power: 2.,
+            filterbank: mel_filterbank(4, 16, 16000., 0., 8000.).into_arc_tensor(),
+            log_floor: 1e-3,
+        };
+        let wire = if self.mfcc {
+            model.wire_node("mfcc", Mfcc { log_mel, coefficients: 3 }, &[a]).unwrap()
+        } else {
+            model.wire_node("log_mel", log_mel, &[a]).unwrap()
+        };
+        model.set_output_outlets(&wire).unwrap();
+        let input = arr1(&self.input).insert_axis(Axis(0));
+        proptest_regular_against_pulse(model, self.pulse, input.into_dyn(), 1)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ResampleProblem {
+    input: Vec<f32>,
+    pulse: usize,
+    from: usize,
+    to: usize,
+}
+
+impl Arbitrary for ResampleProblem {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> BoxedStrategy<Self> {
+        (1usize..4, 1usize..4, 1usize..3)
+            .prop_flat_map(|(from, to, pulse_factor)| {
+                let down = Resample::new(0, from, to, 2).down;
+                (vec(1usize..40), Just(pulse_factor * down), Just(from), Just(to))
+            })
+            .prop_map(|(input, pulse, from, to)| ResampleProblem { input, pulse, from, to })
+            .boxed()
+    }
+}
+
+impl ResampleProblem {
+    pub fn run(&self) -> TestCaseResult {
+        let mut model = TypedModel::default();
+        let s = model.symbol_table.sym("S");
+        let a = model.add_source("a", f32::fact(&[s]).into()).unwrap();
+        let op = Resample::new(0, self.from, self.to, 2);
+        let resample = model.wire_node("resample", op, &[a]).unwrap();
+        model.set_output_outlets(&resample).unwrap();
+        let input = arr1(&self.input);
+        proptest_regular_against_pulse(model, self.pulse, input.into_dyn(), 0)
+    }
+}
+
+proptest! {
+    #[test]
+    fn proptest_log_mel(pb in LogMelProblem::arbitrary()) { pb.run().unwrap() }
+
+    #[test]
+    fn proptest_resample(pb in ResampleProblem::arbitrary()) { pb.run().unwrap() }
+}
+
+#[test]
+fn log_mel_overlapping_frames() {
+    LogMelProblem {
+        input: (0..20).map(|i| (i as f32 * 0.7).sin()).collect(),
+        pulse: 4,
+        frame: 6,
+        stride: 2,
+        mfcc: false,
+    }
+    .run()
+    .unwrap()
+}
+
+#[test]
+fn mfcc_gapped_frames() {
+    LogMelProblem {
+        input: (0..20).map(|i| (i as f32 * 0.7).sin()).collect(),
+        pulse: 3,
+        frame: 2,
+        stride: 3,
+        mfcc: true,
+    }
+    .run()
+    .unwrap()
+}
+
+#[test]
+fn resample_2_3() {
+    ResampleProblem { input: (0..30).map(|i| i as f32).collect(), pulse: 2, from: 2, to: 3 }
+        .run()
+        .unwrap()
+}
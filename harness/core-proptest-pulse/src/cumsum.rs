@@ -0,0 +1,113 @@
+use proptest::proptest;
+use proptest::test_runner::TestCaseResult;
+use tract_core::ops::array::MultiBroadcastTo;
+use tract_core::ops::math::add;
+use tract_core::ops::scan::*;
+
+use super::*;
+
+#[derive(Debug, Clone)]
+struct CumSumProblem {
+    input: Vec<f32>,
+    pulse: usize,
+    delay: usize,
+    exclusive: bool,
+}
+
+impl Arbitrary for CumSumProblem {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> BoxedStrategy<Self> {
+        (1usize..4, 0usize..4, any::<bool>())
+            .prop_flat_map(|(pulse, delay, exclusive)| {
+                (Just(pulse), Just(delay), Just(exclusive), vec(delay + 1..delay + 10))
+            })
+            .prop_map(|(pulse, delay, exclusive, input)| CumSumProblem {
+                input,
+                pulse,
+                delay,
+                exclusive,
+            })
+            .boxed()
+    }
+}
+
+// same network as the ONNX CumSum expansion
+fn wire_cumsum(
+    model: &mut TypedModel,
+    input: OutletId,
+    axis: usize,
+    exclusive: bool,
+) -> TractResult<OutletId> {
+    let fact = model.outlet_fact(input)?.clone();
+    let mut var_shape = fact.shape.clone();
+    var_shape.set(axis, 1.to_dim());
+    let zero = model.add_const("zero", tensor0(0f32))?;
+    let init = model.wire_node("init", MultiBroadcastTo::new(var_shape.clone()), &[zero])?[0];
+    let mut body = TypedModel::default();
+    let var_fact = f32::fact(var_shape);
+    let x = body.add_source("scan_input", var_fact.clone())?;
+    let acc = body.add_source("acc_input", var_fact)?;
+    let sum = body.wire_node("add", add(), &[x, acc])?[0];
+    body.set_output_outlets(&[sum, acc])?;
+    let info = ScanInfo { axis, chunk: 1 };
+    let scan = Scan::new(
+        body,
+        vec![InputMapping::Scan(info), InputMapping::State],
+        vec![
+            OutputMapping {
+                scan: Some((0, info)),
+                full_dim_hint: None,
+                last_value_slot: None,
+                state: true,
+            },
+            OutputMapping {
+                scan: Some((1, info)),
+                full_dim_hint: None,
+                last_value_slot: None,
+                state: false,
+            },
+        ],
+        0,
+    )?;
+    Ok(model.wire_node("cumsum", scan, &[input, init])?[exclusive as usize])
+}
+
+impl CumSumProblem {
+    pub fn run(&self) -> TestCaseResult {
+        let mut model = TypedModel::default();
+        let s = model.symbol_table.sym("S");
+        let a = model.add_source("a", f32::fact(dims!(1, s, 1))).unwrap();
+        let crop = model.wire_node("delay", Slice::new(1, self.delay, s), &[a]).unwrap();
+        let cumsum = wire_cumsum(&mut model, crop[0], 1, self.exclusive).unwrap();
+        model.set_output_outlets(&[cumsum]).unwrap();
+
+        let input = arr1(&self.input).into_shape((1, self.input.len(), 1)).unwrap();
+        proptest_regular_against_pulse(model, self.pulse, input.into_dyn(), 1)
+    }
+}
+
+proptest! {
+    #[test]
+    fn proptest(pb in CumSumProblem::arbitrary()) { pb.run().unwrap() }
+}
+
+#[test]
+fn test_cumsum_delayed() {
+    CumSumProblem {
+        input: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+        pulse: 2,
+        delay: 1,
+        exclusive: false,
+    }
+    .run()
+    .unwrap()
+}
+
+#[test]
+fn test_cumsum_exclusive() {
+    CumSumProblem { input: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], pulse: 1, delay: 0, exclusive: true }
+        .run()
+        .unwrap()
+}
@@ -0,0 +1,87 @@
+use proptest::proptest;
+use proptest::test_runner::TestCaseResult;
+use tract_core::ops::nn::{Reduce, Reducer};
+
+use super::*;
+
+#[derive(Debug, Clone)]
+struct ReduceProblem {
+    input: Vec<f32>,
+    pulse: usize,
+    delay: usize,
+    reducer: Reducer,
+    all_axes: bool,
+}
+
+impl Arbitrary for ReduceProblem {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> BoxedStrategy<Self> {
+        let reducers = prop_oneof![
+            Just(Reducer::Sum),
+            Just(Reducer::Prod),
+            Just(Reducer::Min),
+            Just(Reducer::Max)
+        ];
+        (1usize..4, 0usize..4, reducers, any::<bool>())
+            .prop_flat_map(|(pulse, delay, reducer, all_axes)| {
+                let frames = (delay + 1..delay + 8).prop_map(|frames| frames * 2);
+                (Just(pulse), Just(delay), Just(reducer), Just(all_axes), vec(frames))
+            })
+            .prop_map(|(pulse, delay, reducer, all_axes, input)| ReduceProblem {
+                input,
+                pulse,
+                delay,
+                reducer,
+                all_axes,
+            })
+            .boxed()
+    }
+}
+
+impl ReduceProblem {
+    pub fn run(&self) -> TestCaseResult {
+        let mut model = TypedModel::default();
+        let s = model.symbol_table.sym("S");
+        let a = model.add_source("a", f32::fact(dims!(1, s, 2))).unwrap();
+        let crop = model.wire_node("delay", Slice::new(1, self.delay, s), &[a]).unwrap();
+        let axes = if self.all_axes { tvec!(1, 2) } else { tvec!(1) };
+        let reduce = model.wire_node("reduce", Reduce::new(axes, self.reducer), &crop).unwrap();
+        model.set_output_outlets(&reduce).unwrap();
+
+        let input = arr1(&self.input).into_shape((1, self.input.len() / 2, 2)).unwrap();
+        proptest_regular_against_pulse(model, self.pulse, input.into_dyn(), 1)
+    }
+}
+
+proptest! {
+    #[test]
+    fn proptest(pb in ReduceProblem::arbitrary()) { pb.run().unwrap() }
+}
+
+#[test]
+fn test_sum_over_stream() {
+    ReduceProblem {
+        input: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+        pulse: 2,
+        delay: 0,
+        reducer: Reducer::Sum,
+        all_axes: false,
+    }
+    .run()
+    .unwrap()
+}
+
+#[test]
+fn test_max_over_delayed_stream() {
+    ReduceProblem {
+        input: vec![9.0, 9.0, 1.0, -2.0, 3.0, 4.0, -5.0, 0.0],
+        pulse: 3,
+        delay: 1,
+        reducer: Reducer::Max,
+        all_axes: true,
+    }
+    .run()
+    .unwrap()
+}
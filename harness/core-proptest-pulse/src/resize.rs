@@ -0,0 +1,78 @@
+use proptest::proptest;
+use proptest::test_runner::TestCaseResult;
+use tract_core::ops::resize::{CoordTransformer, Interpolator, Resize};
+
+use super::*;
+
+#[derive(Debug, Clone)]
+struct ResizeProblem {
+    input: Vec<f32>,
+    pulse: usize,
+    factor: usize,
+    interpolator: Interpolator,
+    coord_transformer: CoordTransformer,
+}
+
+impl Arbitrary for ResizeProblem {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> BoxedStrategy<Self> {
+        let interpolator = prop_oneof![
+            Just(Interpolator::Nearest),
+            Just(Interpolator::Linear),
+            Just(Interpolator::Cubic { a: -0.75 })
+        ];
+        let coord_transformer = prop_oneof![
+            Just(CoordTransformer::Asymmetric),
+            Just(CoordTransformer::HalfPixel),
+            Just(CoordTransformer::PytorchHalfPixel)
+        ];
+        (vec(1usize..10), 3usize..6, 1usize..4, interpolator, coord_transformer)
+            .prop_map(|(input, pulse, factor, interpolator, coord_transformer)| ResizeProblem {
+                input,
+                pulse,
+                factor,
+                interpolator,
+                coord_transformer,
+            })
+            .boxed()
+    }
+}
+
+impl ResizeProblem {
+    pub fn run(&self) -> TestCaseResult {
+        let mut model = TypedModel::default();
+        let s = model.symbol_table.sym("S");
+        let a = model.add_source("a", f32::fact(&[s]).into()).unwrap();
+        let op = Resize {
+            axes: tvec!(0),
+            scales: Some(tvec!(self.factor as f32)),
+            interpolator: self.interpolator,
+            coord_transformer: self.coord_transformer,
+            ..Resize::default()
+        };
+        let resize = model.wire_node("resize", op, &[a]).unwrap();
+        model.set_output_outlets(&resize).unwrap();
+        let input = arr1(&self.input);
+        proptest_regular_against_pulse(model, self.pulse, input.into_dyn(), 0)
+    }
+}
+
+proptest! {
+    #[test]
+    fn proptest(pb in ResizeProblem::arbitrary()) { pb.run().unwrap() }
+}
+
+#[test]
+fn upsample_cubic_half_pixel() {
+    ResizeProblem {
+        input: vec![1.0, -2.0, 3.0, 0.0, 4.0],
+        pulse: 3,
+        factor: 2,
+        interpolator: Interpolator::Cubic { a: -0.75 },
+        coord_transformer: CoordTransformer::HalfPixel,
+    }
+    .run()
+    .unwrap()
+}
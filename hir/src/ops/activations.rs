@@ -71,6 +71,37 @@ activation!(Softplus, |_op, name: &str, model: &mut TypedModel, inputs| {
     Ok(wire)
 });
 
+/// GELU, exact (erf based) or with the tanh approximation.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Gelu {
+    pub approximate: bool,
+}
+
+activation!(Gelu, |op, name: &str, model: &mut TypedModel, inputs| {
+    if op.approximate && model.outlet_fact(inputs[0])?.datum_type == f32::datum_type() {
+        return model.wire_node(name, tract_core::ops::nn::gelu_approximate(), inputs);
+    }
+    cst!(model, inputs, name, half, 0.5);
+    cst!(model, inputs, name, one, 1.0);
+    let cdf = if op.approximate {
+        cst!(model, inputs, name, c1, (2.0 / std::f32::consts::PI).sqrt());
+        cst!(model, inputs, name, c2, 0.044715);
+        let x2 = model.wire_node(name.to_string() + ".x2", square(), inputs)?;
+        let x3 = model.wire_node(name.to_string() + ".x3", mul(), &[x2[0], inputs[0]])?;
+        let wire = model.wire_node(name.to_string() + ".mul_c2", mul(), &[c2, x3[0]])?;
+        let wire = model.wire_node(name.to_string() + ".add_x", add(), &[inputs[0], wire[0]])?;
+        let wire = model.wire_node(name.to_string() + ".mul_c1", mul(), &[c1, wire[0]])?;
+        model.wire_node(name.to_string() + ".tanh", tanh(), &wire)?
+    } else {
+        cst!(model, inputs, name, rsqrt2, std::f32::consts::FRAC_1_SQRT_2);
+        let wire = model.wire_node(name.to_string() + ".mul_rsqrt2", mul(), &[rsqrt2, inputs[0]])?;
+        model.wire_node(name.to_string() + ".erf", erf(), &wire)?
+    };
+    let wire = model.wire_node(name.to_string() + ".plus_one", add(), &[one, cdf[0]])?;
+    let wire = model.wire_node(name.to_string() + ".mul_half", mul(), &[half, wire[0]])?;
+    model.wire_node(name.to_string() + ".mul_x", mul(), &[inputs[0], wire[0]])
+});
+
 #[derive(Debug, Clone, new, Hash)]
 pub struct Softsign;
 
@@ -11,6 +11,7 @@ use tract_itertools::Itertools;
 use tract_tensorflow::tfpb::tensorflow::GraphDef;
 
 use crate::model::Model;
+use crate::trace::Trace;
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct NodeQId(pub TVec<(usize, String)>, pub usize);
@@ -45,6 +46,7 @@ pub struct NodeTags {
     pub labels: Vec<String>,
     pub sections: Vec<Vec<String>>,
     pub profile: Option<Duration>,
+    pub estimate: Option<Duration>,
     pub model_input: Option<String>,
     pub model_output: Option<String>,
     pub outlet_labels: Vec<Vec<String>>,
@@ -65,6 +67,8 @@ impl<'a> std::ops::Add<&'a NodeTags> for &'a NodeTags {
             .collect::<Vec<(Cost, TDim)>>();
         let profile = self.profile.unwrap_or_default() + other.profile.unwrap_or_default();
         let profile = if profile != Duration::default() { Some(profile) } else { None };
+        let estimate = self.estimate.unwrap_or_default() + other.estimate.unwrap_or_default();
+        let estimate = if estimate != Duration::default() { Some(estimate) } else { None };
         let style = self.style.or(other.style);
         let labels = self.labels.iter().chain(other.labels.iter()).cloned().collect();
         let sections = self.sections.iter().chain(other.sections.iter()).cloned().collect();
@@ -79,6 +83,7 @@ impl<'a> std::ops::Add<&'a NodeTags> for &'a NodeTags {
         NodeTags {
             cost,
             profile,
+            estimate,
             style,
             labels,
             sections,
@@ -105,6 +110,7 @@ const EMPTY: NodeTags = NodeTags {
     labels: Vec::new(),
     sections: Vec::new(),
     profile: None,
+    estimate: None,
     model_output: None,
     model_input: None,
     outlet_labels: Vec::new(),
@@ -115,6 +121,9 @@ const EMPTY: NodeTags = NodeTags {
 pub struct Annotations {
     pub tags: HashMap<NodeQId, NodeTags>,
     pub profile_summary: Option<ProfileSummary>,
+    pub trace: Option<Trace>,
+    /// Name of the roofline table used for latency estimates.
+    pub roofline_table: Option<String>,
 }
 
 impl Annotations {
@@ -26,6 +26,7 @@ pub struct DisplayParams {
     pub outlet_labels: bool,
     pub io: Io,
     pub json: bool,
+    pub html: bool,
     pub info: bool,
     pub left_column_width: usize,
 }
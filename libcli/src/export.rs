@@ -1,13 +1,17 @@
 use std::collections::HashMap;
 use serde::Serialize;
 use tract_core::internal::*;
-use crate::annotations::Annotations;
+use crate::annotations::{Annotations, NodeQId};
 use crate::model::Model;
+use crate::trace::TraceEvent;
 
 #[derive(Clone, Debug, Default, Serialize)]
 pub struct GraphPerfInfo {
     nodes: Vec<Node>,
     profiling_info: Option<ProfilingInfo>,
+
+    #[serde(rename = "traceEvents", skip_serializing_if = "Vec::is_empty")]
+    trace_events: Vec<TraceEvent>,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize)]
@@ -24,6 +28,9 @@ pub struct Node {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     secs_per_iter: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimated_secs_per_iter: Option<f64>,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -47,12 +54,136 @@ impl GraphPerfInfo {
                 node_name: id.model(model).unwrap().node_name(id.1).to_string(),
                 op_name: id.model(model).unwrap().node_op_name(id.1).to_string(),
                 secs_per_iter: node.profile.map(|s| s.as_secs_f64()),
+                estimated_secs_per_iter: node.estimate.map(|s| s.as_secs_f64()),
             })
             .collect();
         let profiling_info = annotations.profile_summary.as_ref().map(|summary| ProfilingInfo {
             secs_per_iter: summary.entire.as_secs_f64(),
             iterations: summary.iters,
         });
-        GraphPerfInfo { nodes, profiling_info }
+        let trace_events = annotations.trace.as_ref().map(|t| t.events.clone()).unwrap_or_default();
+        GraphPerfInfo { nodes, profiling_info, trace_events }
+    }
+}
+
+/// Self-describing export of a model, its nested models and annotations.
+#[derive(Clone, Debug, Serialize)]
+pub struct GraphExport {
+    model: ModelExport,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    properties: HashMap<String, String>,
+    profiling_info: Option<ProfilingInfo>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ModelExport {
+    nodes: Vec<NodeExport>,
+    inputs: Vec<(usize, usize)>,
+    outputs: Vec<(usize, usize)>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NodeExport {
+    id: usize,
+    qualified_id: NodeQIdSer,
+    op_name: String,
+    node_name: String,
+    #[serde(rename = "const")]
+    konst: bool,
+    inputs: Vec<(usize, usize)>,
+    outputs: Vec<OutletExport>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    info: Vec<String>,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    cost: HashMap<String, String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secs_per_iter: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimated_secs_per_iter: Option<f64>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    nested: Vec<(String, ModelExport)>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OutletExport {
+    fact: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    labels: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    axes: Vec<String>,
+}
+
+impl GraphExport {
+    pub fn from(model: &dyn Model, annotations: &Annotations) -> TractResult<GraphExport> {
+        let properties =
+            model.properties().iter().map(|(k, v)| (k.clone(), format!("{v:?}"))).collect();
+        let profiling_info = annotations.profile_summary.as_ref().map(|summary| ProfilingInfo {
+            secs_per_iter: summary.entire.as_secs_f64(),
+            iterations: summary.iters,
+        });
+        Ok(GraphExport {
+            model: ModelExport::from(model, &[], annotations)?,
+            properties,
+            profiling_info,
+        })
+    }
+}
+
+impl ModelExport {
+    fn from(
+        model: &dyn Model,
+        scope: &[(usize, String)],
+        annotations: &Annotations,
+    ) -> TractResult<ModelExport> {
+        let outlet = |o: &OutletId| (o.node, o.slot);
+        let mut nodes = vec![];
+        for id in 0..model.nodes_len() {
+            let qid = NodeQId(scope.into(), id);
+            let tags = annotations.tags.get(&qid);
+            let outputs = (0..model.node_output_count(id))
+                .map(|slot| OutletExport {
+                    fact: model.outlet_fact_format((id, slot).into()),
+                    labels: tags
+                        .and_then(|t| t.outlet_labels.get(slot))
+                        .cloned()
+                        .unwrap_or_default(),
+                    axes: tags.and_then(|t| t.outlet_axes.get(slot)).cloned().unwrap_or_default(),
+                })
+                .collect();
+            let mut nested = vec![];
+            for (label, sub) in model.nested_models(id) {
+                let mut scope: TVec<(usize, String)> = scope.into();
+                scope.push((id, label.clone()));
+                nested.push((label, ModelExport::from(sub, &scope, annotations)?));
+            }
+            nodes.push(NodeExport {
+                id,
+                qualified_id: NodeQIdSer(qid.0.iter().cloned().collect(), id),
+                op_name: model.node_op_name(id).to_string(),
+                node_name: model.node_name(id).to_string(),
+                konst: model.node_const(id),
+                inputs: model.node_inputs(id).iter().map(outlet).collect(),
+                outputs,
+                info: model.node_op(id).info()?,
+                cost: tags
+                    .map(|t| {
+                        t.cost.iter().map(|(k, v)| (format!("{k:?}"), format!("{v}"))).collect()
+                    })
+                    .unwrap_or_default(),
+                secs_per_iter: tags.and_then(|t| t.profile).map(|s| s.as_secs_f64()),
+                estimated_secs_per_iter: tags.and_then(|t| t.estimate).map(|s| s.as_secs_f64()),
+                nested,
+            });
+        }
+        Ok(ModelExport {
+            nodes,
+            inputs: model.input_outlets().iter().map(outlet).collect(),
+            outputs: model.output_outlets().iter().map(outlet).collect(),
+        })
     }
 }
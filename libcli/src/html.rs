@@ -0,0 +1,61 @@
+use tract_core::internal::*;
+
+use crate::annotations::Annotations;
+use crate::export::GraphExport;
+use crate::model::Model;
+
+const TEMPLATE: &str = include_str!("html/viewer.html");
+const PLACEHOLDER: &str = "/*TRACT_GRAPH*/null";
+
+/// Render a model and its annotations as a standalone HTML page.
+///
+/// The page embeds the JSON export and a small script for layout and
+/// navigation, so it does not need network access.
+pub fn render(model: &dyn Model, annotations: &Annotations) -> TractResult<String> {
+    let export = GraphExport::from(model, annotations)?;
+    // "</" would end the script element early
+    let json = serde_json::to_string(&export)?.replace("</", "<\\/");
+    Ok(TEMPLATE.replace(PLACEHOLDER, &json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::tests::matmul_and_scan;
+
+    fn embedded_json(html: &str) -> serde_json::Value {
+        let start = html.find("const GRAPH = ").unwrap() + "const GRAPH = ".len();
+        let end = start + html[start..].find(";\n").unwrap();
+        serde_json::from_str(&html[start..end]).unwrap()
+    }
+
+    #[test]
+    fn render_embeds_nested_models() -> TractResult<()> {
+        let model = matmul_and_scan()?;
+        let html = render(&model, &Annotations::from_model(&model)?)?;
+        assert!(!html.contains(PLACEHOLDER));
+        let graph = embedded_json(&html);
+        let nodes = graph["model"]["nodes"].as_array().unwrap();
+        assert_eq!(nodes.len(), model.nodes.len());
+        let cumsum = &nodes[model.node_by_name("cumsum")?.id];
+        assert_eq!(cumsum["inputs"].as_array().unwrap().len(), 2);
+        let (label, body) = (&cumsum["nested"][0][0], &cumsum["nested"][0][1]);
+        assert_eq!(label, "loop");
+        assert_eq!(body["nodes"].as_array().unwrap().len(), 3);
+        assert_eq!(body["nodes"][2]["qualified_id"][0][0][1], "loop");
+        assert_eq!(body["outputs"].as_array().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn render_escapes_script_end() -> TractResult<()> {
+        let mut model = matmul_and_scan()?;
+        let mm = model.node_by_name("mm")?.id;
+        model.node_mut(mm).name = "mm</script>".into();
+        let html = render(&model, &Annotations::from_model(&model)?)?;
+        assert_eq!(html.matches("</script>").count(), TEMPLATE.matches("</script>").count());
+        let graph = embedded_json(&html);
+        assert_eq!(graph["model"]["nodes"][mm]["node_name"], "mm</script>");
+        Ok(())
+    }
+}
@@ -5,8 +5,11 @@ pub mod annotations;
 pub mod display_params;
 pub mod draw;
 pub mod export;
+pub mod html;
 pub mod model;
 pub mod profile;
+pub mod roofline;
 pub mod tensor;
 pub mod terminal;
 pub mod time;
+pub mod trace;
@@ -6,7 +6,8 @@ use crate::annotations::*;
 use crate::model::Model;
 use crate::tensor::make_inputs_for_model;
 use std::any::TypeId;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tract_itertools::Itertools;
 
 pub struct BenchLimits {
     pub max_iters: usize,
@@ -36,6 +37,10 @@ pub fn profile(
     let start = crate::time::now();
     let mut time_accounted_by_inner_nodes = Duration::default();
     while iters < bench_limits.max_iters && start.elapsed() < bench_limits.max_time {
+        let turn_start = Instant::now();
+        if let Some(trace) = dg.trace.as_mut() {
+            trace.recording = iters < trace.max_turns;
+        }
         rec_profiler(
             &mut state,
             dg,
@@ -46,6 +51,16 @@ pub fn profile(
             &mut time_accounted_by_inner_nodes,
             folded,
         )?;
+        if let Some(trace) = dg.trace.as_mut().filter(|t| t.recording) {
+            trace.recording = false;
+            trace.span(
+                format!("turn {iters}"),
+                "turn",
+                turn_start,
+                turn_start.elapsed(),
+                HashMap::default(),
+            );
+        }
         iters += 1;
     }
     let entire = start.elapsed() - time_accounted_by_inner_nodes;
@@ -79,7 +94,12 @@ pub fn rec_profiler(
     let r = state.run_plan_with_eval(
         inputs.clone(),
         |session_state, mut node_state, node, input| {
+            let recording = dg.trace.as_ref().map(|t| t.recording).unwrap_or(false);
+            if recording {
+                session_state.steps = Some(vec![]);
+            }
             // Profile node
+            let trace_start = Instant::now();
             let start = crate::time::now();
             let res = tract_core::plan::eval(
                 session_state,
@@ -89,6 +109,14 @@ pub fn rec_profiler(
             );
             let elapsed = start.elapsed().mul_f32(multiplier.unwrap_or(1) as _);
             let node_id = NodeQId(prefix.into(), node.id);
+            if recording {
+                let trace = dg.trace.as_mut().unwrap();
+                let args = trace_args(&node_id, node);
+                trace.span(&node.name, node.op.name(), trace_start, trace_start.elapsed(), args);
+                for step in session_state.steps.take().unwrap_or_default() {
+                    trace.span(step.name, "step", step.start, step.duration, HashMap::default());
+                }
+            }
             *dg.node_mut(node_id).profile.get_or_insert(Duration::default()) += elapsed;
 
             if !folded {
@@ -151,16 +179,36 @@ fn profile_submodel(
             let scan_inputs = make_inputs_for_model(scan_state.model_state.model())?;
             let multi = scan_state.iteration_count(&input);
 
-            rec_profiler(
-                &mut scan_state.model_state,
-                dg,
-                &scan_inputs,
-                None,
-                &new_prefix,
-                Some(multi),
-                time_accounted_by_inner_nodes,
-                false,
-            )?;
+            if dg.trace.as_ref().map(|t| t.recording).unwrap_or(false) {
+                // recorded turns run the body once per iteration to show them in the timeline
+                for iteration in 0..multi {
+                    let start = Instant::now();
+                    rec_profiler(
+                        &mut scan_state.model_state,
+                        dg,
+                        &scan_inputs,
+                        None,
+                        &new_prefix,
+                        None,
+                        time_accounted_by_inner_nodes,
+                        false,
+                    )?;
+                    let args = trace_args(&NodeQId(prefix.into(), node.id), node);
+                    let name = format!("{} iteration {iteration}", node.name);
+                    dg.trace.as_mut().unwrap().span(name, "loop", start, start.elapsed(), args);
+                }
+            } else {
+                rec_profiler(
+                    &mut scan_state.model_state,
+                    dg,
+                    &scan_inputs,
+                    None,
+                    &new_prefix,
+                    Some(multi),
+                    time_accounted_by_inner_nodes,
+                    false,
+                )?;
+            }
         } else if let Some(typed_model_state) = op_state.downcast_mut::<TypedModelOpState>() {
             let mut new_prefix: TVec<_> = prefix.into();
             new_prefix.push((node.id, "submodel".to_string()));
@@ -181,6 +229,17 @@ fn profile_submodel(
     Ok(())
 }
 
+fn trace_args(qid: &NodeQId, node: &TypedNode) -> HashMap<String, String> {
+    let scope = qid.0.iter().map(|(id, label)| format!("{id}:{label}/")).join("");
+    let mut args = HashMap::default();
+    args.insert("id".to_string(), format!("{scope}{}", qid.1));
+    args.insert(
+        "outputs".to_string(),
+        node.outputs.iter().map(|o| format!("{:?}", o.fact)).join(" "),
+    );
+    args
+}
+
 type ProfilerFn = fn(
     &mut dyn OpState,
     TVec<TValue>,
@@ -235,3 +294,80 @@ pub fn extract_costs(annotations: &mut Annotations, model: &dyn Model) -> TractR
     }
     extract_costs_rec(annotations, model, &[], 1.into())
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::trace::Trace;
+    use tract_core::ops::einsum::EinSum;
+    use tract_core::ops::math::add;
+    use tract_core::ops::scan::*;
+
+    /// A matmul followed by a cumulative sum over its rows, as a Scan.
+    pub(crate) fn matmul_and_scan() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let a = model.add_source("a", f32::fact([3, 2]))?;
+        let w = model.add_const("w", Tensor::zero::<f32>(&[2, 4])?)?;
+        let mm = EinSum::new("mk,kn->mn".parse()?, f32::datum_type());
+        let mm = model.wire_node("mm", mm, &[a, w])?[0];
+        let init = model.add_const("init", Tensor::zero::<f32>(&[1, 4])?)?;
+        let mut body = TypedModel::default();
+        let x = body.add_source("x", f32::fact([1, 4]))?;
+        let acc = body.add_source("acc", f32::fact([1, 4]))?;
+        let sum = body.wire_node("add", add(), &[x, acc])?[0];
+        body.set_output_outlets(&[sum])?;
+        let info = ScanInfo { axis: 0, chunk: 1 };
+        let scan = Scan::new(
+            body,
+            vec![InputMapping::Scan(info), InputMapping::State],
+            vec![OutputMapping {
+                scan: Some((0, info)),
+                full_dim_hint: None,
+                last_value_slot: None,
+                state: true,
+            }],
+            0,
+        )?;
+        let cumsum = model.wire_node("cumsum", scan, &[mm, init])?;
+        model.set_output_outlets(&cumsum)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn trace_records_turns_nodes_loop_iterations_and_steps() -> TractResult<()> {
+        let model = matmul_and_scan()?.into_optimized()?;
+        let mut annotations = Annotations::from_model(&model)?;
+        annotations.trace = Some(Trace::new(2));
+        let limits = BenchLimits { max_iters: 5, max_time: Duration::from_secs(60) };
+        let inputs = tvec!(Tensor::zero::<f32>(&[3, 2])?.into_tvalue());
+        profile(&model, &limits, &mut annotations, &inputs, None, false)?;
+
+        let json: serde_json::Value =
+            serde_json::from_str(&annotations.trace.as_ref().unwrap().to_json()?)?;
+        let events = json["traceEvents"].as_array().unwrap();
+        let count = |cat: &str| events.iter().filter(|e| e["cat"] == cat).count();
+        assert_eq!(count("turn"), 2);
+        assert_eq!(count("loop"), 2 * 3);
+        assert!(count("step") >= 2);
+        assert!(events.iter().all(|e| e["ph"] == "X"));
+        let mm = model.node_by_name("mm")?.id.to_string();
+        assert!(events.iter().any(|e| e["name"] == "mm" && e["args"]["id"] == *mm));
+        let body = format!("{}:loop/", model.node_by_name("cumsum")?.id);
+        let mut ids = events.iter().filter_map(|e| e["args"]["id"].as_str());
+        assert!(ids.any(|id| id.starts_with(&body)));
+        Ok(())
+    }
+
+    #[test]
+    fn trace_stops_recording_after_max_turns() -> TractResult<()> {
+        let model = matmul_and_scan()?.into_optimized()?;
+        let mut annotations = Annotations::from_model(&model)?;
+        annotations.trace = Some(Trace::new(0));
+        let limits = BenchLimits { max_iters: 3, max_time: Duration::from_secs(60) };
+        let inputs = tvec!(Tensor::zero::<f32>(&[3, 2])?.into_tvalue());
+        profile(&model, &limits, &mut annotations, &inputs, None, false)?;
+        assert!(annotations.trace.unwrap().events.is_empty());
+        assert_eq!(annotations.profile_summary.unwrap().iters, 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+use tract_core::internal::*;
+use tract_core::tract_linalg::roofline::RooflineTable;
+
+use crate::annotations::*;
+use crate::model::Model;
+
+/// Estimate per-node latencies from their costs with a roofline model.
+///
+/// Costs must have been extracted first (see `profile::extract_costs`).
+/// Nodes with symbolic costs or shapes get no estimate.
+pub fn estimate_latencies(
+    annotations: &mut Annotations,
+    model: &dyn Model,
+    table: &RooflineTable,
+) -> TractResult<()> {
+    for (qid, tags) in annotations.tags.iter_mut() {
+        if let Some(model) = qid.model(model) {
+            tags.estimate = estimate_node(model, qid.1, &tags.cost, table);
+        }
+    }
+    annotations.roofline_table = Some(table.name.clone());
+    Ok(())
+}
+
+fn estimate_node(
+    model: &dyn Model,
+    node: usize,
+    cost: &[(Cost, TDim)],
+    table: &RooflineTable,
+) -> Option<Duration> {
+    if model.node_const(node) || model.node_op_name(node) == "Source" {
+        return None;
+    }
+    let mut compute = 0f64;
+    let mut bytes = 0f64;
+    for (c, n) in cost {
+        let n = n.to_usize().ok()? as f64;
+        match c {
+            Cost::FMA(dt) => {
+                compute += n / table.fma_rate(*dt).or(table.fma_rate(f32::datum_type()))?
+            }
+            Cost::Div(dt) => {
+                compute += n / table.div_rate(*dt).or(table.div_rate(f32::datum_type()))?
+            }
+            Cost::Params(dt) => bytes += n * dt.size_of() as f64,
+            Cost::Buffer(_) => (),
+        }
+    }
+    let outlets = model
+        .node_inputs(node)
+        .iter()
+        .copied()
+        .chain((0..model.node_output_count(node)).map(|slot| OutletId::new(node, slot)));
+    for outlet in outlets {
+        let fact = model.outlet_typedfact(outlet).ok()?;
+        bytes += (fact.shape.volume().to_usize().ok()? * fact.datum_type.size_of()) as f64;
+    }
+    Some(table.estimate(compute, bytes))
+}
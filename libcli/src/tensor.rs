@@ -334,18 +334,26 @@ pub fn retrieve_or_make_inputs(
                 let input_len = value.shape()[input_pulse_axis];
 
                 // how many pulses do we need to push full result out ?
-                // guess by looking at len and delay of the first output
-                let output_pulse_axis = tract
+                // guess by looking at len and delay of the first streaming output
+                let output_axes = tract
                     .properties()
                     .get("pulse.output_axes")
                     .context("Expect pulse.output_axes property")?
                     .cast_to::<i64>()?
-                    .as_slice::<i64>()?[0] as usize;
-                let output_fact = tract.outlet_typedfact(tract.output_outlets()[0])?;
+                    .into_owned();
+                let (output_ix, output_pulse_axis) = output_axes
+                    .as_slice::<i64>()?
+                    .iter()
+                    .enumerate()
+                    .find(|(_, axis)| **axis >= 0)
+                    .map(|(ix, axis)| (ix, *axis as usize))
+                    .context("Expect at least one streaming output")?;
+                let output_fact = tract.outlet_typedfact(tract.output_outlets()[output_ix])?;
                 let output_pulse =
                     output_fact.shape.get(output_pulse_axis).unwrap().to_usize().unwrap();
                 let output_len = input_len * output_pulse / input_pulse;
-                let output_delay = tract.properties()["pulse.delay"].as_slice::<i64>()?[0] as usize;
+                let output_delay =
+                    tract.properties()["pulse.delay"].as_slice::<i64>()?[output_ix] as usize;
                 let last_frame = output_len + output_delay;
                 let needed_pulses = last_frame.divceil(output_pulse);
                 let mut values = vec![];
@@ -127,6 +127,10 @@ fn render_node_prefixed(
                     let padding = 24usize.saturating_sub(value_visible_len + key.len());
                     key + &*std::iter::repeat(' ').take(padding).join("") + &value.to_string() + " "
                 })
+                .chain(tags.estimate.map(|est| {
+                    let value = format!("{:.3} ms/i", est.as_secs_f64() * 1e3);
+                    format!("Est.{:>20} ", value)
+                }))
                 .peekable(),
         )
     } else {
@@ -339,6 +343,16 @@ pub fn render_summaries(
         for (c, i) in &total.cost {
             println!(" * {:?}: {}", c, render_tdim(i));
         }
+        if let (Some(table), Some(estimate)) = (&annotations.roofline_table, total.estimate) {
+            print!(" * Estimated latency ({}): {}", table, dur_avg(estimate));
+            if let Some(summary) = &annotations.profile_summary {
+                print!(
+                    " (measured/estimated: {:.2})",
+                    summary.sum.as_secs_f64() / estimate.as_secs_f64()
+                );
+            }
+            println!();
+        }
     }
 
     if options.profile {
@@ -0,0 +1,73 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tract_core::internal::*;
+
+/// Timeline of profiling runs, in Chrome trace-event format.
+///
+/// Every turn, node evaluation, nested loop iteration and op inner step
+/// becomes a complete ("X") event. Events nest by time on a single track, so
+/// the result loads as-is in chrome://tracing or Perfetto.
+#[derive(Clone, Debug)]
+pub struct Trace {
+    origin: Instant,
+    /// Number of profiling turns to record.
+    pub max_turns: usize,
+    /// Set by the profiler while the current turn is recorded.
+    pub recording: bool,
+    pub events: Vec<TraceEvent>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TraceEvent {
+    name: String,
+    cat: String,
+    ph: &'static str,
+    /// Start, in microseconds from the trace origin.
+    ts: f64,
+    /// Duration, in microseconds.
+    dur: f64,
+    pid: usize,
+    tid: usize,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    args: HashMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct TraceFile<'a> {
+    #[serde(rename = "traceEvents")]
+    trace_events: &'a [TraceEvent],
+    #[serde(rename = "displayTimeUnit")]
+    display_time_unit: &'static str,
+}
+
+impl Trace {
+    pub fn new(max_turns: usize) -> Trace {
+        Trace { origin: Instant::now(), max_turns, recording: false, events: vec![] }
+    }
+
+    pub fn span(
+        &mut self,
+        name: impl Into<String>,
+        cat: impl Into<String>,
+        start: Instant,
+        duration: Duration,
+        args: HashMap<String, String>,
+    ) {
+        self.events.push(TraceEvent {
+            name: name.into(),
+            cat: cat.into(),
+            ph: "X",
+            ts: start.saturating_duration_since(self.origin).as_secs_f64() * 1e6,
+            dur: duration.as_secs_f64() * 1e6,
+            pid: 0,
+            tid: 0,
+            args,
+        })
+    }
+
+    pub fn to_json(&self) -> TractResult<String> {
+        let file = TraceFile { trace_events: &self.events, display_time_unit: "ns" };
+        Ok(serde_json::to_string(&file)?)
+    }
+}
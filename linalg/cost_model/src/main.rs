@@ -1,5 +1,6 @@
 use pbr::ProgressBar;
 use tract_data::internal::*;
+use tract_linalg::roofline::RooflineTable;
 use tract_linalg::{frame::MatMatMul, mmm::FusedSpec};
 
 use rand::prelude::*;
@@ -163,6 +164,29 @@ fn measure_add_mat_mul(bencher: &Bencher, mm: &dyn MatMatMul, m: usize, k: usize
     }
 }
 
+fn measure_roofline(bencher: &Bencher, name: &str) -> RooflineTable {
+    let mut table = RooflineTable::generic();
+    table.name = name.to_string();
+    let (m, k, n) = (256, 256, 256);
+    table.fma.clear();
+    for dt in [DatumType::F16, DatumType::F32] {
+        let mm = tract_linalg::ops().mmm(dt, dt, dt, Some(m), Some(k), Some(n)).unwrap();
+        let time = measure_add_mat_mul(bencher, &*mm, m, k, n);
+        table.fma.push((dt, (m * k * n) as f64 / time));
+    }
+    let len = 1024 * 1024;
+    let time =
+        bencher.run_bench(|| vec![vec![1.5f32; len]], |v| v.iter_mut().for_each(|x| *x = 1.0 / *x));
+    table.div = vec![(DatumType::F32, len as f64 / time)];
+    let len = 64 * 1024 * 1024;
+    let time = bencher.run_bench(
+        || vec![(vec![1u8; len], vec![0u8; len])],
+        |(src, dst)| dst.copy_from_slice(src),
+    );
+    table.memory_bandwidth = 2. * len as f64 / time;
+    table
+}
+
 #[derive(Clone, Debug)]
 enum SamplingStrategy {
     Random(Range<usize>),
@@ -358,6 +382,11 @@ fn main() {
                 .help("Minimum number of chunks"),
         )
         .subcommand(App::new("list-models"))
+        .subcommand(
+            App::new("roofline")
+                .about("Measure a throughput table for roofline latency estimates")
+                .arg(Arg::new("name").long("name").takes_value(true).default_value("measured")),
+        )
         .subcommand(
             App::new("time")
                 .arg(Arg::new("mm").long("mm").help("Filter kernels").takes_value(true))
@@ -456,6 +485,9 @@ fn main() {
             };
             Dataset::make_dataset(&bencher, inputs, &mmms).save(sub.value_of("name").unwrap());
         }
+        Some(("roofline", sub)) => {
+            print!("{}", measure_roofline(&bencher, sub.value_of("name").unwrap()));
+        }
         Some(("time", sub)) => {
             let mut mmms = impls.clone();
             if let Some(mm) = sub.value_of("mm") {
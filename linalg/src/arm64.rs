@@ -8,12 +8,18 @@ mod cortex_a55;
 //mod cortex_a73;
 pub use arm64simd::*;
 
+mod depthwise;
+pub use depthwise::*;
+mod sparse;
+pub use sparse::*;
 mod leaky_relu;
 pub use leaky_relu::*;
 
 use crate::Ops;
 use crate::f16;
 
+use crate::frame::depthwise::DepthWiseKer;
+use crate::frame::sparse::SparseMatMulKer;
 use crate::frame::element_wise::ElementWiseKer;
 use crate::frame::mmm::kernel::MatMatMulKer;
 
@@ -229,10 +235,14 @@ pub fn plug(ops: &mut Ops) {
     ops.leaky_relu_f32 = Box::new(|| arm64simd_leaky_relu_f32_8n::ew());
     ops.sigmoid_f32 = Box::new(|| arm64simd_sigmoid_f32_4n::ew());
     ops.tanh_f32 = Box::new(|| arm64simd_tanh_f32_4n::ew());
+    ops.depthwise_f32 = Box::new(|| arm64simd_depthwise_f32_8n::dw());
+    ops.depthwise_i8 = Box::new(|| arm64simd_depthwise_i8_8n::dw());
+    ops.sparse_f32 = Box::new(|| arm64simd_sparse_f32_4x8::sparse());
     #[cfg(not(feature = "no_fp16"))]
     if has_fp16() {
-        log::info!("ARMv8.2 tanh_f16 and sigmoid_f16 activated");
+        log::info!("ARMv8.2 tanh_f16, sigmoid_f16 and depthwise_f16 activated");
         ops.leaky_relu_f16 = Box::new(|| arm64fp16_leaky_relu_f16_16n::ew());
+        ops.depthwise_f16 = Box::new(|| arm64fp16_depthwise_f16_16n::dw());
         ops.tanh_f16 = Box::new(|| arm64fp16_tanh_f16_8n::ew());
         ops.sigmoid_f16 = Box::new(|| arm64fp16_sigmoid_f16_8n::ew());
     } else {
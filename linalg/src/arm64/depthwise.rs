@@ -0,0 +1,200 @@
+use tract_data::internal::f16;
+
+dw_impl_wrap!(
+    f32,
+    f32,
+    arm64simd_depthwise_f32_8n,
+    8,
+    #[inline(never)]
+    fn mul_add(acc: &mut [f32], x: &[f32], k: &[f32]) {
+        assert!(!acc.is_empty());
+        assert_eq!(acc.len() % 8, 0);
+        assert!(x.len() >= acc.len() && k.len() >= acc.len());
+        unsafe {
+            std::arch::asm!("
+                1:
+                    ldp q0, q1, [{acc}]
+                    ldp q2, q3, [{x}], #32
+                    ldp q4, q5, [{k}], #32
+                    fmla v0.4s, v2.4s, v4.4s
+                    fmla v1.4s, v3.4s, v5.4s
+                    stp q0, q1, [{acc}], #32
+                    subs {len}, {len}, 8
+                    bne 1b
+            ",
+            acc = inout(reg) acc.as_mut_ptr() => _,
+            x = inout(reg) x.as_ptr() => _,
+            k = inout(reg) k.as_ptr() => _,
+            len = inout(reg) acc.len() => _,
+            out("q0") _, out("q1") _, out("q2") _, out("q3") _, out("q4") _, out("q5") _,
+            );
+        }
+    },
+    #[inline(never)]
+    fn mul_add_scalar(acc: &mut [f32], x: &[f32], k: f32) {
+        assert!(!acc.is_empty());
+        assert_eq!(acc.len() % 8, 0);
+        assert!(x.len() >= acc.len());
+        unsafe {
+            std::arch::asm!("
+                dup v6.4s, {k:v}.s[0]
+                1:
+                    ldp q0, q1, [{acc}]
+                    ldp q2, q3, [{x}], #32
+                    fmla v0.4s, v2.4s, v6.4s
+                    fmla v1.4s, v3.4s, v6.4s
+                    stp q0, q1, [{acc}], #32
+                    subs {len}, {len}, 8
+                    bne 1b
+            ",
+            k = in(vreg) k,
+            acc = inout(reg) acc.as_mut_ptr() => _,
+            x = inout(reg) x.as_ptr() => _,
+            len = inout(reg) acc.len() => _,
+            out("q0") _, out("q1") _, out("q2") _, out("q3") _, out("q6") _,
+            );
+        }
+    }
+);
+
+dw_impl_wrap!(
+    f16,
+    f16,
+    arm64fp16_depthwise_f16_16n,
+    16,
+    #[inline(never)]
+    fn mul_add(acc: &mut [f16], x: &[f16], k: &[f16]) {
+        assert!(!acc.is_empty());
+        assert_eq!(acc.len() % 16, 0);
+        assert!(x.len() >= acc.len() && k.len() >= acc.len());
+        #[target_feature(enable = "fp16")]
+        unsafe fn run(acc: &mut [f16], x: &[f16], k: &[f16]) {
+            std::arch::asm!("
+                1:
+                    ldp q0, q1, [{acc}]
+                    ldp q2, q3, [{x}], #32
+                    ldp q4, q5, [{k}], #32
+                    fmla v0.8h, v2.8h, v4.8h
+                    fmla v1.8h, v3.8h, v5.8h
+                    stp q0, q1, [{acc}], #32
+                    subs {len}, {len}, 16
+                    bne 1b
+            ",
+            acc = inout(reg) acc.as_mut_ptr() => _,
+            x = inout(reg) x.as_ptr() => _,
+            k = inout(reg) k.as_ptr() => _,
+            len = inout(reg) acc.len() => _,
+            out("q0") _, out("q1") _, out("q2") _, out("q3") _, out("q4") _, out("q5") _,
+            );
+        }
+        unsafe { run(acc, x, k) }
+    },
+    #[inline(never)]
+    fn mul_add_scalar(acc: &mut [f16], x: &[f16], k: f16) {
+        assert!(!acc.is_empty());
+        assert_eq!(acc.len() % 16, 0);
+        assert!(x.len() >= acc.len());
+        #[target_feature(enable = "fp16")]
+        unsafe fn run(acc: &mut [f16], x: &[f16], k: f16) {
+            std::arch::asm!("
+                dup v6.8h, {k:v}.h[0]
+                1:
+                    ldp q0, q1, [{acc}]
+                    ldp q2, q3, [{x}], #32
+                    fmla v0.8h, v2.8h, v6.8h
+                    fmla v1.8h, v3.8h, v6.8h
+                    stp q0, q1, [{acc}], #32
+                    subs {len}, {len}, 16
+                    bne 1b
+            ",
+            k = in(vreg) k.to_bits(),
+            acc = inout(reg) acc.as_mut_ptr() => _,
+            x = inout(reg) x.as_ptr() => _,
+            len = inout(reg) acc.len() => _,
+            out("q0") _, out("q1") _, out("q2") _, out("q3") _, out("q6") _,
+            );
+        }
+        unsafe { run(acc, x, k) }
+    }
+);
+
+dw_impl_wrap!(
+    i8,
+    i32,
+    arm64simd_depthwise_i8_8n,
+    8,
+    #[inline(never)]
+    fn mul_add(acc: &mut [i32], x: &[i8], k: &[i32]) {
+        assert!(!acc.is_empty());
+        assert_eq!(acc.len() % 8, 0);
+        assert!(x.len() >= acc.len() && k.len() >= acc.len());
+        unsafe {
+            std::arch::asm!("
+                1:
+                    ld1 {{v2.8b}}, [{x}], #8
+                    sxtl v2.8h, v2.8b
+                    sxtl v3.4s, v2.4h
+                    sxtl2 v2.4s, v2.8h
+                    ldp q0, q1, [{acc}]
+                    ldp q4, q5, [{k}], #32
+                    mla v0.4s, v3.4s, v4.4s
+                    mla v1.4s, v2.4s, v5.4s
+                    stp q0, q1, [{acc}], #32
+                    subs {len}, {len}, 8
+                    bne 1b
+            ",
+            acc = inout(reg) acc.as_mut_ptr() => _,
+            x = inout(reg) x.as_ptr() => _,
+            k = inout(reg) k.as_ptr() => _,
+            len = inout(reg) acc.len() => _,
+            out("q0") _, out("q1") _, out("q2") _, out("q3") _, out("q4") _, out("q5") _,
+            );
+        }
+    },
+    #[inline(never)]
+    fn mul_add_scalar(acc: &mut [i32], x: &[i8], k: i32) {
+        assert!(!acc.is_empty());
+        assert_eq!(acc.len() % 8, 0);
+        assert!(x.len() >= acc.len());
+        unsafe {
+            std::arch::asm!("
+                dup v6.4s, {k:w}
+                1:
+                    ld1 {{v2.8b}}, [{x}], #8
+                    sxtl v2.8h, v2.8b
+                    sxtl v3.4s, v2.4h
+                    sxtl2 v2.4s, v2.8h
+                    ldp q0, q1, [{acc}]
+                    mla v0.4s, v3.4s, v6.4s
+                    mla v1.4s, v2.4s, v6.4s
+                    stp q0, q1, [{acc}], #32
+                    subs {len}, {len}, 8
+                    bne 1b
+            ",
+            k = in(reg) k,
+            acc = inout(reg) acc.as_mut_ptr() => _,
+            x = inout(reg) x.as_ptr() => _,
+            len = inout(reg) acc.len() => _,
+            out("q0") _, out("q1") _, out("q2") _, out("q3") _, out("q6") _,
+            );
+        }
+    }
+);
+
+#[cfg(test)]
+pub mod test_arm64simd_depthwise_f32_8n {
+    use super::*;
+    depthwise_frame_tests!(true, f32, f32, arm64simd_depthwise_f32_8n);
+}
+
+#[cfg(test)]
+pub mod test_arm64fp16_depthwise_f16_16n {
+    use super::*;
+    depthwise_frame_tests!(crate::arm64::has_fp16(), f16, f16, arm64fp16_depthwise_f16_16n);
+}
+
+#[cfg(test)]
+pub mod test_arm64simd_depthwise_i8_8n {
+    use super::*;
+    depthwise_frame_tests!(true, i8, i32, arm64simd_depthwise_i8_8n);
+}
@@ -0,0 +1,67 @@
+sparse_impl_wrap!(
+    arm64simd_sparse_f32_4x8,
+    4,
+    8,
+    #[inline(never)]
+    unsafe fn kernel(
+        c: *mut f32,
+        c_rs: usize,
+        b: *const f32,
+        b_rs: usize,
+        cols: &[u32],
+        values: &[f32],
+    ) {
+        debug_assert_eq!(values.len(), cols.len() * 4);
+        std::arch::asm!("
+            eor v0.16b, v0.16b, v0.16b
+            eor v1.16b, v1.16b, v1.16b
+            eor v2.16b, v2.16b, v2.16b
+            eor v3.16b, v3.16b, v3.16b
+            eor v4.16b, v4.16b, v4.16b
+            eor v5.16b, v5.16b, v5.16b
+            eor v6.16b, v6.16b, v6.16b
+            eor v7.16b, v7.16b, v7.16b
+            cbz {len}, 3f
+            2:
+                ldr {col:w}, [{cols}], #4
+                madd {col}, {col}, {b_rs}, {b}
+                ldp q8, q9, [{col}]
+                ldr q10, [{values}], #16
+                fmla v0.4s, v8.4s, v10.s[0]
+                fmla v1.4s, v9.4s, v10.s[0]
+                fmla v2.4s, v8.4s, v10.s[1]
+                fmla v3.4s, v9.4s, v10.s[1]
+                fmla v4.4s, v8.4s, v10.s[2]
+                fmla v5.4s, v9.4s, v10.s[2]
+                fmla v6.4s, v8.4s, v10.s[3]
+                fmla v7.4s, v9.4s, v10.s[3]
+                subs {len}, {len}, 1
+                bne 2b
+            3:
+            stp q0, q1, [{c}]
+            add {c}, {c}, {c_rs}
+            stp q2, q3, [{c}]
+            add {c}, {c}, {c_rs}
+            stp q4, q5, [{c}]
+            add {c}, {c}, {c_rs}
+            stp q6, q7, [{c}]
+        ",
+        c = inout(reg) c => _,
+        c_rs = in(reg) c_rs * 4,
+        b = in(reg) b,
+        b_rs = in(reg) b_rs * 4,
+        cols = inout(reg) cols.as_ptr() => _,
+        values = inout(reg) values.as_ptr() => _,
+        len = inout(reg) cols.len() => _,
+        col = out(reg) _,
+        out("q0") _, out("q1") _, out("q2") _, out("q3") _, out("q4") _, out("q5") _,
+        out("q6") _, out("q7") _, out("q8") _, out("q9") _, out("q10") _,
+        );
+    }
+);
+
+#[cfg(test)]
+pub mod test_arm64simd_sparse_f32_4x8 {
+    use super::*;
+    sparse_frame_tests!(true, arm64simd_sparse_f32_4x8);
+}
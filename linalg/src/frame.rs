@@ -1,4 +1,6 @@
 #[macro_use]
+pub mod depthwise;
+#[macro_use]
 pub mod element_wise;
 #[macro_use]
 pub mod lut;
@@ -6,6 +8,8 @@ pub mod lut;
 pub mod mmm;
 pub mod pack;
 #[macro_use]
+pub mod sparse;
+#[macro_use]
 pub mod leaky_relu;
 #[macro_use]
 pub mod sigmoid;
@@ -16,5 +20,7 @@ pub mod element_wise_helper;
 pub use pack::Packer;
 pub use pack::PackingWriter;
 
+pub use self::depthwise::{DepthWise, DepthWiseImpl};
 pub use self::element_wise::{ ElementWise, ElementWiseImpl};
 pub use self::mmm::{MatMatMul, MatMatMulImpl};
+pub use self::sparse::{BlockSparseMatrix, SparseMatMul, SparseMatMulImpl};
@@ -0,0 +1,181 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::ops::Mul;
+
+use num_traits::AsPrimitive;
+
+use crate::LADatum;
+
+/// Multiply-accumulate primitives of depthwise convolutions.
+///
+/// Accumulators are `TA`, inputs are `TI` and get widened to `TA` (i8 inputs
+/// accumulate in i32). Kernel values are expected in the accumulator type.
+pub trait DepthWise<TI, TA>: Send + Sync + Debug + dyn_clone::DynClone
+where
+    TI: Copy + Debug + Send + Sync,
+    TA: Copy + Debug + Send + Sync,
+{
+    fn name(&self) -> &'static str;
+    /// `acc[i] += x[i] * k[i]`, used when channels are the innermost axis.
+    fn mul_add(&self, acc: &mut [TA], x: &[TI], k: &[TA]);
+    /// `acc[i] += x[i] * k`, used to scan a row of a single channel.
+    fn mul_add_scalar(&self, acc: &mut [TA], x: &[TI], k: TA);
+}
+
+dyn_clone::clone_trait_object!(<TI, TA> DepthWise<TI, TA> where TI: Copy, TA: Copy);
+
+#[derive(Debug, Clone, new)]
+pub struct DepthWiseImpl<K, TI, TA>
+where
+    TI: LADatum + AsPrimitive<TA>,
+    TA: LADatum + Mul<Output = TA>,
+    K: DepthWiseKer<TI, TA> + Clone,
+{
+    phantom: PhantomData<(K, TI, TA)>,
+}
+
+impl<K, TI, TA> DepthWise<TI, TA> for DepthWiseImpl<K, TI, TA>
+where
+    TI: LADatum + AsPrimitive<TA>,
+    TA: LADatum + Mul<Output = TA>,
+    K: DepthWiseKer<TI, TA> + Clone,
+{
+    fn name(&self) -> &'static str {
+        K::name()
+    }
+
+    fn mul_add(&self, acc: &mut [TA], x: &[TI], k: &[TA]) {
+        assert!(x.len() >= acc.len() && k.len() >= acc.len());
+        let len = acc.len();
+        let main = len / K::nr() * K::nr();
+        if main > 0 {
+            K::mul_add(&mut acc[..main], &x[..main], &k[..main]);
+        }
+        for i in main..len {
+            acc[i] += x[i].as_() * k[i];
+        }
+    }
+
+    fn mul_add_scalar(&self, acc: &mut [TA], x: &[TI], k: TA) {
+        assert!(x.len() >= acc.len());
+        let len = acc.len();
+        let main = len / K::nr() * K::nr();
+        if main > 0 {
+            K::mul_add_scalar(&mut acc[..main], &x[..main], k);
+        }
+        for i in main..len {
+            acc[i] += x[i].as_() * k;
+        }
+    }
+}
+
+pub trait DepthWiseKer<TI, TA>:
+    Send + Sync + Debug + dyn_clone::DynClone + Clone + 'static
+where
+    TI: LADatum + AsPrimitive<TA>,
+    TA: LADatum + Mul<Output = TA>,
+{
+    fn name() -> &'static str;
+    fn nr() -> usize;
+    /// Slices are the same length, a non-zero multiple of nr.
+    fn mul_add(acc: &mut [TA], x: &[TI], k: &[TA]);
+    /// Slices are the same length, a non-zero multiple of nr.
+    fn mul_add_scalar(acc: &mut [TA], x: &[TI], k: TA);
+    fn dw() -> Box<dyn DepthWise<TI, TA>> {
+        Box::new(DepthWiseImpl::<Self, TI, TA>::new())
+    }
+}
+
+#[allow(unused_macros)]
+macro_rules! dw_impl_wrap {
+    ($ti: ident, $ta: ident, $func: ident, $nr: expr, $mul_add: item, $mul_add_scalar: item) => {
+        #[derive(Copy, Clone, Debug)]
+        #[allow(non_camel_case_types)]
+        pub struct $func;
+
+        impl crate::frame::depthwise::DepthWiseKer<$ti, $ta> for $func {
+            #[inline(always)]
+            fn name() -> &'static str {
+                stringify!($func)
+            }
+            #[inline(always)]
+            fn nr() -> usize {
+                $nr
+            }
+            $mul_add
+            $mul_add_scalar
+        }
+    };
+}
+
+#[cfg(test)]
+#[macro_use]
+pub mod test {
+    use super::*;
+    use proptest::test_runner::{TestCaseError, TestCaseResult};
+    use tract_data::internal::*;
+
+    #[macro_export]
+    macro_rules! depthwise_frame_tests {
+        ($cond:expr, $ti: ty, $ta: ty, $ker:ty) => {
+            proptest::proptest! {
+                #[test]
+                fn prop_mul_add(pb in proptest::collection::vec((-10i8..10, -10i8..10, -10i8..10), 0..100)) {
+                    if $cond {
+                        $crate::frame::depthwise::test::test_mul_add::<$ker, $ti, $ta>(&pb, None).unwrap()
+                    }
+                }
+
+                #[test]
+                fn prop_mul_add_scalar(pb in proptest::collection::vec((-10i8..10, -10i8..10, -10i8..10), 0..100), k in -10i8..10) {
+                    if $cond {
+                        $crate::frame::depthwise::test::test_mul_add::<$ker, $ti, $ta>(&pb, Some(k)).unwrap()
+                    }
+                }
+            }
+
+            #[test]
+            fn one_block() {
+                if $cond {
+                    let pb = (0..<$ker as $crate::frame::depthwise::DepthWiseKer<$ti, $ta>>::nr())
+                        .map(|i| (i as i8, 1 - i as i8, 2))
+                        .collect::<Vec<_>>();
+                    $crate::frame::depthwise::test::test_mul_add::<$ker, $ti, $ta>(&pb, None).unwrap();
+                    $crate::frame::depthwise::test::test_mul_add::<$ker, $ti, $ta>(&pb, Some(-3)).unwrap();
+                }
+            }
+        };
+    }
+
+    fn cast<T: Datum>(values: impl Iterator<Item = i8>) -> Vec<T> {
+        let values = tensor1(&values.collect::<Vec<i8>>());
+        values.cast_to::<T>().unwrap().as_slice::<T>().unwrap().to_vec()
+    }
+
+    /// Checks the kernel against a naive evaluation over (acc, x, k) triplets.
+    pub fn test_mul_add<K: DepthWiseKer<TI, TA>, TI, TA>(
+        pb: &[(i8, i8, i8)],
+        scalar_k: Option<i8>,
+    ) -> TestCaseResult
+    where
+        TI: LADatum + AsPrimitive<TA>,
+        TA: LADatum + Mul<Output = TA>,
+    {
+        crate::setup_test_logger();
+        let acc: Vec<TA> = cast(pb.iter().map(|t| t.0));
+        let x: Vec<TI> = cast(pb.iter().map(|t| t.1));
+        let k: Vec<TA> = cast(pb.iter().map(|t| scalar_k.unwrap_or(t.2)));
+        let expected = (0..pb.len()).map(|i| acc[i] + x[i].as_() * k[i]).collect::<Vec<TA>>();
+        let mut found = acc;
+        let dw = K::dw();
+        if let Some(k) = k.first().filter(|_| scalar_k.is_some()) {
+            dw.mul_add_scalar(&mut found, &x, *k);
+        } else {
+            dw.mul_add(&mut found, &x, &k);
+        }
+        tensor1(&found)
+            .close_enough(&tensor1(&expected), true)
+            .map_err(|e| TestCaseError::fail(e.root_cause().to_string()))?;
+        Ok(())
+    }
+}
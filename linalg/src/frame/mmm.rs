@@ -25,6 +25,9 @@ pub fn no_prefetch(_ptr: *const u8, _len: usize) {}
 
 macro_rules! MMMKernel {
     ($ti:ident, $func:ident; $mr: expr, $nr: expr; $alignment_bytes_packed_a: expr, $alignment_bytes_packed_b: expr; $end_padding_packed_a: expr, $end_padding_packed_b: expr ; $prefetch: ident, $cond: expr) => {
+        MMMKernel!($ti, $func; $mr, $nr; $alignment_bytes_packed_a, $alignment_bytes_packed_b; $end_padding_packed_a, $end_padding_packed_b; $prefetch, $cond; activations: false);
+    };
+    ($ti:ident, $func:ident; $mr: expr, $nr: expr; $alignment_bytes_packed_a: expr, $alignment_bytes_packed_b: expr; $end_padding_packed_a: expr, $end_padding_packed_b: expr ; $prefetch: ident, $cond: expr; activations: $activations: expr) => {
         paste! {
             mod [<sys_ $func>] {
                 use crate::frame::mmm::*;
@@ -76,6 +79,9 @@ macro_rules! MMMKernel {
                 fn prefetch(ptr: *const u8, len: usize) {
                     ($prefetch)(ptr, len)
                 }
+                fn can_fuse(spec: &FusedSpec) -> bool {
+                    $activations || !matches!(spec, FusedSpec::Activation(_))
+                }
             }
         }
         test_mmm_kernel!($ti, $func, $cond);
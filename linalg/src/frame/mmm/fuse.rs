@@ -36,6 +36,45 @@ impl BinOp {
     }
 }
 
+/// Activations applied element-wise to the accumulators.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Activation {
+    Sigmoid,
+    Tanh,
+    /// GELU, tanh approximation.
+    Gelu,
+    /// x·sigmoid(x), a.k.a. swish.
+    Silu,
+    HardSwish,
+    LeakyRelu(f32),
+}
+
+/// 2·sqrt(2/π), GELU being x·sigmoid(2·sqrt(2/π)·(x + 0.044715·x³)).
+pub const GELU_C1: f32 = 1.595_769_2;
+pub const GELU_C2: f32 = GELU_C1 * 0.044715;
+
+impl Activation {
+    /// Reference implementation, used by the generic kernels.
+    pub fn apply(&self, x: f32) -> f32 {
+        use crate::generic::sigmoid::ssigmoid;
+        use crate::generic::tanh::stanh;
+        match self {
+            Activation::Sigmoid => ssigmoid(x),
+            Activation::Tanh => stanh(x),
+            Activation::Gelu => x * ssigmoid(x * (GELU_C1 + GELU_C2 * x * x)),
+            Activation::Silu => x * ssigmoid(x),
+            Activation::HardSwish => x * (x * (1. / 6.) + 0.5).clamp(0., 1.),
+            Activation::LeakyRelu(alpha) => {
+                if x > 0. {
+                    x
+                } else {
+                    alpha * x
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum FusedSpec<'t> {
     BinScalar(&'t Tensor, BinOp),
@@ -46,6 +85,7 @@ pub enum FusedSpec<'t> {
     QScale(isize, RoundingPolicy, i32),
     RoundingShiftRight(usize, RoundingPolicy),
     ShiftLeft(usize),
+    Activation(Activation),
     Store(OutputStore),
     AddMatMul { k: usize, a: InputStore, b: InputStore },
 }
@@ -95,6 +135,12 @@ pub enum FusedKerSpec<TI: Copy> {
     QScale(isize, RoundingPolicy, i32),         // jump_to:q_scale
     RoundingShiftRight(usize, RoundingPolicy),  // jump_to:q_shr
     ShiftLeft(usize),                           // jump_to:q_shl
+    Sigmoid,                                    // jump_to:sigmoid
+    Tanh,                                       // jump_to:tanh
+    Gelu,                                       // jump_to:gelu
+    Silu,                                       // jump_to:silu
+    HardSwish,                                  // jump_to:hard_swish
+    LeakyRelu(TI),                              // jump_to:leaky_relu
     AddUnicast(OutputStoreKer),                 // jump_to:add_unicast
     AddRowColProducts(*const TI, *const TI),    // jump_to:add_row_col_products
     Store(OutputStoreKer),                      // jump_to:store
@@ -136,7 +182,7 @@ pub mod test {
                 #[allow(unused_imports)]
                 use $crate::frame::mmm::fuse::test;
                 use $crate::frame::mmm::fuse::test::tile;
-                use $crate::frame::mmm::fuse::FusedKerSpec;
+                use $crate::frame::mmm::fuse::{Activation, FusedKerSpec};
 
                 #[test]
                 fn return_zeros() {
@@ -211,6 +257,26 @@ pub mod test {
                 bin!(ScalarSub,  scalar, |a,b| a-b);
                 bin!(ScalarSubF, scalar,  |a,b| b-a);
 
+                macro_rules! activation {
+                    ($name:ident, $act:expr) => {
+                        paste! {
+                            #[test]
+                            fn [<activation_ $name>]() {
+                                if $cond {
+                                    test::activation::<$ker, $tc, $ti>($act);
+                                }
+                            }
+                        }
+                    };
+                }
+
+                activation!(sigmoid, Activation::Sigmoid);
+                activation!(tanh, Activation::Tanh);
+                activation!(gelu, Activation::Gelu);
+                activation!(silu, Activation::Silu);
+                activation!(hard_swish, Activation::HardSwish);
+                activation!(leaky_relu, Activation::LeakyRelu(0.25));
+
                 #[test]
                 fn return_c_add_row_col_product() {
                     if $cond {
@@ -509,6 +575,44 @@ pub mod test {
         )
     }
 
+    /// Compares an activation to its reference implementation, if the
+    /// kernel supports it.
+    pub fn activation<K, TC, TI>(activation: Activation)
+    where
+        K: MatMatMulKer<TI>,
+        TC: LADatum + AsPrimitive<f32>,
+        TI: LADatum + AsPrimitive<TC>,
+        f32: AsPrimitive<TC> + AsPrimitive<TI>,
+    {
+        if !K::can_fuse(&FusedSpec::Activation(activation)) {
+            return;
+        }
+        let len = K::mr() * K::nr();
+        // spread over [-8, 8], saturating sigmoid and tanh on both ends
+        let v: Vec<TC> =
+            (0..len).map(|i| (16. * i as f32 / (len - 1).max(1) as f32 - 8.).as_()).collect();
+        let expected: Vec<TC> = v.iter().map(|x| activation.apply(x.as_()).as_()).collect();
+        let op = match activation {
+            Activation::Sigmoid => FusedKerSpec::Sigmoid,
+            Activation::Tanh => FusedKerSpec::Tanh,
+            Activation::Gelu => FusedKerSpec::Gelu,
+            Activation::Silu => FusedKerSpec::Silu,
+            Activation::HardSwish => FusedKerSpec::HardSwish,
+            Activation::LeakyRelu(alpha) => FusedKerSpec::LeakyRelu(alpha.as_()),
+        };
+        let c = mmm_stride_storage(&v, K::nr());
+        let ops = [
+            FusedKerSpec::Clear,
+            FusedKerSpec::AddUnicast(c),
+            op,
+            FusedKerSpec::Store(c),
+            FusedKerSpec::Done,
+        ];
+        let err = K::kernel(&ops);
+        assert_eq!(err, 0);
+        tensor1(&v).close_enough(&tensor1(&expected), true).unwrap();
+    }
+
     pub fn return_c_clear<K, TC, TI>()
     where
         K: MatMatMulKer<TI>,
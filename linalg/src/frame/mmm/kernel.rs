@@ -1,6 +1,6 @@
 use std::fmt::Debug;
 
-use crate::frame::mmm::FusedKerSpec;
+use crate::frame::mmm::{FusedKerSpec, FusedSpec};
 use crate::LADatum;
 
 use super::{MatMatMul, MatMatMulImpl};
@@ -21,6 +21,12 @@ where
     #[allow(unused_variables)]
     fn prefetch(ptr: *const u8, len: usize) {}
 
+    /// Whether the kernel implements this fused operation. Activations are
+    /// only implemented by some kernels.
+    fn can_fuse(spec: &FusedSpec) -> bool {
+        !matches!(spec, FusedSpec::Activation(_))
+    }
+
     fn mmm() -> Box<dyn MatMatMul> {
         Box::<MatMatMulImpl<Self, TI>>::default()
     }
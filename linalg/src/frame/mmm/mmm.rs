@@ -21,6 +21,9 @@ pub trait MatMatMul:
 
     fn internal_type(&self) -> DatumType;
 
+    /// Whether `run` accepts this fused operation.
+    fn can_fuse(&self, spec: &FusedSpec) -> bool;
+
     unsafe fn a_packed(&self, item_size: usize, k: usize) -> InputStoreSpec;
 
     unsafe fn b_packed(&self, item_size: usize, k: usize) -> InputStoreSpec;
@@ -153,6 +156,10 @@ where
         TI::datum_type()
     }
 
+    fn can_fuse(&self, spec: &FusedSpec) -> bool {
+        K::can_fuse(spec)
+    }
+
     unsafe fn a_packed(&self, item_size: usize, k: usize) -> InputStoreSpec {
         let panel_bytes = k * K::mr() * item_size;
         InputStoreSpec::Prepacked { panel_bytes }
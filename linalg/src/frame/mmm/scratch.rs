@@ -4,7 +4,7 @@ use tract_data::internal::*;
 
 use crate::LADatum;
 
-use super::{BinOp, FusedKerSpec, FusedSpec, MatMatMulKer, OutputStoreKer};
+use super::{Activation, BinOp, FusedKerSpec, FusedSpec, MatMatMulKer, OutputStoreKer};
 use downcast_rs::{impl_downcast, Downcast};
 use tract_data::internal::num_integer::Integer;
 
@@ -85,6 +85,16 @@ impl<TI: LADatum> ScratchSpaceFusedNonLinear<TI> {
                 FS::ShiftLeft(s) => FKS::ShiftLeft(*s),
                 FS::RoundingShiftRight(s, rp) => FKS::RoundingShiftRight(*s, *rp),
                 FS::QScale(s, rp, m) => FKS::QScale(*s, *rp, *m),
+                FS::Activation(activation) => match activation {
+                    Activation::Sigmoid => FKS::Sigmoid,
+                    Activation::Tanh => FKS::Tanh,
+                    Activation::Gelu => FKS::Gelu,
+                    Activation::Silu => FKS::Silu,
+                    Activation::HardSwish => FKS::HardSwish,
+                    Activation::LeakyRelu(alpha) => {
+                        FKS::LeakyRelu(*tensor0(*alpha).cast_to::<TI>()?.to_scalar::<TI>()?)
+                    }
+                },
                 FS::BinPerRow(_, _) => {
                     self.loc_dependant.push(ld(ix, self.uspecs.len(), offset as _));
                     offset += TI::datum_type().size_of() * K::mr();
@@ -0,0 +1,341 @@
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use tract_data::internal::num_integer::Integer;
+use tract_data::internal::*;
+
+/// A f32 matrix stored by blocks of `mr` rows and one column.
+///
+/// For every block row, only the columns where at least one of the `mr` rows
+/// is non-zero are stored, along with the `mr` values of the block. Rows past
+/// `m` in the last block row are zero-padded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockSparseMatrix {
+    pub m: usize,
+    pub k: usize,
+    pub mr: usize,
+    /// Offset of each block row in `cols`, plus the total count.
+    pub row_ptr: Vec<u32>,
+    /// Stored column indices.
+    pub cols: Vec<u32>,
+    /// `mr` values per stored column.
+    pub values: Vec<f32>,
+}
+
+impl BlockSparseMatrix {
+    /// Build from a dense row-major m×k matrix.
+    pub fn from_dense(a: &[f32], m: usize, k: usize, mr: usize) -> BlockSparseMatrix {
+        assert_eq!(a.len(), m * k);
+        let mut row_ptr = vec![0];
+        let mut cols = vec![];
+        let mut values = vec![];
+        for block in 0..Integer::div_ceil(&m, &mr) {
+            let rows = block * mr..(block * mr + mr).min(m);
+            for col in 0..k {
+                if rows.clone().any(|r| a[r * k + col] != 0.0) {
+                    cols.push(col as u32);
+                    values.extend((0..mr).map(|r| {
+                        if r + rows.start < rows.end {
+                            a[(rows.start + r) * k + col]
+                        } else {
+                            0.0
+                        }
+                    }));
+                }
+            }
+            row_ptr.push(cols.len() as u32);
+        }
+        BlockSparseMatrix { m, k, mr, row_ptr, cols, values }
+    }
+
+    pub fn to_dense(&self) -> Vec<f32> {
+        let mut dense = vec![0.0; self.m * self.k];
+        for block in 0..self.block_rows() {
+            for ix in self.row_ptr[block] as usize..self.row_ptr[block + 1] as usize {
+                for r in 0..self.mr.min(self.m - block * self.mr) {
+                    dense[(block * self.mr + r) * self.k + self.cols[ix] as usize] =
+                        self.values[ix * self.mr + r];
+                }
+            }
+        }
+        dense
+    }
+
+    /// Same matrix, blocked by `mr` rows.
+    pub fn with_mr(&self, mr: usize) -> BlockSparseMatrix {
+        if mr == self.mr {
+            self.clone()
+        } else {
+            Self::from_dense(&self.to_dense(), self.m, self.k, mr)
+        }
+    }
+
+    /// Check the structure is consistent. Kernels access the dense operand
+    /// at the stored columns without bound checks.
+    pub fn check(&self) -> TractResult<()> {
+        ensure!(self.mr > 0, "Sparse matrix blocks must have rows");
+        let block_rows = Integer::div_ceil(&self.m, &self.mr);
+        ensure!(
+            self.row_ptr.len() == block_rows + 1,
+            "Expected {} row pointers for {} rows by blocks of {}, got {}",
+            block_rows + 1,
+            self.m,
+            self.mr,
+            self.row_ptr.len()
+        );
+        ensure!(
+            self.row_ptr[0] == 0 && self.row_ptr.windows(2).all(|w| w[0] <= w[1]),
+            "Row pointers must start at zero and be non-decreasing"
+        );
+        ensure!(
+            self.row_ptr[block_rows] as usize == self.cols.len(),
+            "Row pointers end at {}, for {} stored columns",
+            self.row_ptr[block_rows],
+            self.cols.len()
+        );
+        ensure!(
+            self.values.len() == self.cols.len() * self.mr,
+            "Expected {} values, got {}",
+            self.cols.len() * self.mr,
+            self.values.len()
+        );
+        if let Some(col) = self.cols.iter().find(|&&c| c as usize >= self.k) {
+            bail!("Column index {} out of bounds for {} columns", col, self.k);
+        }
+        Ok(())
+    }
+
+    pub fn block_rows(&self) -> usize {
+        self.row_ptr.len() - 1
+    }
+
+    /// Ratio of stored values to the dense matrix size.
+    pub fn density(&self) -> f32 {
+        self.values.len() as f32 / (self.m * self.k).max(1) as f32
+    }
+}
+
+impl Hash for BlockSparseMatrix {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.m, self.k, self.mr).hash(state);
+        self.row_ptr.hash(state);
+        self.cols.hash(state);
+        self.values.iter().for_each(|v| v.to_bits().hash(state));
+    }
+}
+
+/// Sparse × dense matrix product.
+pub trait SparseMatMul: Send + Sync + Debug + dyn_clone::DynClone {
+    fn name(&self) -> &'static str;
+    /// Row blocking expected for the sparse operand.
+    fn mr(&self) -> usize;
+    /// `c = a·b`, with b k×n and c m×n, both row-major and contiguous.
+    fn run(&self, a: &BlockSparseMatrix, b: &[f32], c: &mut [f32], n: usize);
+}
+
+dyn_clone::clone_trait_object!(SparseMatMul);
+
+#[derive(Debug, Clone, new)]
+pub struct SparseMatMulImpl<K: SparseMatMulKer> {
+    phantom: PhantomData<K>,
+}
+
+impl<K: SparseMatMulKer> SparseMatMul for SparseMatMulImpl<K> {
+    fn name(&self) -> &'static str {
+        K::name()
+    }
+
+    fn mr(&self) -> usize {
+        K::mr()
+    }
+
+    fn run(&self, a: &BlockSparseMatrix, b: &[f32], c: &mut [f32], n: usize) {
+        let (mr, nr) = (K::mr(), K::nr());
+        assert_eq!(a.mr, mr);
+        assert!(b.len() >= a.k * n && c.len() >= a.m * n);
+        let mut tile = vec![0.0f32; mr * nr];
+        for block in 0..a.block_rows() {
+            let stored = a.row_ptr[block] as usize..a.row_ptr[block + 1] as usize;
+            let cols = &a.cols[stored.clone()];
+            let values = &a.values[stored.start * mr..stored.end * mr];
+            let rows = (a.m - block * mr).min(mr);
+            let mut j = 0;
+            while j + nr <= n {
+                unsafe {
+                    if rows == mr {
+                        let c = c.as_mut_ptr().add(block * mr * n + j);
+                        K::kernel(c, n, b.as_ptr().add(j), n, cols, values);
+                    } else {
+                        K::kernel(tile.as_mut_ptr(), nr, b.as_ptr().add(j), n, cols, values);
+                        for r in 0..rows {
+                            c[(block * mr + r) * n + j..][..nr]
+                                .copy_from_slice(&tile[r * nr..][..nr]);
+                        }
+                    }
+                }
+                j += nr;
+            }
+            for r in 0..rows {
+                for j in j..n {
+                    let mut acc = 0.0;
+                    for (ix, col) in cols.iter().enumerate() {
+                        acc += values[ix * mr + r] * b[*col as usize * n + j];
+                    }
+                    c[(block * mr + r) * n + j] = acc;
+                }
+            }
+        }
+    }
+}
+
+pub trait SparseMatMulKer: Send + Sync + Debug + dyn_clone::DynClone + Clone + 'static {
+    fn name() -> &'static str;
+    fn mr() -> usize;
+    fn nr() -> usize;
+    /// Computes a full mr×nr tile of c from the nr columns of b at `b`.
+    ///
+    /// Row strides are in elements. `values` holds mr values for each of the
+    /// `cols`.
+    unsafe fn kernel(
+        c: *mut f32,
+        c_rs: usize,
+        b: *const f32,
+        b_rs: usize,
+        cols: &[u32],
+        values: &[f32],
+    );
+    fn sparse() -> Box<dyn SparseMatMul> {
+        Box::new(SparseMatMulImpl::<Self>::new())
+    }
+}
+
+#[allow(unused_macros)]
+macro_rules! sparse_impl_wrap {
+    ($func: ident, $mr: expr, $nr: expr, $kernel: item) => {
+        #[derive(Copy, Clone, Debug)]
+        #[allow(non_camel_case_types)]
+        pub struct $func;
+
+        impl crate::frame::sparse::SparseMatMulKer for $func {
+            #[inline(always)]
+            fn name() -> &'static str {
+                stringify!($func)
+            }
+            #[inline(always)]
+            fn mr() -> usize {
+                $mr
+            }
+            #[inline(always)]
+            fn nr() -> usize {
+                $nr
+            }
+            $kernel
+        }
+    };
+}
+
+#[cfg(test)]
+#[macro_use]
+pub mod test {
+    use super::*;
+    use proptest::prelude::*;
+    use proptest::test_runner::{TestCaseError, TestCaseResult};
+
+    #[derive(Debug)]
+    pub struct SparseProblem {
+        pub m: usize,
+        pub k: usize,
+        pub n: usize,
+        pub a: Vec<f32>,
+        pub b: Vec<f32>,
+    }
+
+    impl Arbitrary for SparseProblem {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<SparseProblem>;
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            (1usize..20, 1usize..20, 1usize..40)
+                .prop_flat_map(|(m, k, n)| {
+                    let a = proptest::collection::vec(
+                        prop_oneof![3 => Just(0i8), 1 => -10i8..10],
+                        m * k,
+                    );
+                    let b = proptest::collection::vec(-10i8..10, k * n);
+                    (Just(m), Just(k), Just(n), a, b)
+                })
+                .prop_map(|(m, k, n, a, b)| SparseProblem {
+                    m,
+                    k,
+                    n,
+                    a: a.into_iter().map(|x| x as f32).collect(),
+                    b: b.into_iter().map(|x| x as f32).collect(),
+                })
+                .boxed()
+        }
+    }
+
+    #[macro_export]
+    macro_rules! sparse_frame_tests {
+        ($cond:expr, $ker:ty) => {
+            proptest::proptest! {
+                #[test]
+                fn prop(pb in proptest::prelude::any::<$crate::frame::sparse::test::SparseProblem>()) {
+                    if $cond {
+                        $crate::frame::sparse::test::test_sparse::<$ker>(&pb).unwrap()
+                    }
+                }
+            }
+
+            #[test]
+            fn empty_block_row() {
+                if $cond {
+                    let pb = $crate::frame::sparse::test::SparseProblem {
+                        m: 9,
+                        k: 3,
+                        n: 17,
+                        a: (0..27).map(|i| if i < 12 { 0.0 } else { i as f32 }).collect(),
+                        b: (0..51).map(|i| i as f32).collect(),
+                    };
+                    $crate::frame::sparse::test::test_sparse::<$ker>(&pb).unwrap()
+                }
+            }
+        };
+    }
+
+    /// Checks the kernel against a naive dense product.
+    pub fn test_sparse<K: SparseMatMulKer>(pb: &SparseProblem) -> TestCaseResult {
+        crate::setup_test_logger();
+        let SparseProblem { m, k, n, .. } = *pb;
+        let sparse = BlockSparseMatrix::from_dense(&pb.a, m, k, K::mr());
+        sparse.check().map_err(|e| TestCaseError::fail(e.to_string()))?;
+        assert_eq!(sparse.to_dense(), pb.a);
+        let expected = (0..m * n)
+            .map(|ix| (0..k).map(|i| pb.a[ix / n * k + i] * pb.b[i * n + ix % n]).sum())
+            .collect::<Vec<f32>>();
+        let mut found = vec![f32::NAN; m * n];
+        K::sparse().run(&sparse, &pb.b, &mut found, n);
+        tensor1(&found)
+            .close_enough(&tensor1(&expected), true)
+            .map_err(|e| TestCaseError::fail(e.root_cause().to_string()))?;
+        Ok(())
+    }
+
+    #[test]
+    fn check_rejects_malformed() {
+        let a: Vec<f32> = (0..30).map(|i| if i % 4 == 0 { i as f32 } else { 0.0 }).collect();
+        let sparse = BlockSparseMatrix::from_dense(&a, 5, 6, 2);
+        sparse.check().unwrap();
+        let mut bad = sparse.clone();
+        bad.cols[0] = 6;
+        assert!(bad.check().is_err());
+        let mut bad = sparse.clone();
+        bad.row_ptr.swap(1, 2);
+        assert!(bad.check().is_err());
+        let mut bad = sparse.clone();
+        bad.row_ptr.pop();
+        assert!(bad.check().is_err());
+        let mut bad = sparse;
+        bad.values.pop();
+        assert!(bad.check().is_err());
+    }
+}
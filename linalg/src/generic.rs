@@ -1,13 +1,17 @@
+pub mod depthwise;
 pub mod erf;
 pub mod leaky_relu;
 pub mod lut;
 pub mod mmm;
 pub mod rounding;
 pub mod sigmoid;
+pub mod sparse;
 pub mod tanh;
 
+pub use self::depthwise::{HDepthWise8, QDepthWise8, SDepthWise4};
 pub use self::erf::SErf4;
 pub use self::leaky_relu::{HLeakyRelu8, SLeakyRelu4};
+pub use self::sparse::SSparse4x4;
 pub use self::lut::GenericLut8;
 pub use self::mmm::GenericMmm4x1;
 pub use self::mmm::GenericMmm4x4;
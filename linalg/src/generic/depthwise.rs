@@ -0,0 +1,78 @@
+use crate::frame::depthwise::DepthWiseKer;
+use tract_data::internal::*;
+
+macro_rules! generic_dw {
+    ($name: ident, $ti: ty, $ta: ty, $nr: expr) => {
+        #[derive(Clone, Debug)]
+        pub struct $name;
+
+        impl DepthWiseKer<$ti, $ta> for $name {
+            fn name() -> &'static str {
+                "generic"
+            }
+
+            fn nr() -> usize {
+                $nr
+            }
+
+            fn mul_add(acc: &mut [$ta], x: &[$ti], k: &[$ta]) {
+                debug_assert_eq!(acc.len() % Self::nr(), 0);
+                acc.iter_mut().zip(x).zip(k).for_each(|((a, x), k)| *a += (*x as $ta) * *k)
+            }
+
+            fn mul_add_scalar(acc: &mut [$ta], x: &[$ti], k: $ta) {
+                debug_assert_eq!(acc.len() % Self::nr(), 0);
+                acc.iter_mut().zip(x).for_each(|(a, x)| *a += (*x as $ta) * k)
+            }
+        }
+    };
+}
+
+generic_dw!(SDepthWise4, f32, f32, 4);
+generic_dw!(QDepthWise8, i8, i32, 8);
+
+#[derive(Clone, Debug)]
+pub struct HDepthWise8;
+
+impl DepthWiseKer<f16, f16> for HDepthWise8 {
+    fn name() -> &'static str {
+        "generic"
+    }
+
+    fn nr() -> usize {
+        8
+    }
+
+    fn mul_add(acc: &mut [f16], x: &[f16], k: &[f16]) {
+        debug_assert_eq!(acc.len() % Self::nr(), 0);
+        acc.iter_mut().zip(x).zip(k).for_each(|((a, x), k)| *a += *x * *k)
+    }
+
+    fn mul_add_scalar(acc: &mut [f16], x: &[f16], k: f16) {
+        debug_assert_eq!(acc.len() % Self::nr(), 0);
+        acc.iter_mut().zip(x).for_each(|(a, x)| *a += *x * k)
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+pub mod s {
+    depthwise_frame_tests!(true, f32, f32, crate::generic::depthwise::SDepthWise4);
+}
+
+#[cfg(test)]
+#[macro_use]
+pub mod h {
+    depthwise_frame_tests!(
+        true,
+        tract_data::internal::f16,
+        tract_data::internal::f16,
+        crate::generic::depthwise::HDepthWise8
+    );
+}
+
+#[cfg(test)]
+#[macro_use]
+pub mod q {
+    depthwise_frame_tests!(true, i8, i32, crate::generic::depthwise::QDepthWise8);
+}
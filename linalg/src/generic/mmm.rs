@@ -52,6 +52,16 @@ macro_rules! per_row {
     };
 }
 
+macro_rules! activation {
+    ($ab: expr, $act: expr) => {
+        for i in 0..$ab.len() {
+            for j in 0..$ab[0].len() {
+                $ab[i][j] = $act.apply($ab[i][j].as_()).as_()
+            }
+        }
+    };
+}
+
 macro_rules! per_col {
     ($ab: expr, $m: expr, $f: expr) => {
         for i in 0..$ab.len() {
@@ -66,8 +76,9 @@ impl<TA, TB, TI> MatMatMulKer<TI> for GenericMmm4x4<TA, TB, TI>
 where
     TA: Datum + Copy + fmt::Debug + AsPrimitive<TI>,
     TB: Datum + Copy + fmt::Debug + AsPrimitive<TI>,
-    TI: LADatum + ScaleShiftAndRound,
+    TI: LADatum + ScaleShiftAndRound + AsPrimitive<f32>,
     usize: AsPrimitive<TI>,
+    f32: AsPrimitive<TI>,
 {
     #[inline(always)]
     fn name() -> &'static str {
@@ -101,6 +112,9 @@ where
     fn alignment_bytes_packed_b() -> usize {
         std::mem::size_of::<TB>()
     }
+    fn can_fuse(spec: &FusedSpec) -> bool {
+        TI::datum_type().is_float() || !matches!(spec, FusedSpec::Activation(_))
+    }
     #[inline(never)]
     fn kernel(spec: &[FusedKerSpec<TI>]) -> isize {
         unsafe {
@@ -161,6 +175,14 @@ where
                             }
                         }
                     }
+                    FusedKerSpec::Sigmoid => activation!(ab, Activation::Sigmoid),
+                    FusedKerSpec::Tanh => activation!(ab, Activation::Tanh),
+                    FusedKerSpec::Gelu => activation!(ab, Activation::Gelu),
+                    FusedKerSpec::Silu => activation!(ab, Activation::Silu),
+                    FusedKerSpec::HardSwish => activation!(ab, Activation::HardSwish),
+                    FusedKerSpec::LeakyRelu(alpha) => {
+                        activation!(ab, Activation::LeakyRelu(alpha.as_()))
+                    }
                     FusedKerSpec::AddMatMul { k, pa, pb, .. } => {
                         let a = pa as *const TA;
                         let b = pb as *const TB;
@@ -221,8 +243,9 @@ impl<TA, TB, TI> MatMatMulKer<TI> for GenericMmm4x1<TA, TB, TI>
 where
     TA: Datum + Copy + fmt::Debug + AsPrimitive<TI>,
     TB: Datum + Copy + fmt::Debug + AsPrimitive<TI>,
-    TI: LADatum + ScaleShiftAndRound,
+    TI: LADatum + ScaleShiftAndRound + AsPrimitive<f32>,
     usize: AsPrimitive<TI>,
+    f32: AsPrimitive<TI>,
 {
     #[inline(always)]
     fn name() -> &'static str {
@@ -256,6 +279,9 @@ where
     fn alignment_bytes_packed_b() -> usize {
         std::mem::size_of::<TB>()
     }
+    fn can_fuse(spec: &FusedSpec) -> bool {
+        TI::datum_type().is_float() || !matches!(spec, FusedSpec::Activation(_))
+    }
     #[inline(never)]
     fn kernel(spec: &[FusedKerSpec<TI>]) -> isize {
         unsafe {
@@ -316,6 +342,14 @@ where
                             ab[i][0] = ab[i][0].q_scale(Scaler::from_fuse_params(shift, rp, mult));
                         }
                     }
+                    FusedKerSpec::Sigmoid => activation!(ab, Activation::Sigmoid),
+                    FusedKerSpec::Tanh => activation!(ab, Activation::Tanh),
+                    FusedKerSpec::Gelu => activation!(ab, Activation::Gelu),
+                    FusedKerSpec::Silu => activation!(ab, Activation::Silu),
+                    FusedKerSpec::HardSwish => activation!(ab, Activation::HardSwish),
+                    FusedKerSpec::LeakyRelu(alpha) => {
+                        activation!(ab, Activation::LeakyRelu(alpha.as_()))
+                    }
                     FusedKerSpec::AddMatMul { k, pa, pb, .. } => {
                         let a = pa as *const TA;
                         let b = pb as *const TB;
@@ -376,8 +410,9 @@ impl<TA, TB, TI> MatMatMulKer<TI> for GenericMmmTest3x2<TA, TB, TI>
 where
     TA: Datum + Copy + fmt::Debug + AsPrimitive<TI>,
     TB: Datum + Copy + fmt::Debug + AsPrimitive<TI>,
-    TI: LADatum + ScaleShiftAndRound,
+    TI: LADatum + ScaleShiftAndRound + AsPrimitive<f32>,
     usize: AsPrimitive<TI>,
+    f32: AsPrimitive<TI>,
 {
     #[inline(always)]
     fn name() -> &'static str {
@@ -411,6 +446,9 @@ where
     fn alignment_bytes_packed_b() -> usize {
         std::mem::size_of::<TB>()
     }
+    fn can_fuse(spec: &FusedSpec) -> bool {
+        TI::datum_type().is_float() || !matches!(spec, FusedSpec::Activation(_))
+    }
     #[inline(never)]
     fn kernel(spec: &[FusedKerSpec<TI>]) -> isize {
         unsafe {
@@ -471,6 +509,14 @@ where
                             }
                         }
                     }
+                    FusedKerSpec::Sigmoid => activation!(ab, Activation::Sigmoid),
+                    FusedKerSpec::Tanh => activation!(ab, Activation::Tanh),
+                    FusedKerSpec::Gelu => activation!(ab, Activation::Gelu),
+                    FusedKerSpec::Silu => activation!(ab, Activation::Silu),
+                    FusedKerSpec::HardSwish => activation!(ab, Activation::HardSwish),
+                    FusedKerSpec::LeakyRelu(alpha) => {
+                        activation!(ab, Activation::LeakyRelu(alpha.as_()))
+                    }
                     FusedKerSpec::AddMatMul { k, pa, pb, .. } => {
                         let a = pa as *const TA;
                         let b = pb as *const TB;
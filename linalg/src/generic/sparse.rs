@@ -0,0 +1,46 @@
+use crate::frame::sparse::SparseMatMulKer;
+
+#[derive(Clone, Debug)]
+pub struct SSparse4x4;
+
+impl SparseMatMulKer for SSparse4x4 {
+    fn name() -> &'static str {
+        "SSparse4x4"
+    }
+
+    fn mr() -> usize {
+        4
+    }
+
+    fn nr() -> usize {
+        4
+    }
+
+    unsafe fn kernel(
+        c: *mut f32,
+        c_rs: usize,
+        b: *const f32,
+        b_rs: usize,
+        cols: &[u32],
+        values: &[f32],
+    ) {
+        let mut acc = [[0f32; 4]; 4];
+        for (col, values) in cols.iter().zip(values.chunks_exact(4)) {
+            let b = b.add(*col as usize * b_rs);
+            for (row, value) in acc.iter_mut().zip(values) {
+                for (j, acc) in row.iter_mut().enumerate() {
+                    *acc += value * *b.add(j);
+                }
+            }
+        }
+        for (r, row) in acc.iter().enumerate() {
+            std::ptr::copy_nonoverlapping(row.as_ptr(), c.add(r * c_rs), 4);
+        }
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+pub mod s {
+    sparse_frame_tests!(true, crate::generic::sparse::SSparse4x4);
+}
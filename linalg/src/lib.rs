@@ -15,7 +15,11 @@ include!(concat!(env!("OUT_DIR"), "/extern_kernel_macro.rs"));
 #[macro_use]
 pub mod frame;
 pub mod generic;
+pub mod roofline;
+pub mod tuning;
+use frame::depthwise::DepthWiseKer;
 use frame::element_wise::ElementWiseKer;
+use frame::sparse::SparseMatMulKer;
 use frame::MatMatMul;
 pub use generic::{ScaleShiftAndRound, Scaler};
 #[cfg(target_arch = "x86_64")]
@@ -27,7 +31,7 @@ pub mod arm64;
 #[cfg(any(target_arch = "arm", target_arch = "armv7"))]
 pub mod arm32;
 
-pub use self::frame::{element_wise, lut, mmm};
+pub use self::frame::{depthwise, element_wise, lut, mmm, sparse};
 
 use crate::frame::mmm::kernel::MatMatMulKer;
 use tract_data::prelude::*;
@@ -62,6 +66,12 @@ pub struct Ops {
     pub tanh_f32: Box<dyn Fn() -> Box<dyn element_wise::ElementWise<f32>> + Send + Sync>,
     pub erf_f32: Box<dyn Fn() -> Box<dyn element_wise::ElementWise<f32>> + Send + Sync>,
     pub lut_u8: Box<dyn Fn(&[u8]) -> Box<dyn lut::Lut> + Send + Sync>,
+
+    pub depthwise_f16: Box<dyn Fn() -> Box<dyn depthwise::DepthWise<f16, f16>> + Send + Sync>,
+    pub depthwise_f32: Box<dyn Fn() -> Box<dyn depthwise::DepthWise<f32, f32>> + Send + Sync>,
+    pub depthwise_i8: Box<dyn Fn() -> Box<dyn depthwise::DepthWise<i8, i32>> + Send + Sync>,
+
+    pub sparse_f32: Box<dyn Fn() -> Box<dyn sparse::SparseMatMul> + Send + Sync>,
 }
 
 impl Ops {
@@ -84,7 +94,21 @@ impl Ops {
                 Some(if n == Some(1) { (self.mmv_f64)(m, k) } else { (self.mmm_f64)(m, k, n) })
             }
             (F32, F32, F32) => {
-                Some(if n == Some(1) { (self.mmv_f32)(m, k) } else { (self.mmm_f32)(m, k, n) })
+                if n == Some(1) {
+                    return Some((self.mmv_f32)(m, k));
+                }
+                let default = (self.mmm_f32)(m, k, n);
+                if let (true, Some(m), Some(k), Some(n)) = (tuning::is_installed(), m, k, n) {
+                    let mut candidates: Vec<&dyn MatMatMul> =
+                        self.mmm_f32_impls.iter().map(|mmm| &**mmm).collect();
+                    if !candidates.iter().any(|c| c.kernel_name() == default.kernel_name()) {
+                        candidates.push(&*default);
+                    }
+                    if let Some(tuned) = tuning::select(&candidates, m, k, n) {
+                        return Some(tuned);
+                    }
+                }
+                Some(default)
             }
             (F16, F16, F16) => {
                 Some(if n == Some(1) { (self.mmv_f16)(m, k) } else { (self.mmm_f16)(m, k, n) })
@@ -119,6 +143,10 @@ pub fn generic() -> Ops {
         tanh_f32: Box::new(|| generic::STanh4::ew()),
         erf_f32: Box::new(|| generic::SErf4::ew()),
         lut_u8: Box::new(|table: &[u8]| Box::new(lut::LutImpl::<generic::GenericLut8>::new(table))),
+        depthwise_f16: Box::new(|| generic::HDepthWise8::dw()),
+        depthwise_f32: Box::new(|| generic::SDepthWise4::dw()),
+        depthwise_i8: Box::new(|| generic::QDepthWise8::dw()),
+        sparse_f32: Box::new(|| generic::SSparse4x4::sparse()),
         /*
         activation_f32: Box::new(|microcode| generic::SActivation::new(microcode))
         */
@@ -0,0 +1,174 @@
+//! Throughput tables of CPUs, for roofline latency estimates.
+//!
+//! A table gives the sustained compute throughput of a CPU by datum type,
+//! its streaming memory bandwidth and a fixed overhead per op evaluation.
+//! Estimated time of an operation is then the max of its compute time and its
+//! memory time, plus the overhead.
+//!
+//! Tables can be measured on a device with the `roofline` command of the
+//! `linalg/cost_model` tool, and read back with [RooflineTable::parse].
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use tract_data::internal::*;
+
+/// Built-in tables: name, f32 and i32 multiply-accumulates per second,
+/// divisions per second and memory bandwidth.
+pub const BUILTINS: &[(&str, f64, f64, f64, f64)] = &[
+    ("generic", 2e9, 4e9, 0.5e9, 4e9),
+    ("x86_64_fma", 40e9, 20e9, 4e9, 15e9),
+    ("x86_64_avx512", 80e9, 40e9, 8e9, 20e9),
+    ("arm64", 16e9, 12e9, 2e9, 10e9),
+    ("arm64_little", 6e9, 6e9, 1e9, 4e9),
+    ("armv7", 3e9, 3e9, 0.5e9, 2e9),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RooflineTable {
+    pub name: String,
+    /// Sustained multiply-accumulates per second, by accumulator type.
+    pub fma: Vec<(DatumType, f64)>,
+    /// Sustained divisions (and other expensive element-wise ops) per second.
+    pub div: Vec<(DatumType, f64)>,
+    /// Streaming memory bandwidth, in bytes per second.
+    pub memory_bandwidth: f64,
+    /// Fixed cost of an op evaluation, in seconds.
+    pub op_overhead: f64,
+}
+
+impl RooflineTable {
+    /// A conservative table for unknown CPUs.
+    pub fn generic() -> RooflineTable {
+        RooflineTable::builtin("generic").unwrap()
+    }
+
+    /// Built-in table by name (see [BUILTINS]).
+    pub fn builtin(name: &str) -> Option<RooflineTable> {
+        use DatumType::*;
+        let (name, f32_fma, i32_fma, div, bandwidth) =
+            *BUILTINS.iter().find(|table| table.0 == name)?;
+        Some(RooflineTable {
+            name: name.to_string(),
+            fma: vec![(F16, f32_fma), (F32, f32_fma), (F64, f32_fma / 2.), (I32, i32_fma)],
+            div: vec![(F16, div), (F32, div), (F64, div / 2.)],
+            memory_bandwidth: bandwidth,
+            op_overhead: 200e-9,
+        })
+    }
+
+    /// Built-in table for the kernels selected on this host.
+    ///
+    /// This is a rough guess from the instruction set, measured tables are
+    /// expected to be much more accurate.
+    pub fn for_host() -> RooflineTable {
+        let kernel = crate::ops()
+            .mmm(DatumType::F32, DatumType::F32, DatumType::F32, None, None, None)
+            .map(|mmm| mmm.kernel_name())
+            .unwrap_or("generic");
+        Self::for_kernel(kernel)
+    }
+
+    fn for_kernel(kernel: &str) -> RooflineTable {
+        let name = if kernel.starts_with("avx512") {
+            "x86_64_avx512"
+        } else if kernel.starts_with("fma") {
+            "x86_64_fma"
+        } else if kernel.contains("a53") || kernel.contains("a55") {
+            "arm64_little"
+        } else if kernel.starts_with("arm64") {
+            "arm64"
+        } else if kernel.starts_with("armv7") {
+            "armv7"
+        } else {
+            "generic"
+        };
+        RooflineTable::builtin(name).unwrap()
+    }
+
+    /// Parse a table in the text format produced by its Display.
+    pub fn parse(s: &str) -> TractResult<RooflineTable> {
+        let mut table = RooflineTable {
+            name: "unnamed".to_string(),
+            fma: vec![],
+            div: vec![],
+            memory_bandwidth: 0.,
+            op_overhead: 0.,
+        };
+        for line in s.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match &*tokens {
+                ["name", name] => table.name = name.to_string(),
+                ["fma", dt, value] => table.fma.push((DatumType::from_str(dt)?, value.parse()?)),
+                ["div", dt, value] => table.div.push((DatumType::from_str(dt)?, value.parse()?)),
+                ["memory_bandwidth", value] => table.memory_bandwidth = value.parse()?,
+                ["op_overhead", value] => table.op_overhead = value.parse()?,
+                _ => bail!("Invalid roofline table line: {}", line),
+            }
+        }
+        ensure!(table.memory_bandwidth > 0., "memory_bandwidth must be set");
+        Ok(table)
+    }
+
+    /// Multiply-accumulates per second for `dt`.
+    pub fn fma_rate(&self, dt: DatumType) -> Option<f64> {
+        Self::lookup(&self.fma, dt)
+    }
+
+    /// Divisions per second for `dt`.
+    pub fn div_rate(&self, dt: DatumType) -> Option<f64> {
+        Self::lookup(&self.div, dt)
+    }
+
+    fn lookup(rates: &[(DatumType, f64)], dt: DatumType) -> Option<f64> {
+        let dt = match dt.unquantized() {
+            DatumType::I8 | DatumType::U8 => DatumType::I32,
+            dt => dt,
+        };
+        rates.iter().find(|(d, _)| *d == dt).map(|(_, r)| *r)
+    }
+
+    /// Roofline estimate for an op evaluation.
+    ///
+    /// `compute` is the time the op would take if it were compute bound.
+    pub fn estimate(&self, compute: f64, memory_bytes: f64) -> Duration {
+        let memory = memory_bytes / self.memory_bandwidth;
+        Duration::from_secs_f64(compute.max(memory) + self.op_overhead)
+    }
+}
+
+impl fmt::Display for RooflineTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "name {}", self.name)?;
+        for (dt, rate) in &self.fma {
+            writeln!(f, "fma {dt:?} {rate:e}")?;
+        }
+        for (dt, rate) in &self.div {
+            writeln!(f, "div {dt:?} {rate:e}")?;
+        }
+        writeln!(f, "memory_bandwidth {:e}", self.memory_bandwidth)?;
+        writeln!(f, "op_overhead {:e}", self.op_overhead)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let table = RooflineTable::for_kernel("fma_mmm_f32_16x6");
+        assert_eq!(RooflineTable::parse(&table.to_string()).unwrap(), table);
+    }
+
+    #[test]
+    fn memory_bound() {
+        let table = RooflineTable::generic();
+        let estimate = table.estimate(1e-6, table.memory_bandwidth * 1e-3);
+        assert!((estimate.as_secs_f64() - 1e-3 - table.op_overhead).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,251 @@
+//! Matrix multiplication kernel autotuning.
+//!
+//! Kernel selection in [crate::Ops::mmm] relies on per-architecture heuristics
+//! on m and n. When a tuning database is installed with [set_tuning], f32
+//! matrix multiplications of known shape use the kernel recorded for the
+//! local CPU and this shape instead. In autotuning mode, shapes missing from
+//! the database are benchmarked on the spot against all candidate kernels, and
+//! the winner is recorded.
+//!
+//! Databases are stored in a simple text format: a `[cpu model]` line opens a
+//! section, followed by `m k n kernel_name` lines.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tract_data::internal::*;
+
+use crate::frame::MatMatMul;
+use crate::mmm::FusedSpec;
+
+lazy_static::lazy_static! {
+    static ref TUNING: Mutex<Option<Arc<TuningDb>>> = Mutex::new(None);
+    static ref CPU_MODEL: String = read_cpu_model();
+}
+
+/// Spares kernel selection the lock when no database is installed.
+static TUNING_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Install (or remove) the tuning database consulted by [crate::Ops::mmm].
+pub fn set_tuning(db: Option<TuningDb>) {
+    TUNING_INSTALLED.store(db.is_some(), Ordering::Relaxed);
+    *TUNING.lock().unwrap() = db.map(Arc::new);
+}
+
+/// A copy of the currently installed tuning database.
+pub fn tuning() -> Option<TuningDb> {
+    TUNING.lock().unwrap().as_deref().cloned()
+}
+
+pub(crate) fn is_installed() -> bool {
+    TUNING_INSTALLED.load(Ordering::Relaxed)
+}
+
+/// Kernel for this shape according to the installed database, if any.
+///
+/// The lock is only held to snapshot the database and to record autotuning
+/// results: benchmarks run without it.
+pub(crate) fn select(
+    candidates: &[&dyn MatMatMul],
+    m: usize,
+    k: usize,
+    n: usize,
+) -> Option<Box<dyn MatMatMul>> {
+    let db = TUNING.lock().ok()?.clone()?;
+    if db.choice(m, k, n).is_some() || !db.autotune {
+        return db.recorded(candidates, m, k, n);
+    }
+    let best = autotune(candidates, m, k, n)?;
+    log::info!("Autotuned {}x{}x{}: {}", m, k, n, best.kernel_name());
+    if let Some(db) = TUNING.lock().ok()?.as_mut() {
+        Arc::make_mut(db)
+            .entries
+            .insert((cpu_model().to_string(), m, k, n), best.kernel_name().to_string());
+    }
+    Some(best)
+}
+
+/// CPU model of the host, as used to key tuning databases.
+pub fn cpu_model() -> &'static str {
+    &CPU_MODEL
+}
+
+fn read_cpu_model() -> String {
+    if let Ok(cpuinfo) = std::fs::read_to_string("/proc/cpuinfo") {
+        for line in cpuinfo.lines() {
+            let mut kv = line.splitn(2, ':');
+            let (Some(k), Some(v)) = (kv.next(), kv.next()) else { continue };
+            if ["model name", "Hardware", "CPU part"].contains(&k.trim()) {
+                return format!("{} {}", std::env::consts::ARCH, v.trim());
+            }
+        }
+    }
+    std::env::consts::ARCH.to_string()
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TuningDb {
+    /// Benchmark and record shapes that are not in the database.
+    pub autotune: bool,
+    /// Kernel names by cpu model and (m, k, n).
+    pub entries: HashMap<(String, usize, usize, usize), String>,
+}
+
+impl TuningDb {
+    pub fn new(autotune: bool) -> TuningDb {
+        TuningDb { autotune, entries: HashMap::default() }
+    }
+
+    /// Parse a database in the text format produced by its Display.
+    pub fn parse(s: &str) -> TractResult<TuningDb> {
+        let mut db = TuningDb::default();
+        let mut cpu = None;
+        for line in s.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                cpu = Some(line[1..line.len() - 1].to_string());
+                continue;
+            }
+            let Some(cpu) = &cpu else { bail!("Tuning entry before any [cpu] section: {}", line) };
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let [m, k, n, kernel] = &*tokens else { bail!("Invalid tuning line: {}", line) };
+            db.entries
+                .insert((cpu.clone(), m.parse()?, k.parse()?, n.parse()?), kernel.to_string());
+        }
+        Ok(db)
+    }
+
+    /// Kernel recorded for the host cpu and this shape.
+    pub fn choice(&self, m: usize, k: usize, n: usize) -> Option<&str> {
+        self.entries.get(&(cpu_model().to_string(), m, k, n)).map(|s| &**s)
+    }
+
+    /// The recorded kernel for this shape, among `candidates`.
+    fn recorded(
+        &self,
+        candidates: &[&dyn MatMatMul],
+        m: usize,
+        k: usize,
+        n: usize,
+    ) -> Option<Box<dyn MatMatMul>> {
+        let choice = self.choice(m, k, n)?;
+        if let Some(mmm) = candidates.iter().find(|mmm| mmm.kernel_name() == choice) {
+            return Some(dyn_clone::clone_box(*mmm));
+        }
+        log::warn!("Tuned kernel {} for {}x{}x{} is not available", choice, m, k, n);
+        None
+    }
+
+    /// Resolve the kernel for this shape among `candidates`, autotuning if
+    /// needed and enabled.
+    pub fn select(
+        &mut self,
+        candidates: &[&dyn MatMatMul],
+        m: usize,
+        k: usize,
+        n: usize,
+    ) -> Option<Box<dyn MatMatMul>> {
+        if self.choice(m, k, n).is_some() || !self.autotune {
+            return self.recorded(candidates, m, k, n);
+        }
+        let best = autotune(candidates, m, k, n)?;
+        log::info!("Autotuned {}x{}x{}: {}", m, k, n, best.kernel_name());
+        self.entries.insert((cpu_model().to_string(), m, k, n), best.kernel_name().to_string());
+        Some(best)
+    }
+}
+
+impl fmt::Display for TuningDb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort();
+        let mut cpu = None;
+        for ((c, m, k, n), kernel) in entries {
+            if cpu != Some(c) {
+                writeln!(f, "[{c}]")?;
+                cpu = Some(c);
+            }
+            writeln!(f, "{m} {k} {n} {kernel}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Benchmark all candidates on a m×k×n product, and pick the fastest.
+pub fn autotune(
+    candidates: &[&dyn MatMatMul],
+    m: usize,
+    k: usize,
+    n: usize,
+) -> Option<Box<dyn MatMatMul>> {
+    let mut best: Option<(Duration, &dyn MatMatMul)> = None;
+    for &mmm in candidates {
+        match unsafe { bench(mmm, m, k, n) } {
+            Ok(time) => {
+                log::debug!("{}x{}x{} {}: {:?}", m, k, n, mmm.kernel_name(), time);
+                if best.map(|b| time < b.0).unwrap_or(true) {
+                    best = Some((time, mmm))
+                }
+            }
+            Err(e) => log::warn!("Failed to benchmark {}: {:?}", mmm.kernel_name(), e),
+        }
+    }
+    best.map(|(_, mmm)| dyn_clone::clone_box(mmm))
+}
+
+unsafe fn bench(mmm: &dyn MatMatMul, m: usize, k: usize, n: usize) -> TractResult<Duration> {
+    let dt = mmm.internal_type();
+    let a = Tensor::zero_aligned_dt(dt, &[mmm.a_pack().len(k, m)], mmm.a_pack().alignment())?;
+    let b = Tensor::zero_aligned_dt(dt, &[mmm.b_pack().len(k, n)], mmm.b_pack().alignment())?;
+    let c = Tensor::zero_dt(dt, &[m, n])?;
+    let ops = [
+        FusedSpec::AddMatMul {
+            a: mmm.a_packed(dt.size_of(), k).wrap(&a.view()),
+            b: mmm.b_packed(dt.size_of(), k).wrap(&b.view()),
+            k,
+        },
+        FusedSpec::Store(mmm.c_view(0, 1).wrap(&c.view())),
+    ];
+    let mut scratch = mmm.allocate_scratch_space();
+    mmm.run_with_scratch_space(m, n, &mut *scratch, &ops)?;
+    let budget = Instant::now();
+    let mut best = Duration::MAX;
+    for _ in 0..100 {
+        let start = Instant::now();
+        mmm.run_with_scratch_space(m, n, &mut *scratch, &ops)?;
+        best = best.min(start.elapsed());
+        if budget.elapsed() > Duration::from_millis(50) {
+            break;
+        }
+    }
+    Ok(best)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut db = TuningDb::default();
+        db.entries.insert(("x86_64 some cpu".to_string(), 64, 32, 16), "fma_mmm_f32_16x6".into());
+        db.entries.insert(("aarch64".to_string(), 8, 8, 8), "arm64simd_mmm_f32_8x8".into());
+        assert_eq!(TuningDb::parse(&db.to_string()).unwrap(), db);
+    }
+
+    #[test]
+    fn autotune_records_choice() {
+        let ops = crate::ops();
+        let candidates: Vec<&dyn MatMatMul> = ops.mmm_f32_impls().iter().map(|k| &**k).collect();
+        let mut db = TuningDb::new(true);
+        let picked = db.select(&candidates, 32, 16, 8).unwrap();
+        assert_eq!(db.choice(32, 16, 8), Some(picked.kernel_name()));
+        db.autotune = false;
+        assert_eq!(db.select(&candidates, 32, 16, 8).unwrap().kernel_name(), picked.kernel_name());
+    }
+}
@@ -1,8 +1,12 @@
+use crate::frame::depthwise::DepthWiseKer;
 use crate::frame::element_wise::ElementWiseKer;
 use crate::frame::mmm::kernel::MatMatMulKer;
+use crate::frame::sparse::SparseMatMulKer;
 use crate::Ops;
 
+pub mod depthwise;
 pub mod mmm;
+pub mod sparse;
 
 mod intel;
 
@@ -11,7 +15,8 @@ sigmoid_impl!(f32, fma_sigmoid_f32, 8, 8, is_x86_feature_detected!("fma"));
 
 fn plug_avx2(ops: &mut Ops) {
     ops.qmmm_i32 = Box::new(|_, _, _| mmm::avx2_mmm_i32_8x8::mmm());
-    log::info!("qmmm_i32: x86_64/avx2 activated");
+    ops.depthwise_i8 = Box::new(|| depthwise::avx2_depthwise_i8_8n::dw());
+    log::info!("qmmm_i32, depthwise_i8: x86_64/avx2 activated");
 }
 
 fn plug_fma(ops: &mut Ops) {
@@ -90,7 +95,11 @@ fn plug_fma(ops: &mut Ops) {
 
     ops.sigmoid_f32 = Box::new(|| fma_sigmoid_f32::ew());
     ops.tanh_f32 = Box::new(|| fma_tanh_f32::ew());
-    log::info!("mmm_f32, mmv_f32, sigmoid_f32, tanh_f32: x86_64/fma activated");
+    ops.depthwise_f32 = Box::new(|| depthwise::fma_depthwise_f32_8n::dw());
+    ops.sparse_f32 = Box::new(|| sparse::fma_sparse_f32_4x8::sparse());
+    log::info!(
+        "mmm_f32, mmv_f32, sigmoid_f32, tanh_f32, depthwise_f32, sparse_f32: x86_64/fma activated"
+    );
 }
 
 fn plug_avx512f(ops: &mut Ops) {
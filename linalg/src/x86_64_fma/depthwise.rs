@@ -0,0 +1,138 @@
+dw_impl_wrap!(
+    f32,
+    f32,
+    fma_depthwise_f32_8n,
+    8,
+    #[inline(never)]
+    fn mul_add(acc: &mut [f32], x: &[f32], k: &[f32]) {
+        assert!(!acc.is_empty());
+        assert_eq!(acc.len() % 8, 0);
+        assert!(x.len() >= acc.len() && k.len() >= acc.len());
+        unsafe {
+            std::arch::asm!("
+                2:
+                    vmovups ymm0, [{acc}]
+                    vmovups ymm1, [{x}]
+                    vfmadd231ps ymm0, ymm1, [{k}]
+                    vmovups [{acc}], ymm0
+                    add {acc}, 32
+                    add {x}, 32
+                    add {k}, 32
+                    sub {len}, 8
+                    jnz 2b
+                vzeroupper
+            ",
+            acc = inout(reg) acc.as_mut_ptr() => _,
+            x = inout(reg) x.as_ptr() => _,
+            k = inout(reg) k.as_ptr() => _,
+            len = inout(reg) acc.len() => _,
+            out("ymm0") _,
+            out("ymm1") _,
+            );
+        }
+    },
+    #[inline(never)]
+    fn mul_add_scalar(acc: &mut [f32], x: &[f32], k: f32) {
+        assert!(!acc.is_empty());
+        assert_eq!(acc.len() % 8, 0);
+        assert!(x.len() >= acc.len());
+        unsafe {
+            std::arch::asm!("
+                vbroadcastss ymm2, {k}
+                2:
+                    vmovups ymm0, [{acc}]
+                    vfmadd231ps ymm0, ymm2, [{x}]
+                    vmovups [{acc}], ymm0
+                    add {acc}, 32
+                    add {x}, 32
+                    sub {len}, 8
+                    jnz 2b
+                vzeroupper
+            ",
+            k = in(xmm_reg) k,
+            acc = inout(reg) acc.as_mut_ptr() => _,
+            x = inout(reg) x.as_ptr() => _,
+            len = inout(reg) acc.len() => _,
+            out("ymm0") _,
+            out("ymm2") _,
+            );
+        }
+    }
+);
+
+dw_impl_wrap!(
+    i8,
+    i32,
+    avx2_depthwise_i8_8n,
+    8,
+    #[inline(never)]
+    fn mul_add(acc: &mut [i32], x: &[i8], k: &[i32]) {
+        assert!(!acc.is_empty());
+        assert_eq!(acc.len() % 8, 0);
+        assert!(x.len() >= acc.len() && k.len() >= acc.len());
+        unsafe {
+            std::arch::asm!("
+                2:
+                    vpmovsxbd ymm1, qword ptr [{x}]
+                    vpmulld ymm1, ymm1, [{k}]
+                    vpaddd ymm0, ymm1, [{acc}]
+                    vmovdqu [{acc}], ymm0
+                    add {acc}, 32
+                    add {x}, 8
+                    add {k}, 32
+                    sub {len}, 8
+                    jnz 2b
+                vzeroupper
+            ",
+            acc = inout(reg) acc.as_mut_ptr() => _,
+            x = inout(reg) x.as_ptr() => _,
+            k = inout(reg) k.as_ptr() => _,
+            len = inout(reg) acc.len() => _,
+            out("ymm0") _,
+            out("ymm1") _,
+            );
+        }
+    },
+    #[inline(never)]
+    fn mul_add_scalar(acc: &mut [i32], x: &[i8], k: i32) {
+        assert!(!acc.is_empty());
+        assert_eq!(acc.len() % 8, 0);
+        assert!(x.len() >= acc.len());
+        unsafe {
+            std::arch::asm!("
+                vmovd xmm2, {k:e}
+                vpbroadcastd ymm2, xmm2
+                2:
+                    vpmovsxbd ymm1, qword ptr [{x}]
+                    vpmulld ymm1, ymm1, ymm2
+                    vpaddd ymm0, ymm1, [{acc}]
+                    vmovdqu [{acc}], ymm0
+                    add {acc}, 32
+                    add {x}, 8
+                    sub {len}, 8
+                    jnz 2b
+                vzeroupper
+            ",
+            k = in(reg) k,
+            acc = inout(reg) acc.as_mut_ptr() => _,
+            x = inout(reg) x.as_ptr() => _,
+            len = inout(reg) acc.len() => _,
+            out("ymm0") _,
+            out("ymm1") _,
+            out("ymm2") _,
+            );
+        }
+    }
+);
+
+#[cfg(test)]
+mod test_fma_depthwise_f32_8n {
+    use super::*;
+    depthwise_frame_tests!(is_x86_feature_detected!("fma"), f32, f32, fma_depthwise_f32_8n);
+}
+
+#[cfg(test)]
+mod test_avx2_depthwise_i8_8n {
+    use super::*;
+    depthwise_frame_tests!(is_x86_feature_detected!("avx2"), i8, i32, avx2_depthwise_i8_8n);
+}
@@ -0,0 +1,65 @@
+sparse_impl_wrap!(
+    fma_sparse_f32_4x8,
+    4,
+    8,
+    #[inline(never)]
+    unsafe fn kernel(
+        c: *mut f32,
+        c_rs: usize,
+        b: *const f32,
+        b_rs: usize,
+        cols: &[u32],
+        values: &[f32],
+    ) {
+        debug_assert_eq!(values.len(), cols.len() * 4);
+        std::arch::asm!("
+            vxorps ymm0, ymm0, ymm0
+            vxorps ymm1, ymm1, ymm1
+            vxorps ymm2, ymm2, ymm2
+            vxorps ymm3, ymm3, ymm3
+            test {len}, {len}
+            jz 3f
+            2:
+                mov {col:e}, dword ptr [{cols}]
+                imul {col}, {b_rs}
+                vmovups ymm4, [{b} + {col}]
+                vbroadcastss ymm5, dword ptr [{values}]
+                vfmadd231ps ymm0, ymm4, ymm5
+                vbroadcastss ymm5, dword ptr [{values} + 4]
+                vfmadd231ps ymm1, ymm4, ymm5
+                vbroadcastss ymm5, dword ptr [{values} + 8]
+                vfmadd231ps ymm2, ymm4, ymm5
+                vbroadcastss ymm5, dword ptr [{values} + 12]
+                vfmadd231ps ymm3, ymm4, ymm5
+                add {cols}, 4
+                add {values}, 16
+                sub {len}, 1
+                jnz 2b
+            3:
+            vmovups [{c}], ymm0
+            add {c}, {c_rs}
+            vmovups [{c}], ymm1
+            add {c}, {c_rs}
+            vmovups [{c}], ymm2
+            add {c}, {c_rs}
+            vmovups [{c}], ymm3
+            vzeroupper
+        ",
+        c = inout(reg) c => _,
+        c_rs = in(reg) c_rs * 4,
+        b = in(reg) b,
+        b_rs = in(reg) b_rs * 4,
+        cols = inout(reg) cols.as_ptr() => _,
+        values = inout(reg) values.as_ptr() => _,
+        len = inout(reg) cols.len() => _,
+        col = out(reg) _,
+        out("ymm0") _, out("ymm1") _, out("ymm2") _, out("ymm3") _, out("ymm4") _, out("ymm5") _,
+        );
+    }
+);
+
+#[cfg(test)]
+mod test_fma_sparse_f32_4x8 {
+    use super::*;
+    sparse_frame_tests!(is_x86_feature_detected!("fma"), fma_sparse_f32_4x8);
+}
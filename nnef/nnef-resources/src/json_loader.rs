@@ -55,6 +55,10 @@ impl Resource for JsonResource {
         convert_value(value)
             .with_context(|| anyhow!("Error while converting JSON value to NNEF value"))
     }
+
+    fn serialize(&self) -> TractResult<Option<(&'static str, Vec<u8>)>> {
+        Ok(Some(("json", serde_json::to_vec(&self.0)?)))
+    }
 }
 
 pub fn convert_value(value: &serde_json::Value) -> TractResult<Value> {
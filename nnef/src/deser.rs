@@ -79,6 +79,8 @@ impl<'mb> ModelBuilder<'mb> {
         }
         self.scopes.push(HashMap::new());
         self.wire_body(&self.proto_model.doc.graph_def.body).context("Wiring root graph body")?;
+        // properties may refer to variables of the root graph body
+        self.parse_properties().context("Parsing properties")?;
         let vars = self.scopes.pop().unwrap();
 
         let outputs = self
@@ -99,8 +101,6 @@ impl<'mb> ModelBuilder<'mb> {
             .collect::<TractResult<TVec<OutletId>>>()?;
         self.model.set_output_outlets(&outputs)?;
 
-        self.parse_properties().context("Parsing properties")?;
-
         for (ix, name) in self.proto_model.doc.graph_def.results.iter().enumerate() {
             self.model.set_outlet_label(outputs[ix], name.0.to_string())?;
         }
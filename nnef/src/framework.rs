@@ -78,6 +78,17 @@ impl Nnef {
         ModelBuilder::new(self, proto_model, symbols).into_typed_model()
     }
 
+    /// Record in `model` properties the weights packed by the host CPU kernels.
+    ///
+    /// Node names change on the way through NNEF, so the packing is done on
+    /// the model as it will be reloaded. Loading the serialized model on a host
+    /// selecting the same kernels then skips the packing at optimization time.
+    pub fn embed_prepacked_weights(&self, model: &mut TypedModel) -> TractResult<()> {
+        let proto = crate::ser::to_proto_model(self, model)?;
+        let optimized = self.model_for_proto_model(&proto)?.into_optimized()?;
+        tract_core::ops::matmul::pack::embed_prepacked_weights(model, &optimized)
+    }
+
     pub fn write(&self, model: &TypedModel, w: impl std::io::Write) -> TractResult<()> {
         self.write_to_tar(model, w)?;
         Ok(())
@@ -178,6 +189,18 @@ impl Nnef {
 
                 ar.append_data(&mut header, filename, &mut &*submodel_data)
                     .with_context(|| format!("Appending submodel {label:?}"))?;
+            } else if let Some((extension, data)) = resource.serialize()? {
+                let mut filename = std::path::PathBuf::from_str(label)?;
+                filename.set_extension(extension);
+
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_mtime(now.as_secs());
+                header.set_cksum();
+
+                ar.append_data(&mut header, filename, &mut &*data)
+                    .with_context(|| format!("Appending resource {label:?}"))?;
             }
         }
         Ok(())
@@ -211,8 +234,37 @@ impl Nnef {
             let mut file = std::fs::File::create(filename)?;
             crate::tensors::write_tensor(&mut file, t)?;
         }
+
+        for (label, resource) in &proto_model.resources {
+            if let Some((extension, data)) = resource.serialize()? {
+                let mut filename = path.join(label);
+                filename.set_extension(extension);
+                std::fs::create_dir_all(filename.parent().unwrap())?;
+                std::fs::write(filename, data)?;
+            }
+        }
         Ok(())
     }
+
+    fn proto_model_for_mapped_tar(
+        &self,
+        storage: Arc<dyn TensorStorage>,
+    ) -> TractResult<ProtoModel> {
+        let mut resources: HashMap<String, Arc<dyn Resource>> = Default::default();
+        let mut tar = tar::Archive::new(storage.as_bytes());
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_path_buf();
+            let start = entry.raw_file_position() as usize;
+            let range = start..start + entry.size() as usize;
+            if path.extension().map(|e| e == "dat").unwrap_or(false) {
+                read_storage(&path, &storage, range, &mut resources, self)?;
+            } else {
+                read_stream(&path, &mut entry, &mut resources, self)?;
+            }
+        }
+        proto_model_from_resources(resources)
+    }
 }
 
 impl tract_core::prelude::Framework<ProtoModel, TypedModel> for Nnef {
@@ -225,6 +277,14 @@ impl tract_core::prelude::Framework<ProtoModel, TypedModel> for Nnef {
         let path = path.as_ref();
         if path.is_file() {
             let mut f = std::fs::File::open(path)?;
+            #[cfg(all(any(windows, unix), not(target_os = "emscripten")))]
+            {
+                // uncompressed tar files are mapped, so tensors can borrow their data
+                let mmap = unsafe { memmap2::Mmap::map(&f)? };
+                if mmap.len() > 2 && mmap[0..2] != [0x1f, 0x8b] {
+                    return self.proto_model_for_mapped_tar(Arc::new(mmap));
+                }
+            }
             return self.proto_model_for_read(&mut f);
         }
 
@@ -245,6 +305,14 @@ impl tract_core::prelude::Framework<ProtoModel, TypedModel> for Nnef {
                 .skip(path.components().count())
                 .collect::<std::path::PathBuf>();
             let mut stream = std::fs::File::open(entry.path())?;
+            #[cfg(all(any(windows, unix), not(target_os = "emscripten")))]
+            if subpath.extension().map(|e| e == "dat").unwrap_or(false) {
+                let storage: Arc<dyn TensorStorage> =
+                    Arc::new(unsafe { memmap2::Mmap::map(&stream)? });
+                let len = storage.as_bytes().len();
+                read_storage(&subpath, &storage, 0..len, &mut resources, self)?;
+                continue;
+            }
             read_stream(&subpath, &mut stream, &mut resources, self)?;
         }
         proto_model_from_resources(resources)
@@ -377,6 +445,27 @@ fn read_stream<R: std::io::Read>(
     reader: &mut R,
     resources: &mut HashMap<String, Arc<dyn Resource>>,
     framework: &Nnef,
+) -> TractResult<()> {
+    load_resource(path, resources, framework, |loader| loader.try_load(path, reader, framework))
+}
+
+fn read_storage(
+    path: &Path,
+    storage: &Arc<dyn TensorStorage>,
+    range: std::ops::Range<usize>,
+    resources: &mut HashMap<String, Arc<dyn Resource>>,
+    framework: &Nnef,
+) -> TractResult<()> {
+    load_resource(path, resources, framework, |loader| {
+        loader.try_load_from_storage(path, storage, range.clone(), framework)
+    })
+}
+
+fn load_resource(
+    path: &Path,
+    resources: &mut HashMap<String, Arc<dyn Resource>>,
+    framework: &Nnef,
+    mut load: impl FnMut(&dyn ResourceLoader) -> TractResult<Option<(String, Arc<dyn Resource>)>>,
 ) -> TractResult<()> {
     // ignore path with any component starting with "." (because OSX's tar is weird)
     #[cfg(target_family = "unix")]
@@ -386,7 +475,7 @@ fn read_stream<R: std::io::Read>(
     let mut last_loader_name;
     for loader in framework.resource_loaders.iter() {
         last_loader_name = Some(loader.name());
-        let loaded = loader.try_load(path, reader, framework).with_context(|| {
+        let loaded = load(&**loader).with_context(|| {
             anyhow!("Error while loading resource by {:?} at path {:?}", loader.name(), path)
         })?;
         if let Some((id, resource)) = loaded {
@@ -403,3 +492,125 @@ fn read_stream<R: std::io::Read>(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use temp_dir::TempDir;
+
+    fn model() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact([16]))?;
+        let weights = model.add_const("weights", Tensor::from_shape(&[16], &[1f32; 16])?)?;
+        let output = model.wire_node("add", tract_core::ops::math::add(), &[source, weights])?;
+        model.set_output_outlets(&output)?;
+        Ok(model)
+    }
+
+    #[test]
+    #[cfg(all(any(windows, unix), not(target_os = "emscripten")))]
+    fn mapped_tensors_from_dir() -> TractResult<()> {
+        let d = TempDir::new()?;
+        let nnef = crate::nnef();
+        nnef.write_to_dir(&model()?, d.path().join("model"))?;
+        let proto = nnef.proto_model_for_path(d.path().join("model"))?;
+        assert!(!proto.tensors.is_empty());
+        assert!(proto.tensors.values().all(|t| t.is_borrowed()));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(all(any(windows, unix), not(target_os = "emscripten")))]
+    fn mapped_tensors_from_tar() -> TractResult<()> {
+        let d = TempDir::new()?;
+        let nnef = crate::nnef();
+        let path = d.path().join("model.nnef.tar");
+        nnef.write_to_tar(&model()?, std::fs::File::create(&path)?)?;
+        let proto = nnef.proto_model_for_path(&path)?;
+        assert!(!proto.tensors.is_empty());
+        assert!(proto.tensors.values().all(|t| t.is_borrowed()));
+        let model = nnef.model_for_proto_model(&proto)?.into_runnable()?;
+        let output = model.run(tvec!(Tensor::from_shape(&[16], &[1f32; 16])?.into()))?;
+        assert_eq!(*output[0], Tensor::from_shape(&[16], &[2f32; 16])?);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(all(any(windows, unix), not(target_os = "emscripten")))]
+    fn prepacked_weights_round_trip() -> TractResult<()> {
+        use tract_core::ops::cnn::{ConvUnary, KernelFormat, PaddingSpec, PoolSpec};
+        use tract_core::ops::konst::Const;
+        use tract_core::ops::nn::DataFormat;
+
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact([1, 2, 8]))?;
+        let kernel =
+            Tensor::from_shape(&[4, 2, 3], &(0..24).map(|x| x as f32).collect::<Vec<_>>())?;
+        let conv = ConvUnary {
+            pool_spec: PoolSpec {
+                data_format: DataFormat::NCHW,
+                kernel_shape: tvec!(3),
+                padding: PaddingSpec::Valid,
+                dilations: None,
+                strides: None,
+                output_channel_override: Some(4),
+            },
+            kernel_fmt: KernelFormat::OIHW,
+            kernel: kernel.into_arc_tensor(),
+            group: 1,
+            bias: None,
+            q_params: None,
+        };
+        let output = model.wire_node("conv", conv, &[source])?;
+        model.set_output_outlets(&output)?;
+        let mut model = model.into_decluttered()?;
+        let optimized = model.clone().into_optimized()?;
+        let nnef = crate::nnef().with_tract_core();
+        nnef.embed_prepacked_weights(&mut model)?;
+
+        let d = TempDir::new()?;
+        let path = d.path().join("model.nnef.tar");
+        nnef.write_to_tar(&model, std::fs::File::create(&path)?)?;
+        let reloaded = nnef.model_for_path(&path)?.into_optimized()?;
+        let packed = &reloaded.properties["tract_prepacked.conv_conv.kernels"];
+        assert!(packed.is_borrowed());
+        let kernels = reloaded.node_by_name("conv_conv.kernels")?.op_as::<Const>().unwrap();
+        assert!(Arc::ptr_eq(&kernels.0, packed));
+
+        let input = Tensor::from_shape(&[1, 2, 8], &(0..16).map(|x| x as f32).collect::<Vec<_>>())?;
+        let expected = optimized.into_runnable()?.run(tvec!(input.clone().into()))?;
+        let found = reloaded.into_runnable()?.run(tvec!(input.into()))?;
+        assert_eq!(expected, found);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(all(any(windows, unix), not(target_os = "emscripten")))]
+    fn sparse_weights_round_trip() -> TractResult<()> {
+        use tract_core::ops::einsum::EinSum;
+        use tract_core::ops::matmul::sparse::*;
+
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact([16, 3]))?;
+        let weights = (0..128).map(|i| if (i / 64 + i % 16) % 4 == 0 { i as f32 } else { 0.0 });
+        let weights = Tensor::from_shape(&[8, 16], &weights.collect::<Vec<_>>())?;
+        let weights = model.add_const("weights", weights)?;
+        let op = EinSum::new("mk,kn->mn".parse()?, f32::datum_type());
+        let output = model.wire_node("product", op, &[weights, source])?;
+        model.set_output_outlets(&output)?;
+        let mut model = model.into_decluttered()?;
+        let input = Tensor::from_shape(&[16, 3], &(0..48).map(|x| x as f32).collect::<Vec<_>>())?;
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone().into()))?;
+        sparsify_weights(&mut model, SPARSE_DENSITY_THRESHOLD)?;
+
+        let nnef = crate::nnef().with_tract_core();
+        let d = TempDir::new()?;
+        let path = d.path().join("model.nnef.tar");
+        nnef.write_to_tar(&model, std::fs::File::create(&path)?)?;
+        let reloaded = nnef.model_for_path(&path)?;
+        assert!(reloaded.nodes().iter().any(|n| n.op_is::<SparseMatMul>()));
+        let found = reloaded.into_optimized()?.into_runnable()?.run(tvec!(input.into()))?;
+        assert_eq!(expected, found);
+        Ok(())
+    }
+}
@@ -1,16 +1,19 @@
 use crate::internal::*;
 use tract_core::ops;
 
+mod audio;
 mod broadcast;
 mod cast;
 #[cfg(feature = "complex")]
 mod complex;
 mod downsample;
+mod dropout;
 mod dyn_slice;
 mod einsum;
 mod fft;
 mod force_eval;
 mod gather;
+mod image;
 mod load;
 mod matmul;
 mod one_hot;
@@ -18,19 +21,25 @@ mod qconv;
 mod qmatmul;
 mod range;
 mod reduce;
+mod resize;
 mod scan;
 mod scatter;
 mod shape_of;
 mod source;
+mod sparse;
 mod store;
 mod submodel;
 mod topk;
+mod training;
 mod trilu;
 
 pub fn register(registry: &mut Registry) {
     registry.register_unit_element_wise("tract_core_round_even", &ops::math::RoundHalfToEven {});
     registry.register_unit_element_wise("tract_core_erf", &ops::math::Erf {});
     registry.register_unit_element_wise("tract_core_hard_swish", &ops::nn::HardSwish {});
+    registry.register_unit_element_wise("tract_core_silu", &ops::nn::Silu {});
+    registry
+        .register_unit_element_wise("tract_core_gelu_approximate", &ops::nn::GeluApproximate {});
 
     registry.register_binary("tract_core_xor", &ops::logic::Xor {});
     registry.register_binary("tract_core_bitand", &ops::logic::BitAnd {});
@@ -40,29 +49,35 @@ pub fn register(registry: &mut Registry) {
 
     registry.register_binary("tract_shl", &ops::math::ShiftLeft);
     registry.register_binary("tract_shr", &ops::math::ShiftRight);
+    audio::register(registry);
     broadcast::register(registry);
     cast::register(registry);
     #[cfg(feature = "complex")]
     complex::register(registry);
     downsample::register(registry);
+    dropout::register(registry);
     dyn_slice::register(registry);
     einsum::register(registry);
     fft::register(registry);
     force_eval::register(registry);
     gather::register(registry);
+    image::register(registry);
     load::register(registry);
     matmul::register(registry);
     one_hot::register(registry);
     qconv::register(registry);
     qmatmul::register(registry);
     reduce::register(registry);
+    resize::register(registry);
     scan::register(registry);
     scatter::register(registry);
     shape_of::register(registry);
     source::register(registry);
+    sparse::register(registry);
     store::register(registry);
     submodel::register(registry);
     range::register(registry);
     topk::register(registry);
+    training::register(registry);
     trilu::register(registry);
 }
@@ -0,0 +1,141 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::audio::{LogMelSpectrogram, Mfcc, Resample};
+
+pub fn register(registry: &mut Registry) {
+    let log_mel_parameters = [
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Integer.named("axis"),
+        TypeName::Integer.named("frame"),
+        TypeName::Integer.named("stride"),
+        TypeName::Integer.named("fft_len"),
+        TypeName::Scalar.tensor().named("window").default(false),
+        TypeName::Scalar.named("power").default(2.0),
+        TypeName::Scalar.tensor().named("filterbank"),
+        TypeName::Scalar.named("log_floor").default(1e-10),
+    ];
+    registry.register_dumper(TypeId::of::<LogMelSpectrogram>(), ser_log_mel);
+    registry.register_primitive(
+        "tract_core_log_mel_spectrogram",
+        &log_mel_parameters,
+        &[("output", TypeName::Scalar.tensor())],
+        de_log_mel,
+    );
+    registry.register_dumper(TypeId::of::<Mfcc>(), ser_mfcc);
+    let mut mfcc_parameters = log_mel_parameters.to_vec();
+    mfcc_parameters.push(TypeName::Integer.named("coefficients"));
+    registry.register_primitive(
+        "tract_core_mfcc",
+        &mfcc_parameters,
+        &[("output", TypeName::Scalar.tensor())],
+        de_mfcc,
+    );
+    registry.register_dumper(TypeId::of::<Resample>(), ser_resample);
+    registry.register_primitive(
+        "tract_core_resample",
+        &[
+            TypeName::Scalar.tensor().named("input"),
+            TypeName::Integer.named("axis"),
+            TypeName::Integer.named("up"),
+            TypeName::Integer.named("down"),
+            TypeName::Scalar.tensor().named("filter"),
+        ],
+        &[("output", TypeName::Scalar.tensor())],
+        de_resample,
+    );
+}
+
+fn log_mel_named_args(
+    ast: &mut IntoAst,
+    node: &TypedNode,
+    op: &LogMelSpectrogram,
+) -> TractResult<TVec<(&'static str, RValue)>> {
+    let filterbank = ast.konst(format!("{}_filterbank", node.name), &op.filterbank)?;
+    let mut named: TVec<(_, RValue)> = tvec![
+        ("axis", numeric(op.axis)),
+        ("frame", numeric(op.frame)),
+        ("stride", numeric(op.stride)),
+        ("fft_len", numeric(op.fft_len)),
+        ("power", numeric(op.power)),
+        ("filterbank", (*filterbank).clone()),
+        ("log_floor", numeric(op.log_floor)),
+    ];
+    if let Some(w) = &op.window {
+        let w = ast.konst(format!("{}_window", node.name), w)?;
+        named.push(("window", (*w).clone()));
+    }
+    Ok(named)
+}
+
+fn ser_log_mel(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<LogMelSpectrogram>().context("wrong op")?;
+    let named = log_mel_named_args(ast, node, op)?;
+    Ok(Some(invocation("tract_core_log_mel_spectrogram", &[input], &named)))
+}
+
+fn ser_mfcc(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<Mfcc>().context("wrong op")?;
+    let mut named = log_mel_named_args(ast, node, &op.log_mel)?;
+    named.push(("coefficients", numeric(op.coefficients)));
+    Ok(Some(invocation("tract_core_mfcc", &[input], &named)))
+}
+
+fn log_mel(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<LogMelSpectrogram> {
+    Ok(LogMelSpectrogram {
+        axis: invocation.named_arg_as(builder, "axis")?,
+        frame: invocation.named_arg_as(builder, "frame")?,
+        stride: invocation.named_arg_as(builder, "stride")?,
+        fft_len: invocation.named_arg_as(builder, "fft_len")?,
+        window: invocation.optional_named_arg_as(builder, "window")?,
+        power: invocation.named_arg_as(builder, "power")?,
+        filterbank: invocation.named_arg_as(builder, "filterbank")?,
+        log_floor: invocation.named_arg_as(builder, "log_floor")?,
+    })
+}
+
+fn de_log_mel(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let op = log_mel(builder, invocation)?;
+    builder.wire(op, &[input])
+}
+
+fn de_mfcc(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let op = Mfcc {
+        log_mel: log_mel(builder, invocation)?,
+        coefficients: invocation.named_arg_as(builder, "coefficients")?,
+    };
+    builder.wire(op, &[input])
+}
+
+fn ser_resample(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<Resample>().context("wrong op")?;
+    let filter = ast.konst(format!("{}_filter", node.name), &op.filter)?;
+    Ok(Some(invocation(
+        "tract_core_resample",
+        &[input],
+        &[
+            ("axis", numeric(op.axis)),
+            ("up", numeric(op.up)),
+            ("down", numeric(op.down)),
+            ("filter", (*filter).clone()),
+        ],
+    )))
+}
+
+fn de_resample(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let op = Resample {
+        axis: invocation.named_arg_as(builder, "axis")?,
+        up: invocation.named_arg_as(builder, "up")?,
+        down: invocation.named_arg_as(builder, "down")?,
+        filter: invocation.named_arg_as(builder, "filter")?,
+    };
+    builder.wire(op, &[input])
+}
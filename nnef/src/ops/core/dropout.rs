@@ -0,0 +1,44 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::nn::Dropout;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<Dropout>(), ser_dropout);
+    registry.register_primitive(
+        "tract_core_dropout",
+        &[
+            TypeName::Scalar.tensor().named("input"),
+            TypeName::Scalar.named("ratio").default(0.5),
+            TypeName::Logical.named("training").default(false),
+            TypeName::Logical.named("output_mask").default(false),
+            TypeName::Integer.named("seed").default(false),
+        ],
+        &[("output", TypeName::Scalar.tensor()), ("mask", TypeName::Logical.tensor())],
+        de_dropout,
+    );
+}
+
+fn ser_dropout(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<Dropout>().context("wrong op")?;
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let mut named = vec![
+        ("ratio", numeric(op.ratio)),
+        ("training", logical(op.training)),
+        ("output_mask", logical(op.output_mask)),
+    ];
+    if let Some(seed) = op.seed {
+        named.push(("seed", numeric(seed)));
+    }
+    Ok(Some(invocation("tract_core_dropout", &[input], &named)))
+}
+
+fn de_dropout(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let op = Dropout {
+        ratio: invocation.named_arg_as(builder, "ratio")?,
+        training: invocation.named_arg_as(builder, "training")?,
+        output_mask: invocation.named_arg_as(builder, "output_mask")?,
+        seed: invocation.optional_named_arg_as::<i64>(builder, "seed")?.map(|s| s as u64),
+    };
+    builder.wire(op, &[input])
+}
@@ -0,0 +1,136 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::image::{CenterCropPad, NormalizeImage, YuvToRgb};
+use tract_core::ops::resize::Interpolator;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<YuvToRgb>(), ser_yuv_to_rgb);
+    registry.register_primitive(
+        "tract_core_yuv_to_rgb",
+        &[
+            TypeName::Integer.tensor().named("input"),
+            TypeName::String.named("format").default("nv12"),
+            TypeName::Logical.named("full_range").default(false),
+        ],
+        &[("output", TypeName::Integer.tensor())],
+        de_yuv_to_rgb,
+    );
+    registry.register_dumper(TypeId::of::<CenterCropPad>(), ser_center_crop_pad);
+    registry.register_primitive(
+        "tract_core_center_crop_pad",
+        &[
+            TypeName::Scalar.tensor().named("input"),
+            TypeName::Integer.array().named("axes"),
+            TypeName::Integer.array().named("sizes"),
+        ],
+        &[("output", TypeName::Scalar.tensor())],
+        de_center_crop_pad,
+    );
+    registry.register_dumper(TypeId::of::<NormalizeImage>(), ser_normalize_image);
+    registry.register_primitive(
+        "tract_core_normalize_image",
+        &[
+            TypeName::Integer.tensor().named("input"),
+            TypeName::Integer.array().named("size").default(false),
+            TypeName::String.named("mode").default("linear"),
+            TypeName::Scalar.named("cubic_coeff_a").default(-0.75),
+            TypeName::Logical.named("antialias").default(false),
+            TypeName::Scalar.named("scale").default(1.0),
+            TypeName::Scalar.array().named("mean"),
+            TypeName::Scalar.array().named("std"),
+        ],
+        &[("output", TypeName::Scalar.tensor())],
+        de_normalize_image,
+    );
+}
+
+fn ser_yuv_to_rgb(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<YuvToRgb>().context("wrong op")?;
+    let input = ast.mapping[&node.inputs[0]].clone();
+    Ok(Some(invocation(
+        "tract_core_yuv_to_rgb",
+        &[input],
+        &[("format", string(op.format.as_str())), ("full_range", logical(op.full_range))],
+    )))
+}
+
+fn de_yuv_to_rgb(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let op = YuvToRgb {
+        format: invocation.named_arg_as::<String>(builder, "format")?.parse()?,
+        full_range: invocation.named_arg_as(builder, "full_range")?,
+    };
+    builder.wire(op, &[input])
+}
+
+fn ser_center_crop_pad(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<CenterCropPad>().context("wrong op")?;
+    let input = ast.mapping[&node.inputs[0]].clone();
+    Ok(Some(invocation(
+        "tract_core_center_crop_pad",
+        &[input],
+        &[("axes", ints(&op.axes)), ("sizes", ints(&op.sizes))],
+    )))
+}
+
+fn de_center_crop_pad(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let op = CenterCropPad {
+        axes: invocation.named_arg_as(builder, "axes")?,
+        sizes: invocation.named_arg_as(builder, "sizes")?,
+    };
+    builder.wire(op, &[input])
+}
+
+fn ser_normalize_image(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<NormalizeImage>().context("wrong op")?;
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let mut named: TVec<(_, RValue)> = tvec![
+        ("mode", string(op.interpolator.as_str())),
+        ("antialias", logical(op.antialias)),
+        ("scale", numeric(op.scale)),
+        ("mean", array(op.mean.iter().map(numeric).collect::<TVec<_>>())),
+        ("std", array(op.std.iter().map(numeric).collect::<TVec<_>>())),
+    ];
+    if let Interpolator::Cubic { a } = op.interpolator {
+        named.push(("cubic_coeff_a", numeric(a)));
+    }
+    if let Some((h, w)) = op.size {
+        named.push(("size", ints(&[h, w])));
+    }
+    Ok(Some(invocation("tract_core_normalize_image", &[input], &named)))
+}
+
+fn de_normalize_image(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let size: Option<TVec<usize>> = invocation.optional_named_arg_as(builder, "size")?;
+    let size = match size.as_deref() {
+        None => None,
+        Some(&[h, w]) => Some((h, w)),
+        Some(s) => bail!("Expected a [height, width] size, got {:?}", s),
+    };
+    let interpolator = match &*invocation.named_arg_as::<String>(builder, "mode")? {
+        "nearest" => Interpolator::Nearest,
+        "linear" => Interpolator::Linear,
+        "cubic" => Interpolator::Cubic { a: invocation.named_arg_as(builder, "cubic_coeff_a")? },
+        s => bail!("Unsupported resize mode: {}", s),
+    };
+    let op = NormalizeImage {
+        size,
+        interpolator,
+        antialias: invocation.named_arg_as(builder, "antialias")?,
+        scale: invocation.named_arg_as(builder, "scale")?,
+        mean: invocation.named_arg_as(builder, "mean")?,
+        std: invocation.named_arg_as(builder, "std")?,
+    };
+    builder.wire(op, &[input])
+}
@@ -0,0 +1,87 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::resize::{Interpolator, Resize};
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<Resize>(), ser_resize);
+    registry.register_primitive(
+        "tract_core_resize",
+        &[
+            TypeName::Scalar.tensor().named("input"),
+            TypeName::Integer.array().named("axes"),
+            TypeName::Scalar.array().named("scales").default(false),
+            TypeName::Integer.array().named("sizes").default(false),
+            TypeName::Scalar.array().named("roi").default(false),
+            TypeName::String.named("mode").default("nearest"),
+            TypeName::String.named("coordinate_transformation_mode").default("half_pixel"),
+            TypeName::String.named("nearest_mode").default("round_prefer_floor"),
+            TypeName::Scalar.named("cubic_coeff_a").default(-0.75),
+            TypeName::Logical.named("antialias").default(false),
+            TypeName::Logical.named("exclude_outside").default(false),
+            TypeName::Scalar.named("extrapolation_value").default(0.0),
+            TypeName::String.named("keep_aspect_ratio_policy").default("stretch"),
+        ],
+        &[("output", TypeName::Scalar.tensor())],
+        de_resize,
+    );
+}
+
+fn ser_resize(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<Resize>().context("wrong op")?;
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let mut named: TVec<(_, RValue)> = tvec![
+        ("axes", ints(&op.axes)),
+        ("mode", string(op.interpolator.as_str())),
+        ("coordinate_transformation_mode", string(op.coord_transformer.as_str())),
+        ("nearest_mode", string(op.nearest.as_str())),
+        ("antialias", logical(op.antialias)),
+        ("exclude_outside", logical(op.exclude_outside)),
+        ("extrapolation_value", numeric(op.extrapolation_value)),
+        ("keep_aspect_ratio_policy", string(op.keep_aspect_ratio_policy.as_str())),
+    ];
+    if let Interpolator::Cubic { a } = op.interpolator {
+        named.push(("cubic_coeff_a", numeric(a)));
+    }
+    if let Some(scales) = &op.scales {
+        named.push(("scales", array(scales.iter().map(numeric).collect::<TVec<_>>())));
+    }
+    if let Some(sizes) = &op.sizes {
+        named.push(("sizes", tdims(sizes)));
+    }
+    if let Some(roi) = &op.roi {
+        named.push(("roi", array(roi.iter().map(numeric).collect::<TVec<_>>())));
+    }
+    Ok(Some(invocation("tract_core_resize", &[input], &named)))
+}
+
+fn de_resize(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let axes: TVec<usize> = invocation.named_arg_as(builder, "axes")?;
+    let scales: Option<TVec<f32>> = invocation.optional_named_arg_as(builder, "scales")?;
+    let sizes: Option<TVec<TDim>> = invocation.optional_named_arg_as(builder, "sizes")?;
+    let roi: Option<TVec<f32>> = invocation.optional_named_arg_as(builder, "roi")?;
+    let interpolator = match &*invocation.named_arg_as::<String>(builder, "mode")? {
+        "nearest" => Interpolator::Nearest,
+        "linear" => Interpolator::Linear,
+        "cubic" => Interpolator::Cubic { a: invocation.named_arg_as(builder, "cubic_coeff_a")? },
+        s => bail!("Unsupported resize mode: {}", s),
+    };
+    let op = Resize {
+        axes,
+        scales,
+        sizes,
+        roi,
+        coord_transformer: invocation
+            .named_arg_as::<String>(builder, "coordinate_transformation_mode")?
+            .parse()?,
+        interpolator,
+        nearest: invocation.named_arg_as::<String>(builder, "nearest_mode")?.parse()?,
+        antialias: invocation.named_arg_as(builder, "antialias")?,
+        exclude_outside: invocation.named_arg_as(builder, "exclude_outside")?,
+        extrapolation_value: invocation.named_arg_as(builder, "extrapolation_value")?,
+        keep_aspect_ratio_policy: invocation
+            .named_arg_as::<String>(builder, "keep_aspect_ratio_policy")?
+            .parse()?,
+    };
+    builder.wire(op, &[input])
+}
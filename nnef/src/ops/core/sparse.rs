@@ -0,0 +1,92 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::matmul::sparse::SparseMatMul;
+use tract_core::tract_linalg::sparse::BlockSparseMatrix;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<SparseMatMul>(), ser_sparse_matmul);
+    registry.register_primitive(
+        "tract_core_sparse_matmul",
+        &[
+            TypeName::Scalar.tensor().named("input"),
+            TypeName::Integer.tensor().named("row_ptr"),
+            TypeName::Integer.tensor().named("cols"),
+            TypeName::Scalar.tensor().named("values"),
+            TypeName::Integer.named("m"),
+            TypeName::Integer.named("k"),
+            TypeName::Integer.named("mr"),
+            TypeName::Integer.named("b_k"),
+            TypeName::Integer.named("b_n"),
+            TypeName::Integer.named("c_m"),
+            TypeName::Integer.named("c_n"),
+        ],
+        &[("output", TypeName::Scalar.tensor())],
+        de_sparse_matmul,
+    );
+}
+
+fn ser_sparse_matmul(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<SparseMatMul>().unwrap();
+    let w = &op.weights;
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let indices = |v: &[u32]| tensor1(&v.iter().map(|x| *x as i32).collect::<Vec<_>>());
+    let row_ptr = ast
+        .konst_variable(format!("{}_row_ptr", node.name), &indices(&w.row_ptr).into_arc_tensor())?;
+    let cols =
+        ast.konst_variable(format!("{}_cols", node.name), &indices(&w.cols).into_arc_tensor())?;
+    let values =
+        ast.konst_variable(format!("{}_values", node.name), &tensor1(&w.values).into_arc_tensor())?;
+    Ok(Some(invocation(
+        "tract_core_sparse_matmul",
+        &[input, row_ptr, cols, values],
+        &[
+            ("m", numeric(w.m)),
+            ("k", numeric(w.k)),
+            ("mr", numeric(w.mr)),
+            ("b_k", numeric(op.b_k)),
+            ("b_n", numeric(op.b_n)),
+            ("c_m", numeric(op.c_m)),
+            ("c_n", numeric(op.c_n)),
+        ],
+    )))
+}
+
+fn de_sparse_matmul(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let row_ptr = indices(builder, invocation, "row_ptr")?;
+    let cols = indices(builder, invocation, "cols")?;
+    let values: Arc<Tensor> = invocation.named_arg_as(builder, "values")?;
+    let weights = BlockSparseMatrix {
+        m: invocation.named_arg_as(builder, "m")?,
+        k: invocation.named_arg_as(builder, "k")?,
+        mr: invocation.named_arg_as(builder, "mr")?,
+        row_ptr,
+        cols,
+        values: values.as_slice::<f32>()?.to_vec(),
+    };
+    weights.check().context("Inconsistent sparse weights")?;
+    let op = SparseMatMul::new(
+        &weights,
+        invocation.named_arg_as(builder, "b_k")?,
+        invocation.named_arg_as(builder, "b_n")?,
+        invocation.named_arg_as(builder, "c_m")?,
+        invocation.named_arg_as(builder, "c_n")?,
+    );
+    builder.wire(op, &[input])
+}
+
+fn indices(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+    name: &str,
+) -> TractResult<Vec<u32>> {
+    let t: Arc<Tensor> = invocation.named_arg_as(builder, name)?;
+    t.cast_to::<i64>()?
+        .as_slice::<i64>()?
+        .iter()
+        .map(|&x| u32::try_from(x).with_context(|| format!("Invalid index {x} in {name}")))
+        .collect()
+}
@@ -0,0 +1,149 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::training::{Adam, Sgd};
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<Sgd>(), ser_sgd);
+    registry.register_primitive(
+        "tract_core_sgd",
+        &[
+            TypeName::Scalar.tensor().named("param"),
+            TypeName::Scalar.tensor().named("grad"),
+            TypeName::Scalar.tensor().named("velocity").default(false),
+            TypeName::Scalar.named("learning_rate"),
+            TypeName::Scalar.named("momentum").default(0.0),
+        ],
+        &[("output", TypeName::Scalar.tensor()), ("velocity", TypeName::Scalar.tensor())],
+        de_sgd,
+    );
+    registry.register_dumper(TypeId::of::<Adam>(), ser_adam);
+    registry.register_primitive(
+        "tract_core_adam",
+        &[
+            TypeName::Scalar.tensor().named("param"),
+            TypeName::Scalar.tensor().named("grad"),
+            TypeName::Scalar.tensor().named("m"),
+            TypeName::Scalar.tensor().named("v"),
+            TypeName::Integer.tensor().named("step"),
+            TypeName::Scalar.named("learning_rate"),
+            TypeName::Scalar.named("beta1").default(0.9),
+            TypeName::Scalar.named("beta2").default(0.999),
+            TypeName::Scalar.named("epsilon").default(1e-8),
+        ],
+        &[
+            ("output", TypeName::Scalar.tensor()),
+            ("m", TypeName::Scalar.tensor()),
+            ("v", TypeName::Scalar.tensor()),
+        ],
+        de_adam,
+    );
+}
+
+fn ser_sgd(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<Sgd>().context("wrong op")?;
+    let param = ast.mapping[&node.inputs[0]].clone();
+    let grad = ast.mapping[&node.inputs[1]].clone();
+    let mut named =
+        vec![("learning_rate", numeric(op.learning_rate)), ("momentum", numeric(op.momentum))];
+    if let Some(velocity) = node.inputs.get(2) {
+        named.push(("velocity", (*ast.mapping[velocity]).clone()));
+    }
+    Ok(Some(invocation("tract_core_sgd", &[param, grad], &named)))
+}
+
+fn de_sgd(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let mut inputs: TVec<OutletId> = tvec!(
+        invocation.named_arg_as(builder, "param")?,
+        invocation.named_arg_as(builder, "grad")?
+    );
+    inputs.extend(invocation.optional_named_arg_as::<OutletId>(builder, "velocity")?);
+    let op = Sgd {
+        learning_rate: invocation.named_arg_as(builder, "learning_rate")?,
+        momentum: invocation.named_arg_as(builder, "momentum")?,
+    };
+    builder.wire(op, &inputs)
+}
+
+fn ser_adam(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<Adam>().context("wrong op")?;
+    let inputs: TVec<Arc<RValue>> = node.inputs.iter().map(|i| ast.mapping[i].clone()).collect();
+    Ok(Some(invocation(
+        "tract_core_adam",
+        &inputs,
+        &[
+            ("learning_rate", numeric(op.learning_rate)),
+            ("beta1", numeric(op.beta1)),
+            ("beta2", numeric(op.beta2)),
+            ("epsilon", numeric(op.epsilon)),
+        ],
+    )))
+}
+
+fn de_adam(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let inputs = ["param", "grad", "m", "v", "step"]
+        .iter()
+        .map(|name| invocation.named_arg_as(builder, name))
+        .collect::<TractResult<TVec<OutletId>>>()?;
+    let op = Adam {
+        learning_rate: invocation.named_arg_as(builder, "learning_rate")?,
+        beta1: invocation.named_arg_as(builder, "beta1")?,
+        beta2: invocation.named_arg_as(builder, "beta2")?,
+        epsilon: invocation.named_arg_as(builder, "epsilon")?,
+    };
+    builder.wire(op, &inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tract_core::ops::einsum::EinSum;
+    use tract_core::ops::math;
+    use tract_core::ops::nn::{Reduce, Reducer};
+    use tract_core::training::wire_training_step;
+
+    fn training_step(optimizer: &dyn tract_core::training::Optimizer) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact([4, 2]))?;
+        let w = model.add_const("w", tensor1(&[0f32, 0.]))?;
+        let y =
+            model.wire_node("mv", EinSum::new("mk,k->m".parse()?, f32::datum_type()), &[x, w])?[0];
+        let target = model.add_const("target", tensor1(&[1f32, -2., -1., 3.]))?;
+        let diff = model.wire_node("diff", math::sub(), &[y, target])?[0];
+        let square = model.wire_node("square", math::square(), &[diff])?[0];
+        let sum = model.wire_node("sum", Reduce::new(tvec!(0), Reducer::Sum), &[square])?[0];
+        let loss = model.wire_node("loss", AxisOp::Rm(0), &[sum])?[0];
+        let loss = wire_training_step(&mut model, loss, &[w.node], optimizer)?;
+        model.set_output_outlets(&[loss])?;
+        model.into_decluttered()
+    }
+
+    fn round_trip(optimizer: &dyn tract_core::training::Optimizer) -> TractResult<()> {
+        let model = training_step(optimizer)?;
+        let nnef = crate::nnef().with_tract_core();
+        let mut buffer = vec![];
+        nnef.write(&model, &mut buffer)?;
+        let reloaded = nnef.model_for_read(&mut &*buffer)?.into_decluttered()?;
+
+        let x = tensor2(&[[1f32, 0.], [0., 1.], [1., 1.], [-1., -2.]]);
+        let plan = SimplePlan::new(model)?;
+        let mut state = SimpleState::new(&plan)?;
+        let reloaded_plan = SimplePlan::new(reloaded)?;
+        let mut reloaded_state = SimpleState::new(&reloaded_plan)?;
+        for _ in 0..3 {
+            let expected = state.run(tvec!(x.clone().into_tvalue()))?;
+            let found = reloaded_state.run(tvec!(x.clone().into_tvalue()))?;
+            expected[0].close_enough(&found[0], true)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sgd_round_trip() -> TractResult<()> {
+        round_trip(&Sgd { learning_rate: 0.05, momentum: 0.5 })
+    }
+
+    #[test]
+    fn adam_round_trip() -> TractResult<()> {
+        round_trip(&Adam::default())
+    }
+}
@@ -20,6 +20,12 @@ pub trait Resource: DowncastSync + std::fmt::Debug + Send + Sync {
     fn get(&self, _key: &str) -> TractResult<Value> {
         bail!("No key access supported by this resource");
     }
+
+    /// File extension and content of the resource, for resources that can be written back in
+    /// a NNEF archive next to the graph. None for resources that are not serialized this way.
+    fn serialize(&self) -> TractResult<Option<(&'static str, Vec<u8>)>> {
+        Ok(None)
+    }
 }
 
 impl_downcast!(sync Resource);
@@ -36,6 +42,19 @@ pub trait ResourceLoader: Send + Sync {
         framework: &Nnef,
     ) -> TractResult<Option<(String, Arc<dyn Resource>)>>;
 
+    /// Try to load a resource from a file mapped in memory, given the range
+    /// of its content. Loaders that can borrow their data from the mapping
+    /// instead of copying it override this.
+    fn try_load_from_storage(
+        &self,
+        path: &Path,
+        storage: &Arc<dyn TensorStorage>,
+        range: std::ops::Range<usize>,
+        framework: &Nnef,
+    ) -> TractResult<Option<(String, Arc<dyn Resource>)>> {
+        self.try_load(path, &mut &storage.as_bytes()[range], framework)
+    }
+
     fn into_boxed(self) -> Box<dyn ResourceLoader>
     where
         Self: Sized + 'static,
@@ -95,6 +114,22 @@ impl ResourceLoader for DatLoader {
             Ok(None)
         }
     }
+
+    fn try_load_from_storage(
+        &self,
+        path: &Path,
+        storage: &Arc<dyn TensorStorage>,
+        range: std::ops::Range<usize>,
+        _framework: &Nnef,
+    ) -> TractResult<Option<(String, Arc<dyn Resource>)>> {
+        if path.extension().map(|e| e == "dat").unwrap_or(false) {
+            let tensor = crate::tensors::read_tensor_from_storage(storage.clone(), range.start)
+                .with_context(|| format!("Error while reading tensor {path:?}"))?;
+            Ok(Some((resource_path_to_id(path)?, Arc::new(tensor))))
+        } else {
+            Ok(None)
+        }
+    }
 }
 
 impl Resource for HashMap<String, QuantFormat> {}
@@ -19,7 +19,29 @@ struct Header {
     padding: [u32; 11],
 }
 
+/// Read a tensor from a .dat file mapped in memory, starting at `offset`.
+///
+/// Numeric data is borrowed from the mapping instead of being copied.
+pub fn read_tensor_from_storage(
+    storage: Arc<dyn TensorStorage>,
+    offset: usize,
+) -> TractResult<Tensor> {
+    let mut reader = storage.as_bytes().get(offset..).context("Offset beyond storage")?;
+    let (header, dt, shape) = read_header(&mut reader)?;
+    if dt.is_copy() && dt != DatumType::Bool {
+        let data_offset = offset + std::mem::size_of::<Header>();
+        unsafe { Tensor::from_storage_dt(dt, &shape, storage.clone(), data_offset) }
+    } else {
+        read_data(reader, &header, dt, &shape)
+    }
+}
+
 pub fn read_tensor<R: std::io::Read>(mut reader: R) -> TractResult<Tensor> {
+    let (header, dt, shape) = read_header(&mut reader)?;
+    read_data(reader, &header, dt, &shape)
+}
+
+fn read_header<R: std::io::Read>(reader: &mut R) -> TractResult<(Header, DatumType, TVec<usize>)> {
     unsafe {
         let mut header: Header = std::mem::zeroed();
         let buffer: &mut [u8; 128] = std::mem::transmute(&mut header);
@@ -119,8 +141,19 @@ pub fn read_tensor<R: std::io::Read>(mut reader: R) -> TractResult<Tensor> {
                 header.bits_per_item
             ),
         };
+        Ok((header, dt, shape))
+    }
+}
+
+fn read_data<R: std::io::Read>(
+    mut reader: R,
+    header: &Header,
+    dt: DatumType,
+    shape: &[usize],
+) -> TractResult<Tensor> {
+    unsafe {
         if dt.is_copy() {
-            let mut tensor = Tensor::uninitialized_dt(dt, &shape)?;
+            let mut tensor = Tensor::uninitialized_dt(dt, shape)?;
             if dt == DatumType::Bool && header.bits_per_item == 1 {
                 let buf = tensor.as_slice_mut::<bool>()?;
 
@@ -137,7 +170,7 @@ pub fn read_tensor<R: std::io::Read>(mut reader: R) -> TractResult<Tensor> {
             }
             Ok(tensor)
         } else if dt == DatumType::String {
-            let mut tensor = Tensor::zero_dt(dt, &shape)?;
+            let mut tensor = Tensor::zero_dt(dt, shape)?;
             for item in tensor.as_slice_mut_unchecked::<String>() {
                 let len: u32 = reader.read_u32::<LE>()?;
                 let mut bytes = Vec::with_capacity(len as usize);
@@ -213,6 +246,17 @@ mod test {
         assert_eq!(std::mem::size_of::<Header>(), 128);
     }
 
+    #[test]
+    fn read_tensor_borrows_storage() -> TractResult<()> {
+        let t = tensor2(&[[1f32, 2.0], [3.0, 4.0]]);
+        let mut buffer = Vec::<u8>::new();
+        write_tensor(&mut buffer, &t)?;
+        let read = read_tensor_from_storage(Arc::new(buffer), 0)?;
+        assert!(read.is_borrowed());
+        assert_eq!(t, read);
+        Ok(())
+    }
+
     #[test]
     #[cfg(feature="complex")]
     fn serde_tensor_complex_f32() -> TractResult<()> {
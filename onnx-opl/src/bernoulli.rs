@@ -0,0 +1,99 @@
+use tract_nnef::internal::*;
+use tract_nnef::tract_core::random::{RandomOp, RandomOpState, RandomStream};
+
+pub fn register(registry: &mut Registry) {
+    registry.register_primitive(
+        "tract_onnx_bernoulli",
+        &[
+            TypeName::Scalar.tensor().named("input"),
+            TypeName::String.named("datum_type"),
+            TypeName::Integer.named("seed").default(false),
+        ],
+        &[("output", TypeName::Scalar.tensor())],
+        load,
+    );
+    registry.register_dumper(TypeId::of::<Bernoulli>(), dump);
+}
+
+/// Draws 1 with the probability given by each input element, 0 otherwise.
+///
+/// https://github.com/onnx/onnx/blob/main/docs/Operators.md#Bernoulli
+#[derive(Clone, Debug, Hash)]
+pub struct Bernoulli {
+    pub datum_type: DatumType,
+    pub seed: Option<u64>,
+}
+
+impl Op for Bernoulli {
+    fn name(&self) -> Cow<str> {
+        "Bernoulli".into()
+    }
+
+    fn validation(&self) -> Validation {
+        Validation::Random
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for Bernoulli {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn state(
+        &self,
+        session: &mut SessionState,
+        node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        let stream = RandomStream::for_op(session, node_id, self.seed);
+        Ok(Some(Box::new(RandomOpState::<Self>::new(stream))))
+    }
+}
+
+impl RandomOp for Bernoulli {
+    fn eval_with_stream(
+        &self,
+        _session: &SessionState,
+        stream: &mut RandomStream,
+        inputs: TVec<TValue>,
+    ) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let double = input.datum_type() == f64::datum_type();
+        let probs = input.cast_to::<f64>()?;
+        let draws: Vec<bool> = probs
+            .as_slice::<f64>()?
+            .iter()
+            .map(|&p| if double { stream.next_f64() < p } else { (stream.next_f32() as f64) < p })
+            .collect();
+        let draws = tensor1(&draws).into_shape(input.shape())?;
+        let output = draws.cast_to_dt(self.datum_type)?.into_owned();
+        Ok(tvec!(output.into_tvalue()))
+    }
+}
+
+impl TypedOp for Bernoulli {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs[0].datum_type.is_float(), "Bernoulli expects float probabilities");
+        Ok(tvec!(self.datum_type.fact(inputs[0].shape.clone())))
+    }
+
+    as_op!();
+}
+
+fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<Bernoulli>().context("wrong op")?;
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let mut named = vec![("datum_type", string(format!("{:?}", op.datum_type)))];
+    if let Some(seed) = op.seed {
+        named.push(("seed", numeric(seed)));
+    }
+    Ok(Some(invocation("tract_onnx_bernoulli", &[input], &named)))
+}
+
+fn load(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let datum_type = invocation.named_arg_as::<String>(builder, "datum_type")?.parse()?;
+    let seed = invocation.optional_named_arg_as::<i64>(builder, "seed")?.map(|s| s as u64);
+    builder.wire(Bernoulli { datum_type, seed }, &[input])
+}
@@ -0,0 +1,169 @@
+use tract_nnef::internal::*;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<ImageDecoder>(), dump);
+    registry.register_primitive(
+        "tract_onnx_image_decoder",
+        &[
+            TypeName::Integer.tensor().named("input"),
+            TypeName::String.named("pixel_format").default("RGB"),
+        ],
+        &[("output", TypeName::Integer.tensor())],
+        load,
+    );
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PixelFormat {
+    Rgb,
+    Bgr,
+    Grayscale,
+}
+
+impl PixelFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PixelFormat::Rgb => "RGB",
+            PixelFormat::Bgr => "BGR",
+            PixelFormat::Grayscale => "Grayscale",
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        if *self == PixelFormat::Grayscale {
+            1
+        } else {
+            3
+        }
+    }
+}
+
+impl std::str::FromStr for PixelFormat {
+    type Err = TractError;
+    fn from_str(s: &str) -> TractResult<Self> {
+        Ok(match s {
+            "RGB" => PixelFormat::Rgb,
+            "BGR" => PixelFormat::Bgr,
+            "Grayscale" => PixelFormat::Grayscale,
+            _ => bail!("Unsupported pixel format: {}", s),
+        })
+    }
+}
+
+/// Decodes an encoded image (PNG, JPEG, ...) from a 1D u8 tensor into a
+/// [height, width, channels] u8 tensor.
+///
+/// Decoding needs the `image` feature; without it, the op can be loaded and
+/// serialized but fails at evaluation.
+///
+/// https://github.com/onnx/onnx/blob/main/docs/Operators.md#ImageDecoder
+#[derive(Clone, Debug, Hash)]
+pub struct ImageDecoder {
+    pub pixel_format: PixelFormat,
+    pub height: Symbol,
+    pub width: Symbol,
+}
+
+impl ImageDecoder {
+    #[cfg(feature = "image")]
+    fn decode(&self, data: &[u8]) -> TractResult<Tensor> {
+        let image = image::load_from_memory(data).context("Decoding image")?;
+        let (w, h) = (image.width() as usize, image.height() as usize);
+        let mut pixels = if self.pixel_format == PixelFormat::Grayscale {
+            image.to_luma8().into_raw()
+        } else {
+            image.to_rgb8().into_raw()
+        };
+        if self.pixel_format == PixelFormat::Bgr {
+            pixels.chunks_mut(3).for_each(|p| p.swap(0, 2));
+        }
+        tensor1(&pixels).into_shape(&[h, w, self.pixel_format.channels()])
+    }
+
+    #[cfg(not(feature = "image"))]
+    fn decode(&self, _data: &[u8]) -> TractResult<Tensor> {
+        bail!("ImageDecoder requires tract-onnx-opl to be built with the \"image\" feature")
+    }
+}
+
+impl Op for ImageDecoder {
+    fn name(&self) -> Cow<str> {
+        "ImageDecoder".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("pixel_format: {}", self.pixel_format.as_str())])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for ImageDecoder {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        Ok(tvec!(self.decode(input.as_slice::<u8>()?)?.into_tvalue()))
+    }
+}
+
+impl TypedOp for ImageDecoder {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs[0].datum_type == u8::datum_type(), "ImageDecoder expects u8 input");
+        ensure!(inputs[0].rank() == 1, "ImageDecoder expects a 1D encoded stream");
+        Ok(tvec!(u8::fact([
+            self.height.to_dim(),
+            self.width.to_dim(),
+            self.pixel_format.channels().to_dim()
+        ])))
+    }
+
+    as_op!();
+}
+
+fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<ImageDecoder>().context("wrong op")?;
+    Ok(Some(invocation(
+        "tract_onnx_image_decoder",
+        &[input],
+        &[("pixel_format", string(op.pixel_format.as_str()))],
+    )))
+}
+
+fn load(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let op = ImageDecoder {
+        pixel_format: invocation.named_arg_as::<String>(builder, "pixel_format")?.parse()?,
+        height: builder.model.symbol_table.new_with_prefix("h"),
+        width: builder.model.symbol_table.new_with_prefix("w"),
+    };
+    builder.wire(op, &[input])
+}
+
+#[cfg(all(test, feature = "image"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_png() -> TractResult<()> {
+        let image = image::RgbImage::from_raw(2, 1, vec![255, 0, 0, 0, 0, 255]).unwrap();
+        let mut png = std::io::Cursor::new(vec![]);
+        image.write_to(&mut png, image::ImageOutputFormat::Png)?;
+        let symbols = SymbolTable::default();
+        let mut op = ImageDecoder {
+            pixel_format: PixelFormat::Rgb,
+            height: symbols.sym("h"),
+            width: symbols.sym("w"),
+        };
+        let input = tensor1(png.get_ref()).into_tvalue();
+        let rgb = op.eval(tvec!(input.clone()))?.remove(0);
+        assert_eq!(*rgb, tensor3(&[[[255u8, 0, 0], [0, 0, 255]]]));
+        op.pixel_format = PixelFormat::Bgr;
+        let bgr = op.eval(tvec!(input))?.remove(0);
+        assert_eq!(*bgr, tensor3(&[[[0u8, 0, 255], [255, 0, 0]]]));
+        Ok(())
+    }
+}
@@ -2,6 +2,8 @@
 
 use tract_nnef::internal::*;
 
+pub mod bernoulli;
+pub mod image_decoder;
 pub mod is_inf;
 pub mod is_nan;
 pub mod lrn;
@@ -9,6 +11,7 @@ pub mod ml;
 pub mod non_max_suppression;
 pub mod multinomial;
 pub mod random;
+pub mod text;
 
 pub trait WithOnnx {
     fn with_onnx(self) -> Self;
@@ -28,10 +31,13 @@ impl WithOnnx for tract_nnef::framework::Nnef {
 
 fn onnx_opl_registry() -> Registry {
     let mut registry: Registry = Registry::new("tract_onnx");
+    bernoulli::register(&mut registry);
+    image_decoder::register(&mut registry);
     ml::register(&mut registry);
     non_max_suppression::register(&mut registry);
     multinomial::register(&mut registry);
     random::register(&mut registry);
+    text::register(&mut registry);
     registry.register_element_wise(
         "tract_onnx_isinf",
         TypeId::of::<is_inf::IsInf>(),
@@ -0,0 +1,586 @@
+use tract_nnef::internal::*;
+use tract_nnef::ser::ints;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_primitive(
+        "tract_onnx_ml_svm_classifier",
+        &parameters_classifier(),
+        &[("label", TypeName::Integer.tensor()), ("scores", TypeName::Scalar.tensor())],
+        load_classifier,
+    );
+    registry.register_primitive(
+        "tract_onnx_ml_svm_regressor",
+        &parameters_regressor(),
+        &[("output", TypeName::Scalar.tensor())],
+        load_regressor,
+    );
+    registry.register_dumper(TypeId::of::<SvmClassifier>(), dump_classifier);
+    registry.register_dumper(TypeId::of::<SvmRegressor>(), dump_regressor);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KernelType {
+    Linear,
+    Poly,
+    Rbf,
+    Sigmoid,
+}
+
+impl KernelType {
+    pub fn parse(s: &str) -> TractResult<KernelType> {
+        match s {
+            "LINEAR" => Ok(KernelType::Linear),
+            "POLY" => Ok(KernelType::Poly),
+            "RBF" => Ok(KernelType::Rbf),
+            "SIGMOID" => Ok(KernelType::Sigmoid),
+            _ => bail!("Invalid SVM kernel type: {}", s),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KernelType::Linear => "LINEAR",
+            KernelType::Poly => "POLY",
+            KernelType::Rbf => "RBF",
+            KernelType::Sigmoid => "SIGMOID",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Kernel {
+    pub kernel_type: KernelType,
+    pub gamma: f32,
+    pub coef0: f32,
+    pub degree: f32,
+}
+
+impl Kernel {
+    pub fn eval(&self, a: &[f32], b: &[f32]) -> f32 {
+        let dot = || a.iter().zip(b.iter()).map(|(a, b)| a * b).sum::<f32>();
+        match self.kernel_type {
+            KernelType::Linear => dot(),
+            KernelType::Poly => (self.gamma * dot() + self.coef0).powf(self.degree),
+            KernelType::Sigmoid => (self.gamma * dot() + self.coef0).tanh(),
+            KernelType::Rbf => {
+                let d2 = a.iter().zip(b.iter()).map(|(a, b)| (a - b) * (a - b)).sum::<f32>();
+                (-self.gamma * d2).exp()
+            }
+        }
+    }
+}
+
+/// Support vector classifier, as defined by libsvm.
+///
+/// When `vectors_per_class` is empty, the classifier is in linear mode: `coefficients` holds one
+/// row of weights per class and `support_vectors` is ignored. Otherwise, decisions are computed
+/// for every pair of classes (one-vs-one) and the winner is the class with most votes, or
+/// the most probable one if Platt scaling coefficients (`prob_a`, `prob_b`) are present.
+///
+/// Outputs are the winning class index (i32, [N]) and the scores (f32, [N, n_scores]).
+#[derive(Clone, Debug)]
+pub struct SvmClassifier {
+    pub kernel: Kernel,
+    pub n_classes: usize,
+    pub vectors_per_class: TVec<usize>,
+    // f32 [n_support_vectors, n_features]
+    pub support_vectors: Option<Arc<Tensor>>,
+    // f32, [n_classes - 1, n_support_vectors] in svc mode, [n_classes, n_features] in linear mode
+    pub coefficients: Arc<Tensor>,
+    pub rho: Arc<Tensor>,
+    pub prob_a: Option<Arc<Tensor>>,
+    pub prob_b: Option<Arc<Tensor>>,
+}
+
+impl SvmClassifier {
+    fn is_linear(&self) -> bool {
+        self.vectors_per_class.is_empty()
+    }
+
+    fn n_pairs(&self) -> usize {
+        self.n_classes * (self.n_classes - 1) / 2
+    }
+
+    pub fn n_scores(&self) -> usize {
+        if self.is_linear() {
+            self.rho.len().max(2)
+        } else if self.prob_a.is_some() || self.n_classes == 2 {
+            self.n_classes
+        } else {
+            self.n_pairs()
+        }
+    }
+
+    fn eval_linear(&self, x: &[f32], label: &mut i32, scores: &mut [f32]) -> TractResult<()> {
+        let coefs =
+            self.coefficients.to_array_view::<f32>()?.into_dimensionality::<tract_ndarray::Ix2>()?;
+        let rho = self.rho.as_slice::<f32>()?;
+        if rho.len() == 1 {
+            let score = self.kernel.eval(x, coefs.row(0).as_slice().unwrap()) + rho[0];
+            *label = (score > 0.0) as i32;
+            scores[0] = -score;
+            scores[1] = score;
+        } else {
+            for (c, coefs) in coefs.outer_iter().enumerate() {
+                scores[c] = self.kernel.eval(x, coefs.as_slice().unwrap()) + rho[c];
+            }
+            *label = argmax(scores) as i32;
+        }
+        Ok(())
+    }
+
+    fn eval_svc(
+        &self,
+        x: &[f32],
+        kernels: &mut [f32],
+        decisions: &mut [f32],
+        label: &mut i32,
+        scores: &mut [f32],
+    ) -> TractResult<()> {
+        let svs = self.support_vectors.as_ref().context("Missing support vectors")?;
+        let svs = svs.to_array_view::<f32>()?.into_dimensionality::<tract_ndarray::Ix2>()?;
+        let n_sv = svs.shape()[0];
+        for (k, sv) in kernels.iter_mut().zip(svs.outer_iter()) {
+            *k = self.kernel.eval(x, sv.as_slice().unwrap());
+        }
+        let coefs = self.coefficients.as_slice::<f32>()?;
+        let rho = self.rho.as_slice::<f32>()?;
+        let starts: TVec<usize> = self
+            .vectors_per_class
+            .iter()
+            .scan(0, |acc, v| {
+                let start = *acc;
+                *acc += v;
+                Some(start)
+            })
+            .collect();
+        let mut votes: TVec<usize> = tvec!(0; self.n_classes);
+        let mut pair = 0;
+        for i in 0..self.n_classes {
+            for j in i + 1..self.n_classes {
+                let (si, sj) = (starts[i], starts[j]);
+                let (ci, cj) = (self.vectors_per_class[i], self.vectors_per_class[j]);
+                let mut sum = rho[pair];
+                for k in 0..ci {
+                    sum += coefs[(j - 1) * n_sv + si + k] * kernels[si + k];
+                }
+                for k in 0..cj {
+                    sum += coefs[i * n_sv + sj + k] * kernels[sj + k];
+                }
+                decisions[pair] = sum;
+                votes[if sum > 0.0 { i } else { j }] += 1;
+                pair += 1;
+            }
+        }
+        if let (Some(prob_a), Some(prob_b)) = (&self.prob_a, &self.prob_b) {
+            let (prob_a, prob_b) = (prob_a.as_slice::<f32>()?, prob_b.as_slice::<f32>()?);
+            let n = self.n_classes;
+            let mut pairwise = vec![0f32; n * n];
+            let mut pair = 0;
+            for i in 0..n {
+                for j in i + 1..n {
+                    let p = sigmoid_predict(decisions[pair], prob_a[pair], prob_b[pair])
+                        .clamp(1e-7, 1.0 - 1e-7);
+                    pairwise[i * n + j] = p;
+                    pairwise[j * n + i] = 1.0 - p;
+                    pair += 1;
+                }
+            }
+            multiclass_probability(n, &pairwise, scores);
+            *label = argmax(scores) as i32;
+        } else {
+            *label = argmax(&votes) as i32;
+            if self.n_classes == 2 {
+                scores[0] = decisions[0];
+                scores[1] = -decisions[0];
+            } else {
+                scores.copy_from_slice(decisions);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn argmax<T: PartialOrd>(values: &[T]) -> usize {
+    let mut best = 0;
+    for (ix, v) in values.iter().enumerate() {
+        if *v > values[best] {
+            best = ix;
+        }
+    }
+    best
+}
+
+fn sigmoid_predict(decision: f32, a: f32, b: f32) -> f32 {
+    let f_ab = decision * a + b;
+    if f_ab >= 0.0 {
+        (-f_ab).exp() / (1.0 + (-f_ab).exp())
+    } else {
+        1.0 / (1.0 + f_ab.exp())
+    }
+}
+
+// pairwise coupling, method 2 from Wu, Lin and Weng, as implemented in libsvm
+fn multiclass_probability(k: usize, r: &[f32], p: &mut [f32]) {
+    let max_iter = 100.max(k);
+    let eps = 0.005 / k as f32;
+    let mut q = vec![0f32; k * k];
+    let mut qp = vec![0f32; k];
+    for t in 0..k {
+        p[t] = 1.0 / k as f32;
+        for j in 0..k {
+            if j != t {
+                q[t * k + t] += r[j * k + t] * r[j * k + t];
+                q[t * k + j] = -r[j * k + t] * r[t * k + j];
+            }
+        }
+    }
+    for _ in 0..max_iter {
+        let mut pqp = 0.0;
+        for t in 0..k {
+            qp[t] = (0..k).map(|j| q[t * k + j] * p[j]).sum();
+            pqp += p[t] * qp[t];
+        }
+        let max_error = qp.iter().map(|qp| (qp - pqp).abs()).fold(0.0, f32::max);
+        if max_error < eps {
+            break;
+        }
+        for t in 0..k {
+            let diff = (-qp[t] + pqp) / q[t * k + t];
+            p[t] += diff;
+            pqp = (pqp + diff * (diff * q[t * k + t] + 2.0 * qp[t])) / (1.0 + diff) / (1.0 + diff);
+            for j in 0..k {
+                qp[j] = (qp[j] + diff * q[t * k + j]) / (1.0 + diff);
+                p[j] /= 1.0 + diff;
+            }
+        }
+    }
+}
+
+impl Op for SvmClassifier {
+    fn name(&self) -> Cow<str> {
+        "SvmClassifier".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "{:?} kernel, {} classes, {}",
+            self.kernel.kernel_type,
+            self.n_classes,
+            if self.is_linear() { "linear mode" } else { "one-vs-one" }
+        )])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for SvmClassifier {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let input = input.cast_to::<f32>()?;
+        let input = input.to_array_view::<f32>()?.into_dimensionality::<tract_ndarray::Ix2>()?;
+        let n = input.shape()[0];
+        let n_scores = self.n_scores();
+        let mut labels = tract_ndarray::Array1::<i32>::zeros(n);
+        let mut scores = tract_ndarray::Array2::<f32>::zeros((n, n_scores));
+        let n_sv = self.support_vectors.as_ref().map(|t| t.shape()[0]).unwrap_or(0);
+        let mut kernels = vec![0f32; n_sv];
+        let mut decisions = vec![0f32; self.n_pairs()];
+        for (ix, x) in input.outer_iter().enumerate() {
+            let x = x.to_vec();
+            let mut scores = scores.row_mut(ix);
+            let scores = scores.as_slice_mut().unwrap();
+            if self.is_linear() {
+                self.eval_linear(&x, &mut labels[ix], scores)?;
+            } else {
+                self.eval_svc(&x, &mut kernels, &mut decisions, &mut labels[ix], scores)?;
+            }
+        }
+        Ok(tvec!(labels.into_tvalue(), scores.into_tvalue()))
+    }
+}
+
+impl TypedOp for SvmClassifier {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let n = &inputs[0].shape[0];
+        Ok(tvec!(i32::fact([n.clone()]), f32::fact([n.clone(), self.n_scores().to_dim()])))
+    }
+
+    as_op!();
+}
+
+/// Support vector regressor, as defined by libsvm.
+///
+/// When `support_vectors` is absent, the regressor is in linear mode and `coefficients` is
+/// directly a vector of feature weights. Output is f32, [N, 1].
+#[derive(Clone, Debug)]
+pub struct SvmRegressor {
+    pub kernel: Kernel,
+    pub support_vectors: Option<Arc<Tensor>>,
+    pub coefficients: Arc<Tensor>,
+    pub rho: f32,
+    pub one_class: bool,
+}
+
+impl SvmRegressor {
+    fn eval_one(&self, x: &[f32]) -> TractResult<f32> {
+        let coefs = self.coefficients.as_slice::<f32>()?;
+        let mut score = self.rho;
+        if let Some(svs) = &self.support_vectors {
+            let svs = svs.to_array_view::<f32>()?.into_dimensionality::<tract_ndarray::Ix2>()?;
+            for (coef, sv) in coefs.iter().zip(svs.outer_iter()) {
+                score += coef * self.kernel.eval(x, sv.as_slice().unwrap());
+            }
+        } else {
+            score += self.kernel.eval(x, coefs);
+        }
+        if self.one_class {
+            score = if score > 0.0 { 1.0 } else { -1.0 };
+        }
+        Ok(score)
+    }
+}
+
+impl Op for SvmRegressor {
+    fn name(&self) -> Cow<str> {
+        "SvmRegressor".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("{:?} kernel, one_class: {}", self.kernel.kernel_type, self.one_class)])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for SvmRegressor {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let input = input.cast_to::<f32>()?;
+        let input = input.to_array_view::<f32>()?.into_dimensionality::<tract_ndarray::Ix2>()?;
+        let mut output = tract_ndarray::Array2::<f32>::zeros((input.shape()[0], 1));
+        for (x, y) in input.outer_iter().zip(output.iter_mut()) {
+            *y = self.eval_one(&x.to_vec())?;
+        }
+        Ok(tvec!(output.into_tvalue()))
+    }
+}
+
+impl TypedOp for SvmRegressor {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(f32::fact([inputs[0].shape[0].clone(), 1.to_dim()])))
+    }
+
+    as_op!();
+}
+
+fn kernel_parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::String.named("kernel"),
+        TypeName::Scalar.named("gamma").default(0.0f32),
+        TypeName::Scalar.named("coef0").default(0.0f32),
+        TypeName::Scalar.named("degree").default(1.0f32),
+    ]
+}
+
+fn parameters_classifier() -> Vec<Parameter> {
+    let mut params = vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Scalar.tensor().named("coefficients"),
+        TypeName::Scalar.tensor().named("rho"),
+        TypeName::Scalar.tensor().named("support_vectors").default(false),
+        TypeName::Scalar.tensor().named("prob_a").default(false),
+        TypeName::Scalar.tensor().named("prob_b").default(false),
+        TypeName::Integer.array().named("vectors_per_class"),
+        TypeName::Integer.named("n_classes"),
+    ];
+    params.extend(kernel_parameters());
+    params
+}
+
+fn parameters_regressor() -> Vec<Parameter> {
+    let mut params = vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Scalar.tensor().named("coefficients"),
+        TypeName::Scalar.named("rho"),
+        TypeName::Scalar.tensor().named("support_vectors").default(false),
+        TypeName::Logical.named("one_class").default(false),
+    ];
+    params.extend(kernel_parameters());
+    params
+}
+
+fn dump_kernel(kernel: &Kernel) -> Vec<(&'static str, RValue)> {
+    vec![
+        ("kernel", string(kernel.kernel_type.as_str())),
+        ("gamma", numeric(kernel.gamma)),
+        ("coef0", numeric(kernel.coef0)),
+        ("degree", numeric(kernel.degree)),
+    ]
+}
+
+fn load_kernel(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Kernel> {
+    let kernel_type: String = invocation.named_arg_as(builder, "kernel")?;
+    Ok(Kernel {
+        kernel_type: KernelType::parse(&kernel_type)?,
+        gamma: invocation.named_arg_as(builder, "gamma")?,
+        coef0: invocation.named_arg_as(builder, "coef0")?,
+        degree: invocation.named_arg_as(builder, "degree")?,
+    })
+}
+
+fn dump_classifier(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<SvmClassifier>().context("wrong op")?;
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let coefs = ast.konst_variable(format!("{}.coefficients", node.name), &op.coefficients)?;
+    let rho = ast.konst_variable(format!("{}.rho", node.name), &op.rho)?;
+    let mut named = dump_kernel(&op.kernel);
+    named.push(("vectors_per_class", ints(&op.vectors_per_class)));
+    named.push(("n_classes", numeric(op.n_classes)));
+    for (name, tensor) in
+        [("support_vectors", &op.support_vectors), ("prob_a", &op.prob_a), ("prob_b", &op.prob_b)]
+    {
+        if let Some(tensor) = tensor {
+            let value = ast.konst_variable(format!("{}.{}", node.name, name), tensor)?;
+            named.push((name, value.as_ref().clone()));
+        }
+    }
+    Ok(Some(invocation("tract_onnx_ml_svm_classifier", &[input, coefs, rho], &named)))
+}
+
+fn load_classifier(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let coefficients = invocation.named_arg_as(builder, "coefficients")?;
+    let rho = invocation.named_arg_as(builder, "rho")?;
+    let support_vectors = invocation.optional_named_arg_as(builder, "support_vectors")?;
+    let prob_a = invocation.optional_named_arg_as(builder, "prob_a")?;
+    let prob_b = invocation.optional_named_arg_as(builder, "prob_b")?;
+    let vectors_per_class = invocation.named_arg_as(builder, "vectors_per_class")?;
+    let n_classes = invocation.named_arg_as(builder, "n_classes")?;
+    let kernel = load_kernel(builder, invocation)?;
+    let op = SvmClassifier {
+        kernel,
+        n_classes,
+        vectors_per_class,
+        support_vectors,
+        coefficients,
+        rho,
+        prob_a,
+        prob_b,
+    };
+    builder.wire(op, &[input])
+}
+
+fn dump_regressor(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<SvmRegressor>().context("wrong op")?;
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let coefs = ast.konst_variable(format!("{}.coefficients", node.name), &op.coefficients)?;
+    let mut named = dump_kernel(&op.kernel);
+    named.push(("rho", numeric(op.rho)));
+    named.push(("one_class", logical(op.one_class)));
+    if let Some(svs) = &op.support_vectors {
+        let svs = ast.konst_variable(format!("{}.support_vectors", node.name), svs)?;
+        named.push(("support_vectors", svs.as_ref().clone()));
+    }
+    Ok(Some(invocation("tract_onnx_ml_svm_regressor", &[input, coefs], &named)))
+}
+
+fn load_regressor(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let coefficients = invocation.named_arg_as(builder, "coefficients")?;
+    let rho = invocation.named_arg_as(builder, "rho")?;
+    let support_vectors = invocation.optional_named_arg_as(builder, "support_vectors")?;
+    let one_class = invocation.named_arg_as(builder, "one_class")?;
+    let kernel = load_kernel(builder, invocation)?;
+    let op = SvmRegressor { kernel, support_vectors, coefficients, rho, one_class };
+    builder.wire(op, &[input])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rbf(gamma: f32) -> Kernel {
+        Kernel { kernel_type: KernelType::Rbf, gamma, coef0: 0.0, degree: 1.0 }
+    }
+
+    #[test]
+    fn kernels() {
+        let a = [1.0f32, 2.0];
+        let b = [3.0f32, -1.0];
+        let linear = Kernel { kernel_type: KernelType::Linear, gamma: 0.0, coef0: 0.0, degree: 1.0 };
+        assert_eq!(linear.eval(&a, &b), 1.0);
+        let poly = Kernel { kernel_type: KernelType::Poly, gamma: 2.0, coef0: 1.0, degree: 2.0 };
+        assert_eq!(poly.eval(&a, &b), 9.0);
+        assert!((rbf(0.1).eval(&a, &b) - (-1.3f32).exp()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn svc_three_classes_votes() -> TractResult<()> {
+        // one support vector per class, at 0, 1 and 2 on a single feature
+        let op = SvmClassifier {
+            kernel: rbf(1.0),
+            n_classes: 3,
+            vectors_per_class: tvec!(1, 1, 1),
+            support_vectors: Some(rctensor2(&[[0f32], [1.0], [2.0]])),
+            coefficients: rctensor2(&[[1f32, -1.0, -1.0], [1.0, 1.0, -1.0]]),
+            rho: rctensor1(&[0f32, 0.0, 0.0]),
+            prob_a: None,
+            prob_b: None,
+        };
+        let input = tensor2(&[[0f32], [1.0], [2.0]]);
+        let outputs = op.eval(tvec!(input.into_tvalue()))?;
+        assert_eq!(*outputs[0], tensor1(&[0i32, 1, 2]));
+        assert_eq!(outputs[1].shape(), &[3, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn svc_probabilities_sum_to_one() -> TractResult<()> {
+        let op = SvmClassifier {
+            kernel: rbf(1.0),
+            n_classes: 3,
+            vectors_per_class: tvec!(1, 1, 1),
+            support_vectors: Some(rctensor2(&[[0f32], [1.0], [2.0]])),
+            coefficients: rctensor2(&[[1f32, -1.0, -1.0], [1.0, 1.0, -1.0]]),
+            rho: rctensor1(&[0f32, 0.0, 0.0]),
+            prob_a: Some(rctensor1(&[-3f32, -3.0, -3.0])),
+            prob_b: Some(rctensor1(&[0f32, 0.0, 0.0])),
+        };
+        let input = tensor2(&[[0f32], [2.0]]);
+        let outputs = op.eval(tvec!(input.into_tvalue()))?;
+        assert_eq!(*outputs[0], tensor1(&[0i32, 2]));
+        for row in outputs[1].to_array_view::<f32>()?.outer_iter() {
+            assert!((row.sum() - 1.0).abs() < 1e-4);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn svr_linear_mode() -> TractResult<()> {
+        let op = SvmRegressor {
+            kernel: Kernel { kernel_type: KernelType::Linear, gamma: 0.0, coef0: 0.0, degree: 1.0 },
+            support_vectors: None,
+            coefficients: rctensor1(&[1f32, 2.0]),
+            rho: 0.5,
+            one_class: false,
+        };
+        let outputs = op.eval(tvec!(tensor2(&[[1f32, 1.0], [0.0, -1.0]]).into_tvalue()))?;
+        assert_eq!(*outputs[0], tensor2(&[[3.5f32], [-1.5]]));
+        Ok(())
+    }
+}
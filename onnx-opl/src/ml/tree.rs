@@ -52,7 +52,7 @@ impl Cmp {
 impl TryFrom<u8> for Cmp {
     type Error = TractError;
     fn try_from(value: u8) -> Result<Self, Self::Error> {
-        if (1..=5).contains(&value) {
+        if (1..=6).contains(&value) {
             unsafe { Ok(std::mem::transmute(value)) }
         } else {
             bail!("Invalid value for Cmp: {}", value);
@@ -251,20 +251,34 @@ impl AggregateFn for AvgFn {
 }
 
 #[derive(Clone, Copy, Default, Debug)]
-pub struct MaxFn;
+pub struct MaxFn {
+    seen: bool,
+}
 
 impl AggregateFn for MaxFn {
     fn aggregate(&mut self, score: f32, total: &mut f32) {
-        *total = total.max(score);
+        *total = if self.seen { total.max(score) } else { score };
+        self.seen = true;
+    }
+
+    fn post_aggregate(&mut self, _total: &mut f32) {
+        self.seen = false;
     }
 }
 
 #[derive(Clone, Copy, Default, Debug)]
-pub struct MinFn;
+pub struct MinFn {
+    seen: bool,
+}
 
 impl AggregateFn for MinFn {
     fn aggregate(&mut self, score: f32, total: &mut f32) {
-        *total = total.min(score);
+        *total = if self.seen { total.min(score) } else { score };
+        self.seen = true;
+    }
+
+    fn post_aggregate(&mut self, _total: &mut f32) {
+        self.seen = false;
     }
 }
 
@@ -575,4 +589,12 @@ mod tests {
         let output = ensemble.eval(&input.view().into_dyn()).unwrap();
         assert_eq!(output, generate_gbm_raw_output().into_dyn());
     }
+
+    #[test]
+    fn cmp_from_u8() {
+        assert_eq!(Cmp::try_from(1u8).unwrap(), Cmp::Equal);
+        assert_eq!(Cmp::try_from(6u8).unwrap(), Cmp::GreaterEqual);
+        assert!(Cmp::try_from(0u8).is_err());
+        assert!(Cmp::try_from(7u8).is_err());
+    }
 }
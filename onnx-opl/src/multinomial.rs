@@ -1,9 +1,5 @@
-use rand::distributions::Standard;
-use rand::prelude::Distribution;
-use rand::rngs::SmallRng;
-use rand::{Rng, SeedableRng};
-
 use tract_nnef::internal::*;
+use tract_nnef::tract_core::random::{RandomOp, RandomOpState, RandomStream};
 use tract_nnef::tract_ndarray::s;
 use tract_nnef::tract_num_traits::{AsPrimitive, Float, Zero};
 
@@ -26,31 +22,25 @@ pub struct Multinomial {
 }
 
 impl Multinomial {
-    fn eval_t0<T1>(&self, input: TValue) -> TractResult<TValue>
+    fn eval_t0<T1>(&self, input: TValue, rng: &mut RandomStream) -> TractResult<TValue>
     where
         T1: Datum + std::ops::SubAssign + Float + std::iter::Sum,
-        Standard: Distribution<T1>,
     {
         match self.dtype {
-            DatumType::I32 => self.eval_t::<T1, i32>(input),
-            DatumType::I64 => self.eval_t::<T1, i64>(input),
+            DatumType::I32 => self.eval_t::<T1, i32>(input, rng),
+            DatumType::I64 => self.eval_t::<T1, i64>(input, rng),
             dt => bail!("Unsupported output datum type for Multinomial: {:?}", dt),
         }
     }
-    fn eval_t<T1, T2>(&self, input: TValue) -> TractResult<TValue>
+    fn eval_t<T1, T2>(&self, input: TValue, rng: &mut RandomStream) -> TractResult<TValue>
     where
         T1: Datum + std::ops::SubAssign + Float + std::iter::Sum,
-        Standard: Distribution<T1>,
         T2: Datum + Zero + Copy,
         usize: AsPrimitive<T2>,
     {
         let batch_size = input.shape()[0];
         let class_size = input.shape()[1];
 
-        let mut rng = self.seed.map_or_else(SmallRng::from_entropy, |seed| {
-            SmallRng::seed_from_u64(seed.to_bits() as _)
-        });
-
         // shape: [batch_size, class_size]
         let input = input.to_array_view::<T1>()?;
 
@@ -66,7 +56,7 @@ impl Multinomial {
         let output = tract_ndarray::ArrayD::from_shape_fn(out_shape, |co_o| -> T2 {
             let batch = co_o[0];
 
-            let mut rand = rng.gen::<T1>() * maximums[batch];
+            let mut rand = rng.next_float::<T1>() * maximums[batch];
             let mut ret: T2 = usize::as_(class_size - 1);
 
             for (i, prob) in input.slice(s![batch, ..]).iter().enumerate() {
@@ -95,16 +85,32 @@ impl Op for Multinomial {
 
 impl EvalOp for Multinomial {
     fn is_stateless(&self) -> bool {
-        true
+        false
     }
 
-    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+    fn state(
+        &self,
+        session: &mut SessionState,
+        node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        let stream = RandomStream::for_op(session, node_id, self.seed.map(|s| s.to_bits() as u64));
+        Ok(Some(Box::new(RandomOpState::<Self>::new(stream))))
+    }
+}
+
+impl RandomOp for Multinomial {
+    fn eval_with_stream(
+        &self,
+        _session: &SessionState,
+        rng: &mut RandomStream,
+        inputs: TVec<TValue>,
+    ) -> TractResult<TVec<TValue>> {
         let input = args_1!(inputs);
 
         let output = match input.datum_type() {
             // DatumType::F16 => self.eval_t0::<f16>(input), // TODO: implement random for f16
-            DatumType::F32 => self.eval_t0::<f32>(input),
-            DatumType::F64 => self.eval_t0::<f64>(input),
+            DatumType::F32 => self.eval_t0::<f32>(input, rng),
+            DatumType::F64 => self.eval_t0::<f64>(input, rng),
             dt => bail!("Unsupported input datum type for Multinomial: {:?}", dt),
         }?;
 
@@ -1,12 +1,6 @@
-use rand::distributions::uniform::SampleUniform;
-use rand::prelude::Distribution;
-use rand::rngs::SmallRng;
-use rand::SeedableRng;
-use rand_distr::num_traits::Float;
-use rand_distr::StandardNormal;
 use tract_nnef::internal::*;
 use tract_nnef::ser::{array, tdims};
-use tract_nnef::tract_core::trivial_op_state_freeeze;
+use tract_nnef::tract_core::random::{RandomOp, RandomOpState, RandomStream};
 
 pub fn register(registry: &mut Registry) {
     registry.register_primitive(
@@ -90,8 +84,6 @@ pub struct Random {
     pub seed: Option<u64>,
 }
 
-
-
 impl Op for Random {
     fn name(&self) -> Cow<str> {
         "Random".into()
@@ -115,80 +107,87 @@ impl EvalOp for Random {
 
     fn state(
         &self,
-        _session: &mut SessionState,
-        _node_id: usize,
+        session: &mut SessionState,
+        node_id: usize,
     ) -> TractResult<Option<Box<dyn OpState>>> {
-        let rng = self.seed.map(SmallRng::seed_from_u64).unwrap_or_else(SmallRng::from_entropy);
-        Ok(Some(Box::new(RandomState(rng))))
+        let stream = RandomStream::for_op(session, node_id, self.seed);
+        Ok(Some(Box::new(RandomOpState::<Self>::new(stream))))
     }
 }
 
-#[derive(Clone, Debug)]
-struct RandomState(SmallRng);
-
-impl OpState for RandomState {
-    fn eval(
-        &mut self,
-        session: &mut SessionState,
-        op: &dyn Op,
+impl RandomOp for Random {
+    fn eval_with_stream(
+        &self,
+        session: &SessionState,
+        stream: &mut RandomStream,
         _inputs: TVec<TValue>,
     ) -> TractResult<TVec<TValue>> {
-        let op = op.downcast_ref::<Random>().context("op and state mismatch")?;
-        let mut tensor = unsafe {
-            Tensor::uninitialized_dt(
-                op.fact.datum_type,
-                &op.fact.shape.eval_to_usize(&session.resolved_symbols)?,
-            )?
-        };
-        match &op.dist {
-            Dist::Uniform { low, high } => match op.fact.datum_type {
-                DatumType::F32 => sample_uniform::<f32>(&mut tensor, &mut self.0, low, high)?,
-                DatumType::F64 => sample_uniform::<f64>(&mut tensor, &mut self.0, low, high)?,
-                DatumType::F16 => {
-                    sample_uniform::<f32>(&mut tensor, &mut self.0, low, high)?;
-                    tensor = tensor.cast_to::<f16>()?.into_owned();
-                }
-                _ => bail!("Random only support float types"),
-            },
-            Dist::Normal { mean, dev } => match op.fact.datum_type {
-                DatumType::F32 => sample_normal::<f32>(&mut tensor, &mut self.0, mean, dev)?,
-                DatumType::F64 => sample_normal::<f64>(&mut tensor, &mut self.0, mean, dev)?,
-                DatumType::F16 => {
-                    sample_uniform::<f32>(&mut tensor, &mut self.0, mean, dev)?;
-                    tensor = tensor.cast_to::<f16>()?.into_owned();
-                }
-                _ => bail!("Random only support float types"),
-            },
+        let shape = self.fact.shape.eval_to_usize(&session.resolved_symbols)?;
+        let dt = self.fact.datum_type;
+        let sampling_dt = if dt == DatumType::F64 { dt } else { DatumType::F32 };
+        ensure!(dt.is_float(), "Random only support float types");
+        let mut tensor = Tensor::zero_dt(sampling_dt, &shape)?;
+        match (&self.dist, sampling_dt) {
+            (Dist::Uniform { low, high }, DatumType::F64) => stream.fill_uniform::<f64>(
+                &mut tensor,
+                low.cast_to_scalar()?,
+                high.cast_to_scalar()?,
+            )?,
+            (Dist::Uniform { low, high }, _) => stream.fill_uniform::<f32>(
+                &mut tensor,
+                low.cast_to_scalar()?,
+                high.cast_to_scalar()?,
+            )?,
+            (Dist::Normal { mean, dev }, DatumType::F64) => stream.fill_normal::<f64>(
+                &mut tensor,
+                mean.cast_to_scalar()?,
+                dev.cast_to_scalar()?,
+            )?,
+            (Dist::Normal { mean, dev }, _) => stream.fill_normal::<f32>(
+                &mut tensor,
+                mean.cast_to_scalar()?,
+                dev.cast_to_scalar()?,
+            )?,
         }
-        Ok(tvec!(tensor.into_tvalue()))
+        Ok(tvec!(tensor.cast_to_dt(dt)?.into_owned().into_tvalue()))
     }
 }
 
-trivial_op_state_freeeze!(RandomState);
-
-fn sample_uniform<T: Datum + SampleUniform + Copy>(
-    t: &mut Tensor,
-    r: &mut SmallRng,
-    low: &Tensor,
-    high: &Tensor,
-) -> TractResult<()> {
-    let dist =
-        rand::distributions::Uniform::new(low.cast_to_scalar::<T>()?, high.cast_to_scalar::<T>()?);
-    t.as_slice_mut::<T>()?.iter_mut().zip(dist.sample_iter(r)).for_each(|(v, r)| *v = r);
-    Ok(())
-}
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn model(seed: Option<u64>) -> TractResult<TypedRunnableModel<TypedModel>> {
+        let mut model = TypedModel::default();
+        let dist = Dist::Normal { mean: rctensor0(0f32), dev: rctensor0(1f32) };
+        let op = Random { fact: f32::fact([5]), dist, seed };
+        let wire = model.wire_node("random", op, &[])?;
+        model.set_output_outlets(&wire)?;
+        model.into_runnable()
+    }
 
-fn sample_normal<T: Datum + Float + Copy>(
-    t: &mut Tensor,
-    r: &mut SmallRng,
-    mean: &Tensor,
-    dev: &Tensor,
-) -> TractResult<()>
-where
-    StandardNormal: Distribution<T>,
-{
-    let dist =
-        rand_distr::Normal::<T>::new(mean.cast_to_scalar::<T>()?, dev.cast_to_scalar::<T>()?)?;
-    t.as_slice_mut::<T>()?.iter_mut().zip(dist.sample_iter(r)).for_each(|(v, r)| *v = r);
-    Ok(())
+    #[test]
+    fn seeded_op_replays() -> TractResult<()> {
+        let plan = model(Some(3))?;
+        let a = plan.run(tvec!())?.remove(0);
+        let b = plan.run(tvec!())?.remove(0);
+        assert_eq!(a, b);
+        Ok(())
+    }
+
+    #[test]
+    fn seeded_state() -> TractResult<()> {
+        let plan = model(None)?;
+        let mut state = SimpleState::new(&plan)?;
+        state.set_seed(42)?;
+        let first = state.run(tvec!())?.remove(0);
+        let frozen = state.freeze();
+        let second = state.run(tvec!())?.remove(0);
+        assert_ne!(first, second);
+        assert_eq!(frozen.unfreeze().run(tvec!())?.remove(0), second);
+        let mut other = SimpleState::new(&plan)?;
+        other.set_seed(42)?;
+        assert_eq!(other.run(tvec!())?.remove(0), first);
+        Ok(())
+    }
 }
@@ -0,0 +1,118 @@
+use regex::Regex;
+use tract_nnef::internal::*;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<RegexFullMatch>(), dump);
+    registry.register_primitive(
+        "tract_onnx_regex_full_match",
+        &[TypeName::String.tensor().named("input"), TypeName::String.named("pattern")],
+        &[("output", TypeName::Logical.tensor())],
+        load,
+    );
+}
+
+/// Checks if each string of the input matches a regular expression as a
+/// whole.
+#[derive(Clone, Debug)]
+pub struct RegexFullMatch {
+    pub pattern: String,
+    regex: Regex,
+}
+
+impl RegexFullMatch {
+    pub fn new(pattern: &str) -> TractResult<RegexFullMatch> {
+        let regex = Regex::new(&format!("^(?:{pattern})$"))
+            .with_context(|| format!("Invalid regular expression {pattern:?}"))?;
+        Ok(RegexFullMatch { pattern: pattern.to_string(), regex })
+    }
+}
+
+impl Op for RegexFullMatch {
+    fn name(&self) -> Cow<str> {
+        "RegexFullMatch".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("pattern: {:?}", self.pattern)])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for RegexFullMatch {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let output = input.to_array_view::<String>()?.mapv(|s| self.regex.is_match(&s));
+        Ok(tvec!(output.into_tvalue()))
+    }
+}
+
+impl TypedOp for RegexFullMatch {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs[0].datum_type == String::datum_type());
+        Ok(tvec!(bool::fact(inputs[0].shape.iter())))
+    }
+
+    fn axes_mapping(
+        &self,
+        inputs: &[&TypedFact],
+        outputs: &[&TypedFact],
+    ) -> TractResult<AxesMapping> {
+        AxesMapping::natural(inputs, outputs)
+    }
+
+    fn change_axes(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+        _io: InOut,
+        change: &AxisOp,
+    ) -> TractResult<Option<AxisChangeConsequence>> {
+        Ok(Some(AxisChangeConsequence::new(model, node, None, change)))
+    }
+
+    as_op!();
+}
+
+fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<RegexFullMatch>().context("wrong op")?;
+    Ok(Some(invocation(
+        "tract_onnx_regex_full_match",
+        &[input],
+        &[("pattern", string(&op.pattern))],
+    )))
+}
+
+fn load(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let pattern: String = invocation.named_arg_as(builder, "pattern")?;
+    builder.wire(RegexFullMatch::new(&pattern)?, &[input])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn full_match() -> TractResult<()> {
+        let op = RegexFullMatch::new(r"[a-z]+@[a-z]+\.com|admin")?;
+        let input: Vec<String> = ["bob@mail.com", "bob@mail.com.fr", "admin", "administrator", ""]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let input = tensor1(&input).into_shape(&[1, 5])?;
+        let output = op.eval(tvec!(input.into_tvalue()))?;
+        assert_eq!(*output[0], tensor2(&[[true, false, true, false, false]]));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        assert!(RegexFullMatch::new("(").is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use tract_nnef::internal::*;
+use tract_nnef::tract_core::broadcast::multi_broadcast;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<StringConcat>(), dump);
+    registry.register_primitive(
+        "tract_onnx_string_concat",
+        &[TypeName::String.tensor().named("a"), TypeName::String.tensor().named("b")],
+        &[("output", TypeName::String.tensor())],
+        load,
+    );
+}
+
+/// Concatenates strings from two broadcast inputs.
+#[derive(Clone, Debug, Default, Hash)]
+pub struct StringConcat;
+
+impl Op for StringConcat {
+    fn name(&self) -> Cow<str> {
+        "StringConcat".into()
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for StringConcat {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let (a, b) = args_2!(inputs);
+        let shape = multi_broadcast(&[a.shape(), b.shape()])
+            .with_context(|| format!("Can not broadcast {:?} and {:?}", a.shape(), b.shape()))?;
+        let mut output = tract_ndarray::ArrayD::<String>::default(&*shape);
+        tract_ndarray::Zip::from(&mut output)
+            .and_broadcast(&a.to_array_view::<String>()?)
+            .and_broadcast(&b.to_array_view::<String>()?)
+            .for_each(|o, a, b| *o = format!("{a}{b}"));
+        Ok(tvec!(output.into_tvalue()))
+    }
+}
+
+impl TypedOp for StringConcat {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs.iter().all(|i| i.datum_type == String::datum_type()));
+        let shape = multi_broadcast(&[inputs[0].shape.to_tvec(), inputs[1].shape.to_tvec()])
+            .with_context(|| {
+                format!("Can not broadcast {:?} and {:?}", inputs[0].shape, inputs[1].shape)
+            })?;
+        Ok(tvec!(String::fact(shape)))
+    }
+
+    as_op!();
+}
+
+fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let a = ast.mapping[&node.inputs[0]].clone();
+    let b = ast.mapping[&node.inputs[1]].clone();
+    Ok(Some(invocation("tract_onnx_string_concat", &[a, b], &[])))
+}
+
+fn load(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let a = invocation.named_arg_as(builder, "a")?;
+    let b = invocation.named_arg_as(builder, "b")?;
+    builder.wire(StringConcat, &[a, b])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn strings(s: &[&str]) -> Tensor {
+        tensor1(&s.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn broadcast() -> TractResult<()> {
+        let a = strings(&["a", "b"]).into_shape(&[2, 1])?;
+        let b = strings(&["x", "", "z"]);
+        let output = StringConcat.eval(tvec!(a.into_tvalue(), b.into_tvalue()))?;
+        let expected = strings(&["ax", "a", "az", "bx", "b", "bz"]).into_shape(&[2, 3])?;
+        assert_eq!(*output[0], expected);
+        Ok(())
+    }
+
+    #[test]
+    fn incompatible_shapes() {
+        let a = strings(&["a", "b"]);
+        let b = strings(&["x", "y", "z"]);
+        assert!(StringConcat.eval(tvec!(a.into_tvalue(), b.into_tvalue())).is_err());
+    }
+}
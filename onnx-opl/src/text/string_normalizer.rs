@@ -0,0 +1,213 @@
+use std::str::FromStr;
+use tract_nnef::internal::*;
+use tract_nnef::ser::array;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<StringNormalizer>(), dump);
+    registry.register_primitive(
+        "tract_onnx_string_normalizer",
+        &parameters(),
+        &[("output", TypeName::String.tensor())],
+        load,
+    );
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CaseChangeAction {
+    Lower,
+    Upper,
+    None,
+}
+
+impl CaseChangeAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaseChangeAction::Lower => "LOWER",
+            CaseChangeAction::Upper => "UPPER",
+            CaseChangeAction::None => "NONE",
+        }
+    }
+}
+
+impl FromStr for CaseChangeAction {
+    type Err = TractError;
+    fn from_str(s: &str) -> TractResult<Self> {
+        match s {
+            "LOWER" => Ok(CaseChangeAction::Lower),
+            "UPPER" => Ok(CaseChangeAction::Upper),
+            "NONE" => Ok(CaseChangeAction::None),
+            _ => bail!("Unsupported case change action: {}", s),
+        }
+    }
+}
+
+/// Removes stopwords from a [C] or [1, C] string tensor, then changes the
+/// case of the remaining strings. When every string is removed, the output
+/// is a single empty string.
+#[derive(Clone, Debug, Hash)]
+pub struct StringNormalizer {
+    pub case_change_action: CaseChangeAction,
+    pub is_case_sensitive: bool,
+    pub stopwords: Vec<String>,
+    pub output_len: Symbol,
+}
+
+impl StringNormalizer {
+    fn normalize(&self, s: &str) -> Option<String> {
+        let stop = if self.is_case_sensitive {
+            self.stopwords.iter().any(|w| w == s)
+        } else {
+            let s = s.to_lowercase();
+            self.stopwords.iter().any(|w| w.to_lowercase() == s)
+        };
+        if stop {
+            return None;
+        }
+        Some(match self.case_change_action {
+            CaseChangeAction::Lower => s.to_lowercase(),
+            CaseChangeAction::Upper => s.to_uppercase(),
+            CaseChangeAction::None => s.to_string(),
+        })
+    }
+}
+
+impl Op for StringNormalizer {
+    fn name(&self) -> Cow<str> {
+        "StringNormalizer".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "case change: {}, case sensitive: {}, {} stopwords",
+            self.case_change_action.as_str(),
+            self.is_case_sensitive,
+            self.stopwords.len()
+        )])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for StringNormalizer {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let mut output: Vec<String> =
+            input.as_slice::<String>()?.iter().filter_map(|s| self.normalize(s)).collect();
+        if output.is_empty() && input.len() > 0 {
+            output.push(String::new());
+        }
+        let mut shape: TVec<usize> = input.shape().into();
+        *shape.last_mut().unwrap() = output.len();
+        Ok(tvec!(tensor1(&output).into_shape(&shape)?.into_tvalue()))
+    }
+}
+
+impl TypedOp for StringNormalizer {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let shape = &inputs[0].shape;
+        ensure!(inputs[0].datum_type == String::datum_type());
+        ensure!(
+            shape.rank() == 1 || (shape.rank() == 2 && shape[0] == 1.to_dim()),
+            "StringNormalizer expects a [C] or [1, C] input, got {:?}",
+            shape
+        );
+        let mut shape = shape.to_tvec();
+        if !self.stopwords.is_empty() {
+            *shape.last_mut().unwrap() = self.output_len.to_dim();
+        }
+        Ok(tvec!(String::fact(shape)))
+    }
+
+    as_op!();
+}
+
+fn parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::String.tensor().named("input"),
+        TypeName::String.named("case_change_action").default("NONE"),
+        TypeName::Logical.named("is_case_sensitive").default(false),
+        TypeName::String.array().named("stopwords"),
+    ]
+}
+
+fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<StringNormalizer>().context("wrong op")?;
+    Ok(Some(invocation(
+        "tract_onnx_string_normalizer",
+        &[input],
+        &[
+            ("case_change_action", string(op.case_change_action.as_str())),
+            ("is_case_sensitive", logical(op.is_case_sensitive)),
+            ("stopwords", array(op.stopwords.iter().map(string).collect::<TVec<_>>())),
+        ],
+    )))
+}
+
+fn load(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let case_change_action =
+        invocation.named_arg_as::<String>(builder, "case_change_action")?.parse()?;
+    let is_case_sensitive = invocation.named_arg_as(builder, "is_case_sensitive")?;
+    let stopwords: TVec<String> = invocation.named_arg_as(builder, "stopwords")?;
+    let op = StringNormalizer {
+        case_change_action,
+        is_case_sensitive,
+        stopwords: stopwords.into_vec(),
+        output_len: builder.model.symbol_table.new_with_prefix("n"),
+    };
+    builder.wire(op, &[input])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn normalizer(
+        action: CaseChangeAction,
+        sensitive: bool,
+        stopwords: &[&str],
+    ) -> StringNormalizer {
+        StringNormalizer {
+            case_change_action: action,
+            is_case_sensitive: sensitive,
+            stopwords: stopwords.iter().map(|s| s.to_string()).collect(),
+            output_len: SymbolTable::default().sym("n"),
+        }
+    }
+
+    fn strings(s: &[&str]) -> Tensor {
+        tensor1(&s.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn case_sensitive_lower() -> TractResult<()> {
+        let op = normalizer(CaseChangeAction::Lower, true, &["monday"]);
+        let input = strings(&["monday", "tuesday", "Monday", "THURSDAY"]);
+        let output = op.eval(tvec!(input.into_tvalue()))?.remove(0);
+        assert_eq!(*output, strings(&["tuesday", "monday", "thursday"]));
+        Ok(())
+    }
+
+    #[test]
+    fn insensitive_upper_twodim() -> TractResult<()> {
+        let op = normalizer(CaseChangeAction::Upper, false, &["monday"]);
+        let input = strings(&["Monday", "tuesday", "MONDAY", "wednesday"]).into_shape(&[1, 4])?;
+        let output = op.eval(tvec!(input.into_tvalue()))?.remove(0);
+        assert_eq!(*output, strings(&["TUESDAY", "WEDNESDAY"]).into_shape(&[1, 2])?);
+        Ok(())
+    }
+
+    #[test]
+    fn empty_output() -> TractResult<()> {
+        let op = normalizer(CaseChangeAction::Upper, true, &["monday"]);
+        let input = strings(&["monday", "monday"]);
+        let output = op.eval(tvec!(input.into_tvalue()))?.remove(0);
+        assert_eq!(*output, strings(&[""]));
+        Ok(())
+    }
+}
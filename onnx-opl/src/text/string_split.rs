@@ -0,0 +1,163 @@
+use tract_nnef::internal::*;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<StringSplit>(), dump);
+    registry.register_primitive(
+        "tract_onnx_string_split",
+        &[
+            TypeName::String.tensor().named("input"),
+            TypeName::String.named("delimiter").default(""),
+            TypeName::Integer.named("maxsplit").default(-1),
+        ],
+        &[("substrings", TypeName::String.tensor()), ("lengths", TypeName::Integer.tensor())],
+        load,
+    );
+}
+
+/// Splits each input string on a delimiter, or on runs of whitespace when
+/// there is none. Substrings go along a new last axis, padded with empty
+/// strings, and their counts go to the second output.
+#[derive(Clone, Debug, Hash)]
+pub struct StringSplit {
+    pub delimiter: Option<String>,
+    pub maxsplit: Option<usize>,
+    pub output_len: Symbol,
+}
+
+impl StringSplit {
+    fn split<'s>(&self, s: &'s str) -> Vec<&'s str> {
+        let max = self.maxsplit.map(|m| m + 1).unwrap_or(usize::MAX);
+        if let Some(delimiter) = &self.delimiter {
+            return s.splitn(max, &**delimiter).collect();
+        }
+        let mut parts = vec![];
+        let mut rest = s.trim_start();
+        while !rest.is_empty() {
+            if parts.len() + 1 == max {
+                parts.push(rest);
+                break;
+            }
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            parts.push(&rest[..end]);
+            rest = rest[end..].trim_start();
+        }
+        parts
+    }
+}
+
+impl Op for StringSplit {
+    fn name(&self) -> Cow<str> {
+        "StringSplit".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("delimiter: {:?}, maxsplit: {:?}", self.delimiter, self.maxsplit)])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for StringSplit {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let parts: Vec<Vec<&str>> =
+            input.as_slice::<String>()?.iter().map(|s| self.split(s)).collect();
+        let len = parts.iter().map(|p| p.len()).max().unwrap_or(0);
+        let mut substrings = vec![String::new(); parts.len() * len];
+        for (chunk, parts) in substrings.chunks_mut(len.max(1)).zip(&parts) {
+            for (s, part) in chunk.iter_mut().zip(parts) {
+                *s = part.to_string();
+            }
+        }
+        let lengths: Vec<i64> = parts.iter().map(|p| p.len() as i64).collect();
+        let mut shape: TVec<usize> = input.shape().into();
+        let lengths = tensor1(&lengths).into_shape(&shape)?;
+        shape.push(len);
+        let substrings = tensor1(&substrings).into_shape(&shape)?;
+        Ok(tvec!(substrings.into_tvalue(), lengths.into_tvalue()))
+    }
+}
+
+impl TypedOp for StringSplit {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs[0].datum_type == String::datum_type());
+        let mut shape = inputs[0].shape.to_tvec();
+        let lengths = i64::fact(shape.clone());
+        shape.push(self.output_len.to_dim());
+        Ok(tvec!(String::fact(shape), lengths))
+    }
+
+    as_op!();
+}
+
+fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<StringSplit>().context("wrong op")?;
+    Ok(Some(invocation(
+        "tract_onnx_string_split",
+        &[input],
+        &[
+            ("delimiter", string(op.delimiter.as_deref().unwrap_or(""))),
+            ("maxsplit", numeric(op.maxsplit.map(|m| m as i64).unwrap_or(-1))),
+        ],
+    )))
+}
+
+fn load(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let delimiter: String = invocation.named_arg_as(builder, "delimiter")?;
+    let maxsplit: i64 = invocation.named_arg_as(builder, "maxsplit")?;
+    let op = StringSplit {
+        delimiter: Some(delimiter).filter(|d| !d.is_empty()),
+        maxsplit: Some(maxsplit as usize).filter(|_| maxsplit >= 0),
+        output_len: builder.model.symbol_table.new_with_prefix("n"),
+    };
+    builder.wire(op, &[input])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn split(delimiter: Option<&str>, maxsplit: Option<usize>, s: &str) -> Vec<String> {
+        let op = StringSplit {
+            delimiter: delimiter.map(|s| s.to_string()),
+            maxsplit,
+            output_len: SymbolTable::default().sym("n"),
+        };
+        op.split(s).into_iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn whitespace() {
+        assert_eq!(split(None, None, "  hello   world "), vec!["hello", "world"]);
+        assert_eq!(split(None, Some(1), " a b  c "), vec!["a", "b  c "]);
+        assert!(split(None, None, "   ").is_empty());
+    }
+
+    #[test]
+    fn delimiter() {
+        assert_eq!(split(Some("-"), None, "1-2--3"), vec!["1", "2", "", "3"]);
+        assert_eq!(split(Some("-"), Some(1), "1-2--3"), vec!["1", "2--3"]);
+        assert_eq!(split(Some("-"), None, ""), vec![""]);
+    }
+
+    #[test]
+    fn padding() -> TractResult<()> {
+        let op = StringSplit {
+            delimiter: None,
+            maxsplit: None,
+            output_len: SymbolTable::default().sym("n"),
+        };
+        let input = tensor1(&["a b".to_string(), "c".to_string()]);
+        let outputs = op.eval(tvec!(input.into_tvalue()))?;
+        let expected: Vec<String> = ["a", "b", "c", ""].iter().map(|s| s.to_string()).collect();
+        assert_eq!(*outputs[0], tensor1(&expected).into_shape(&[2, 2])?);
+        assert_eq!(*outputs[1], tensor1(&[2i64, 1]));
+        Ok(())
+    }
+}
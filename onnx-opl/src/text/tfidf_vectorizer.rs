@@ -0,0 +1,372 @@
+use std::str::FromStr;
+use tract_nnef::internal::*;
+use tract_nnef::ser::{array, ints};
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<TfIdfVectorizer>(), dump);
+    registry.register_primitive(
+        "tract_onnx_tfidf_vectorizer",
+        &parameters(),
+        &[("output", TypeName::Scalar.tensor())],
+        load,
+    );
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TfIdfMode {
+    Tf,
+    Idf,
+    TfIdf,
+}
+
+impl TfIdfMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TfIdfMode::Tf => "TF",
+            TfIdfMode::Idf => "IDF",
+            TfIdfMode::TfIdf => "TFIDF",
+        }
+    }
+}
+
+impl FromStr for TfIdfMode {
+    type Err = TractError;
+    fn from_str(s: &str) -> TractResult<Self> {
+        match s {
+            "TF" => Ok(TfIdfMode::Tf),
+            "IDF" => Ok(TfIdfMode::Idf),
+            "TFIDF" => Ok(TfIdfMode::TfIdf),
+            _ => bail!("Unsupported TfIdfVectorizer mode: {}", s),
+        }
+    }
+}
+
+/// Counts the n-grams of a pool in the rows of a [C] or [N, C] tensor of
+/// strings or integers, with skips between the n-gram items.
+#[derive(Clone, Debug)]
+pub struct TfIdfVectorizer {
+    pub mode: TfIdfMode,
+    pub min_gram_length: usize,
+    pub max_gram_length: usize,
+    pub max_skip_count: usize,
+    /// Start of the n-grams of each length in the pool, 1-grams first.
+    pub ngram_counts: Vec<usize>,
+    /// Output index of each n-gram in the pool.
+    pub ngram_indexes: Vec<usize>,
+    /// Flattened n-grams, either i64 or String.
+    pub pool: Arc<Tensor>,
+    pub weights: Option<Vec<f32>>,
+    vocabulary: Option<HashMap<String, i64>>,
+    /// Trie of the pool n-grams, node 0 is the root.
+    edges: HashMap<(usize, i64), usize>,
+    /// N-gram id of each trie node, starting at 1, 0 for nodes that are not
+    /// in the pool.
+    ids: Vec<usize>,
+}
+
+impl TfIdfVectorizer {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        mode: TfIdfMode,
+        min_gram_length: usize,
+        max_gram_length: usize,
+        max_skip_count: usize,
+        ngram_counts: Vec<usize>,
+        ngram_indexes: Vec<usize>,
+        pool: Arc<Tensor>,
+        weights: Option<Vec<f32>>,
+    ) -> TractResult<TfIdfVectorizer> {
+        ensure!(
+            0 < min_gram_length && min_gram_length <= max_gram_length,
+            "Invalid n-gram lengths {}..={}",
+            min_gram_length,
+            max_gram_length
+        );
+        let (vocabulary, keys) = if pool.datum_type() == String::datum_type() {
+            let mut vocabulary = HashMap::<String, i64>::default();
+            let keys = pool
+                .as_slice::<String>()?
+                .iter()
+                .map(|s| {
+                    let next = vocabulary.len() as i64;
+                    *vocabulary.entry(s.clone()).or_insert(next)
+                })
+                .collect::<Vec<i64>>();
+            (Some(vocabulary), keys)
+        } else {
+            (None, pool.cast_to::<i64>()?.as_slice::<i64>()?.to_vec())
+        };
+        let mut edges = HashMap::default();
+        let mut ids = vec![0];
+        let mut ngram_id = 1;
+        for (ix, &start) in ngram_counts.iter().enumerate() {
+            let n = ix + 1;
+            let end = ngram_counts.get(ix + 1).copied().unwrap_or(keys.len());
+            ensure!(
+                start <= end && end <= keys.len(),
+                "Inconsistent ngram_counts {:?}",
+                ngram_counts
+            );
+            let count = (end - start) / n;
+            if n < min_gram_length || n > max_gram_length {
+                ngram_id += count;
+                continue;
+            }
+            for gram in keys[start..start + count * n].chunks(n) {
+                let mut node = 0;
+                for &key in gram {
+                    node = *edges.entry((node, key)).or_insert_with(|| {
+                        ids.push(0);
+                        ids.len() - 1
+                    });
+                }
+                if ids[node] == 0 {
+                    ids[node] = ngram_id;
+                }
+                ngram_id += 1;
+            }
+        }
+        ensure!(
+            ngram_id - 1 <= ngram_indexes.len(),
+            "Expected {} ngram_indexes, got {}",
+            ngram_id - 1,
+            ngram_indexes.len()
+        );
+        Ok(TfIdfVectorizer {
+            mode,
+            min_gram_length,
+            max_gram_length,
+            max_skip_count,
+            ngram_counts,
+            ngram_indexes,
+            pool,
+            weights,
+            vocabulary,
+            edges,
+            ids,
+        })
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.ngram_indexes.iter().max().map(|m| m + 1).unwrap_or(0)
+    }
+
+    fn keys(&self, input: &Tensor) -> TractResult<Vec<Option<i64>>> {
+        if let Some(vocabulary) = &self.vocabulary {
+            Ok(input.as_slice::<String>()?.iter().map(|s| vocabulary.get(s).copied()).collect())
+        } else {
+            Ok(input.cast_to::<i64>()?.as_slice::<i64>()?.iter().map(|&k| Some(k)).collect())
+        }
+    }
+
+    fn count_row(&self, row: &[Option<i64>], counts: &mut [f32]) {
+        let mut start_size = self.min_gram_length;
+        for skip in 1..=self.max_skip_count + 1 {
+            for start in 0..row.len() {
+                // no n-gram of any wanted size fits from here
+                if start + skip * (start_size - 1) >= row.len() {
+                    break;
+                }
+                let mut node = 0;
+                for (size, item) in (start..row.len()).step_by(skip).enumerate() {
+                    if size >= self.max_gram_length {
+                        break;
+                    }
+                    let Some(&next) = row[item].and_then(|k| self.edges.get(&(node, k))) else {
+                        break;
+                    };
+                    node = next;
+                    if size + 1 >= start_size && self.ids[node] != 0 {
+                        counts[self.ngram_indexes[self.ids[node] - 1]] += 1.0;
+                    }
+                }
+            }
+            // unigrams do not depend on skips, they are only counted once
+            if start_size == 1 {
+                start_size += 1;
+                if start_size > self.max_gram_length {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl Op for TfIdfVectorizer {
+    fn name(&self) -> Cow<str> {
+        "TfIdfVectorizer".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "{} {}..={}-grams, up to {} skips, {} outputs",
+            self.mode.as_str(),
+            self.min_gram_length,
+            self.max_gram_length,
+            self.max_skip_count,
+            self.output_size()
+        )])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for TfIdfVectorizer {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let keys = self.keys(&input)?;
+        let size = self.output_size();
+        let (rows, shape) = if input.rank() == 1 {
+            (1, tvec!(size))
+        } else {
+            (input.shape()[0], tvec!(input.shape()[0], size))
+        };
+        let mut counts = vec![0f32; rows * size];
+        if keys.len() > 0 && size > 0 {
+            for (row, counts) in keys.chunks(keys.len() / rows).zip(counts.chunks_mut(size)) {
+                self.count_row(row, counts);
+            }
+        }
+        let weight = |ix: usize| self.weights.as_ref().map(|w| w[ix % size]).unwrap_or(1.0);
+        match self.mode {
+            TfIdfMode::Tf => (),
+            TfIdfMode::Idf => counts
+                .iter_mut()
+                .enumerate()
+                .for_each(|(ix, c)| *c = if *c > 0.0 { weight(ix) } else { 0.0 }),
+            TfIdfMode::TfIdf => counts.iter_mut().enumerate().for_each(|(ix, c)| *c *= weight(ix)),
+        }
+        Ok(tvec!(tensor1(&counts).into_shape(&shape)?.into_tvalue()))
+    }
+}
+
+impl TypedOp for TfIdfVectorizer {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let shape = &inputs[0].shape;
+        ensure!(
+            shape.rank() == 1 || shape.rank() == 2,
+            "TfIdfVectorizer expects a [C] or [N, C] input, got {:?}",
+            shape
+        );
+        ensure!(
+            (inputs[0].datum_type == String::datum_type()) == self.vocabulary.is_some(),
+            "TfIdfVectorizer input is {:?}, pool is {:?}",
+            inputs[0].datum_type,
+            self.pool.datum_type()
+        );
+        let mut shape = shape.to_tvec();
+        *shape.last_mut().unwrap() = self.output_size().to_dim();
+        Ok(tvec!(f32::fact(shape)))
+    }
+
+    as_op!();
+}
+
+fn parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Scalar.tensor().named("pool"),
+        TypeName::String.named("mode"),
+        TypeName::Integer.named("min_gram_length"),
+        TypeName::Integer.named("max_gram_length"),
+        TypeName::Integer.named("max_skip_count"),
+        TypeName::Integer.array().named("ngram_counts"),
+        TypeName::Integer.array().named("ngram_indexes"),
+        TypeName::Scalar.array().named("weights").default(false),
+    ]
+}
+
+fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<TfIdfVectorizer>().context("wrong op")?;
+    let pool = ast.konst_variable(format!("{}.pool", node.name), &op.pool)?;
+    let mut named: TVec<(_, RValue)> = tvec![
+        ("mode", string(op.mode.as_str())),
+        ("min_gram_length", numeric(op.min_gram_length)),
+        ("max_gram_length", numeric(op.max_gram_length)),
+        ("max_skip_count", numeric(op.max_skip_count)),
+        ("ngram_counts", ints(&op.ngram_counts)),
+        ("ngram_indexes", ints(&op.ngram_indexes)),
+    ];
+    if let Some(weights) = &op.weights {
+        named.push(("weights", array(weights.iter().map(numeric).collect::<TVec<_>>())));
+    }
+    Ok(Some(invocation("tract_onnx_tfidf_vectorizer", &[input, pool], &named)))
+}
+
+fn load(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let pool: Arc<Tensor> = invocation.named_arg_as(builder, "pool")?;
+    let mode = invocation.named_arg_as::<String>(builder, "mode")?.parse()?;
+    let min_gram_length = invocation.named_arg_as(builder, "min_gram_length")?;
+    let max_gram_length = invocation.named_arg_as(builder, "max_gram_length")?;
+    let max_skip_count = invocation.named_arg_as(builder, "max_skip_count")?;
+    let ngram_counts: TVec<usize> = invocation.named_arg_as(builder, "ngram_counts")?;
+    let ngram_indexes: TVec<usize> = invocation.named_arg_as(builder, "ngram_indexes")?;
+    let weights: Option<TVec<f32>> = invocation.optional_named_arg_as(builder, "weights")?;
+    let op = TfIdfVectorizer::new(
+        mode,
+        min_gram_length,
+        max_gram_length,
+        max_skip_count,
+        ngram_counts.into_vec(),
+        ngram_indexes.into_vec(),
+        pool,
+        weights.map(|w| w.into_vec()),
+    )?;
+    builder.wire(op, &[input])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // pool and input of the ONNX backend tf_*_skip* tests
+    fn vectorizer(min: usize, max: usize, skip: usize) -> TractResult<TfIdfVectorizer> {
+        TfIdfVectorizer::new(
+            TfIdfMode::Tf,
+            min,
+            max,
+            skip,
+            vec![0, 4],
+            vec![0, 1, 2, 3, 4, 5, 6],
+            rctensor1(&[2i64, 3, 5, 4, 5, 6, 7, 8, 6, 7]),
+            None,
+        )
+    }
+
+    fn run(op: &TfIdfVectorizer, input: Tensor) -> TractResult<Tensor> {
+        Ok(op.eval(tvec!(input.into_tvalue()))?.remove(0).into_tensor())
+    }
+
+    #[test]
+    fn only_bigrams_skip0() -> TractResult<()> {
+        let input = tensor1(&[1i32, 1, 3, 3, 3, 7, 8, 6, 7, 5, 6, 8]);
+        let output = run(&vectorizer(2, 2, 0)?, input)?;
+        assert_eq!(output, tensor1(&[0f32, 0., 0., 0., 1., 1., 1.]));
+        Ok(())
+    }
+
+    #[test]
+    fn uni_and_bigrams_skip5() -> TractResult<()> {
+        let input = tensor1(&[1i32, 1, 3, 3, 3, 7, 8, 6, 7, 5, 6, 8]);
+        let output = run(&vectorizer(1, 2, 5)?, input)?;
+        assert_eq!(output, tensor1(&[0f32, 3., 1., 0., 1., 3., 1.]));
+        Ok(())
+    }
+
+    #[test]
+    fn batch_only_bigrams_skip5() -> TractResult<()> {
+        let input = tensor2(&[[1i32, 1, 3, 3, 3, 7], [8, 6, 7, 5, 6, 8]]);
+        let output = run(&vectorizer(2, 2, 5)?, input)?;
+        assert_eq!(
+            output,
+            tensor2(&[[0f32, 0., 0., 0., 0., 0., 0.], [0., 0., 0., 0., 1., 1., 1.]])
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+use regex::Regex;
+use tract_itertools::Itertools;
+use tract_nnef::internal::*;
+use tract_nnef::ser::array;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<Tokenizer>(), dump);
+    registry.register_primitive(
+        "tract_onnx_tokenizer",
+        &[
+            TypeName::String.tensor().named("input"),
+            TypeName::Logical.named("mark"),
+            TypeName::String.named("pad_value"),
+            TypeName::Integer.named("mincharnum"),
+            TypeName::String.array().named("separators").default(false),
+            TypeName::String.named("tokenexp").default(false),
+        ],
+        &[("output", TypeName::String.tensor())],
+        load,
+    );
+}
+
+/// Tokenizer from the com.microsoft domain: splits strings on separator
+/// expressions, or collects the matches of a token expression. Tokens go
+/// along a new last axis, padded with `pad_value`, and are optionally
+/// surrounded by start (0x02) and end (0x03) of text marks.
+#[derive(Clone, Debug)]
+pub struct Tokenizer {
+    pub mark: bool,
+    pub pad_value: String,
+    pub mincharnum: usize,
+    pub separators: Option<Vec<String>>,
+    pub tokenexp: Option<String>,
+    pub output_len: Symbol,
+    /// None for character level tokenization.
+    regex: Option<Regex>,
+}
+
+impl Tokenizer {
+    pub fn new(
+        mark: bool,
+        pad_value: String,
+        mincharnum: usize,
+        separators: Option<Vec<String>>,
+        tokenexp: Option<String>,
+        output_len: Symbol,
+    ) -> TractResult<Tokenizer> {
+        let regex = match (&separators, &tokenexp) {
+            (Some(separators), None) if separators.iter().any(|s| s.is_empty()) => None,
+            (Some(separators), None) => {
+                let pattern = separators.iter().map(|s| format!("(?:{s})")).join("|");
+                Some(
+                    Regex::new(&pattern)
+                        .with_context(|| format!("Invalid separators {separators:?}"))?,
+                )
+            }
+            (None, Some(tokenexp)) => Some(
+                Regex::new(tokenexp).with_context(|| format!("Invalid tokenexp {tokenexp:?}"))?,
+            ),
+            _ => bail!("Tokenizer expects exactly one of separators and tokenexp"),
+        };
+        Ok(Tokenizer { mark, pad_value, mincharnum, separators, tokenexp, output_len, regex })
+    }
+
+    fn tokenize<'s>(&self, s: &'s str) -> Vec<&'s str> {
+        let tokens: Vec<&str> = match &self.regex {
+            Some(regex) if self.tokenexp.is_some() => {
+                regex.find_iter(s).map(|m| m.as_str()).collect()
+            }
+            Some(regex) => regex.split(s).collect(),
+            None => s.char_indices().map(|(ix, c)| &s[ix..ix + c.len_utf8()]).collect(),
+        };
+        let min = self.mincharnum.max(1);
+        let mut tokens: Vec<&str> =
+            tokens.into_iter().filter(|t| t.chars().count() >= min).collect();
+        if self.mark {
+            tokens.insert(0, "\u{2}");
+            tokens.push("\u{3}");
+        }
+        tokens
+    }
+}
+
+impl Op for Tokenizer {
+    fn name(&self) -> Cow<str> {
+        "Tokenizer".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        let mut info = vec![format!(
+            "mark: {}, pad_value: {:?}, mincharnum: {}",
+            self.mark, self.pad_value, self.mincharnum
+        )];
+        if let Some(separators) = &self.separators {
+            info.push(format!("separators: {separators:?}"));
+        }
+        if let Some(tokenexp) = &self.tokenexp {
+            info.push(format!("tokenexp: {tokenexp:?}"));
+        }
+        Ok(info)
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for Tokenizer {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let tokens: Vec<Vec<&str>> =
+            input.as_slice::<String>()?.iter().map(|s| self.tokenize(s)).collect();
+        let len = tokens.iter().map(|t| t.len()).max().unwrap_or(0);
+        let mut output = vec![self.pad_value.clone(); tokens.len() * len];
+        for (chunk, tokens) in output.chunks_mut(len.max(1)).zip(&tokens) {
+            for (o, token) in chunk.iter_mut().zip(tokens) {
+                *o = token.to_string();
+            }
+        }
+        let mut shape: TVec<usize> = input.shape().into();
+        shape.push(len);
+        Ok(tvec!(tensor1(&output).into_shape(&shape)?.into_tvalue()))
+    }
+}
+
+impl TypedOp for Tokenizer {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs[0].datum_type == String::datum_type());
+        ensure!(
+            inputs[0].rank() == 1 || inputs[0].rank() == 2,
+            "Tokenizer expects a [C] or [N, C] input, got {:?}",
+            inputs[0].shape
+        );
+        let mut shape = inputs[0].shape.to_tvec();
+        shape.push(self.output_len.to_dim());
+        Ok(tvec!(String::fact(shape)))
+    }
+
+    as_op!();
+}
+
+fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<Tokenizer>().context("wrong op")?;
+    let mut named: TVec<(_, RValue)> = tvec![
+        ("mark", logical(op.mark)),
+        ("pad_value", string(&op.pad_value)),
+        ("mincharnum", numeric(op.mincharnum)),
+    ];
+    if let Some(separators) = &op.separators {
+        named.push(("separators", array(separators.iter().map(string).collect::<TVec<_>>())));
+    }
+    if let Some(tokenexp) = &op.tokenexp {
+        named.push(("tokenexp", string(tokenexp)));
+    }
+    Ok(Some(invocation("tract_onnx_tokenizer", &[input], &named)))
+}
+
+fn load(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let separators: Option<TVec<String>> =
+        invocation.optional_named_arg_as(builder, "separators")?;
+    let op = Tokenizer::new(
+        invocation.named_arg_as(builder, "mark")?,
+        invocation.named_arg_as(builder, "pad_value")?,
+        invocation.named_arg_as(builder, "mincharnum")?,
+        separators.map(|s| s.into_vec()),
+        invocation.optional_named_arg_as(builder, "tokenexp")?,
+        builder.model.symbol_table.new_with_prefix("n"),
+    )?;
+    builder.wire(op, &[input])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tokenizer(separators: Option<&[&str]>, tokenexp: Option<&str>) -> TractResult<Tokenizer> {
+        Tokenizer::new(
+            true,
+            "#".to_string(),
+            1,
+            separators.map(|s| s.iter().map(|s| s.to_string()).collect()),
+            tokenexp.map(|s| s.to_string()),
+            SymbolTable::default().sym("n"),
+        )
+    }
+
+    fn run(op: &Tokenizer, input: &[&str]) -> TractResult<Tensor> {
+        let input = tensor1(&input.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        Ok(op.eval(tvec!(input.into_tvalue()))?.remove(0).into_tensor())
+    }
+
+    fn strings(s: &[&str], shape: &[usize]) -> TractResult<Tensor> {
+        tensor1(&s.iter().map(|s| s.to_string()).collect::<Vec<_>>()).into_shape(shape)
+    }
+
+    #[test]
+    fn separators() -> TractResult<()> {
+        let op = tokenizer(Some(&[" ", ","]), None)?;
+        let output = run(&op, &["Hello World!", "a,b c"])?;
+        let expected = strings(
+            &["\u{2}", "Hello", "World!", "\u{3}", "#", "\u{2}", "a", "b", "c", "\u{3}"],
+            &[2, 5],
+        )?;
+        assert_eq!(output, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn characters() -> TractResult<()> {
+        let op = tokenizer(Some(&[""]), None)?;
+        let output = run(&op, &["ab"])?;
+        assert_eq!(output, strings(&["\u{2}", "a", "b", "\u{3}"], &[1, 4])?);
+        Ok(())
+    }
+
+    #[test]
+    fn tokenexp() -> TractResult<()> {
+        let op = tokenizer(None, Some("[a-z]+"))?;
+        let output = run(&op, &["abc 12 de"])?;
+        assert_eq!(output, strings(&["\u{2}", "abc", "de", "\u{3}"], &[1, 4])?);
+        Ok(())
+    }
+
+    #[test]
+    fn mincharnum_and_padding() -> TractResult<()> {
+        let mut op = tokenizer(Some(&[" "]), None)?;
+        op.mark = false;
+        op.mincharnum = 2;
+        let output = run(&op, &["a bc def", "gh"])?;
+        assert_eq!(output, strings(&["bc", "def", "gh", "#"], &[2, 2])?);
+        Ok(())
+    }
+}
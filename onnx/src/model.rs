@@ -2,6 +2,7 @@ use std::convert::TryInto;
 use std::path::PathBuf;
 use std::{fs, path};
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 
 use tract_hir::internal::*;
@@ -40,6 +41,9 @@ pub fn optional_outputs(pb: &pb::NodeProto) -> impl Iterator<Item = Option<usize
 pub struct TensorPlusPath<'a> {
     pub tensor: &'a pb::TensorProto,
     pub model_path: &'a str,
+    /// External data files already opened, by location, so tensors sharing a
+    /// file share a single mapping.
+    pub external_data: &'a RefCell<HashMap<String, Arc<dyn TensorStorage>>>,
 }
 
 #[derive(Clone)]
@@ -71,11 +75,13 @@ impl<'a> ParsingContext<'a> {
         #[allow(unused_assignments)]
         let mut initializers: HashMap<&str, Tensor> = HashMap::default();
         if let Some(path) = self.model_path {
+            let external_data = RefCell::default();
             initializers = graph
                 .initializer
                 .iter()
                 .map(|tensor| {
-                    let tensor_struct: TensorPlusPath = TensorPlusPath { tensor, model_path: path };
+                    let tensor_struct: TensorPlusPath =
+                        TensorPlusPath { tensor, model_path: path, external_data: &external_data };
                     Ok((&*tensor.name, tensor_struct.try_into()?))
                 })
                 .collect::<TractResult<_>>()?;
@@ -224,6 +230,7 @@ pub struct Onnx {
     pub op_register: OnnxOpRegister,
     pub use_output_shapes: bool,
     pub ignore_output_types: bool,
+    pub stochastic_dropout: bool,
 }
 
 impl Onnx {
@@ -270,6 +277,12 @@ impl Onnx {
         Self { ignore_output_types: ignore, ..self }
     }
 
+    /// Make Dropout randomly mask its input at inference, as in training
+    /// mode, for Monte-Carlo dropout.
+    pub fn with_stochastic_dropout(self, enable: bool) -> Onnx {
+        Self { stochastic_dropout: enable, ..self }
+    }
+
     pub fn determinize(model: &mut InferenceModel) -> TractResult<()> {
         use crate::ops::multinomial::Multinomial;
         for node in model.nodes_mut() {
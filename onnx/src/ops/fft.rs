@@ -1,5 +1,6 @@
 use crate::model::{OnnxOpRegister, ParsingContext};
 use crate::pb::NodeProto;
+use tract_core::ops::audio::WindowType;
 use tract_hir::internal::*;
 use tract_hir::ops::array::Pad;
 use tract_hir::ops::cast::cast;
@@ -58,9 +59,9 @@ fn window(
     let datum_type = node.get_attr_opt("output_datatype")?.unwrap_or(DatumType::F32);
     let periodic = node.get_attr_opt("periodic")?.unwrap_or(1i64) == 1i64;
     let window = match &*node.op_type {
-        "BlackmanWindow" => StftWindowType::Blackman,
-        "HammingWindow" => StftWindowType::Hamming,
-        "HannWindow" => StftWindowType::Hann,
+        "BlackmanWindow" => WindowType::Blackman,
+        "HammingWindow" => WindowType::Hamming,
+        "HannWindow" => WindowType::Hann,
         _ => unreachable!(),
     };
     Ok((expand(StftWindow { datum_type, periodic, window }), vec![]))
@@ -382,45 +383,11 @@ impl Expansion for MelWeightMatrix {
     }
 }
 
-#[derive(Copy, Clone, Debug, Hash)]
-enum StftWindowType {
-    Blackman,
-    Hamming,
-    Hann,
-}
-
-impl StftWindowType {
-    fn generate(&self, size: usize, periodic: bool) -> TractResult<Tensor> {
-        use std::f32::consts::PI;
-        let divisor = ((size - 1 + periodic as usize) as f32).recip();
-        let mut output = Tensor::zero::<f32>(&[size])?;
-        match self {
-            Self::Blackman => {
-                output.as_slice_mut::<f32>()?.iter_mut().enumerate().for_each(|(ix, y)| {
-                    *y = 0.42 - 0.5 * (2. * PI * ix as f32 * divisor).cos()
-                        + 0.08 * (4. * PI * ix as f32 * divisor).cos()
-                })
-            }
-            Self::Hamming => {
-                output.as_slice_mut::<f32>()?.iter_mut().enumerate().for_each(|(ix, y)| {
-                    *y = (25. / 46.) - (21. / 46.) * (2. * PI * ix as f32 * divisor).cos()
-                })
-            }
-            Self::Hann => output
-                .as_slice_mut::<f32>()?
-                .iter_mut()
-                .enumerate()
-                .for_each(|(ix, y)| *y = 0.5 - 0.5 * (2. * PI * ix as f32 * divisor).cos()),
-        }
-        Ok(output)
-    }
-}
-
 #[derive(Clone, Debug, Hash)]
 pub struct StftWindow {
     datum_type: DatumType,
     periodic: bool,
-    window: StftWindowType,
+    window: WindowType,
 }
 
 
@@ -0,0 +1,125 @@
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::image;
+use tract_onnx_opl::image_decoder;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert("CenterCropPad", center_crop_pad);
+    reg.insert("ImageDecoder", image_decoder);
+}
+
+fn center_crop_pad(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    Ok((expand(CenterCropPad { axes: node.get_attr_opt_vec("axes")? }), vec![]))
+}
+
+#[derive(Debug, Clone)]
+struct CenterCropPad {
+    axes: Option<Vec<i64>>,
+}
+
+impl CenterCropPad {
+    fn core_op(&self, rank: usize, shape: &Tensor) -> TractResult<image::CenterCropPad> {
+        let axes: TVec<usize> = if let Some(axes) = &self.axes {
+            axes.iter().map(|&a| (if a < 0 { a + rank as i64 } else { a }) as usize).collect()
+        } else {
+            (0..rank).collect()
+        };
+        let sizes: TVec<usize> =
+            shape.cast_to::<i64>()?.as_slice::<i64>()?.iter().map(|&s| s as usize).collect();
+        ensure!(sizes.len() == axes.len(), "Expected {} sizes, got {:?}", axes.len(), sizes);
+        Ok(image::CenterCropPad { axes, sizes })
+    }
+}
+
+impl Expansion for CenterCropPad {
+    fn name(&self) -> Cow<str> {
+        "CenterCropPad".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 2)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].rank, &outputs[0].rank)?;
+        s.given_2(&inputs[0].shape, &inputs[1].value, move |s, shape, sizes| {
+            let op = self.core_op(shape.len(), &sizes)?;
+            let mut shape = shape.to_vec();
+            for (&axis, &size) in op.axes.iter().zip(&op.sizes) {
+                shape[axis] = size.to_dim();
+            }
+            s.equals(&outputs[0].shape, ShapeFactoid::from(shape))
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let fact = model.outlet_fact(inputs[1])?;
+        let sizes = fact
+            .konst
+            .clone()
+            .with_context(|| format!("CenterCropPad expects a constant shape, got {fact:?}"))?;
+        let op = self.core_op(model.outlet_fact(inputs[0])?.rank(), &sizes)?;
+        model.wire_node(prefix, op, &[inputs[0]])
+    }
+}
+
+fn image_decoder(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let op = image_decoder::ImageDecoder {
+        pixel_format: node.get_attr_opt("pixel_format")?.unwrap_or("RGB").parse()?,
+        height: ctx.symbol_table.new_with_prefix("h"),
+        width: ctx.symbol_table.new_with_prefix("w"),
+    };
+    Ok((expand(ImageDecoder(op)), vec![]))
+}
+
+#[derive(Debug, Clone)]
+struct ImageDecoder(image_decoder::ImageDecoder);
+
+impl Expansion for ImageDecoder {
+    fn name(&self) -> Cow<str> {
+        "ImageDecoder".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, u8::datum_type())?;
+        s.equals(&inputs[0].rank, 1)?;
+        s.equals(&outputs[0].datum_type, u8::datum_type())?;
+        let channels = self.0.pixel_format.channels().to_dim();
+        s.equals(
+            &outputs[0].shape,
+            ShapeFactoid::from(vec![self.0.height.to_dim(), self.0.width.to_dim(), channels]),
+        )
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        model.wire_node(prefix, self.0.clone(), inputs)
+    }
+}
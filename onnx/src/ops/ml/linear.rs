@@ -0,0 +1,268 @@
+use super::tree_ensemble_classifier::*;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+use tract_hir::internal::*;
+use tract_hir::ops::array::TypedConcat;
+use tract_hir::tract_core::ops::einsum::EinSum;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert("LinearClassifier", linear_classifier);
+    reg.insert("LinearRegressor", linear_regressor);
+}
+
+fn parse_weights(node: &NodeProto, rows: usize) -> TractResult<(Arc<Tensor>, Arc<Tensor>)> {
+    let coefficients: Vec<f32> = node.get_attr_vec("coefficients")?;
+    node.expect_attr("coefficients", rows > 0 && coefficients.len() % rows == 0, || {
+        format!("a multiple of {rows} coefficients")
+    })?;
+    let coefficients =
+        tensor1(&coefficients).into_shape(&[rows, coefficients.len() / rows])?.into_arc_tensor();
+    let intercepts =
+        get_vec_attr_opt::<f32>(node, "intercepts", rows)?.unwrap_or_else(|| vec![0f32; rows]);
+    Ok((coefficients, tensor1(&intercepts).into_shape(&[1, rows])?.into_arc_tensor()))
+}
+
+fn linear_classifier(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let class_labels = parse_class_labels(node, "classlabels_ints")?;
+    let intercepts: Option<Vec<f32>> = node.get_attr_opt_vec("intercepts")?;
+    let rows = if let Some(intercepts) = intercepts {
+        intercepts.len()
+    } else if class_labels.len() == 2 {
+        1
+    } else {
+        class_labels.len()
+    };
+    let (coefficients, intercepts) = parse_weights(node, rows)?;
+    let post_transform =
+        node.get_attr_opt("post_transform")?.map(parse_post_transform).transpose()?.unwrap_or(None);
+    Ok((
+        expand(LinearClassifier { coefficients, intercepts, class_labels, post_transform }),
+        vec![],
+    ))
+}
+
+fn linear_regressor(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let targets = node.get_attr_opt("targets")?.unwrap_or(1);
+    let (coefficients, intercepts) = parse_weights(node, targets)?;
+    let post_transform =
+        node.get_attr_opt("post_transform")?.map(parse_post_transform).transpose()?.unwrap_or(None);
+    Ok((expand(LinearRegressor { coefficients, intercepts, post_transform }), vec![]))
+}
+
+/// Wire `x.coefficientsᵀ + intercepts`, x being casted to f32 if needed.
+fn wire_linear(
+    prefix: &str,
+    model: &mut TypedModel,
+    input: OutletId,
+    coefficients: &Arc<Tensor>,
+    intercepts: &Arc<Tensor>,
+) -> TractResult<OutletId> {
+    let input = super::wire_cast_to_f32(prefix, model, input)?;
+    let coefs = model.add_const(format!("{prefix}.coefficients"), coefficients.clone())?;
+    let intercepts = model.add_const(format!("{prefix}.intercepts"), intercepts.clone())?;
+    let product = model.wire_node(
+        format!("{prefix}.product"),
+        EinSum::new("nf,cf->nc".parse()?, f32::datum_type()),
+        &[input, coefs],
+    )?;
+    Ok(model.wire_node(
+        format!("{prefix}.intercepts.add"),
+        tract_core::ops::math::add(),
+        &[product[0], intercepts],
+    )?[0])
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct LinearClassifier {
+    pub coefficients: Arc<Tensor>,
+    pub intercepts: Arc<Tensor>,
+    pub class_labels: Arc<Tensor>,
+    pub post_transform: Option<PostTransform>,
+}
+
+impl LinearClassifier {
+    // binary classifiers are expressed with a single row of coefficients
+    fn is_binary(&self) -> bool {
+        self.coefficients.shape()[0] == 1
+    }
+}
+
+impl Expansion for LinearClassifier {
+    fn name(&self) -> Cow<str> {
+        "LinearClassifier".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 2)?;
+
+        s.equals(&outputs[0].datum_type, self.class_labels.datum_type())?;
+        s.equals(&outputs[1].datum_type, DatumType::F32)?;
+
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&outputs[0].rank, 1)?;
+        s.equals(&outputs[1].rank, 2)?;
+        s.equals(&outputs[0].shape[0], &inputs[0].shape[0])?;
+        s.equals(&outputs[1].shape[0], &inputs[0].shape[0])?;
+        s.equals(&inputs[0].shape[1], self.coefficients.shape()[1].to_dim())?;
+        let n_scores = if self.is_binary() { 2 } else { self.coefficients.shape()[0] };
+        s.equals(&outputs[1].shape[1], n_scores.to_dim())?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        use tract_core::ops::nn::*;
+        let raw = wire_linear(prefix, model, inputs[0], &self.coefficients, &self.intercepts)?;
+        let class_ids = if self.is_binary() {
+            let zero = model.add_const(format!("{prefix}.zero"), rctensor2(&[[0f32]]))?;
+            model.wire_node(
+                format!("{prefix}.positive"),
+                tract_core::ops::logic::greater(),
+                &[raw, zero],
+            )?
+        } else {
+            model.wire_node(
+                format!("{prefix}.argmax"),
+                Reduce::new(tvec!(1), Reducer::ArgMax(false)),
+                &[raw],
+            )?
+        };
+        let class_ids = model.wire_node(
+            format!("{prefix}.rm_axis"),
+            tract_core::ops::change_axes::AxisOp::Rm(1),
+            &class_ids,
+        )?[0];
+        let labels = wire_class_labels(prefix, model, &self.class_labels, class_ids)?;
+        let mut scores = wire_post_transform(prefix, model, self.post_transform, tvec!(raw))?;
+        if self.is_binary() {
+            let complement = if self.post_transform.is_some() {
+                let one = model.add_const(format!("{prefix}.one"), rctensor2(&[[1f32]]))?;
+                model.wire_node(
+                    format!("{prefix}.binary_result_complement"),
+                    tract_core::ops::math::sub(),
+                    &[one, scores[0]],
+                )?
+            } else {
+                model.wire_node(
+                    format!("{prefix}.binary_result_complement"),
+                    tract_core::ops::math::neg(),
+                    &scores,
+                )?
+            };
+            scores = model.wire_node(
+                format!("{prefix}.binary_result"),
+                TypedConcat::new(1),
+                &[complement[0], scores[0]],
+            )?;
+        }
+        Ok(tvec!(labels, scores[0]))
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(2)
+    }
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct LinearRegressor {
+    pub coefficients: Arc<Tensor>,
+    pub intercepts: Arc<Tensor>,
+    pub post_transform: Option<PostTransform>,
+}
+
+impl Expansion for LinearRegressor {
+    fn name(&self) -> Cow<str> {
+        "LinearRegressor".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+
+        s.equals(&outputs[0].datum_type, DatumType::F32)?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.equals(&outputs[0].shape[0], &inputs[0].shape[0])?;
+        s.equals(&inputs[0].shape[1], self.coefficients.shape()[1].to_dim())?;
+        s.equals(&outputs[0].shape[1], self.coefficients.shape()[0].to_dim())?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let raw = wire_linear(prefix, model, inputs[0], &self.coefficients, &self.intercepts)?;
+        wire_post_transform(prefix, model, self.post_transform, tvec!(raw))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn regressor() -> TractResult<()> {
+        let attributes = vec![
+            int("targets", 2),
+            floats("coefficients", &[1., 2., 3., 4.]),
+            floats("intercepts", &[0.5, -1.]),
+        ];
+        let input = tensor2(&[[1f32, 1.], [0., 2.]]);
+        let outputs = run(linear_regressor, "LinearRegressor", attributes, tvec!(input))?;
+        assert_eq!(*outputs[0], tensor2(&[[3.5f32, 6.], [4.5, 7.]]));
+        Ok(())
+    }
+
+    #[test]
+    fn binary_classifier() -> TractResult<()> {
+        let attributes = vec![
+            ints("classlabels_ints", &[0, 1]),
+            floats("coefficients", &[1., -1.]),
+            floats("intercepts", &[0.]),
+        ];
+        let input = tensor2(&[[2f32, 1.], [0., 3.]]);
+        let outputs = run(linear_classifier, "LinearClassifier", attributes, tvec!(input))?;
+        assert_eq!(*outputs[0], tensor1(&[1i64, 0]));
+        assert_eq!(*outputs[1], tensor2(&[[-1f32, 1.], [3., -3.]]));
+        Ok(())
+    }
+
+    #[test]
+    fn multiclass_classifier() -> TractResult<()> {
+        let attributes = vec![
+            strings("classlabels_strings", &["a", "b", "c"]),
+            floats("coefficients", &[1., 0., 0., 1., -1., -1.]),
+            string("post_transform", "NONE"),
+        ];
+        let input = tensor2(&[[1f32, 2.], [3., -1.]]);
+        let outputs = run(linear_classifier, "LinearClassifier", attributes, tvec!(input))?;
+        assert_eq!(*outputs[0], tensor1(&["b".to_string(), "a".to_string()]));
+        assert_eq!(*outputs[1], tensor2(&[[1f32, 2., -3.], [3., -1., -2.]]));
+        Ok(())
+    }
+}
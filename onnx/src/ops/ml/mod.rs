@@ -1,9 +1,119 @@
 mod category_mapper;
+mod linear;
+mod preprocessing;
+mod svm;
 mod tree_ensemble_classifier;
+mod tree_ensemble_regressor;
+mod zip_map;
 
 use crate::model::OnnxOpRegister;
+use tract_hir::internal::*;
 
 pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     category_mapper::register_all_ops(reg);
+    linear::register_all_ops(reg);
+    preprocessing::register_all_ops(reg);
+    svm::register_all_ops(reg);
     tree_ensemble_classifier::register_all_ops(reg);
+    tree_ensemble_regressor::register_all_ops(reg);
+    zip_map::register_all_ops(reg);
+}
+
+/// ONNX-ML ops accept integer and double inputs, but compute in f32.
+fn wire_cast_to_f32(
+    prefix: &str,
+    model: &mut TypedModel,
+    input: OutletId,
+) -> TractResult<OutletId> {
+    if model.outlet_fact(input)?.datum_type == f32::datum_type() {
+        Ok(input)
+    } else {
+        Ok(model.wire_node(
+            format!("{prefix}.cast_to_f32"),
+            tract_core::ops::cast::cast(f32::datum_type()),
+            &[input],
+        )?[0])
+    }
+}
+
+#[cfg(test)]
+mod test_utils {
+    use crate::model::ParsingContext;
+    use crate::pb::attribute_proto::AttributeType;
+    use crate::pb::{AttributeProto, ModelProto, NodeProto};
+    use tract_hir::internal::*;
+
+    type Parser =
+        fn(&ParsingContext, &NodeProto) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)>;
+
+    fn attr(name: &str, r#type: AttributeType) -> AttributeProto {
+        AttributeProto {
+            name: name.to_string(),
+            r#type: r#type as i32,
+            ..AttributeProto::default()
+        }
+    }
+
+    pub fn int(name: &str, i: i64) -> AttributeProto {
+        AttributeProto { i, ..attr(name, AttributeType::Int) }
+    }
+
+    pub fn float(name: &str, f: f32) -> AttributeProto {
+        AttributeProto { f, ..attr(name, AttributeType::Float) }
+    }
+
+    pub fn string(name: &str, s: &str) -> AttributeProto {
+        AttributeProto { s: s.as_bytes().to_vec(), ..attr(name, AttributeType::String) }
+    }
+
+    pub fn ints(name: &str, ints: &[i64]) -> AttributeProto {
+        AttributeProto { ints: ints.to_vec(), ..attr(name, AttributeType::Ints) }
+    }
+
+    pub fn floats(name: &str, floats: &[f32]) -> AttributeProto {
+        AttributeProto { floats: floats.to_vec(), ..attr(name, AttributeType::Floats) }
+    }
+
+    pub fn strings(name: &str, strings: &[&str]) -> AttributeProto {
+        let strings = strings.iter().map(|s| s.to_string().into_bytes()).collect();
+        AttributeProto { strings, ..attr(name, AttributeType::Strings) }
+    }
+
+    /// Parse a node with `parser`, and run it on `inputs`.
+    pub fn run(
+        parser: Parser,
+        op_type: &str,
+        attribute: Vec<AttributeProto>,
+        inputs: TVec<Tensor>,
+    ) -> TractResult<TVec<TValue>> {
+        let node = NodeProto {
+            name: op_type.to_string(),
+            op_type: op_type.to_string(),
+            attribute,
+            ..NodeProto::default()
+        };
+        let onnx = crate::onnx();
+        let proto = ModelProto::default();
+        let ctx = ParsingContext {
+            onnx_operator_set_version: 18,
+            framework: &onnx,
+            model: &proto,
+            parent_graphs: vec![],
+            model_path: None,
+            symbol_table: SymbolTable::default(),
+        };
+        let (op, _) = parser(&ctx, &node)?;
+        let mut model = InferenceModel::default();
+        let mut wires = tvec!();
+        for (ix, input) in inputs.iter().enumerate() {
+            let fact = InferenceFact::dt_shape(input.datum_type(), input.shape());
+            wires.push(model.add_source(format!("input.{ix}"), fact)?);
+        }
+        let outputs = model.wire_node(op_type, op, &wires)?;
+        model.set_output_outlets(&outputs)?;
+        model
+            .into_optimized()?
+            .into_runnable()?
+            .run(inputs.into_iter().map(|t| t.into_tvalue()).collect())
+    }
 }
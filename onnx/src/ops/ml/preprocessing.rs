@@ -0,0 +1,506 @@
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+use tract_hir::internal::*;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert("Normalizer", normalizer);
+    reg.insert("Scaler", scaler);
+    reg.insert("Binarizer", binarizer);
+    reg.insert("Imputer", imputer);
+    reg.insert("OneHotEncoder", one_hot_encoder);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Norm {
+    Max,
+    L1,
+    L2,
+}
+
+fn normalizer(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let norm = match node.get_attr_opt("norm")?.unwrap_or("MAX") {
+        "MAX" => Norm::Max,
+        "L1" => Norm::L1,
+        "L2" => Norm::L2,
+        other => bail!("Invalid norm for Normalizer: {}", other),
+    };
+    Ok((expand(Normalizer { norm }), vec![]))
+}
+
+fn scaler(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let offset: Vec<f32> = node.get_attr_opt_vec("offset")?.unwrap_or_else(|| vec![0.0]);
+    let scale: Vec<f32> = node.get_attr_opt_vec("scale")?.unwrap_or_else(|| vec![1.0]);
+    Ok((expand(Scaler { offset: rctensor1(&offset), scale: rctensor1(&scale) }), vec![]))
+}
+
+fn binarizer(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let threshold = node.get_attr_opt("threshold")?.unwrap_or(0.0);
+    Ok((expand(Binarizer { threshold }), vec![]))
+}
+
+fn imputer(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let floats: Option<Vec<f32>> = node.get_attr_opt_vec("imputed_value_floats")?;
+    let ints: Option<Vec<i64>> = node.get_attr_opt_vec("imputed_value_int64s")?;
+    let op = match (floats, ints) {
+        (Some(floats), None) => Imputer {
+            imputed: rctensor1(&floats),
+            replaced: rctensor0(node.get_attr_opt("replaced_value_float")?.unwrap_or(0f32)),
+        },
+        (None, Some(ints)) => Imputer {
+            imputed: rctensor1(&ints),
+            replaced: rctensor0(node.get_attr_opt("replaced_value_int64")?.unwrap_or(0i64)),
+        },
+        _ => bail!("Imputer requires exactly one of imputed_value_floats and imputed_value_int64s"),
+    };
+    Ok((expand(op), vec![]))
+}
+
+fn one_hot_encoder(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let ints: Option<Vec<i64>> = node.get_attr_opt_vec("cats_int64s")?;
+    let strings: Option<Vec<String>> = node.get_attr_opt_vec("cats_strings")?;
+    let categories = match (ints, strings) {
+        (Some(ints), None) => rctensor1(&ints),
+        (None, Some(strings)) => rctensor1(&strings),
+        _ => bail!("OneHotEncoder requires exactly one of cats_int64s and cats_strings"),
+    };
+    let zeros = node.get_attr_opt::<i64>("zeros")?.unwrap_or(1) != 0;
+    Ok((expand(OneHotEncoder { categories, zeros }), vec![]))
+}
+
+/// Normalize each row of the input so that its max, L1 or L2 norm is 1. Rows with a zero norm
+/// are kept unchanged.
+#[derive(Debug, Clone, Hash)]
+pub struct Normalizer {
+    pub norm: Norm,
+}
+
+impl Expansion for Normalizer {
+    fn name(&self) -> Cow<str> {
+        "Normalizer".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&outputs[0].datum_type, DatumType::F32)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        use tract_core::ops::math;
+        use tract_core::ops::nn::{Reduce, Reducer};
+        let input = super::wire_cast_to_f32(prefix, model, inputs[0])?;
+        let axis = model.outlet_fact(input)?.rank() - 1;
+        let mut norm = match self.norm {
+            Norm::Max => tvec!(input),
+            Norm::L1 => model.wire_node(format!("{prefix}.abs"), math::abs(), &[input])?,
+            Norm::L2 => model.wire_node(format!("{prefix}.square"), math::square(), &[input])?,
+        };
+        let reducer = if self.norm == Norm::Max { Reducer::Max } else { Reducer::Sum };
+        norm =
+            model.wire_node(format!("{prefix}.norm"), Reduce::new(tvec!(axis), reducer), &norm)?;
+        if self.norm == Norm::L2 {
+            norm = model.wire_node(format!("{prefix}.sqrt"), math::sqrt(), &norm)?;
+        }
+        // rows with a zero norm are left as is instead of turning into NaN
+        let rank = axis + 1;
+        let zero =
+            model.add_const(format!("{prefix}.zero"), tensor0(0f32).broadcast_into_rank(rank)?)?;
+        let one =
+            model.add_const(format!("{prefix}.one"), tensor0(1f32).broadcast_into_rank(rank)?)?;
+        let is_zero = model.wire_node(
+            format!("{prefix}.is_zero"),
+            tract_core::ops::logic::equals(),
+            &[norm[0], zero],
+        )?;
+        norm = model.wire_node(
+            format!("{prefix}.safe_norm"),
+            tract_core::ops::logic::Iff,
+            &[is_zero[0], one, norm[0]],
+        )?;
+        model.wire_node(prefix, math::div(), &[input, norm[0]])
+    }
+}
+
+/// Compute `(x - offset) * scale`, offset and scale being either scalar or per-feature.
+#[derive(Debug, Clone, Hash)]
+pub struct Scaler {
+    pub offset: Arc<Tensor>,
+    pub scale: Arc<Tensor>,
+}
+
+impl Expansion for Scaler {
+    fn name(&self) -> Cow<str> {
+        "Scaler".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&outputs[0].datum_type, DatumType::F32)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        use tract_core::ops::math;
+        let input = super::wire_cast_to_f32(prefix, model, inputs[0])?;
+        let rank = model.outlet_fact(input)?.rank();
+        let offset = (*self.offset).clone().broadcast_into_rank(rank)?;
+        let offset = model.add_const(format!("{prefix}.offset"), offset)?;
+        let scale = (*self.scale).clone().broadcast_into_rank(rank)?;
+        let scale = model.add_const(format!("{prefix}.scale"), scale)?;
+        let centered = model.wire_node(format!("{prefix}.sub"), math::sub(), &[input, offset])?;
+        model.wire_node(prefix, math::mul(), &[centered[0], scale])
+    }
+}
+
+/// Replace values greater than the threshold by 1, others by 0, keeping the input type.
+#[derive(Debug, Clone)]
+pub struct Binarizer {
+    pub threshold: f32,
+}
+
+impl Expansion for Binarizer {
+    fn name(&self) -> Cow<str> {
+        "Binarizer".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let fact = model.outlet_fact(inputs[0])?.clone();
+        let threshold = tensor0(self.threshold)
+            .cast_to_dt(fact.datum_type)?
+            .into_owned()
+            .broadcast_into_rank(fact.rank())?;
+        let threshold = model.add_const(format!("{prefix}.threshold"), threshold)?;
+        let above = model.wire_node(
+            format!("{prefix}.greater"),
+            tract_core::ops::logic::greater(),
+            &[inputs[0], threshold],
+        )?;
+        model.wire_node(prefix, tract_core::ops::cast::cast(fact.datum_type), &above)
+    }
+}
+
+/// Replace missing values (NaN, or the value designated by `replaced`) by the per-feature
+/// `imputed` value.
+#[derive(Debug, Clone, Hash)]
+pub struct Imputer {
+    pub imputed: Arc<Tensor>,
+    pub replaced: Arc<Tensor>,
+}
+
+impl Expansion for Imputer {
+    fn name(&self) -> Cow<str> {
+        "Imputer".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&outputs[0].datum_type, self.imputed.datum_type())?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let dt = self.imputed.datum_type();
+        let mut input = inputs[0];
+        if model.outlet_fact(input)?.datum_type != dt {
+            input = model.wire_node(
+                format!("{prefix}.cast"),
+                tract_core::ops::cast::cast(dt),
+                &[input],
+            )?[0];
+        }
+        let rank = model.outlet_fact(input)?.rank();
+        let missing = if dt == f32::datum_type() && self.replaced.cast_to_scalar::<f32>()?.is_nan()
+        {
+            model.wire_node(
+                format!("{prefix}.is_nan"),
+                tract_onnx_opl::is_nan::is_nan(),
+                &[input],
+            )?
+        } else {
+            let replaced = (*self.replaced).clone().broadcast_into_rank(rank)?;
+            let replaced = model.add_const(format!("{prefix}.replaced"), replaced)?;
+            model.wire_node(
+                format!("{prefix}.is_missing"),
+                tract_core::ops::logic::equals(),
+                &[input, replaced],
+            )?
+        };
+        let imputed = (*self.imputed).clone().broadcast_into_rank(rank)?;
+        let imputed = model.add_const(format!("{prefix}.imputed"), imputed)?;
+        model.wire_node(prefix, tract_core::ops::logic::Iff, &[missing[0], imputed, input])
+    }
+}
+
+/// One-hot encode categories (integers or strings) on a new trailing axis. Unknown categories
+/// are encoded as all zeros if `zeros` is set, and fail the evaluation otherwise.
+#[derive(Debug, Clone, Hash)]
+pub struct OneHotEncoder {
+    pub categories: Arc<Tensor>,
+    pub zeros: bool,
+}
+
+impl Expansion for OneHotEncoder {
+    fn name(&self) -> Cow<str> {
+        "OneHotEncoder".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&outputs[0].datum_type, DatumType::F32)?;
+        s.equals(inputs[0].rank.bex() + 1, outputs[0].rank.bex())?;
+        s.given(&inputs[0].shape, move |s, shape| {
+            let mut shape = shape.clone();
+            shape.push(self.categories.len().to_dim());
+            s.equals(&outputs[0].shape, shape)
+        })?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let n = self.categories.len();
+        let dt = self.categories.datum_type();
+        let mut input = inputs[0];
+        if model.outlet_fact(input)?.datum_type != dt {
+            input = model.wire_node(
+                format!("{prefix}.cast"),
+                tract_core::ops::cast::cast(dt),
+                &[input],
+            )?[0];
+        }
+        let rank = model.outlet_fact(input)?.rank();
+        // unknown categories are mapped to an extra category, sliced out after one-hot encoding
+        let mut index = model.wire_node(
+            format!("{prefix}.index"),
+            tract_onnx_opl::ml::ReverseLookup::new(self.categories.clone(), n as i32)?,
+            &[input],
+        )?;
+        if !self.zeros {
+            index = model.wire_node(
+                format!("{prefix}.known"),
+                RejectUnknownCategory { unknown: n as i32 },
+                &index,
+            )?;
+        }
+        let one_hot = model.wire_node(
+            format!("{prefix}.one_hot"),
+            tract_core::ops::array::OneHot {
+                axis: rank,
+                dim: n + 1,
+                off: rctensor0(0f32),
+                on: rctensor0(1f32),
+            },
+            &index,
+        )?;
+        model.wire_node(prefix, tract_core::ops::array::Slice::new(rank, 0, n), &one_hot)
+    }
+}
+
+/// Pass category indices through, failing on the index of the unknown categories.
+#[derive(Debug, Clone, Hash)]
+pub struct RejectUnknownCategory {
+    pub unknown: i32,
+}
+
+impl Op for RejectUnknownCategory {
+    fn name(&self) -> Cow<str> {
+        "RejectUnknownCategory".into()
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for RejectUnknownCategory {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        if input.as_slice::<i32>()?.contains(&self.unknown) {
+            bail!("OneHotEncoder input contains an unknown category, and zeros is not set")
+        }
+        Ok(tvec!(input))
+    }
+}
+
+impl TypedOp for RejectUnknownCategory {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(inputs[0].without_value()))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn normalizer() -> TractResult<()> {
+        let input = tensor2(&[[3f32, 4.], [0., 0.]]);
+        let outputs =
+            run(super::normalizer, "Normalizer", vec![string("norm", "L2")], tvec!(input))?;
+        outputs[0].close_enough(&tensor2(&[[0.6f32, 0.8], [0., 0.]]), true)?;
+        let input = tensor2(&[[1f32, -3.], [2., 4.]]);
+        let outputs =
+            run(super::normalizer, "Normalizer", vec![string("norm", "L1")], tvec!(input))?;
+        outputs[0].close_enough(&tensor2(&[[0.25f32, -0.75], [1. / 3., 2. / 3.]]), true)?;
+        let input = tensor2(&[[2i64, 4], [0, 0]]);
+        let outputs =
+            run(super::normalizer, "Normalizer", vec![string("norm", "MAX")], tvec!(input))?;
+        outputs[0].close_enough(&tensor2(&[[0.5f32, 1.], [0., 0.]]), true)?;
+        Ok(())
+    }
+
+    #[test]
+    fn scaler() -> TractResult<()> {
+        let attributes = vec![floats("offset", &[1., 2.]), floats("scale", &[2., 0.5])];
+        let input = tensor2(&[[3f32, 4.], [1., 0.]]);
+        let outputs = run(super::scaler, "Scaler", attributes, tvec!(input))?;
+        assert_eq!(*outputs[0], tensor2(&[[4f32, 1.], [0., -1.]]));
+        Ok(())
+    }
+
+    #[test]
+    fn binarizer() -> TractResult<()> {
+        let input = tensor2(&[[0.5f32, 1., 2.]]);
+        let outputs =
+            run(super::binarizer, "Binarizer", vec![float("threshold", 1.)], tvec!(input))?;
+        assert_eq!(*outputs[0], tensor2(&[[0f32, 0., 1.]]));
+        Ok(())
+    }
+
+    #[test]
+    fn imputer() -> TractResult<()> {
+        let attributes = vec![
+            floats("imputed_value_floats", &[9., 8.]),
+            float("replaced_value_float", f32::NAN),
+        ];
+        let input = tensor2(&[[f32::NAN, 1.], [2., f32::NAN]]);
+        let outputs = run(super::imputer, "Imputer", attributes, tvec!(input))?;
+        assert_eq!(*outputs[0], tensor2(&[[9f32, 1.], [2., 8.]]));
+        let attributes = vec![ints("imputed_value_int64s", &[7]), int("replaced_value_int64", -1)];
+        let input = tensor2(&[[-1i64, 3], [4, -1]]);
+        let outputs = run(super::imputer, "Imputer", attributes, tvec!(input))?;
+        assert_eq!(*outputs[0], tensor2(&[[7i64, 3], [4, 7]]));
+        Ok(())
+    }
+
+    #[test]
+    fn one_hot_encoder() -> TractResult<()> {
+        let input = tensor1(&[3i64, 4]);
+        let outputs = run(
+            super::one_hot_encoder,
+            "OneHotEncoder",
+            vec![ints("cats_int64s", &[1, 3, 5])],
+            tvec!(input),
+        )?;
+        assert_eq!(*outputs[0], tensor2(&[[0f32, 1., 0.], [0., 0., 0.]]));
+        let input = tensor1(&["b".to_string()]);
+        let outputs = run(
+            super::one_hot_encoder,
+            "OneHotEncoder",
+            vec![strings("cats_strings", &["a", "b"])],
+            tvec!(input),
+        )?;
+        assert_eq!(*outputs[0], tensor2(&[[0f32, 1.]]));
+        Ok(())
+    }
+
+    #[test]
+    fn one_hot_encoder_rejects_unknown_categories() -> TractResult<()> {
+        let attributes = vec![ints("cats_int64s", &[1, 3, 5]), int("zeros", 0)];
+        let outputs = run(
+            super::one_hot_encoder,
+            "OneHotEncoder",
+            attributes.clone(),
+            tvec!(tensor1(&[3i64, 5])),
+        )?;
+        assert_eq!(*outputs[0], tensor2(&[[0f32, 1., 0.], [0., 0., 1.]]));
+        let result =
+            run(super::one_hot_encoder, "OneHotEncoder", attributes, tvec!(tensor1(&[4i64])));
+        assert!(result.is_err());
+        Ok(())
+    }
+}
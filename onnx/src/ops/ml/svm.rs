@@ -0,0 +1,199 @@
+use super::tree_ensemble_classifier::*;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+use tract_hir::internal::*;
+use tract_onnx_opl::ml::svm::*;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert("SVMClassifier", svm_classifier);
+    reg.insert("SVMRegressor", svm_regressor);
+}
+
+fn parse_kernel(node: &NodeProto) -> TractResult<Kernel> {
+    let kernel_type = KernelType::parse(node.get_attr_opt("kernel_type")?.unwrap_or("LINEAR"))?;
+    let params: Vec<f32> = node.get_attr_opt_vec("kernel_params")?.unwrap_or_default();
+    node.expect_attr("kernel_params", params.is_empty() || params.len() == 3, "3 values")?;
+    let param = |ix: usize, default: f32| params.get(ix).copied().unwrap_or(default);
+    Ok(Kernel { kernel_type, gamma: param(0, 0.0), coef0: param(1, 0.0), degree: param(2, 1.0) })
+}
+
+fn parse_support_vectors(node: &NodeProto, n_sv: usize) -> TractResult<Option<Arc<Tensor>>> {
+    if n_sv == 0 {
+        return Ok(None);
+    }
+    let svs: Vec<f32> = node.get_attr_vec("support_vectors")?;
+    node.expect_attr("support_vectors", svs.len() % n_sv == 0, || {
+        format!("a multiple of {n_sv} values")
+    })?;
+    let n_features = svs.len() / n_sv;
+    Ok(Some(tensor1(&svs).into_shape(&[n_sv, n_features])?.into_arc_tensor()))
+}
+
+fn svm_classifier(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let class_labels = parse_class_labels(node, "classlabels_ints")?;
+    let n_classes = class_labels.len();
+    let kernel = parse_kernel(node)?;
+    let vectors_per_class: TVec<usize> =
+        node.get_attr_opt_tvec("vectors_per_class")?.unwrap_or_default();
+    let n_sv = vectors_per_class.iter().sum();
+    let support_vectors = parse_support_vectors(node, n_sv)?;
+    let coefficients: Vec<f32> = node.get_attr_vec("coefficients")?;
+    let rho: Vec<f32> = node.get_attr_vec("rho")?;
+    let coefficients = if vectors_per_class.is_empty() {
+        node.expect_attr("coefficients", coefficients.len() % rho.len() == 0, || {
+            format!("a multiple of {} values", rho.len())
+        })?;
+        tensor1(&coefficients).into_shape(&[rho.len(), coefficients.len() / rho.len()])?
+    } else {
+        node.expect_attr("vectors_per_class", vectors_per_class.len() == n_classes, || {
+            format!("{n_classes} values (one per class)")
+        })?;
+        node.expect_attr("coefficients", coefficients.len() == (n_classes - 1) * n_sv, || {
+            format!("{} values", (n_classes - 1) * n_sv)
+        })?;
+        node.expect_attr("rho", rho.len() == n_classes * (n_classes - 1) / 2, "one per pair")?;
+        tensor1(&coefficients).into_shape(&[n_classes - 1, n_sv])?
+    };
+    let prob_a: Option<Vec<f32>> = node.get_attr_opt_vec("prob_a")?;
+    let prob_b: Option<Vec<f32>> = node.get_attr_opt_vec("prob_b")?;
+    let (prob_a, prob_b) = match (prob_a, prob_b) {
+        (Some(a), Some(b)) if !a.is_empty() && !vectors_per_class.is_empty() => {
+            node.expect_attr("prob_b", a.len() == b.len(), "same length as prob_a")?;
+            (Some(rctensor1(&a)), Some(rctensor1(&b)))
+        }
+        _ => (None, None),
+    };
+    let post_transform =
+        node.get_attr_opt("post_transform")?.map(parse_post_transform).transpose()?.unwrap_or(None);
+    let svm = tract_onnx_opl::ml::SvmClassifier {
+        kernel,
+        n_classes,
+        vectors_per_class,
+        support_vectors,
+        coefficients: coefficients.into_arc_tensor(),
+        rho: rctensor1(&rho),
+        prob_a,
+        prob_b,
+    };
+    Ok((expand(SVMClassifier { svm, class_labels, post_transform }), vec![]))
+}
+
+fn svm_regressor(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let kernel = parse_kernel(node)?;
+    let n_supports: usize = node.get_attr_opt("n_supports")?.unwrap_or(0);
+    let support_vectors = parse_support_vectors(node, n_supports)?;
+    let coefficients: Vec<f32> = node.get_attr_vec("coefficients")?;
+    if n_supports > 0 {
+        node.expect_attr("coefficients", coefficients.len() == n_supports, "one per support")?;
+    }
+    let rho: Vec<f32> = node.get_attr_vec("rho")?;
+    node.expect_attr("rho", rho.len() == 1, "a single value")?;
+    let one_class = node.get_attr_opt::<i64>("one_class")?.unwrap_or(0) != 0;
+    let post_transform =
+        node.get_attr_opt("post_transform")?.map(parse_post_transform).transpose()?.unwrap_or(None);
+    let svm = SvmRegressor {
+        kernel,
+        support_vectors,
+        coefficients: rctensor1(&coefficients),
+        rho: rho[0],
+        one_class,
+    };
+    Ok((expand(SVMRegressor { svm, post_transform }), vec![]))
+}
+
+#[derive(Debug, Clone)]
+pub struct SVMClassifier {
+    pub svm: SvmClassifier,
+    pub class_labels: Arc<Tensor>,
+    pub post_transform: Option<PostTransform>,
+}
+
+impl Expansion for SVMClassifier {
+    fn name(&self) -> Cow<str> {
+        "SVMClassifier".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 2)?;
+
+        s.equals(&outputs[0].datum_type, self.class_labels.datum_type())?;
+        s.equals(&outputs[1].datum_type, DatumType::F32)?;
+
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&outputs[0].rank, 1)?;
+        s.equals(&outputs[1].rank, 2)?;
+        s.equals(&outputs[0].shape[0], &inputs[0].shape[0])?;
+        s.equals(&outputs[1].shape[0], &inputs[0].shape[0])?;
+        s.equals(&outputs[1].shape[1], self.svm.n_scores().to_dim())?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input = super::wire_cast_to_f32(prefix, model, inputs[0])?;
+        let wires = model.wire_node(format!("{prefix}.svm"), self.svm.clone(), &[input])?;
+        let labels = wire_class_labels(prefix, model, &self.class_labels, wires[0])?;
+        let scores = wire_post_transform(prefix, model, self.post_transform, tvec!(wires[1]))?;
+        Ok(tvec!(labels, scores[0]))
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(2)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SVMRegressor {
+    pub svm: SvmRegressor,
+    pub post_transform: Option<PostTransform>,
+}
+
+impl Expansion for SVMRegressor {
+    fn name(&self) -> Cow<str> {
+        "SVMRegressor".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+
+        s.equals(&outputs[0].datum_type, DatumType::F32)?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.equals(&outputs[0].shape[0], &inputs[0].shape[0])?;
+        s.equals(&outputs[0].shape[1], 1.to_dim())?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input = super::wire_cast_to_f32(prefix, model, inputs[0])?;
+        let scores = model.wire_node(format!("{prefix}.svm"), self.svm.clone(), &[input])?;
+        wire_post_transform(prefix, model, self.post_transform, scores)
+    }
+}
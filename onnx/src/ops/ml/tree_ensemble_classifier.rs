@@ -74,7 +74,7 @@ fn parse_node_mode(s: &str) -> TractResult<Option<Cmp>> {
     }
 }
 
-fn get_vec_attr<'a, T>(node: &'a NodeProto, attr: &str, n: usize) -> TractResult<Vec<T>>
+pub fn get_vec_attr<'a, T>(node: &'a NodeProto, attr: &str, n: usize) -> TractResult<Vec<T>>
 where
     T: AttrTVecType<'a>,
 {
@@ -83,7 +83,7 @@ where
     Ok(vec)
 }
 
-fn get_vec_attr_opt<'a, T>(node: &'a NodeProto, attr: &str, n: usize) -> TractResult<Option<Vec<T>>>
+pub fn get_vec_attr_opt<'a, T>(node: &'a NodeProto, attr: &str, n: usize) -> TractResult<Option<Vec<T>>>
 where
     T: AttrTVecType<'a>,
 {
@@ -98,23 +98,29 @@ where
     }
 }
 
-fn parse_class_data(node: &NodeProto) -> TractResult<Arc<Tensor>> {
+pub fn parse_class_data(node: &NodeProto) -> TractResult<Arc<Tensor>> {
+    parse_class_labels(node, "classlabels_int64s")
+}
+
+/// Parse class labels, given as strings or as integers in `ints_attr` (LinearClassifier and
+/// SVMClassifier call it `classlabels_ints`).
+pub fn parse_class_labels(node: &NodeProto, ints_attr: &str) -> TractResult<Arc<Tensor>> {
     // parse n_classes from protobuf
-    let ints = node.get_attr_opt_slice::<i64>("classlabels_int64s")?;
+    let ints = node.get_attr_opt_slice::<i64>(ints_attr)?;
     let strs = node.get_attr_opt_tvec::<&str>("classlabels_strings")?;
     match (ints, strs) {
         (Some(n), None) => Ok(rctensor1(n)),
         (None, Some(n)) => Ok(rctensor1(&n.iter().map(|d| d.to_string()).collect::<Vec<_>>())),
         (None, None) => {
-            bail!("cannot find neither 'classlabels_int64s' not 'classlabels_strings'")
+            bail!("cannot find neither '{}' not 'classlabels_strings'", ints_attr)
         }
         (Some(_), Some(_)) => {
-            bail!("only one of 'classlabels_int64s' and 'classlabels_strings' can be set")
+            bail!("only one of '{}' and 'classlabels_strings' can be set", ints_attr)
         }
     }
 }
 
-fn parse_nodes_data(node: &NodeProto, is_classifier: bool) -> TractResult<TreeEnsemble> {
+pub fn parse_nodes_data(node: &NodeProto, is_classifier: bool) -> TractResult<TreeEnsemble> {
     // parse n_classes from protobuf
     let n_classes = if is_classifier {
         let ints = node.get_attr_opt_slice::<i64>("classlabels_int64s")?;
@@ -157,7 +163,7 @@ fn parse_nodes_data(node: &NodeProto, is_classifier: bool) -> TractResult<TreeEn
     let aggregate_fn = parse_aggregate(if is_classifier {
         "SUM"
     } else {
-        node.get_attr_opt("aggregate")?.unwrap_or("SUM")
+        node.get_attr_opt("aggregate_function")?.unwrap_or("SUM")
     })?;
 
     // parse leaf data from protobuf
@@ -304,23 +310,7 @@ impl Expansion for TreeEnsembleClassifier {
                 &[scores[0], base],
             )?;
         }
-        match self.post_transform {
-            None => (),
-            Some(PostTransform::Softmax) => {
-                scores = tract_hir::ops::nn::LayerSoftmax::new(1, false).wire(
-                    &format!("{prefix}.softmax"),
-                    model,
-                    &scores,
-                )?;
-            }
-            Some(PostTransform::Logistic) => {
-                scores = model.wire_node(
-                    &format!("{prefix}.logistic"),
-                    tract_core::ops::nn::sigmoid(),
-                    &scores,
-                )?;
-            }
-        }
+        scores = wire_post_transform(prefix, model, self.post_transform, scores)?;
         let processed_scores = scores.clone();
         if self.binary_result_layout {
             scores = model.wire_node(
@@ -350,19 +340,7 @@ impl Expansion for TreeEnsembleClassifier {
             tract_core::ops::change_axes::AxisOp::Rm(1),
             &winners,
         )?;
-        let casted = model.wire_node(
-            format!("{prefix}.casted"),
-            tract_core::ops::cast::cast(i32::datum_type()),
-            &reduced,
-        )?;
-        let labels = model.wire_node(
-            format!("{prefix}.labels"),
-            tract_onnx_opl::ml::DirectLookup::new(
-                self.class_labels.clone(),
-                Tensor::zero_dt(self.class_labels.datum_type(), &[])?.into_arc_tensor(),
-            )?,
-            &casted,
-        )?[0];
+        let labels = wire_class_labels(prefix, model, &self.class_labels, reduced[0])?;
         Ok(tvec!(labels, scores[0]))
     }
 
@@ -370,3 +348,48 @@ impl Expansion for TreeEnsembleClassifier {
         Ok(2)
     }
 }
+
+pub fn wire_post_transform(
+    prefix: &str,
+    model: &mut TypedModel,
+    post_transform: Option<PostTransform>,
+    scores: TVec<OutletId>,
+) -> TractResult<TVec<OutletId>> {
+    match post_transform {
+        None => Ok(scores),
+        Some(PostTransform::Softmax) => tract_hir::ops::nn::LayerSoftmax::new(1, false).wire(
+            &format!("{prefix}.softmax"),
+            model,
+            &scores,
+        ),
+        Some(PostTransform::Logistic) => model.wire_node(
+            format!("{prefix}.logistic"),
+            tract_core::ops::nn::sigmoid(),
+            &scores,
+        ),
+    }
+}
+
+/// Map class indices (of any integer type) to the actual class labels.
+pub fn wire_class_labels(
+    prefix: &str,
+    model: &mut TypedModel,
+    class_labels: &Arc<Tensor>,
+    class_ids: OutletId,
+) -> TractResult<OutletId> {
+    let casted = model.wire_node(
+        format!("{prefix}.casted"),
+        tract_core::ops::cast::cast(i32::datum_type()),
+        &[class_ids],
+    )?;
+    let fallback = if class_labels.datum_type() == String::datum_type() {
+        rctensor0(String::new())
+    } else {
+        Tensor::zero_dt(class_labels.datum_type(), &[])?.into_arc_tensor()
+    };
+    Ok(model.wire_node(
+        format!("{prefix}.labels"),
+        tract_onnx_opl::ml::DirectLookup::new(class_labels.clone(), fallback)?,
+        &casted,
+    )?[0])
+}
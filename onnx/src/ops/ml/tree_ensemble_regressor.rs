@@ -0,0 +1,122 @@
+use super::tree_ensemble_classifier::*;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+use tract_hir::internal::*;
+use tract_onnx_opl::ml::tree::*;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert("TreeEnsembleRegressor", tree_regressor);
+}
+
+fn tree_regressor(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let ensemble = parse_nodes_data(node, false)?;
+    let base_values =
+        get_vec_attr_opt::<f32>(node, "base_values", ensemble.n_classes())?.map(|t| rctensor1(&t));
+    let post_transform =
+        node.get_attr_opt("post_transform")?.map(parse_post_transform).transpose()?.unwrap_or(None);
+    Ok((expand(TreeEnsembleRegressor { ensemble, base_values, post_transform }), vec![]))
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct TreeEnsembleRegressor {
+    pub ensemble: TreeEnsemble,
+    pub base_values: Option<Arc<Tensor>>,
+    pub post_transform: Option<PostTransform>,
+}
+
+impl Expansion for TreeEnsembleRegressor {
+    fn name(&self) -> Cow<str> {
+        "TreeEnsembleRegressor".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+
+        s.equals(&outputs[0].datum_type, DatumType::F32)?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.equals(&outputs[0].shape[0], &inputs[0].shape[0])?;
+        s.equals(&outputs[0].shape[1], self.ensemble.n_classes().to_dim())?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        // the classifier op only computes the aggregated scores of the ensemble, with one
+        // "class" per regression target
+        let mut scores = model.wire_node(
+            format!("{prefix}.regressor"),
+            tract_onnx_opl::ml::tree_ensemble_classifier::TreeEnsembleClassifier {
+                ensemble: self.ensemble.clone(),
+            },
+            inputs,
+        )?;
+        if let Some(base_values) = self.base_values.as_deref() {
+            let base = base_values.clone().broadcast_into_rank(2)?.into_arc_tensor();
+            let base = model.add_const(prefix.to_string() + ".base", base)?;
+            scores = model.wire_node(
+                format!("{prefix}.base_values"),
+                tract_core::ops::math::add(),
+                &[scores[0], base],
+            )?;
+        }
+        wire_post_transform(prefix, model, self.post_transform, scores)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    // two stumps on the feature 0: x < 0.5 ? 10 : 20, and x <= 1.5 ? 1 : 2
+    fn stumps(aggregate: &str) -> Vec<crate::pb::AttributeProto> {
+        vec![
+            int("n_targets", 1),
+            string("aggregate_function", aggregate),
+            floats("base_values", &[100.]),
+            ints("nodes_treeids", &[0, 0, 0, 1, 1, 1]),
+            ints("nodes_nodeids", &[0, 1, 2, 0, 1, 2]),
+            ints("nodes_featureids", &[0, 0, 0, 0, 0, 0]),
+            floats("nodes_values", &[0.5, 0., 0., 1.5, 0., 0.]),
+            strings("nodes_modes", &["BRANCH_LT", "LEAF", "LEAF", "BRANCH_LEQ", "LEAF", "LEAF"]),
+            ints("nodes_truenodeids", &[1, 0, 0, 1, 0, 0]),
+            ints("nodes_falsenodeids", &[2, 0, 0, 2, 0, 0]),
+            ints("target_treeids", &[0, 0, 1, 1]),
+            ints("target_nodeids", &[1, 2, 1, 2]),
+            ints("target_ids", &[0, 0, 0, 0]),
+            floats("target_weights", &[10., 20., 1., 2.]),
+        ]
+    }
+
+    #[test]
+    fn sum() -> TractResult<()> {
+        let input = tensor2(&[[0f32], [1.], [2.]]);
+        let outputs = run(tree_regressor, "TreeEnsembleRegressor", stumps("SUM"), tvec!(input))?;
+        assert_eq!(*outputs[0], tensor2(&[[111f32], [121.], [122.]]));
+        Ok(())
+    }
+
+    #[test]
+    fn min_and_max() -> TractResult<()> {
+        let input = tensor2(&[[0f32], [1.], [2.]]);
+        let outputs = run(tree_regressor, "TreeEnsembleRegressor", stumps("MAX"), tvec!(input))?;
+        assert_eq!(*outputs[0], tensor2(&[[110f32], [120.], [120.]]));
+        let input = tensor2(&[[0f32], [1.], [2.]]);
+        let outputs = run(tree_regressor, "TreeEnsembleRegressor", stumps("MIN"), tvec!(input))?;
+        assert_eq!(*outputs[0], tensor2(&[[101f32], [101.], [102.]]));
+        Ok(())
+    }
+}
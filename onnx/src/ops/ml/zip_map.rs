@@ -0,0 +1,88 @@
+use super::tree_ensemble_classifier::parse_class_data;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+use tract_hir::internal::*;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert("ZipMap", zip_map);
+}
+
+fn zip_map(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    Ok((expand(ZipMap { class_labels: parse_class_data(node)? }), vec![]))
+}
+
+/// ZipMap turns a [N, C] score tensor into a sequence of maps from class label to score. tract
+/// has no sequence or map types, so the scores go through unchanged and the class labels are
+/// stored in the `onnx.zip_map.<node>.class_labels` model property, in column order.
+#[derive(Debug, Clone, Hash)]
+pub struct ZipMap {
+    pub class_labels: Arc<Tensor>,
+}
+
+impl Expansion for ZipMap {
+    fn name(&self) -> Cow<str> {
+        "ZipMap".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, f32::datum_type())?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&inputs[0].shape[1], self.class_labels.len().to_dim())?;
+        s.equals(&outputs[0].datum_type, &inputs[0].datum_type)?;
+        s.equals(&outputs[0].shape, &inputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        model
+            .properties
+            .insert(format!("onnx.zip_map.{prefix}.class_labels"), self.class_labels.clone());
+        Ok(tvec!(inputs[0]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn passes_scores_through() -> TractResult<()> {
+        let attributes = vec![strings("classlabels_strings", &["cat", "dog"])];
+        let input = tensor2(&[[0.2f32, 0.8], [0.6, 0.4]]);
+        let output = run(zip_map, "ZipMap", attributes, tvec!(input.clone()))?;
+        output[0].close_enough(&input, Approximation::Exact)
+    }
+
+    #[test]
+    fn class_labels_property() -> TractResult<()> {
+        let node = NodeProto {
+            name: "zipmap".to_string(),
+            attribute: vec![ints("classlabels_int64s", &[3, 7])],
+            ..NodeProto::default()
+        };
+        let op = ZipMap { class_labels: parse_class_data(&node)? };
+        let mut model = InferenceModel::default();
+        let scores = model.add_source("scores", f32::fact([1, 2]).into())?;
+        let output = model.wire_node("zipmap", expand(op), &[scores])?;
+        model.set_output_outlets(&output)?;
+        let model = model.into_optimized()?;
+        assert_eq!(*model.properties["onnx.zip_map.zipmap.class_labels"], tensor1(&[3i64, 7]));
+        Ok(())
+    }
+}
@@ -8,6 +8,7 @@ pub mod cumsum;
 mod d2s;
 mod einsum;
 mod fft;
+mod image;
 pub mod logic;
 mod math;
 mod ml;
@@ -19,6 +20,7 @@ mod random;
 pub mod rec;
 mod resize;
 mod s2d;
+mod text;
 
 pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("Constant", konst);
@@ -34,6 +36,7 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     cumsum::register_all_ops(reg);
     d2s::register_all_ops(reg);
     fft::register_all_ops(reg);
+    image::register_all_ops(reg);
     logic::register_all_ops(reg);
     math::register_all_ops(reg);
     ml::register_all_ops(reg);
@@ -42,6 +45,7 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     random::register_all_ops(reg);
     rec::register_all_ops(reg);
     s2d::register_all_ops(reg);
+    text::register_all_ops(reg);
 }
 
 fn konst(
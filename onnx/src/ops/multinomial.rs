@@ -29,6 +29,13 @@ impl Expansion for Multinomial {
         "Multinomial".into()
     }
 
+    fn validation(&self) -> Validation {
+        Validation::Random
+    }
+
+    fn is_stateless(&self) -> bool {
+        false
+    }
 
     fn rules<'r, 'p: 'r, 's: 'r>(
         &'s self,
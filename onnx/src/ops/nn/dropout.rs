@@ -1,90 +1,214 @@
-use crate::model::ParsingContext;
+use crate::model::{optional_inputs, ParsingContext};
 use crate::pb::*;
 use tract_hir::internal::*;
+use tract_hir::tract_core::ops::nn;
 
 pub fn dropout(
-    _ctx: &ParsingContext,
+    ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    Ok((Box::new(Dropout::new(node.output.len() == 2)), vec![]))
+    let mut inputs = optional_inputs(node).skip(1);
+    let training_mode =
+        node.input.get(2).filter(|s| !s.is_empty()).and_then(|name| constant_bool(ctx, name));
+    let op = Dropout {
+        output_mask: node.output.len() == 2,
+        ratio: node.get_attr_opt("ratio")?.unwrap_or(0.5),
+        ratio_input: inputs.next().unwrap(),
+        training_mode_input: inputs.next().unwrap(),
+        training_mode,
+        seed: node.get_attr_opt::<i64>("seed")?.map(|s| s as u64),
+        stochastic: ctx.framework.stochastic_dropout,
+    };
+    Ok((expand(op), vec![]))
 }
 
-#[derive(Debug, Clone, new, Default, Hash)]
+/// Value of a boolean initializer or Constant output of the graph being parsed.
+fn constant_bool(ctx: &ParsingContext, name: &str) -> Option<bool> {
+    let graph = ctx.parent_graphs.last()?;
+    let proto = graph.initializer.iter().find(|t| t.name == name).or_else(|| {
+        graph
+            .node
+            .iter()
+            .filter(|n| n.op_type == "Constant" && n.output.iter().any(|o| o == name))
+            .find_map(|n| n.attribute.iter().find(|a| a.name == "value"))
+            .and_then(|a| a.t.as_ref())
+    })?;
+    let tensor = Tensor::try_from(proto).ok()?;
+    tensor.cast_to_scalar::<bool>().ok()
+}
+
+/// ONNX Dropout. It is an identity unless `training_mode` is set, or the
+/// model was loaded with `Onnx::with_stochastic_dropout` for Monte-Carlo
+/// dropout, in which case it becomes a randomly masking core Dropout.
+#[derive(Debug, Clone)]
 pub struct Dropout {
     output_mask: bool,
+    ratio: f32,
+    ratio_input: Option<usize>,
+    training_mode_input: Option<usize>,
+    /// `training_mode`, when it is a constant of the graph.
+    training_mode: Option<bool>,
+    pub seed: Option<u64>,
+    stochastic: bool,
 }
 
+impl Dropout {
+    fn may_train(&self) -> bool {
+        self.stochastic || (self.training_mode_input.is_some() && self.training_mode != Some(false))
+    }
+}
 
-
-impl Op for Dropout {
+impl Expansion for Dropout {
     fn name(&self) -> Cow<str> {
         "Dropout".into()
     }
 
-    op_as_typed_op!();
-}
+    fn validation(&self) -> Validation {
+        if self.may_train() {
+            Validation::Random
+        } else {
+            Validation::Accurate
+        }
+    }
 
-impl EvalOp for Dropout {
     fn is_stateless(&self) -> bool {
-        true
+        !self.may_train()
     }
 
-    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
-        if self.output_mask {
-            let input = args_1!(inputs);
-            let mask = tract_ndarray::ArrayD::from_elem(input.shape(), true);
-            Ok(tvec!(input, mask.into_tvalue()))
-        } else {
-            Ok(inputs)
-        }
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(1 + self.output_mask as usize)
     }
-}
 
-impl InferenceRulesOp for Dropout {
     fn rules<'r, 'p: 'r, 's: 'r>(
         &'s self,
         s: &mut Solver<'r>,
         inputs: &'p [TensorProxy],
         outputs: &'p [TensorProxy],
     ) -> InferenceResult {
-        check_input_arity(inputs, 1)?;
+        check_input_arity(
+            inputs,
+            1 + self.ratio_input.is_some() as usize + self.training_mode_input.is_some() as usize,
+        )?;
         check_output_arity(outputs, 1 + self.output_mask as usize)?;
         s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
         s.equals(&inputs[0].shape, &outputs[0].shape)?;
-        if outputs.len() == 2 {
+        if let Some(ratio) = self.ratio_input {
+            s.equals(&inputs[ratio].rank, 0)?;
+        }
+        if let Some(training_mode) = self.training_mode_input {
+            s.equals(&inputs[training_mode].datum_type, bool::datum_type())?;
+            s.equals(&inputs[training_mode].rank, 0)?;
+        }
+        if self.output_mask {
             s.equals(&outputs[1].datum_type, bool::datum_type())?;
             s.equals(&inputs[0].shape, &outputs[1].shape)?;
         }
         Ok(())
     }
 
-    fn nboutputs(&self) -> TractResult<usize> {
-        Ok(1 + self.output_mask as usize)
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let konst = |input: usize| -> TractResult<Arc<Tensor>> {
+            let fact = model.outlet_fact(inputs[input])?;
+            fact.konst.clone().with_context(|| {
+                format!("Dropout expects constant ratio and training_mode, got {fact:?}")
+            })
+        };
+        let ratio = if let Some(ratio) = self.ratio_input {
+            konst(ratio)?.cast_to_scalar::<f32>()?
+        } else {
+            self.ratio
+        };
+        let training_mode = if let Some(training_mode) = self.training_mode_input {
+            konst(training_mode)?.cast_to_scalar::<bool>()?
+        } else {
+            false
+        };
+        let op = nn::Dropout {
+            ratio,
+            training: training_mode || self.stochastic,
+            output_mask: self.output_mask,
+            seed: self.seed,
+        };
+        model.wire_node(prefix, op, &[inputs[0]])
     }
-
-    as_op!();
-    to_typed!();
 }
 
-impl TypedOp for Dropout {
-    as_op!();
-    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
-        Ok(tvec!(inputs[0].clone()))
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pb::tensor_proto::DataType;
 
-    fn declutter(
-        &self,
-        model: &TypedModel,
-        node: &TypedNode,
-    ) -> TractResult<Option<TypedModelPatch>> {
-        if node.outputs.len() == 1 || node.outputs[1].successors.len() == 0 {
-            Ok(Some(TypedModelPatch::single_unary_op(
-                model,
-                node,
-                tract_hir::ops::identity::Identity,
-            )?))
-        } else {
-            Ok(None)
+    fn bool_proto(name: &str, value: bool) -> TensorProto {
+        TensorProto {
+            name: name.to_string(),
+            data_type: DataType::Bool as i32,
+            int32_data: vec![value as i32],
+            ..TensorProto::default()
         }
     }
+
+    fn is_stateless(training_mode: &str, graph: GraphProto) -> TractResult<bool> {
+        let onnx = crate::onnx();
+        let proto = ModelProto::default();
+        let ctx = ParsingContext {
+            onnx_operator_set_version: 13,
+            framework: &onnx,
+            model: &proto,
+            parent_graphs: vec![&graph],
+            model_path: None,
+            symbol_table: SymbolTable::default(),
+        };
+        let node = NodeProto {
+            op_type: "Dropout".to_string(),
+            input: vec!["x".to_string(), String::new(), training_mode.to_string()],
+            output: vec!["y".to_string()],
+            ..NodeProto::default()
+        };
+        let (op, _) = dropout(&ctx, &node)?;
+        assert_eq!(op.is_stateless(), op.validation() == Validation::Accurate);
+        Ok(op.is_stateless())
+    }
+
+    #[test]
+    fn no_training_mode() -> TractResult<()> {
+        assert!(is_stateless("", GraphProto::default())?);
+        Ok(())
+    }
+
+    #[test]
+    fn training_mode_initializer_false() -> TractResult<()> {
+        let graph =
+            GraphProto { initializer: vec![bool_proto("t", false)], ..GraphProto::default() };
+        assert!(is_stateless("t", graph)?);
+        Ok(())
+    }
+
+    #[test]
+    fn training_mode_constant_true() -> TractResult<()> {
+        let value = AttributeProto {
+            name: "value".to_string(),
+            t: Some(bool_proto("", true)),
+            ..AttributeProto::default()
+        };
+        let constant = NodeProto {
+            op_type: "Constant".to_string(),
+            output: vec!["t".to_string()],
+            attribute: vec![value],
+            ..NodeProto::default()
+        };
+        let graph = GraphProto { node: vec![constant], ..GraphProto::default() };
+        assert!(!is_stateless("t", graph)?);
+        Ok(())
+    }
+
+    #[test]
+    fn training_mode_input() -> TractResult<()> {
+        assert!(!is_stateless("t", GraphProto::default())?);
+        Ok(())
+    }
 }
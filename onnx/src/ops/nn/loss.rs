@@ -0,0 +1,336 @@
+use crate::model::{optional_inputs, ParsingContext};
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_hir::ops::cast::cast;
+use tract_hir::tract_core::ops::array::{Gather, GatherElements, MultiBroadcastTo};
+use tract_hir::tract_core::ops::{logic, math, nn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Reduction {
+    None,
+    Sum,
+    Mean,
+}
+
+fn reduction(node: &NodeProto) -> TractResult<Reduction> {
+    let reduction = node.get_attr_opt("reduction")?.unwrap_or("mean");
+    node.check_value(
+        "reduction",
+        match reduction {
+            "none" => Ok(Reduction::None),
+            "sum" => Ok(Reduction::Sum),
+            "mean" => Ok(Reduction::Mean),
+            _ => Err(reduction),
+        },
+    )
+}
+
+pub fn negative_log_likelihood_loss(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let op = NegativeLogLikelihoodLoss {
+        reduction: reduction(node)?,
+        ignore_index: node.get_attr_opt("ignore_index")?,
+        weight_input: optional_inputs(node).nth(2).unwrap(),
+    };
+    Ok((expand(op), vec![]))
+}
+
+pub fn softmax_cross_entropy_loss(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let op = SoftmaxCrossEntropyLoss {
+        reduction: reduction(node)?,
+        ignore_index: node.get_attr_opt("ignore_index")?,
+        weight_input: optional_inputs(node).nth(2).unwrap(),
+        output_log_prob: node.output.len() == 2,
+    };
+    Ok((expand(op), vec![]))
+}
+
+fn loss_rules<'r, 'p: 'r, 's: 'r>(
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+    reduction: Reduction,
+    weight_input: Option<usize>,
+) -> InferenceResult {
+    check_input_arity(inputs, 2 + weight_input.is_some() as usize)?;
+    s.equals(&outputs[0].datum_type, &inputs[0].datum_type)?;
+    s.equals(inputs[0].rank.bex() - 1, inputs[1].rank.bex())?;
+    s.equals(&inputs[1].shape[0], &inputs[0].shape[0])?;
+    s.given(&inputs[1].rank, move |s, rank| {
+        for axis in 1..rank as usize {
+            s.equals(&inputs[1].shape[axis], &inputs[0].shape[axis + 1])?;
+        }
+        Ok(())
+    })?;
+    if let Some(weight) = weight_input {
+        s.equals(&inputs[weight].datum_type, &inputs[0].datum_type)?;
+        s.equals(&inputs[weight].rank, 1)?;
+        s.equals(&inputs[weight].shape[0], &inputs[0].shape[1])?;
+    }
+    if reduction == Reduction::None {
+        s.equals(&outputs[0].shape, &inputs[1].shape)?;
+    } else {
+        s.equals(&outputs[0].rank, 0)?;
+    }
+    Ok(())
+}
+
+/// Wire the negative log likelihood of `log_prob` (N, C, d1, ...) at the
+/// `target` classes (N, d1, ...), scaled by the class weights.
+///
+/// Targets matching `ignore_index` do not contribute, neither to the loss
+/// nor to the total weight the mean reduction divides by.
+fn wire_nll(
+    prefix: &str,
+    model: &mut TypedModel,
+    reduction: Reduction,
+    ignore_index: Option<i64>,
+    log_prob: OutletId,
+    target: OutletId,
+    weight: Option<OutletId>,
+) -> TractResult<TVec<OutletId>> {
+    let fact = model.outlet_fact(log_prob)?.clone();
+    let target_dt = model.outlet_fact(target)?.datum_type;
+    let rank = fact.rank();
+    ensure!(rank >= 2, "Loss expects inputs of rank 2 or more, got {:?}", fact);
+    let scalar = |model: &mut TypedModel, name: &str, value: Tensor| -> TractResult<OutletId> {
+        model.add_const(format!("{prefix}.{name}"), value.broadcast_into_rank(rank)?)
+    };
+
+    // targets, with the class axis, and ignored ones pointing at class 0
+    let mut index = model.wire_node(format!("{prefix}.target"), AxisOp::Add(1), &[target])?[0];
+    let kept = if let Some(ignore_index) = ignore_index {
+        let ignored = tensor0(ignore_index).cast_to_dt(target_dt)?.into_owned();
+        let ignored = scalar(model, "ignore_index", ignored)?;
+        let mask =
+            model.wire_node(format!("{prefix}.ignored"), logic::equals(), &[index, ignored])?[0];
+        let kept = model.wire_node(format!("{prefix}.kept"), logic::not(), &[mask])?[0];
+        let zero = scalar(model, "first_class", tensor0(0).cast_to_dt(target_dt)?.into_owned())?;
+        index = model.wire_node(format!("{prefix}.index"), logic::Iff, &[kept, index, zero])?[0];
+        Some(kept)
+    } else {
+        None
+    };
+
+    let picked =
+        model.wire_node(format!("{prefix}.pick"), GatherElements::new(1), &[log_prob, index])?[0];
+    let mut loss = model.wire_node(format!("{prefix}.neg"), math::neg(), &[picked])?[0];
+    let sample_weight = if let Some(weight) = weight {
+        let weight =
+            model.wire_node(format!("{prefix}.sample_weight"), Gather::new(0), &[weight, index])?
+                [0];
+        loss = model.wire_node(format!("{prefix}.weighted"), math::mul(), &[loss, weight])?[0];
+        Some(weight)
+    } else {
+        None
+    };
+    let zero = scalar(model, "zero", tensor0(0f32).cast_to_dt(fact.datum_type)?.into_owned())?;
+    if let Some(kept) = kept {
+        // select rather than multiply, so ignored infinite log probs do not turn into NaN
+        loss = model.wire_node(format!("{prefix}.masked"), logic::Iff, &[kept, loss, zero])?[0];
+    }
+    let loss = model.wire_node(format!("{prefix}.loss"), AxisOp::Rm(1), &[loss])?[0];
+    if reduction == Reduction::None {
+        return Ok(tvec!(loss));
+    }
+
+    let sum_all = |model: &mut TypedModel, name: &str, wire: OutletId| -> TractResult<OutletId> {
+        let rank = model.outlet_fact(wire)?.rank();
+        let mut wire = model.wire_node(
+            format!("{prefix}.{name}"),
+            nn::Reduce::new((0..rank).collect(), nn::Reducer::Sum),
+            &[wire],
+        )?[0];
+        for axis in (0..rank).rev() {
+            wire =
+                model.wire_node(format!("{prefix}.{name}_rm_{axis}"), AxisOp::Rm(axis), &[wire])?
+                    [0];
+        }
+        Ok(wire)
+    };
+    let sum = sum_all(model, "sum", loss)?;
+    if reduction == Reduction::Sum {
+        return Ok(tvec!(sum));
+    }
+    let weight = match (sample_weight, kept) {
+        (Some(weight), Some(kept)) => {
+            model.wire_node(format!("{prefix}.kept_weight"), logic::Iff, &[kept, weight, zero])?[0]
+        }
+        (Some(weight), None) => weight,
+        (None, Some(kept)) => {
+            model.wire_node(format!("{prefix}.kept_weight"), cast(fact.datum_type), &[kept])?[0]
+        }
+        (None, None) => {
+            let one =
+                scalar(model, "one", tensor0(1f32).cast_to_dt(fact.datum_type)?.into_owned())?;
+            let shape = model.outlet_fact(picked)?.shape.clone();
+            model.wire_node(format!("{prefix}.ones"), MultiBroadcastTo::new(shape), &[one])?[0]
+        }
+    };
+    let total_weight = sum_all(model, "total_weight", weight)?;
+    model.wire_node(format!("{prefix}.mean"), math::div(), &[sum, total_weight])
+}
+
+/// Log softmax over the class axis, as `x - max - ln(sum(exp(x - max)))`, which
+/// stays finite where the softmax underflows.
+fn wire_log_softmax(prefix: &str, model: &mut TypedModel, x: OutletId) -> TractResult<OutletId> {
+    let max = model.wire_node(
+        format!("{prefix}.max"),
+        nn::Reduce::new(tvec!(1), nn::Reducer::Max),
+        &[x],
+    )?[0];
+    let shifted = model.wire_node(format!("{prefix}.shifted"), math::sub(), &[x, max])?[0];
+    let exp = model.wire_node(format!("{prefix}.exp"), math::exp(), &[shifted])?[0];
+    let sum = model.wire_node(
+        format!("{prefix}.sum_exp"),
+        nn::Reduce::new(tvec!(1), nn::Reducer::Sum),
+        &[exp],
+    )?[0];
+    let log_sum = model.wire_node(format!("{prefix}.log_sum_exp"), math::ln(), &[sum])?[0];
+    Ok(model.wire_node(format!("{prefix}.log_prob"), math::sub(), &[shifted, log_sum])?[0])
+}
+
+/// ONNX NegativeLogLikelihoodLoss, expanded to a gather of the target
+/// classes.
+#[derive(Debug, Clone, Hash)]
+pub struct NegativeLogLikelihoodLoss {
+    reduction: Reduction,
+    ignore_index: Option<i64>,
+    weight_input: Option<usize>,
+}
+
+impl Expansion for NegativeLogLikelihoodLoss {
+    fn name(&self) -> Cow<str> {
+        "NegativeLogLikelihoodLoss".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_output_arity(outputs, 1)?;
+        loss_rules(s, inputs, outputs, self.reduction, self.weight_input)
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        wire_nll(
+            prefix,
+            model,
+            self.reduction,
+            self.ignore_index,
+            inputs[0],
+            inputs[1],
+            self.weight_input.map(|w| inputs[w]),
+        )
+    }
+}
+
+/// ONNX SoftmaxCrossEntropyLoss: the negative log likelihood of the log
+/// softmax of the scores over the class axis, which is the optional second
+/// output.
+#[derive(Debug, Clone, Hash)]
+pub struct SoftmaxCrossEntropyLoss {
+    reduction: Reduction,
+    ignore_index: Option<i64>,
+    weight_input: Option<usize>,
+    output_log_prob: bool,
+}
+
+impl Expansion for SoftmaxCrossEntropyLoss {
+    fn name(&self) -> Cow<str> {
+        "SoftmaxCrossEntropyLoss".into()
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(1 + self.output_log_prob as usize)
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_output_arity(outputs, 1 + self.output_log_prob as usize)?;
+        if self.output_log_prob {
+            s.equals(&outputs[1].datum_type, &inputs[0].datum_type)?;
+            s.equals(&outputs[1].shape, &inputs[0].shape)?;
+        }
+        loss_rules(s, inputs, outputs, self.reduction, self.weight_input)
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let log_prob = wire_log_softmax(prefix, model, inputs[0])?;
+        let mut outputs = wire_nll(
+            prefix,
+            model,
+            self.reduction,
+            self.ignore_index,
+            log_prob,
+            inputs[1],
+            self.weight_input.map(|w| inputs[w]),
+        )?;
+        if self.output_log_prob {
+            outputs.push(log_prob);
+        }
+        Ok(outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(op: Box<dyn InferenceOp>, x: Tensor, target: Tensor) -> TractResult<TVec<TValue>> {
+        let mut model = InferenceModel::default();
+        let s = model.add_source("x", InferenceFact::from(&x).without_value())?;
+        let t = model.add_source("target", InferenceFact::from(&target).without_value())?;
+        let loss = model.wire_node("loss", op, &[s, t])?;
+        model.set_output_outlets(&loss)?;
+        model.into_optimized()?.into_runnable()?.run(tvec!(x.into_tvalue(), target.into_tvalue()))
+    }
+
+    #[test]
+    fn sce_large_logits() -> TractResult<()> {
+        let op = SoftmaxCrossEntropyLoss {
+            reduction: Reduction::None,
+            ignore_index: None,
+            weight_input: None,
+            output_log_prob: true,
+        };
+        let x = tensor2(&[[1000f32, 0., -1000.], [-1000., 0., 1000.]]);
+        let outputs = run(expand(op), x, tensor1(&[0i64, 1]))?;
+        outputs[0].close_enough(&tensor1(&[0f32, 1000.]), Approximation::Close)?;
+        let expected = tensor2(&[[0f32, -1000., -2000.], [-2000., -1000., 0.]]);
+        outputs[1].close_enough(&expected, Approximation::Close)
+    }
+
+    #[test]
+    fn nll_ignores_infinite_log_probs() -> TractResult<()> {
+        let op = NegativeLogLikelihoodLoss {
+            reduction: Reduction::Mean,
+            ignore_index: Some(-1),
+            weight_input: None,
+        };
+        let x = tensor2(&[[-0.5f32, f32::NEG_INFINITY], [f32::NEG_INFINITY, -1.5], [-2., -3.]]);
+        let outputs = run(expand(op), x, tensor1(&[0i64, 1, -1]))?;
+        outputs[0].close_enough(&tensor0(1f32), Approximation::Close)
+    }
+}
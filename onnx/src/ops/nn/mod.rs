@@ -10,6 +10,7 @@ mod batch_norm;
 mod conv_transpose;
 mod dropout;
 mod instance_norm;
+mod loss;
 mod lrn;
 mod reduce;
 
@@ -39,6 +40,7 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("ConvTranspose", conv_transpose::conv_transpose);
     reg.insert("Dropout", dropout::dropout);
     reg.insert("Elu", elu);
+    reg.insert("Gelu", gelu);
     reg.insert("GlobalAveragePool", |_, _| Ok((expand(ops::nn::GlobalAvgPool), vec![])));
     reg.insert("GlobalLpPool", global_lp_pool);
     reg.insert("GlobalMaxPool", |_, _| Ok((expand(ops::nn::GlobalMaxPool), vec![])));
@@ -49,6 +51,7 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("LogSoftmax", layer_log_soft_max);
     reg.insert("LRN", lrn::lrn);
     reg.insert("MaxPool", max_pool);
+    reg.insert("NegativeLogLikelihoodLoss", loss::negative_log_likelihood_loss);
     reg.insert("ParametricSoftplus", parametric_softplus);
     reg.insert("QLinearConv", conv_qlinear);
     reg.insert("PRelu", |_, _| Ok((expand(Prelu), vec![])));
@@ -70,6 +73,7 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("Sigmoid", |_, _| Ok((ops::nn::sigmoid().into_hir(), vec![])));
     reg.insert("HardSwish", |_, _| Ok((ops::nn::hard_swish().into_hir(), vec![])));
     reg.insert("Softmax", layer_soft_max);
+    reg.insert("SoftmaxCrossEntropyLoss", loss::softmax_cross_entropy_loss);
     reg.insert("Softplus", |_, _| Ok((expand(ops::activations::Softplus), vec![])));
     reg.insert("Softsign", |_, _| Ok((expand(ops::activations::Softsign), vec![])));
 }
@@ -233,6 +237,22 @@ pub fn elu(
     Ok((expand(ops::activations::Elu(alpha)), vec![]))
 }
 
+pub fn gelu(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let approximate = node.get_attr_opt("approximate")?.unwrap_or("none");
+    let approximate = node.check_value(
+        "approximate",
+        match approximate {
+            "none" => Ok(false),
+            "tanh" => Ok(true),
+            _ => Err(approximate),
+        },
+    )?;
+    Ok((expand(ops::activations::Gelu::new(approximate)), vec![]))
+}
+
 pub fn global_lp_pool(
     _ctx: &ParsingContext,
     node: &NodeProto,
@@ -405,3 +425,39 @@ pub fn thresholded_relu(
     let alpha = node.get_attr_opt("alpha")?.unwrap_or(1.);
     Ok((expand(ops::activations::ThresholdRelu(alpha)), vec![]))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tract_hir::tract_core::ops::element_wise::ElementWiseOp;
+
+    fn run_gelu(approximate: bool) -> TractResult<(TypedModel, TValue)> {
+        let mut model = InferenceModel::default();
+        let x = model.add_source("x", InferenceFact::dt_shape(f32::datum_type(), [6usize]))?;
+        let y = model.wire_node("gelu", expand(ops::activations::Gelu::new(approximate)), &[x])?;
+        model.set_output_outlets(&y)?;
+        let model = model.into_optimized()?;
+        let x = tensor1(&[-2f32, -1., 0., 0.5, 1., 2.]);
+        let y = model.clone().into_runnable()?.run(tvec!(x.into_tvalue()))?.remove(0);
+        Ok((model, y))
+    }
+
+    #[test]
+    fn gelu_exact() -> TractResult<()> {
+        let (_, y) = run_gelu(false)?;
+        let expected =
+            tensor1(&[-0.045_500_3f32, -0.158_655_3, 0., 0.345_731_2, 0.841_344_7, 1.954_499_7]);
+        y.close_enough(&expected, Approximation::Approximate)
+    }
+
+    #[test]
+    fn gelu_tanh_is_gelu_approximate() -> TractResult<()> {
+        let (model, y) = run_gelu(true)?;
+        let expected =
+            tensor1(&[-0.045_402_3f32, -0.158_808, 0., 0.345_714, 0.841_192, 1.954_597_7]);
+        y.close_enough(&expected, Approximation::Close)?;
+        let op = model.nodes().iter().find_map(|n| n.op_as::<ElementWiseOp>()).unwrap();
+        assert!(op.0.is::<tract_hir::tract_core::ops::nn::GeluApproximate>());
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@ use tract_hir::internal::*;
 use tract_onnx_opl::random::Dist;
 
 pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert("Bernoulli", bernoulli);
     reg.insert("RandomUniform", random);
     reg.insert("RandomUniformLike", random);
     reg.insert("RandomNormal", random);
@@ -150,3 +151,70 @@ impl Expansion for RandomLike {
         )
     }
 }
+
+pub fn bernoulli(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let dt: Option<DatumType> = node.get_attr_opt("dtype")?;
+    let seed = node.get_attr_opt::<f32>("seed")?;
+    Ok((expand(Bernoulli { dt, seed }), vec![]))
+}
+
+#[derive(Debug, Clone)]
+struct Bernoulli {
+    dt: Option<DatumType>,
+    seed: Option<f32>,
+}
+
+impl Expansion for Bernoulli {
+    fn name(&self) -> Cow<str> {
+        "Bernoulli".into()
+    }
+
+    fn validation(&self) -> Validation {
+        Validation::Random
+    }
+
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+
+        s.equals(&outputs[0].shape, &inputs[0].shape)?;
+        if let Some(dt) = self.dt {
+            s.equals(&outputs[0].datum_type, dt)?;
+        } else {
+            s.equals(&outputs[0].datum_type, &inputs[0].datum_type)?;
+        }
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let datum_type = match self.dt {
+            Some(dt) => dt,
+            None => model.outlet_fact(inputs[0])?.datum_type,
+        };
+        model.wire_node(
+            prefix,
+            tract_onnx_opl::bernoulli::Bernoulli {
+                datum_type,
+                seed: self.seed.map(|f| f.to_bits() as u64),
+            },
+            inputs,
+        )
+    }
+}
@@ -1,210 +1,130 @@
 use crate::model::ParsingContext;
 use crate::pb::*;
-use std::hash::Hash;
 use tract_hir::internal::*;
+use tract_hir::tract_core::ops::resize::{self, CoordTransformer, Interpolator, Nearest};
 
 pub fn resize(
-    _ctx: &ParsingContext,
+    ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    let coord_transformer =
-        match node.get_attr_opt("coordinate_transformation_mode")?.unwrap_or("half_pixel") {
-            "align_corners" => CoordTransformer::AlignCorners,
-            "half_pixel" => CoordTransformer::HalfPixel,
-            "asymmetric" => CoordTransformer::Asymmetric,
-            s => todo!("coordinate_transformation_mode: {}", s),
-        };
+    // before opset 11, inputs are (X, scales) and nearest picks the floor of x_out / scale
+    let legacy = ctx.onnx_operator_set_version < 11;
+    let coord_transformer = node
+        .get_attr_opt("coordinate_transformation_mode")?
+        .unwrap_or(if legacy { "asymmetric" } else { "half_pixel" })
+        .parse()?;
     let interpolator = match node.get_attr_opt("mode")?.unwrap_or("nearest") {
         "nearest" => Interpolator::Nearest,
-        "linear" => Interpolator::Linear,
-        s => todo!("mode: {}", s),
+        "linear" | "bilinear" | "trilinear" => Interpolator::Linear,
+        "cubic" | "bicubic" => {
+            Interpolator::Cubic { a: node.get_attr_opt("cubic_coeff_a")?.unwrap_or(-0.75) }
+        }
+        s => bail!("Unsupported resize mode: {}", s),
     };
-    let nearest = match node.get_attr_opt("nearest_mode")?.unwrap_or("round_prefer_floor") {
-        "floor" => Nearest::Floor,
-        "ceil" => Nearest::Ceil,
-        "round_prefer_floor" => Nearest::RoundPreferFloor,
-        "round_prefer_ceil" => Nearest::RoundPreferCeil,
-        s => todo!("nearest_mode: {}", s),
+    let nearest: Nearest = node
+        .get_attr_opt("nearest_mode")?
+        .unwrap_or(if legacy { "floor" } else { "round_prefer_floor" })
+        .parse()?;
+    let template = resize::Resize {
+        coord_transformer,
+        interpolator,
+        nearest,
+        antialias: node.get_attr_opt::<i64>("antialias")?.unwrap_or(0) != 0,
+        exclude_outside: node.get_attr_opt::<i64>("exclude_outside")?.unwrap_or(0) != 0,
+        extrapolation_value: node.get_attr_opt("extrapolation_value")?.unwrap_or(0.0),
+        keep_aspect_ratio_policy: node
+            .get_attr_opt("keep_aspect_ratio_policy")?
+            .unwrap_or("stretch")
+            .parse()?,
+        ..resize::Resize::default()
     };
-    let mut options = crate::model::optional_inputs(node).skip(2);
-    Ok((
-        Box::new(Resize {
+    let axes = node.get_attr_opt_vec("axes")?;
+    let op = if legacy {
+        Resize {
+            template,
+            axes,
+            optional_roi_input: None,
+            optional_scales_input: Some(1),
+            optional_sizes_input: None,
+        }
+    } else {
+        let mut options = crate::model::optional_inputs(node).skip(1);
+        Resize {
+            template,
+            axes,
+            optional_roi_input: options.next().unwrap(),
             optional_scales_input: options.next().unwrap(),
             optional_sizes_input: options.next().unwrap(),
-            coord_transformer,
-            interpolator,
-            nearest,
-        }),
-        vec![],
-    ))
-}
-
-#[derive(Clone, Debug, Hash)]
-enum CoordTransformer {
-    HalfPixel,
-    AlignCorners,
-    Asymmetric,
-}
-
-impl CoordTransformer {
-    fn transform(&self, x_out: usize, scale: f32, len_in: usize, len_out: usize) -> f32 {
-        match self {
-            CoordTransformer::HalfPixel => (x_out as f32 + 0.5) / scale - 0.5,
-            CoordTransformer::AlignCorners => {
-                (x_out as f32 * (len_in as f32 - 1.0)) / (len_out as f32 - 1.0)
-            }
-            CoordTransformer::Asymmetric => (x_out as f32) / scale,
         }
-    }
-}
-
-#[derive(Clone, Debug, Hash)]
-enum Interpolator {
-    Linear,
-    Nearest,
-}
-
-impl Interpolator {
-    fn interpolate(&self, y_left: f32, y_right: f32, x_ratio: f32, nearest_mode: Nearest) -> f32 {
-        match self {
-            Interpolator::Linear => y_left * (1.0 - x_ratio) + y_right * x_ratio,
-            Interpolator::Nearest => match nearest_mode {
-                Nearest::Floor => y_left,
-                Nearest::Ceil => y_right,
-                Nearest::RoundPreferFloor => {
-                    if x_ratio <= 0.5 {
-                        y_left
-                    } else {
-                        y_right
-                    }
-                }
-                Nearest::RoundPreferCeil => {
-                    if x_ratio < 0.5 {
-                        y_left
-                    } else {
-                        y_right
-                    }
-                }
-            },
-        }
-    }
-}
-
-#[derive(Clone, Copy, Debug, Hash)]
-enum Nearest {
-    Floor,
-    Ceil,
-    RoundPreferFloor,
-    RoundPreferCeil,
+    };
+    Ok((expand(op), vec![]))
 }
 
-#[derive(Clone, new, Debug, Hash)]
+#[derive(Clone, Debug)]
 struct Resize {
-    coord_transformer: CoordTransformer,
-    interpolator: Interpolator,
-    nearest: Nearest,
+    template: resize::Resize,
+    axes: Option<Vec<i64>>,
+    optional_roi_input: Option<usize>,
     optional_scales_input: Option<usize>,
     optional_sizes_input: Option<usize>,
 }
 
-impl Op for Resize {
-    fn name(&self) -> Cow<str> {
-        "Resize".into()
-    }
-
-    op_as_typed_op!();
-}
-
 impl Resize {
-    fn compute_output_shape<D: DimLike>(
+    /// Core op for an input rank and the values of the optional inputs.
+    /// Empty scales or sizes stand for missing ones.
+    fn core_op(
         &self,
-        input_shape: &[D],
-        input_scale: Option<&Tensor>,
-        input_sizes: Option<&Tensor>,
-    ) -> TractResult<TVec<D>> {
-        if let Some(scale) = input_scale {
-            if scale.len() == input_shape.len() {
-                let mut shape = tvec!();
-                for (i, s) in
-                    input_shape.iter().zip(scale.cast_to::<f32>()?.as_slice::<f32>()?.iter())
-                {
-                    if s.round() == *s {
-                        shape.push(i.clone() * (*s as usize));
-                    } else if let Ok(i) = i.to_usize() {
-                        shape.push(((i as f32 * s) as usize).into());
-                    } else {
-                        bail!("Can not compute output shape. inputs are {input_shape:?} and scale {scale:?}")
-                    }
-                }
-                return Ok(shape);
-            }
+        rank: usize,
+        roi: Option<&Tensor>,
+        scales: Option<&Tensor>,
+        sizes: Option<&Tensor>,
+    ) -> TractResult<resize::Resize> {
+        let axes: TVec<usize> = if let Some(axes) = &self.axes {
+            axes.iter().map(|&a| (if a < 0 { a + rank as i64 } else { a }) as usize).collect()
+        } else {
+            (0..rank).collect()
+        };
+        let scales = scales.filter(|s| s.len() > 0);
+        let sizes = sizes.filter(|s| s.len() > 0);
+        let mut op = resize::Resize { axes, ..self.template.clone() };
+        if let Some(scales) = scales {
+            ensure!(
+                scales.len() == op.axes.len(),
+                "Expected {} scales, got {:?}",
+                op.axes.len(),
+                scales
+            );
+            op.scales = Some(scales.cast_to::<f32>()?.as_slice::<f32>()?.into());
+        } else if let Some(sizes) = sizes {
+            ensure!(
+                sizes.len() == op.axes.len(),
+                "Expected {} sizes, got {:?}",
+                op.axes.len(),
+                sizes
+            );
+            op.sizes = Some(sizes.cast_to::<TDim>()?.as_slice::<TDim>()?.into());
+        } else {
+            bail!("Resize needs either scales or sizes");
         }
-        if let Some(sizes) = input_sizes {
-            if sizes.len() == input_shape.len() {
-                return sizes
-                    .cast_to::<TDim>()?
-                    .as_slice::<TDim>()?
-                    .iter()
-                    .map(|i| i.try_into())
-                    .collect();
-            }
+        if let Some(roi) = roi.filter(|_| op.coord_transformer == CoordTransformer::TfCropAndResize)
+        {
+            ensure!(
+                roi.len() == 2 * op.axes.len(),
+                "Expected {} roi values, got {:?}",
+                2 * op.axes.len(),
+                roi
+            );
+            op.roi = Some(roi.cast_to::<f32>()?.as_slice::<f32>()?.into());
         }
-        bail!(
-            "Neither shape not scale makes sense: input_shape: {:?}, scale: {:?}, sizes: {:?}",
-            input_shape,
-            input_scale,
-            input_sizes,
-        );
+        Ok(op)
     }
 }
 
-impl EvalOp for Resize {
-    fn is_stateless(&self) -> bool {
-        true
-    }
-
-    fn eval(&self, mut inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
-        let scales = self.optional_scales_input.and_then(|ix| inputs.get(ix));
-        let sizes = self.optional_sizes_input.and_then(|ix| inputs.get(ix));
-        let output_shape = self.compute_output_shape(
-            inputs[0].shape(),
-            scales.map(|t| &**t),
-            sizes.map(|t| &**t),
-        )?;
-        let mut data = inputs.remove(0).into_tensor().into_array::<f32>()?;
-        for axis in 0..data.ndim() {
-            #[allow(clippy::comparison_chain)]
-            if output_shape[axis] == data.shape()[axis] {
-                continue;
-            } else if output_shape[axis] > data.shape()[axis] {
-                let scale = output_shape[axis] as f32 / data.shape()[axis] as f32;
-                let mut new_shape: TVec<usize> = data.shape().into();
-                new_shape[axis] = output_shape[axis];
-                data = tract_ndarray::ArrayD::from_shape_fn(&*new_shape, |co_o| -> f32 {
-                    let x_out = co_o[axis];
-                    let x_in = self.coord_transformer.transform(
-                        x_out,
-                        scale,
-                        data.shape()[axis],
-                        new_shape[axis],
-                    );
-                    let mut co_i = co_o;
-                    let x_left = (x_in as usize).clamp(0, data.shape()[axis] - 1);
-                    co_i[axis] = x_left;
-                    let y_left = data[&co_i];
-                    let x_right = (x_left + 1).min(data.shape()[axis] - 1);
-                    co_i[axis] = x_right;
-                    let y_right = data[&co_i];
-                    let x_frac = x_in - x_left as f32;
-                    self.interpolator.interpolate(y_left, y_right, x_frac, self.nearest)
-                })
-            }
-        }
-        Ok(tvec!(data.into_tvalue()))
+impl Expansion for Resize {
+    fn name(&self) -> Cow<str> {
+        "Resize".into()
     }
-}
 
-impl InferenceRulesOp for Resize {
     fn rules<'r, 'p: 'r, 's: 'r>(
         &'s self,
         s: &mut Solver<'r>,
@@ -214,90 +134,51 @@ impl InferenceRulesOp for Resize {
         check_output_arity(outputs, 1)?;
         s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
         s.equals(&inputs[0].rank, &outputs[0].rank)?;
-        if inputs.len() == 3 && self.optional_scales_input == Some(2) {
-            rules_with_scales(self, s, inputs, outputs)
-        } else if inputs.len() == 3 && self.optional_sizes_input == Some(2) {
-            rules_with_sizes(self, s, inputs, outputs)
-        } else {
-            // bogus 4 inputs case
-            s.given_2(
-                &inputs[0].rank,
-                &inputs[self.optional_scales_input.unwrap()].shape,
-                move |s, input_rank, scale_shape| {
-                    if scale_shape.len() == 0 || scale_shape[0] != input_rank.to_dim() {
-                        rules_with_sizes(self, s, inputs, outputs)
-                    } else {
-                        rules_with_scales(self, s, inputs, outputs)
-                    }
-                },
-            )
-        }
-    }
-
-    as_op!();
-    to_typed!();
-}
-
-fn rules_with_scales<'r, 'p: 'r, 's: 'r>(
-    op: &'s Resize,
-    s: &mut Solver<'r>,
-    inputs: &'p [TensorProxy],
-    outputs: &'p [TensorProxy],
-) -> InferenceResult {
-    let scales = &inputs[op.optional_scales_input.unwrap()];
-    s.equals(&scales.datum_type, f32::datum_type())?;
-    s.equals(&scales.rank, 1)?;
-    s.equals(&scales.shape[0], inputs[0].rank.bex().to_dim())?;
-    s.given_2(
-        &inputs[0].shape,
-        &inputs[op.optional_scales_input.unwrap()].value,
-        move |s, input_shape, scales| {
-            let output_size = op.compute_output_shape(&input_shape, Some(scales.as_ref()), None)?;
-            let rank = input_shape.len();
-            for i in 0..rank {
-                s.equals(&outputs[0].shape[i], output_size[i].to_dim())?;
-            }
-            Ok(())
-        },
-    )
-}
-
-fn rules_with_sizes<'r, 'p: 'r, 's: 'r>(
-    op: &'s Resize,
-    s: &mut Solver<'r>,
-    inputs: &'p [TensorProxy],
-    outputs: &'p [TensorProxy],
-) -> InferenceResult {
-    let sizes = &inputs[op.optional_sizes_input.unwrap()];
-    s.equals(&sizes.rank, 1)?;
-    s.equals(&sizes.shape[0], inputs[0].rank.bex().to_dim())?;
-    s.given(&inputs[0].rank, move |s, rank| {
-        for i in 0..(rank as usize) {
-            s.equals(&outputs[0].shape[i], sizes.value[i].bex().to_dim())?;
+        for ix in [self.optional_scales_input, self.optional_sizes_input].into_iter().flatten() {
+            s.given_2(&inputs[0].shape, &inputs[ix].value, move |s, shape, value| {
+                if value.len() == 0 {
+                    return Ok(());
+                }
+                let is_scales = Some(ix) == self.optional_scales_input;
+                let op = self.core_op(
+                    shape.len(),
+                    None,
+                    Some(&*value).filter(|_| is_scales),
+                    Some(&*value).filter(|_| !is_scales),
+                )?;
+                let output_shape = op.output_shape(&shape)?;
+                s.equals(&outputs[0].shape, ShapeFactoid::from(output_shape))
+            })?;
         }
         Ok(())
-    })
-}
-
-impl TypedOp for Resize {
-    as_op!();
-
-    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
-        let scales = self.optional_scales_input.and_then(|ix| inputs.get(ix));
-        let sizes = self.optional_sizes_input.and_then(|ix| inputs.get(ix));
-        let output_shape = self.compute_output_shape(
-            &inputs[0].shape,
-            scales.and_then(|f| f.konst.as_deref()),
-            sizes.and_then(|f| f.konst.as_deref()),
-        )?;
-        Ok(tvec!(inputs[0].datum_type.fact(&output_shape)))
     }
 
-    fn declutter(
+    fn wire(
         &self,
-        _model: &TypedModel,
-        _node: &TypedNode,
-    ) -> TractResult<Option<TypedModelPatch>> {
-        Ok(None)
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let konst = |ix: Option<usize>| -> TractResult<Option<Arc<Tensor>>> {
+            let Some(ix) = ix else { return Ok(None) };
+            let fact = model.outlet_fact(inputs[ix])?;
+            Ok(Some(fact.konst.clone().with_context(|| {
+                format!("Resize expects constant scales, sizes and roi, got {fact:?}")
+            })?))
+        };
+        let roi = if self.template.coord_transformer == CoordTransformer::TfCropAndResize {
+            konst(self.optional_roi_input)?
+        } else {
+            None
+        };
+        let scales = konst(self.optional_scales_input)?;
+        let sizes = konst(self.optional_sizes_input)?;
+        let op = self.core_op(
+            model.outlet_fact(inputs[0])?.rank(),
+            roi.as_deref(),
+            scales.as_deref(),
+            sizes.as_deref(),
+        )?;
+        model.wire_node(prefix, op, &[inputs[0]])
     }
 }
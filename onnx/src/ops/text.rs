@@ -0,0 +1,321 @@
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_onnx_opl::text;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert("RegexFullMatch", regex_full_match);
+    reg.insert("StringConcat", |_, _| Ok((expand(StringConcat), vec![])));
+    reg.insert("StringNormalizer", string_normalizer);
+    reg.insert("StringSplit", string_split);
+    reg.insert("TfIdfVectorizer", tfidf_vectorizer);
+    // com.microsoft
+    reg.insert("Tokenizer", tokenizer);
+}
+
+fn regex_full_match(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let pattern: String = node.get_attr("pattern")?;
+    Ok((expand(RegexFullMatch(text::RegexFullMatch::new(&pattern)?)), vec![]))
+}
+
+#[derive(Debug, Clone)]
+struct RegexFullMatch(text::RegexFullMatch);
+
+impl Expansion for RegexFullMatch {
+    fn name(&self) -> Cow<str> {
+        "RegexFullMatch".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, String::datum_type())?;
+        s.equals(&outputs[0].datum_type, bool::datum_type())?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        model.wire_node(prefix, self.0.clone(), inputs)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StringConcat;
+
+impl Expansion for StringConcat {
+    fn name(&self) -> Cow<str> {
+        "StringConcat".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 2)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, String::datum_type())?;
+        s.equals(&inputs[1].datum_type, String::datum_type())?;
+        s.equals(&outputs[0].datum_type, String::datum_type())?;
+        s.given_2(&inputs[0].shape, &inputs[1].shape, move |s, a, b| {
+            let shape = tract_core::broadcast::multi_broadcast(&[&a, &b])
+                .with_context(|| format!("Failed to broadcast {a:?} and {b:?}"))?;
+            s.equals(&outputs[0].shape, ShapeFactoid::from(shape))
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        model.wire_node(prefix, text::StringConcat, inputs)
+    }
+}
+
+fn string_normalizer(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let op = text::StringNormalizer {
+        case_change_action: node.get_attr_opt("case_change_action")?.unwrap_or("NONE").parse()?,
+        is_case_sensitive: node.get_attr_opt::<i64>("is_case_sensitive")?.unwrap_or(0) != 0,
+        stopwords: node.get_attr_opt_vec("stopwords")?.unwrap_or_default(),
+        output_len: ctx.symbol_table.new_with_prefix("n"),
+    };
+    Ok((expand(StringNormalizer(op)), vec![]))
+}
+
+#[derive(Debug, Clone)]
+struct StringNormalizer(text::StringNormalizer);
+
+impl Expansion for StringNormalizer {
+    fn name(&self) -> Cow<str> {
+        "StringNormalizer".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, String::datum_type())?;
+        s.equals(&outputs[0].datum_type, String::datum_type())?;
+        s.equals(&inputs[0].rank, &outputs[0].rank)?;
+        s.given(&inputs[0].rank, move |s, rank| {
+            if rank == 2 {
+                s.equals(&outputs[0].shape[0], 1.to_dim())?;
+            }
+            let last = rank as usize - 1;
+            if self.0.stopwords.is_empty() {
+                s.equals(&inputs[0].shape[last], &outputs[0].shape[last])
+            } else {
+                s.equals(&outputs[0].shape[last], self.0.output_len.to_dim())
+            }
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        model.wire_node(prefix, self.0.clone(), inputs)
+    }
+}
+
+fn string_split(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let delimiter: Option<String> = node.get_attr_opt("delimiter")?;
+    let maxsplit: Option<i64> = node.get_attr_opt("maxsplit")?;
+    let op = text::StringSplit {
+        delimiter: delimiter.filter(|d| !d.is_empty()),
+        maxsplit: maxsplit.filter(|m| *m >= 0).map(|m| m as usize),
+        output_len: ctx.symbol_table.new_with_prefix("n"),
+    };
+    Ok((expand(StringSplit(op)), vec![]))
+}
+
+#[derive(Debug, Clone)]
+struct StringSplit(text::StringSplit);
+
+impl Expansion for StringSplit {
+    fn name(&self) -> Cow<str> {
+        "StringSplit".into()
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(2)
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 2)?;
+        s.equals(&inputs[0].datum_type, String::datum_type())?;
+        s.equals(&outputs[0].datum_type, String::datum_type())?;
+        s.equals(&outputs[1].datum_type, i64::datum_type())?;
+        s.equals(&inputs[0].shape, &outputs[1].shape)?;
+        s.given(&inputs[0].shape, move |s, shape| {
+            let mut shape = shape.to_vec();
+            shape.push(self.0.output_len.to_dim());
+            s.equals(&outputs[0].shape, ShapeFactoid::from(shape))
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        model.wire_node(prefix, self.0.clone(), inputs)
+    }
+}
+
+fn tfidf_vectorizer(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let usizes = |name: &str| -> TractResult<Vec<usize>> {
+        Ok(node.get_attr_vec::<i64>(name)?.into_iter().map(|i| i as usize).collect())
+    };
+    let pool = if let Some(strings) = node.get_attr_opt_vec::<String>("pool_strings")? {
+        rctensor1(&strings)
+    } else {
+        rctensor1(&node.get_attr_vec::<i64>("pool_int64s")?)
+    };
+    let op = text::TfIdfVectorizer::new(
+        node.get_attr::<String>("mode")?.parse()?,
+        node.get_attr::<i64>("min_gram_length")? as usize,
+        node.get_attr::<i64>("max_gram_length")? as usize,
+        node.get_attr::<i64>("max_skip_count")? as usize,
+        usizes("ngram_counts")?,
+        usizes("ngram_indexes")?,
+        pool,
+        node.get_attr_opt_vec("weights")?,
+    )?;
+    Ok((expand(TfIdfVectorizer(op)), vec![]))
+}
+
+#[derive(Debug, Clone)]
+struct TfIdfVectorizer(text::TfIdfVectorizer);
+
+impl Expansion for TfIdfVectorizer {
+    fn name(&self) -> Cow<str> {
+        "TfIdfVectorizer".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&outputs[0].datum_type, f32::datum_type())?;
+        s.equals(&inputs[0].rank, &outputs[0].rank)?;
+        s.given(&inputs[0].rank, move |s, rank| {
+            if rank == 2 {
+                s.equals(&inputs[0].shape[0], &outputs[0].shape[0])?;
+            }
+            s.equals(&outputs[0].shape[rank as usize - 1], self.0.output_size().to_dim())
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let mut input = inputs[0];
+        // int32 inputs are matched against the int64 pool
+        let dt = model.outlet_fact(input)?.datum_type;
+        if dt != String::datum_type() && dt != i64::datum_type() {
+            input = model.wire_node(
+                format!("{prefix}.cast"),
+                tract_core::ops::cast::cast(i64::datum_type()),
+                &[input],
+            )?[0];
+        }
+        model.wire_node(prefix, self.0.clone(), &[input])
+    }
+}
+
+fn tokenizer(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let op = text::Tokenizer::new(
+        node.get_attr::<i64>("mark")? != 0,
+        node.get_attr("pad_value")?,
+        node.get_attr::<i64>("mincharnum")? as usize,
+        node.get_attr_opt_vec("separators")?,
+        node.get_attr_opt("tokenexp")?,
+        ctx.symbol_table.new_with_prefix("n"),
+    )?;
+    Ok((expand(Tokenizer(op)), vec![]))
+}
+
+#[derive(Debug, Clone)]
+struct Tokenizer(text::Tokenizer);
+
+impl Expansion for Tokenizer {
+    fn name(&self) -> Cow<str> {
+        "Tokenizer".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, String::datum_type())?;
+        s.equals(&outputs[0].datum_type, String::datum_type())?;
+        s.given(&inputs[0].shape, move |s, shape| {
+            let mut shape = shape.to_vec();
+            shape.push(self.0.output_len.to_dim());
+            s.equals(&outputs[0].shape, ShapeFactoid::from(shape))
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        model.wire_node(prefix, self.0.clone(), inputs)
+    }
+}
@@ -2,6 +2,7 @@ use crate::model::{ParsingContext, TensorPlusPath};
 use crate::pb::tensor_proto::DataType;
 use crate::pb::*;
 use prost::Message;
+use std::cell::RefCell;
 use std::convert::{TryFrom, TryInto};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -56,43 +57,60 @@ pub fn translate_inference_fact(
 }
 
 #[cfg(target_family="wasm")]
-fn extend_bytes_from_path(buf: &mut Vec<u8>, p: impl AsRef<Path>) -> TractResult<()> {
+fn storage_from_path(p: impl AsRef<Path>) -> TractResult<Arc<dyn TensorStorage>> {
     use std::io::BufRead;
 
     let file = fs::File::open(p)?;
     let file_size = file.metadata()?.len() as usize;
-    if buf.capacity() < file_size + buf.len() {
-        buf.reserve(file_size);
-    }
+    let mut buf = Vec::with_capacity(file_size);
 
     let mut reader = std::io::BufReader::new(file);
     while reader.fill_buf()?.len() > 0 {
         buf.extend_from_slice(reader.buffer());
         reader.consume(reader.buffer().len());
     }
-    Ok(())
+    Ok(Arc::new(buf))
 }
 
 #[cfg(all(any(windows, unix), not(target_os = "emscripten")))]
-fn extend_bytes_from_path(buf: &mut Vec<u8>, p: impl AsRef<Path>) -> TractResult<()> {
+fn storage_from_path(p: impl AsRef<Path>) -> TractResult<Arc<dyn TensorStorage>> {
     let file = fs::File::open(p)?;
     let mmap = unsafe { memmap2::Mmap::map(&file)? };
-    buf.extend_from_slice(&mmap);
-    Ok(())
+    Ok(Arc::new(mmap))
 }
 
-fn get_external_resources(t: &TensorProto, path: &str) -> TractResult<Vec<u8>> {
-    let mut tensor_data: Vec<u8> = Vec::new();
-    trace!("number of external file needed for this tensor: {}", t.external_data.len());
-    for external_data in t.external_data.iter()
-    // according to the onnx format, it is possible to have multiple files for one tensor
-    {
-        let p = PathBuf::from(format!("{}/{}", path, external_data.value));
-        trace!("external file detected: {:?}", p);
-        extend_bytes_from_path(&mut tensor_data, p)?;
-        trace!("external file loaded");
+/// Locate the external data of a tensor: the file is mapped in memory (where
+/// supported) so constants can borrow their data from it.
+fn get_external_resources(
+    t: &TensorProto,
+    path: &str,
+    opened: &RefCell<HashMap<String, Arc<dyn TensorStorage>>>,
+) -> TractResult<(Arc<dyn TensorStorage>, std::ops::Range<usize>)> {
+    let mut location = None;
+    let mut offset = 0;
+    let mut length = None;
+    for entry in &t.external_data {
+        match &*entry.key {
+            "location" => location = Some(&entry.value),
+            "offset" => offset = entry.value.parse()?,
+            "length" => length = Some(entry.value.parse::<usize>()?),
+            _ => (),
+        }
     }
-    Ok(tensor_data)
+    let location = location.context("No location in tensor external data")?;
+    let p = PathBuf::from(format!("{path}/{location}"));
+    trace!("external file detected: {:?}", p);
+    let cached = opened.borrow().get(location).cloned();
+    let storage = if let Some(storage) = cached {
+        storage
+    } else {
+        let storage = storage_from_path(&p).with_context(|| format!("Opening {p:?}"))?;
+        opened.borrow_mut().insert(location.to_string(), storage.clone());
+        storage
+    };
+    let end = length.map(|l| offset + l).unwrap_or_else(|| storage.as_bytes().len());
+    ensure!(end <= storage.as_bytes().len(), "External data {:?} is too short", p);
+    Ok((storage, offset..end))
 }
 
 fn create_tensor(shape: Vec<usize>, dt: DatumType, data: &[u8]) -> TractResult<Tensor> {
@@ -118,7 +136,7 @@ fn create_tensor(shape: Vec<usize>, dt: DatumType, data: &[u8]) -> TractResult<T
     }
 }
 
-fn common_tryfrom(t: &TensorProto, path: Option<&str>) -> TractResult<Tensor> {
+fn common_tryfrom(t: &TensorProto, external: Option<&TensorPlusPath>) -> TractResult<Tensor> {
     let dt = DataType::from_i32(t.data_type).unwrap().try_into()?;
     let shape: Vec<usize> = t.dims.iter().map(|&i| i as usize).collect();
     // detect if the tensor is rather in an external file than inside the onnx file directly
@@ -126,10 +144,27 @@ fn common_tryfrom(t: &TensorProto, path: Option<&str>) -> TractResult<Tensor> {
     if t.raw_data.len() > 0 {
         create_tensor(shape, dt, &t.raw_data)
     } else if is_external {
-        if let Some(model_path) = path {
-            // external files will be loaded and fed to the tensor if necessary
-            let external_data = get_external_resources(t, model_path)?;
-            create_tensor(shape, dt, &external_data)
+        if let Some(external) = external {
+            let (storage, range) =
+                get_external_resources(t, external.model_path, external.external_data)?;
+            if dt.is_copy() {
+                // without a length key, the range runs to the end of the file
+                let len = shape.iter().product::<usize>() * dt.size_of();
+                ensure!(
+                    len <= range.len(),
+                    "External data for {} is too short: {} bytes, expected {}",
+                    t.name,
+                    range.len(),
+                    len
+                );
+                if dt == DatumType::Bool {
+                    create_tensor(shape, dt, &storage.as_bytes()[range.start..range.start + len])
+                } else {
+                    unsafe { Tensor::from_storage_dt(dt, &shape, storage, range.start) }
+                }
+            } else {
+                create_tensor(shape, dt, &storage.as_bytes()[range])
+            }
         } else {
             bail!("no model path was specified in the parsing context, yet external data was detected. aborting");
         }
@@ -181,7 +216,7 @@ fn common_tryfrom(t: &TensorProto, path: Option<&str>) -> TractResult<Tensor> {
 impl TryFrom<TensorPlusPath<'_>> for Tensor {
     type Error = TractError;
     fn try_from(st: TensorPlusPath) -> TractResult<Tensor> {
-        common_tryfrom(st.tensor, Some(st.model_path))
+        common_tryfrom(st.tensor, Some(&st))
     }
 }
 
@@ -209,3 +244,68 @@ pub fn proto_from_reader<R: ::std::io::Read>(mut r: R) -> TractResult<TensorProt
 pub fn from_reader<R: ::std::io::Read>(r: R) -> TractResult<Tensor> {
     proto_from_reader(r)?.try_into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn external(name: &str, offset: usize, length: Option<usize>) -> TensorProto {
+        let entry = |key: &str, value: String| StringStringEntryProto { key: key.into(), value };
+        let mut external_data =
+            vec![entry("location", "weights.bin".into()), entry("offset", offset.to_string())];
+        if let Some(length) = length {
+            external_data.push(entry("length", length.to_string()));
+        }
+        TensorProto {
+            name: name.into(),
+            dims: vec![2],
+            data_type: DataType::Float as i32,
+            data_location: Some(1),
+            external_data,
+            ..TensorProto::default()
+        }
+    }
+
+    fn weights() -> TractResult<tempfile::TempDir> {
+        let dir = tempfile::tempdir()?;
+        let bytes: Vec<u8> = [1f32, 2., 3., 4.].iter().flat_map(|x| x.to_le_bytes()).collect();
+        fs::write(dir.path().join("weights.bin"), bytes)?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn external_data_file_is_opened_once() -> TractResult<()> {
+        let dir = weights()?;
+        let path = dir.path().to_str().unwrap();
+        let external_data = RefCell::default();
+        let (a, b) = (external("a", 0, Some(8)), external("b", 8, Some(8)));
+        let a: Tensor =
+            TensorPlusPath { tensor: &a, model_path: path, external_data: &external_data }
+                .try_into()?;
+        let b: Tensor =
+            TensorPlusPath { tensor: &b, model_path: path, external_data: &external_data }
+                .try_into()?;
+        assert_eq!(a, tensor1(&[1f32, 2.]));
+        assert_eq!(b, tensor1(&[3f32, 4.]));
+        assert_eq!(external_data.borrow().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn external_data_without_length() -> TractResult<()> {
+        let dir = weights()?;
+        let path = dir.path().to_str().unwrap();
+        let external_data = RefCell::default();
+        let a = external("a", 0, None);
+        let a: Tensor =
+            TensorPlusPath { tensor: &a, model_path: path, external_data: &external_data }
+                .try_into()?;
+        assert_eq!(a, tensor1(&[1f32, 2.]));
+        let b = external("b", 12, None);
+        let b: TractResult<Tensor> =
+            TensorPlusPath { tensor: &b, model_path: path, external_data: &external_data }
+                .try_into();
+        assert!(b.is_err());
+        Ok(())
+    }
+}
@@ -3,7 +3,9 @@ use tract_nnef::internal::*;
 mod concat;
 mod deconv_delay;
 mod delay;
+mod mask;
 mod pad;
+mod reduce;
 mod slice;
 
 pub use tract_nnef;
@@ -17,7 +19,9 @@ pub mod prelude {
 pub mod ops {
     pub use super::deconv_delay::DeconvDelay;
     pub use super::delay::{ Delay, DelayState };
+    pub use super::mask::PulseMask;
     pub use super::pad::PulsePad;
+    pub use super::reduce::PulsedAxisReduce;
     pub use super::slice::PulsedAxisSlice;
 }
 
@@ -41,6 +45,8 @@ pub fn tract_nnef_registry() -> Registry {
     let mut reg = Registry::new("tract_pulse");
     reg.aliases.push("pulse".into());
     delay::register(&mut reg);
+    mask::register(&mut reg);
     pad::register(&mut reg);
+    reduce::register(&mut reg);
     reg
 }
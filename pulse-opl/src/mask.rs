@@ -0,0 +1,167 @@
+use tract_core::ndarray::*;
+use tract_nnef::internal::*;
+use tract_nnef::ser::tdim;
+use tract_nnef::tract_core::ops::OpStateFreeze;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_primitive(
+        "tract_pulse_mask",
+        &[
+            TypeName::Scalar.tensor().named("input"),
+            TypeName::Integer.named("axis"),
+            TypeName::Integer.named("begin"),
+            TypeName::Integer.named("end"),
+            TypeName::Scalar.named("value"),
+            TypeName::Integer.named("overlap"),
+        ],
+        &[("output", TypeName::Scalar.tensor())],
+        deser,
+    );
+    registry.register_dumper(TypeId::of::<PulseMask>(), ser)
+}
+
+fn ser(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<PulseMask>().unwrap();
+    let wire = ast.mapping[&node.inputs[0]].clone();
+    let params = vec![
+        ("axis", numeric(op.axis)),
+        ("begin", numeric(op.begin)),
+        ("end", tdim(&op.end)),
+        ("value", numeric(op.value.cast_to_scalar::<f32>()?)),
+        ("overlap", numeric(op.overlap)),
+    ];
+    Ok(Some(invocation("tract_pulse_mask", &[wire], &params)))
+}
+
+fn deser(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let wire = invocation.named_arg_as(builder, "input")?;
+    let axis = invocation.named_arg_as(builder, "axis")?;
+    let begin = invocation.named_arg_as(builder, "begin")?;
+    let overlap = invocation.named_arg_as(builder, "overlap")?;
+    let value = invocation.named_arg_as::<f32>(builder, "value")?;
+    let end = builder.allowing_new_symbols(|builder| invocation.named_arg_as(builder, "end"))?;
+    let dt = builder.model.outlet_fact(wire)?.datum_type;
+    let value = tensor0(value).cast_to_dt(dt)?.into_owned();
+    builder.wire(PulseMask { axis, begin, end, value, overlap }, &[wire])
+}
+
+/// Overwrite the frames of a pulsed stream that lie outside of the
+/// `begin..end` valid range with a constant value.
+///
+/// Frames are counted in the op input stream referential, `overlap` frames
+/// being shared between consecutive pulses (as produced by a Delay).
+#[derive(Debug, Clone, Hash)]
+pub struct PulseMask {
+    pub axis: usize,
+    pub begin: usize,
+    pub end: TDim,
+    pub value: Tensor,
+    pub overlap: usize,
+}
+
+impl Op for PulseMask {
+    fn name(&self) -> Cow<str> {
+        "PulseMask".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "axis: {} begin: {} end: {} value: {:?} overlap: {}",
+            self.axis, self.begin, self.end, self.value, self.overlap
+        )])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for PulseMask {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn state(
+        &self,
+        _session: &mut SessionState,
+        _node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        Ok(Some(Box::<PulseMaskOpState>::default()))
+    }
+}
+
+impl TypedOp for PulseMask {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(self.value.datum_type() == inputs[0].datum_type);
+        Ok(tvec!(inputs[0].without_value()))
+    }
+
+    as_op!();
+}
+
+#[derive(Debug, Clone, Default, Hash)]
+struct PulseMaskOpState {
+    current_pos: usize,
+}
+
+impl OpState for PulseMaskOpState {
+    fn eval(
+        &mut self,
+        session: &mut SessionState,
+        op: &dyn Op,
+        inputs: TVec<TValue>,
+    ) -> TractResult<TVec<TValue>> {
+        let mut input = args_1!(inputs).into_tensor();
+        let op = op.downcast_ref::<PulseMask>().ok_or_else(|| format_err!("Wrong Op type"))?;
+        let pulse = input.shape()[op.axis];
+        let pulse_begin = self.current_pos;
+        let pulse_end = self.current_pos + pulse;
+        self.current_pos += pulse - op.overlap;
+        let end = op.end.eval(&session.resolved_symbols).to_usize().unwrap_or(usize::MAX);
+        if pulse_begin < op.begin {
+            let fill_up_to = (op.begin - pulse_begin).min(pulse);
+            unsafe {
+                dispatch_copy_by_size!(Self::fill_slice_constant(input.datum_type())(
+                    &mut input,
+                    &op.value,
+                    op.axis,
+                    0..fill_up_to
+                ))
+            }
+        }
+        if pulse_end > end {
+            let fill_from = end.saturating_sub(pulse_begin);
+            unsafe {
+                dispatch_copy_by_size!(Self::fill_slice_constant(input.datum_type())(
+                    &mut input,
+                    &op.value,
+                    op.axis,
+                    fill_from..pulse
+                ))
+            }
+        }
+        Ok(tvec!(input.into_tvalue()))
+    }
+}
+
+impl PulseMaskOpState {
+    unsafe fn fill_slice_constant<T: Datum + Copy>(
+        data: &mut Tensor,
+        constant: &Tensor,
+        axis: usize,
+        range: std::ops::Range<usize>,
+    ) {
+        let c = constant.to_scalar_unchecked::<T>();
+        data.to_array_view_mut_unchecked::<T>().slice_axis_mut(Axis(axis), range.into()).fill(*c);
+    }
+}
+
+impl OpStateFreeze for PulseMaskOpState {
+    fn freeze(&self) -> Box<dyn FrozenOpState> {
+        Box::new(self.clone())
+    }
+}
+
+impl FrozenOpState for PulseMaskOpState {
+    fn unfreeze(&self) -> Box<dyn OpState> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,168 @@
+use tract_nnef::internal::*;
+use tract_nnef::ser::tdim;
+use tract_nnef::tract_core::ops::nn::Reducer;
+use tract_nnef::tract_core::ops::OpStateFreeze;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_primitive(
+        "tract_pulse_axis_reduce",
+        &[
+            TypeName::Scalar.tensor().named("input"),
+            TypeName::Integer.named("axis"),
+            TypeName::String.named("reducer"),
+            TypeName::Integer.named("begin"),
+            TypeName::Integer.named("end"),
+        ],
+        &[("output", TypeName::Scalar.tensor())],
+        deser,
+    );
+    registry.register_dumper(TypeId::of::<PulsedAxisReduce>(), ser)
+}
+
+fn ser(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<PulsedAxisReduce>().unwrap();
+    let wire = ast.mapping[&node.inputs[0]].clone();
+    let reducer = match op.reducer {
+        Reducer::Sum => "sum",
+        Reducer::Prod => "prod",
+        Reducer::Min => "min",
+        Reducer::Max => "max",
+        _ => return Ok(None),
+    };
+    let params = vec![
+        ("axis", numeric(op.axis)),
+        ("reducer", string(reducer)),
+        ("begin", numeric(op.begin)),
+        ("end", tdim(&op.end)),
+    ];
+    Ok(Some(invocation("tract_pulse_axis_reduce", &[wire], &params)))
+}
+
+fn deser(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let wire = invocation.named_arg_as(builder, "input")?;
+    let axis = invocation.named_arg_as(builder, "axis")?;
+    let reducer = match &*invocation.named_arg_as::<String>(builder, "reducer")? {
+        "sum" => Reducer::Sum,
+        "prod" => Reducer::Prod,
+        "min" => Reducer::Min,
+        "max" => Reducer::Max,
+        s => bail!("Unsupported reducer for tract_pulse_axis_reduce: {}", s),
+    };
+    let begin = invocation.named_arg_as(builder, "begin")?;
+    let end = builder.allowing_new_symbols(|builder| invocation.named_arg_as(builder, "end"))?;
+    builder.wire(PulsedAxisReduce { axis, reducer, begin, end }, &[wire])
+}
+
+/// Running reduction over the streaming axis.
+///
+/// Each pulse is reduced over its frames lying in `begin..end` and combined
+/// with the accumulated value, so the output after the last pulse is the
+/// reduction of the whole stream. Only associative reducers are supported.
+#[derive(Debug, Clone, Hash)]
+pub struct PulsedAxisReduce {
+    pub axis: usize,
+    pub reducer: Reducer,
+    pub begin: usize,
+    pub end: TDim,
+}
+
+impl Op for PulsedAxisReduce {
+    fn name(&self) -> Cow<str> {
+        format!("PulsedAxisReduce<{:?}>", self.reducer).into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} begin: {} end: {}", self.axis, self.begin, self.end)])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for PulsedAxisReduce {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn state(
+        &self,
+        _session: &mut SessionState,
+        _node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        Ok(Some(Box::<PulsedAxisReduceState>::default()))
+    }
+}
+
+impl TypedOp for PulsedAxisReduce {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(matches!(self.reducer, Reducer::Sum | Reducer::Prod | Reducer::Min | Reducer::Max));
+        let mut shape = inputs[0].shape.clone();
+        shape.set(self.axis, 1.to_dim());
+        Ok(tvec!(inputs[0].datum_type.fact(shape)))
+    }
+
+    as_op!();
+}
+
+#[derive(Debug, Clone, Default)]
+struct PulsedAxisReduceState {
+    current_pos: usize,
+    accumulator: Option<Tensor>,
+}
+
+impl OpState for PulsedAxisReduceState {
+    fn eval(
+        &mut self,
+        session: &mut SessionState,
+        op: &dyn Op,
+        inputs: TVec<TValue>,
+    ) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let op = op.downcast_ref::<PulsedAxisReduce>().context("Wrong Op type")?;
+        let pulse = input.shape()[op.axis];
+        let pulse_begin = self.current_pos;
+        self.current_pos += pulse;
+        let end = op.end.eval(&session.resolved_symbols).to_usize().unwrap_or(usize::MAX);
+        let valid_begin = op.begin.saturating_sub(pulse_begin).min(pulse);
+        let valid_end = end.saturating_sub(pulse_begin).min(pulse);
+        if valid_begin < valid_end {
+            let chunk = input.slice(op.axis, valid_begin, valid_end)?;
+            let mut reduced = op.reducer.reduce(&[op.axis], &chunk)?;
+            if let Some(acc) = self.accumulator.take() {
+                let both = Tensor::stack_tensors(op.axis, &[acc, reduced])?;
+                reduced = op.reducer.reduce(&[op.axis], &both)?;
+            }
+            self.accumulator = Some(reduced);
+        }
+        if let Some(acc) = &self.accumulator {
+            Ok(tvec!(acc.clone().into_tvalue()))
+        } else {
+            let mut shape: TVec<usize> = input.shape().into();
+            shape[op.axis] = 1;
+            Ok(tvec!(Tensor::zero_dt(input.datum_type(), &shape)?.into_tvalue()))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FrozenPulsedAxisReduceState {
+    current_pos: usize,
+    accumulator: Option<Arc<Tensor>>,
+}
+
+impl OpStateFreeze for PulsedAxisReduceState {
+    fn freeze(&self) -> Box<dyn FrozenOpState> {
+        Box::new(FrozenPulsedAxisReduceState {
+            current_pos: self.current_pos,
+            accumulator: self.accumulator.as_ref().map(|t| t.clone().into_arc_tensor()),
+        })
+    }
+}
+
+impl FrozenOpState for FrozenPulsedAxisReduceState {
+    fn unfreeze(&self) -> Box<dyn OpState> {
+        Box::new(PulsedAxisReduceState {
+            current_pos: self.current_pos,
+            accumulator: self.accumulator.as_ref().map(|t| t.clone().into_tensor()),
+        })
+    }
+}
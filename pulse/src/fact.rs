@@ -2,6 +2,7 @@ use crate::internal::*;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct StreamInfo {
+    pub symbol: Symbol,
     pub axis: usize,
     pub dim: TDim,
     pub delay: usize,
@@ -46,12 +47,22 @@ impl PulsedFact {
             .shape
             .stream_info(symbol)
             .ok_or_else(|| format_err!("Can not pulse a tensor with no streaming dim"))?;
+        // a stream dim like 4*S comes at four times the rate of S: its pulse
+        // is scaled accordingly.
+        let at = |p: &TDim| len.substitute(symbol, p);
+        let rate = at(pulse) - at(&0.to_dim());
+        if let Ok(p) = pulse.to_usize() {
+            let double = at(&(2 * p).to_dim()) - at(pulse);
+            if rate.to_usize().map(|r| r == 0).unwrap_or(true) || double != rate {
+                bail!("Pulse {} is not compatible with streaming dim {}", pulse, len)
+            }
+        }
         let mut shape: TVec<TDim> = tf.shape.iter().collect();
-        shape[axis] = pulse.clone();
+        shape[axis] = rate;
         Ok(PulsedFact {
             datum_type,
             shape: shape.into(),
-            stream: Some(StreamInfo { axis, dim: len.clone(), delay: 0 }),
+            stream: Some(StreamInfo { symbol: symbol.clone(), axis, dim: len.clone(), delay: 0 }),
         })
     }
 
@@ -5,6 +5,7 @@ pub mod macros;
 pub mod fact;
 pub mod model;
 pub mod ops;
+pub mod report;
 
 pub mod internal {
     pub use std::fmt;
@@ -16,6 +17,7 @@ pub mod internal {
     pub use crate::fact::PulsedFact;
     pub use crate::model::{PulsedModel, PulsedModelExt};
     pub use crate::ops::{OpPulsifier, PulsedOp};
+    pub use crate::report::PulseReport;
 }
 
 use std::ops::ControlFlow;
@@ -93,4 +95,62 @@ mod tests {
         assert_eq!(*pulse.input_fact(0).unwrap().to_typed_fact().unwrap(), f32::fact([4, 2, 3]));
         assert_eq!(*pulse.output_fact(0).unwrap().to_typed_fact().unwrap(), f32::fact([4, 2, 3]));
     }
+
+    #[test]
+    fn test_independent_streams() {
+        let mut model = TypedModel::default();
+        let s = model.symbol_table.sym("S");
+        let t = model.symbol_table.sym("T");
+        let a = model.add_source("a", f32::fact(dims![s, 2].as_ref())).unwrap();
+        let b = model.add_source("b", f32::fact(dims![t, 3].as_ref())).unwrap();
+        model.set_output_outlets(&[b, a]).unwrap();
+
+        let streams = [(s, 4.to_dim()), (t, 1.to_dim())];
+        let pulse = PulsedModel::new_with_streams(&model, &streams).unwrap();
+        assert_eq!(*pulse.input_fact(0).unwrap().to_typed_fact().unwrap(), f32::fact([4, 2]));
+        assert_eq!(*pulse.input_fact(1).unwrap().to_typed_fact().unwrap(), f32::fact([1, 3]));
+
+        let typed = pulse.into_typed().unwrap();
+        assert_eq!(
+            *typed.properties["pulse.input_symbols"],
+            tensor1(&["S".to_string(), "T".to_string()])
+        );
+        assert_eq!(
+            *typed.properties["pulse.output_symbols"],
+            tensor1(&["T".to_string(), "S".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_stream_rate_ratio() {
+        let mut model = TypedModel::default();
+        let s = model.symbol_table.sym("S");
+        let audio = model.add_source("audio", f32::fact(dims![s.to_dim() * 4].as_ref())).unwrap();
+        let text = model.add_source("text", f32::fact(dims![s].as_ref())).unwrap();
+        let down =
+            model.wire_node("down", tract_core::ops::Downsample::new(0, 4, 0), &[audio]).unwrap();
+        let sum = model.wire_node("sum", tract_core::ops::math::add(), &[down[0], text]).unwrap();
+        model.set_output_outlets(&sum).unwrap();
+
+        let pulse = PulsedModel::new(&model, s, &2.to_dim()).unwrap();
+        assert_eq!(*pulse.input_fact(0).unwrap().to_typed_fact().unwrap(), f32::fact([8]));
+        assert_eq!(*pulse.input_fact(1).unwrap().to_typed_fact().unwrap(), f32::fact([2]));
+        assert_eq!(*pulse.output_fact(0).unwrap().to_typed_fact().unwrap(), f32::fact([2]));
+
+        let plan = SimplePlan::new(pulse).unwrap();
+        let mut state = SimpleState::new(&plan).unwrap();
+        let audio = tensor1(&[1f32, 0., 0., 0., 2., 0., 0., 0.]);
+        let output = state.run(tvec!(audio.into(), tensor1(&[10f32, 20.]).into())).unwrap();
+        assert_eq!(*output[0], tensor1(&[11f32, 22.]));
+    }
+
+    #[test]
+    fn test_incompatible_stream_pulse() {
+        let mut model = TypedModel::default();
+        let s = model.symbol_table.sym("S");
+        let _a = model.add_source("a", f32::fact(dims![s.to_dim() / 4].as_ref())).unwrap();
+        model.auto_outputs().unwrap();
+        assert!(PulsedModel::new(&model, s.clone(), &2.to_dim()).is_err());
+        assert!(PulsedModel::new(&model, s, &8.to_dim()).is_ok());
+    }
 }
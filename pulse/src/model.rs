@@ -3,6 +3,7 @@ use std::sync::Mutex;
 use crate::fact::StreamInfo;
 use crate::{internal::*, ops::sync_inputs};
 use tract_core::model::translator::Translate;
+use tract_itertools::Itertools;
 use tract_pulse_opl::tract_core::ops::source::TypedSource;
 
 pub type PulsedModel = Graph<PulsedFact, Box<dyn PulsedOp>>;
@@ -18,6 +19,16 @@ pub trait PulsedModelExt {
         pulse: &TDim,
     ) -> TractResult<(PulsedModel, HashMap<OutletId, OutletId>)>;
 
+    /// Pulsify a model with several independent streams, each one given by
+    /// its symbol and the number of frames it provides at every turn.
+    fn new_with_streams(source: &TypedModel, streams: &[(Symbol, TDim)])
+        -> TractResult<PulsedModel>;
+
+    fn new_with_streams_and_mapping(
+        source: &TypedModel,
+        streams: &[(Symbol, TDim)],
+    ) -> TractResult<(PulsedModel, HashMap<OutletId, OutletId>)>;
+
     fn into_typed(self) -> TractResult<TypedModel>;
 }
 
@@ -31,8 +42,30 @@ impl PulsedModelExt for PulsedModel {
         symbol: Symbol,
         pulse: &TDim,
     ) -> TractResult<(PulsedModel, HashMap<OutletId, OutletId>)> {
+        PulsedModel::new_with_streams_and_mapping(source, &[(symbol, pulse.to_owned())])
+    }
+
+    fn new_with_streams(
+        source: &TypedModel,
+        streams: &[(Symbol, TDim)],
+    ) -> TractResult<PulsedModel> {
+        Ok(PulsedModel::new_with_streams_and_mapping(source, streams)?.0)
+    }
+
+    fn new_with_streams_and_mapping(
+        source: &TypedModel,
+        streams: &[(Symbol, TDim)],
+    ) -> TractResult<(PulsedModel, HashMap<OutletId, OutletId>)> {
+        ensure!(!streams.is_empty(), "At least one stream is required to pulsify a model");
+        for (ix, (symbol, _)) in streams.iter().enumerate() {
+            ensure!(
+                streams[ix + 1..].iter().all(|(other, _)| other != symbol),
+                "Stream {} declared twice",
+                symbol
+            );
+        }
         let pulsifiers = crate::ops::OpPulsifier::inventory();
-        Pulsifier(symbol, pulse.to_owned(), pulsifiers).translate_model_with_mappings(source)
+        Pulsifier(streams.into(), pulsifiers).translate_model_with_mappings(source)
     }
 
     fn into_typed(self) -> TractResult<TypedModel> {
@@ -42,16 +75,15 @@ impl PulsedModelExt for PulsedModel {
             .unwrap()
             .stream
             .is_some()));
-        ensure!(self.output_outlets()?.iter().all(|o| self
-            .outlet_fact(*o)
-            .unwrap()
-            .stream
-            .is_some()));
+        // non-streaming outputs (like running reductions over the streaming
+        // axis) are reported with no delay and a -1 axis.
         let delays = tensor1(
             &self
                 .output_outlets()?
                 .iter()
-                .map(|oo| Ok(self.outlet_fact(*oo)?.stream.as_ref().unwrap().delay as _))
+                .map(|oo| {
+                    Ok(self.outlet_fact(*oo)?.stream.as_ref().map(|s| s.delay as _).unwrap_or(0))
+                })
                 .collect::<TractResult<TVec<i64>>>()?,
         );
         typed.properties.insert("pulse.delay".to_string(), delays.into_arc_tensor());
@@ -67,10 +99,37 @@ impl PulsedModelExt for PulsedModel {
             &self
                 .output_outlets()?
                 .iter()
-                .map(|oo| Ok(self.outlet_fact(*oo)?.stream.as_ref().unwrap().axis as _))
+                .map(|oo| {
+                    Ok(self.outlet_fact(*oo)?.stream.as_ref().map(|s| s.axis as _).unwrap_or(-1))
+                })
                 .collect::<TractResult<TVec<i64>>>()?,
         );
         typed.properties.insert("pulse.output_axes".to_string(), output_axes.into_arc_tensor());
+        let input_symbols = tensor1(
+            &self
+                .input_outlets()?
+                .iter()
+                .map(|oo| Ok(self.outlet_fact(*oo)?.stream.as_ref().unwrap().symbol.to_string()))
+                .collect::<TractResult<Vec<String>>>()?,
+        );
+        typed.properties.insert("pulse.input_symbols".to_string(), input_symbols.into_arc_tensor());
+        let output_symbols = tensor1(
+            &self
+                .output_outlets()?
+                .iter()
+                .map(|oo| {
+                    Ok(self
+                        .outlet_fact(*oo)?
+                        .stream
+                        .as_ref()
+                        .map(|s| s.symbol.to_string())
+                        .unwrap_or_default())
+                })
+                .collect::<TractResult<Vec<String>>>()?,
+        );
+        typed
+            .properties
+            .insert("pulse.output_symbols".to_string(), output_symbols.into_arc_tensor());
         Ok(typed)
     }
 }
@@ -109,11 +168,11 @@ impl SpecialOps<PulsedFact, Box<dyn PulsedOp>> for PulsedModel {
     }
 }
 
-struct Pulsifier(Symbol, TDim, Arc<Mutex<HashMap<TypeId, crate::ops::OpPulsifier>>>);
+struct Pulsifier(TVec<(Symbol, TDim)>, Arc<Mutex<HashMap<TypeId, crate::ops::OpPulsifier>>>);
 
 impl std::fmt::Debug for Pulsifier {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Pulsifier({})", self.0)
+        write!(f, "Pulsifier({})", self.0.iter().map(|(s, p)| format!("{s}={p}")).join(","))
     }
 }
 
@@ -133,16 +192,40 @@ impl
         mapping: &HashMap<OutletId, OutletId>,
     ) -> TractResult<TVec<OutletId>> {
         if let Some(op) = node.op_as::<TypedSource>() {
+            let symbols = node.outputs[0].fact.shape.iter().flat_map(|d| d.symbols()).collect_vec();
+            let mut streams = self.0.iter().filter(|(s, _)| symbols.contains(s));
+            let (symbol, pulse) = streams.next().with_context(|| {
+                format!("Can not pulse {}: no streaming dim in {:?}", node, node.outputs[0].fact)
+            })?;
+            if let Some((other, _)) = streams.next() {
+                bail!("Can not pulse {}: both {} and {} are streaming", node, symbol, other)
+            }
             return Ok(crate::ops::source::pulsify(
-                op, source, node, target, mapping, &self.0, &self.1,
+                op, source, node, target, mapping, symbol, pulse,
             )?
             .unwrap());
         }
 
-        if let Some(pulsified) =
-            OpPulsifier::pulsify(source, node, target, mapping, &self.0, &self.1)?
-        {
-            return Ok(pulsified);
+        // pulsifiers are offered the streams of the node inputs, or all of
+        // them for nodes with no streaming input (like broadcasting a const
+        // to a streaming shape).
+        let mut input_streams: TVec<&(Symbol, TDim)> = tvec!();
+        for input in &node.inputs {
+            if let Some(stream) = &target.outlet_fact(mapping[input])?.stream {
+                let declared = self.0.iter().find(|(s, _)| s == &stream.symbol).unwrap();
+                if !input_streams.contains(&declared) {
+                    input_streams.push(declared);
+                }
+            }
+        }
+        let candidates: TVec<&(Symbol, TDim)> =
+            if input_streams.is_empty() { self.0.iter().collect() } else { input_streams };
+        for (symbol, pulse) in candidates {
+            if let Some(pulsified) =
+                OpPulsifier::pulsify(source, node, target, mapping, symbol, pulse)?
+            {
+                return Ok(pulsified);
+            }
         }
 
         let pulse_facts: TVec<PulsedFact> =
@@ -225,11 +308,7 @@ impl PulsedOp for PulseWrappingOp {
                     Ok(PulsedFact {
                         shape: tf.shape,
                         datum_type: tf.datum_type,
-                        stream: Some(StreamInfo {
-                            delay: stream.delay,
-                            axis,
-                            dim: stream.dim.clone(),
-                        }),
+                        stream: Some(StreamInfo { axis, ..stream.clone() }),
                     })
                 } else {
                     bail!("Disappearing pulsing axis")
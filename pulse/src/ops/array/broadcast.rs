@@ -18,7 +18,7 @@ fn pulsify(
         let fact = PulsedFact {
             datum_type: _source.outlet_fact(node.inputs[0])?.datum_type,
             shape: op.shape.iter().map(|dim| dim.substitute(symbol, pulse)).collect(),
-            stream: Some(StreamInfo { axis, dim: full_dim, delay: 0 }),
+            stream: Some(StreamInfo { symbol: symbol.clone(), axis, dim: full_dim, delay: 0 }),
         };
         let new_op = PulsedMultibroadcastTo { fact };
         target
@@ -4,5 +4,6 @@ mod broadcast;
 mod concat;
 mod pad;
 mod slice;
+mod trilu;
 
-register_all_mod!(broadcast, concat, pad, slice);
+register_all_mod!(broadcast, concat, pad, slice, trilu);
@@ -0,0 +1,58 @@
+use crate::internal::*;
+use crate::model::NonPulsingWrappingOp;
+use tract_core::ops::array::Trilu;
+use tract_core::ops::konst::Const;
+
+register_all!(Trilu: pulsify);
+
+/// Trilu applied on attention scores, whose columns span the look-back
+/// window of the keys in the pulsed network: the diagonal is shifted by the
+/// number of frames the window holds before the pulse.
+fn pulsify(
+    op: &Trilu,
+    source: &TypedModel,
+    node: &TypedNode,
+    target: &mut PulsedModel,
+    mapping: &HashMap<OutletId, OutletId>,
+    symbol: &Symbol,
+    _pulse: &TDim,
+) -> TractResult<Option<TVec<OutletId>>> {
+    let input = mapping[&node.inputs[0]];
+    let fact = target.outlet_fact(input)?.clone();
+    let stream = if let Some(stream) = fact.stream.as_ref() {
+        stream
+    } else {
+        return Ok(None);
+    };
+    let rank = fact.shape.len();
+    if stream.axis + 2 < rank {
+        let k = mapping[&node.inputs[1]];
+        return target.wire_node(&node.name, op.clone(), &[input, k]).map(Some);
+    }
+    let source_cols = &source.outlet_fact(node.inputs[0])?.shape[rank - 1];
+    if stream.axis != rank - 2 || !source_cols.symbols().contains(symbol) {
+        bail!("Trilu over the streaming axis can only be pulsified as an attention mask")
+    }
+    let k = source
+        .outlet_fact(node.inputs[1])?
+        .konst
+        .as_ref()
+        .context("Trilu diagonal must be a constant to be pulsified")?
+        .cast_to_scalar::<i64>()?;
+    let overlap = fact.shape[rank - 1].to_usize()? - fact.shape[rank - 2].to_usize()?;
+    let k = target.wire_node(
+        format!("{}.k", node.name),
+        NonPulsingWrappingOp(Box::new(Const::new(rctensor0(k + overlap as i64)))),
+        &[],
+    )?[0];
+    target.wire_node(&node.name, op.clone(), &[input, k]).map(Some)
+}
+
+impl PulsedOp for Trilu {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        Ok(tvec!(inputs[0].clone()))
+    }
+
+    as_op!();
+    pulsed_op_to_typed_op!();
+}
@@ -0,0 +1,127 @@
+use crate::internal::*;
+use tract_core::num_traits::Zero;
+use tract_core::ops::audio::{LogMelSpectrogram, Mfcc, Resample};
+use tract_pulse_opl::ops::Delay;
+
+register_all!(LogMelSpectrogram: pulsify_log_mel, Mfcc: pulsify_mfcc, Resample: pulsify_resample);
+
+/// Delay the input so that pulses carry `overlap` frames of context and start
+/// on a multiple of `alignment` in the delayed stream.
+fn overlap_and_align(
+    node: &TypedNode,
+    target: &mut PulsedModel,
+    mapping: &HashMap<OutletId, OutletId>,
+    axis: usize,
+    overlap: usize,
+    alignment: usize,
+) -> TractResult<OutletId> {
+    let input = mapping[&node.inputs[0]];
+    let fact = target.outlet_fact(input)?.clone();
+    let stream = fact.stream.as_ref().unwrap();
+    if stream.axis != axis {
+        bail!("{} can only be pulsified along its time axis", node.op.name())
+    }
+    let pulse = fact.pulse().unwrap();
+    if !(pulse.to_owned() % (alignment as i64)).is_zero() {
+        bail!("Pulsification requires pulse ({}) to be a multiple of {}", pulse, alignment)
+    }
+    let misalignment = (stream.delay + overlap) % alignment;
+    let extra_delay = if misalignment > 0 { alignment - misalignment } else { 0 };
+    if overlap == 0 && extra_delay == 0 {
+        return Ok(input);
+    }
+    Ok(target.wire_node(
+        format!("{}.delay", node.name),
+        Delay::new_typed(&(&fact).into(), axis, extra_delay, overlap),
+        &[input],
+    )?[0])
+}
+
+fn pulsify_log_mel(
+    op: &LogMelSpectrogram,
+    _source: &TypedModel,
+    node: &TypedNode,
+    target: &mut PulsedModel,
+    mapping: &HashMap<OutletId, OutletId>,
+    _symbol: &Symbol,
+    _pulse: &TDim,
+) -> TractResult<Option<TVec<OutletId>>> {
+    let overlap = op.frame.saturating_sub(op.stride);
+    let wire = overlap_and_align(node, target, mapping, op.axis, overlap, op.stride)?;
+    Ok(Some(target.wire_node(&node.name, op.clone(), &[wire])?))
+}
+
+fn pulsify_mfcc(
+    op: &Mfcc,
+    _source: &TypedModel,
+    node: &TypedNode,
+    target: &mut PulsedModel,
+    mapping: &HashMap<OutletId, OutletId>,
+    _symbol: &Symbol,
+    _pulse: &TDim,
+) -> TractResult<Option<TVec<OutletId>>> {
+    let log_mel = &op.log_mel;
+    let overlap = log_mel.frame.saturating_sub(log_mel.stride);
+    let wire = overlap_and_align(node, target, mapping, log_mel.axis, overlap, log_mel.stride)?;
+    Ok(Some(target.wire_node(&node.name, op.clone(), &[wire])?))
+}
+
+fn pulsify_resample(
+    op: &Resample,
+    _source: &TypedModel,
+    node: &TypedNode,
+    target: &mut PulsedModel,
+    mapping: &HashMap<OutletId, OutletId>,
+    _symbol: &Symbol,
+    _pulse: &TDim,
+) -> TractResult<Option<TVec<OutletId>>> {
+    let wire = overlap_and_align(node, target, mapping, op.axis, op.taps() - 1, op.down)?;
+    Ok(Some(target.wire_node(&node.name, op.clone(), &[wire])?))
+}
+
+/// Framing ops output one frame every `stride` inputs.
+fn framed_output_facts(
+    op: &dyn TypedOp,
+    log_mel: &LogMelSpectrogram,
+    inputs: &[&PulsedFact],
+) -> TractResult<TVec<PulsedFact>> {
+    let mut fact = inputs[0].clone();
+    let input: TypedFact = inputs[0].into();
+    fact.shape = op.output_facts(&[&input])?.remove(0).shape;
+    let stream = fact.stream.as_mut().unwrap();
+    stream.delay /= log_mel.stride;
+    stream.dim = log_mel.frames(&stream.dim);
+    Ok(tvec!(fact))
+}
+
+impl PulsedOp for LogMelSpectrogram {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        framed_output_facts(self, self, inputs)
+    }
+
+    as_op!();
+    pulsed_op_to_typed_op!();
+}
+
+impl PulsedOp for Mfcc {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        framed_output_facts(self, &self.log_mel, inputs)
+    }
+
+    as_op!();
+    pulsed_op_to_typed_op!();
+}
+
+impl PulsedOp for Resample {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        let mut fact = inputs[0].clone();
+        fact.shape.set(self.axis, self.output_len(&fact.shape[self.axis]));
+        let stream = fact.stream.as_mut().unwrap();
+        stream.delay = stream.delay * self.up / self.down;
+        stream.dim = self.output_len(&stream.dim);
+        Ok(tvec!(fact))
+    }
+
+    as_op!();
+    pulsed_op_to_typed_op!();
+}
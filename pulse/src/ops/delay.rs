@@ -41,11 +41,12 @@ mod test {
 
     fn test_pulse_delay_over(pulse: usize, delay: usize, overlap: usize) {
         let mut model = PulsedModel::default();
-        let stream_dim = model.symbol_table.sym("S").to_dim();
+        let symbol = model.symbol_table.sym("S");
+        let stream_dim = symbol.to_dim();
         let fact1 = PulsedFact {
             datum_type: u8::datum_type(),
             shape: (&[pulse]).into(),
-            stream: Some(StreamInfo { axis: 0, dim: stream_dim, delay: 0 }),
+            stream: Some(StreamInfo { symbol, axis: 0, dim: stream_dim, delay: 0 }),
         };
         let source = model.add_source("source", fact1.clone()).unwrap();
         model
@@ -95,11 +96,12 @@ mod test {
     fn test_two_delays() {
         let pulse = 4usize;
         let mut model = PulsedModel::default();
-        let stream_dim = model.symbol_table.sym("S").to_dim();
+        let symbol = model.symbol_table.sym("S");
+        let stream_dim = symbol.to_dim();
         let fact_0 = PulsedFact {
             datum_type: u8::datum_type(),
             shape: (&[pulse]).into(),
-            stream: Some(StreamInfo { axis: 0, dim: stream_dim, delay: 0 }),
+            stream: Some(StreamInfo { symbol, axis: 0, dim: stream_dim, delay: 0 }),
         };
         let stream = fact_0.stream.as_ref().unwrap();
         let source = model.add_source("source", fact_0.clone()).unwrap();
@@ -0,0 +1,303 @@
+use crate::internal::*;
+use crate::model::{NonPulsingWrappingOp, PulseWrappingOp};
+use crate::ops::sync_inputs;
+use tract_core::ops::array::Trilu;
+use tract_core::ops::einsum::EinSum;
+use tract_core::ops::konst::Const;
+use tract_core::ops::logic::Iff;
+use tract_pulse_opl::ops::{Delay, PulseMask};
+
+register_all!(EinSum: pulsify, Iff: pulsify_attention_mask);
+
+/// Pulsify causal attention: an EinSum where two inputs stream along
+/// different axes, queries along `s` and keys (or values) along `t`.
+///
+/// Keys are kept in a ring buffer (a Delay with overlap) covering the
+/// look-back window, so in the pulsed network the `t` axis spans the
+/// `window - 1` frames preceding the pulse plus the pulse itself. The window
+/// is either found on an input where a previous attention EinSum already
+/// materialized it, or read from the band mask applied on the EinSum output:
+/// Trilu ops zeroing the scores, or an Iff selecting them with a Trilu band
+/// condition (filling the rest with -inf ahead of a Softmax).
+fn pulsify(
+    op: &EinSum,
+    source: &TypedModel,
+    node: &TypedNode,
+    target: &mut PulsedModel,
+    mapping: &HashMap<OutletId, OutletId>,
+    symbol: &Symbol,
+    _pulse: &TDim,
+) -> TractResult<Option<TVec<OutletId>>> {
+    let mut streaming_axes: TVec<char> = tvec!();
+    let mut window_axis: Option<(char, usize)> = None;
+    for (ix, input) in node.inputs.iter().enumerate() {
+        let source_fact = source.outlet_fact(*input)?;
+        let fact = target.outlet_fact(mapping[input])?;
+        for axis in 0..source_fact.rank() {
+            let repr = op.axes.axis((InOut::In(ix), axis))?.repr;
+            if fact.stream.as_ref().map(|s| s.axis) == Some(axis) {
+                if !streaming_axes.contains(&repr) {
+                    streaming_axes.push(repr);
+                }
+            } else if source_fact.shape[axis].symbols().contains(symbol) {
+                window_axis = Some((repr, fact.shape[axis].to_usize()?));
+            }
+        }
+    }
+    if streaming_axes.len() < 2 {
+        return Ok(None);
+    }
+    ensure!(streaming_axes.len() == 2, "Can not pulsify EinSum with three streaming axes");
+
+    let output_rank = op.axes.rank(InOut::Out(0));
+    let (query_axis, key_axis, window) = if let Some((key_axis, len)) = window_axis {
+        let query_axis = *streaming_axes.iter().find(|a| **a != key_axis).with_context(|| {
+            format!("Inconsistent window axis {key_axis} in attention EinSum {node}")
+        })?;
+        (query_axis, key_axis, Window::Materialized(len))
+    } else {
+        let query_axis = op.axes.axis((InOut::Out(0), output_rank - 2))?.repr;
+        let key_axis = op.axes.axis((InOut::Out(0), output_rank - 1))?.repr;
+        if !streaming_axes.contains(&query_axis) || !streaming_axes.contains(&key_axis) {
+            bail!(
+                "Attention EinSum {} must output queries and keys streaming axes last to be pulsified",
+                node
+            )
+        }
+        (query_axis, key_axis, Window::LookBack(look_back_window(source, node)?))
+    };
+    if op.axes.axis(query_axis)?.outputs[0].len() != 1 {
+        bail!("Attention EinSum {} must keep the queries streaming axis in its output", node)
+    }
+
+    let mut inputs = sync_inputs(node, target, mapping)?;
+    let pulse = node
+        .inputs
+        .iter()
+        .enumerate()
+        .find_map(|(ix, _)| {
+            let fact = target.outlet_fact(inputs[ix]).ok()?;
+            let stream = fact.stream.as_ref()?;
+            (op.axes.axis((InOut::In(ix), stream.axis)).ok()?.repr == query_axis)
+                .then(|| fact.shape[stream.axis].to_usize())
+        })
+        .context("No queries input")??;
+    let overlap = match window {
+        Window::Materialized(len) => len.checked_sub(pulse).context("Window shorter than pulse")?,
+        Window::LookBack(w) => w - 1,
+    };
+
+    for (ix, input) in inputs.iter_mut().enumerate() {
+        let fact = target.outlet_fact(*input)?.clone();
+        let stream = if let Some(stream) = fact.stream.as_ref() {
+            stream.clone()
+        } else {
+            continue;
+        };
+        if op.axes.axis((InOut::In(ix), stream.axis))?.repr != key_axis {
+            continue;
+        }
+        if fact.shape[stream.axis].to_usize()? != pulse {
+            bail!("Queries and keys must have the same pulse in attention EinSum {}", node)
+        }
+        if overlap > 0 {
+            *input = target.wire_node(
+                format!("{}.window.{}", node.name, ix),
+                Delay::new_typed(&(&fact).into(), stream.axis, 0, overlap),
+                &[*input],
+            )?[0];
+        }
+        let value = Tensor::zero_dt(fact.datum_type, &[])?;
+        let mask = PulseMask {
+            axis: stream.axis,
+            begin: stream.delay + overlap,
+            end: stream.dim.clone() + stream.delay + overlap,
+            value,
+            overlap,
+        };
+        *input = target.wire_node(format!("{}.mask.{}", node.name, ix), mask, &[*input])?[0];
+    }
+    target.wire_node(&node.name, op.clone(), &inputs).map(Some)
+}
+
+enum Window {
+    Materialized(usize),
+    LookBack(usize),
+}
+
+/// Look for the band mask on an attention scores tensor, and return the
+/// look-back window it allows.
+fn look_back_window(source: &TypedModel, node: &TypedNode) -> TractResult<usize> {
+    let mut trilus = tvec!();
+    let mut outlet = OutletId::new(node.id, 0);
+    while let &[succ] = source.outlet_successors(outlet) {
+        let succ_node = source.node(succ.node);
+        if succ_node.op_is::<Trilu>() && succ.slot == 0 {
+            trilus.push(succ_node);
+            outlet = succ_node.id.into();
+        } else {
+            if succ_node.op_is::<Iff>() && succ.slot == 1 {
+                trilus.extend(band_condition(source, succ_node));
+            }
+            break;
+        }
+    }
+    let (causal, window) = band(source, &trilus)?;
+    if !causal {
+        bail!("Attention EinSum {} must be followed by a causal mask to be pulsified", node)
+    }
+    window.with_context(|| {
+        format!("Attention EinSum {node} requires a bounded look-back window to be pulsified")
+    })
+}
+
+/// The Trilu chain computing the condition of an Iff masking scores.
+fn band_condition<'a>(source: &'a TypedModel, iff: &TypedNode) -> TVec<&'a TypedNode> {
+    let mut trilus = tvec!();
+    let mut outlet = iff.inputs[0];
+    loop {
+        let producer = source.node(outlet.node);
+        if !producer.op_is::<Trilu>() {
+            break;
+        }
+        trilus.push(producer);
+        outlet = producer.inputs[0];
+    }
+    trilus
+}
+
+/// Read a band from Trilu ops: a lower Trilu (k <= 0) makes it causal, and an
+/// upper Trilu (k <= 0) bounds the look-back window to 1-k frames.
+fn band(source: &TypedModel, trilus: &[&TypedNode]) -> TractResult<(bool, Option<usize>)> {
+    let mut causal = false;
+    let mut window = None;
+    for trilu in trilus {
+        let k = source
+            .outlet_fact(trilu.inputs[1])?
+            .konst
+            .as_ref()
+            .with_context(|| format!("Non constant diagonal for {trilu}"))?
+            .cast_to_scalar::<i64>()?;
+        if trilu.op_as::<Trilu>().unwrap().upper {
+            if k <= 0 {
+                window = Some((1 - k) as usize);
+            }
+        } else {
+            causal |= k <= 0;
+        }
+    }
+    Ok((causal, window))
+}
+
+/// Iff masking attention scores with a band condition, filling the scores
+/// outside of it (typically with -inf, ahead of a Softmax).
+///
+/// In the pulsed network, the columns of the scores span the look-back
+/// window of the keys: the condition is rebuilt for this layout, and the
+/// keys that lie outside of the stream are filled too, as zeroed keys would
+/// still weigh in a Softmax.
+fn pulsify_attention_mask(
+    _op: &Iff,
+    source: &TypedModel,
+    node: &TypedNode,
+    target: &mut PulsedModel,
+    mapping: &HashMap<OutletId, OutletId>,
+    symbol: &Symbol,
+    _pulse: &TDim,
+) -> TractResult<Option<TVec<OutletId>>> {
+    let scores = mapping[&node.inputs[1]];
+    let fact = target.outlet_fact(scores)?.clone();
+    let stream = if let Some(stream) = fact.stream.as_ref() {
+        stream
+    } else {
+        return Ok(None);
+    };
+    let rank = fact.shape.len();
+    let cond_shape = &source.outlet_fact(node.inputs[0])?.shape;
+    if rank < 2
+        || stream.axis != rank - 2
+        || !cond_shape[rank - 1].symbols().contains(symbol)
+        || target.outlet_fact(mapping[&node.inputs[2]])?.stream.is_some()
+    {
+        return Ok(None);
+    }
+    let trilus = band_condition(source, node);
+    if trilus.is_empty() {
+        return Ok(None);
+    }
+    let (causal, window) = band(source, &trilus)?;
+    let window = window.filter(|_| causal).with_context(|| {
+        format!("Attention mask {node} must be a causal band with a bounded window to be pulsified")
+    })?;
+    let fill = source
+        .outlet_fact(node.inputs[2])?
+        .konst
+        .as_ref()
+        .with_context(|| format!("Attention mask {node} must fill with a constant"))?;
+    ensure!(fill.len() == 1, "Attention mask {} must fill with a scalar", node);
+    let fill = fill.clone().into_tensor().into_shape(&[])?;
+
+    let rows = fact.shape[rank - 2].to_usize()?;
+    let cols = fact.shape[rank - 1].to_usize()?;
+    let overlap = cols.checked_sub(rows).context("Window shorter than pulse")?;
+    let mut cond_shape = tvec!(1; rank);
+    cond_shape[rank - 2] = rows;
+    cond_shape[rank - 1] = cols;
+    // key j of the window is frame j - overlap relative to the pulse start
+    let cond = tract_ndarray::ArrayD::from_shape_fn(&*cond_shape, |coords| {
+        let (row, col) = (coords[rank - 2], coords[rank - 1]);
+        col <= row + overlap && col + window > row + overlap
+    });
+    let cond = target.wire_node(
+        format!("{}.band", node.name),
+        NonPulsingWrappingOp(Box::new(Const::new(cond.into_arc_tensor()))),
+        &[],
+    )?[0];
+    let inputs = [cond, scores, mapping[&node.inputs[2]]];
+    let masked = target.wire_node(
+        format!("{}.masked", node.name),
+        PulseWrappingOp(node.op.clone()),
+        &inputs,
+    )?[0];
+    let mask = PulseMask {
+        axis: rank - 1,
+        begin: stream.delay + overlap,
+        end: stream.dim.clone() + stream.delay + overlap,
+        value: fill,
+        overlap,
+    };
+    target.wire_node(&node.name, mask, &[masked]).map(Some)
+}
+
+impl PulsedOp for EinSum {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        let typed_inputs = inputs.iter().map(|f| f.to_pulse_fact()).collect::<TVec<_>>();
+        let typed_inputs_ref = typed_inputs.iter().collect::<TVec<_>>();
+        let output = self.output_facts(&typed_inputs_ref)?.remove(0);
+        // the queries stream is the one making it to the output with the
+        // shortest pulse: keys come out of the window buffer.
+        let (axis, stream) = inputs
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, f)| {
+                let stream = f.stream.as_ref()?;
+                let axis = self.axes.axis((InOut::In(ix), stream.axis)).ok()?;
+                if let &[position] = &*axis.outputs[0] {
+                    Some((position, f.shape[stream.axis].clone(), stream))
+                } else {
+                    None
+                }
+            })
+            .min_by_key(|(position, pulse, _)| (pulse.to_usize().unwrap_or(usize::MAX), *position))
+            .map(|(position, _, stream)| (position, stream))
+            .context("No streaming axis in EinSum output")?;
+        Ok(tvec!(PulsedFact {
+            datum_type: output.datum_type,
+            shape: output.shape,
+            stream: Some(crate::fact::StreamInfo { axis, ..stream.clone() }),
+        }))
+    }
+
+    as_op!();
+    pulsed_op_to_typed_op!();
+}
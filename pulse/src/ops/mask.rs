@@ -0,0 +1,11 @@
+use crate::internal::*;
+use tract_pulse_opl::ops::PulseMask;
+
+impl PulsedOp for PulseMask {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        Ok(tvec!(inputs[0].clone()))
+    }
+
+    as_op!();
+    pulsed_op_to_typed_op!();
+}
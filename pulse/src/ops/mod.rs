@@ -6,10 +6,15 @@ use lazy_static::lazy_static;
 use tract_pulse_opl::ops::Delay;
 
 pub mod array;
+pub mod audio;
 pub mod cnn;
 pub mod delay;
 pub mod downsample;
 pub mod dummy;
+pub mod einsum;
+pub mod mask;
+pub mod reduce;
+pub mod resize;
 pub mod scan;
 pub mod slice;
 pub mod source;
@@ -20,10 +25,28 @@ pub(crate) fn sync_inputs(
     mapping: &HashMap<OutletId, OutletId>,
 ) -> TractResult<TVec<OutletId>> {
     let mut max_delay = 0;
+    let mut reference: Option<(&Symbol, TDim)> = None;
     for input in &node.inputs {
         let fact = target.outlet_fact(mapping[input])?;
         if let Some(stream) = &fact.stream {
             max_delay = max_delay.max(stream.delay);
+            // streams from different sources can only be combined once their
+            // rates match, so that delays are counted in the same frames.
+            let pulse = fact.shape[stream.axis].clone();
+            if let Some((symbol, reference_pulse)) = &reference {
+                if *symbol != &stream.symbol && reference_pulse != &pulse {
+                    bail!(
+                        "{} combines streams {} and {} with pulses {} and {}, rates must be matched first",
+                        node,
+                        symbol,
+                        stream.symbol,
+                        reference_pulse,
+                        pulse
+                    );
+                }
+            } else {
+                reference = Some((&stream.symbol, pulse));
+            }
         }
     }
     let mut inputs = tvec!();
@@ -46,7 +69,7 @@ pub(crate) fn sync_inputs(
     Ok(inputs)
 }
 
-register_all_mod!(array, cnn, downsample, scan, source);
+register_all_mod!(array, audio, cnn, downsample, einsum, reduce, resize, scan, source);
 
 type PulsifierFn = fn(
     &TypedModel,
@@ -0,0 +1,60 @@
+use crate::internal::*;
+use crate::model::PulseWrappingOp;
+use tract_core::ops::nn::{Reduce, Reducer};
+use tract_pulse_opl::ops::PulsedAxisReduce;
+
+register_all!(Reduce: pulsify);
+
+fn pulsify(
+    op: &Reduce,
+    _source: &TypedModel,
+    node: &TypedNode,
+    target: &mut PulsedModel,
+    mapping: &HashMap<OutletId, OutletId>,
+    _symbol: &Symbol,
+    _pulse: &TDim,
+) -> TractResult<Option<TVec<OutletId>>> {
+    let input = mapping[&node.inputs[0]];
+    let fact = target.outlet_fact(input)?.clone();
+    let stream = if let Some(stream) = fact.stream.as_ref() {
+        stream
+    } else {
+        return Ok(None);
+    };
+    if !op.axes.contains(&stream.axis) {
+        return Ok(None);
+    }
+    if !matches!(op.reducer, Reducer::Sum | Reducer::Prod | Reducer::Min | Reducer::Max) {
+        bail!("Can not pulsify {:?} reduction over the streaming axis", op.reducer)
+    }
+    let mut wire = tvec!(input);
+    let other_axes: TVec<usize> = op.axes.iter().copied().filter(|ax| *ax != stream.axis).collect();
+    if !other_axes.is_empty() {
+        let reduce = Reduce::new(other_axes, op.reducer);
+        wire = target.wire_node(
+            format!("{}.non_streaming_axes", node.name),
+            PulseWrappingOp(Box::new(reduce)),
+            &wire,
+        )?;
+    }
+    let op = PulsedAxisReduce {
+        axis: stream.axis,
+        reducer: op.reducer,
+        begin: stream.delay,
+        end: stream.dim.clone() + stream.delay,
+    };
+    target.wire_node(&node.name, op, &wire).map(Some)
+}
+
+impl PulsedOp for PulsedAxisReduce {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        let mut fact = inputs[0].clone();
+        ensure!(fact.stream.as_ref().map(|s| s.axis) == Some(self.axis));
+        fact.shape.set(self.axis, 1.to_dim());
+        fact.stream = None;
+        Ok(tvec!(fact))
+    }
+
+    as_op!();
+    pulsed_op_to_typed_op!();
+}
@@ -0,0 +1,181 @@
+use crate::internal::*;
+use tract_core::ops::array::PadMode;
+use tract_core::ops::resize::{resize_tensor_axis, CoordTransformer, Resize, Taps};
+use tract_pulse_opl::ops::{Delay, PulsePad};
+
+register_all!(Resize: pulsify);
+
+fn pulsify(
+    op: &Resize,
+    _source: &TypedModel,
+    node: &TypedNode,
+    target: &mut PulsedModel,
+    mapping: &HashMap<OutletId, OutletId>,
+    _symbol: &Symbol,
+    _pulse: &TDim,
+) -> TractResult<Option<TVec<OutletId>>> {
+    let mut input = mapping[&node.inputs[0]];
+    let fact = target.outlet_fact(input)?.clone();
+    let stream = fact.stream.as_ref().unwrap();
+    let Some(ix) = op.axes.iter().position(|&axis| axis == stream.axis) else { return Ok(None) };
+    if op.axes.len() > 1 {
+        bail!("Resize can only be pulsified when the streaming axis is the only resized axis")
+    }
+    let factor = match &op.scales {
+        Some(scales) if scales[ix] >= 1.0 && scales[ix].fract() == 0.0 => scales[ix] as usize,
+        _ => bail!("Resize can only be pulsified with an integer upsampling scale"),
+    };
+    use CoordTransformer::*;
+    if !matches!(
+        op.coord_transformer,
+        HalfPixel | HalfPixelSymmetric | PytorchHalfPixel | Asymmetric
+    ) || op.exclude_outside
+    {
+        bail!(
+            "Resize with {} coordinates (exclude_outside: {}) can not be pulsified",
+            op.coord_transformer.as_str(),
+            op.exclude_outside
+        )
+    }
+    // away from the stream edges, taps only depend on the output phase: look at
+    // the middle of a long enough axis, taps offsets are relative to input 32.
+    let taps = op.axis_taps(ix, 64, 64 * factor, factor as f32);
+    let phases: Vec<TVec<(isize, f32)>> = (0..factor)
+        .map(|p| {
+            let taps = taps[32 * factor + p].as_ref().unwrap();
+            taps.iter().map(|&(i, w)| (i as isize - 32, w)).collect()
+        })
+        .collect();
+    let before = phases.iter().flatten().map(|t| -t.0).max().unwrap_or(0).max(0) as usize;
+    let after = phases.iter().flatten().map(|t| t.0).max().unwrap_or(0).max(0) as usize;
+    // edges are clamped to the first and last input: this is edge padding
+    let mut extra_delay = before.saturating_sub(stream.delay);
+    if before > 0 {
+        let pulse = if let Ok(pulse) = fact.pulse().unwrap().to_usize() {
+            pulse
+        } else {
+            bail!("Resize can only by pulsified with concrete integer pulse")
+        };
+        if before < pulse {
+            let start_offset = (stream.delay + extra_delay) % pulse;
+            if before > start_offset {
+                extra_delay += before - start_offset;
+            }
+        } else {
+            bail!(
+                "Resize needs pulse strictly bigger than its left context (pulse={} context={})",
+                pulse,
+                before
+            )
+        }
+    }
+    if extra_delay > 0 {
+        input = target.wire_node(
+            format!("{}.Delay", node.name),
+            Delay::new_typed(&(&fact).into(), stream.axis, extra_delay, 0),
+            &[input],
+        )?[0];
+    }
+    let overlap = before + after;
+    if overlap > 0 {
+        let pad = PulsePad {
+            axis: stream.axis,
+            before,
+            after: after.into(),
+            begin_input: stream.delay + extra_delay,
+            end_input: stream.delay.to_dim() + extra_delay + &stream.dim,
+            mode: PadMode::Edge,
+            overlap: 0,
+        };
+        input = target.wire_node(format!("{}.Pad", node.name), pad, &[input])?[0];
+        let padded = target.outlet_fact(input)?.clone();
+        input = target.wire_node(
+            format!("{}.Overlap", node.name),
+            Delay::new_typed(&(&padded).into(), stream.axis, 0, overlap),
+            &[input],
+        )?[0];
+    }
+    let taps = phases
+        .into_iter()
+        .map(|taps| {
+            taps.into_iter().map(|(off, w)| ((off + before as isize) as usize, w)).collect()
+        })
+        .collect();
+    let op = PulsedResize {
+        axis: stream.axis,
+        factor,
+        overlap,
+        taps,
+        extrapolation_value: op.extrapolation_value,
+    };
+    Ok(Some(target.wire_node(&*node.name, op, &[input])?))
+}
+
+/// Upsampling along the streaming axis, on pulses carrying `overlap` frames of
+/// context. Output `q * factor + p` is the sum of the `taps[p]` weighted
+/// inputs, at offsets from input `q`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PulsedResize {
+    pub axis: usize,
+    pub factor: usize,
+    pub overlap: usize,
+    pub taps: Vec<TVec<(usize, f32)>>,
+    pub extrapolation_value: f32,
+}
+
+impl Op for PulsedResize {
+    fn name(&self) -> Cow<str> {
+        "PulsedResize".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} factor: {} overlap: {}", self.axis, self.factor, self.overlap)])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for PulsedResize {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let len = input.shape()[self.axis] - self.overlap;
+        let taps: Vec<Taps> = (0..len * self.factor)
+            .map(|o| {
+                let (q, p) = (o / self.factor, o % self.factor);
+                Some(self.taps[p].iter().map(|&(i, w)| (q + i, w)).collect())
+            })
+            .collect();
+        let output = resize_tensor_axis(&input, self.axis, &taps, self.extrapolation_value)?;
+        Ok(tvec!(output.into_tvalue()))
+    }
+}
+
+impl TypedOp for PulsedResize {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let mut fact = inputs[0].without_value();
+        let len = (fact.shape[self.axis].clone() - self.overlap) * self.factor;
+        fact.shape.set(self.axis, len);
+        Ok(tvec!(fact))
+    }
+
+    as_op!();
+}
+
+impl PulsedOp for PulsedResize {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        let mut fact = inputs[0].clone();
+        let len = (fact.shape[self.axis].clone() - self.overlap) * self.factor;
+        fact.shape.set(self.axis, len);
+        let stream = fact.stream.as_mut().unwrap();
+        stream.dim = (stream.dim.clone() - self.overlap) * self.factor;
+        stream.delay *= self.factor;
+        Ok(tvec!(fact))
+    }
+
+    as_op!();
+    pulsed_op_to_typed_op!();
+}
@@ -13,29 +13,15 @@ fn pulsify(
     symbol: &Symbol,
     pulse: &TDim,
 ) -> TractResult<Option<TVec<OutletId>>> {
-
-
-/*
-
-    dbg!(source.node_axes_mapping(node.id)?.to_string());
-    for input_id in &node.inputs {
-        dbg!(target.outlet_fact(mapping[input_id]))?;
-    }
-    for input_id in 0..node.inputs.len() {
-        let input = mapping[&node.inputs[input_id]];
-        let input_fact = target.outlet_fact(input)?;
-        if let Some(info) = op.input_mapping[input_id].as_scan() {
-            if info.chunk < 0 {
-                bail!("Can not pulsify a backward scan.")
-            }
-            if input_fact.stream.as_ref().context("scan on non-streamed input")?.axis != info.axis {
-                bail!("Scan pulsification limited to scanning axis");
+    let pulse_inputs = node.inputs.iter().map(|i| mapping[i]).collect::<TVec<_>>();
+    for (slot, input) in pulse_inputs.iter().enumerate() {
+        if let Some(info) = op.input_mapping[slot].as_scan() {
+            let stream = target.outlet_fact(*input)?.stream.as_ref();
+            if stream.map(|s| s.axis) == Some(info.axis) && info.chunk < 0 {
+                bail!("Can not pulsify a backward scan over the streaming axis")
             }
         }
     }
-*/
-
-    let pulse_inputs = node.inputs.iter().map(|i| mapping[i]).collect::<TVec<_>>();
 
     let axes_mapping = source.node_axes_mapping(node.id)?;
     let first_scan_slot = op.input_mapping.iter().position(InputMapping::is_scan).unwrap();
@@ -56,7 +42,7 @@ fn pulsify(
         new_op.reset_every_turn = true;
         target.wire_node(&node.name, new_op, &pulse_inputs).map(Some)
     } else {
-        todo!("Unsupported pulsification")
+        bail!("Scan pulsification limited to scanning axis or axes mapped to all outputs")
     }
 }
 
@@ -102,8 +88,7 @@ impl PulsedOp for Scan {
                     shape,
                     stream: Some(StreamInfo {
                         axis: output_mapping.scan.unwrap().1.axis,
-                        dim: inputs[first_scan_slot].stream.as_ref().unwrap().dim.clone(),
-                        delay: inputs[first_scan_slot].stream.as_ref().unwrap().delay,
+                        ..inputs[first_scan_slot].stream.clone().unwrap()
                     }),
                 }
             } else {
@@ -117,8 +102,7 @@ impl PulsedOp for Scan {
                     shape,
                     stream: Some(StreamInfo {
                         axis: pulse_axis,
-                        dim: inputs[first_scan_slot].stream.as_ref().unwrap().dim.clone(),
-                        delay: inputs[first_scan_slot].stream.as_ref().unwrap().delay,
+                        ..inputs[first_scan_slot].stream.clone().unwrap()
                     }),
                 }
             };
@@ -0,0 +1,178 @@
+use crate::internal::*;
+use tract_itertools::Itertools;
+use tract_pulse_opl::ops::{DeconvDelay, Delay};
+
+/// Streaming properties of a node of a pulsed network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PulseNodeReport {
+    pub node: usize,
+    pub name: String,
+    pub op: String,
+    /// Accumulated delay of the node first output, in frames of its stream.
+    pub delay: Option<usize>,
+    /// Frames kept in the node buffer from one pulse to the next.
+    pub buffer_frames: usize,
+    /// Frames of the buffer re-emitted with every pulse.
+    pub overlap: usize,
+    pub state_bytes: TDim,
+    /// Frames of the input stream contributing to an output frame, or None
+    /// if the output is not streaming or if the pulse is not known.
+    pub receptive_field: Option<usize>,
+}
+
+/// Latency and state memory analysis of a pulsed network.
+///
+/// Buffers are the ones of `Delay` and `DeconvDelay` operators. Receptive
+/// fields are expressed in frames of the fastest input stream of the same
+/// symbol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PulseReport {
+    pub nodes: Vec<PulseNodeReport>,
+    pub output_delays: TVec<Option<usize>>,
+    pub state_bytes: TDim,
+}
+
+impl PulseReport {
+    pub fn new(model: &PulsedModel) -> TractResult<PulseReport> {
+        let mut input_pulses: HashMap<Symbol, usize> = HashMap::default();
+        for input in model.input_outlets()? {
+            let fact = model.outlet_fact(*input)?;
+            let stream = fact.stream.as_ref().context("Non streaming input")?;
+            if let Ok(pulse) = fact.shape[stream.axis].to_usize() {
+                let max = input_pulses.entry(stream.symbol.clone()).or_default();
+                *max = pulse.max(*max);
+            }
+        }
+        // look back of each streaming outlet, in input frames
+        let mut look_back: HashMap<OutletId, Option<usize>> = HashMap::default();
+        let mut nodes = vec![];
+        let mut state_bytes = TDim::from(0);
+        for node_id in model.eval_order()? {
+            let node = model.node(node_id);
+            let output = &node.outputs[0].fact;
+            let stream = output.stream.as_ref();
+            let input_frames = |outlet: OutletId, frames: usize| -> Option<usize> {
+                let fact = model.outlet_fact(outlet).ok()?;
+                let stream = fact.stream.as_ref()?;
+                let pulse = fact.shape[stream.axis].to_usize().ok()?;
+                let reference = *input_pulses.get(&stream.symbol)?;
+                Some((frames * reference).divceil(pulse))
+            };
+            let mut node_look_back = Some(0);
+            for input in &node.inputs {
+                let input_stream = model.outlet_fact(*input)?.stream.as_ref();
+                if input_stream.map(|s| &s.symbol) == stream.map(|s| &s.symbol) {
+                    node_look_back = node_look_back
+                        .zip(look_back.get(input).copied().flatten())
+                        .map(|(a, b)| a.max(b));
+                }
+            }
+            let (buffer_frames, overlap, bytes) = if let Some(op) = node.op_as::<Delay>() {
+                node_look_back = node_look_back
+                    .zip(input_frames(node.inputs[0], op.overlap))
+                    .map(|(a, b)| a + b);
+                let bytes = op.buffer_shape.iter().product::<TDim>() * op.datum_type.size_of();
+                (op.delay + op.overlap, op.overlap, bytes)
+            } else if let Some(op) = node.op_as::<DeconvDelay>() {
+                node_look_back = node_look_back
+                    .zip(input_frames(node.id.into(), op.overlap))
+                    .map(|(a, b)| a + b);
+                let input = model.outlet_fact(node.inputs[0])?;
+                let mut shape: TVec<TDim> = input.shape.to_tvec();
+                shape[op.axis] = op.overlap.to_dim();
+                let bytes = shape.iter().product::<TDim>() * input.datum_type.size_of();
+                (op.overlap, op.overlap, bytes)
+            } else {
+                (0, 0, TDim::from(0))
+            };
+            for ix in 0..node.outputs.len() {
+                let outlet = OutletId::new(node.id, ix);
+                if model.outlet_fact(outlet)?.stream.is_some() {
+                    look_back.insert(outlet, node_look_back);
+                }
+            }
+            state_bytes += &bytes;
+            nodes.push(PulseNodeReport {
+                node: node.id,
+                name: node.name.clone(),
+                op: node.op.name().to_string(),
+                delay: stream.map(|s| s.delay),
+                buffer_frames,
+                overlap,
+                state_bytes: bytes,
+                receptive_field: stream.and(node_look_back).map(|lb| lb + 1),
+            });
+        }
+        let output_delays = model
+            .output_outlets()?
+            .iter()
+            .map(|o| Ok(model.outlet_fact(*o)?.stream.as_ref().map(|s| s.delay)))
+            .collect::<TractResult<_>>()?;
+        Ok(PulseReport { nodes, output_delays, state_bytes: state_bytes.simplify() })
+    }
+}
+
+impl fmt::Display for PulseReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let opt = |o: Option<usize>| o.map(|x| x.to_string()).unwrap_or_else(|| "-".to_string());
+        for node in &self.nodes {
+            write!(
+                f,
+                "{:>5} {:<20} {:<40} delay: {:>5} receptive field: {:>5}",
+                node.node,
+                node.op,
+                node.name,
+                opt(node.delay),
+                opt(node.receptive_field)
+            )?;
+            if node.buffer_frames > 0 {
+                write!(
+                    f,
+                    " buffer: {} frames (overlap {}) {} bytes",
+                    node.buffer_frames, node.overlap, node.state_bytes
+                )?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "Output delays: {}", self.output_delays.iter().map(|d| opt(*d)).join(", "))?;
+        writeln!(f, "Total state: {} bytes", self.state_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tract_core::ops::cnn::{ConvUnary, KernelFormat, PaddingSpec, PoolSpec};
+    use tract_core::ops::nn::DataFormat;
+
+    #[test]
+    fn conv_stack() {
+        let mut model = TypedModel::default();
+        let s = model.symbol_table.sym("S");
+        let a = model.add_source("a", f32::fact(dims![1, 1, s].as_ref())).unwrap();
+        let conv = ConvUnary {
+            pool_spec: PoolSpec {
+                data_format: DataFormat::NCHW,
+                kernel_shape: tvec!(3),
+                padding: PaddingSpec::Valid,
+                dilations: None,
+                strides: None,
+                output_channel_override: Some(1),
+            },
+            kernel_fmt: KernelFormat::OIHW,
+            kernel: rctensor3(&[[[1f32, 1., 1.]]]),
+            group: 1,
+            bias: None,
+            q_params: None,
+        };
+        let c1 = model.wire_node("c1", conv.clone(), &[a]).unwrap();
+        let c2 = model.wire_node("c2", conv, &c1).unwrap();
+        model.set_output_outlets(&c2).unwrap();
+
+        let pulsed = PulsedModel::new(&model, s, &4.to_dim()).unwrap();
+        let report = PulseReport::new(&pulsed).unwrap();
+        assert_eq!(report.output_delays, tvec!(Some(4)));
+        assert_eq!(report.state_bytes, 16.to_dim());
+        assert_eq!(report.nodes.last().unwrap().receptive_field, Some(5));
+    }
+}
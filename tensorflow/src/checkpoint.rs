@@ -0,0 +1,317 @@
+//! Reader for TensorFlow "V2" checkpoints (TensorBundle format).
+//!
+//! A checkpoint with prefix `variables/variables` is made of an index file
+//! (`variables.index`, a leveldb-style SSTable mapping tensor names to their
+//! `BundleEntryProto`) and one or more data shards
+//! (`variables.data-00000-of-00001`) containing the raw tensor bytes.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use prost::Message;
+use tract_hir::internal::*;
+
+use crate::tfpb::tensorflow::{DataType, TensorShapeProto};
+
+/// Checkpoint key of the serialized TrackableObjectGraph in TF2 checkpoints.
+pub const OBJECT_GRAPH_PROTO_KEY: &str = "_CHECKPOINTABLE_OBJECT_GRAPH";
+
+const TABLE_MAGIC: u64 = 0xdb4775248b80fb57;
+const FOOTER_LEN: usize = 48;
+const BLOCK_TRAILER_LEN: usize = 5;
+
+// tensor_bundle.proto is not part of the generated protos.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BundleHeaderProto {
+    #[prost(int32, tag = "1")]
+    pub num_shards: i32,
+    #[prost(int32, tag = "2")]
+    pub endianness: i32,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BundleEntryProto {
+    #[prost(enumeration = "DataType", tag = "1")]
+    pub dtype: i32,
+    #[prost(message, optional, tag = "2")]
+    pub shape: ::core::option::Option<TensorShapeProto>,
+    #[prost(int32, tag = "3")]
+    pub shard_id: i32,
+    #[prost(int64, tag = "4")]
+    pub offset: i64,
+    #[prost(int64, tag = "5")]
+    pub size: i64,
+    #[prost(fixed32, tag = "6")]
+    pub crc32c: u32,
+    #[prost(bytes = "vec", repeated, tag = "7")]
+    pub slices: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+}
+
+/// An opened checkpoint: the decoded index, and the memory-mapped data shards.
+pub struct Checkpoint {
+    pub entries: HashMap<String, BundleEntryProto>,
+    shards: Vec<memmap2::Mmap>,
+}
+
+impl std::fmt::Debug for Checkpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Checkpoint({} entries, {} shards)", self.entries.len(), self.shards.len())
+    }
+}
+
+impl Checkpoint {
+    /// Open a checkpoint from its prefix (e.g. `saved_model_dir/variables/variables`).
+    pub fn open(prefix: impl AsRef<Path>) -> TractResult<Checkpoint> {
+        let prefix = prefix.as_ref();
+        let index_path = with_suffix(prefix, ".index");
+        let index = fs::read(&index_path)
+            .with_context(|| format!("Reading checkpoint index {index_path:?}"))?;
+        let mut entries = HashMap::default();
+        let mut header = None;
+        for (key, value) in read_table(&index)? {
+            if key.is_empty() {
+                header = Some(BundleHeaderProto::decode(&*value)?);
+            } else {
+                let key = String::from_utf8(key)
+                    .map_err(|_| format_err!("Non UTF-8 key in checkpoint index"))?;
+                entries.insert(key, BundleEntryProto::decode(&*value)?);
+            }
+        }
+        let header = header.context("Checkpoint index has no header")?;
+        ensure!(header.endianness == 0, "Only little endian checkpoints are supported");
+        let shards = (0..header.num_shards)
+            .map(|ix| {
+                let path =
+                    with_suffix(prefix, &format!(".data-{:05}-of-{:05}", ix, header.num_shards));
+                let file = fs::File::open(&path)
+                    .with_context(|| format!("Opening checkpoint shard {path:?}"))?;
+                Ok(unsafe { memmap2::Mmap::map(&file)? })
+            })
+            .collect::<TractResult<Vec<_>>>()?;
+        Ok(Checkpoint { entries, shards })
+    }
+
+    /// Raw bytes of a tensor.
+    pub fn raw(&self, key: &str) -> TractResult<&[u8]> {
+        let entry = self.entries.get(key).with_context(|| format!("No {key} in checkpoint"))?;
+        ensure!(entry.slices.is_empty(), "Partitioned variable {} is not supported", key);
+        let shard = self
+            .shards
+            .get(entry.shard_id as usize)
+            .with_context(|| format!("Shard {} not found for {}", entry.shard_id, key))?;
+        let end = entry
+            .offset
+            .checked_add(entry.size)
+            .with_context(|| format!("Invalid data range for {key} in checkpoint"))?;
+        let range = usize::try_from(entry.offset)?..usize::try_from(end)?;
+        shard.get(range).with_context(|| format!("Truncated data shard for {key}"))
+    }
+
+    /// Read a tensor from the checkpoint.
+    pub fn tensor(&self, key: &str) -> TractResult<Tensor> {
+        let entry = self.entries.get(key).with_context(|| format!("No {key} in checkpoint"))?;
+        let dt = DataType::from_i32(entry.dtype)
+            .with_context(|| format!("Invalid datatype for {key} in checkpoint"))?;
+        let dt: DatumType = dt.try_into()?;
+        let shape: TVec<usize> =
+            entry.shape.as_ref().map(|s| s.try_into()).transpose()?.unwrap_or_default();
+        let raw = self.raw(key)?;
+        if dt == DatumType::Blob {
+            let strings = read_strings(raw, shape.iter().product())
+                .with_context(|| format!("Decoding string tensor {key}"))?;
+            Ok(tract_ndarray::ArrayD::from_shape_vec(&*shape, strings)?.into())
+        } else {
+            unsafe { Tensor::from_raw_dt(dt, &shape, raw) }
+        }
+    }
+}
+
+fn with_suffix(prefix: &Path, suffix: &str) -> PathBuf {
+    let mut s = prefix.as_os_str().to_owned();
+    s.push(suffix);
+    s.into()
+}
+
+fn varint(bytes: &[u8], pos: &mut usize) -> TractResult<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let b = *bytes.get(*pos).context("Truncated varint")?;
+        *pos += 1;
+        value |= ((b & 0x7f) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("Invalid varint")
+}
+
+fn u32_le(bytes: &[u8], pos: usize) -> TractResult<u32> {
+    let b = bytes.get(pos..pos + 4).context("Truncated table")?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+// Strings are stored as all the varint64 lengths, a 4 bytes checksum of the
+// lengths, then the concatenated bytes.
+fn read_strings(raw: &[u8], len: usize) -> TractResult<Vec<Blob>> {
+    let mut pos = 0;
+    let lengths =
+        (0..len).map(|_| Ok(varint(raw, &mut pos)? as usize)).collect::<TractResult<Vec<_>>>()?;
+    pos += 4;
+    lengths
+        .into_iter()
+        .map(|l| {
+            let s = raw.get(pos..pos + l).context("Truncated string tensor")?;
+            pos += l;
+            Ok(Blob(s.to_vec()))
+        })
+        .collect()
+}
+
+fn block_handle(bytes: &[u8], pos: &mut usize) -> TractResult<(usize, usize)> {
+    Ok((varint(bytes, pos)? as usize, varint(bytes, pos)? as usize))
+}
+
+fn block(table: &[u8], (offset, size): (usize, usize)) -> TractResult<&[u8]> {
+    let end = offset
+        .checked_add(size)
+        .and_then(|end| end.checked_add(BLOCK_TRAILER_LEN))
+        .context("Invalid block handle")?;
+    let content = table.get(offset..end).context("Truncated table")?;
+    ensure!(content[size] == 0, "Compressed checkpoint indexes are not supported");
+    Ok(&content[..size])
+}
+
+fn block_entries(block: &[u8]) -> TractResult<Vec<(Vec<u8>, &[u8])>> {
+    ensure!(block.len() >= 4, "Truncated table block");
+    let restarts = u32_le(block, block.len() - 4)? as usize;
+    let end = block
+        .len()
+        .checked_sub(4 * (restarts + 1))
+        .context("Invalid restart array in table block")?;
+    let mut entries = vec![];
+    let mut key: Vec<u8> = vec![];
+    let mut pos = 0;
+    while pos < end {
+        let shared = varint(block, &mut pos)? as usize;
+        let non_shared = varint(block, &mut pos)? as usize;
+        let value_len = varint(block, &mut pos)? as usize;
+        ensure!(shared <= key.len(), "Invalid key prefix in table block");
+        key.truncate(shared);
+        key.extend_from_slice(block.get(pos..pos + non_shared).context("Truncated key")?);
+        pos += non_shared;
+        let value = block.get(pos..pos + value_len).context("Truncated value")?;
+        pos += value_len;
+        entries.push((key.clone(), value));
+    }
+    Ok(entries)
+}
+
+/// Decode all (key, value) pairs of an uncompressed leveldb-style table.
+fn read_table(table: &[u8]) -> TractResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    ensure!(table.len() >= FOOTER_LEN, "Checkpoint index is too short");
+    let footer = &table[table.len() - FOOTER_LEN..];
+    let magic =
+        u32_le(footer, FOOTER_LEN - 8)? as u64 | (u32_le(footer, FOOTER_LEN - 4)? as u64) << 32;
+    ensure!(magic == TABLE_MAGIC, "Checkpoint index has wrong magic number");
+    let mut pos = 0;
+    let _metaindex = block_handle(footer, &mut pos)?;
+    let index = block_handle(footer, &mut pos)?;
+    let mut pairs = vec![];
+    for (_, handle) in block_entries(block(table, index)?)? {
+        let data = block(table, block_handle(handle, &mut 0)?)?;
+        for (k, v) in block_entries(data)? {
+            pairs.push((k, v.to_vec()));
+        }
+    }
+    Ok(pairs)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn put_varint(buf: &mut Vec<u8>, mut v: u64) {
+        while v >= 0x80 {
+            buf.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        buf.push(v as u8);
+    }
+
+    fn encode_block(entries: &[(&[u8], &[u8])]) -> Vec<u8> {
+        let mut block = vec![];
+        let mut previous: &[u8] = &[];
+        for (k, v) in entries {
+            let shared = previous.iter().zip(k.iter()).take_while(|(a, b)| a == b).count();
+            put_varint(&mut block, shared as u64);
+            put_varint(&mut block, (k.len() - shared) as u64);
+            put_varint(&mut block, v.len() as u64);
+            block.extend_from_slice(&k[shared..]);
+            block.extend_from_slice(v);
+            previous = k;
+        }
+        block.extend_from_slice(&0u32.to_le_bytes());
+        block.extend_from_slice(&1u32.to_le_bytes());
+        block
+    }
+
+    /// Write a single shard checkpoint with numeric tensors.
+    pub(crate) fn write_bundle(prefix: &Path, tensors: &[(&str, Tensor)]) -> TractResult<()> {
+        let mut tensors: Vec<(String, Tensor)> =
+            tensors.iter().map(|(k, t)| (k.to_string(), t.clone())).collect();
+        tensors.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut data = vec![];
+        let header = BundleHeaderProto { num_shards: 1, endianness: 0 }.encode_to_vec();
+        let mut entries = vec![(vec![], header)];
+        for (key, tensor) in &tensors {
+            let proto = crate::tfpb::tensorflow::TensorProto::try_from(tensor)?;
+            let bytes = unsafe { tensor.as_bytes() };
+            let entry = BundleEntryProto {
+                dtype: proto.dtype,
+                shape: proto.tensor_shape,
+                shard_id: 0,
+                offset: data.len() as i64,
+                size: bytes.len() as i64,
+                crc32c: 0,
+                slices: vec![],
+            };
+            data.extend_from_slice(bytes);
+            entries.push((key.as_bytes().to_vec(), entry.encode_to_vec()));
+        }
+        fs::write(with_suffix(prefix, ".data-00000-of-00001"), &data)?;
+
+        let entries: Vec<(&[u8], &[u8])> = entries.iter().map(|(k, v)| (&**k, &**v)).collect();
+        let data_block = encode_block(&entries);
+        let mut index = data_block.clone();
+        index.extend_from_slice(&[0; BLOCK_TRAILER_LEN]);
+        let mut handle = vec![];
+        put_varint(&mut handle, 0);
+        put_varint(&mut handle, data_block.len() as u64);
+        let index_block = encode_block(&[(entries.last().unwrap().0, &handle)]);
+        let index_offset = index.len();
+        index.extend_from_slice(&index_block);
+        index.extend_from_slice(&[0; BLOCK_TRAILER_LEN]);
+        let mut footer = vec![];
+        put_varint(&mut footer, 0);
+        put_varint(&mut footer, 0);
+        put_varint(&mut footer, index_offset as u64);
+        put_varint(&mut footer, index_block.len() as u64);
+        footer.resize(FOOTER_LEN - 8, 0);
+        footer.extend_from_slice(&TABLE_MAGIC.to_le_bytes());
+        index.extend_from_slice(&footer);
+        fs::write(with_suffix(prefix, ".index"), &index)?;
+        Ok(())
+    }
+
+    #[test]
+    fn read_bundle() -> TractResult<()> {
+        let dir = tempfile::tempdir()?;
+        let prefix = dir.path().join("variables");
+        let kernel = tensor2(&[[1f32, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let bias = tensor1(&[-1i64, 1]);
+        write_bundle(&prefix, &[("dense/kernel", kernel.clone()), ("dense/bias", bias.clone())])?;
+        let ckpt = Checkpoint::open(&prefix)?;
+        assert_eq!(ckpt.tensor("dense/kernel")?, kernel);
+        assert_eq!(ckpt.tensor("dense/bias")?, bias);
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+//! TensorFlow function library support.
+//!
+//! TF2 graphs are mostly made of calls (`StatefulPartitionedCall`,
+//! `PartitionedCall`, or direct calls using the function name as op) to
+//! functions from the graph library. tract does not have a notion of function:
+//! calls are inlined, and function bodies used by functional control flow
+//! (`If`, `While`) are converted to standalone graphs.
+use std::collections::HashSet;
+use tract_hir::internal::*;
+
+use crate::tfpb::tensorflow::attr_value::Value;
+use crate::tfpb::tensorflow::{AttrValue, FunctionDef, FunctionDefLibrary, GraphDef, NodeDef};
+
+/// Ops calling a function referenced by their "f" attribute.
+pub(crate) const CALL_OPS: &[&str] = &["StatefulPartitionedCall", "PartitionedCall"];
+
+pub struct FunctionLibrary<'a> {
+    functions: HashMap<&'a str, &'a FunctionDef>,
+}
+
+impl<'a> FunctionLibrary<'a> {
+    pub fn new(library: Option<&'a FunctionDefLibrary>) -> FunctionLibrary<'a> {
+        let functions = library
+            .into_iter()
+            .flat_map(|lib| lib.function.iter())
+            .filter_map(|f| f.signature.as_ref().map(|s| (&*s.name, f)))
+            .collect();
+        FunctionLibrary { functions }
+    }
+
+    pub fn get(&self, name: &str) -> TractResult<&'a FunctionDef> {
+        self.functions
+            .get(name)
+            .copied()
+            .with_context(|| format!("Function {name} not found in graph library"))
+    }
+
+    /// The function called by a node, if the node is a function call.
+    pub fn callee(&self, node: &NodeDef) -> TractResult<Option<&'a FunctionDef>> {
+        if CALL_OPS.contains(&&*node.op) {
+            Ok(Some(self.get(&node.get_attr_func("f")?.name)?))
+        } else {
+            Ok(self.functions.get(&*node.op).copied())
+        }
+    }
+
+    pub fn has_calls(&self, nodes: &[NodeDef]) -> TractResult<bool> {
+        for node in nodes {
+            if self.callee(node)?.is_some() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Inline all function calls from a list of GraphDef nodes, recursively.
+    pub fn inline(&self, nodes: &[NodeDef]) -> TractResult<Vec<NodeDef>> {
+        let mut inliner = Inliner { library: self, nodes: vec![], aliases: HashMap::default() };
+        for node in nodes {
+            inliner.add(node.clone())?;
+        }
+        inliner.finish()
+    }
+
+    /// Convert a function body to a standalone graph. Arguments become
+    /// placeholders, and each returned value is exposed by an Identity node.
+    ///
+    /// Returns the graph, and the names of the input and output nodes.
+    pub fn function_as_graph(
+        &self,
+        name: &str,
+    ) -> TractResult<(GraphDef, Vec<String>, Vec<String>)> {
+        let func = self.get(name)?;
+        let signature = func.signature.as_ref().context("Function without signature")?;
+        let mut nodes = vec![];
+        let mut inputs = vec![];
+        for arg in &signature.input_arg {
+            ensure!(
+                arg.number_attr.is_empty() && arg.type_list_attr.is_empty(),
+                "List argument {} in function {} is not supported",
+                arg.name,
+                name
+            );
+            let mut placeholder = crate::tfpb::node().name(&arg.name).op("Placeholder");
+            if arg.r#type != 0 {
+                placeholder.attr.insert(
+                    "dtype".to_string(),
+                    AttrValue { value: Some(Value::Type(arg.r#type)) },
+                );
+            }
+            inputs.push(placeholder.name.clone());
+            nodes.push(placeholder);
+        }
+        let mut call = crate::tfpb::node().name("").op(name);
+        call.input = inputs.clone();
+        let mut inliner = Inliner { library: self, nodes, aliases: HashMap::default() };
+        inliner.add(call)?;
+        let mut outputs = vec![];
+        for (ix, arg) in signature.output_arg.iter().enumerate() {
+            let mut output_name = arg.name.clone();
+            while inliner.nodes.iter().any(|n| n.name == output_name) {
+                output_name.push('_');
+            }
+            let output =
+                crate::tfpb::node().name(&output_name).op("Identity").input(format!(":{ix}"));
+            inliner.nodes.push(output);
+            outputs.push(output_name);
+        }
+        let nodes = prune(inliner.finish()?, &outputs, &inputs);
+        let graph = GraphDef { node: nodes, library: Some(self.library()), ..GraphDef::default() };
+        Ok((graph, inputs, outputs))
+    }
+
+    fn library(&self) -> FunctionDefLibrary {
+        let mut function: Vec<FunctionDef> = self.functions.values().map(|&f| f.clone()).collect();
+        function.sort_by(|a, b| {
+            a.signature.as_ref().map(|s| &s.name).cmp(&b.signature.as_ref().map(|s| &s.name))
+        });
+        FunctionDefLibrary { function, gradient: vec![] }
+    }
+}
+
+/// Keep only the nodes required to compute the `outputs` nodes (and the
+/// `inputs` nodes, even if unused). Control dependencies are not followed,
+/// and dangling ones are removed.
+pub fn prune(nodes: Vec<NodeDef>, outputs: &[String], inputs: &[String]) -> Vec<NodeDef> {
+    let by_name: HashMap<&str, &NodeDef> = nodes.iter().map(|n| (&*n.name, n)).collect();
+    let mut kept: HashSet<String> = inputs.iter().cloned().collect();
+    let mut todo: Vec<&str> = outputs.iter().map(|s| &**s).collect();
+    while let Some(name) = todo.pop() {
+        if !kept.insert(name.to_string()) {
+            continue;
+        }
+        if let Some(node) = by_name.get(name) {
+            todo.extend(node.input.iter().filter(|i| !i.starts_with('^')).map(|i| node_name(i)));
+        }
+    }
+    nodes
+        .into_iter()
+        .filter(|n| kept.contains(&n.name))
+        .map(|mut n| {
+            n.input.retain(|i| !i.starts_with('^') || kept.contains(&i[1..]));
+            n
+        })
+        .collect()
+}
+
+pub(crate) fn node_name(input: &str) -> &str {
+    input.trim_start_matches('^').split(':').next().unwrap()
+}
+
+// "node" is a shortcut for "node:0"
+fn normalize(input: &str) -> String {
+    if input.contains(':') {
+        input.to_string()
+    } else {
+        format!("{input}:0")
+    }
+}
+
+fn scoped(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{prefix}/{name}")
+    }
+}
+
+/// Position of the first tensor of an output argument of an op, for ops with
+/// more than one output argument. Function bodies refer to outputs as
+/// "node:output_arg:index" where GraphDef use a flat "node:index".
+fn builtin_output_arg_offset(op: &str, arg: &str) -> Option<usize> {
+    let args: &[&str] = match op {
+        "FusedBatchNorm" | "FusedBatchNormV2" | "FusedBatchNormV3" => &[
+            "y",
+            "batch_mean",
+            "batch_variance",
+            "reserve_space_1",
+            "reserve_space_2",
+            "reserve_space_3",
+        ],
+        "Switch" | "RefSwitch" => &["output_false", "output_true"],
+        "Merge" | "RefMerge" => &["output", "value_index"],
+        "Unique" => &["y", "idx"],
+        "TopK" | "TopKV2" => &["values", "indices"],
+        "BroadcastGradientArgs" => &["r0", "r1"],
+        _ => return None,
+    };
+    args.iter().position(|a| *a == arg)
+}
+
+struct Inliner<'l, 'a> {
+    library: &'l FunctionLibrary<'a>,
+    nodes: Vec<NodeDef>,
+    aliases: HashMap<String, String>,
+}
+
+impl<'l, 'a> Inliner<'l, 'a> {
+    fn add(&mut self, node: NodeDef) -> TractResult<()> {
+        if let Some(func) = self.library.callee(&node)? {
+            self.inline_call(node, func)
+                .with_context(|| format!("Inlining {}", func.signature.as_ref().unwrap().name))
+        } else {
+            self.nodes.push(node);
+            Ok(())
+        }
+    }
+
+    fn inline_call(&mut self, call: NodeDef, func: &FunctionDef) -> TractResult<()> {
+        let signature = func.signature.as_ref().context("Function without signature")?;
+        let prefix = &call.name;
+        // attributes bound to the function placeholders
+        let bindings = if CALL_OPS.contains(&&*call.op) {
+            call.get_attr_func("f")?.attr.clone()
+        } else {
+            call.attr.clone()
+        };
+
+        let data_inputs: Vec<&String> = call.input.iter().filter(|i| !i.starts_with('^')).collect();
+        let mut args: HashMap<&str, Vec<String>> = HashMap::default();
+        let mut cursor = 0;
+        for arg in &signature.input_arg {
+            let count = if !arg.number_attr.is_empty() {
+                match bindings.get(&arg.number_attr).and_then(|a| a.value.as_ref()) {
+                    Some(Value::I(i)) => *i as usize,
+                    _ => bail!("Can not resolve length of argument {}", arg.name),
+                }
+            } else if !arg.type_list_attr.is_empty() {
+                match bindings.get(&arg.type_list_attr).and_then(|a| a.value.as_ref()) {
+                    Some(Value::List(l)) => l.r#type.len(),
+                    _ => bail!("Can not resolve length of argument {}", arg.name),
+                }
+            } else {
+                1
+            };
+            let values = data_inputs
+                .get(cursor..cursor + count)
+                .with_context(|| format!("Missing input for argument {}", arg.name))?;
+            args.insert(&arg.name, values.iter().map(|s| s.to_string()).collect());
+            cursor += count;
+        }
+
+        let body_ops: HashMap<&str, &str> =
+            func.node_def.iter().map(|n| (&*n.name, &*n.op)).collect();
+        let translate = |input: &str| -> TractResult<String> {
+            let parts: Vec<&str> = input.split(':').collect();
+            if let Some(values) = args.get(parts[0]) {
+                let ix = parts.get(1).map(|s| s.parse::<usize>()).transpose()?.unwrap_or(0);
+                return values
+                    .get(ix)
+                    .cloned()
+                    .with_context(|| format!("Invalid reference to argument {input}"));
+            }
+            let op = body_ops
+                .get(parts[0])
+                .with_context(|| format!("Invalid reference to {input} in function body"))?;
+            let ix = if parts.len() == 1 {
+                0
+            } else {
+                let offset = self.output_arg_offset(op, parts[1])?;
+                offset + parts.get(2).map(|s| s.parse::<usize>()).transpose()?.unwrap_or(0)
+            };
+            Ok(format!("{}:{}", scoped(prefix, parts[0]), ix))
+        };
+
+        let mut body = vec![];
+        for node in &func.node_def {
+            let mut inlined = node.clone();
+            inlined.name = scoped(prefix, &node.name);
+            inlined.input = node
+                .input
+                .iter()
+                .filter_map(|i| {
+                    if let Some(control) = i.strip_prefix('^') {
+                        body_ops
+                            .contains_key(control)
+                            .then(|| Ok(format!("^{}", scoped(prefix, control))))
+                    } else {
+                        Some(translate(i))
+                    }
+                })
+                .collect::<TractResult<_>>()?;
+            for value in inlined.attr.values_mut() {
+                if let Some(Value::Placeholder(p)) = &value.value {
+                    *value = bindings
+                        .get(p)
+                        .cloned()
+                        .with_context(|| format!("No value for attribute placeholder {p}"))?;
+                }
+            }
+            body.push(inlined);
+        }
+        let rets = signature
+            .output_arg
+            .iter()
+            .enumerate()
+            .map(|(ix, arg)| {
+                let ret = func
+                    .ret
+                    .get(&arg.name)
+                    .with_context(|| format!("Function does not return {}", arg.name))?;
+                Ok((format!("{prefix}:{ix}"), normalize(&translate(ret)?)))
+            })
+            .collect::<TractResult<Vec<_>>>()?;
+        self.aliases.extend(rets);
+        for node in body {
+            self.add(node)?;
+        }
+        Ok(())
+    }
+
+    fn output_arg_offset(&self, op: &str, arg: &str) -> TractResult<usize> {
+        if let Some(func) = self.library.functions.get(op) {
+            let signature = func.signature.as_ref().context("Function without signature")?;
+            signature
+                .output_arg
+                .iter()
+                .position(|a| a.name == arg)
+                .with_context(|| format!("Function {op} has no output {arg}"))
+        } else {
+            Ok(builtin_output_arg_offset(op, arg).unwrap_or(0))
+        }
+    }
+
+    fn resolve(&self, input: &str) -> TractResult<String> {
+        let mut input = normalize(input);
+        for _ in 0..=self.aliases.len() {
+            if let Some(alias) = self.aliases.get(&input) {
+                input = alias.clone();
+            } else {
+                return Ok(input);
+            }
+        }
+        bail!("Cycle in function outputs resolving {}", input)
+    }
+
+    fn finish(mut self) -> TractResult<Vec<NodeDef>> {
+        let names: HashSet<String> = self.nodes.iter().map(|n| n.name.clone()).collect();
+        let mut nodes = std::mem::take(&mut self.nodes);
+        for node in &mut nodes {
+            let mut inputs = vec![];
+            for input in &node.input {
+                if let Some(control) = input.strip_prefix('^') {
+                    // control dependencies to inlined calls are dropped
+                    if names.contains(control) {
+                        inputs.push(input.clone());
+                    }
+                } else {
+                    inputs.push(self.resolve(input)?);
+                }
+            }
+            node.input = inputs;
+        }
+        Ok(nodes)
+    }
+}
@@ -50,8 +50,11 @@ pub extern crate tract_hir;
 #[cfg(feature = "conform")]
 pub mod conform;
 
+pub mod checkpoint;
+pub mod functions;
 pub mod model;
 pub mod ops;
+pub mod saved_model;
 pub mod tensor;
 pub mod tfpb;
 
@@ -1,3 +1,5 @@
+use crate::functions::FunctionLibrary;
+use crate::tfpb::tensorflow::attr_value::Value;
 use crate::tfpb::tensorflow::{GraphDef, NodeDef, SavedModel};
 use prost::Message;
 use std::{fs, path};
@@ -6,6 +8,8 @@ use tract_hir::internal::*;
 #[derive(Default)]
 pub struct ParsingContext {
     pub node_output_arities: HashMap<String, usize>,
+    /// Functions used as bodies by functional control flow ops.
+    pub functions: HashMap<String, InferenceModel>,
 }
 
 type OpBuilder = fn(&ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>>;
@@ -128,12 +132,39 @@ impl Tensorflow {
     ) -> TractResult<TfModelAndExtensions> {
         use crate::ops::control_flow as cf;
 
+        let library = FunctionLibrary::new(graph.library.as_ref());
+        let inlined;
+        let graph = if library.has_calls(&graph.node)? {
+            inlined = GraphDef {
+                node: library.inline(&graph.node)?,
+                library: graph.library.clone(),
+                ..GraphDef::default()
+            };
+            &inlined
+        } else {
+            graph
+        };
+
         let mut model =
             InferenceModel { symbol_table: symbols.to_owned(), ..InferenceModel::default() };
         let mut inputs = tvec!();
         let mut context = ParsingContext::default();
         let mut control_inputs = vec![];
 
+        // parse bodies of functional control flow ops
+        for pbnode in &graph.node {
+            for attr in ["then_branch", "else_branch", "cond", "body"] {
+                if let Some(Value::Func(f)) = pbnode.attr.get(attr).and_then(|a| a.value.as_ref()) {
+                    if !context.functions.contains_key(&f.name) {
+                        let body = self
+                            .parse_function(&library, &f.name, symbols)
+                            .with_context(|| format!("Parsing function {}", f.name))?;
+                        context.functions.insert(f.name.clone(), body);
+                    }
+                }
+            }
+        }
+
         // compute min output arity for all nodes
         for pbnode in &graph.node {
             for i in &pbnode.input {
@@ -242,11 +273,30 @@ impl Tensorflow {
         let extensions = TfModelExtensions { control_inputs, initializing_nodes: vec![] };
         Ok(TfModelAndExtensions(model, extensions))
     }
+
+    /// Parse a function from the graph library as a standalone model, with
+    /// one input per argument and one output per returned value.
+    pub fn parse_function(
+        &self,
+        library: &FunctionLibrary,
+        name: &str,
+        symbols: &SymbolTable,
+    ) -> TractResult<InferenceModel> {
+        let (graph, inputs, outputs) = library.function_as_graph(name)?;
+        let mut model = self.parse_graph_with_symbols(&graph, symbols)?.0;
+        model.set_input_names(&inputs)?;
+        model.set_output_names(&outputs)?;
+        Ok(model)
+    }
 }
 
 impl Framework<GraphDef, InferenceModel> for Tensorflow {
     /// This method will try to read as frozen model, then as a saved model.
+    /// Directories are opened as TF2 SavedModel, using the default signature.
     fn proto_model_for_path(&self, r: impl AsRef<path::Path>) -> TractResult<GraphDef> {
+        if r.as_ref().is_dir() {
+            return self.read_saved_model_dir(r, None);
+        }
         self.read_frozen_model(&mut fs::File::open(r.as_ref())?)
             .or_else(|_| self.read_saved_model(&mut fs::File::open(r.as_ref())?))
     }
@@ -0,0 +1,292 @@
+use tract_hir::internal::*;
+
+use crate::model::{ParsingContext, TfOpRegister};
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn register_all_ops(reg: &mut TfOpRegister) {
+    reg.insert("If", _if);
+    reg.insert("StatelessIf", _if);
+    reg.insert("While", _while);
+    reg.insert("StatelessWhile", _while);
+}
+
+fn function(ctx: &ParsingContext, node: &NodeDef, attr: &str) -> TractResult<InferenceModel> {
+    let name = &node.get_attr_func(attr)?.name;
+    ctx.functions
+        .get(name)
+        .cloned()
+        .with_context(|| format!("Function {name} not found for {}", node.name))
+}
+
+fn _if(ctx: &ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let then_body = function(ctx, node, "then_branch")?;
+    let else_body = function(ctx, node, "else_branch")?;
+    Ok(Box::new(If { then_body, else_body }))
+}
+
+fn _while(ctx: &ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let cond = function(ctx, node, "cond")?;
+    let body = function(ctx, node, "body")?;
+    Ok(Box::new(While { cond, body }))
+}
+
+// Only datum type and shape are propagated between the outer graph and the
+// bodies: a constant input must not be folded into a body.
+fn unify_type_and_shape(a: &mut InferenceFact, b: &mut InferenceFact) -> TractResult<bool> {
+    Ok(a.datum_type.unify_with_mut(&mut b.datum_type)? | a.shape.unify_with_mut(&mut b.shape)?)
+}
+
+/// Functional conditional: both branches take all the inputs but the
+/// condition.
+#[derive(Debug, Clone)]
+pub struct If {
+    pub then_body: InferenceModel,
+    pub else_body: InferenceModel,
+}
+
+impl Op for If {
+    fn name(&self) -> Cow<str> {
+        "If".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl EvalOp for If {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let cond = inputs.remove(0).cast_to_scalar::<bool>()?;
+        let body = if cond { &self.then_body } else { &self.else_body };
+        body.clone().into_runnable()?.run(inputs)
+    }
+}
+
+impl InferenceOp for If {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        loop {
+            let mut changed = false;
+            for body in [&mut self.then_body, &mut self.else_body] {
+                for (ix, input) in inputs.iter_mut().skip(1).enumerate() {
+                    changed |= unify_type_and_shape(body.input_fact_mut(ix)?, input)?;
+                }
+                changed |= body.analyse(false)?;
+            }
+            for (ix, output) in outputs.iter_mut().enumerate() {
+                changed |= self
+                    .then_body
+                    .output_fact_mut(ix)?
+                    .datum_type
+                    .unify_with_mut(&mut output.datum_type)?;
+                changed |= self
+                    .else_body
+                    .output_fact_mut(ix)?
+                    .datum_type
+                    .unify_with_mut(&mut output.datum_type)?;
+                if self.then_body.output_fact(ix)?.shape == self.else_body.output_fact(ix)?.shape {
+                    changed |= self
+                        .then_body
+                        .output_fact_mut(ix)?
+                        .shape
+                        .unify_with_mut(&mut output.shape)?;
+                }
+            }
+            if !changed {
+                return Ok((inputs, outputs, observed.into_iter().cloned().collect()));
+            }
+        }
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        let then_outputs = self.then_body.outputs.len();
+        let else_outputs = self.else_body.outputs.len();
+        ensure!(
+            then_outputs == else_outputs,
+            "If branches must have the same number of outputs (got {} and {})",
+            then_outputs,
+            else_outputs
+        );
+        Ok(then_outputs)
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let mut inputs: TVec<OutletId> = node.inputs.iter().map(|o| mapping[o]).collect();
+        if target.outlet_fact(inputs[0])?.datum_type != bool::datum_type() {
+            inputs[0] = target.wire_node(
+                format!("{}.cond", node.name),
+                tract_core::ops::cast::cast(bool::datum_type()),
+                &[inputs[0]],
+            )?[0];
+        }
+        let op = tract_core::ops::logic::IfThenElse {
+            then_body: self.then_body.clone().into_typed()?,
+            else_body: self.else_body.clone().into_typed()?,
+            then_input_mapping: (1..node.inputs.len()).collect(),
+            else_input_mapping: (1..node.inputs.len()).collect(),
+        };
+        target.wire_node(&*node.name, op, &inputs)
+    }
+
+    as_op!();
+}
+
+/// Functional loop: `body` is applied to the loop variables as long as
+/// `cond` evaluates to true.
+#[derive(Debug, Clone)]
+pub struct While {
+    pub cond: InferenceModel,
+    pub body: InferenceModel,
+}
+
+impl Op for While {
+    fn name(&self) -> Cow<str> {
+        "While".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl EvalOp for While {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        run_loop(&SimplePlan::new(&self.cond)?, &SimplePlan::new(&self.body)?, inputs)
+    }
+}
+
+impl InferenceOp for While {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        loop {
+            let mut changed = false;
+            for (ix, (input, output)) in inputs.iter_mut().zip(outputs.iter_mut()).enumerate() {
+                changed |= input.datum_type.unify_with_mut(&mut output.datum_type)?;
+                changed |= unify_type_and_shape(self.cond.input_fact_mut(ix)?, input)?;
+                changed |= unify_type_and_shape(self.body.input_fact_mut(ix)?, input)?;
+                changed |= self
+                    .body
+                    .output_fact_mut(ix)?
+                    .datum_type
+                    .unify_with_mut(&mut output.datum_type)?;
+                // loop variables keeping their shape across iterations
+                let body_input_shape = self.body.input_fact(ix)?.shape.concretize();
+                let body_output_shape = self.body.output_fact(ix)?.shape.concretize();
+                if body_input_shape.is_some() && body_input_shape == body_output_shape {
+                    changed |= input.shape.unify_with_mut(&mut output.shape)?;
+                }
+            }
+            changed |= self.cond.analyse(false)?;
+            changed |= self.body.analyse(false)?;
+            if !changed {
+                return Ok((inputs, outputs, observed.into_iter().cloned().collect()));
+            }
+        }
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.body.outputs.len())
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let inputs: TVec<OutletId> = node.inputs.iter().map(|o| mapping[o]).collect();
+        let op = WhileLoop {
+            cond: self.cond.clone().into_typed()?,
+            body: self.body.clone().into_typed()?,
+        };
+        target.wire_node(&*node.name, op, &inputs)
+    }
+
+    as_op!();
+}
+
+fn run_loop<M: std::borrow::Borrow<Graph<F, O>>, F, O>(
+    cond: &SimplePlan<F, O, M>,
+    body: &SimplePlan<F, O, M>,
+    mut vars: TVec<TValue>,
+) -> TractResult<TVec<TValue>>
+where
+    F: Fact + Clone + 'static,
+    O: std::fmt::Debug + std::fmt::Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static,
+{
+    while cond.run(vars.clone())?[0].cast_to_scalar::<bool>()? {
+        vars = body.run(vars)?;
+    }
+    Ok(vars)
+}
+
+/// Typed version of While. Loop variables must keep the same type and shape
+/// across iterations.
+#[derive(Debug, Clone)]
+pub struct WhileLoop {
+    pub cond: TypedModel,
+    pub body: TypedModel,
+}
+
+impl Op for WhileLoop {
+    fn name(&self) -> Cow<str> {
+        "WhileLoop".into()
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for WhileLoop {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        run_loop(&SimplePlan::new(&self.cond)?, &SimplePlan::new(&self.body)?, inputs)
+    }
+}
+
+impl TypedOp for WhileLoop {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(self.body.inputs.len() == inputs.len());
+        ensure!(self.body.outputs.len() == inputs.len());
+        ensure!(self.cond.inputs.len() == inputs.len());
+        for ix in 0..inputs.len() {
+            let input = self.body.input_fact(ix)?.without_value();
+            let output = self.body.output_fact(ix)?.without_value();
+            ensure!(
+                input == output,
+                "Loop variable #{} changes from {:?} to {:?} across iterations",
+                ix,
+                input,
+                output
+            );
+        }
+        Ok(inputs.iter().map(|f| f.without_value()).collect())
+    }
+
+    as_op!();
+}
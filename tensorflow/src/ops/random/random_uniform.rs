@@ -1,8 +1,7 @@
 use crate::model::ParsingContext;
 use crate::tfpb::tensorflow::NodeDef;
 use tract_hir::internal::*;
-
-use super::philox::Philox4x32x10;
+use tract_hir::tract_core::random::{Philox4x32x10, RandomOp, RandomOpState, RandomStream};
 
 pub fn random_uniform(_ctx: &ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
     let dtype = node.get_attr_datum_type("dtype")?;
@@ -28,8 +27,6 @@ pub struct RandomUniform {
     seed2: u64,
 }
 
-
-
 impl Op for RandomUniform {
     fn name(&self) -> Cow<str> {
         "RandomUniform".into()
@@ -48,14 +45,30 @@ impl Op for RandomUniform {
 
 impl EvalOp for RandomUniform {
     fn is_stateless(&self) -> bool {
-        true
+        false
     }
 
-    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+    fn state(
+        &self,
+        session: &mut SessionState,
+        node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        let stream = tf_stream(session, node_id, self.seed1, self.seed2);
+        Ok(Some(Box::new(RandomOpState::<Self>::new(stream))))
+    }
+}
+
+impl RandomOp for RandomUniform {
+    fn eval_with_stream(
+        &self,
+        _session: &SessionState,
+        stream: &mut RandomStream,
+        inputs: TVec<TValue>,
+    ) -> TractResult<TVec<TValue>> {
         let shape: TVec<usize> =
             inputs[0].cast_to::<i64>()?.as_slice::<i64>()?.iter().map(|&x| x as usize).collect();
         match self.t {
-            DatumType::F32 => Ok(tvec!(make_f32(&shape, self.seed1, self.seed2)?)),
+            DatumType::F32 => Ok(tvec!(make_f32(&shape, stream)?)),
             dt => bail!("RandomUniform not implemented for {:?}", dt),
         }
     }
@@ -132,13 +145,29 @@ impl Op for TypedRandomUniform {
 
 impl EvalOp for TypedRandomUniform {
     fn is_stateless(&self) -> bool {
-        true
+        false
     }
 
-    fn eval(&self, _inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+    fn state(
+        &self,
+        session: &mut SessionState,
+        node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        let stream = tf_stream(session, node_id, self.seed1, self.seed2);
+        Ok(Some(Box::new(RandomOpState::<Self>::new(stream))))
+    }
+}
+
+impl RandomOp for TypedRandomUniform {
+    fn eval_with_stream(
+        &self,
+        _session: &SessionState,
+        stream: &mut RandomStream,
+        _inputs: TVec<TValue>,
+    ) -> TractResult<TVec<TValue>> {
         let shape = self.shape.iter().map(|d| d.to_usize()).collect::<TractResult<TVec<_>>>()?;
         match self.t {
-            DatumType::F32 => Ok(tvec!(make_f32(&shape, self.seed1, self.seed2)?)),
+            DatumType::F32 => Ok(tvec!(make_f32(&shape, stream)?)),
             dt => bail!("RandomUniform not implemented for {:?}", dt),
         }
     }
@@ -152,20 +181,21 @@ impl TypedOp for TypedRandomUniform {
     as_op!();
 }
 
-pub fn make_f32(shape: &[usize], seed1: u64, seed2: u64) -> TractResult<TValue> {
-    let mut rng = Philox4x32x10::weird_tf_constructor(seed1, seed2).u32_iter();
-    unsafe {
-        let mut tensor = Tensor::uninitialized::<f32>(shape)?;
-        tensor.as_slice_mut::<f32>()?.iter_mut().for_each(|x| {
-            let mantissa = rng.next().unwrap() & 0x7fffff;
-            let exp = 127u32;
-            let f = exp << 23 | mantissa;
-            *x = f32::from_bits(f) - 1.0
-        });
-        Ok(tensor.into_tvalue())
+/// TensorFlow seeding: generators are random unless one of the seeds is set.
+fn tf_stream(session: &SessionState, node_id: usize, seed1: u64, seed2: u64) -> RandomStream {
+    if seed1 == 0 && seed2 == 0 {
+        RandomStream::for_node(session, node_id)
+    } else {
+        RandomStream::new(Philox4x32x10::weird_tf_constructor(seed1, seed2))
     }
 }
 
+pub fn make_f32(shape: &[usize], stream: &mut RandomStream) -> TractResult<TValue> {
+    let mut tensor = Tensor::zero::<f32>(shape)?;
+    tensor.as_slice_mut::<f32>()?.iter_mut().for_each(|x| *x = stream.next_f32());
+    Ok(tensor.into_tvalue())
+}
+
 #[derive(Debug, Clone, new, Hash)]
 pub struct RandomUniformInt {
     t: DatumType,
@@ -173,21 +203,22 @@ pub struct RandomUniformInt {
     seed2: u64,
 }
 
-
-
 impl RandomUniformInt {
-    pub fn make_i32(&self, shape: &[usize], lo: i32, hi: i32) -> TractResult<TValue> {
-        let mut rng = Philox4x32x10::weird_tf_constructor(self.seed1, self.seed2).u32_iter();
-        unsafe {
-            let mut tensor = Tensor::uninitialized::<i32>(shape)?;
-            tensor.as_slice_mut::<i32>()?.iter_mut().for_each(|x| {
-                // reproduce TF casts, with no conviction
-                let lo = lo as u32;
-                let hi = hi as u32;
-                *x = (lo + rng.next().unwrap() % (hi - lo)) as i32;
-            });
-            Ok(tensor.into_tvalue())
-        }
+    pub fn make_i32(
+        &self,
+        shape: &[usize],
+        lo: i32,
+        hi: i32,
+        stream: &mut RandomStream,
+    ) -> TractResult<TValue> {
+        let mut tensor = Tensor::zero::<i32>(shape)?;
+        tensor.as_slice_mut::<i32>()?.iter_mut().for_each(|x| {
+            // reproduce TF casts, with no conviction
+            let lo = lo as u32;
+            let hi = hi as u32;
+            *x = (lo + stream.next_u32() % (hi - lo)) as i32;
+        });
+        Ok(tensor.into_tvalue())
     }
 }
 
@@ -209,10 +240,26 @@ impl Op for RandomUniformInt {
 
 impl EvalOp for RandomUniformInt {
     fn is_stateless(&self) -> bool {
-        true
+        false
     }
 
-    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+    fn state(
+        &self,
+        session: &mut SessionState,
+        node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        let stream = tf_stream(session, node_id, self.seed1, self.seed2);
+        Ok(Some(Box::new(RandomOpState::<Self>::new(stream))))
+    }
+}
+
+impl RandomOp for RandomUniformInt {
+    fn eval_with_stream(
+        &self,
+        _session: &SessionState,
+        stream: &mut RandomStream,
+        inputs: TVec<TValue>,
+    ) -> TractResult<TVec<TValue>> {
         let shape: TVec<usize> =
             inputs[0].cast_to::<i64>()?.as_slice::<i64>()?.iter().map(|&x| x as usize).collect();
         match self.t {
@@ -220,7 +267,8 @@ impl EvalOp for RandomUniformInt {
                 self,
                 &shape,
                 *inputs[1].to_scalar::<i32>()?,
-                *inputs[2].to_scalar::<i32>()?
+                *inputs[2].to_scalar::<i32>()?,
+                stream,
             )?)),
             dt => bail!("RandomUniformInt not implemented for {:?}", dt),
         }
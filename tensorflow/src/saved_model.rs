@@ -0,0 +1,453 @@
+//! TF2 SavedModel directories.
+//!
+//! A SavedModel directory contains a `saved_model.pb` (graphs, function
+//! library and signatures) and a `variables/` checkpoint. Loading one of its
+//! signatures produces a self-contained frozen GraphDef: variables are replaced
+//! by constants read from the checkpoint, function calls are inlined, and the
+//! graph is pruned to the signature inputs and outputs.
+use std::fs;
+use std::path::Path;
+
+use prost::Message;
+use tract_hir::internal::*;
+
+use crate::checkpoint::{Checkpoint, OBJECT_GRAPH_PROTO_KEY};
+use crate::functions::{node_name, prune, FunctionLibrary, CALL_OPS};
+use crate::tfpb::tensorflow::tensor_info::Encoding;
+use crate::tfpb::tensorflow::{
+    DataType, GraphDef, MetaGraphDef, NodeDef, SavedModel, SignatureDef, TensorInfo, TensorProto,
+    TrackableObjectGraph,
+};
+use crate::Tensorflow;
+
+/// Meta graph tag used for inference.
+pub const SERVE_TAG: &str = "serve";
+/// Default signature key.
+pub const DEFAULT_SIGNATURE: &str = "serving_default";
+
+/// A SavedModel signature, frozen as a standalone graph.
+#[derive(Clone, Debug)]
+pub struct FrozenSignature {
+    pub graph: GraphDef,
+    /// Placeholder node names, in signature key order.
+    pub inputs: Vec<String>,
+    /// Output node names, in signature key order.
+    pub outputs: Vec<String>,
+}
+
+impl Tensorflow {
+    /// Freeze a signature from a SavedModel directory. The "serving_default"
+    /// signature is used if none is specified.
+    pub fn freeze_saved_model_dir(
+        &self,
+        dir: impl AsRef<Path>,
+        signature: Option<&str>,
+    ) -> TractResult<FrozenSignature> {
+        let dir = dir.as_ref();
+        let saved_model = self
+            .open_saved_model(&mut fs::File::open(dir.join("saved_model.pb"))?)
+            .with_context(|| format!("Reading {:?}", dir.join("saved_model.pb")))?;
+        let prefix = dir.join("variables").join("variables");
+        let checkpoint = if prefix.with_extension("index").exists() {
+            Some(Checkpoint::open(&prefix)?)
+        } else {
+            None
+        };
+        freeze(&saved_model, checkpoint.as_ref(), signature)
+    }
+
+    /// Read a signature from a SavedModel directory as a frozen GraphDef.
+    pub fn read_saved_model_dir(
+        &self,
+        dir: impl AsRef<Path>,
+        signature: Option<&str>,
+    ) -> TractResult<GraphDef> {
+        Ok(self.freeze_saved_model_dir(dir, signature)?.graph)
+    }
+
+    /// Load a signature from a SavedModel directory. Model inputs and outputs
+    /// follow the signature, ordered by key.
+    pub fn model_for_saved_model_dir(
+        &self,
+        dir: impl AsRef<Path>,
+        signature: Option<&str>,
+    ) -> TractResult<InferenceModel> {
+        let frozen = self.freeze_saved_model_dir(dir, signature)?;
+        let mut model = self.parse_graph(&frozen.graph)?.0;
+        model.set_input_names(&frozen.inputs)?;
+        model.set_output_names(&frozen.outputs)?;
+        Ok(model)
+    }
+}
+
+/// Freeze a signature of a SavedModel, using the variable values from the
+/// checkpoint.
+pub fn freeze(
+    saved_model: &SavedModel,
+    checkpoint: Option<&Checkpoint>,
+    signature: Option<&str>,
+) -> TractResult<FrozenSignature> {
+    let meta = saved_model
+        .meta_graphs
+        .iter()
+        .find(|m| {
+            m.meta_info_def.as_ref().map(|i| i.tags.iter().any(|t| t == SERVE_TAG)).unwrap_or(false)
+        })
+        .or_else(|| saved_model.meta_graphs.first())
+        .context("SavedModel contains no meta graph")?;
+    let (signature_name, signature) = pick_signature(meta, signature)?;
+    debug!("Loading signature {}", signature_name);
+    let graph = meta.graph_def.as_ref().context("Meta graph without graph")?;
+
+    let mut nodes = graph.node.clone();
+    let mut library = graph.library.clone().unwrap_or_default();
+    if let Some(checkpoint) = checkpoint {
+        let keys = variable_keys(meta, checkpoint)?;
+        for node in &mut nodes {
+            if node.op == "VarHandleOp" {
+                if let Some(key) = keys.get(&node.name) {
+                    let value = checkpoint.tensor(key).with_context(|| {
+                        format!("Reading variable {} from checkpoint", node.name)
+                    })?;
+                    *node = konst(&node.name, &value)?;
+                }
+            }
+        }
+    }
+    rewrite_resource_ops(&mut nodes, false)?;
+    for function in &mut library.function {
+        rewrite_resource_ops(&mut function.node_def, true)?;
+    }
+
+    let mut inputs = vec![];
+    for (key, info) in sorted(&signature.inputs) {
+        let name = tensor_name(key, info)?;
+        ensure!(
+            name.ends_with(":0") || !name.contains(':'),
+            "Signature input {} is not a placeholder ({})",
+            key,
+            name
+        );
+        inputs.push(node_name(name).to_string());
+    }
+    let mut outputs = vec![];
+    for (key, info) in sorted(&signature.outputs) {
+        let mut output_name = key.to_string();
+        while nodes.iter().any(|n| n.name == output_name) {
+            output_name.push('_');
+        }
+        nodes.push(
+            crate::tfpb::node().name(&output_name).op("Identity").input(tensor_name(key, info)?),
+        );
+        outputs.push(output_name);
+    }
+
+    let nodes = FunctionLibrary::new(Some(&library)).inline(&nodes)?;
+    let (mut placeholders, nodes): (Vec<NodeDef>, Vec<NodeDef>) =
+        nodes.into_iter().partition(|n| inputs.contains(&n.name));
+    placeholders.sort_by_key(|n| inputs.iter().position(|i| i == &n.name));
+    placeholders.extend(nodes);
+    let nodes = prune(placeholders, &outputs, &inputs);
+    for node in &nodes {
+        ensure!(
+            node.op != "VarHandleOp",
+            "Variable {} could not be resolved from the checkpoint",
+            node.name
+        );
+    }
+    let graph = GraphDef {
+        node: nodes,
+        library: Some(library),
+        versions: graph.versions.clone(),
+        ..GraphDef::default()
+    };
+    Ok(FrozenSignature { graph, inputs, outputs })
+}
+
+fn pick_signature<'m>(
+    meta: &'m MetaGraphDef,
+    name: Option<&str>,
+) -> TractResult<(&'m str, &'m SignatureDef)> {
+    let available = || sorted(&meta.signature_def).map(|(k, _)| k.as_str()).collect::<Vec<_>>();
+    let name = match name {
+        Some(name) => name,
+        None if meta.signature_def.contains_key(DEFAULT_SIGNATURE) => DEFAULT_SIGNATURE,
+        None if meta.signature_def.len() == 1 => meta.signature_def.keys().next().unwrap(),
+        None => bail!("No default signature, please pick one of {:?}", available()),
+    };
+    meta.signature_def
+        .get_key_value(name)
+        .map(|(k, v)| (k.as_str(), v))
+        .with_context(|| format!("No signature {name}, available signatures are {:?}", available()))
+}
+
+fn sorted<V>(map: &std::collections::HashMap<String, V>) -> impl Iterator<Item = (&String, &V)> {
+    let mut items: Vec<_> = map.iter().collect();
+    items.sort_by_key(|(k, _)| *k);
+    items.into_iter()
+}
+
+fn tensor_name<'i>(key: &str, info: &'i TensorInfo) -> TractResult<&'i str> {
+    match &info.encoding {
+        Some(Encoding::Name(name)) => Ok(name),
+        _ => bail!("Signature tensor {} is not a dense tensor", key),
+    }
+}
+
+fn konst(name: &str, value: &Tensor) -> TractResult<NodeDef> {
+    let dt = DataType::try_from(value.datum_type())?;
+    Ok(crate::tfpb::node()
+        .name(name)
+        .op("Const")
+        .attr("dtype", dt)
+        .attr("value", TensorProto::try_from(value)?))
+}
+
+/// Map VarHandleOp node names from the main graph to checkpoint keys.
+///
+/// TF2 checkpoints are keyed by object path. The concrete functions from the
+/// SavedObjectGraph list the objects (variables) bound to the trailing inputs
+/// of the function calls, and the checkpoint object graph gives the key of
+/// each object. Variables not found this way are looked up by name, as in TF1
+/// checkpoints.
+fn variable_keys(
+    meta: &MetaGraphDef,
+    checkpoint: &Checkpoint,
+) -> TractResult<HashMap<String, String>> {
+    let graph = meta.graph_def.as_ref().context("Meta graph without graph")?;
+    let object_graph = if checkpoint.entries.contains_key(OBJECT_GRAPH_PROTO_KEY) {
+        let proto = checkpoint.tensor(OBJECT_GRAPH_PROTO_KEY)?;
+        Some(TrackableObjectGraph::decode(&*proto.to_scalar::<Blob>()?.0)?)
+    } else {
+        None
+    };
+    let mut keys = HashMap::default();
+    if let (Some(object_graph), Some(saved)) = (&object_graph, &meta.object_graph_def) {
+        let value_key = |id: i32| {
+            object_graph
+                .nodes
+                .get(id as usize)?
+                .attributes
+                .iter()
+                .find(|a| a.name == "VARIABLE_VALUE")
+                .map(|a| a.checkpoint_key.clone())
+        };
+        for node in &graph.node {
+            if !CALL_OPS.contains(&&*node.op) {
+                continue;
+            }
+            let function = node.get_attr_func("f")?;
+            let concrete = if let Some(concrete) = saved.concrete_functions.get(&function.name) {
+                concrete
+            } else {
+                continue;
+            };
+            let data_inputs: Vec<&String> =
+                node.input.iter().filter(|i| !i.starts_with('^')).collect();
+            let bound = &concrete.bound_inputs;
+            if data_inputs.len() < bound.len() {
+                continue;
+            }
+            for (input, id) in data_inputs[data_inputs.len() - bound.len()..].iter().zip(bound) {
+                if let Some(key) = value_key(*id) {
+                    keys.insert(node_name(input).to_string(), key);
+                }
+            }
+        }
+    }
+    for node in &graph.node {
+        if node.op != "VarHandleOp" || keys.contains_key(&node.name) {
+            continue;
+        }
+        let shared_name = node
+            .get_attr_opt_str("shared_name")?
+            .filter(|s| !s.is_empty())
+            .unwrap_or(node.name.clone());
+        let by_full_name = object_graph.iter().flat_map(|g| g.nodes.iter()).find_map(|n| {
+            n.attributes
+                .iter()
+                .find(|a| a.name == "VARIABLE_VALUE" && a.full_name == shared_name)
+                .map(|a| a.checkpoint_key.clone())
+        });
+        if let Some(key) = by_full_name {
+            keys.insert(node.name.clone(), key);
+        } else if checkpoint.entries.contains_key(&shared_name) {
+            keys.insert(node.name.clone(), shared_name);
+        }
+    }
+    Ok(keys)
+}
+
+/// Once variables are constants, reading a variable is an identity, and
+/// ResourceGather a plain gather. References to the added axis constant use
+/// the function body syntax in function bodies.
+fn rewrite_resource_ops(nodes: &mut Vec<NodeDef>, in_function: bool) -> TractResult<()> {
+    let mut axes = vec![];
+    for node in nodes.iter_mut() {
+        match &*node.op {
+            "ReadVariableOp" => {
+                node.op = "Identity".to_string();
+            }
+            "ResourceGather" => {
+                ensure!(
+                    node.get_attr_opt_int::<i64>("batch_dims")?.unwrap_or(0) == 0,
+                    "ResourceGather with batch_dims is not supported ({})",
+                    node.name
+                );
+                let axis = format!("{}/axis", node.name);
+                let axis_ref = if in_function { format!("{axis}:output:0") } else { axis.clone() };
+                node.op = "GatherV2".to_string();
+                node.input.insert(2, axis_ref);
+                axes.push(axis);
+            }
+            _ => (),
+        }
+    }
+    for axis in axes {
+        nodes.push(konst(&axis, &tensor0(0i32))?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tfpb::tensorflow::attr_value::Value;
+    use crate::tfpb::tensorflow::op_def::ArgDef;
+    use crate::tfpb::tensorflow::{
+        AttrValue, FunctionDef, FunctionDefLibrary, NameAttrList, OpDef, SignatureDef,
+    };
+    use DataType::*;
+
+    fn func(name: &str) -> AttrValue {
+        AttrValue {
+            value: Some(Value::Func(NameAttrList { name: name.into(), attr: Default::default() })),
+        }
+    }
+
+    fn function(
+        name: &str,
+        inputs: &[(&str, DataType)],
+        outputs: &[(&str, DataType, &str)],
+        node_def: Vec<NodeDef>,
+    ) -> FunctionDef {
+        let arg = |name: &str, dt: DataType| ArgDef {
+            name: name.into(),
+            r#type: dt as i32,
+            ..ArgDef::default()
+        };
+        FunctionDef {
+            signature: Some(OpDef {
+                name: name.into(),
+                input_arg: inputs.iter().map(|(n, dt)| arg(n, *dt)).collect(),
+                output_arg: outputs.iter().map(|(n, dt, _)| arg(n, *dt)).collect(),
+                ..OpDef::default()
+            }),
+            node_def,
+            ret: outputs.iter().map(|(n, _, r)| (n.to_string(), r.to_string())).collect(),
+            attr: Default::default(),
+        }
+    }
+
+    fn binary(name: &str, op: &str, a: &str, b: &str) -> NodeDef {
+        crate::tfpb::node().name(name).op(op).input(a).input(b)
+    }
+
+    // signature(x) = while i < 3 { x *= 2, i += 1 } applied to x * w, w being a variable
+    fn saved_model() -> TractResult<SavedModel> {
+        let signature = function(
+            "signature",
+            &[("x", DtFloat), ("w", DtResource)],
+            &[("output_0", DtFloat, "loop:output:0")],
+            vec![
+                crate::tfpb::node().name("read").op("ReadVariableOp").input("w"),
+                binary("scaled", "Mul", "x", "read:value:0"),
+                konst("zero", &tensor0(0i32))?,
+                binary("loop", "StatelessWhile", "scaled:z:0", "zero:output:0")
+                    .attr("cond", func("cond"))
+                    .attr("body", func("body")),
+            ],
+        );
+        let cond = function(
+            "cond",
+            &[("acc", DtFloat), ("i", DtInt32)],
+            &[("z", DtBool, "less:z:0")],
+            vec![konst("three", &tensor0(3i32))?, binary("less", "Less", "i", "three:output:0")],
+        );
+        let body = function(
+            "body",
+            &[("acc", DtFloat), ("i", DtInt32)],
+            &[("acc_next", DtFloat, "twice:z:0"), ("i_next", DtInt32, "next:z:0")],
+            vec![
+                binary("twice", "AddV2", "acc", "acc"),
+                konst("one", &tensor0(1i32))?,
+                binary("next", "AddV2", "i", "one:output:0"),
+            ],
+        );
+        let graph = GraphDef {
+            node: vec![
+                crate::tfpb::node()
+                    .name("serving_default_x")
+                    .op("Placeholder")
+                    .attr("dtype", DtFloat),
+                crate::tfpb::node().name("w").op("VarHandleOp").attr("shared_name", "w"),
+                crate::tfpb::node()
+                    .name("saver_filename")
+                    .op("Placeholder")
+                    .attr("dtype", DtString),
+                binary(
+                    "StatefulPartitionedCall",
+                    "StatefulPartitionedCall",
+                    "serving_default_x",
+                    "w",
+                )
+                .attr("f", func("signature")),
+            ],
+            library: Some(FunctionDefLibrary {
+                function: vec![signature, cond, body],
+                gradient: vec![],
+            }),
+            ..GraphDef::default()
+        };
+        let tensor_info = |name: &str| TensorInfo {
+            dtype: DtFloat as i32,
+            tensor_shape: None,
+            encoding: Some(Encoding::Name(name.into())),
+        };
+        let signature = SignatureDef {
+            inputs: [("x".to_string(), tensor_info("serving_default_x:0"))].into_iter().collect(),
+            outputs: [("output_0".to_string(), tensor_info("StatefulPartitionedCall:0"))]
+                .into_iter()
+                .collect(),
+            method_name: "tensorflow/serving/predict".into(),
+        };
+        let meta = MetaGraphDef {
+            graph_def: Some(graph),
+            signature_def: [(DEFAULT_SIGNATURE.to_string(), signature)].into_iter().collect(),
+            ..MetaGraphDef::default()
+        };
+        Ok(SavedModel { saved_model_schema_version: 1, meta_graphs: vec![meta] })
+    }
+
+    #[test]
+    fn load_saved_model_dir() -> TractResult<()> {
+        let dir = tempfile::tempdir()?;
+        let dir = dir.path();
+        fs::create_dir_all(dir.join("variables"))?;
+        fs::write(dir.join("saved_model.pb"), saved_model()?.encode_to_vec())?;
+        crate::checkpoint::tests::write_bundle(
+            &dir.join("variables").join("variables"),
+            &[("w", tensor1(&[3f32]))],
+        )?;
+        let mut model = crate::tensorflow().model_for_saved_model_dir(dir, None)?;
+        assert_eq!(model.input_outlets()?.len(), 1);
+        model.set_input_fact(0, f32::fact([2]).into())?;
+        let input = tensor1(&[1f32, 2.0]);
+        let expected = tensor1(&[24f32, 48.0]);
+        let result = model.clone().into_runnable()?.run(tvec!(input.clone().into()))?;
+        assert_eq!(*result[0], expected);
+        let result = model.into_optimized()?.into_runnable()?.run(tvec!(input.into()))?;
+        assert_eq!(*result[0], expected);
+        Ok(())
+    }
+}
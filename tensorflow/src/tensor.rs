@@ -89,21 +89,18 @@ impl<'a> TryFrom<&'a TensorProto> for Tensor {
         let content = &t.tensor_content;
         let dtype = DataType::from_i32(t.dtype).unwrap();
         let mat: Tensor = if content.len() != 0 {
-            unsafe {
-                match dtype {
-                    DataType::DtFloat => Self::from_raw::<f32>(&dims, content)?,
-                    DataType::DtDouble => Self::from_raw::<f64>(&dims, content)?,
-                    DataType::DtInt32 => Self::from_raw::<i32>(&dims, content)?,
-                    DataType::DtInt64 => Self::from_raw::<i64>(&dims, content)?,
-                    _ => unimplemented!("missing type (for get_tensor_content) {:?}", dtype),
-                }
+            let dt: DatumType = dtype.try_into()?;
+            if dt == DatumType::Blob {
+                bail!("String tensors can not be stored in tensor_content");
             }
+            unsafe { Self::from_raw_dt(dt, &dims, content)? }
         } else {
             match dtype {
                 DataType::DtInt32 => tensor_from_repeated_field(&dims, t.int_val.to_vec())?,
                 DataType::DtInt64 => tensor_from_repeated_field(&dims, t.int64_val.to_vec())?,
                 DataType::DtFloat => tensor_from_repeated_field(&dims, t.float_val.to_vec())?,
                 DataType::DtDouble => tensor_from_repeated_field(&dims, t.double_val.to_vec())?,
+                DataType::DtBool => tensor_from_repeated_field(&dims, t.bool_val.to_vec())?,
                 DataType::DtString => {
                     let strings =
                         t.string_val.iter().map(|s| Blob(s.to_owned())).collect::<Vec<Blob>>();
@@ -163,6 +160,14 @@ impl<'a> TryFrom<&'a Tensor> for TensorProto {
             DatumType::I64 => {
                 tensor.int64_val = from.to_array_view::<i64>()?.iter().cloned().collect();
             }
+            DatumType::Bool => {
+                tensor.bool_val = from.to_array_view::<bool>()?.iter().cloned().collect();
+            }
+            DatumType::Blob => {
+                tensor.string_val =
+                    from.to_array_view::<Blob>()?.iter().map(|b| b.0.clone()).collect();
+            }
+            dt if dt.is_copy() => tensor.tensor_content = unsafe { from.as_bytes() }.to_vec(),
             _ => unimplemented!("missing type {:?}", from.datum_type()),
         }
         Ok(tensor)
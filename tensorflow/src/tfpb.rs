@@ -16,7 +16,9 @@ pub mod tensorflow {
 
 use self::tensorflow::attr_value::ListValue;
 use self::tensorflow::attr_value::Value;
-use self::tensorflow::{AttrValue, DataType, GraphDef, NodeDef, TensorProto, TensorShapeProto};
+use self::tensorflow::{
+    AttrValue, DataType, GraphDef, NameAttrList, NodeDef, TensorProto, TensorShapeProto,
+};
 
 use std::convert::TryInto;
 
@@ -168,6 +170,21 @@ impl NodeDef {
         Ok(None)
     }
 
+    pub fn get_attr_func(&self, name: &str) -> TractResult<&NameAttrList> {
+        self.get_attr_opt_func(name)?.with_context(|| {
+            format!("Node {} ({}) expected func attribute '{}'", self.name, self.op, name)
+        })
+    }
+
+    pub fn get_attr_opt_func(&self, name: &str) -> TractResult<Option<&NameAttrList>> {
+        if let Some(a) = self.attr.get(name) {
+            if let Value::Func(ref f) = a.value.as_ref().unwrap() {
+                return Ok(Some(f));
+            }
+        };
+        Ok(None)
+    }
+
     pub fn get_attr_int<T: tract_num_traits::FromPrimitive>(&self, name: &str) -> TractResult<T> {
         self.get_attr_opt_int(name)?.with_context(|| {
             format!("Node {} ({}) expected int attribute '{}'", self.name, self.op, name)
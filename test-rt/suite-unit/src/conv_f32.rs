@@ -1142,5 +1142,68 @@ pub fn suite() -> TractResult<TestSuite> {
         },
     );
 
+    suite.add(
+        "winograd_f2_0",
+        ConvProblem {
+            shape_in: DataFormat::NCHW.from_n_c_hw(1, 64, [6, 6])?,
+            kernel_format: KernelFormat::OIHW,
+            group: 1,
+            data: pattern(&[1, 64, 6, 6]),
+            kernel: pattern(&[64, 64, 3, 3]),
+            bias: None,
+            pad: PaddingSpec::SameUpper,
+            strides: tvec!(1, 1),
+        },
+    );
+
+    suite.add(
+        "winograd_f4_0",
+        ConvProblem {
+            shape_in: DataFormat::HWC.from_n_c_hw(1, 64, [10, 11])?,
+            kernel_format: KernelFormat::HWIO,
+            group: 1,
+            data: pattern(&[10, 11, 64]),
+            kernel: pattern(&[3, 3, 64, 64]),
+            bias: Some(pattern(&[64])),
+            pad: PaddingSpec::Valid,
+            strides: tvec!(1, 1),
+        },
+    );
+
+    suite.add(
+        "depthwise_hwc_0",
+        ConvProblem {
+            shape_in: DataFormat::HWC.from_n_c_hw(1, 19, [5, 6])?,
+            kernel_format: KernelFormat::OIHW,
+            group: 19,
+            data: pattern(&[5, 6, 19]),
+            kernel: pattern(&[19, 1, 3, 3]),
+            bias: Some(pattern(&[19])),
+            pad: PaddingSpec::SameUpper,
+            strides: tvec!(1, 1),
+        },
+    );
+
+    suite.add(
+        "depthwise_strided_0",
+        ConvProblem {
+            shape_in: DataFormat::NCHW.from_n_c_hw(2, 3, [9, 20])?,
+            kernel_format: KernelFormat::OIHW,
+            group: 3,
+            data: pattern(&[2, 3, 9, 20]),
+            kernel: pattern(&[3, 1, 3, 3]),
+            bias: None,
+            pad: PaddingSpec::SameUpper,
+            strides: tvec!(2, 2),
+        },
+    );
+
     Ok(suite)
 }
+
+// small integers, so that products are exact
+fn pattern(shape: &[usize]) -> ArrayD<f32> {
+    let len = shape.iter().product::<usize>();
+    ArrayD::from_shape_vec(shape, (0..len).map(|i| ((i * 7 + 3) % 11) as f32 - 5.0).collect())
+        .unwrap()
+}
@@ -589,6 +589,47 @@ pub fn suite() -> TractResult<TestSuite> {
         },
     );
 
+    let mut qp = qp_noop_i8();
+    qp[0] = tensor0(2i32);
+    qp[1] = tensor1(&[0.5f32, 1., 0.25]);
+    qp[2] = tensor0(-3i32);
+    qp[4] = tensor0(5i32);
+    qp[5] = tensor0(2f32);
+    suite.add(
+        "depthwise_0",
+        QConvProblem {
+            shape_in: HWC.from_n_c_hw(1, 3, [5]).unwrap(),
+            co: 3,
+            kernel_format: OIHW,
+            group: 3,
+            data: arr2(&[[1i8, -7, 20], [3, 4, -5], [-128, 127, 0], [9, -9, 11], [60, 2, -40]])
+                .into_dyn(),
+            kernel: arr3(&[[[1i8, -2, 3]], [[-50, 7, 12]], [[0, 127, -127]]]).into_dyn(),
+            bias: Some(arr1(&[10i32, -20, 3]).into_dyn()),
+            qp,
+        },
+    );
+    let mut qp = qp_noop_i8();
+    qp[0] = tensor0(-1i32);
+    qp[2] = tensor0(4i32);
+    qp[3] = tensor0(0.5f32);
+    qp[4] = tensor0(-2i32);
+    suite.add(
+        "depthwise_1",
+        QConvProblem {
+            shape_in: NCHW.from_n_c_hw(1, 2, [3, 4]).unwrap(),
+            co: 2,
+            kernel_format: OIHW,
+            group: 2,
+            data: ArrayD::from_shape_fn(vec![1, 2, 3, 4], |ix| {
+                ((ix[1] * 31 + ix[2] * 7 + ix[3] * 13) % 41) as i8 - 20
+            }),
+            kernel: arr4(&[[[[3i8, -1], [2, 5]]], [[[-4, 0], [1, -8]]]]).into_dyn(),
+            bias: None,
+            qp,
+        },
+    );
+
     let mut qp = qp_noop_i8();
     qp[1] = tensor0(0.5f32);
     qp[2] = tensor0(2i32);
@@ -48,7 +48,6 @@ test_quantizelinear
 test_qlinearmatmul_2D
 test_qlinearmatmul_3D
 test_reshape_reordered_dims
-test_resize_upsample_scales_linear_align_corners
 test_unsqueeze
 "#
     .trim()
@@ -11,6 +11,7 @@ use tract_core::ops::array::{Pad, PadMode};
 use tract_core::ops::cnn::KernelFormat;
 use tract_core::ops::cnn::{ConvUnary, PaddingSpec};
 use tract_core::ops::nn::DataFormat;
+use tract_core::ops::resize::{CoordTransformer, Interpolator, Nearest, Resize};
 use tract_core::prelude::tract_itertools::Itertools;
 
 pub fn register_all(reg: &mut Registry) {
@@ -19,6 +20,8 @@ pub fn register_all(reg: &mut Registry) {
     reg.reg_to_tflite(ser_conv);
     reg.reg_to_tract(BuiltinOperator::DEPTHWISE_CONV_2D, de_dw_conv2d);
     reg.reg_to_tflite(ser_pad);
+    reg.reg_to_tract(BuiltinOperator::RESIZE_BILINEAR, de_resize_bilinear);
+    reg.reg_to_tract(BuiltinOperator::RESIZE_NEAREST_NEIGHBOR, de_resize_nearest_neighbor);
 }
 
 fn average_pool_2d(op: &mut DeserOp) -> TractResult<TVec<OutletId>> {
@@ -43,6 +46,52 @@ fn average_pool_2d(op: &mut DeserOp) -> TractResult<TVec<OutletId>> {
     wire_fused_activation(op, &wires, &options.fused_activation_function())
 }
 
+fn wire_resize(
+    op: &mut DeserOp,
+    interpolator: Interpolator,
+    coord_transformer: CoordTransformer,
+    nearest: Nearest,
+) -> TractResult<TVec<OutletId>> {
+    let (_input, size) = args_2!(op.facts()?);
+    let size = size.konst.context("Dynamic resize size is not supported")?;
+    let sizes = size.cast_to::<TDim>()?.as_slice::<TDim>()?.into();
+    let resize = Resize {
+        axes: tvec!(1, 2),
+        sizes: Some(sizes),
+        coord_transformer,
+        interpolator,
+        nearest,
+        ..Resize::default()
+    };
+    op.ctx.target.wire_node(op.prefix, resize, &op.inputs[0..1])
+}
+
+fn de_resize_bilinear(op: &mut DeserOp) -> TractResult<TVec<OutletId>> {
+    let options = builtin!(op, builtin_options_as_resize_bilinear_options);
+    let coord_transformer = if options.align_corners() {
+        CoordTransformer::AlignCorners
+    } else if options.half_pixel_centers() {
+        CoordTransformer::HalfPixel
+    } else {
+        CoordTransformer::Asymmetric
+    };
+    wire_resize(op, Interpolator::Linear, coord_transformer, Nearest::Floor)
+}
+
+fn de_resize_nearest_neighbor(op: &mut DeserOp) -> TractResult<TVec<OutletId>> {
+    let options = builtin!(op, builtin_options_as_resize_nearest_neighbor_options);
+    // tflite rounds half away from zero when aligning corners, and picks floor((x + 0.5) * scale)
+    // with half pixel centers, which is a half pixel coordinate rounded up
+    let (coord_transformer, nearest) = if options.align_corners() {
+        (CoordTransformer::AlignCorners, Nearest::RoundPreferCeil)
+    } else if options.half_pixel_centers() {
+        (CoordTransformer::HalfPixel, Nearest::RoundPreferCeil)
+    } else {
+        (CoordTransformer::Asymmetric, Nearest::Floor)
+    };
+    wire_resize(op, Interpolator::Nearest, coord_transformer, nearest)
+}
+
 fn ser_conv(
     builder: &mut SubgraphBuilder,
     model: &TypedModel,